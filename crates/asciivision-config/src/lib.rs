@@ -0,0 +1,91 @@
+//! Settings shared by every asciivision frontend, the chat deck, the player
+//! and the analytics dashboard, so each is configured once in `config.toml`.
+//!
+//! [`paths`] says where files live, [`theme`] holds the palette they all draw
+//! with, and [`load`] reads a settings type in layers: its defaults, then the
+//! file, then `ASCIIVISION__*` environment variables, then `--set` flags.
+
+use anyhow::{anyhow, Context, Result};
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+pub mod paths;
+pub mod theme;
+
+/// Prefix of environment variables that override a setting, with `__`
+/// between nested keys: `ASCIIVISION__THEME=dracula`,
+/// `ASCIIVISION__RETENTION__MAX_AGE_DAYS=90`.
+pub const ENV_PREFIX: &str = "ASCIIVISION__";
+
+/// `config.toml` in the config directory.
+pub fn config_path() -> Result<PathBuf> {
+    Ok(paths::with_legacy_fallback(&paths::config_dir()?, "config.toml"))
+}
+
+/// Reads `T` from `file` (skipped when missing) with the environment and
+/// then `overrides`, each `key.path=value`, applied on top. Settings no layer
+/// mentions keep `T`'s serde defaults. Values are TOML (`90`, `true`,
+/// `"text"`), and anything that does not parse as TOML is taken as a string.
+pub fn load<T: DeserializeOwned>(file: &Path, overrides: &[String]) -> Result<T> {
+    let mut table = Table::new();
+    if file.exists() {
+        let raw = std::fs::read_to_string(file)
+            .with_context(|| format!("failed to read {}", file.display()))?;
+        table = raw
+            .parse()
+            .with_context(|| format!("failed to parse {}", file.display()))?;
+    }
+    let mut env: Vec<(String, String)> = std::env::vars()
+        .filter_map(|(name, value)| Some((name.strip_prefix(ENV_PREFIX)?.to_string(), value)))
+        .collect();
+    env.sort();
+    for (name, value) in env {
+        let path: Vec<String> = name.split("__").map(str::to_lowercase).collect();
+        set(&mut table, &path, parse_value(&value))
+            .with_context(|| format!("{}{}", ENV_PREFIX, name))?;
+    }
+    for item in overrides {
+        let (key, value) = item
+            .split_once('=')
+            .ok_or_else(|| anyhow!("--set {} is not key=value", item))?;
+        let path: Vec<String> = key.trim().split('.').map(str::to_string).collect();
+        set(&mut table, &path, parse_value(value.trim()))
+            .with_context(|| format!("--set {}", item))?;
+    }
+    let source = if file.exists() {
+        file.display().to_string()
+    } else {
+        "settings".to_string()
+    };
+    Value::Table(table)
+        .try_into()
+        .with_context(|| format!("failed to parse {}", source))
+}
+
+fn parse_value(raw: &str) -> Value {
+    format!("value = {}", raw)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/// Sets `path` in `table`, creating the tables on the way.
+fn set(table: &mut Table, path: &[String], value: Value) -> Result<()> {
+    let (last, parents) = path.split_last().ok_or_else(|| anyhow!("empty key"))?;
+    if path.iter().any(|key| key.is_empty()) {
+        return Err(anyhow!("empty key"));
+    }
+    let mut current = table;
+    for key in parents {
+        let entry = current
+            .entry(key.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        current = entry
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("`{}` is not a table", key))?;
+    }
+    current.insert(last.clone(), value);
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! Where asciivision keeps its config and data on each platform.
+//!
+//! Linux and other Unixes follow the XDG base directories, macOS uses
+//! `~/Library/Application Support`, and Windows uses `%APPDATA%`. Each location
+//! can be overridden with `ASCIIVISION_CONFIG_DIR` or `ASCIIVISION_DATA_DIR`.
+
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+const APP: &str = "asciivision";
+
+/// The user's home directory: `HOME`, or `USERPROFILE` on Windows.
+pub fn home_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home)).filter(|home| !home.as_os_str().is_empty())
+}
+
+/// Directory holding `config.toml`.
+pub fn config_dir() -> Result<PathBuf> {
+    if let Some(dir) = env_dir("ASCIIVISION_CONFIG_DIR") {
+        return Ok(dir);
+    }
+    if cfg!(windows) {
+        return Ok(app_data()?.join(APP).join("config"));
+    }
+    if cfg!(target_os = "macos") {
+        return Ok(application_support()?.join(APP));
+    }
+    Ok(xdg("XDG_CONFIG_HOME", ".config")?.join(APP))
+}
+
+/// Directory holding the conversation database and its backups.
+pub fn data_dir() -> Result<PathBuf> {
+    if let Some(dir) = env_dir("ASCIIVISION_DATA_DIR") {
+        return Ok(dir);
+    }
+    if cfg!(windows) {
+        return Ok(app_data()?.join(APP).join("data"));
+    }
+    if cfg!(target_os = "macos") {
+        return Ok(application_support()?.join(APP));
+    }
+    Ok(xdg("XDG_DATA_HOME", ".local/share")?.join(APP))
+}
+
+/// `file` in `dir`, unless only the copy under `~/.config/asciivision` (where
+/// older builds kept everything) exists yet.
+pub fn with_legacy_fallback(dir: &Path, file: &str) -> PathBuf {
+    let path = dir.join(file);
+    if path.exists() {
+        return path;
+    }
+    home_dir()
+        .map(|home| home.join(".config").join(APP).join(file))
+        .filter(|legacy| legacy.exists())
+        .unwrap_or(path)
+}
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// `$<var>` when set to an absolute path (as the XDG spec requires), otherwise
+/// `~/<fallback>`.
+fn xdg(var: &str, fallback: &str) -> Result<PathBuf> {
+    match env_dir(var).filter(|dir| dir.is_absolute()) {
+        Some(dir) => Ok(dir),
+        None => Ok(home()?.join(fallback)),
+    }
+}
+
+fn app_data() -> Result<PathBuf> {
+    match env_dir("APPDATA") {
+        Some(dir) => Ok(dir),
+        None => Ok(home()?.join("AppData").join("Roaming")),
+    }
+}
+
+fn application_support() -> Result<PathBuf> {
+    Ok(home()?.join("Library").join("Application Support"))
+}
+
+fn home() -> Result<PathBuf> {
+    home_dir().ok_or_else(|| anyhow!("neither HOME nor USERPROFILE is set"))
+}
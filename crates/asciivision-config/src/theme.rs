@@ -0,0 +1,573 @@
+//! The palette every frontend draws with, read through [`t`].
+
+use anyhow::{anyhow, Result};
+use parking_lot::RwLock;
+use rand::Rng;
+use ratatui::prelude::{Color, Line, Style, Stylize};
+use ratatui::widgets::{Block, BorderType, Borders};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+static THEME: OnceLock<RwLock<Theme>> = OnceLock::new();
+static OVERRIDES: OnceLock<RwLock<ColorOverrides>> = OnceLock::new();
+static VISION: OnceLock<RwLock<ColorVision>> = OnceLock::new();
+
+fn theme_lock() -> &'static RwLock<Theme> {
+    THEME.get_or_init(|| RwLock::new(Theme::default_theme()))
+}
+
+fn overrides_lock() -> &'static RwLock<ColorOverrides> {
+    OVERRIDES.get_or_init(|| RwLock::new(ColorOverrides::default()))
+}
+
+fn vision_lock() -> &'static RwLock<ColorVision> {
+    VISION.get_or_init(|| RwLock::new(ColorVision::default()))
+}
+
+pub fn t() -> parking_lot::RwLockReadGuard<'static, Theme> {
+    theme_lock().read()
+}
+
+pub fn set_random_theme() {
+    store(Theme::randomize());
+}
+
+pub fn reset_theme() {
+    store(Theme::default_theme());
+}
+
+pub fn set_theme(name: ThemeName) {
+    store(Theme::named(name));
+}
+
+/// Installs `[colors]`, which then stick through every theme switch.
+pub fn set_color_overrides(colors: ColorOverrides) {
+    *overrides_lock().write() = colors;
+    let current = t().clone();
+    store(current);
+}
+
+/// Installs `color_vision`, which like `[colors]` sticks through every theme
+/// switch; `[colors]` still wins where both set a color.
+pub fn set_color_vision(vision: ColorVision) {
+    *vision_lock().write() = vision;
+    let current = t().clone();
+    store(current);
+}
+
+fn store(mut theme: Theme) {
+    vision_lock().read().apply(&mut theme);
+    overrides_lock().read().apply(&mut theme);
+    *theme_lock().write() = theme;
+}
+
+/// `[colors]`: `#rrggbb` replacements for single colors of whichever theme
+/// is active.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorOverrides {
+    pub bg_base: Option<String>,
+    pub bg_alt: Option<String>,
+    pub panel_bg: Option<String>,
+    pub panel_alt: Option<String>,
+    pub accent1: Option<String>,
+    pub accent2: Option<String>,
+    pub accent3: Option<String>,
+    pub accent4: Option<String>,
+    pub text: Option<String>,
+    pub danger: Option<String>,
+    pub muted: Option<String>,
+    pub user: Option<String>,
+    pub system: Option<String>,
+}
+
+impl ColorOverrides {
+    /// Each override with the theme color it replaces.
+    fn slots<'a>(
+        &'a self,
+        theme: &'a mut Theme,
+    ) -> [(&'static str, Option<&'a str>, &'a mut Color); 13] {
+        [
+            ("bg_base", self.bg_base.as_deref(), &mut theme.bg_base),
+            ("bg_alt", self.bg_alt.as_deref(), &mut theme.bg_alt),
+            ("panel_bg", self.panel_bg.as_deref(), &mut theme.panel_bg),
+            ("panel_alt", self.panel_alt.as_deref(), &mut theme.panel_alt),
+            ("accent1", self.accent1.as_deref(), &mut theme.accent1),
+            ("accent2", self.accent2.as_deref(), &mut theme.accent2),
+            ("accent3", self.accent3.as_deref(), &mut theme.accent3),
+            ("accent4", self.accent4.as_deref(), &mut theme.accent4),
+            ("text", self.text.as_deref(), &mut theme.text),
+            ("danger", self.danger.as_deref(), &mut theme.danger),
+            ("muted", self.muted.as_deref(), &mut theme.muted),
+            ("user", self.user.as_deref(), &mut theme.user),
+            ("system", self.system.as_deref(), &mut theme.system),
+        ]
+    }
+
+    /// Fails on the first value that is not `#rrggbb`.
+    pub fn validate(&self) -> Result<()> {
+        let mut theme = Theme::default_theme();
+        for (name, value, _) in self.slots(&mut theme) {
+            if let Some(value) = value.filter(|value| parse_hex_color(value).is_none()) {
+                return Err(anyhow!("[colors] {} `{}` is not #rrggbb", name, value));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&self, theme: &mut Theme) {
+        for (_, value, color) in self.slots(theme) {
+            if let Some(parsed) = value.and_then(parse_hex_color) {
+                *color = parsed;
+            }
+        }
+    }
+}
+
+/// Palettes selectable with `theme = "..."` in the config or `/theme <name>`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    #[default]
+    Default,
+    Solarized,
+    Dracula,
+    Monochrome,
+    Random,
+}
+
+impl ThemeName {
+    pub const ALL: [ThemeName; 5] = [
+        ThemeName::Default,
+        ThemeName::Solarized,
+        ThemeName::Dracula,
+        ThemeName::Monochrome,
+        ThemeName::Random,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemeName::Default => "default",
+            ThemeName::Solarized => "solarized",
+            ThemeName::Dracula => "dracula",
+            ThemeName::Monochrome => "monochrome",
+            ThemeName::Random => "random",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        Self::ALL.into_iter().find(|theme| theme.name() == name)
+    }
+}
+
+/// `color_vision = "..."`: provider and status colors chosen to stay apart
+/// for a color vision deficiency, laid over whichever theme is active. Only
+/// accents change; backgrounds, text and borders stay the theme's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorVision {
+    #[default]
+    Normal,
+    /// Red-green, green weak. Blue against orange instead of green against red.
+    Deuteranopia,
+    /// Red-green, red weak. As deuteranopia, with a brighter danger color.
+    Protanopia,
+    /// Blue-yellow. Red, teal and pink instead of blue against yellow.
+    Tritanopia,
+}
+
+impl ColorVision {
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorVision::Normal => "normal",
+            ColorVision::Deuteranopia => "deuteranopia",
+            ColorVision::Protanopia => "protanopia",
+            ColorVision::Tritanopia => "tritanopia",
+        }
+    }
+
+    fn apply(self, theme: &mut Theme) {
+        // Okabe-Ito colors, lightened where they vanish on a dark background
+        let red_green = |danger| {
+            (
+                Color::Rgb(230, 159, 0),
+                Color::Rgb(240, 228, 66),
+                Color::Rgb(86, 180, 233),
+                Color::Rgb(170, 200, 255),
+                danger,
+                Color::Rgb(170, 200, 255),
+                ProviderColors {
+                    claude: Color::Rgb(230, 159, 0),
+                    grok: Color::Rgb(86, 180, 233),
+                    openai: Color::Rgb(0, 158, 115),
+                    gemini: Color::Rgb(120, 140, 255),
+                    ollama: Color::Rgb(240, 228, 66),
+                    openrouter: Color::Rgb(204, 121, 167),
+                },
+            )
+        };
+        let (accent1, accent2, accent3, accent4, danger, user, providers) = match self {
+            ColorVision::Normal => return,
+            ColorVision::Deuteranopia => red_green(Color::Rgb(213, 94, 0)),
+            ColorVision::Protanopia => red_green(Color::Rgb(255, 128, 32)),
+            ColorVision::Tritanopia => (
+                Color::Rgb(235, 110, 100),
+                Color::Rgb(255, 170, 200),
+                Color::Rgb(0, 190, 190),
+                Color::Rgb(200, 240, 240),
+                Color::Rgb(220, 30, 40),
+                Color::Rgb(150, 230, 230),
+                ProviderColors {
+                    claude: Color::Rgb(235, 110, 100),
+                    grok: Color::Rgb(0, 190, 190),
+                    openai: Color::Rgb(235, 235, 235),
+                    gemini: Color::Rgb(255, 150, 210),
+                    ollama: Color::Rgb(150, 150, 150),
+                    openrouter: Color::Rgb(170, 40, 90),
+                },
+            ),
+        };
+        theme.accent1 = accent1;
+        theme.accent2 = accent2;
+        theme.accent3 = accent3;
+        theme.accent4 = accent4;
+        theme.danger = danger;
+        theme.user = user;
+        theme.providers = providers;
+    }
+}
+
+/// Accent colors of the built-in providers; custom providers bring their own.
+#[derive(Clone, Copy)]
+pub struct ProviderColors {
+    pub claude: Color,
+    pub grok: Color,
+    pub openai: Color,
+    pub gemini: Color,
+    pub ollama: Color,
+    pub openrouter: Color,
+}
+
+/// Slow hue oscillation layered over the video panel with tachyonfx.
+#[derive(Clone, Copy)]
+pub struct Drift {
+    /// Peak hue shift in degrees.
+    pub hue: f32,
+    /// Time for one sweep, in milliseconds.
+    pub period_ms: u32,
+}
+
+#[derive(Clone)]
+pub struct Theme {
+    pub name: ThemeName,
+    pub bg_base: Color,
+    pub bg_alt: Color,
+    pub panel_bg: Color,
+    pub panel_alt: Color,
+    pub accent1: Color,
+    pub accent2: Color,
+    pub accent3: Color,
+    pub accent4: Color,
+    pub text: Color,
+    pub danger: Color,
+    pub muted: Color,
+    /// Label color of the user's messages.
+    pub user: Color,
+    /// Body text of system notes.
+    pub system: Color,
+    pub providers: ProviderColors,
+    /// Border of the main frames (command deck, input, overlays, analytics).
+    pub border_type: BorderType,
+    /// How strongly the `[chat_background]` video shows through, from 0.0
+    /// (not at all) to 1.0 (full color), unless that table says otherwise.
+    pub backdrop: f32,
+    pub drift: Option<Drift>,
+}
+
+impl Theme {
+    pub fn named(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Default => Self::default_theme(),
+            ThemeName::Solarized => Self::solarized(),
+            ThemeName::Dracula => Self::dracula(),
+            ThemeName::Monochrome => Self::monochrome(),
+            ThemeName::Random => Self::randomize(),
+        }
+    }
+
+    pub fn default_theme() -> Self {
+        Self {
+            name: ThemeName::Default,
+            bg_base: Color::Rgb(3, 8, 12),
+            bg_alt: Color::Rgb(10, 17, 24),
+            panel_bg: Color::Rgb(6, 13, 19),
+            panel_alt: Color::Rgb(10, 19, 29),
+            accent1: Color::Rgb(214, 153, 104),
+            accent2: Color::Rgb(241, 189, 105),
+            accent3: Color::Rgb(54, 154, 158),
+            accent4: Color::Rgb(118, 214, 226),
+            text: Color::Rgb(207, 230, 232),
+            danger: Color::Rgb(225, 92, 84),
+            muted: Color::Rgb(101, 121, 134),
+            user: Color::Rgb(118, 214, 226),
+            system: Color::Rgb(171, 183, 192),
+            providers: DEFAULT_PROVIDER_COLORS,
+            border_type: BorderType::Double,
+            backdrop: 0.22,
+            drift: None,
+        }
+    }
+
+    pub fn solarized() -> Self {
+        Self {
+            name: ThemeName::Solarized,
+            bg_base: Color::Rgb(0, 34, 43),
+            bg_alt: Color::Rgb(0, 43, 54),
+            panel_bg: Color::Rgb(0, 43, 54),
+            panel_alt: Color::Rgb(7, 54, 66),
+            accent1: Color::Rgb(203, 75, 22),
+            accent2: Color::Rgb(181, 137, 0),
+            accent3: Color::Rgb(42, 161, 152),
+            accent4: Color::Rgb(38, 139, 210),
+            text: Color::Rgb(147, 161, 161),
+            danger: Color::Rgb(220, 50, 47),
+            muted: Color::Rgb(88, 110, 117),
+            user: Color::Rgb(38, 139, 210),
+            system: Color::Rgb(131, 148, 150),
+            providers: ProviderColors {
+                claude: Color::Rgb(203, 75, 22),
+                grok: Color::Rgb(42, 161, 152),
+                openai: Color::Rgb(133, 153, 0),
+                gemini: Color::Rgb(38, 139, 210),
+                ollama: Color::Rgb(181, 137, 0),
+                openrouter: Color::Rgb(108, 113, 196),
+            },
+            border_type: BorderType::Double,
+            backdrop: 0.2,
+            drift: Some(Drift {
+                hue: 12.0,
+                period_ms: 6000,
+            }),
+        }
+    }
+
+    pub fn dracula() -> Self {
+        Self {
+            name: ThemeName::Dracula,
+            bg_base: Color::Rgb(33, 34, 44),
+            bg_alt: Color::Rgb(40, 42, 54),
+            panel_bg: Color::Rgb(40, 42, 54),
+            panel_alt: Color::Rgb(68, 71, 90),
+            accent1: Color::Rgb(189, 147, 249),
+            accent2: Color::Rgb(255, 121, 198),
+            accent3: Color::Rgb(98, 114, 164),
+            accent4: Color::Rgb(139, 233, 253),
+            text: Color::Rgb(248, 248, 242),
+            danger: Color::Rgb(255, 85, 85),
+            muted: Color::Rgb(98, 114, 164),
+            user: Color::Rgb(80, 250, 123),
+            system: Color::Rgb(170, 174, 196),
+            providers: ProviderColors {
+                claude: Color::Rgb(255, 184, 108),
+                grok: Color::Rgb(139, 233, 253),
+                openai: Color::Rgb(80, 250, 123),
+                gemini: Color::Rgb(189, 147, 249),
+                ollama: Color::Rgb(241, 250, 140),
+                openrouter: Color::Rgb(255, 121, 198),
+            },
+            border_type: BorderType::Rounded,
+            backdrop: 0.18,
+            drift: Some(Drift {
+                hue: 30.0,
+                period_ms: 4000,
+            }),
+        }
+    }
+
+    pub fn monochrome() -> Self {
+        Self {
+            name: ThemeName::Monochrome,
+            bg_base: Color::Rgb(8, 8, 8),
+            bg_alt: Color::Rgb(16, 16, 16),
+            panel_bg: Color::Rgb(12, 12, 12),
+            panel_alt: Color::Rgb(30, 30, 30),
+            accent1: Color::Rgb(200, 200, 200),
+            accent2: Color::Rgb(235, 235, 235),
+            accent3: Color::Rgb(140, 140, 140),
+            accent4: Color::Rgb(255, 255, 255),
+            text: Color::Rgb(215, 215, 215),
+            danger: Color::Rgb(255, 255, 255),
+            muted: Color::Rgb(110, 110, 110),
+            user: Color::Rgb(255, 255, 255),
+            system: Color::Rgb(150, 150, 150),
+            providers: ProviderColors {
+                claude: Color::Rgb(230, 230, 230),
+                grok: Color::Rgb(200, 200, 200),
+                openai: Color::Rgb(180, 180, 180),
+                gemini: Color::Rgb(215, 215, 215),
+                ollama: Color::Rgb(165, 165, 165),
+                openrouter: Color::Rgb(190, 190, 190),
+            },
+            border_type: BorderType::Plain,
+            backdrop: 0.28,
+            drift: None,
+        }
+    }
+
+    pub fn randomize() -> Self {
+        let mut rng = rand::thread_rng();
+        let base_hue: f32 = rng.gen_range(0.0..360.0);
+
+        let bg_base = hsl_to_color(base_hue, 0.3, rng.gen_range(0.02..0.05));
+        let bg_alt = hsl_to_color(base_hue + 5.0, 0.25, rng.gen_range(0.04..0.08));
+        let panel_bg = hsl_to_color(base_hue + 10.0, 0.28, rng.gen_range(0.03..0.07));
+        let panel_alt = hsl_to_color(base_hue + 15.0, 0.22, rng.gen_range(0.05..0.10));
+
+        let accent1 = hsl_to_color(
+            base_hue + rng.gen_range(0.0..30.0),
+            rng.gen_range(0.55..0.78),
+            rng.gen_range(0.55..0.70),
+        );
+        let accent2 = hsl_to_color(
+            base_hue + rng.gen_range(25.0..55.0),
+            rng.gen_range(0.60..0.88),
+            rng.gen_range(0.58..0.75),
+        );
+        let accent3 = hsl_to_color(
+            base_hue + rng.gen_range(120.0..180.0),
+            rng.gen_range(0.45..0.68),
+            rng.gen_range(0.40..0.58),
+        );
+        let accent4 = hsl_to_color(
+            base_hue + rng.gen_range(150.0..210.0),
+            rng.gen_range(0.50..0.78),
+            rng.gen_range(0.55..0.72),
+        );
+
+        let text = hsl_to_color(
+            base_hue + rng.gen_range(140.0..200.0),
+            rng.gen_range(0.10..0.28),
+            rng.gen_range(0.85..0.94),
+        );
+
+        let danger = hsl_to_color(
+            rng.gen_range(348.0..382.0) % 360.0,
+            rng.gen_range(0.65..0.88),
+            rng.gen_range(0.48..0.62),
+        );
+
+        let muted = hsl_to_color(
+            base_hue + rng.gen_range(10.0..30.0),
+            rng.gen_range(0.08..0.22),
+            rng.gen_range(0.38..0.52),
+        );
+
+        Self {
+            name: ThemeName::Random,
+            bg_base,
+            bg_alt,
+            panel_bg,
+            panel_alt,
+            accent1,
+            accent2,
+            accent3,
+            accent4,
+            text,
+            danger,
+            muted,
+            user: accent4,
+            system: muted,
+            providers: DEFAULT_PROVIDER_COLORS,
+            border_type: BorderType::Double,
+            backdrop: 0.2,
+            drift: Some(Drift {
+                hue: rng.gen_range(10.0..40.0),
+                period_ms: 5000,
+            }),
+        }
+    }
+
+    pub fn text_style(&self) -> Style {
+        Style::default().fg(self.text)
+    }
+
+    pub fn muted_style(&self) -> Style {
+        Style::default().fg(self.muted)
+    }
+
+    /// Bold field labels (`TOTAL MSGS:`, `mode:`, help headings).
+    pub fn label_style(&self) -> Style {
+        Style::default().fg(self.accent2).bold()
+    }
+
+    pub fn panel_style(&self) -> Style {
+        Style::default().bg(self.panel_bg)
+    }
+
+    /// A titled frame with the theme's border type.
+    pub fn block<'a>(
+        &self,
+        title: impl Into<Line<'a>>,
+        title_color: Color,
+        border_color: Color,
+    ) -> Block<'a> {
+        Block::default()
+            .title(title)
+            .title_style(Style::default().fg(title_color).bold())
+            .borders(Borders::ALL)
+            .border_type(self.border_type)
+            .border_style(Style::default().fg(border_color))
+    }
+}
+
+const DEFAULT_PROVIDER_COLORS: ProviderColors = ProviderColors {
+    claude: Color::Rgb(218, 155, 102),
+    grok: Color::Rgb(104, 206, 232),
+    openai: Color::Rgb(108, 197, 181),
+    gemini: Color::Rgb(119, 153, 234),
+    ollama: Color::Rgb(144, 214, 121),
+    openrouter: Color::Rgb(176, 140, 236),
+};
+
+/// `#rrggbb` as a color.
+pub fn parse_hex_color(value: &str) -> Option<Color> {
+    let hex = value.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |range| u8::from_str_radix(hex.get(range)?, 16).ok();
+    Some(Color::Rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?))
+}
+
+pub fn color_to_rgb(c: Color) -> (u8, u8, u8) {
+    match c {
+        Color::Rgb(r, g, b) => (r, g, b),
+        _ => (180, 180, 180),
+    }
+}
+
+fn hsl_to_color(h: f32, s: f32, l: f32) -> Color {
+    let h = ((h % 360.0) + 360.0) % 360.0;
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let m = l - c / 2.0;
+    let (r1, g1, b1) = if h < 60.0 {
+        (c, x, 0.0)
+    } else if h < 120.0 {
+        (x, c, 0.0)
+    } else if h < 180.0 {
+        (0.0, c, x)
+    } else if h < 240.0 {
+        (0.0, x, c)
+    } else if h < 300.0 {
+        (x, 0.0, c)
+    } else {
+        (c, 0.0, x)
+    };
+    Color::Rgb(
+        ((r1 + m) * 255.0).clamp(0.0, 255.0) as u8,
+        ((g1 + m) * 255.0).clamp(0.0, 255.0) as u8,
+        ((b1 + m) * 255.0).clamp(0.0, 255.0) as u8,
+    )
+}
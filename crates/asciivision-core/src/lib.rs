@@ -0,0 +1,416 @@
+//! The ASCII engine behind asciivision.
+//!
+//! The pipeline has two halves that can be used independently:
+//!
+//! 1. **Convert** an RGB24 pixel buffer into an [`AsciiFrame`]: one glyph per pixel,
+//!    picked from [`PALETTE`] by luminance, carrying the source color along.
+//! 2. **Render** an [`AsciiFrame`] into a ratatui [`Buffer`], either 1:1 and centered
+//!    ([`render_cropped`]) or letterboxed to fit the target area ([`render_fitted`]),
+//!    or faintly behind text already in the buffer ([`render_backdrop`]).
+//!
+//! Colors are written as 24-bit RGB unless [`set_color_depth`] says the terminal
+//! shows fewer, and [`cell_aspect`] is the width-to-height ratio of its cells
+//! that sizing code corrects for; both are set once at startup from what the
+//! terminal reports.
+//!
+//! Enable the `ffmpeg` feature for the [`ffmpeg`] module, which builds the RGB24
+//! scaler and converts decoded `ffmpeg-next` video frames directly. The
+//! `testing` feature adds the `testing` module, which turns rendered buffers
+//! into the text the workspace's insta snapshot tests compare.
+//!
+//! ```
+//! use asciivision_core::{render_fitted, AsciiFrame};
+//! use ratatui::{buffer::Buffer, layout::Rect};
+//!
+//! // a 2x1 image: one black pixel, one white pixel
+//! let pixels = [0, 0, 0, 255, 255, 255];
+//! let frame = AsciiFrame::from_rgb24(&pixels, 2, 1, 6);
+//! assert_eq!(frame.cell(0, 0).map(|c| c.0), Some(' '));
+//! assert_eq!(frame.cell(1, 0).map(|c| c.0), Some('$'));
+//!
+//! let area = Rect::new(0, 0, 2, 1);
+//! let mut buffer = Buffer::empty(area);
+//! render_fitted(&mut buffer, area, &frame, 1.0);
+//! ```
+
+use ratatui::{buffer::Buffer, layout::Rect, style::Color};
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use unicode_width::UnicodeWidthStr;
+
+/// Glyph ramp ordered from darkest to brightest.
+pub const PALETTE: &[u8] =
+    b" .'`^\",:;Il!i><~+_-?][}{1)(|\\tfjrxnuvczXYUJCLQ0OZmwqpdbkhao*#MW&8%B@$";
+
+/// How many colors the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorDepth {
+    /// 24-bit RGB.
+    #[default]
+    TrueColor,
+    /// The xterm 256-color palette.
+    Ansi256,
+    /// The 16 basic ANSI colors.
+    Ansi16,
+}
+
+impl ColorDepth {
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorDepth::TrueColor => "truecolor",
+            ColorDepth::Ansi256 => "256",
+            ColorDepth::Ansi16 => "16",
+        }
+    }
+
+    /// An RGB color as a terminal of this depth can show it: unchanged in
+    /// truecolor, the nearest palette entry otherwise.
+    pub fn rgb(self, r: u8, g: u8, b: u8) -> Color {
+        match self {
+            ColorDepth::TrueColor => Color::Rgb(r, g, b),
+            ColorDepth::Ansi256 => Color::Indexed(ansi256(r, g, b)),
+            ColorDepth::Ansi16 => Color::Indexed(ansi16(r, g, b)),
+        }
+    }
+}
+
+static COLOR_DEPTH: AtomicU8 = AtomicU8::new(0);
+/// Bits of the `f32` cell width / height; cells are about twice as tall as wide.
+static CELL_ASPECT: AtomicU32 = AtomicU32::new(0.5f32.to_bits());
+
+/// Sets the depth [`rgb`] reduces colors to for every renderer.
+pub fn set_color_depth(depth: ColorDepth) {
+    COLOR_DEPTH.store(depth as u8, Ordering::Relaxed);
+}
+
+pub fn color_depth() -> ColorDepth {
+    match COLOR_DEPTH.load(Ordering::Relaxed) {
+        1 => ColorDepth::Ansi256,
+        2 => ColorDepth::Ansi16,
+        _ => ColorDepth::TrueColor,
+    }
+}
+
+/// Sets the cell width / height ratio, e.g. 0.45 for 9x20 pixel cells.
+/// Values outside 0.2 to 1.5 are ignored.
+pub fn set_cell_aspect(aspect: f32) {
+    if (0.2..=1.5).contains(&aspect) {
+        CELL_ASPECT.store(aspect.to_bits(), Ordering::Relaxed);
+    }
+}
+
+pub fn cell_aspect() -> f32 {
+    f32::from_bits(CELL_ASPECT.load(Ordering::Relaxed))
+}
+
+/// An RGB color at the current [`color_depth`].
+pub fn rgb(r: u8, g: u8, b: u8) -> Color {
+    color_depth().rgb(r, g, b)
+}
+
+/// Levels of each channel in the 6x6x6 cube at indices 16 to 231.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The xterm defaults for the 16 basic colors.
+const BASIC: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+fn distance((r, g, b): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> i32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+    d(r, r2) + d(g, g2) + d(b, b2)
+}
+
+/// Nearest of the color cube and the 24-step gray ramp.
+fn ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| {
+        (0..6u8)
+            .min_by_key(|&i| (CUBE[i as usize] as i32 - v as i32).abs())
+            .unwrap_or(0)
+    };
+    let (cr, cg, cb) = (level(r), level(g), level(b));
+    let cube = (CUBE[cr as usize], CUBE[cg as usize], CUBE[cb as usize]);
+    let average = (r as u16 + g as u16 + b as u16) / 3;
+    let step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray = 8 + step * 10;
+    if distance((r, g, b), (gray, gray, gray)) < distance((r, g, b), cube) {
+        232 + step
+    } else {
+        16 + 36 * cr + 6 * cg + cb
+    }
+}
+
+fn ansi16(r: u8, g: u8, b: u8) -> u8 {
+    (0..16u8)
+        .min_by_key(|&i| distance((r, g, b), BASIC[i as usize]))
+        .unwrap_or(0)
+}
+
+/// A grid of colored glyphs, row-major, `width * height` cells long.
+///
+/// Each cell is `(glyph, r, g, b)` where the color is the source pixel color.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AsciiFrame {
+    pub width: u16,
+    pub height: u16,
+    pub cells: Vec<(char, u8, u8, u8)>,
+}
+
+impl AsciiFrame {
+    /// Converts a packed RGB24 buffer into a frame.
+    ///
+    /// `stride` is the number of bytes per row (at least `width * 3`). Rows that are
+    /// shorter than expected are padded with blank cells instead of panicking.
+    pub fn from_rgb24(data: &[u8], width: u16, height: u16, stride: usize) -> Self {
+        let (w, h) = (width as usize, height as usize);
+        let mut cells = Vec::with_capacity(w * h);
+
+        for y in 0..h {
+            let start = y * stride;
+            let row = data.get(start..start + w * 3).unwrap_or(&[]);
+            for x in 0..w {
+                match row.get(x * 3..x * 3 + 3) {
+                    Some(&[r, g, b]) => cells.push((glyph_for(r, g, b), r, g, b)),
+                    _ => cells.push((' ', 0, 0, 0)),
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Returns the cell at `(x, y)`, or `None` when out of bounds.
+    pub fn cell(&self, x: u16, y: u16) -> Option<(char, u8, u8, u8)> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells
+            .get(y as usize * self.width as usize + x as usize)
+            .copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0 || self.cells.is_empty()
+    }
+}
+
+/// Rec. 601 luma of an RGB color.
+pub fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32).min(255.0) as u8
+}
+
+/// Picks the [`PALETTE`] glyph matching the brightness of an RGB color.
+pub fn glyph_for(r: u8, g: u8, b: u8) -> char {
+    let y = luminance(r, g, b) as usize;
+    let index = (y * (PALETTE.len() - 1)) / 255;
+    PALETTE[index.min(PALETTE.len() - 1)] as char
+}
+
+/// Multiplies an RGB color by `factor`, saturating each channel, at the
+/// current [`color_depth`].
+pub fn scale_rgb(r: u8, g: u8, b: u8, factor: f32) -> Color {
+    rgb(
+        (r as f32 * factor).clamp(0.0, 255.0) as u8,
+        (g as f32 * factor).clamp(0.0, 255.0) as u8,
+        (b as f32 * factor).clamp(0.0, 255.0) as u8,
+    )
+}
+
+/// Renders `ascii` 1:1, centered in `area` and cropped when it does not fit.
+///
+/// `intensity` scales brightness (1.0 is the source color); alternating rows are
+/// dimmed slightly for a scanline look and the background gets a faint tint.
+pub fn render_cropped(buffer: &mut Buffer, area: Rect, ascii: &AsciiFrame, intensity: f32) {
+    if area.width == 0 || area.height == 0 || ascii.is_empty() {
+        return;
+    }
+
+    let content_width = ascii.width.min(area.width);
+    let content_height = ascii.height.min(area.height);
+    let offset_x = area.x + (area.width - content_width) / 2;
+    let offset_y = area.y + (area.height - content_height) / 2;
+
+    for y in 0..content_height {
+        for x in 0..content_width {
+            if let Some(cell) = ascii.cell(x, y) {
+                paint_cell(buffer, offset_x + x, offset_y + y, y, cell, intensity);
+            }
+        }
+    }
+}
+
+/// Renders `ascii` scaled to fit `area` with its aspect ratio preserved.
+///
+/// Sizes within two cells of the target are drawn 1:1 (see [`render_cropped`]);
+/// anything else is nearest-neighbor sampled and letterboxed. Both sides are in
+/// terminal-cell coordinates, so no extra cell aspect correction is applied.
+pub fn render_fitted(buffer: &mut Buffer, area: Rect, ascii: &AsciiFrame, intensity: f32) {
+    if area.width == 0 || area.height == 0 || ascii.is_empty() {
+        return;
+    }
+
+    let needs_scaling =
+        ascii.width.abs_diff(area.width) > 2 || ascii.height.abs_diff(area.height) > 2;
+    if !needs_scaling {
+        render_cropped(buffer, area, ascii, intensity);
+        return;
+    }
+
+    let src_ratio = ascii.width as f32 / ascii.height as f32;
+    let dst_ratio = area.width as f32 / area.height as f32;
+
+    let (fit_w, fit_h) = if src_ratio > dst_ratio {
+        let h = (area.width as f32 / src_ratio).round().max(1.0) as u16;
+        (area.width, h.min(area.height))
+    } else {
+        let w = (area.height as f32 * src_ratio).round().max(1.0) as u16;
+        (w.min(area.width), area.height)
+    };
+
+    let offset_x = area.x + area.width.saturating_sub(fit_w) / 2;
+    let offset_y = area.y + area.height.saturating_sub(fit_h) / 2;
+
+    for y in 0..fit_h {
+        let src_y = ((y as f32 * ascii.height as f32 / fit_h as f32) as u16)
+            .min(ascii.height - 1);
+        for x in 0..fit_w {
+            let src_x = ((x as f32 * ascii.width as f32 / fit_w as f32) as u16)
+                .min(ascii.width - 1);
+            if let Some(cell) = ascii.cell(src_x, src_y) {
+                paint_cell(buffer, offset_x + x, offset_y + y, y, cell, intensity);
+            }
+        }
+    }
+}
+
+/// Draws `ascii` behind whatever is already in `area`: it is scaled to cover
+/// the area, cropping the overflow, and only blank cells take a glyph (not
+/// the one hidden behind a wide CJK or emoji glyph to its left). Glyph
+/// colors are mixed `amount` (0.0 to 1.0) of the way from `base` toward the
+/// source, and cell backgrounds are left alone, so text stays readable on top.
+pub fn render_backdrop(
+    buffer: &mut Buffer,
+    area: Rect,
+    ascii: &AsciiFrame,
+    amount: f32,
+    base: Color,
+) {
+    if area.width == 0 || area.height == 0 || ascii.is_empty() {
+        return;
+    }
+
+    let amount = amount.clamp(0.0, 1.0);
+    let (base_r, base_g, base_b) = match base {
+        Color::Rgb(r, g, b) => (r, g, b),
+        _ => (0, 0, 0),
+    };
+    let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount) as u8;
+
+    let scale = (area.width as f32 / ascii.width as f32)
+        .max(area.height as f32 / ascii.height as f32);
+    let skip_x = (ascii.width as f32 * scale - area.width as f32) / 2.0;
+    let skip_y = (ascii.height as f32 * scale - area.height as f32) / 2.0;
+
+    for y in 0..area.height {
+        let src_y = (((y as f32 + skip_y) / scale) as u16).min(ascii.height - 1);
+        for x in 0..area.width {
+            let src_x = (((x as f32 + skip_x) / scale) as u16).min(ascii.width - 1);
+            let Some((glyph, r, g, b)) = ascii.cell(src_x, src_y) else {
+                continue;
+            };
+            let hidden = (area.x + x)
+                .checked_sub(1)
+                .and_then(|left| buffer.cell((left, area.y + y)))
+                .is_some_and(|left| left.symbol().width() > 1);
+            if hidden {
+                continue;
+            }
+            let Some(cell) = buffer.cell_mut((area.x + x, area.y + y)) else {
+                continue;
+            };
+            if glyph == ' ' || cell.symbol() != " " {
+                continue;
+            }
+            cell.set_char(glyph);
+            cell.set_fg(rgb(mix(base_r, r), mix(base_g, g), mix(base_b, b)));
+        }
+    }
+}
+
+fn paint_cell(
+    buffer: &mut Buffer,
+    x: u16,
+    y: u16,
+    row: u16,
+    (glyph, r, g, b): (char, u8, u8, u8),
+    intensity: f32,
+) {
+    let scanline = if row.is_multiple_of(2) { 0.84 } else { 1.0 };
+    let factor = (intensity * scanline).clamp(0.1, 1.2);
+
+    if let Some(cell) = buffer.cell_mut((x, y)) {
+        cell.set_char(glyph);
+        cell.set_fg(scale_rgb(r, g, b, factor));
+        cell.set_bg(scale_rgb(r, g, b, factor * 0.16));
+    }
+}
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+/// FFmpeg glue: scaler construction and decoded-frame conversion.
+#[cfg(feature = "ffmpeg")]
+pub mod ffmpeg {
+    use super::AsciiFrame;
+    use ffmpeg_next::format::Pixel;
+    use ffmpeg_next::software::scaling::{context::Context as Scaler, flag::Flags};
+    use ffmpeg_next::util::frame::video::Video;
+
+    /// Builds a bilinear scaler from the decoder's native format to RGB24 at the
+    /// requested output size, ready to feed [`frame_from_video`].
+    pub fn build_scaler(
+        src_format: Pixel,
+        src_width: u32,
+        src_height: u32,
+        dst_width: u32,
+        dst_height: u32,
+    ) -> Result<Scaler, ffmpeg_next::Error> {
+        Scaler::get(
+            src_format,
+            src_width,
+            src_height,
+            Pixel::RGB24,
+            dst_width,
+            dst_height,
+            Flags::BILINEAR,
+        )
+    }
+
+    /// Converts an RGB24 video frame (the scaler output) into an [`AsciiFrame`].
+    pub fn frame_from_video(rgb: &Video) -> AsciiFrame {
+        AsciiFrame::from_rgb24(
+            rgb.data(0),
+            rgb.width() as u16,
+            rgb.height() as u16,
+            rgb.stride(0),
+        )
+    }
+}
@@ -0,0 +1,28 @@
+//! Plain-text renders of ratatui buffers for snapshot tests.
+//!
+//! [`buffer_text`] turns a [`Buffer`] into one line of text per row, which
+//! tests hand to `insta::assert_snapshot!`. A new or changed render fails the
+//! test and leaves a `.snap.new` file beside the snapshot; review it with
+//! `cargo insta review`.
+
+use ratatui::buffer::Buffer;
+use unicode_width::UnicodeWidthStr;
+
+/// The buffer's symbols row by row, with trailing blanks trimmed.
+pub fn buffer_text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        let mut line = String::new();
+        let mut x = area.left();
+        while x < area.right() {
+            let symbol = buffer[(x, y)].symbol();
+            line.push_str(symbol);
+            // a wide glyph covers the cells after it
+            x += (symbol.width() as u16).max(1);
+        }
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
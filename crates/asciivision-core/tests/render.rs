@@ -0,0 +1,100 @@
+use asciivision_core::testing::buffer_text;
+use asciivision_core::{
+    render_backdrop, render_cropped, render_fitted, scale_rgb, AsciiFrame, ColorDepth,
+};
+use insta::assert_snapshot;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+};
+
+/// A `width` x `height` grey ramp, black on the left to white on the right.
+fn gradient(width: u16, height: u16) -> AsciiFrame {
+    let mut pixels = Vec::new();
+    for _ in 0..height {
+        for x in 0..width {
+            let level = (x as u32 * 255 / (width as u32 - 1)) as u8;
+            pixels.extend([level, level, level]);
+        }
+    }
+    AsciiFrame::from_rgb24(&pixels, width, height, width as usize * 3)
+}
+
+/// Red and white squares of `cell` pixels, `squares` to a side.
+fn checkerboard(squares: u16, cell: u16) -> AsciiFrame {
+    let side = squares * cell;
+    let mut pixels = Vec::new();
+    for y in 0..side {
+        for x in 0..side {
+            let white = (x / cell + y / cell).is_multiple_of(2);
+            pixels.extend(if white { [255, 255, 255] } else { [200, 0, 0] });
+        }
+    }
+    AsciiFrame::from_rgb24(&pixels, side, side, side as usize * 3)
+}
+
+fn render(width: u16, height: u16, draw: impl FnOnce(&mut Buffer, Rect)) -> Buffer {
+    let area = Rect::new(0, 0, width, height);
+    let mut buffer = Buffer::empty(area);
+    draw(&mut buffer, area);
+    buffer
+}
+
+#[test]
+fn gradient_walks_the_palette() {
+    let frame = gradient(32, 2);
+    let buffer = render(32, 2, |buffer, area| render_cropped(buffer, area, &frame, 1.0));
+    assert_snapshot!("gradient_cropped", buffer_text(&buffer));
+}
+
+#[test]
+fn cropped_frames_are_centered() {
+    let frame = checkerboard(3, 2);
+    let large = render(10, 8, |buffer, area| render_cropped(buffer, area, &frame, 1.0));
+    assert_snapshot!("checkerboard_centered", buffer_text(&large));
+    let small = render(4, 4, |buffer, area| render_cropped(buffer, area, &frame, 1.0));
+    assert_snapshot!("checkerboard_cropped", buffer_text(&small));
+}
+
+#[test]
+fn fitted_frames_are_letterboxed() {
+    let frame = checkerboard(4, 4);
+    let wide = render(40, 8, |buffer, area| render_fitted(buffer, area, &frame, 1.0));
+    assert_snapshot!("checkerboard_fitted_wide", buffer_text(&wide));
+    let tall = render(8, 12, |buffer, area| render_fitted(buffer, area, &frame, 1.0));
+    assert_snapshot!("checkerboard_fitted_tall", buffer_text(&tall));
+}
+
+#[test]
+fn even_rows_are_dimmed_for_scanlines() {
+    let frame = checkerboard(1, 2);
+    let buffer = render(2, 2, |buffer, area| render_cropped(buffer, area, &frame, 1.0));
+    assert_eq!(buffer[(0, 0)].fg, scale_rgb(255, 255, 255, 0.84));
+    assert_eq!(buffer[(0, 1)].fg, scale_rgb(255, 255, 255, 1.0));
+    assert_eq!(buffer[(0, 1)].bg, scale_rgb(255, 255, 255, 0.16));
+}
+
+#[test]
+fn backdrop_covers_the_area_behind_text() {
+    let frame = checkerboard(4, 4);
+    let buffer = render(24, 6, |buffer, area| {
+        buffer.set_string(2, 1, "text stays", Style::default());
+        render_backdrop(buffer, area, &frame, 0.5, Color::Rgb(0, 0, 0));
+    });
+    assert_snapshot!("checkerboard_backdrop", buffer_text(&buffer));
+    assert_eq!(buffer[(2, 1)].symbol(), "t");
+    assert_eq!(buffer[(0, 0)].fg, Color::Rgb(100, 0, 0));
+    assert_eq!(buffer[(6, 0)].fg, Color::Rgb(127, 127, 127));
+    assert_eq!(buffer[(0, 0)].bg, Color::Reset);
+}
+
+#[test]
+fn colors_are_reduced_to_the_terminal_palette() {
+    assert_eq!(ColorDepth::TrueColor.rgb(255, 0, 0), Color::Rgb(255, 0, 0));
+    assert_eq!(ColorDepth::Ansi256.rgb(255, 0, 0), Color::Indexed(196));
+    assert_eq!(ColorDepth::Ansi256.rgb(128, 128, 128), Color::Indexed(244));
+    assert_eq!(ColorDepth::Ansi256.rgb(0, 95, 135), Color::Indexed(24));
+    assert_eq!(ColorDepth::Ansi16.rgb(250, 10, 10), Color::Indexed(9));
+    assert_eq!(ColorDepth::Ansi16.rgb(10, 10, 10), Color::Indexed(0));
+}
@@ -0,0 +1,155 @@
+//! Terminal setup shared by every asciivision frontend, the chat deck, the
+//! player and the analytics dashboard.
+//!
+//! [`TerminalGuard::enter`] switches to raw mode and the alternate screen.
+//! The terminal is put back when the guard is restored or dropped, and, once
+//! [`install_panic_hook`] has run, when the thread that entered it panics, so
+//! the panic message lands on a normal screen instead of a raw one.
+//! [`TerminalGuard::enter_inline`] does the same without the alternate screen,
+//! for a viewport drawn into the normal scrollback. [`silence_stderr`] keeps
+//! library chatter from drawing over the UI and hands the real stderr back on
+//! restore. [`Capabilities`] is what the terminal can show, from the
+//! environment and, once raw mode is on, from asking the terminal itself.
+
+use anyhow::Result;
+use crossterm::{
+    cursor::Show,
+    event::{DisableFocusChange, EnableFocusChange},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::sync::{
+    atomic::{AtomicBool, AtomicI32, Ordering},
+    Mutex,
+};
+use std::thread::{self, ThreadId};
+
+mod probe;
+
+pub use probe::Capabilities;
+
+/// Whether raw mode is currently on.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+/// Whether the alternate screen is on, which inline guards leave off.
+static ALTERNATE: AtomicBool = AtomicBool::new(false);
+/// The thread that entered, the only one whose panic takes the UI down.
+static OWNER: Mutex<Option<ThreadId>> = Mutex::new(None);
+/// A copy of the real stderr while fd 2 points at `/dev/null`, or -1.
+static SAVED_STDERR: AtomicI32 = AtomicI32::new(-1);
+
+#[cfg(unix)]
+extern "C" {
+    fn dup(fd: i32) -> i32;
+    fn dup2(oldfd: i32, newfd: i32) -> i32;
+    fn close(fd: i32) -> i32;
+}
+
+/// Raw mode, the alternate screen and focus reporting for as long as it lives.
+pub struct TerminalGuard {
+    _private: (),
+}
+
+impl TerminalGuard {
+    pub fn enter() -> Result<Self> {
+        Self::start(true)
+    }
+
+    /// Raw mode and focus reporting only. What is drawn stays in the
+    /// scrollback once the guard is restored.
+    pub fn enter_inline() -> Result<Self> {
+        Self::start(false)
+    }
+
+    fn start(alternate: bool) -> Result<Self> {
+        *OWNER.lock().unwrap_or_else(|e| e.into_inner()) = Some(thread::current().id());
+        // built first so a failure half way through still undoes what was done
+        let guard = TerminalGuard { _private: () };
+        ACTIVE.store(true, Ordering::SeqCst);
+        ALTERNATE.store(alternate, Ordering::SeqCst);
+        enable_raw_mode()?;
+        if alternate {
+            execute!(std::io::stdout(), EnterAlternateScreen)?;
+        }
+        execute!(std::io::stdout(), EnableFocusChange)?;
+        Ok(guard)
+    }
+
+    /// Restores the terminal now, so errors can be reported; dropping the
+    /// guard afterwards does nothing.
+    pub fn restore(&mut self) -> Result<()> {
+        restore()
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore();
+    }
+}
+
+/// Restores the terminal before the panic message is printed when the thread
+/// that called [`TerminalGuard::enter`] panics. Panics on other threads, like
+/// a background task or the webcam reader, are left to whoever catches them.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let owner = *OWNER.lock().unwrap_or_else(|e| e.into_inner());
+        if owner == Some(thread::current().id()) {
+            let _ = restore();
+        }
+        previous(info);
+    }));
+}
+
+/// Points stderr at `/dev/null` until the terminal is restored. FFmpeg and
+/// other native libraries write there, which would corrupt the display.
+pub fn silence_stderr() {
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+        if SAVED_STDERR.load(Ordering::SeqCst) >= 0 {
+            return;
+        }
+        let Ok(devnull) = std::fs::OpenOptions::new().write(true).open("/dev/null") else {
+            return;
+        };
+        let saved = unsafe { dup(2) };
+        if saved < 0 {
+            return;
+        }
+        if unsafe { dup2(devnull.as_raw_fd(), 2) } < 0 {
+            unsafe { close(saved) };
+            return;
+        }
+        SAVED_STDERR.store(saved, Ordering::SeqCst);
+    }
+}
+
+fn restore_stderr() {
+    #[cfg(unix)]
+    {
+        let saved = SAVED_STDERR.swap(-1, Ordering::SeqCst);
+        if saved >= 0 {
+            unsafe {
+                dup2(saved, 2);
+                close(saved);
+            }
+        }
+    }
+}
+
+fn restore() -> Result<()> {
+    restore_stderr();
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    // leave the alternate screen even when raw mode refuses to turn off
+    let raw = disable_raw_mode();
+    execute!(std::io::stdout(), DisableFocusChange)?;
+    if ALTERNATE.swap(false, Ordering::SeqCst) {
+        execute!(std::io::stdout(), LeaveAlternateScreen)?;
+    }
+    execute!(std::io::stdout(), Show)?;
+    raw?;
+    Ok(())
+}
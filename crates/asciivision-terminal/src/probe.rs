@@ -0,0 +1,314 @@
+//! What the terminal can show, worked out once at startup.
+//!
+//! [`Capabilities::from_env`] reads `COLORTERM`, `TERM`, `TERM_PROGRAM` and the
+//! locale. [`Capabilities::probe`] adds the terminal's own answers to a few
+//! queries: its window and cell size in pixels (CSI 14t and 16t), whether it
+//! draws a wide glyph across two cells (a cursor position report after one),
+//! kitty graphics (a query image) and sixel (the primary device attributes,
+//! which every terminal answers, so they also end the wait).
+
+use asciivision_core::ColorDepth;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    /// `TERM`, empty when unset.
+    pub term: String,
+    /// `TERM_PROGRAM`, e.g. `iTerm.app` or `WezTerm`.
+    pub program: Option<String>,
+    pub colors: ColorDepth,
+    /// The variable `colors` was read from, or `default`.
+    pub colors_from: &'static str,
+    /// The locale's character set is UTF-8.
+    pub utf8: bool,
+    /// A wide glyph took two cells; `None` when it was not measured.
+    pub wide_glyphs: Option<bool>,
+    pub kitty_graphics: bool,
+    pub sixel: bool,
+    /// Columns and rows.
+    pub cells: (u16, u16),
+    /// Window size in pixels, width then height.
+    pub window_pixels: Option<(u16, u16)>,
+    /// One cell in pixels, width then height.
+    pub cell_pixels: Option<(u16, u16)>,
+    /// How long the terminal took to answer; `None` when it was not asked or
+    /// did not answer.
+    pub answered: Option<Duration>,
+}
+
+impl Capabilities {
+    /// What the environment says, without writing to the terminal.
+    pub fn from_env() -> Self {
+        let mut caps = Self::from_vars(|name| std::env::var(name).ok());
+        caps.cells = crossterm::terminal::size().unwrap_or((0, 0));
+        if let Ok(size) = crossterm::terminal::window_size() {
+            if size.width > 0 && size.height > 0 {
+                caps.window_pixels = Some((size.width, size.height));
+            }
+        }
+        caps.derive_cell_pixels();
+        caps
+    }
+
+    /// [`from_env`](Self::from_env) plus the terminal's answers, waiting up to
+    /// `timeout` for them. Raw mode must be on so the replies are not echoed;
+    /// anything the terminal leaves unanswered keeps its environment value.
+    pub fn probe(timeout: Duration) -> Self {
+        let mut caps = Self::from_env();
+        if let Some((reply, took)) = query(timeout) {
+            caps.apply_reply(&reply);
+            caps.answered = Some(took);
+        }
+        caps
+    }
+
+    /// Reads the environment through `lookup`, so it can be faked.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name: &str| lookup(name).filter(|value| !value.is_empty());
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM");
+        let colorterm = var("COLORTERM").unwrap_or_default().to_lowercase();
+        let (colors, colors_from) = if colorterm == "truecolor" || colorterm == "24bit" {
+            (ColorDepth::TrueColor, "COLORTERM")
+        } else if ["truecolor", "24bit", "direct"].iter().any(|t| term.contains(t)) {
+            (ColorDepth::TrueColor, "TERM")
+        } else if program.as_deref().is_some_and(|p| TRUECOLOR_PROGRAMS.contains(&p)) {
+            (ColorDepth::TrueColor, "TERM_PROGRAM")
+        } else if var("WT_SESSION").is_some() || var("KITTY_WINDOW_ID").is_some() {
+            (ColorDepth::TrueColor, "terminal")
+        } else if term.contains("256color") {
+            (ColorDepth::Ansi256, "TERM")
+        } else if term.is_empty() || term == "dumb" || term == "linux" || term == "vt100" {
+            (ColorDepth::Ansi16, "TERM")
+        } else {
+            (ColorDepth::Ansi256, "default")
+        };
+        let locale = var("LC_ALL").or_else(|| var("LC_CTYPE")).or_else(|| var("LANG"));
+        let utf8 = match locale {
+            Some(locale) => {
+                let locale = locale.to_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            }
+            None => cfg!(windows),
+        };
+        let kitty_graphics = term == "xterm-kitty"
+            || term == "xterm-ghostty"
+            || var("KITTY_WINDOW_ID").is_some()
+            || program.as_deref() == Some("WezTerm");
+        Self {
+            term,
+            program,
+            colors,
+            colors_from,
+            utf8,
+            kitty_graphics,
+            ..Self::default()
+        }
+    }
+
+    /// Cell width / height from the pixel sizes, for
+    /// [`asciivision_core::set_cell_aspect`].
+    pub fn cell_aspect(&self) -> Option<f32> {
+        let (width, height) = self.cell_pixels?;
+        (width > 0 && height > 0).then(|| width as f32 / height as f32)
+    }
+
+    /// Takes what it understands from the terminal's replies to [`QUERY`].
+    pub fn apply_reply(&mut self, reply: &[u8]) {
+        for answer in parse_replies(reply) {
+            match answer {
+                Answer::WindowPixels(width, height) => self.window_pixels = Some((width, height)),
+                Answer::CellPixels(width, height) => self.cell_pixels = Some((width, height)),
+                Answer::Cursor(column) => self.wide_glyphs = Some(column == 3),
+                Answer::Attributes(params) => self.sixel = params.contains(&4),
+                Answer::Kitty(ok) => self.kitty_graphics = ok,
+            }
+        }
+        self.derive_cell_pixels();
+    }
+
+    fn derive_cell_pixels(&mut self) {
+        let (columns, rows) = self.cells;
+        if let (None, Some((width, height))) = (self.cell_pixels, self.window_pixels) {
+            if columns > 0 && rows > 0 {
+                self.cell_pixels = Some((width / columns, height / rows));
+            }
+        }
+    }
+}
+
+/// `TERM_PROGRAM` values of terminals with 24-bit color that do not set
+/// `COLORTERM`.
+const TRUECOLOR_PROGRAMS: &[&str] = &["iTerm.app", "WezTerm", "vscode", "ghostty", "Hyper"];
+
+/// Window pixels, cell pixels, a kitty query image, a wide glyph then the
+/// cursor position and a cleared line, and the device attributes last.
+pub const QUERY: &[u8] = b"\x1b[14t\x1b[16t\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\\
+\r\xe4\xb8\xad\x1b[6n\r\x1b[2K\x1b[c";
+
+#[derive(Debug, PartialEq)]
+enum Answer {
+    WindowPixels(u16, u16),
+    CellPixels(u16, u16),
+    /// 1-based column of the cursor.
+    Cursor(u16),
+    Attributes(Vec<u16>),
+    Kitty(bool),
+}
+
+/// Splits the terminal's replies into the answers [`QUERY`] asks for,
+/// skipping anything else.
+fn parse_replies(reply: &[u8]) -> Vec<Answer> {
+    let mut answers = Vec::new();
+    let mut rest = reply;
+    while let Some(start) = rest.iter().position(|&b| b == 0x1b) {
+        rest = &rest[start..];
+        if rest.starts_with(b"\x1b_G") {
+            let end = find(rest, b"\x1b\\").unwrap_or(rest.len());
+            let body = String::from_utf8_lossy(&rest[3..end]);
+            answers.push(Answer::Kitty(body.contains(";OK")));
+            rest = &rest[(end + 2).min(rest.len())..];
+            continue;
+        }
+        if !rest.starts_with(b"\x1b[") {
+            rest = &rest[1..];
+            continue;
+        }
+        let Some(end) = rest[2..].iter().position(|b| (0x40..=0x7e).contains(b)) else {
+            break;
+        };
+        let params = String::from_utf8_lossy(&rest[2..2 + end]);
+        let private = params.starts_with('?');
+        let numbers: Vec<u16> = params
+            .trim_start_matches('?')
+            .split(';')
+            .filter_map(|n| n.parse().ok())
+            .collect();
+        match (rest[2 + end], numbers.as_slice()) {
+            (b't', [4, height, width]) => answers.push(Answer::WindowPixels(*width, *height)),
+            (b't', [6, height, width]) => answers.push(Answer::CellPixels(*width, *height)),
+            (b'R', [_, column]) => answers.push(Answer::Cursor(*column)),
+            (b'c', _) if private => {
+                answers.push(Answer::Attributes(numbers.iter().skip(1).copied().collect()))
+            }
+            _ => {}
+        }
+        rest = &rest[3 + end..];
+    }
+    answers
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(unix)]
+#[repr(C)]
+struct PollFd {
+    fd: i32,
+    events: i16,
+    revents: i16,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+type Nfds = std::ffi::c_ulong;
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+type Nfds = std::ffi::c_uint;
+
+#[cfg(unix)]
+extern "C" {
+    fn poll(fds: *mut PollFd, nfds: Nfds, timeout: i32) -> i32;
+}
+
+/// Writes [`QUERY`] to the controlling terminal and reads until the device
+/// attributes come back or `timeout` passes.
+#[cfg(unix)]
+fn query(timeout: Duration) -> Option<(Vec<u8>, Duration)> {
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+
+    let mut tty = std::fs::OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+    let started = Instant::now();
+    tty.write_all(QUERY).ok()?;
+    tty.flush().ok()?;
+    let mut reply = Vec::new();
+    let mut buf = [0u8; 256];
+    while let Some(left) = timeout.checked_sub(started.elapsed()) {
+        let mut fd = PollFd {
+            fd: tty.as_raw_fd(),
+            events: 1, // POLLIN
+            revents: 0,
+        };
+        if unsafe { poll(&mut fd, 1, left.as_millis() as i32) } <= 0 {
+            break;
+        }
+        match tty.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => reply.extend_from_slice(&buf[..n]),
+        }
+        let attributes = parse_replies(&reply)
+            .iter()
+            .any(|answer| matches!(answer, Answer::Attributes(_)));
+        if attributes {
+            return Some((reply, started.elapsed()));
+        }
+    }
+    (!reply.is_empty()).then(|| (reply, started.elapsed()))
+}
+
+#[cfg(not(unix))]
+fn query(_timeout: Duration) -> Option<(Vec<u8>, Duration)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars<'a>(pairs: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| pairs.iter().find(|(key, _)| *key == name).map(|(_, v)| v.to_string())
+    }
+
+    #[test]
+    fn colors_come_from_the_environment() {
+        let caps = Capabilities::from_vars(vars(&[("COLORTERM", "truecolor")]));
+        assert_eq!((caps.colors, caps.colors_from), (ColorDepth::TrueColor, "COLORTERM"));
+        let caps = Capabilities::from_vars(vars(&[("TERM", "xterm-256color")]));
+        assert_eq!(caps.colors, ColorDepth::Ansi256);
+        let caps = Capabilities::from_vars(vars(&[
+            ("TERM", "xterm-256color"),
+            ("TERM_PROGRAM", "iTerm.app"),
+            ("LANG", "en_US.UTF-8"),
+        ]));
+        assert_eq!(caps.colors, ColorDepth::TrueColor);
+        assert!(caps.utf8);
+        let caps = Capabilities::from_vars(vars(&[("TERM", "linux"), ("LC_ALL", "C")]));
+        assert_eq!(caps.colors, ColorDepth::Ansi16);
+        assert!(!caps.utf8);
+    }
+
+    #[test]
+    fn replies_are_parsed() {
+        let mut caps = Capabilities {
+            cells: (80, 24),
+            ..Capabilities::default()
+        };
+        caps.apply_reply(
+            b"\x1b[4;480;800t\x1b[6;20;10t\x1b_Gi=31;OK\x1b\\\x1b[12;3R\x1b[?62;4;22c",
+        );
+        assert_eq!(caps.window_pixels, Some((800, 480)));
+        assert_eq!(caps.cell_pixels, Some((10, 20)));
+        assert_eq!(caps.cell_aspect(), Some(0.5));
+        assert_eq!(caps.wide_glyphs, Some(true));
+        assert!(caps.kitty_graphics && caps.sixel);
+
+        let mut caps = Capabilities {
+            cells: (100, 40),
+            ..Capabilities::default()
+        };
+        caps.apply_reply(b"\x1b[4;800;900t\x1b[5;2R\x1b[?1;2c");
+        assert_eq!(caps.cell_pixels, Some((9, 20)));
+        assert_eq!(caps.wide_glyphs, Some(false));
+        assert!(!caps.kitty_graphics && !caps.sixel);
+    }
+}
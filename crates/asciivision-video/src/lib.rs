@@ -0,0 +1,376 @@
+//! The one ASCII video player used by every asciivision frontend.
+//!
+//! A [`VideoPlayer`] decodes a file on a background thread, converts frames with
+//! [`asciivision_core`], and hands the newest frame to the UI on [`VideoPlayer::tick`].
+//! Behavior that used to drift between copies is now expressed through
+//! [`VideoOptions`]: how the decode size is chosen ([`SizePolicy`]), how frames are
+//! placed in the render area ([`RenderFit`]), and whether playback loops.
+//! Post-processing uses `tachyonfx` effects added with [`VideoPlayer::add_effect`],
+//! unless [`VideoOptions::effects`] turns them off.
+
+use anyhow::{Context, Result};
+use asciivision_core::ffmpeg::{build_scaler, frame_from_video};
+use asciivision_core::{cell_aspect, render_backdrop, render_cropped, render_fitted};
+use crossbeam_channel::{bounded, Receiver};
+use ff::format::context::Input;
+use ff::format::Pixel;
+use ff::util::frame::video::Video;
+use ffmpeg_next as ff;
+use ratatui::{prelude::*, widgets::Paragraph};
+use std::{
+    cell::{Cell, RefCell},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+use tachyonfx::{Effect, EffectManager};
+
+pub use asciivision_core::AsciiFrame;
+
+/// How the decoder picks the size of the ASCII frames it produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizePolicy {
+    /// Always decode to exactly this many columns and rows.
+    Fixed(u16, u16),
+    /// Keep the source aspect ratio (corrected for the terminal's cell shape,
+    /// see [`asciivision_core::cell_aspect`]) within the given bounds.
+    FitSource { max_width: u16, max_height: u16 },
+}
+
+impl SizePolicy {
+    fn resolve(self, src_width: u32, src_height: u32) -> (u16, u16) {
+        match self {
+            SizePolicy::Fixed(width, height) => (width.max(1), height.max(1)),
+            SizePolicy::FitSource {
+                max_width,
+                max_height,
+            } => {
+                if src_width == 0 || src_height == 0 {
+                    return (max_width.max(1), max_height.max(1));
+                }
+                let aspect = src_width as f32 / src_height as f32;
+                let cell = cell_aspect();
+                let height = (max_width as f32 / aspect * cell).round() as u16;
+                if height <= max_height {
+                    (max_width.max(1), height.max(1))
+                } else {
+                    let width = (max_height as f32 * aspect / cell).round() as u16;
+                    (width.clamp(1, max_width.max(1)), max_height.max(1))
+                }
+            }
+        }
+    }
+}
+
+/// How a decoded frame is placed inside the area it is rendered into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderFit {
+    /// Draw 1:1, centered, cropping whatever does not fit.
+    #[default]
+    Crop,
+    /// Scale to fit the area, letterboxing to keep the aspect ratio.
+    Letterbox,
+}
+
+#[derive(Debug, Clone)]
+pub struct VideoOptions {
+    pub size: SizePolicy,
+    pub fit: RenderFit,
+    /// Restart from the beginning once the last frame has been shown.
+    pub looping: bool,
+    /// Apply effects queued with [`VideoPlayer::add_effect`]; off for reduced
+    /// motion, when they are dropped as they are added.
+    pub effects: bool,
+}
+
+impl Default for VideoOptions {
+    fn default() -> Self {
+        Self {
+            size: SizePolicy::Fixed(132, 46),
+            fit: RenderFit::Crop,
+            looping: false,
+            effects: true,
+        }
+    }
+}
+
+pub struct VideoPlayer {
+    path: PathBuf,
+    options: VideoOptions,
+    rx: Receiver<AsciiFrame>,
+    latest: Option<AsciiFrame>,
+    decoded_all: Arc<AtomicBool>,
+    /// Tells the decode thread to exit; set by [`VideoPlayer::stop`] and on drop.
+    stopped: Arc<AtomicBool>,
+    effects: RefCell<EffectManager<()>>,
+    last_render: Cell<Instant>,
+    /// Frames picked up by [`VideoPlayer::tick`], and when the first was.
+    shown: u64,
+    first_shown: Option<Instant>,
+}
+
+impl VideoPlayer {
+    pub fn new(path: impl Into<PathBuf>, options: VideoOptions) -> Result<Self> {
+        let path = path.into();
+        let decoded_all = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let rx = spawn_decode(
+            path.as_path(),
+            options.size,
+            decoded_all.clone(),
+            stopped.clone(),
+        )?;
+
+        Ok(Self {
+            path,
+            options,
+            rx,
+            latest: None,
+            decoded_all,
+            stopped,
+            effects: RefCell::new(EffectManager::default()),
+            last_render: Cell::new(Instant::now()),
+            shown: 0,
+            first_shown: None,
+        })
+    }
+
+    /// Pulls the newest decoded frame and restarts decoding when looping.
+    pub fn tick(&mut self) {
+        let mut fresh = false;
+        while let Ok(frame) = self.rx.try_recv() {
+            self.latest = Some(frame);
+            fresh = true;
+        }
+        if fresh {
+            self.shown += 1;
+            self.first_shown.get_or_insert_with(Instant::now);
+        }
+
+        if self.options.looping && self.drained() && !self.is_stopped() {
+            self.decoded_all.store(false, Ordering::Relaxed);
+            if let Ok(rx) = spawn_decode(
+                self.path.as_path(),
+                self.options.size,
+                self.decoded_all.clone(),
+                self.stopped.clone(),
+            ) {
+                self.rx = rx;
+            }
+        }
+    }
+
+    /// Interrupts decoding: the background thread exits at its next packet and
+    /// a looping player stops restarting. The last frame stays renderable.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    pub fn has_signal(&self) -> bool {
+        self.latest.is_some()
+    }
+
+    /// True once a non-looping video has decoded and delivered its last frame.
+    pub fn is_finished(&self) -> bool {
+        !self.options.looping && self.drained()
+    }
+
+    /// Frames shown per second since the first one, counting each tick that
+    /// brought a new frame; `None` until there are two to measure between.
+    pub fn average_fps(&self) -> Option<f32> {
+        let elapsed = self.first_shown?.elapsed().as_secs_f32();
+        (self.shown > 1 && elapsed > 0.0).then(|| (self.shown - 1) as f32 / elapsed)
+    }
+
+    pub fn options(&self) -> &VideoOptions {
+        &self.options
+    }
+
+    /// Queues a `tachyonfx` effect applied over the video area on each render.
+    pub fn add_effect(&mut self, effect: impl Into<Effect>) {
+        if !self.options.effects {
+            return;
+        }
+        self.effects.get_mut().add_effect(effect);
+    }
+
+    /// Drops every queued effect.
+    pub fn clear_effects(&mut self) {
+        *self.effects.get_mut() = EffectManager::default();
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, intensity: f32) {
+        if area.width < 4 || area.height < 4 {
+            return;
+        }
+
+        if let Some(ref ascii) = self.latest {
+            match self.options.fit {
+                RenderFit::Crop => render_cropped(frame.buffer_mut(), area, ascii, intensity),
+                RenderFit::Letterbox => render_fitted(frame.buffer_mut(), area, ascii, intensity),
+            }
+        } else {
+            let placeholder = Paragraph::new("signal lock pending")
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(Color::Rgb(112, 166, 179)));
+            frame.render_widget(placeholder, area);
+        }
+
+        let elapsed = self.last_render.replace(Instant::now()).elapsed();
+        self.effects
+            .borrow_mut()
+            .process_effects(elapsed.into(), frame.buffer_mut(), area);
+    }
+
+    /// Draws the newest frame faintly into the blank cells of `area`, behind
+    /// text already rendered there (see [`asciivision_core::render_backdrop`]).
+    /// Effects are not applied and nothing is drawn before the first frame.
+    pub fn render_backdrop(&self, buffer: &mut Buffer, area: Rect, amount: f32, base: Color) {
+        if let Some(ref ascii) = self.latest {
+            render_backdrop(buffer, area, ascii, amount, base);
+        }
+    }
+
+    fn drained(&self) -> bool {
+        self.decoded_all.load(Ordering::Relaxed) && self.rx.is_empty()
+    }
+}
+
+impl Drop for VideoPlayer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Decodes the first frame of `path` on the calling thread, for a still such
+/// as an image thumbnail.
+pub fn still(path: impl AsRef<Path>, size: SizePolicy) -> Result<AsciiFrame> {
+    let Decoder {
+        mut input,
+        video_index,
+        mut decoder,
+    } = open_decoder(path.as_ref())?;
+    let (width, height) = size.resolve(decoder.width(), decoder.height());
+    let mut scaler = build_scaler(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        width as u32,
+        height as u32,
+    )
+    .context("create scaler")?;
+    let mut rgb = Video::new(Pixel::RGB24, width as u32, height as u32);
+    let mut decoded = Video::empty();
+
+    for (stream, packet) in input.packets() {
+        if stream.index() != video_index {
+            continue;
+        }
+        decoder.send_packet(&packet)?;
+        if decoder.receive_frame(&mut decoded).is_ok() {
+            scaler.run(&decoded, &mut rgb)?;
+            return Ok(frame_from_video(&rgb));
+        }
+    }
+    decoder.send_eof()?;
+    decoder.receive_frame(&mut decoded).context("no frame decoded")?;
+    scaler.run(&decoded, &mut rgb)?;
+    Ok(frame_from_video(&rgb))
+}
+
+struct Decoder {
+    input: Input,
+    video_index: usize,
+    decoder: ff::codec::decoder::Video,
+}
+
+fn open_decoder(path: &Path) -> Result<Decoder> {
+    ff::init().context("init ffmpeg")?;
+    // suppress all FFmpeg log output -- it writes to stderr and corrupts the TUI
+    unsafe { ffmpeg_sys_next::av_log_set_level(ffmpeg_sys_next::AV_LOG_QUIET) };
+    let input =
+        ff::format::input(path).with_context(|| format!("open input {}", path.display()))?;
+    let stream = input
+        .streams()
+        .best(ff::media::Type::Video)
+        .context("no video stream found")?;
+    let video_index = stream.index();
+    let context = ff::codec::context::Context::from_parameters(stream.parameters())?;
+    let decoder = context.decoder().video()?;
+
+    Ok(Decoder {
+        input,
+        video_index,
+        decoder,
+    })
+}
+
+fn spawn_decode(
+    path: &Path,
+    size: SizePolicy,
+    decoded_all: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+) -> Result<Receiver<AsciiFrame>> {
+    let path = path.to_path_buf();
+    let (tx, rx) = bounded(8);
+
+    std::thread::spawn(move || {
+        let result: Result<()> = (|| {
+            let Decoder {
+                mut input,
+                video_index,
+                mut decoder,
+            } = open_decoder(path.as_path())?;
+            let (target_width, target_height) = size.resolve(decoder.width(), decoder.height());
+            let mut scaler = build_scaler(
+                decoder.format(),
+                decoder.width(),
+                decoder.height(),
+                target_width as u32,
+                target_height as u32,
+            )
+            .context("create scaler")?;
+            let mut rgb = Video::new(Pixel::RGB24, target_width as u32, target_height as u32);
+            let mut decoded = Video::empty();
+
+            for (stream, packet) in input.packets() {
+                if stopped.load(Ordering::Relaxed) {
+                    return Ok(());
+                }
+                if stream.index() != video_index {
+                    continue;
+                }
+
+                decoder.send_packet(&packet)?;
+                while decoder.receive_frame(&mut decoded).is_ok() {
+                    scaler.run(&decoded, &mut rgb)?;
+                    if tx.send(frame_from_video(&rgb)).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+
+            decoder.send_eof()?;
+            while decoder.receive_frame(&mut decoded).is_ok() {
+                scaler.run(&decoded, &mut rgb)?;
+                let _ = tx.send(frame_from_video(&rgb));
+            }
+
+            decoded_all.store(true, Ordering::Relaxed);
+            Ok(())
+        })();
+        // logged, never printed to stderr where it would corrupt the display
+        if let Err(error) = result {
+            tracing::warn!(path = %path.display(), "video decode stopped: {:#}", error);
+        }
+    });
+
+    Ok(rx)
+}
@@ -1,142 +1,257 @@
 use anyhow::{anyhow, Context, Result};
 use futures::StreamExt;
 use ratatui::style::Color;
-use reqwest::Client;
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::{Client, Request, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc;
 
+use crate::capabilities::Capabilities;
+use crate::keys;
+use crate::metrics::{self, Metric};
+use crate::net;
+use crate::provider::{Provider, Turn, Vendor, Wire, BUILTIN, CLAUDE};
 use crate::tools::{ToolCall, ToolDefinition, ToolResult};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum AIProvider {
-    Claude,
-    Grok,
-    OpenAI,
-    Gemini,
-    Ollama,
+/// A provider the deck can route to. Cheap to clone; two are equal when they
+/// share a key.
+#[derive(Clone)]
+pub struct AIProvider(Arc<dyn Provider>);
+
+/// Per-provider overrides from config.toml; unset fields keep the built-in defaults.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct ProviderSettings {
+    pub model: Option<String>,
+    pub system_prompt: Option<String>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// Set to `false` to silence `[notifications]` for this provider.
+    pub notify: Option<bool>,
+    /// Context window in tokens for the header gauge; overrides the built-in guess.
+    pub context_window: Option<u32>,
+    /// Set to `false` to send Claude requests without prompt-cache breakpoints.
+    pub prompt_cache: Option<bool>,
+    /// Extra env vars holding keys for this provider. On 401/429 requests move
+    /// on to the next key in the pool.
+    pub api_key_envs: Vec<String>,
 }
 
 impl AIProvider {
-    pub fn from_input(value: &str) -> Self {
-        match value.trim().to_ascii_lowercase().as_str() {
-            "claude" | "anthropic" => Self::Claude,
-            "grok" | "xai" => Self::Grok,
-            "gpt" | "openai" | "gpt5" | "gpt-5" => Self::OpenAI,
-            "gemini" | "google" => Self::Gemini,
-            "ollama" | "local" => Self::Ollama,
-            _ => Self::Claude,
-        }
+    pub fn new(provider: impl Provider + 'static) -> Self {
+        Self(Arc::new(provider))
     }
 
-    pub fn cycle(&self) -> Self {
-        match self {
-            Self::Claude => Self::Grok,
-            Self::Grok => Self::OpenAI,
-            Self::OpenAI => Self::Gemini,
-            Self::Gemini => Self::Ollama,
-            Self::Ollama => Self::Claude,
-        }
+    /// Whether this is the built-in `vendor`.
+    pub fn is(&self, vendor: &Vendor) -> bool {
+        self.db_key() == vendor.key
     }
 
-    pub fn name(&self) -> &'static str {
-        match self {
-            Self::Claude => "Claude Haiku 4.5",
-            Self::Grok => "Grok 4 Fast",
-            Self::OpenAI => "GPT-5 Nano",
-            Self::Gemini => "Gemini 3 Flash",
-            Self::Ollama => "Ollama Local",
-        }
+    pub fn name(&self) -> &str {
+        self.0.name()
     }
 
-    pub fn badge(&self) -> &'static str {
-        match self {
-            Self::Claude => "ANTHROPIC",
-            Self::Grok => "X.AI",
-            Self::OpenAI => "OPENAI",
-            Self::Gemini => "GOOGLE",
-            Self::Ollama => "OLLAMA",
-        }
+    pub fn badge(&self) -> &str {
+        self.0.badge()
     }
 
-    pub fn db_key(&self) -> &'static str {
-        match self {
-            Self::Claude => "claude",
-            Self::Grok => "grok",
-            Self::OpenAI => "gpt",
-            Self::Gemini => "gemini",
-            Self::Ollama => "ollama",
-        }
+    pub fn db_key(&self) -> &str {
+        self.0.key()
     }
 
     pub fn color(&self) -> Color {
-        match self {
-            Self::Claude => Color::Rgb(218, 155, 102),
-            Self::Grok => Color::Rgb(104, 206, 232),
-            Self::OpenAI => Color::Rgb(108, 197, 181),
-            Self::Gemini => Color::Rgb(119, 153, 234),
-            Self::Ollama => Color::Rgb(144, 214, 121),
-        }
+        self.0.color()
     }
 
-    fn api_url(&self) -> &'static str {
-        match self {
-            Self::Claude => "https://api.anthropic.com/v1/messages",
-            Self::Grok => "https://api.x.ai/v1/chat/completions",
-            Self::OpenAI => "https://api.openai.com/v1/chat/completions",
-            Self::Gemini => {
-                "https://generativelanguage.googleapis.com/v1beta/models/gemini-3-flash-preview:generateContent"
-            }
-            Self::Ollama => "http://127.0.0.1:11434/v1/chat/completions",
-        }
+    pub fn capabilities(&self) -> Capabilities {
+        self.0.capabilities()
     }
 
-    #[allow(dead_code)]
-    fn stream_url(&self) -> &'static str {
-        self.api_url()
+    fn api_url(&self) -> String {
+        self.0.endpoint()
     }
 
-    fn model(&self) -> &'static str {
-        match self {
-            Self::Claude => "claude-haiku-4-5",
-            Self::Grok => "grok-4-fast-non-reasoning",
-            Self::OpenAI => "gpt-5-nano",
-            Self::Gemini => "gemini-3-flash-preview",
-            Self::Ollama => "",
-        }
+    /// The model used when neither settings nor a picker choose one.
+    pub fn model(&self) -> &str {
+        self.0.default_model()
     }
 
-    fn api_key_env(&self) -> &'static str {
-        match self {
-            Self::Claude => "CLAUDE_API_KEY",
-            Self::Grok => "GROK_API_KEY",
-            Self::OpenAI => "OPENAI_API_KEY",
-            Self::Gemini => "GEMINI_API_KEY",
-            Self::Ollama => "",
-        }
+    /// Providers whose model is picked at runtime from a catalog.
+    pub fn has_model_picker(&self) -> bool {
+        self.0.has_model_picker()
+    }
+
+    /// False when the provider's API key variable is missing; Ollama and
+    /// keyless custom endpoints always count as configured.
+    pub fn is_configured(&self) -> bool {
+        !self.uses_api_key() || keys::lookup(self.api_key_env(), self.db_key()).is_some()
+    }
+
+    /// Whether requests carry a key (from the environment or the OS keychain).
+    pub fn uses_api_key(&self) -> bool {
+        !self.api_key_env().is_empty()
+    }
+
+    fn api_key_env(&self) -> &str {
+        self.0.api_key_env()
     }
 
     fn api_key(&self) -> Result<String> {
-        match self {
-            Self::Ollama => Ok("ollama".to_string()),
-            _ => env::var(self.api_key_env())
-                .with_context(|| format!("{} not set in environment", self.api_key_env())),
-        }
+        keys::lookup(self.api_key_env(), self.db_key()).with_context(|| {
+            format!(
+                "{} not set in environment or keychain (asciivision keys set {})",
+                self.api_key_env(),
+                self.db_key()
+            )
+        })
     }
 
     fn openai_bearer_token(&self) -> Result<Option<String>> {
-        match self {
-            Self::OpenAI | Self::Grok => Ok(Some(self.api_key()?)),
-            Self::Ollama => Ok(None),
-            _ => Ok(None),
+        if !self.uses_api_key() {
+            return Ok(None);
+        }
+        Ok(Some(self.api_key()?))
+    }
+
+    /// Embedding model used when `[rag]` names none; `None` when the provider
+    /// has no OpenAI-compatible `/embeddings` endpoint.
+    pub fn default_embedding_model(&self) -> Option<&str> {
+        self.0.embeddings().map(|(_, model)| model)
+    }
+
+    /// Embeds `inputs` in one request, returning vectors in input order.
+    pub async fn embed(&self, model: &str, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        let (url, _) = self
+            .0
+            .embeddings()
+            .ok_or_else(|| anyhow!("{} has no embeddings endpoint", self.name()))?;
+        let mut request = net::client()
+            .post(url)
+            .timeout(Duration::from_secs(120))
+            .json(&serde_json::json!({ "model": model, "input": inputs }));
+        if let Some(token) = self.openai_bearer_token()? {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApiError::new(format!("{} embeddings", self.name()), status, body).into());
+        }
+        let mut body: EmbeddingResponse = response.json().await?;
+        body.data.sort_by_key(|item| item.index);
+        if body.data.len() != inputs.len() {
+            return Err(anyhow!(
+                "{} returned {} embeddings for {} inputs",
+                self.name(),
+                body.data.len(),
+                inputs.len()
+            ));
+        }
+        Ok(body.data.into_iter().map(|item| item.embedding).collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingItem>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingItem {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+impl From<Vendor> for AIProvider {
+    fn from(vendor: Vendor) -> Self {
+        Self::new(vendor)
+    }
+}
+
+impl PartialEq for AIProvider {
+    fn eq(&self, other: &Self) -> bool {
+        self.db_key() == other.db_key()
+    }
+}
+
+impl Eq for AIProvider {}
+
+impl std::fmt::Debug for AIProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("AIProvider").field(&self.db_key()).finish()
+    }
+}
+
+/// Every provider the deck can route to: the built-ins followed by config-declared ones.
+#[derive(Debug, Clone)]
+pub struct ProviderRegistry {
+    builtin: Vec<AIProvider>,
+    custom: Vec<AIProvider>,
+}
+
+impl ProviderRegistry {
+    pub fn new(custom: Vec<AIProvider>) -> Self {
+        Self {
+            builtin: BUILTIN.into_iter().map(AIProvider::from).collect(),
+            custom,
         }
     }
+
+    pub fn all(&self) -> impl Iterator<Item = &AIProvider> {
+        self.builtin.iter().chain(self.custom.iter())
+    }
+
+    /// The ones declared in config.
+    pub fn custom(&self) -> impl Iterator<Item = &AIProvider> {
+        self.custom.iter()
+    }
+
+    /// Config rejects custom names that match a built-in's key or alias;
+    /// unknown names fall back to Claude.
+    pub fn resolve(&self, value: &str) -> AIProvider {
+        let wanted = value.trim().to_ascii_lowercase();
+        let builtin = self.builtin.iter().find(|provider| {
+            provider.db_key() == wanted || provider.0.aliases().contains(&wanted.as_str())
+        });
+        self.custom
+            .iter()
+            .find(|provider| provider.name().eq_ignore_ascii_case(&wanted))
+            .or(builtin)
+            .cloned()
+            .unwrap_or_else(|| CLAUDE.into())
+    }
+
+    pub fn next(&self, current: &AIProvider) -> AIProvider {
+        let all: Vec<&AIProvider> = self.all().collect();
+        let index = all.iter().position(|provider| *provider == current).unwrap_or(0);
+        all[(index + 1) % all.len()].clone()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub role: String,
     pub content: String,
+    /// Pictures for a vision model, sent ahead of the text.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<Image>,
+}
+
+/// An image attached to a message, base64 encoded as every provider takes it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Image {
+    pub media_type: String,
+    pub data: String,
+}
+
+impl Image {
+    /// `data:image/png;base64,...`, the form OpenAI-style APIs expect.
+    fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.data)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -149,9 +264,91 @@ pub enum AIResponse {
 pub enum StreamChunk {
     Delta(String),
     ToolCallsReceived(Vec<ToolCall>, String),
+    /// The provider answered 429/5xx; the request is re-sent after `delay`.
+    Retrying {
+        attempt: u32,
+        max_retries: u32,
+        delay: Duration,
+        status: u16,
+    },
+    /// The active key was refused or rate limited; the request is re-sent with
+    /// the next configured key. Both keys are named by their env var.
+    KeyRotated {
+        status: u16,
+        from: String,
+        to: String,
+    },
+    /// Token counts reported by the provider; may arrive more than once per reply.
+    Usage(Usage),
+    /// HTTP status of the response being streamed.
+    Status(u16),
+    /// Why the model stopped, as the provider names it (`end_turn`, `stop`,
+    /// `max_tokens`, `length`, ...).
+    Finished(String),
     Done,
 }
 
+/// A provider answered with a non-success HTTP status.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(label: impl std::fmt::Display, status: StatusCode, body: String) -> Self {
+        Self {
+            status: status.as_u16(),
+            message: format!("{} {}: {}", label, status, body),
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Whether `error` means the provider could not be reached at all (DNS failure,
+/// refused connection, timeout) rather than answering with an error.
+pub fn is_network_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(|error| error.is_connect() || error.is_timeout())
+    })
+}
+
+/// Prompt and completion token counts for one or more API calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Uncached prompt tokens.
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    /// Prompt tokens served from Anthropic's prompt cache.
+    pub cache_read_tokens: u32,
+    /// Prompt tokens written to the prompt cache by this call.
+    pub cache_write_tokens: u32,
+}
+
+impl Usage {
+    pub fn is_empty(&self) -> bool {
+        self.input_tokens == 0 && self.output_tokens == 0
+    }
+}
+
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct OllamaModelInfo {
     pub name: String,
@@ -178,7 +375,7 @@ pub async fn list_ollama_models() -> Result<Vec<OllamaModelInfo>> {
         };
     }
 
-    let client = Client::new();
+    let client = net::client();
     let response = client
         .get("http://127.0.0.1:11434/api/tags")
         .send()
@@ -223,6 +420,32 @@ pub async fn list_ollama_models() -> Result<Vec<OllamaModelInfo>> {
         .collect())
 }
 
+/// What Ollama reports an installed model can do (`completion`, `tools`,
+/// `vision`, ...), from `/api/show`.
+pub async fn ollama_model_capabilities(name: &str) -> Result<Vec<String>> {
+    let response = net::client()
+        .post("http://127.0.0.1:11434/api/show")
+        .json(&serde_json::json!({ "model": name }))
+        .send()
+        .await
+        .context("failed to reach the local Ollama API")?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Ollama model details failed with {}", response.status()));
+    }
+    let payload: OllamaShowResponse = response
+        .json()
+        .await
+        .context("failed to parse Ollama model details")?;
+    Ok(payload.capabilities)
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaShowResponse {
+    /// Missing on Ollama releases before 0.6.
+    #[serde(default)]
+    capabilities: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct OllamaTagsResponse {
     #[serde(default)]
@@ -248,12 +471,109 @@ struct OllamaModelDetails {
     family: Option<String>,
 }
 
+#[derive(Debug, Clone)]
+pub struct OpenRouterModelInfo {
+    pub id: String,
+    pub context_length: Option<u64>,
+    /// USD per million prompt / completion tokens
+    pub prompt_price: Option<f64>,
+    pub completion_price: Option<f64>,
+    /// Takes image input.
+    pub vision: bool,
+    /// Lists `tools` among its supported parameters.
+    pub tools: bool,
+}
+
+pub async fn list_openrouter_models() -> Result<Vec<OpenRouterModelInfo>> {
+    let response = net::client()
+        .get("https://openrouter.ai/api/v1/models")
+        .send()
+        .await
+        .context("failed to reach the OpenRouter model catalog")?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!(
+            "OpenRouter model lookup failed with {}: {}",
+            status,
+            body
+        ));
+    }
+
+    let payload: OpenRouterModelsResponse = response
+        .json()
+        .await
+        .context("failed to parse OpenRouter model list")?;
+
+    let mut models: Vec<OpenRouterModelInfo> = payload
+        .data
+        .into_iter()
+        .map(|model| OpenRouterModelInfo {
+            id: model.id,
+            context_length: model.context_length,
+            prompt_price: per_million(model.pricing.prompt.as_deref()),
+            completion_price: per_million(model.pricing.completion.as_deref()),
+            vision: model.architecture.input_modalities.iter().any(|m| m == "image"),
+            tools: model.supported_parameters.iter().any(|p| p == "tools"),
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(models)
+}
+
+// the catalog quotes prices per token as decimal strings
+fn per_million(price: Option<&str>) -> Option<f64> {
+    price
+        .and_then(|value| value.parse::<f64>().ok())
+        .filter(|value| *value >= 0.0)
+        .map(|value| value * 1_000_000.0)
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterModelsResponse {
+    #[serde(default)]
+    data: Vec<OpenRouterModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterModelEntry {
+    id: String,
+    #[serde(default)]
+    context_length: Option<u64>,
+    #[serde(default)]
+    pricing: OpenRouterPricing,
+    #[serde(default)]
+    architecture: OpenRouterArchitecture,
+    #[serde(default)]
+    supported_parameters: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OpenRouterArchitecture {
+    #[serde(default)]
+    input_modalities: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OpenRouterPricing {
+    #[serde(default)]
+    prompt: Option<String>,
+    #[serde(default)]
+    completion: Option<String>,
+}
+
 // Claude types
 #[derive(Debug, Serialize)]
 struct ClaudeRequest {
     model: String,
     messages: Vec<ClaudeMessage>,
     max_tokens: u32,
+    /// A plain string, or text blocks when the prompt is marked for caching.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<ClaudeTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -266,6 +586,32 @@ struct ClaudeMessage {
     content: serde_json::Value,
 }
 
+impl From<&Message> for ClaudeMessage {
+    /// Plain text, or image blocks followed by the text when there are images.
+    fn from(message: &Message) -> Self {
+        let content = if message.images.is_empty() {
+            serde_json::Value::String(message.content.clone())
+        } else {
+            let images = message.images.iter().map(|image| {
+                serde_json::json!({
+                    "type": "image",
+                    "source": {
+                        "type": "base64",
+                        "media_type": image.media_type,
+                        "data": image.data,
+                    },
+                })
+            });
+            let text = serde_json::json!({ "type": "text", "text": message.content });
+            serde_json::Value::Array(images.chain(std::iter::once(text)).collect())
+        };
+        Self {
+            role: message.role.clone(),
+            content,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct ClaudeTool {
     name: String,
@@ -279,6 +625,31 @@ struct ClaudeResponse {
     #[serde(default)]
     #[allow(dead_code)]
     stop_reason: Option<String>,
+    #[serde(default)]
+    usage: Option<ClaudeUsage>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ClaudeUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+    #[serde(default)]
+    cache_read_input_tokens: u32,
+    #[serde(default)]
+    cache_creation_input_tokens: u32,
+}
+
+impl From<&ClaudeUsage> for Usage {
+    fn from(usage: &ClaudeUsage) -> Self {
+        Self {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_read_tokens: usage.cache_read_input_tokens,
+            cache_write_tokens: usage.cache_creation_input_tokens,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -303,13 +674,20 @@ struct OpenAIRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OpenAITool>>,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct OpenAIMessage {
     role: String,
+    /// A string, or text and `image_url` parts for a vision model.
     #[serde(skip_serializing_if = "Option::is_none")]
-    content: Option<String>,
+    content: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_calls: Option<Vec<OpenAIToolCall>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -347,6 +725,26 @@ struct OpenAIFunctionCall {
 #[derive(Debug, Deserialize)]
 struct OpenAIResponse {
     choices: Vec<OpenAIChoice>,
+    #[serde(default)]
+    usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIUsage {
+    #[serde(default)]
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
+}
+
+impl From<OpenAIUsage> for Usage {
+    fn from(usage: OpenAIUsage) -> Self {
+        Self {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+            ..Self::default()
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -364,10 +762,24 @@ struct OpenAIResponseMessage {
 
 // Gemini types
 #[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiRequest {
     contents: Vec<GeminiContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<GeminiToolDeclaration>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<GeminiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generation_config: Option<GeminiGenerationConfig>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiGenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -385,6 +797,38 @@ struct GeminiPart {
     function_call: Option<GeminiFunctionCall>,
     #[serde(skip_serializing_if = "Option::is_none", rename = "functionResponse")]
     function_response: Option<GeminiFunctionResponse>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "inlineData")]
+    inline_data: Option<GeminiInlineData>,
+}
+
+impl GeminiPart {
+    fn text(text: String) -> Self {
+        Self {
+            text: Some(text),
+            function_call: None,
+            function_response: None,
+            inline_data: None,
+        }
+    }
+
+    fn image(image: &Image) -> Self {
+        Self {
+            text: None,
+            function_call: None,
+            function_response: None,
+            inline_data: Some(GeminiInlineData {
+                mime_type: image.media_type.clone(),
+                data: image.data.clone(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiInlineData {
+    #[serde(rename = "mimeType")]
+    mime_type: String,
+    data: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -414,6 +858,16 @@ struct GeminiFunctionDecl {
 #[derive(Debug, Deserialize)]
 struct GeminiResponse {
     candidates: Vec<GeminiCandidate>,
+    #[serde(default, rename = "usageMetadata")]
+    usage_metadata: Option<GeminiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiUsage {
+    #[serde(default, rename = "promptTokenCount")]
+    prompt_token_count: u32,
+    #[serde(default, rename = "candidatesTokenCount")]
+    candidates_token_count: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -445,6 +899,42 @@ struct ClaudeStreamEvent {
     delta: Option<ClaudeStreamDelta>,
     #[serde(default)]
     content_block: Option<ClaudeStreamContentBlock>,
+    /// `message_start` carries the prompt token count here.
+    #[serde(default)]
+    message: Option<ClaudeStreamMessage>,
+    /// `message_delta` carries the final completion token count here.
+    #[serde(default)]
+    usage: Option<ClaudeUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClaudeStreamMessage {
+    #[serde(default)]
+    usage: Option<ClaudeUsage>,
+}
+
+impl ClaudeStreamEvent {
+    fn usage(&self) -> Option<Usage> {
+        match self.event_type.as_str() {
+            "message_start" => self
+                .message
+                .as_ref()
+                .and_then(|message| message.usage.as_ref())
+                .map(|usage| Usage {
+                    output_tokens: 0,
+                    ..usage.into()
+                }),
+            "message_delta" => self.usage.as_ref().map(|usage| Usage {
+                output_tokens: usage.output_tokens,
+                ..Usage::default()
+            }),
+            _ => None,
+        }
+    }
+
+    fn stop_reason(&self) -> Option<String> {
+        self.delta.as_ref()?.stop_reason.clone()
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -456,8 +946,8 @@ struct ClaudeStreamDelta {
     text: Option<String>,
     #[serde(default)]
     partial_json: Option<String>,
+    /// Set on `message_delta`.
     #[serde(default)]
-    #[allow(dead_code)]
     stop_reason: Option<String>,
 }
 
@@ -473,12 +963,19 @@ struct ClaudeStreamContentBlock {
 
 #[derive(Debug, Deserialize)]
 struct OpenAIStreamResponse {
+    #[serde(default)]
     choices: Vec<OpenAIStreamChoice>,
+    /// Only on the last chunk, when `stream_options.include_usage` is set.
+    #[serde(default)]
+    usage: Option<OpenAIUsage>,
 }
 
 #[derive(Debug, Deserialize)]
 struct OpenAIStreamChoice {
     delta: OpenAIStreamDelta,
+    /// Set on the choice's last chunk.
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -511,17 +1008,50 @@ pub struct AIClient {
     provider: AIProvider,
     client: Client,
     model_override: Option<String>,
+    settings: ProviderSettings,
+    max_retries: u32,
+    capabilities: Capabilities,
 }
 
 impl AIClient {
     pub fn new(provider: AIProvider, model_override: Option<String>) -> Self {
         Self {
+            capabilities: Capabilities::provider_default(&provider),
             provider,
-            client: Client::new(),
+            client: net::client(),
             model_override,
+            settings: ProviderSettings::default(),
+            max_retries: 3,
         }
     }
 
+    /// What the model accepts; requests leave out or fold in what it lacks.
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// How many times a 429/5xx response is retried before the error is returned.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_settings(mut self, settings: ProviderSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Overrides the configured sampling temperature for requests made by this client.
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.settings.temperature = Some(temperature);
+        self
+    }
+
     #[allow(dead_code)]
     pub fn provider(&self) -> &AIProvider {
         &self.provider
@@ -529,7 +1059,7 @@ impl AIClient {
 
     #[allow(dead_code)]
     pub async fn send_message(&self, messages: Vec<Message>) -> Result<String> {
-        match self.send_message_with_tools(messages, None).await? {
+        match self.send_message_with_tools(messages, None).await?.0 {
             AIResponse::Text(text) => Ok(text),
             AIResponse::ToolCalls(_, text) => Ok(text),
         }
@@ -539,35 +1069,31 @@ impl AIClient {
         &self,
         messages: Vec<Message>,
         tools: Option<&[ToolDefinition]>,
-    ) -> Result<AIResponse> {
-        match self.provider {
-            AIProvider::Claude => self.send_claude_with_tools(messages, tools).await,
-            AIProvider::Grok | AIProvider::OpenAI | AIProvider::Ollama => {
-                self.send_openai_with_tools(messages, tools).await
-            }
-            AIProvider::Gemini => self.send_gemini_with_tools(messages, tools).await,
-        }
+    ) -> Result<(AIResponse, Usage)> {
+        let (messages, tools) = self.adapt(messages, tools);
+        let turn = Turn {
+            messages,
+            tools,
+            results: None,
+        };
+        self.note_failure(self.provider.0.send(self, turn).await)
     }
 
-    #[allow(dead_code)]
     pub async fn send_streaming(
         &self,
         messages: Vec<Message>,
         chunk_tx: mpsc::UnboundedSender<StreamChunk>,
     ) -> Result<()> {
-        match self.provider {
-            AIProvider::Claude => self.stream_claude(messages, chunk_tx).await,
-            AIProvider::Grok | AIProvider::OpenAI | AIProvider::Ollama => {
-                self.stream_openai(messages, chunk_tx).await
-            }
-            AIProvider::Gemini => {
-                // Gemini doesn't have great SSE support, fall back to non-streaming
-                let result = self.send_message(messages).await?;
-                let _ = chunk_tx.send(StreamChunk::Delta(result));
-                let _ = chunk_tx.send(StreamChunk::Done);
-                Ok(())
-            }
+        let (messages, _) = self.adapt(messages, None);
+        let turn = Turn {
+            messages,
+            tools: None,
+            results: None,
+        };
+        if !self.capabilities.streaming {
+            return self.note_failure(self.reply_as_stream(turn, chunk_tx).await);
         }
+        self.note_failure(self.provider.0.stream(self, turn, chunk_tx).await)
     }
 
     pub async fn send_with_tool_results(
@@ -576,62 +1102,339 @@ impl AIClient {
         tool_calls: &[ToolCall],
         tool_results: &[ToolResult],
         tools: Option<&[ToolDefinition]>,
-    ) -> Result<AIResponse> {
-        match self.provider {
-            AIProvider::Claude => {
-                self.send_claude_tool_results(messages, tool_calls, tool_results, tools)
+    ) -> Result<(AIResponse, Usage)> {
+        let (messages, tools) = self.adapt(messages, tools);
+        let turn = Turn {
+            messages,
+            tools,
+            results: Some((tool_calls, tool_results)),
+        };
+        self.note_failure(self.provider.0.send(self, turn).await)
+    }
+
+    pub async fn send_streaming_with_tools(
+        &self,
+        messages: Vec<Message>,
+        tools: Option<&[ToolDefinition]>,
+        chunk_tx: mpsc::UnboundedSender<StreamChunk>,
+    ) -> Result<()> {
+        let (messages, tools) = self.adapt(messages, tools);
+        let turn = Turn {
+            messages,
+            tools,
+            results: None,
+        };
+        if !self.capabilities.streaming {
+            return self.note_failure(self.reply_as_stream(turn, chunk_tx).await);
+        }
+        self.note_failure(self.provider.0.stream(self, turn, chunk_tx).await)
+    }
+
+    /// Counts a failed request for the dashboard's Health view.
+    fn note_failure<T>(&self, result: Result<T>) -> Result<T> {
+        if result.is_err() {
+            let provider = self.provider.db_key().to_string();
+            metrics::record(Metric::ProviderError { provider });
+        }
+        result
+    }
+
+    /// Sends `turn` in `wire`'s request format.
+    pub(crate) async fn send_over(
+        &self,
+        wire: Wire,
+        turn: Turn<'_>,
+    ) -> Result<(AIResponse, Usage)> {
+        let Turn {
+            messages,
+            tools,
+            results,
+        } = turn;
+        match (wire, results) {
+            (Wire::Anthropic, None) => self.send_claude_with_tools(messages, tools).await,
+            (Wire::Anthropic, Some((calls, results))) => {
+                self.send_claude_tool_results(messages, calls, results, tools)
                     .await
             }
-            AIProvider::Grok | AIProvider::OpenAI | AIProvider::Ollama => {
-                self.send_openai_tool_results(messages, tool_calls, tool_results, tools)
+            (Wire::OpenAI, None) => self.send_openai_with_tools(messages, tools).await,
+            (Wire::OpenAI, Some((calls, results))) => {
+                self.send_openai_tool_results(messages, calls, results, tools)
                     .await
             }
-            AIProvider::Gemini => {
-                self.send_gemini_tool_results(messages, tool_calls, tool_results, tools)
+            (Wire::Gemini, None) => self.send_gemini_with_tools(messages, tools).await,
+            (Wire::Gemini, Some((calls, results))) => {
+                self.send_gemini_tool_results(messages, calls, results, tools)
                     .await
             }
         }
     }
 
-    pub async fn send_streaming_with_tools(
+    /// Streams `turn` in `wire`'s format.
+    pub(crate) async fn stream_over(
         &self,
-        messages: Vec<Message>,
-        tools: Option<&[ToolDefinition]>,
+        wire: Wire,
+        turn: Turn<'_>,
         chunk_tx: mpsc::UnboundedSender<StreamChunk>,
     ) -> Result<()> {
-        match self.provider {
-            AIProvider::Claude => {
-                self.stream_claude_with_tools(messages, tools, chunk_tx).await
+        match (wire, turn.tools) {
+            (Wire::Anthropic, None) => self.stream_claude(turn.messages, chunk_tx).await,
+            (Wire::Anthropic, tools) => {
+                self.stream_claude_with_tools(turn.messages, tools, chunk_tx).await
             }
-            AIProvider::Grok | AIProvider::OpenAI | AIProvider::Ollama => {
-                self.stream_openai_with_tools(messages, tools, chunk_tx).await
+            (Wire::OpenAI, None) => self.stream_openai(turn.messages, chunk_tx).await,
+            (Wire::OpenAI, tools) => {
+                self.stream_openai_with_tools(turn.messages, tools, chunk_tx).await
             }
-            AIProvider::Gemini => {
-                let result = self.send_gemini_with_tools(messages, tools).await?;
-                match result {
-                    AIResponse::Text(text) => {
-                        let _ = chunk_tx.send(StreamChunk::Delta(text));
-                        let _ = chunk_tx.send(StreamChunk::Done);
-                    }
-                    AIResponse::ToolCalls(calls, text) => {
-                        if !text.is_empty() {
-                            let _ = chunk_tx.send(StreamChunk::Delta(text.clone()));
-                        }
-                        let _ = chunk_tx.send(StreamChunk::ToolCallsReceived(calls, text));
-                    }
+            // Gemini doesn't have great SSE support, fall back to non-streaming
+            (Wire::Gemini, _) => self.reply_as_stream(turn, chunk_tx).await,
+        }
+    }
+
+    /// Sends without streaming and forwards the reply as stream chunks.
+    pub(crate) async fn reply_as_stream(
+        &self,
+        turn: Turn<'_>,
+        chunk_tx: mpsc::UnboundedSender<StreamChunk>,
+    ) -> Result<()> {
+        let (result, usage) = self.provider.0.send(self, turn).await?;
+        if !usage.is_empty() {
+            let _ = chunk_tx.send(StreamChunk::Usage(usage));
+        }
+        match result {
+            AIResponse::Text(text) => {
+                let _ = chunk_tx.send(StreamChunk::Delta(text));
+                let _ = chunk_tx.send(StreamChunk::Done);
+            }
+            AIResponse::ToolCalls(calls, text) => {
+                if !text.is_empty() {
+                    let _ = chunk_tx.send(StreamChunk::Delta(text.clone()));
                 }
-                Ok(())
+                let _ = chunk_tx.send(StreamChunk::ToolCallsReceived(calls, text));
             }
         }
+        Ok(())
     }
 
+    /// Folds the system prompt into the first user turn and drops tool
+    /// definitions for models that take neither.
+    fn adapt<'a>(
+        &self,
+        mut messages: Vec<Message>,
+        tools: Option<&'a [ToolDefinition]>,
+    ) -> (Vec<Message>, Option<&'a [ToolDefinition]>) {
+        if !self.capabilities.system_prompt {
+            let first = messages.iter_mut().find(|message| message.role == "user");
+            if let (Some(prompt), Some(first)) = (&self.settings.system_prompt, first) {
+                first.content = format!("{}\n\n{}", prompt, first.content);
+            }
+        }
+        (messages, tools.filter(|_| self.capabilities.tools))
+    }
+
+    pub(crate) fn settings(&self) -> &ProviderSettings {
+        &self.settings
+    }
+
+    pub(crate) fn system_prompt(&self) -> Option<&String> {
+        let prompt = self.settings.system_prompt.as_ref();
+        prompt.filter(|_| self.capabilities.system_prompt)
+    }
+
+    /// The model requests are sent to, or an empty string when none is selected.
+    pub fn model_label(&self) -> &str {
+        self.model_name().unwrap_or("")
+    }
+
+    /// Sends `request`, retrying 429 and 5xx responses with exponential backoff
+    /// and jitter. The final response is returned as-is for the caller to report.
+    async fn send_with_retry(
+        &self,
+        request: RequestBuilder,
+        notify: Option<&mpsc::UnboundedSender<StreamChunk>>,
+    ) -> Result<Response> {
+        let pool = self.key_pool();
+        let provider = self.provider.db_key();
+        let mut attempt = 0;
+        let mut rotations = 0;
+        loop {
+            let Some(this_try) = request.try_clone() else {
+                return Ok(request.send().await?);
+            };
+            let mut this_try = this_try.build()?;
+            let active = keys::active(provider, pool.len());
+            if pool.len() > 1 {
+                set_key(&mut this_try, &pool[active].1)?;
+            }
+            let response = self.client.execute(this_try).await?;
+            let status = response.status();
+            if let Some((label, _)) = pool.get(active) {
+                keys::record_use(provider, label, status.as_u16());
+            }
+            let refused =
+                status == StatusCode::UNAUTHORIZED || status == StatusCode::TOO_MANY_REQUESTS;
+            if refused && rotations + 1 < pool.len() {
+                rotations += 1;
+                let next = keys::rotate(provider, active, pool.len());
+                if let Some(tx) = notify {
+                    let _ = tx.send(StreamChunk::KeyRotated {
+                        status: status.as_u16(),
+                        from: pool[active].0.clone(),
+                        to: pool[next].0.clone(),
+                    });
+                }
+                continue;
+            }
+            let retryable = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+            if !retryable || attempt >= self.max_retries {
+                if let Some(tx) = notify {
+                    let _ = tx.send(StreamChunk::Status(status.as_u16()));
+                }
+                return Ok(response);
+            }
+
+            attempt += 1;
+            let delay = retry_delay(attempt, &response);
+            if let Some(tx) = notify {
+                let _ = tx.send(StreamChunk::Retrying {
+                    attempt,
+                    max_retries: self.max_retries,
+                    delay,
+                    status: status.as_u16(),
+                });
+            }
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Keys to rotate through, labelled by env var: the provider's own key, then
+    /// the `api_key_envs` that are set.
+    fn key_pool(&self) -> Vec<(String, String)> {
+        if !self.provider.uses_api_key() {
+            return Vec::new();
+        }
+        let env = self.provider.api_key_env();
+        let primary = keys::lookup(env, self.provider.db_key()).map(|key| (env.to_string(), key));
+        let extra = self.settings.api_key_envs.iter().filter_map(|name| {
+            let key = std::env::var(name).ok().filter(|key| !key.trim().is_empty())?;
+            Some((name.clone(), key))
+        });
+        primary.into_iter().chain(extra).collect()
+    }
+
+    /// Picker selection first, then the configured model, then the built-in default.
     fn model_name(&self) -> Result<&str> {
-        match self.provider {
-            AIProvider::Ollama => self
-                .model_override
-                .as_deref()
-                .context("Ollama is active but no model is selected"),
-            _ => Ok(self.provider.model()),
+        let chosen = self
+            .model_override
+            .as_deref()
+            .or(self.settings.model.as_deref());
+        match (chosen, self.provider.model()) {
+            (Some(model), _) => Ok(model),
+            (None, "") => {
+                Err(anyhow!("{} is active but no model is selected", self.provider.name()))
+            }
+            (None, model) => Ok(model),
+        }
+    }
+
+    fn openai_history(&self, messages: &[Message]) -> Vec<OpenAIMessage> {
+        let system = self.system_prompt().into_iter().map(|prompt| OpenAIMessage {
+            role: "system".to_string(),
+            content: Some(prompt.clone().into()),
+            tool_calls: None,
+            tool_call_id: None,
+        });
+        system
+            .chain(messages.iter().map(|m| OpenAIMessage {
+                role: m.role.clone(),
+                content: Some(Self::openai_content(m)),
+                tool_calls: None,
+                tool_call_id: None,
+            }))
+            .collect()
+    }
+
+    fn openai_content(message: &Message) -> serde_json::Value {
+        if message.images.is_empty() {
+            return message.content.clone().into();
+        }
+        let images = message.images.iter().map(|image| {
+            serde_json::json!({ "type": "image_url", "image_url": { "url": image.data_url() } })
+        });
+        let text = serde_json::json!({ "type": "text", "text": message.content });
+        serde_json::Value::Array(images.chain(std::iter::once(text)).collect())
+    }
+
+    /// The configured system prompt plus any `system` turns in `messages`.
+    fn gemini_system_instruction(&self, messages: &[Message]) -> Option<GeminiContent> {
+        let history = messages.iter().filter(|m| m.role == "system");
+        let parts: Vec<GeminiPart> = self
+            .system_prompt()
+            .into_iter()
+            .chain(history.map(|m| &m.content))
+            .map(|text| GeminiPart::text(text.clone()))
+            .collect();
+        (!parts.is_empty()).then_some(GeminiContent { parts, role: None })
+    }
+
+    /// One content per turn, `user` or `model`. Back-to-back messages from the
+    /// same side become parts of a single turn, as Gemini expects turns to alternate.
+    fn gemini_contents(messages: &[Message]) -> Vec<GeminiContent> {
+        let mut contents: Vec<GeminiContent> = Vec::new();
+        for message in messages.iter().filter(|m| m.role != "system") {
+            let role = if message.role == "assistant" { "model" } else { "user" };
+            let mut parts: Vec<GeminiPart> = message.images.iter().map(GeminiPart::image).collect();
+            parts.push(GeminiPart::text(message.content.clone()));
+            match contents.last_mut() {
+                Some(last) if last.role.as_deref() == Some(role) => last.parts.extend(parts),
+                _ => contents.push(GeminiContent {
+                    parts,
+                    role: Some(role.to_string()),
+                }),
+            }
+        }
+        contents
+    }
+
+    fn gemini_generation_config(&self) -> Option<GeminiGenerationConfig> {
+        if self.settings.temperature.is_none() && self.settings.max_tokens.is_none() {
+            return None;
+        }
+        Some(GeminiGenerationConfig {
+            temperature: self.settings.temperature,
+            max_output_tokens: self.settings.max_tokens,
+        })
+    }
+
+    fn prompt_cache(&self) -> bool {
+        self.settings.prompt_cache.unwrap_or(true)
+    }
+
+    /// The system prompt, as a cache breakpoint unless caching is off. The
+    /// breakpoint also covers the tool definitions, which come before it.
+    fn claude_system(&self) -> Option<serde_json::Value> {
+        let prompt = self.system_prompt()?.clone();
+        if !self.prompt_cache() {
+            return Some(prompt.into());
+        }
+        Some(serde_json::json!([{
+            "type": "text",
+            "text": prompt,
+            "cache_control": { "type": "ephemeral" },
+        }]))
+    }
+
+    /// Marks the newest turn and the user turn before it as cache breakpoints:
+    /// this request writes the whole history to the cache, and reads the part
+    /// the previous request wrote. Prompts under the model's minimum cacheable
+    /// length are sent uncached by the API.
+    fn cache_claude_turns(&self, messages: &mut [ClaudeMessage]) {
+        if !self.prompt_cache() || messages.is_empty() {
+            return;
+        }
+        let last = messages.len() - 1;
+        let previous = messages[..last].iter().rposition(|message| message.role == "user");
+        for index in previous.into_iter().chain([last]) {
+            mark_cache_breakpoint(&mut messages[index].content);
         }
     }
 
@@ -640,7 +1443,7 @@ impl AIClient {
         &self,
         messages: Vec<Message>,
         tools: Option<&[ToolDefinition]>,
-    ) -> Result<AIResponse> {
+    ) -> Result<(AIResponse, Usage)> {
         let claude_tools = tools.map(|t| {
             t.iter()
                 .map(|td| ClaudeTool {
@@ -651,35 +1454,36 @@ impl AIClient {
                 .collect::<Vec<_>>()
         });
 
-        let request = ClaudeRequest {
-            model: self.provider.model().to_string(),
+        let mut request = ClaudeRequest {
+            model: self.model_name()?.to_string(),
             messages: messages
                 .iter()
-                .map(|m| ClaudeMessage {
-                    role: m.role.clone(),
-                    content: serde_json::Value::String(m.content.clone()),
-                })
+                .map(ClaudeMessage::from)
                 .collect(),
-            max_tokens: 4096,
+            max_tokens: self.settings.max_tokens.unwrap_or(4096),
+            system: self.claude_system(),
+            temperature: self.settings.temperature,
             tools: claude_tools,
             stream: None,
         };
+        self.cache_claude_turns(&mut request.messages);
 
-        let response = self
+        let builder = self
             .client
             .post(self.provider.api_url())
             .header("x-api-key", self.provider.api_key()?)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&request)
-            .send()
+            .json(&request);
+        let response = self
+            .send_with_retry(builder, None)
             .await
             .context("failed to send Claude request")?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("Claude API error {}: {}", status, body));
+            return Err(ApiError::new("Claude API error", status, body).into());
         }
 
         let payload: ClaudeResponse = response
@@ -696,7 +1500,7 @@ impl AIClient {
         tool_calls: &[ToolCall],
         tool_results: &[ToolResult],
         tools: Option<&[ToolDefinition]>,
-    ) -> Result<AIResponse> {
+    ) -> Result<(AIResponse, Usage)> {
         let claude_tools = tools.map(|t| {
             t.iter()
                 .map(|td| ClaudeTool {
@@ -709,10 +1513,7 @@ impl AIClient {
 
         let mut messages: Vec<ClaudeMessage> = original_messages
             .iter()
-            .map(|m| ClaudeMessage {
-                role: m.role.clone(),
-                content: serde_json::Value::String(m.content.clone()),
-            })
+            .map(ClaudeMessage::from)
             .collect();
 
         // Add the assistant message with tool_use blocks
@@ -749,29 +1550,33 @@ impl AIClient {
             content: serde_json::Value::Array(result_blocks),
         });
 
-        let request = ClaudeRequest {
-            model: self.provider.model().to_string(),
+        let mut request = ClaudeRequest {
+            model: self.model_name()?.to_string(),
             messages,
-            max_tokens: 4096,
+            max_tokens: self.settings.max_tokens.unwrap_or(4096),
+            system: self.claude_system(),
+            temperature: self.settings.temperature,
             tools: claude_tools,
             stream: None,
         };
+        self.cache_claude_turns(&mut request.messages);
 
-        let response = self
+        let builder = self
             .client
             .post(self.provider.api_url())
             .header("x-api-key", self.provider.api_key()?)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&request)
-            .send()
+            .json(&request);
+        let response = self
+            .send_with_retry(builder, None)
             .await
             .context("failed to send Claude tool result")?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("Claude API error {}: {}", status, body));
+            return Err(ApiError::new("Claude API error", status, body).into());
         }
 
         let payload: ClaudeResponse = response
@@ -787,35 +1592,36 @@ impl AIClient {
         messages: Vec<Message>,
         chunk_tx: mpsc::UnboundedSender<StreamChunk>,
     ) -> Result<()> {
-        let request = ClaudeRequest {
-            model: self.provider.model().to_string(),
+        let mut request = ClaudeRequest {
+            model: self.model_name()?.to_string(),
             messages: messages
                 .iter()
-                .map(|m| ClaudeMessage {
-                    role: m.role.clone(),
-                    content: serde_json::Value::String(m.content.clone()),
-                })
+                .map(ClaudeMessage::from)
                 .collect(),
-            max_tokens: 4096,
+            max_tokens: self.settings.max_tokens.unwrap_or(4096),
+            system: self.claude_system(),
+            temperature: self.settings.temperature,
             tools: None,
             stream: Some(true),
         };
+        self.cache_claude_turns(&mut request.messages);
 
-        let response = self
+        let builder = self
             .client
-            .post(self.provider.stream_url())
+            .post(self.provider.api_url())
             .header("x-api-key", self.provider.api_key()?)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&request)
-            .send()
+            .json(&request);
+        let response = self
+            .send_with_retry(builder, Some(&chunk_tx))
             .await
             .context("failed to send Claude stream request")?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("Claude stream error {}: {}", status, body));
+            return Err(ApiError::new("Claude stream error", status, body).into());
         }
 
         let mut bytes = Vec::new();
@@ -835,6 +1641,12 @@ impl AIClient {
                             return Ok(());
                         }
                         if let Ok(event) = serde_json::from_str::<ClaudeStreamEvent>(data) {
+                            if let Some(usage) = event.usage() {
+                                let _ = chunk_tx.send(StreamChunk::Usage(usage));
+                            }
+                            if let Some(reason) = event.stop_reason() {
+                                let _ = chunk_tx.send(StreamChunk::Finished(reason));
+                            }
                             if event.event_type == "content_block_delta" {
                                 if let Some(delta) = &event.delta {
                                     if let Some(ref t) = delta.text {
@@ -871,35 +1683,36 @@ impl AIClient {
                 .collect::<Vec<_>>()
         });
 
-        let request = ClaudeRequest {
-            model: self.provider.model().to_string(),
+        let mut request = ClaudeRequest {
+            model: self.model_name()?.to_string(),
             messages: messages
                 .iter()
-                .map(|m| ClaudeMessage {
-                    role: m.role.clone(),
-                    content: serde_json::Value::String(m.content.clone()),
-                })
+                .map(ClaudeMessage::from)
                 .collect(),
-            max_tokens: 4096,
+            max_tokens: self.settings.max_tokens.unwrap_or(4096),
+            system: self.claude_system(),
+            temperature: self.settings.temperature,
             tools: claude_tools,
             stream: Some(true),
         };
+        self.cache_claude_turns(&mut request.messages);
 
-        let response = self
+        let builder = self
             .client
             .post(self.provider.api_url())
             .header("x-api-key", self.provider.api_key()?)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&request)
-            .send()
+            .json(&request);
+        let response = self
+            .send_with_retry(builder, Some(&chunk_tx))
             .await
             .context("failed to send Claude streaming request")?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("Claude stream error {}: {}", status, body));
+            return Err(ApiError::new("Claude stream error", status, body).into());
         }
 
         let mut bytes = Vec::new();
@@ -937,6 +1750,12 @@ impl AIClient {
                             return Ok(());
                         }
                         if let Ok(event) = serde_json::from_str::<ClaudeStreamEvent>(data) {
+                            if let Some(usage) = event.usage() {
+                                let _ = chunk_tx.send(StreamChunk::Usage(usage));
+                            }
+                            if let Some(reason) = event.stop_reason() {
+                                let _ = chunk_tx.send(StreamChunk::Finished(reason));
+                            }
                             match event.event_type.as_str() {
                                 "content_block_start" => {
                                     if let Some(ref block) = event.content_block {
@@ -1008,7 +1827,7 @@ impl AIClient {
         &self,
         messages: Vec<Message>,
         tools: Option<&[ToolDefinition]>,
-    ) -> Result<AIResponse> {
+    ) -> Result<(AIResponse, Usage)> {
         let openai_tools = tools.map(|t| {
             t.iter()
                 .map(|td| OpenAITool {
@@ -1024,17 +1843,12 @@ impl AIClient {
 
         let request = OpenAIRequest {
             model: self.model_name()?.to_string(),
-            messages: messages
-                .iter()
-                .map(|m| OpenAIMessage {
-                    role: m.role.clone(),
-                    content: Some(m.content.clone()),
-                    tool_calls: None,
-                    tool_call_id: None,
-                })
-                .collect(),
+            messages: self.openai_history(&messages),
             tools: openai_tools,
             stream: false,
+            temperature: self.settings.temperature,
+            max_tokens: self.settings.max_tokens,
+            stream_options: None,
         };
 
         let mut request_builder = self
@@ -1044,21 +1858,16 @@ impl AIClient {
         if let Some(token) = self.provider.openai_bearer_token()? {
             request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
         }
-        let response = request_builder
-            .json(&request)
-            .send()
+        let response = self
+            .send_with_retry(request_builder.json(&request), None)
             .await
             .with_context(|| format!("failed to send {} request", self.provider.name()))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!(
-                "{} API error {}: {}",
-                self.provider.name(),
-                status,
-                body
-            ));
+            let label = format!("{} API error", self.provider.name());
+            return Err(ApiError::new(label, status, body).into());
         }
 
         let payload: OpenAIResponse = response
@@ -1075,7 +1884,7 @@ impl AIClient {
         tool_calls: &[ToolCall],
         tool_results: &[ToolResult],
         tools: Option<&[ToolDefinition]>,
-    ) -> Result<AIResponse> {
+    ) -> Result<(AIResponse, Usage)> {
         let openai_tools = tools.map(|t| {
             t.iter()
                 .map(|td| OpenAITool {
@@ -1089,15 +1898,7 @@ impl AIClient {
                 .collect::<Vec<_>>()
         });
 
-        let mut msgs: Vec<OpenAIMessage> = original_messages
-            .iter()
-            .map(|m| OpenAIMessage {
-                role: m.role.clone(),
-                content: Some(m.content.clone()),
-                tool_calls: None,
-                tool_call_id: None,
-            })
-            .collect();
+        let mut msgs: Vec<OpenAIMessage> = self.openai_history(&original_messages);
 
         // Add assistant message with tool calls
         let oai_tool_calls: Vec<OpenAIToolCall> = tool_calls
@@ -1123,7 +1924,7 @@ impl AIClient {
         for tr in tool_results {
             msgs.push(OpenAIMessage {
                 role: "tool".to_string(),
-                content: Some(tr.content.clone()),
+                content: Some(tr.content.clone().into()),
                 tool_calls: None,
                 tool_call_id: Some(tr.tool_call_id.clone()),
             });
@@ -1134,6 +1935,9 @@ impl AIClient {
             messages: msgs,
             tools: openai_tools,
             stream: false,
+            temperature: self.settings.temperature,
+            max_tokens: self.settings.max_tokens,
+            stream_options: None,
         };
 
         let mut request_builder = self
@@ -1143,12 +1947,15 @@ impl AIClient {
         if let Some(token) = self.provider.openai_bearer_token()? {
             request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
         }
-        let response = request_builder.json(&request).send().await?;
+        let response = self
+            .send_with_retry(request_builder.json(&request), None)
+            .await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("{} API error {}: {}", self.provider.name(), status, body));
+            let label = format!("{} API error", self.provider.name());
+            return Err(ApiError::new(label, status, body).into());
         }
 
         let payload: OpenAIResponse = response.json().await?;
@@ -1162,32 +1969,30 @@ impl AIClient {
     ) -> Result<()> {
         let request = OpenAIRequest {
             model: self.model_name()?.to_string(),
-            messages: messages
-                .iter()
-                .map(|m| OpenAIMessage {
-                    role: m.role.clone(),
-                    content: Some(m.content.clone()),
-                    tool_calls: None,
-                    tool_call_id: None,
-                })
-                .collect(),
+            messages: self.openai_history(&messages),
             tools: None,
             stream: true,
+            temperature: self.settings.temperature,
+            max_tokens: self.settings.max_tokens,
+            stream_options: Some(serde_json::json!({ "include_usage": true })),
         };
 
         let mut request_builder = self
             .client
-            .post(self.provider.stream_url())
+            .post(self.provider.api_url())
             .header("content-type", "application/json");
         if let Some(token) = self.provider.openai_bearer_token()? {
             request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
         }
-        let response = request_builder.json(&request).send().await?;
+        let response = self
+            .send_with_retry(request_builder.json(&request), Some(&chunk_tx))
+            .await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("{} stream error {}: {}", self.provider.name(), status, body));
+            let label = format!("{} stream error", self.provider.name());
+            return Err(ApiError::new(label, status, body).into());
         }
 
         let mut bytes = Vec::new();
@@ -1207,7 +2012,13 @@ impl AIClient {
                             return Ok(());
                         }
                         if let Ok(resp) = serde_json::from_str::<OpenAIStreamResponse>(data) {
+                            if let Some(usage) = resp.usage {
+                                let _ = chunk_tx.send(StreamChunk::Usage(usage.into()));
+                            }
                             if let Some(choice) = resp.choices.first() {
+                                if let Some(reason) = &choice.finish_reason {
+                                    let _ = chunk_tx.send(StreamChunk::Finished(reason.clone()));
+                                }
                                 if let Some(content) = &choice.delta.content {
                                     let _ = chunk_tx.send(StreamChunk::Delta(content.clone()));
                                 }
@@ -1243,17 +2054,12 @@ impl AIClient {
 
         let request = OpenAIRequest {
             model: self.model_name()?.to_string(),
-            messages: messages
-                .iter()
-                .map(|m| OpenAIMessage {
-                    role: m.role.clone(),
-                    content: Some(m.content.clone()),
-                    tool_calls: None,
-                    tool_call_id: None,
-                })
-                .collect(),
+            messages: self.openai_history(&messages),
             tools: openai_tools,
             stream: true,
+            temperature: self.settings.temperature,
+            max_tokens: self.settings.max_tokens,
+            stream_options: Some(serde_json::json!({ "include_usage": true })),
         };
 
         let mut request_builder = self
@@ -1263,12 +2069,15 @@ impl AIClient {
         if let Some(token) = self.provider.openai_bearer_token()? {
             request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
         }
-        let response = request_builder.json(&request).send().await?;
+        let response = self
+            .send_with_retry(request_builder.json(&request), Some(&chunk_tx))
+            .await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("{} stream error {}: {}", self.provider.name(), status, body));
+            let label = format!("{} stream error", self.provider.name());
+            return Err(ApiError::new(label, status, body).into());
         }
 
         let mut bytes = Vec::new();
@@ -1316,7 +2125,13 @@ impl AIClient {
                             return Ok(());
                         }
                         if let Ok(resp) = serde_json::from_str::<OpenAIStreamResponse>(data) {
+                            if let Some(usage) = resp.usage {
+                                let _ = chunk_tx.send(StreamChunk::Usage(usage.into()));
+                            }
                             if let Some(choice) = resp.choices.first() {
+                                if let Some(reason) = &choice.finish_reason {
+                                    let _ = chunk_tx.send(StreamChunk::Finished(reason.clone()));
+                                }
                                 if let Some(content) = &choice.delta.content {
                                     accumulated_text.push_str(content);
                                     let _ = chunk_tx.send(StreamChunk::Delta(content.clone()));
@@ -1373,7 +2188,7 @@ impl AIClient {
         &self,
         messages: Vec<Message>,
         tools: Option<&[ToolDefinition]>,
-    ) -> Result<AIResponse> {
+    ) -> Result<(AIResponse, Usage)> {
         let gemini_tools = tools.map(|t| {
             vec![GeminiToolDeclaration {
                 function_declarations: t
@@ -1388,41 +2203,32 @@ impl AIClient {
         });
 
         let request = GeminiRequest {
-            contents: vec![GeminiContent {
-                parts: vec![GeminiPart {
-                    text: Some(
-                        messages
-                            .iter()
-                            .map(|m| format!("{}: {}", m.role, m.content))
-                            .collect::<Vec<_>>()
-                            .join("\n\n"),
-                    ),
-                    function_call: None,
-                    function_response: None,
-                }],
-                role: Some("user".to_string()),
-            }],
+            contents: Self::gemini_contents(&messages),
             tools: gemini_tools,
+            system_instruction: self.gemini_system_instruction(&messages),
+            generation_config: self.gemini_generation_config(),
         };
 
         let url = format!(
-            "{}?key={}",
+            "{}/{}:generateContent?key={}",
             self.provider.api_url(),
+            self.model_name()?,
             self.provider.api_key()?
         );
-        let response = self
+        let builder = self
             .client
             .post(url)
             .header("content-type", "application/json")
-            .json(&request)
-            .send()
+            .json(&request);
+        let response = self
+            .send_with_retry(builder, None)
             .await
             .context("failed to send Gemini request")?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("Gemini API error {}: {}", status, body));
+            return Err(ApiError::new("Gemini API error", status, body).into());
         }
 
         let payload: GeminiResponse = response
@@ -1439,7 +2245,7 @@ impl AIClient {
         tool_calls: &[ToolCall],
         tool_results: &[ToolResult],
         tools: Option<&[ToolDefinition]>,
-    ) -> Result<AIResponse> {
+    ) -> Result<(AIResponse, Usage)> {
         let gemini_tools = tools.map(|t| {
             vec![GeminiToolDeclaration {
                 function_declarations: t
@@ -1453,20 +2259,7 @@ impl AIClient {
             }]
         });
 
-        let mut contents = vec![GeminiContent {
-            parts: vec![GeminiPart {
-                text: Some(
-                    original_messages
-                        .iter()
-                        .map(|m| format!("{}: {}", m.role, m.content))
-                        .collect::<Vec<_>>()
-                        .join("\n\n"),
-                ),
-                function_call: None,
-                function_response: None,
-            }],
-            role: Some("user".to_string()),
-        }];
+        let mut contents = Self::gemini_contents(&original_messages);
 
         // Add model's function call
         let fc_parts: Vec<GeminiPart> = tool_calls
@@ -1478,6 +2271,7 @@ impl AIClient {
                     args: tc.arguments.clone(),
                 }),
                 function_response: None,
+                inline_data: None,
             })
             .collect();
         contents.push(GeminiContent {
@@ -1495,6 +2289,7 @@ impl AIClient {
                     name: tr.name.clone(),
                     response: serde_json::json!({ "result": tr.content }),
                 }),
+                inline_data: None,
             })
             .collect();
         contents.push(GeminiContent {
@@ -1505,25 +2300,29 @@ impl AIClient {
         let request = GeminiRequest {
             contents,
             tools: gemini_tools,
+            system_instruction: self.gemini_system_instruction(&original_messages),
+            generation_config: self.gemini_generation_config(),
         };
 
         let url = format!(
-            "{}?key={}",
+            "{}/{}:generateContent?key={}",
             self.provider.api_url(),
+            self.model_name()?,
             self.provider.api_key()?
         );
-        let response = self
+        let builder = self
             .client
             .post(url)
             .header("content-type", "application/json")
-            .json(&request)
-            .send()
+            .json(&request);
+        let response = self
+            .send_with_retry(builder, None)
             .await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("Gemini API error {}: {}", status, body));
+            return Err(ApiError::new("Gemini API error", status, body).into());
         }
 
         let payload: GeminiResponse = response.json().await?;
@@ -1531,7 +2330,20 @@ impl AIClient {
     }
 }
 
-fn parse_claude_response(payload: ClaudeResponse) -> Result<AIResponse> {
+/// Adds `cache_control` to the last content block, turning plain text into a
+/// single text block first.
+fn mark_cache_breakpoint(content: &mut serde_json::Value) {
+    if let Some(text) = content.as_str().filter(|text| !text.is_empty()) {
+        *content = serde_json::json!([{ "type": "text", "text": text }]);
+    }
+    let block = content.as_array_mut().and_then(|blocks| blocks.last_mut());
+    if let Some(block) = block.and_then(serde_json::Value::as_object_mut) {
+        block.insert("cache_control".to_string(), serde_json::json!({ "type": "ephemeral" }));
+    }
+}
+
+fn parse_claude_response(payload: ClaudeResponse) -> Result<(AIResponse, Usage)> {
+    let usage = payload.usage.as_ref().map(Usage::from).unwrap_or_default();
     let mut text_parts = Vec::new();
     let mut tool_calls = Vec::new();
 
@@ -1560,15 +2372,16 @@ fn parse_claude_response(payload: ClaudeResponse) -> Result<AIResponse> {
     let text = text_parts.join("");
 
     if !tool_calls.is_empty() {
-        Ok(AIResponse::ToolCalls(tool_calls, text))
+        Ok((AIResponse::ToolCalls(tool_calls, text), usage))
     } else if !text.is_empty() {
-        Ok(AIResponse::Text(text))
+        Ok((AIResponse::Text(text), usage))
     } else {
         Err(anyhow!("Claude returned no content"))
     }
 }
 
-fn parse_openai_response(payload: OpenAIResponse) -> Result<AIResponse> {
+fn parse_openai_response(payload: OpenAIResponse) -> Result<(AIResponse, Usage)> {
+    let usage = payload.usage.map(Usage::from).unwrap_or_default();
     let choice = payload
         .choices
         .first()
@@ -1586,7 +2399,7 @@ fn parse_openai_response(payload: OpenAIResponse) -> Result<AIResponse> {
                 })
                 .collect();
             let text = choice.message.content.clone().unwrap_or_default();
-            return Ok(AIResponse::ToolCalls(calls, text));
+            return Ok((AIResponse::ToolCalls(calls, text), usage));
         }
     }
 
@@ -1594,11 +2407,20 @@ fn parse_openai_response(payload: OpenAIResponse) -> Result<AIResponse> {
         .message
         .content
         .clone()
-        .map(AIResponse::Text)
+        .map(|text| (AIResponse::Text(text), usage))
         .ok_or_else(|| anyhow!("no content in response"))
 }
 
-fn parse_gemini_response(payload: GeminiResponse) -> Result<AIResponse> {
+fn parse_gemini_response(payload: GeminiResponse) -> Result<(AIResponse, Usage)> {
+    let usage = payload
+        .usage_metadata
+        .as_ref()
+        .map(|usage| Usage {
+            input_tokens: usage.prompt_token_count,
+            output_tokens: usage.candidates_token_count,
+            ..Usage::default()
+        })
+        .unwrap_or_default();
     let candidate = payload
         .candidates
         .first()
@@ -1623,10 +2445,48 @@ fn parse_gemini_response(payload: GeminiResponse) -> Result<AIResponse> {
     let text = text_parts.join("");
 
     if !tool_calls.is_empty() {
-        Ok(AIResponse::ToolCalls(tool_calls, text))
+        Ok((AIResponse::ToolCalls(tool_calls, text), usage))
     } else if !text.is_empty() {
-        Ok(AIResponse::Text(text))
+        Ok((AIResponse::Text(text), usage))
     } else {
         Err(anyhow!("Gemini returned no content"))
     }
 }
+
+/// Puts `key` wherever the provider carries it: `x-api-key`, a bearer token, or
+/// Gemini's `key` query parameter.
+fn set_key(request: &mut Request, key: &str) -> Result<()> {
+    let headers = request.headers_mut();
+    if headers.contains_key("x-api-key") {
+        headers.insert("x-api-key", HeaderValue::from_str(key)?);
+    } else if headers.contains_key(AUTHORIZATION) {
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", key))?);
+    } else if request.url().query_pairs().any(|(name, _)| name == "key") {
+        let pairs: Vec<(String, String)> = request
+            .url()
+            .query_pairs()
+            .map(|(name, value)| {
+                let value = if name == "key" { key.to_string() } else { value.into_owned() };
+                (name.into_owned(), value)
+            })
+            .collect();
+        request.url_mut().query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    Ok(())
+}
+
+/// `Retry-After` when the server sends one (capped at a minute), otherwise
+/// 1s, 2s, 4s, ... up to 30s, plus up to 500ms of jitter.
+fn retry_delay(attempt: u32, response: &Response) -> Duration {
+    let jitter = Duration::from_millis(rand::random::<u64>() % 500);
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+    let base = match retry_after {
+        Some(secs) => Duration::from_secs(secs.min(60)),
+        None => Duration::from_secs(1u64 << (attempt - 1).min(5)).min(Duration::from_secs(30)),
+    };
+    base + jitter
+}
@@ -1,81 +1,135 @@
-use crate::db::Database;
+use crate::db::{Database, MessageFilter};
+use crate::notify;
+use crate::pricing::format_cost;
+use rusqlite::ToSql;
+use chrono::{Local, TimeZone};
 use ratatui::{
     prelude::*,
-    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+    widgets::{Paragraph, Wrap},
 };
 
 use crate::theme::t;
 
+/// Condition for the bookmark filter: only messages that are bookmarked.
+const BOOKMARKED: &str = "id IN (SELECT message_id FROM bookmarks)";
+
 pub struct AnalyticsPanel {
     pub active: bool,
+    /// Restricts every stat to bookmarked messages.
+    pub bookmarks_only: bool,
+    /// Role and date range set in the standalone dashboard.
+    pub filter: MessageFilter,
     stats_cache: Option<AnalyticsStats>,
     last_refresh: std::time::Instant,
+    /// A `StatsQuery` handed out by `queue_refresh` that has not come back.
+    loading: bool,
+}
+
+/// What the panel counts: everything `load` needs, so the chat deck can run
+/// it on the archiver's thread.
+#[derive(Clone, PartialEq)]
+pub struct StatsQuery {
+    bookmarks_only: bool,
+    filter: MessageFilter,
 }
 
-struct AnalyticsStats {
+pub struct AnalyticsStats {
     total_messages: usize,
     user_messages: usize,
     assistant_messages: usize,
     shell_commands: usize,
     providers_used: Vec<String>,
+    total_tokens: i64,
+    total_cost: f64,
+    performance: Vec<ProviderPerformance>,
+    leaderboard: Vec<ArenaStanding>,
+    /// Newest bookmarks as `(provider, preview)`, filled under the filter.
+    bookmarks: Vec<(String, String)>,
+}
+
+/// `/arena` record of one provider: rounds won out of rounds answered.
+struct ArenaStanding {
+    provider: String,
+    wins: usize,
+    rounds: usize,
+}
+
+struct ProviderPerformance {
+    provider: String,
+    replies: usize,
+    errors: usize,
+    avg_latency_ms: f64,
 }
 
 impl AnalyticsPanel {
     pub fn new() -> Self {
         Self {
             active: false,
+            bookmarks_only: false,
+            filter: MessageFilter::default(),
             stats_cache: None,
             last_refresh: std::time::Instant::now(),
+            loading: false,
+        }
+    }
+
+    /// Flips the bookmark filter and forces a refresh.
+    pub fn toggle_bookmark_filter(&mut self) {
+        self.bookmarks_only = !self.bookmarks_only;
+        self.stats_cache = None;
+    }
+
+    pub fn set_filter(&mut self, filter: MessageFilter) {
+        self.filter = filter;
+        self.stats_cache = None;
+    }
+
+    fn due(&self) -> bool {
+        self.last_refresh.elapsed().as_secs() >= 5 || self.stats_cache.is_none()
+    }
+
+    fn query(&self) -> StatsQuery {
+        StatsQuery {
+            bookmarks_only: self.bookmarks_only,
+            filter: self.filter.clone(),
         }
     }
 
+    /// Reloads the stats from `db` when they are stale, on this thread.
     pub fn refresh(&mut self, db: Option<&Database>) {
-        if self.last_refresh.elapsed().as_secs() < 5 && self.stats_cache.is_some() {
+        if !self.due() {
             return;
         }
         self.last_refresh = std::time::Instant::now();
+        self.stats_cache = db.map(|db| self.query().load(db));
+    }
 
-        let db = match db {
-            Some(db) => db,
-            None => {
-                self.stats_cache = None;
-                return;
-            }
-        };
-
-        let conn = db.connection();
-        let total = count_query(conn, "SELECT COUNT(*) FROM messages");
-        let user_msgs = count_query(conn, "SELECT COUNT(*) FROM messages WHERE role = 'user' AND kind = 'chat'");
-        let assistant_msgs = count_query(conn, "SELECT COUNT(*) FROM messages WHERE role = 'assistant'");
-        let shell_cmds = count_query(conn, "SELECT COUNT(*) FROM messages WHERE kind = 'shell'");
-
-        let providers: Vec<String> = conn
-            .prepare("SELECT DISTINCT provider FROM messages")
-            .ok()
-            .map(|mut stmt| {
-                stmt.query_map([], |row| row.get::<_, String>(0))
-                    .ok()
-                    .map(|rows| rows.filter_map(|r| r.ok()).collect())
-                    .unwrap_or_default()
-            })
-            .unwrap_or_default();
+    /// The query to run elsewhere when the stats are stale and none is
+    /// already out; its result goes to `loaded`.
+    pub fn queue_refresh(&mut self) -> Option<StatsQuery> {
+        if self.loading || !self.due() {
+            return None;
+        }
+        self.last_refresh = std::time::Instant::now();
+        self.loading = true;
+        Some(self.query())
+    }
 
-        self.stats_cache = Some(AnalyticsStats {
-            total_messages: total,
-            user_messages: user_msgs,
-            assistant_messages: assistant_msgs,
-            shell_commands: shell_cmds,
-            providers_used: providers,
-        });
+    /// Takes the stats `query` produced, unless the filter changed meanwhile.
+    pub fn loaded(&mut self, query: StatsQuery, stats: AnalyticsStats) {
+        self.loading = false;
+        if query == self.query() {
+            self.stats_cache = Some(stats);
+        }
     }
 
     pub fn render(&self, frame: &mut Frame, area: Rect, phase: f32) {
-        let block = Block::default()
-            .title(" ANALYTICS DASHBOARD ")
-            .title_style(Style::default().fg(t().accent2).bold())
-            .borders(Borders::ALL)
-            .border_type(BorderType::Double)
-            .border_style(Style::default().fg(t().accent1));
+        let title = if self.bookmarks_only {
+            " ANALYTICS DASHBOARD // BOOKMARKS "
+        } else {
+            " ANALYTICS DASHBOARD "
+        };
+        let block = t().block(title, t().accent2, t().accent1);
         frame.render_widget(block, area);
 
         let inner = area.inner(Margin {
@@ -98,64 +152,145 @@ impl AnalyticsPanel {
 
             let mut lines = vec![
                 Line::from(vec![
-                    Span::styled("TOTAL MSGS:  ", Style::default().fg(t().accent2).bold()),
+                    Span::styled("TOTAL MSGS:  ", t().label_style()),
                     Span::styled(
                         format!("{}", stats.total_messages),
-                        Style::default().fg(t().text),
+                        t().text_style(),
                     ),
                 ]),
                 Line::from(""),
                 Line::from(vec![
-                    Span::styled("USER:        ", Style::default().fg(t().accent2).bold()),
+                    Span::styled("USER:        ", t().label_style()),
                     Span::styled(
                         format!("{:>5}  ", stats.user_messages),
-                        Style::default().fg(t().text),
+                        t().text_style(),
                     ),
                 ]),
                 Line::from(user_bar),
                 Line::from(vec![
-                    Span::styled("AI:          ", Style::default().fg(t().accent2).bold()),
+                    Span::styled("AI:          ", t().label_style()),
                     Span::styled(
                         format!("{:>5}  ", stats.assistant_messages),
-                        Style::default().fg(t().text),
+                        t().text_style(),
                     ),
                 ]),
                 Line::from(ai_bar),
                 Line::from(vec![
-                    Span::styled("SHELL:       ", Style::default().fg(t().accent2).bold()),
+                    Span::styled("SHELL:       ", t().label_style()),
                     Span::styled(
                         format!("{:>5}  ", stats.shell_commands),
-                        Style::default().fg(t().text),
+                        t().text_style(),
                     ),
                 ]),
                 Line::from(shell_bar),
                 Line::from(""),
                 Line::from(vec![
-                    Span::styled("PROVIDERS:   ", Style::default().fg(t().accent2).bold()),
-                    Span::styled(provider_str, Style::default().fg(t().text)),
+                    Span::styled("PROVIDERS:   ", t().label_style()),
+                    Span::styled(provider_str, t().text_style()),
+                ]),
+                Line::from(vec![
+                    Span::styled("TOKENS:      ", t().label_style()),
+                    Span::styled(
+                        format!("{}", stats.total_tokens),
+                        t().text_style(),
+                    ),
+                ]),
+                Line::from(vec![
+                    Span::styled("SPEND:       ", t().label_style()),
+                    Span::styled(format_cost(stats.total_cost), t().text_style()),
                 ]),
             ];
 
+            if !stats.performance.is_empty() {
+                let slowest = stats
+                    .performance
+                    .iter()
+                    .map(|perf| perf.avg_latency_ms)
+                    .fold(1.0, f64::max);
+                let bar_width = inner.width.saturating_sub(38) as usize;
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    "AVG LATENCY  (replies / errors)",
+                    t().label_style(),
+                )));
+                for perf in &stats.performance {
+                    let filled = ((perf.avg_latency_ms / slowest) * bar_width as f64) as usize;
+                    lines.push(Line::from(vec![
+                        Span::styled(
+                            format!("  {:<10} ", truncate_name(&perf.provider, 10)),
+                            t().text_style(),
+                        ),
+                        Span::styled(
+                            format!("{:>6.2}s ", perf.avg_latency_ms / 1000.0),
+                            Style::default().fg(t().accent3),
+                        ),
+                        Span::styled("\u{2588}".repeat(filled.max(1)), Style::default().fg(t().accent1)),
+                        Span::styled(
+                            format!(" {} / {}", perf.replies, perf.errors),
+                            Style::default().fg(if perf.errors > 0 { t().danger } else { t().muted }),
+                        ),
+                    ]));
+                }
+            }
+
+            if !stats.leaderboard.is_empty() {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    "ARENA LEADERBOARD  (wins / rounds)",
+                    t().label_style(),
+                )));
+                for (rank, standing) in stats.leaderboard.iter().enumerate() {
+                    let rate = standing.wins as f64 * 100.0 / standing.rounds.max(1) as f64;
+                    lines.push(Line::from(vec![
+                        Span::styled(format!("  {}. ", rank + 1), t().muted_style()),
+                        Span::styled(
+                            format!("{:<10} ", truncate_name(&standing.provider, 10)),
+                            t().text_style(),
+                        ),
+                        Span::styled(
+                            format!("{:>3} / {:<3} ", standing.wins, standing.rounds),
+                            Style::default().fg(t().accent3),
+                        ),
+                        Span::styled(format!("{:>3.0}%", rate), Style::default().fg(t().accent1)),
+                    ]));
+                }
+            }
+
+            if !stats.bookmarks.is_empty() {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled("RECENT BOOKMARKS", t().label_style())));
+                for (provider, preview) in &stats.bookmarks {
+                    lines.push(Line::from(vec![
+                        Span::styled("  ★ ", Style::default().fg(t().accent3)),
+                        Span::styled(
+                            format!("{:<10} ", truncate_name(provider, 10)),
+                            t().muted_style(),
+                        ),
+                        Span::styled(preview.clone(), t().text_style()),
+                    ]));
+                }
+            }
+
             let spinner_idx = ((phase * 4.0) as usize) % 4;
             let spinner = ["-", "\\", "|", "/"][spinner_idx];
             lines.push(Line::from(""));
             lines.push(Line::from(vec![
                 Span::styled(
                     format!("[{}] live analytics feed", spinner),
-                    Style::default().fg(t().muted),
+                    t().muted_style(),
                 ),
             ]));
 
             frame.render_widget(
                 Paragraph::new(Text::from(lines))
                     .wrap(Wrap { trim: false })
-                    .style(Style::default().bg(t().panel_bg)),
+                    .style(t().panel_style()),
                 inner,
             );
         } else {
             frame.render_widget(
                 Paragraph::new("analytics offline: no database connection")
-                    .style(Style::default().fg(t().muted).bg(t().panel_bg))
+                    .style(t().muted_style().bg(t().panel_bg))
                     .alignment(Alignment::Center),
                 inner,
             );
@@ -163,8 +298,155 @@ impl AnalyticsPanel {
     }
 }
 
-fn count_query(conn: &rusqlite::Connection, sql: &str) -> usize {
-    conn.query_row(sql, [], |row| row.get::<_, i64>(0))
+impl StatsQuery {
+    pub fn load(&self, db: &Database) -> AnalyticsStats {
+        let conn = db.connection();
+        let mut conditions = Vec::new();
+        if self.bookmarks_only {
+            conditions.push(BOOKMARKED.to_string());
+        }
+        let filter = self.filter.sql();
+        conditions.push(filter.condition.clone());
+        let source = format!("(SELECT * FROM messages WHERE {})", conditions.join(" AND "));
+        let params = filter.params(&[]);
+        let params = params.as_slice();
+        let total = count_query(conn, &format!("SELECT COUNT(*) FROM {}", source), params);
+        let count = |condition: &str| {
+            let sql = format!("SELECT COUNT(*) FROM {} WHERE {}", source, condition);
+            count_query(conn, &sql, params)
+        };
+        let user_msgs = count("role = 'user' AND kind = 'chat'");
+        let assistant_msgs = count("role = 'assistant' AND kind = 'chat' AND replaced = 0");
+        let shell_cmds = count("kind = 'shell'");
+
+        let total_tokens: i64 = conn
+            .query_row(
+                &format!(
+                    "SELECT COALESCE(SUM(COALESCE(input_tokens, 0) + COALESCE(output_tokens, 0)), 0)
+                     FROM {}",
+                    source
+                ),
+                params,
+                |row| row.get(0),
+            )
+            .unwrap_or(0);
+        let total_cost: f64 = conn
+            .query_row(
+                &format!("SELECT COALESCE(SUM(cost_usd), 0.0) FROM {}", source),
+                params,
+                |row| row.get(0),
+            )
+            .unwrap_or(0.0);
+
+        let performance: Vec<ProviderPerformance> = conn
+            .prepare(&format!(
+                "SELECT provider,
+                        SUM(kind = 'chat'),
+                        SUM(kind = 'error'),
+                        AVG(CASE WHEN kind = 'chat' THEN latency_ms END)
+                 FROM {}
+                 WHERE role = 'assistant' AND latency_ms IS NOT NULL
+                 GROUP BY provider
+                 ORDER BY provider",
+                source
+            ))
+            .ok()
+            .map(|mut stmt| {
+                stmt.query_map(params, |row| {
+                    Ok(ProviderPerformance {
+                        provider: row.get(0)?,
+                        replies: row.get::<_, i64>(1)? as usize,
+                        errors: row.get::<_, i64>(2)? as usize,
+                        avg_latency_ms: row.get::<_, Option<f64>>(3)?.unwrap_or(0.0),
+                    })
+                })
+                .ok()
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+                .unwrap_or_default()
+            })
+            .unwrap_or_default();
+
+        let (leaderboard, bookmarks) = if self.bookmarks_only {
+            let bookmarks = db
+                .bookmarks(8)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|bookmark| (bookmark.provider, notify::preview(&bookmark.content, 70)))
+                .collect();
+            (Vec::new(), bookmarks)
+        } else {
+            (arena_leaderboard(conn), Vec::new())
+        };
+
+        let providers: Vec<String> = conn
+            .prepare(&format!("SELECT DISTINCT provider FROM {}", source))
+            .ok()
+            .map(|mut stmt| {
+                stmt.query_map(params, |row| row.get::<_, String>(0))
+                    .ok()
+                    .map(|rows| rows.filter_map(|r| r.ok()).collect())
+                    .unwrap_or_default()
+            })
+            .unwrap_or_default();
+
+        AnalyticsStats {
+            total_messages: total,
+            user_messages: user_msgs,
+            assistant_messages: assistant_msgs,
+            shell_commands: shell_cmds,
+            providers_used: providers,
+            total_tokens,
+            total_cost,
+            performance,
+            leaderboard,
+            bookmarks,
+        }
+    }
+}
+
+fn truncate_name(name: &str, max: usize) -> String {
+    name.chars().take(max).collect()
+}
+
+/// Wins and appearances per provider from `arena_votes`, best win count first.
+fn arena_leaderboard(conn: &rusqlite::Connection) -> Vec<ArenaStanding> {
+    let votes: Vec<(String, String)> = conn
+        .prepare("SELECT winner, candidates FROM arena_votes")
+        .ok()
+        .map(|mut stmt| {
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .ok()
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+                .unwrap_or_default()
+        })
+        .unwrap_or_default();
+
+    let mut standings: Vec<ArenaStanding> = Vec::new();
+    for (winner, candidates) in &votes {
+        for candidate in candidates.split(',').filter(|name| !name.is_empty()) {
+            let index = match standings.iter().position(|s| s.provider == candidate) {
+                Some(index) => index,
+                None => {
+                    standings.push(ArenaStanding {
+                        provider: candidate.to_string(),
+                        wins: 0,
+                        rounds: 0,
+                    });
+                    standings.len() - 1
+                }
+            };
+            standings[index].rounds += 1;
+            if candidate == winner {
+                standings[index].wins += 1;
+            }
+        }
+    }
+    standings.sort_by(|a, b| b.wins.cmp(&a.wins).then(a.rounds.cmp(&b.rounds)));
+    standings
+}
+
+fn count_query(conn: &rusqlite::Connection, sql: &str, params: &[(&str, &dyn ToSql)]) -> usize {
+    conn.query_row(sql, params, |row| row.get::<_, i64>(0))
         .unwrap_or(0) as usize
 }
 
@@ -180,7 +462,7 @@ fn make_bar(value: usize, total: usize, width: usize, color: Color) -> Vec<Span<
         ),
         Span::styled(
             "\u{2591}".repeat(empty),
-            Style::default().fg(Color::Rgb(40, 50, 60)),
+            Style::default().fg(t().panel_alt),
         ),
         Span::styled(
             format!(" {:.0}%", pct * 100.0),
@@ -188,3 +470,381 @@ fn make_bar(value: usize, total: usize, width: usize, color: Color) -> Vec<Span<
         ),
     ]
 }
+
+/// Tokens and spend for one provider, model or day.
+pub struct UsageRow {
+    pub label: String,
+    pub replies: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cost: f64,
+    /// Replies with token counts but no known price.
+    pub unpriced: i64,
+}
+
+impl UsageRow {
+    fn tokens(&self) -> i64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// The dashboard's Usage view: token and cost totals by provider, by model and
+/// per day over the filter's date range.
+#[derive(Default)]
+pub struct UsageReport {
+    pub total: Option<UsageRow>,
+    pub providers: Vec<UsageRow>,
+    pub models: Vec<UsageRow>,
+    /// Newest day first, labelled `YYYY-MM-DD` in local time.
+    pub days: Vec<UsageRow>,
+}
+
+impl UsageReport {
+    /// Sums every reply in `filter`'s date range (its role is ignored: usage is
+    /// only recorded on replies).
+    pub fn load(db: &Database, filter: &MessageFilter) -> Self {
+        let filter = MessageFilter {
+            role: None,
+            ..filter.clone()
+        };
+        let conn = db.connection();
+        let grouped = |column: &str, order: &str| {
+            usage_rows(conn, &filter, column, &format!("GROUP BY 1 ORDER BY {}", order))
+        };
+        Self {
+            total: usage_rows(conn, &filter, "'total'", "").into_iter().next(),
+            providers: grouped("provider", "6 DESC, 2 DESC"),
+            models: grouped("COALESCE(model, '(unknown)')", "6 DESC, 2 DESC"),
+            days: grouped("date(timestamp, 'unixepoch', 'localtime')", "1 DESC"),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, range: &str) {
+        let block = t().block(format!(" USAGE // {} ", range), t().accent2, t().accent1);
+        frame.render_widget(block, area);
+        let inner = area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        });
+        let Some(total) = self.total.as_ref().filter(|total| total.replies > 0) else {
+            frame.render_widget(
+                Paragraph::new("no replies with token usage in this range")
+                    .style(t().muted_style().bg(t().panel_bg))
+                    .alignment(Alignment::Center),
+                inner,
+            );
+            return;
+        };
+
+        let [summary, tables, chart] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Percentage(50),
+            Constraint::Min(3),
+        ])
+        .areas(inner);
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("SPEND:       ", t().label_style()),
+                Span::styled(format_cost(total.cost), Style::default().fg(t().accent4).bold()),
+                Span::styled(format!("  over {} replies", total.replies), t().muted_style()),
+            ]),
+            Line::from(vec![
+                Span::styled("TOKENS:      ", t().label_style()),
+                Span::styled(
+                    format!(
+                        "{} in  {} out  {} cached",
+                        compact(total.input_tokens),
+                        compact(total.output_tokens),
+                        compact(total.cache_read_tokens)
+                    ),
+                    t().text_style(),
+                ),
+            ]),
+        ];
+        if total.unpriced > 0 {
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "{} replies have no known price and count as $0 (add [pricing] entries)",
+                    total.unpriced
+                ),
+                Style::default().fg(t().danger),
+            )));
+        }
+        frame.render_widget(Paragraph::new(lines).style(t().panel_style()), summary);
+
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(tables);
+        render_usage_table(frame, left, "BY PROVIDER", &self.providers);
+        render_usage_table(frame, right, "BY MODEL", &self.models);
+        render_daily_spend(frame, chart, &self.days);
+    }
+}
+
+/// Usage sums labelled by `column`; `tail` groups and orders them.
+fn usage_rows(
+    conn: &rusqlite::Connection,
+    filter: &MessageFilter,
+    column: &str,
+    tail: &str,
+) -> Vec<UsageRow> {
+    let filter = filter.sql();
+    let sql = format!(
+        "SELECT {},
+                COUNT(*),
+                COALESCE(SUM(input_tokens), 0),
+                COALESCE(SUM(output_tokens), 0),
+                COALESCE(SUM(cache_read_tokens), 0),
+                COALESCE(SUM(cost_usd), 0.0),
+                SUM(cost_usd IS NULL)
+         FROM messages
+         WHERE role = 'assistant' AND input_tokens IS NOT NULL AND {}
+         {}",
+        column,
+        filter.condition,
+        tail
+    );
+    conn.prepare(&sql)
+        .ok()
+        .map(|mut stmt| {
+            stmt.query_map(filter.params(&[]).as_slice(), |row| {
+                Ok(UsageRow {
+                    label: row.get(0)?,
+                    replies: row.get(1)?,
+                    input_tokens: row.get(2)?,
+                    output_tokens: row.get(3)?,
+                    cache_read_tokens: row.get(4)?,
+                    cost: row.get(5)?,
+                    unpriced: row.get::<_, Option<i64>>(6)?.unwrap_or(0),
+                })
+            })
+            .ok()
+            .map(|rows| rows.filter_map(|r| r.ok()).collect())
+            .unwrap_or_default()
+        })
+        .unwrap_or_default()
+}
+
+fn render_usage_table(frame: &mut Frame, area: Rect, title: &str, rows: &[UsageRow]) {
+    let name_width = (area.width as usize).saturating_sub(30).clamp(8, 28);
+    let mut lines = vec![Line::from(Span::styled(
+        format!("{:<width$} {:>8} {:>8} {:>10}", title, "in", "out", "spend", width = name_width),
+        t().label_style(),
+    ))];
+    for row in rows.iter().take(area.height.saturating_sub(1) as usize) {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:<width$} ", truncate_name(&row.label, name_width), width = name_width),
+                t().text_style(),
+            ),
+            Span::styled(
+                format!("{:>8} {:>8} ", compact(row.input_tokens), compact(row.output_tokens)),
+                Style::default().fg(t().accent3),
+            ),
+            Span::styled(
+                format!("{:>10}", format_cost(row.cost)),
+                Style::default().fg(t().accent1),
+            ),
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines).style(t().panel_style()), area);
+}
+
+/// One provider's line in the Compare view.
+pub struct ComparisonRow {
+    pub provider: String,
+    pub prompts: i64,
+    pub replies: i64,
+    pub errors: i64,
+    /// Mean reply length in characters.
+    pub avg_chars: f64,
+    pub avg_latency_ms: Option<f64>,
+    pub tokens: i64,
+    pub cost: f64,
+    pub first: i64,
+    pub last: i64,
+}
+
+/// The dashboard's Compare view: every provider in the filter side by side,
+/// busiest first.
+#[derive(Default)]
+pub struct Comparison {
+    pub rows: Vec<ComparisonRow>,
+}
+
+impl Comparison {
+    pub fn load(db: &Database, filter: &MessageFilter) -> Self {
+        let filter = filter.sql();
+        let sql = format!(
+            "SELECT provider,
+                    SUM(role = 'user' AND kind = 'chat'),
+                    SUM(role = 'assistant' AND kind = 'chat' AND replaced = 0),
+                    SUM(kind = 'error'),
+                    AVG(CASE WHEN role = 'assistant' AND kind = 'chat' AND replaced = 0
+                             THEN length(content) END),
+                    AVG(CASE WHEN role = 'assistant' AND kind = 'chat' THEN latency_ms END),
+                    COALESCE(SUM(COALESCE(input_tokens, 0) + COALESCE(output_tokens, 0)), 0),
+                    COALESCE(SUM(cost_usd), 0.0),
+                    MIN(timestamp),
+                    MAX(timestamp)
+             FROM messages
+             WHERE {}
+             GROUP BY provider
+             ORDER BY COUNT(*) DESC",
+            filter.condition
+        );
+        let rows = db
+            .connection()
+            .prepare(&sql)
+            .ok()
+            .map(|mut stmt| {
+                stmt.query_map(filter.params(&[]).as_slice(), |row| {
+                    Ok(ComparisonRow {
+                        provider: row.get(0)?,
+                        prompts: row.get(1)?,
+                        replies: row.get(2)?,
+                        errors: row.get(3)?,
+                        avg_chars: row.get::<_, Option<f64>>(4)?.unwrap_or(0.0),
+                        avg_latency_ms: row.get(5)?,
+                        tokens: row.get(6)?,
+                        cost: row.get(7)?,
+                        first: row.get(8)?,
+                        last: row.get(9)?,
+                    })
+                })
+                .ok()
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+                .unwrap_or_default()
+            })
+            .unwrap_or_default();
+        Self { rows }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, scope: &str) {
+        let title = format!(" COMPARE // {} // {} providers ", scope, self.rows.len());
+        frame.render_widget(t().block(title, t().accent2, t().accent1), area);
+        let inner = area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        });
+        if self.rows.is_empty() {
+            frame.render_widget(
+                Paragraph::new("no messages for this filter")
+                    .style(t().muted_style().bg(t().panel_bg))
+                    .alignment(Alignment::Center),
+                inner,
+            );
+            return;
+        }
+
+        let name_width = (inner.width as usize).saturating_sub(92).clamp(8, 20);
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!(
+                    "{:<width$} {:>7} {:>7} {:>6} {:>9} {:>8} {:>8} {:>10}  {:<11}  {:<11}",
+                    "PROVIDER",
+                    "prompts",
+                    "replies",
+                    "errors",
+                    "avg chars",
+                    "latency",
+                    "tokens",
+                    "spend",
+                    "first",
+                    "last",
+                    width = name_width
+                ),
+                t().label_style(),
+            )),
+            Line::default(),
+        ];
+        let busiest = self.rows.iter().map(|row| row.replies).max().unwrap_or(0);
+        for row in self.rows.iter().take(inner.height.saturating_sub(2) as usize) {
+            let latency = row
+                .avg_latency_ms
+                .map(|ms| format!("{:.1}s", ms / 1000.0))
+                .unwrap_or_else(|| "-".to_string());
+            let name_style = if row.replies == busiest && busiest > 0 {
+                Style::default().fg(t().accent4).bold()
+            } else {
+                t().text_style()
+            };
+            let errors_style = if row.errors > 0 {
+                Style::default().fg(t().danger)
+            } else {
+                t().muted_style()
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!(
+                        "{:<width$} ",
+                        truncate_name(&row.provider, name_width),
+                        width = name_width
+                    ),
+                    name_style,
+                ),
+                Span::styled(
+                    format!("{:>7} {:>7} ", row.prompts, row.replies),
+                    t().text_style(),
+                ),
+                Span::styled(format!("{:>6} ", row.errors), errors_style),
+                Span::styled(
+                    format!("{:>9.0} {:>8} ", row.avg_chars, latency),
+                    Style::default().fg(t().accent3),
+                ),
+                Span::styled(
+                    format!("{:>8} {:>10}  ", compact(row.tokens), format_cost(row.cost)),
+                    Style::default().fg(t().accent1),
+                ),
+                Span::styled(
+                    format!("{:<11}  {:<11}", short_date(row.first), short_date(row.last)),
+                    t().muted_style(),
+                ),
+            ]));
+        }
+        frame.render_widget(Paragraph::new(lines).style(t().panel_style()), inner);
+    }
+}
+
+/// `10-17 13:55` in local time.
+fn short_date(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// One bar per day, newest at the bottom, scaled to the costliest day.
+fn render_daily_spend(frame: &mut Frame, area: Rect, days: &[UsageRow]) {
+    let shown = days.len().min(area.height.saturating_sub(1) as usize);
+    let days: Vec<&UsageRow> = days.iter().take(shown).rev().collect();
+    let priciest = days.iter().map(|day| day.cost).fold(0.0, f64::max);
+    let bar_width = (area.width as usize).saturating_sub(34);
+    let mut lines = vec![Line::from(Span::styled(
+        "PER DAY  (spend, tokens)",
+        t().label_style(),
+    ))];
+    for day in days {
+        let filled = if priciest > 0.0 {
+            ((day.cost / priciest) * bar_width as f64).round() as usize
+        } else {
+            0
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{} ", day.label), t().muted_style()),
+            Span::styled(
+                format!("{:>9} ", format_cost(day.cost)),
+                Style::default().fg(t().accent4),
+            ),
+            Span::styled("\u{2588}".repeat(filled), Style::default().fg(t().accent1)),
+            Span::styled(format!(" {}", compact(day.tokens())), t().muted_style()),
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines).style(t().panel_style()), area);
+}
+
+fn compact(tokens: i64) -> String {
+    crate::format_tokens(tokens.clamp(0, u32::MAX as i64) as u32)
+}
@@ -0,0 +1,61 @@
+//! The chat deck's path to the conversation database: a thread that owns its
+//! own connection and runs queued writes, and the reads that would otherwise
+//! hold up a frame, in order, so a large insert or a lock held by another
+//! process never stalls the render loop.
+
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread::JoinHandle;
+
+use crate::db::Database;
+
+/// Jobs that may wait before `queue` starts blocking the caller.
+const QUEUE: usize = 64;
+
+type Job = Box<dyn FnOnce(&Database) + Send>;
+
+pub struct Archiver {
+    jobs: Option<SyncSender<Job>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Archiver {
+    pub fn spawn(db: Database) -> Self {
+        let (jobs, queue) = mpsc::sync_channel::<Job>(QUEUE);
+        let thread = std::thread::spawn(move || {
+            for job in queue {
+                job(&db);
+            }
+        });
+        Self {
+            jobs: Some(jobs),
+            thread: Some(thread),
+        }
+    }
+
+    /// Queues `job` behind every earlier one. Only a full queue, meaning the
+    /// database has been stuck for a while, makes this wait.
+    pub fn queue(&self, job: impl FnOnce(&Database) + Send + 'static) {
+        let Some(jobs) = &self.jobs else {
+            return;
+        };
+        if let Err(TrySendError::Full(job)) = jobs.try_send(Box::new(job)) {
+            let _ = jobs.send(job);
+        }
+    }
+
+    /// Waits for the queued writes to land, for a clean exit. Dropping the
+    /// archiver does the same, so an exit through an error loses nothing.
+    pub fn finish(self) {
+        drop(self);
+    }
+}
+
+impl Drop for Archiver {
+    fn drop(&mut self) {
+        // closing the channel ends the worker's loop once the queue is empty
+        self.jobs = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
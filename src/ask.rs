@@ -0,0 +1,170 @@
+use anyhow::{anyhow, Result};
+use std::io::{IsTerminal, Read};
+use std::time::Instant;
+
+use crate::ai::{AIClient, AIProvider, AIResponse, ApiError, Message, ProviderRegistry, Usage};
+use crate::attach::Attachment;
+use crate::capabilities::CapabilityTable;
+use crate::config::Config;
+use crate::db::{Database, ReplyMeta};
+use crate::keys;
+use crate::metrics;
+use crate::pricing::PriceTable;
+
+/// Reads all of stdin when it is piped or redirected. Must run before the
+/// terminal is switched to raw mode.
+pub fn read_piped_stdin() -> Option<Attachment> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return None;
+    }
+    let mut text = String::new();
+    stdin.read_to_string(&mut text).ok()?;
+    if text.trim().is_empty() {
+        return None;
+    }
+    Some(Attachment::from_text("stdin", text))
+}
+
+/// The client `ask` and scheduled prompts send with: the provider's
+/// settings, retries and model capabilities from config.
+pub fn client(provider: &AIProvider, model: Option<String>, config: &Config) -> AIClient {
+    let settings = config
+        .provider_settings
+        .get(provider.db_key())
+        .cloned()
+        .unwrap_or_default();
+    let client = AIClient::new(provider.clone(), model)
+        .with_settings(settings)
+        .with_max_retries(config.max_retries());
+    let capabilities = CapabilityTable::new(config.capabilities.clone())
+        .lookup(provider, client.model_label());
+    client.with_capabilities(capabilities)
+}
+
+/// Sends `prompt` with `attachments` as a single turn and archives the
+/// exchange, or the provider error, as a new conversation in `db`. Returns
+/// the result and its latency in milliseconds.
+pub async fn exchange(
+    client: &AIClient,
+    prompt: &str,
+    attachments: &[Attachment],
+    db: Option<&Database>,
+    config: &Config,
+) -> (Result<(AIResponse, Usage)>, u64) {
+    let mut content = prompt.to_string();
+    for attachment in attachments {
+        content.push_str(&attachment.to_prompt_block());
+    }
+    let messages = vec![Message {
+        role: "user".to_string(),
+        content: content.clone(),
+        images: Vec::new(),
+    }];
+    let started = Instant::now();
+    let result = client.send_message_with_tools(messages, None).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let uses = keys::take_uses();
+    let Some(db) = db else {
+        return (result, latency_ms);
+    };
+    if !uses.is_empty() {
+        let _ = db.record_key_uses(&uses);
+    }
+    let metrics = metrics::take();
+    if !metrics.is_empty() {
+        let _ = db.record_metrics(&metrics);
+    }
+    let model = client.model_label().to_string();
+    let mut meta = ReplyMeta {
+        cost: None,
+        model,
+        latency_ms,
+        http_status: None,
+        usage: Default::default(),
+        finish_reason: None,
+    };
+    let (kind, reply) = match &result {
+        Ok((response, usage)) => {
+            meta.usage = *usage;
+            meta.cost = PriceTable::new(config.pricing.clone())
+                .lookup(&meta.model)
+                .map(|price| price.cost(*usage));
+            let (AIResponse::Text(text) | AIResponse::ToolCalls(_, text)) = response;
+            ("chat", text.clone())
+        }
+        Err(error) => {
+            meta.http_status = error.downcast_ref::<ApiError>().map(|error| error.status);
+            ("error", format!("{:#}", error))
+        }
+    };
+    let saved =
+        db.save_exchange(client.provider().db_key(), &content, attachments, kind, &reply, &meta);
+    if let Err(error) = saved {
+        eprintln!("warning: exchange not archived: {:#}", error);
+    }
+    (result, latency_ms)
+}
+
+/// `asciivision ask <prompt>`: sends one prompt, plus any piped stdin, and
+/// prints the reply, or with `json` a single object describing it.
+pub async fn run(
+    prompt: &str,
+    provider: Option<&str>,
+    model: Option<String>,
+    stdin: Option<Attachment>,
+    json: bool,
+    config: &Config,
+) -> Result<()> {
+    let providers = ProviderRegistry::new(config.custom_providers());
+    let provider = providers.resolve(
+        provider
+            .or(config.default_provider.as_deref())
+            .unwrap_or("claude"),
+    );
+    if provider.has_model_picker() && model.is_none() {
+        return Err(anyhow!("{} needs --model <id>", provider.db_key()));
+    }
+    if !provider.is_configured() {
+        keys::prompt(&provider)?;
+    }
+    let client = client(&provider, model, config);
+
+    let db = config.open_database().ok();
+    let (result, latency_ms) = exchange(&client, prompt, stdin.as_slice(), db.as_ref(), config).await;
+
+    if !json {
+        let reply = match result?.0 {
+            AIResponse::Text(text) | AIResponse::ToolCalls(_, text) => text,
+        };
+        println!("{}", reply.trim_end());
+        return Ok(());
+    }
+
+    let mut output = serde_json::json!({
+        "provider": provider.db_key(),
+        "model": client.model_label(),
+        "latency_ms": latency_ms,
+    });
+    match result {
+        Ok((response, usage)) => {
+            let content = match response {
+                AIResponse::Text(text) | AIResponse::ToolCalls(_, text) => text,
+            };
+            output["content"] = content.into();
+            output["usage"] = serde_json::json!({
+                "input_tokens": usage.input_tokens,
+                "output_tokens": usage.output_tokens,
+                "cache_read_tokens": usage.cache_read_tokens,
+                "cache_write_tokens": usage.cache_write_tokens,
+            });
+            println!("{}", output);
+            Ok(())
+        }
+        Err(error) => {
+            output["error"] = format!("{:#}", error).into();
+            println!("{}", output);
+            Err(error)
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use anyhow::{anyhow, bail, Context, Result};
+use asciivision_video::{AsciiFrame, SizePolicy};
+use base64::Engine;
+use std::path::PathBuf;
+
+use crate::ai::Image;
+use crate::config::expand_home;
+
+/// Files larger than this are cut off before being sent to the model.
+pub const MAX_ATTACHMENT_BYTES: usize = 64 * 1024;
+
+/// Larger images are refused; providers reject them anyway.
+pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Bounds of the ASCII preview drawn above the input.
+const THUMBNAIL_SIZE: SizePolicy = SizePolicy::FitSource {
+    max_width: 24,
+    max_height: 8,
+};
+
+/// Whether `path` names an image, going by its extension.
+pub fn is_image(path: &str) -> bool {
+    let extension = std::path::Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    matches!(
+        extension.as_deref(),
+        Some("png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp")
+    )
+}
+
+/// MIME type archived with an attachment, going by its extension.
+pub fn mime_type(name: &str) -> &'static str {
+    let extension = std::path::Path::new(name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("md" | "markdown") => "text/markdown",
+        Some("json") => "application/json",
+        Some("html" | "htm") => "text/html",
+        Some("csv") => "text/csv",
+        Some("xml") => "application/xml",
+        Some("toml") => "application/toml",
+        Some("yaml" | "yml") => "application/yaml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        _ => "text/plain",
+    }
+}
+
+/// The type of image `data` holds, going by its first bytes; only formats
+/// every vision provider accepts.
+pub fn image_type(data: &[u8]) -> Option<&'static str> {
+    match data {
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [0xff, 0xd8, 0xff, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// A file queued with `/attach`, or an image pasted with Ctrl+v, for the
+/// next prompt.
+#[derive(Clone)]
+pub struct Attachment {
+    pub name: String,
+    pub content: String,
+    /// Size on disk, before truncation.
+    pub bytes: usize,
+    pub truncated: bool,
+    /// The whole file, archived with the prompt even when `content` is cut.
+    pub data: Vec<u8>,
+    /// MIME type of an image, which is sent as-is rather than as text.
+    pub image_type: Option<&'static str>,
+    /// ASCII preview of an image, when ffmpeg could decode it.
+    pub thumbnail: Option<AsciiFrame>,
+}
+
+impl Attachment {
+    pub fn load(path: &str) -> Result<Self> {
+        let path = expand_home(path);
+        let raw =
+            std::fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let text = String::from_utf8(raw)
+            .map_err(|_| anyhow!("{} is not a UTF-8 text file", path.display()))?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string());
+        Ok(Self::from_text(name, text))
+    }
+
+    /// Wraps text that did not come from a file, such as piped stdin.
+    pub fn from_text(name: impl Into<String>, text: String) -> Self {
+        let bytes = text.len();
+        let truncated = bytes > MAX_ATTACHMENT_BYTES;
+        let content = if truncated {
+            let mut end = MAX_ATTACHMENT_BYTES;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            text[..end].to_string()
+        } else {
+            text.clone()
+        };
+        Self {
+            name: name.into(),
+            content,
+            bytes,
+            truncated,
+            data: text.into_bytes(),
+            image_type: None,
+            thumbnail: None,
+        }
+    }
+
+    /// Reads an image file for a vision model.
+    pub fn load_image(path: &str) -> Result<Self> {
+        let path = expand_home(path);
+        let data =
+            std::fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string());
+        let mut attachment = Self::image(name, data)?;
+        attachment.thumbnail = asciivision_video::still(&path, THUMBNAIL_SIZE).ok();
+        Ok(attachment)
+    }
+
+    /// Wraps image bytes from the clipboard, decoding the preview through a
+    /// temporary file since ffmpeg reads from paths.
+    pub fn pasted_image(data: Vec<u8>) -> Result<Self> {
+        let extension = image_type(&data).map_or("png", |kind| kind.trim_start_matches("image/"));
+        let name = format!("clipboard-{}.{}", chrono::Local::now().format("%H%M%S"), extension);
+        let mut attachment = Self::image(name, data)?;
+        let path = std::env::temp_dir()
+            .join(format!("asciivision-paste-{}.{}", std::process::id(), extension));
+        if std::fs::write(&path, &attachment.data).is_ok() {
+            attachment.thumbnail = asciivision_video::still(&path, THUMBNAIL_SIZE).ok();
+            let _ = std::fs::remove_file(&path);
+        }
+        Ok(attachment)
+    }
+
+    fn image(name: String, data: Vec<u8>) -> Result<Self> {
+        let Some(image_type) = image_type(&data) else {
+            bail!("{} is not a PNG, JPEG, GIF or WebP image", name);
+        };
+        if data.len() > MAX_IMAGE_BYTES {
+            bail!(
+                "{} is {:.1} MB; images are limited to {} MB",
+                name,
+                data.len() as f32 / (1024.0 * 1024.0),
+                MAX_IMAGE_BYTES / (1024 * 1024)
+            );
+        }
+        Ok(Self {
+            name,
+            content: String::new(),
+            bytes: data.len(),
+            truncated: false,
+            data,
+            image_type: Some(image_type),
+            thumbnail: None,
+        })
+    }
+
+    /// The image as sent to the provider, `None` for text.
+    pub fn to_image(&self) -> Option<Image> {
+        Some(Image {
+            media_type: self.image_type?.to_string(),
+            data: base64::engine::general_purpose::STANDARD.encode(&self.data),
+        })
+    }
+
+    /// The fenced block appended to the outgoing prompt; an image leaves only
+    /// its name, marking where it went in the transcript.
+    pub fn to_prompt_block(&self) -> String {
+        if self.image_type.is_some() {
+            return format!("\n\n[Image: {}]", self.name);
+        }
+        let note = if self.truncated {
+            format!("\n[truncated: first {} of {} bytes]", MAX_ATTACHMENT_BYTES, self.bytes)
+        } else {
+            String::new()
+        };
+        format!(
+            "\n\n[Attachment: {}]\n```\n{}\n```{}",
+            self.name, self.content, note
+        )
+    }
+
+    /// Chip label shown above the input, e.g. `main.rs 4.2K`.
+    pub fn chip(&self) -> String {
+        let size = if self.bytes < 1024 {
+            format!("{}B", self.bytes)
+        } else {
+            format!("{:.1}K", self.bytes as f32 / 1024.0)
+        };
+        if self.truncated {
+            format!("{} {} (truncated)", self.name, size)
+        } else {
+            format!("{} {}", self.name, size)
+        }
+    }
+}
+
+/// Entries matching a partially typed path, as full paths. Directories get a
+/// trailing `/`; hidden entries only show once the name starts with `.`.
+pub fn path_candidates(partial: &str) -> Vec<String> {
+    let (dir_part, prefix) = match partial.rfind('/') {
+        Some(index) => (&partial[..=index], &partial[index + 1..]),
+        None => ("", partial),
+    };
+    let dir = if dir_part.is_empty() {
+        PathBuf::from(".")
+    } else {
+        expand_home(dir_part)
+    };
+
+    let mut matches: Vec<String> = std::fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    let hidden = name.starts_with('.') && !prefix.starts_with('.');
+                    if hidden || !name.starts_with(prefix) {
+                        return None;
+                    }
+                    let is_dir = entry.file_type().map(|kind| kind.is_dir()).unwrap_or(false);
+                    let suffix = if is_dir { "/" } else { "" };
+                    Some(format!("{}{}{}", dir_part, name, suffix))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    matches.sort();
+    matches
+}
+
+/// The longest prefix shared by every name; `names` must not be empty.
+pub fn common_prefix(names: &[String]) -> String {
+    let first = &names[0];
+    let mut end = first.len();
+    for name in &names[1..] {
+        end = end.min(
+            first
+                .char_indices()
+                .zip(name.chars())
+                .take_while(|((_, a), b)| a == b)
+                .map(|((index, a), _)| index + a.len_utf8())
+                .last()
+                .unwrap_or(0),
+        );
+    }
+    first[..end].to_string()
+}
+
@@ -0,0 +1,76 @@
+use anyhow::Result;
+use asciivision_video::{RenderFit, SizePolicy, VideoOptions, VideoPlayer};
+use ratatui::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::theme::t;
+
+/// `[chat_background]`: an ASCII video looped faintly behind the transcript.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ChatBackgroundConfig {
+    /// Video to loop; unset turns the background off.
+    pub video: Option<String>,
+    /// How strongly it shows through under every theme, 0.0 to 1.0; defaults
+    /// to the theme's own.
+    pub strength: Option<f32>,
+    /// Per-theme strengths keyed by theme name, e.g. `dracula = 0.1`.
+    pub themes: HashMap<String, f32>,
+}
+
+impl ChatBackgroundConfig {
+    /// Strength under the current theme.
+    pub fn strength(&self) -> f32 {
+        let theme = t();
+        self.themes
+            .get(theme.name.name())
+            .copied()
+            .or(self.strength)
+            .unwrap_or(theme.backdrop)
+            .clamp(0.0, 1.0)
+    }
+}
+
+pub struct ChatBackground {
+    video: VideoPlayer,
+    config: ChatBackgroundConfig,
+}
+
+impl ChatBackground {
+    /// Starts decoding `path` sized for a `width` x `height` terminal.
+    pub fn start(
+        path: &Path,
+        width: u16,
+        height: u16,
+        config: ChatBackgroundConfig,
+    ) -> Result<Self> {
+        let video = VideoPlayer::new(
+            path,
+            VideoOptions {
+                size: SizePolicy::FitSource {
+                    max_width: width,
+                    max_height: height,
+                },
+                fit: RenderFit::Crop,
+                looping: true,
+                effects: false,
+            },
+        )?;
+        Ok(Self { video, config })
+    }
+
+    pub fn tick(&mut self) {
+        self.video.tick();
+    }
+
+    /// Draws the current frame into the blank cells of `area`, behind the
+    /// messages already rendered there.
+    pub fn render(&self, buffer: &mut Buffer, area: Rect) {
+        let strength = self.config.strength();
+        if strength > 0.0 {
+            self.video.render_backdrop(buffer, area, strength, t().panel_bg);
+        }
+    }
+}
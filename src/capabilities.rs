@@ -0,0 +1,114 @@
+//! What each provider and model accepts, so the chat deck can leave out or
+//! explain what a model lacks instead of sending a request it will reject.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::ai::AIProvider;
+use crate::provider::OLLAMA;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Accepts image input.
+    pub vision: bool,
+    /// Accepts tool definitions (agent tools are left out otherwise).
+    pub tools: bool,
+    /// Streams replies over SSE (otherwise the whole reply lands at once).
+    pub streaming: bool,
+    /// Honours a system prompt (otherwise it is folded into the first prompt).
+    pub system_prompt: bool,
+    /// Context window in tokens.
+    pub context_window: u32,
+}
+
+impl Capabilities {
+    /// What the provider's stock model handles; specific models refine it.
+    pub fn provider_default(provider: &AIProvider) -> Self {
+        provider.capabilities()
+    }
+}
+
+/// A `[capabilities."<model>"]` table; unset fields keep the detected value.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CapabilityOverride {
+    pub vision: Option<bool>,
+    pub tools: Option<bool>,
+    pub streaming: Option<bool>,
+    pub system_prompt: Option<bool>,
+    pub context_window: Option<u32>,
+}
+
+/// Vision, tools and context window matched by model-name prefix; the longest
+/// match wins.
+const BUILTIN: &[(&str, bool, bool, u32)] = &[
+    ("claude-", true, true, 200_000),
+    ("gpt-5", true, true, 400_000),
+    ("gpt-4.1", true, true, 1_047_576),
+    ("gpt-4o", true, true, 128_000),
+    ("grok-4-fast", true, true, 2_000_000),
+    ("grok-4", true, true, 256_000),
+    ("grok-3-mini", false, true, 131_072),
+    ("gemini-", true, true, 1_048_576),
+];
+
+/// Built-in model entries, plus ones reported by the OpenRouter catalog and
+/// Ollama, with `[capabilities]` from config.toml applied last.
+#[derive(Debug, Clone, Default)]
+pub struct CapabilityTable {
+    detected: HashMap<String, Capabilities>,
+    overrides: HashMap<String, CapabilityOverride>,
+}
+
+impl CapabilityTable {
+    pub fn new(overrides: HashMap<String, CapabilityOverride>) -> Self {
+        Self {
+            detected: HashMap::new(),
+            overrides,
+        }
+    }
+
+    /// Records what a catalog or local server reported for `model`.
+    pub fn insert(&mut self, model: String, capabilities: Capabilities) {
+        self.detected.insert(model, capabilities);
+    }
+
+    pub fn lookup(&self, provider: &AIProvider, model: &str) -> Capabilities {
+        let mut capabilities = self
+            .detected
+            .get(model)
+            .copied()
+            .unwrap_or_else(|| builtin(provider, model));
+        if let Some(overrides) = self.overrides.get(model) {
+            let current = capabilities;
+            capabilities = Capabilities {
+                vision: overrides.vision.unwrap_or(current.vision),
+                tools: overrides.tools.unwrap_or(current.tools),
+                streaming: overrides.streaming.unwrap_or(current.streaming),
+                system_prompt: overrides.system_prompt.unwrap_or(current.system_prompt),
+                context_window: overrides.context_window.unwrap_or(current.context_window),
+            };
+        }
+        capabilities
+    }
+}
+
+fn builtin(provider: &AIProvider, model: &str) -> Capabilities {
+    let default = Capabilities::provider_default(provider);
+    // Ollama's window is its num_ctx, not the model's maximum
+    if provider.is(&OLLAMA) {
+        return default;
+    }
+    // OpenRouter ids are `vendor/model`; match on the bare model name.
+    let bare = model.rsplit('/').next().unwrap_or(model);
+    BUILTIN
+        .iter()
+        .filter(|(prefix, ..)| bare.starts_with(prefix))
+        .max_by_key(|(prefix, ..)| prefix.len())
+        .map_or(default, |&(_, vision, tools, context_window)| Capabilities {
+            vision,
+            tools,
+            context_window,
+            ..default
+        })
+}
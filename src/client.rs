@@ -1,5 +1,5 @@
 use crate::message::WsMessage;
-use crate::video::AsciiFrame;
+use asciivision_core::AsciiFrame;
 use crate::webcam::{ascii_frame_to_ws, ws_frame_to_ascii, WebcamCapture, WebcamConfig};
 use anyhow::Result;
 use futures::{SinkExt, StreamExt};
@@ -0,0 +1,59 @@
+use anyhow::{anyhow, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Clipboard helpers tried in order; the first one that runs wins.
+const COPY_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+/// Copies `text` to the system clipboard.
+pub fn copy(text: &str) -> Result<()> {
+    for (program, args) in COPY_COMMANDS {
+        let Ok(mut child) = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+    Err(anyhow!("no clipboard tool found (pbcopy, wl-copy, xclip or xsel)"))
+}
+
+/// Clipboard readers asked for image data, tried in order like `COPY_COMMANDS`.
+const PASTE_IMAGE_COMMANDS: &[(&str, &[&str])] = &[
+    ("pngpaste", &["-"]),
+    ("wl-paste", &["--no-newline", "--type", "image/png"]),
+    ("xclip", &["-selection", "clipboard", "-target", "image/png", "-out"]),
+];
+
+/// The image on the system clipboard, as PNG bytes.
+pub fn paste_image() -> Result<Vec<u8>> {
+    let mut found = false;
+    for (program, args) in PASTE_IMAGE_COMMANDS {
+        let Ok(output) = Command::new(program).args(*args).stdin(Stdio::null()).output() else {
+            continue;
+        };
+        found = true;
+        if output.status.success() && !output.stdout.is_empty() {
+            return Ok(output.stdout);
+        }
+    }
+    if found {
+        Err(anyhow!("the clipboard holds no image"))
+    } else {
+        Err(anyhow!("no clipboard tool found (pngpaste, wl-paste or xclip)"))
+    }
+}
@@ -0,0 +1,464 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Paragraph, Tabs, Wrap},
+};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::ai::{AIClient, AIProvider, Usage};
+use crate::text;
+use crate::theme::t;
+
+/// One side of `/compare`: a provider and the reply streaming into it.
+pub struct ComparePane {
+    pub provider: AIProvider,
+    pub client: AIClient,
+    pub reply: String,
+    pub usage: Usage,
+    pub http_status: Option<u16>,
+    pub finish_reason: Option<String>,
+    /// Time to the first streamed text.
+    pub first_token: Option<Duration>,
+    /// Time until the reply finished or failed.
+    pub latency: Option<Duration>,
+    pub error: Option<String>,
+    /// Transient notice such as a retry countdown.
+    pub note: Option<String>,
+    pub busy: bool,
+    /// Database id of the archived reply, once finished.
+    pub message_id: Option<i64>,
+    /// `ChatMessage::key` the reply gets in the transcript, so an id that
+    /// arrives after the round is recorded still finds it.
+    pub message_key: u64,
+}
+
+impl ComparePane {
+    pub fn new(provider: AIProvider, client: AIClient) -> Self {
+        Self {
+            provider,
+            client,
+            reply: String::new(),
+            usage: Usage::default(),
+            http_status: None,
+            finish_reason: None,
+            first_token: None,
+            latency: None,
+            error: None,
+            note: None,
+            busy: false,
+            message_id: None,
+            message_key: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.reply.clear();
+        self.usage = Usage::default();
+        self.http_status = None;
+        self.finish_reason = None;
+        self.first_token = None;
+        self.latency = None;
+        self.error = None;
+        self.note = None;
+        self.busy = true;
+        self.message_id = None;
+    }
+}
+
+/// How the panes share the body area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaneLayout {
+    /// `/compare`: panes side by side.
+    Columns,
+    /// `/arena`: one pane at a time under a tab bar.
+    Tabs,
+}
+
+/// Multi-provider mode: every prompt goes to all panes at once.
+pub struct CompareState {
+    pub panes: Vec<ComparePane>,
+    pub layout: PaneLayout,
+    /// Pane shown in the tabbed layout.
+    pub active: usize,
+    /// Bumped per prompt so late events from an earlier round are ignored.
+    pub round: u64,
+    pub prompt: String,
+    pub started: Instant,
+    pub cancel: Option<CancellationToken>,
+    /// Set once the finished round has been voted on (arena only).
+    pub voted: bool,
+    /// `diff`: replies split into sentences and lined up, with the ones the
+    /// other reply lacks marked.
+    pub diff: bool,
+}
+
+/// A reply laid out for diff mode.
+struct DiffBody {
+    lines: Vec<Line<'static>>,
+    /// Sentences the other reply has nothing like.
+    differing: usize,
+    total: usize,
+}
+
+impl CompareState {
+    pub fn new(panes: Vec<ComparePane>, layout: PaneLayout) -> Self {
+        Self {
+            panes,
+            layout,
+            active: 0,
+            round: 0,
+            prompt: String::new(),
+            started: Instant::now(),
+            cancel: None,
+            voted: false,
+            diff: false,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self.layout {
+            PaneLayout::Columns => "compare",
+            PaneLayout::Tabs => "arena",
+        }
+    }
+
+    /// Moves the tabbed view by `step` panes, wrapping around.
+    pub fn cycle_tab(&mut self, step: isize) {
+        let count = self.panes.len() as isize;
+        self.active = (self.active as isize + step).rem_euclid(count) as usize;
+    }
+
+    pub fn busy(&self) -> bool {
+        self.panes.iter().any(|pane| pane.busy)
+    }
+
+    /// Starts a new round for `prompt` and returns its id and cancel token.
+    pub fn begin(&mut self, prompt: &str) -> (u64, CancellationToken) {
+        self.round = self.round.wrapping_add(1);
+        self.prompt = prompt.to_string();
+        self.started = Instant::now();
+        self.voted = false;
+        for pane in &mut self.panes {
+            pane.reset();
+        }
+        let cancel = CancellationToken::new();
+        self.cancel = Some(cancel.clone());
+        (self.round, cancel)
+    }
+
+    /// Aborts the round; returns false when nothing was in flight.
+    pub fn cancel(&mut self) -> bool {
+        if !self.busy() {
+            return false;
+        }
+        if let Some(cancel) = self.cancel.take() {
+            cancel.cancel();
+        }
+        self.round = self.round.wrapping_add(1);
+        let elapsed = self.started.elapsed();
+        for pane in self.panes.iter_mut().filter(|pane| pane.busy) {
+            pane.busy = false;
+            pane.latency = Some(elapsed);
+            pane.error = Some("cancelled".to_string());
+        }
+        true
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        match self.layout {
+            PaneLayout::Columns => {
+                let columns = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(vec![Constraint::Fill(1); self.panes.len()])
+                    .split(area);
+                let mut bodies = match self.panes.as_slice() {
+                    [left, right] if self.diff => {
+                        let widths = [columns[0], columns[1]].map(inner_width);
+                        diff_bodies([left, right], widths, true).map(Some)
+                    }
+                    _ => Default::default(),
+                };
+                for (index, (pane, rect)) in self.panes.iter().zip(columns.iter()).enumerate() {
+                    let body = bodies.get_mut(index).and_then(Option::take);
+                    self.render_pane(frame, pane, *rect, body);
+                }
+            }
+            PaneLayout::Tabs => {
+                let rows = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(1), Constraint::Min(3)])
+                    .split(area);
+                let titles = self.panes.iter().enumerate().map(|(index, pane)| {
+                    let timing = match pane.latency {
+                        Some(latency) if pane.error.is_some() => {
+                            format!(" {:.1}s !", latency.as_secs_f32())
+                        }
+                        Some(latency) => format!(" {:.1}s", latency.as_secs_f32()),
+                        None if pane.busy => " ...".to_string(),
+                        None => String::new(),
+                    };
+                    Line::from(Span::styled(
+                        format!("{} {}{}", index + 1, pane.provider.badge(), timing),
+                        Style::default().fg(pane.provider.color()),
+                    ))
+                });
+                let tabs = Tabs::new(titles)
+                    .select(self.active)
+                    .highlight_style(Style::default().reversed().bold())
+                    .divider(Span::styled("|", t().muted_style()))
+                    .style(t().panel_style());
+                frame.render_widget(tabs, rows[0]);
+                if let Some(pane) = self.panes.get(self.active) {
+                    // the first reply is held against the second, the rest
+                    // against the first
+                    let other = if self.active == 0 { 1 } else { 0 };
+                    let body = match self.panes.get(other) {
+                        Some(other) if self.diff => {
+                            let width = inner_width(rows[1]);
+                            let [body, _] = diff_bodies([pane, other], [width, width], false);
+                            Some(body)
+                        }
+                        _ => None,
+                    };
+                    self.render_pane(frame, pane, rows[1], body);
+                }
+            }
+        }
+    }
+
+    /// Draws one pane; `body`, in diff mode, stands in for the reply text.
+    fn render_pane(
+        &self,
+        frame: &mut Frame,
+        pane: &ComparePane,
+        area: Rect,
+        body: Option<DiffBody>,
+    ) {
+        let color = pane.provider.color();
+        let model = pane.client.model_label();
+        let title = if model.is_empty() {
+            format!(" {} ", pane.provider.name())
+        } else {
+            format!(" {} // {} ", pane.provider.name(), model)
+        };
+        let timing = match (pane.latency, pane.busy) {
+            (Some(latency), _) => format!(" {:.2}s ", latency.as_secs_f32()),
+            (None, true) => format!(" {:.1}s ... ", self.started.elapsed().as_secs_f32()),
+            (None, false) => " idle ".to_string(),
+        };
+        let mut block = t()
+            .block(title, color, color)
+            .title_bottom(Line::from(timing).right_aligned());
+        if let Some(body) = body.as_ref().filter(|body| body.total > 0) {
+            block = block.title_bottom(format!(
+                " diff: {}/{} sentences differ ",
+                body.differing, body.total
+            ));
+        }
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut lines = Vec::new();
+        if !self.prompt.is_empty() {
+            lines.push(Line::from(vec![
+                Span::styled("YOU ", Style::default().fg(t().user).bold()),
+                Span::styled(self.prompt.lines().next().unwrap_or(""), t().muted_style()),
+            ]));
+            lines.push(Line::from(""));
+        }
+        match body {
+            Some(body) => lines.extend(body.lines),
+            None => {
+                for line in pane.reply.lines() {
+                    lines.push(Line::from(Span::styled(line, t().text_style())));
+                }
+            }
+        }
+        if let Some(error) = &pane.error {
+            lines.push(Line::from(Span::styled(
+                format!("error: {}", error),
+                Style::default().fg(t().danger),
+            )));
+        } else if pane.busy && pane.reply.is_empty() {
+            let waiting = pane.note.as_deref().unwrap_or(". . .");
+            lines.push(Line::from(Span::styled(waiting, t().muted_style())));
+        }
+        if let Some(first_token) = pane.first_token.filter(|_| !pane.busy) {
+            let tokens = pane.usage.input_tokens + pane.usage.output_tokens;
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "first token {:.2}s // {} tok",
+                    first_token.as_secs_f32(),
+                    tokens
+                ),
+                t().muted_style(),
+            )));
+        }
+
+        // Keep the tail of a long reply in view.
+        let width = inner.width.max(1) as usize;
+        let height: usize = lines
+            .iter()
+            .map(|line| line.width().div_ceil(width).max(1))
+            .sum();
+        let scroll = height.saturating_sub(inner.height as usize);
+        frame.render_widget(
+            Paragraph::new(Text::from(lines))
+                .wrap(Wrap { trim: false })
+                .scroll((scroll as u16, 0))
+                .style(t().panel_style()),
+            inner,
+        );
+    }
+}
+
+/// Columns inside a pane's border.
+fn inner_width(area: Rect) -> usize {
+    area.width.saturating_sub(2).max(1) as usize
+}
+
+/// The sentences of `reply`: each line split after `.`, `!` or `?` followed
+/// by a space. Blank lines are dropped.
+fn sentences(reply: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    for line in reply.lines() {
+        let mut start = 0;
+        let mut chars = line.char_indices().peekable();
+        while let Some((index, c)) = chars.next() {
+            let ends = matches!(c, '.' | '!' | '?')
+                && chars.peek().is_some_and(|(_, next)| next.is_whitespace());
+            if ends {
+                sentences.push(&line[start..=index]);
+                start = index + 1;
+            }
+        }
+        sentences.push(&line[start..]);
+    }
+    sentences.retain(|sentence| !sentence.trim().is_empty());
+    sentences.iter().map(|sentence| sentence.trim()).collect()
+}
+
+/// What two sentences are compared by: lowercase words, punctuation and
+/// markdown left out.
+fn comparable(sentence: &str) -> String {
+    let words = sentence
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase);
+    words.collect::<Vec<_>>().join(" ")
+}
+
+/// Longest-common-subsequence alignment of two sentence lists: rows holding
+/// a sentence from each side when they match, or from one side when not.
+fn align(left: &[String], right: &[String]) -> Vec<(Option<usize>, Option<usize>)> {
+    let (n, m) = (left.len(), right.len());
+    // common[i][j]: the LCS length of left[i..] and right[j..]
+    let mut common = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            common[i][j] = if left[i] == right[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut rows = Vec::with_capacity(n.max(m));
+    while i < n || j < m {
+        if i < n && j < m && left[i] == right[j] {
+            rows.push((Some(i), Some(j)));
+            i += 1;
+            j += 1;
+        } else if j == m || (i < n && common[i + 1][j] >= common[i][j + 1]) {
+            rows.push((Some(i), None));
+            i += 1;
+        } else {
+            rows.push((None, Some(j)));
+            j += 1;
+        }
+    }
+    rows
+}
+
+/// Both replies wrapped to `widths` in aligned sentence rows. Sentences the
+/// other side lacks get a bar in the pane's color; shared ones are dimmed.
+/// With `pad`, the shorter side of each row is filled with blank lines so
+/// matching sentences sit level across columns.
+fn diff_bodies(panes: [&ComparePane; 2], widths: [usize; 2], pad: bool) -> [DiffBody; 2] {
+    let sentences = panes.map(|pane| sentences(&pane.reply));
+    let [left, right] = sentences
+        .each_ref()
+        .map(|side| side.iter().map(|sentence| comparable(sentence)).collect::<Vec<_>>());
+    let mut bodies = sentences.each_ref().map(|side| DiffBody {
+        lines: Vec::new(),
+        differing: 0,
+        total: side.len(),
+    });
+    for (left_index, right_index) in align(&left, &right) {
+        let shared = left_index.is_some() && right_index.is_some();
+        let rows = [left_index, right_index];
+        let wrapped: [Vec<String>; 2] = std::array::from_fn(|side| match rows[side] {
+            Some(index) => text::wrap(sentences[side][index], widths[side].saturating_sub(2)),
+            None => Vec::new(),
+        });
+        let height = if pad { wrapped[0].len().max(wrapped[1].len()) } else { 0 };
+        for (side, wrapped) in wrapped.into_iter().enumerate() {
+            let body = &mut bodies[side];
+            let count = wrapped.len();
+            if count > 0 && !shared {
+                body.differing += 1;
+            }
+            let (gutter, style) = if shared {
+                ("  ", t().muted_style())
+            } else {
+                ("\u{258c} ", t().text_style())
+            };
+            let color = panes[side].provider.color();
+            for row in wrapped {
+                body.lines.push(Line::from(vec![
+                    Span::styled(gutter, Style::default().fg(color)),
+                    Span::styled(row, style),
+                ]));
+            }
+            for _ in count..height {
+                body.lines.push(Line::from(""));
+            }
+        }
+    }
+    bodies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(reply: &str) -> Vec<String> {
+        sentences(reply).iter().map(|sentence| comparable(sentence)).collect()
+    }
+
+    #[test]
+    fn sentences_split_after_stops() {
+        assert_eq!(
+            sentences("Use WAL. It helps!\n\n- keep backups\nv1.2 is fine"),
+            ["Use WAL.", "It helps!", "- keep backups", "v1.2 is fine"]
+        );
+        assert_eq!(comparable("**Use WAL**, always."), "use wal always");
+    }
+
+    #[test]
+    fn shared_sentences_line_up() {
+        let left = keys("Use WAL. Back up nightly. Test restores.");
+        let right = keys("use wal! Vacuum weekly. Test restores.");
+        assert_eq!(
+            align(&left, &right),
+            [
+                (Some(0), Some(0)),
+                (Some(1), None),
+                (None, Some(1)),
+                (Some(2), Some(2)),
+            ]
+        );
+    }
+}
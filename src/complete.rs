@@ -0,0 +1,166 @@
+use crate::attach::{common_prefix, path_candidates};
+
+/// Slash commands offered when completing a bare `/`.
+pub const COMMANDS: &[&str] = &[
+    "/3d",
+    "/analytics",
+    "/archive",
+    "/arena",
+    "/ask",
+    "/attach",
+    "/bash",
+    "/bookmarks",
+    "/brew",
+    "/chat",
+    "/clear",
+    "/compare",
+    "/connect",
+    "/curl",
+    "/detach",
+    "/draft",
+    "/edit",
+    "/effects",
+    "/export",
+    "/forget",
+    "/fx",
+    "/games",
+    "/help",
+    "/keys",
+    "/layout",
+    "/memory",
+    "/meta",
+    "/ollama",
+    "/openrouter",
+    "/pin",
+    "/provider",
+    "/rag",
+    "/randomize",
+    "/recall",
+    "/remember",
+    "/retry",
+    "/run",
+    "/screensaver",
+    "/select",
+    "/server",
+    "/streaming",
+    "/summarize",
+    "/sysmon",
+    "/tag",
+    "/theme",
+    "/tiles",
+    "/trust",
+    "/undo",
+    "/unpin",
+    "/username",
+    "/video",
+    "/vim",
+    "/voice",
+    "/vote",
+    "/web",
+    "/webcam",
+    "/youtube",
+];
+
+const LAYOUTS: &[&str] = &["default", "dual", "triple", "quad", "webcam", "focus"];
+
+/// Argument values the completer cannot know on its own.
+pub struct Sources {
+    /// Provider keys, built-in and custom.
+    pub providers: Vec<String>,
+    /// Model ids from the loaded OpenRouter catalog.
+    pub models: Vec<String>,
+    pub themes: Vec<String>,
+}
+
+/// The Tab popup: candidates for the input from byte `start` onwards.
+pub struct Completion {
+    pub start: usize,
+    pub candidates: Vec<String>,
+    pub selected: usize,
+}
+
+impl Completion {
+    /// Candidates for the token under the cursor (the end of `input`), or
+    /// `None` when the input is not a slash command.
+    pub fn new(input: &str, sources: &Sources) -> Option<Self> {
+        if !input.starts_with('/') {
+            return None;
+        }
+        let (start, candidates) = match input.split_once(' ') {
+            None => (0, matching(COMMANDS.iter().copied(), input)),
+            Some(("/attach" | "/export", path)) => {
+                (input.len() - path.len(), path_candidates(path))
+            }
+            Some((command, _)) => {
+                let start = input.rfind(' ').map_or(0, |index| index + 1);
+                let token = &input[start..];
+                let options: Vec<&str> = match command {
+                    "/provider" => sources.providers.iter().map(String::as_str).collect(),
+                    "/compare" | "/arena" => {
+                        let providers = sources.providers.iter().map(String::as_str);
+                        providers.chain(["diff", "off"]).collect()
+                    }
+                    // only the provider; the question after it is free text
+                    "/ask" if start == "/ask ".len() => {
+                        sources.providers.iter().map(String::as_str).collect()
+                    }
+                    "/openrouter" => sources.models.iter().map(String::as_str).collect(),
+                    "/theme" => {
+                        let themes = sources.themes.iter().map(String::as_str);
+                        themes.chain(["random", "reset"]).collect()
+                    }
+                    "/layout" => LAYOUTS.to_vec(),
+                    "/analytics" => vec!["bookmarks"],
+                    "/draft" => vec!["restore", "discard"],
+                    "/summarize" => vec!["trim", "off"],
+                    "/recall" => vec!["use"],
+                    _ => Vec::new(),
+                };
+                (start, matching(options.into_iter(), token))
+            }
+        };
+        Some(Self {
+            start,
+            candidates,
+            selected: 0,
+        })
+    }
+
+    /// The longest text every candidate starts with.
+    pub fn common(&self) -> String {
+        common_prefix(&self.candidates)
+    }
+
+    pub fn step(&mut self, delta: isize) {
+        let count = self.candidates.len() as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(count.max(1)) as usize;
+    }
+
+    /// Replaces the completed token in `input` with `choice`. Finished words get
+    /// a trailing space; directories stay open for the next Tab.
+    pub fn apply(&self, input: &mut String, choice: &str) {
+        input.truncate(self.start);
+        input.push_str(choice);
+        if !choice.ends_with('/') {
+            input.push(' ');
+        }
+    }
+}
+
+/// Options starting with `token`, falling back to ones containing it (handy for
+/// long `vendor/model` ids).
+fn matching<'a>(options: impl Iterator<Item = &'a str> + Clone, token: &str) -> Vec<String> {
+    let token = token.to_ascii_lowercase();
+    let prefixed: Vec<String> = options
+        .clone()
+        .filter(|option| option.to_ascii_lowercase().starts_with(&token))
+        .map(str::to_string)
+        .collect();
+    if !prefixed.is_empty() {
+        return prefixed;
+    }
+    options
+        .filter(|option| option.to_ascii_lowercase().contains(&token))
+        .map(str::to_string)
+        .collect()
+}
@@ -0,0 +1,304 @@
+use anyhow::{anyhow, Result};
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::ai::{AIProvider, ProviderSettings};
+use crate::capabilities::CapabilityOverride;
+use crate::db::{Database, RetentionPolicy};
+use crate::diagnose::TerminalConfig;
+use crate::backdrop::ChatBackgroundConfig;
+use crate::logging::LogConfig;
+use crate::net::NetworkConfig;
+use crate::notify::NotifyConfig;
+use crate::paths;
+use crate::plugin::PluginProvider;
+use crate::pricing::ModelPrice;
+use crate::provider::{CustomProvider, BUILTIN};
+use crate::rag::RagConfig;
+use crate::screensaver::ScreensaverConfig;
+use crate::sync::SyncConfig;
+use crate::theme::{parse_hex_color, ColorOverrides, ColorVision, ThemeName};
+use crate::voice::VoiceConfig;
+use crate::websearch::WebSearchConfig;
+
+/// User settings read from `config.toml` in the config directory (see `paths`),
+/// with `ASCIIVISION__*` variables and `--set` flags layered on top.
+///
+/// A missing file is the same as an empty one; a broken file is reported once at
+/// startup via `load_error` and otherwise ignored.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Provider used when `--provider` is not given.
+    pub default_provider: Option<String>,
+    /// Conversation database; `~` expands to the home directory.
+    pub db_path: Option<String>,
+    /// `[[db_profiles]]`: other archives the analytics dashboard can switch to.
+    pub db_profiles: Vec<DbProfile>,
+    /// `[retention]`: how much history to keep, applied on startup and by `db prune`.
+    pub retention: RetentionPolicy,
+    /// Video for the intro and video panel when no `--*-video` flag is given.
+    pub loading_video: Option<String>,
+    /// `default`, `solarized`, `dracula`, `monochrome` or `random`.
+    pub theme: ThemeName,
+    /// `[colors]`: single theme colors replaced in every view and theme.
+    pub colors: ColorOverrides,
+    /// `deuteranopia`, `protanopia` or `tritanopia`: provider and status colors
+    /// kept distinguishable, over any theme (default `normal`).
+    pub color_vision: ColorVision,
+    /// Messages of the starting provider restored from the database (default 20, 0 disables).
+    pub history_limit: Option<usize>,
+    /// Retries for rate-limited (429) or failing (5xx) model requests (default 3).
+    pub max_retries: Option<u32>,
+    /// Start the chat deck in vim-style normal mode (`i` to type, `Esc` back).
+    pub vim_mode: bool,
+    /// Always start the chat deck in `--plain` mode, for screen readers.
+    pub plain: bool,
+    /// `--no-effects`: no hue drift, fades, 3D effects, intro or animated
+    /// backdrop, for motion sensitivity or slow remote terminals.
+    pub reduce_motion: bool,
+    /// Record app health metrics (sessions, video frame rates, provider
+    /// errors) in the local archive for the dashboard's Health view.
+    pub metrics: bool,
+    /// `[web_search]` backend used by `/web`.
+    pub web_search: WebSearchConfig,
+    /// `[rag]` embeddings used by `asciivision index` and `/rag`.
+    pub rag: RagConfig,
+    /// `[voice]`: recorder and transcription backend used by `/voice`.
+    pub voice: VoiceConfig,
+    /// `[notifications]`: bell and desktop alerts for replies that land while unfocused.
+    pub notifications: NotifyConfig,
+    /// `[screensaver]`: looping ASCII video after a stretch of inactivity.
+    pub screensaver: ScreensaverConfig,
+    /// `[chat_background]`: a dim looping ASCII video behind the transcript.
+    pub chat_background: ChatBackgroundConfig,
+    /// `[network]` proxy and extra CA certificates for provider and search requests.
+    pub network: NetworkConfig,
+    /// `[logging]`: level and how many days of log files to keep.
+    pub logging: LogConfig,
+    /// `[terminal]`: whether to probe the terminal at startup, and a color depth
+    /// to use instead of the detected one.
+    pub terminal: TerminalConfig,
+    /// `[sync]`: WebDAV or S3 location `db sync` keeps the archive in step with.
+    pub sync: SyncConfig,
+    /// `[provider_settings.<provider>]` tables keyed by provider (`claude`, `gpt`, a custom name, ...).
+    pub provider_settings: HashMap<String, ProviderSettings>,
+    /// `[pricing]` entries keyed by exact model name, USD per million tokens.
+    pub pricing: HashMap<String, ModelPrice>,
+    /// `[capabilities."<model>"]` corrections to what a model is assumed to support.
+    pub capabilities: HashMap<String, CapabilityOverride>,
+    pub providers: Vec<ProviderEntry>,
+    #[serde(skip)]
+    pub load_error: Option<String>,
+}
+
+/// One `[[providers]]` table: an OpenAI-compatible endpoint at `base_url`, or
+/// a plugin `command` speaking the JSON-lines protocol in `plugin.rs`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderEntry {
+    pub name: String,
+    #[serde(default)]
+    pub base_url: String,
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub model: String,
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+    /// `#rrggbb`
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// One `[[db_profiles]]` table: a named conversation database, such as a
+/// backup or an archive copied from another machine.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DbProfile {
+    pub name: String,
+    /// `~` expands to the home directory.
+    pub path: String,
+}
+
+impl Config {
+    pub fn path() -> Result<PathBuf> {
+        asciivision_config::config_path()
+    }
+
+    /// Reads the layered settings, `overrides` being the `--set` flags.
+    pub fn load(overrides: &[String]) -> Self {
+        match Self::try_load(overrides) {
+            Ok(config) => config,
+            Err(error) => Self {
+                load_error: Some(format!("{:#}", error)),
+                ..Self::default()
+            },
+        }
+    }
+
+    fn try_load(overrides: &[String]) -> Result<Self> {
+        let config: Self = asciivision_config::load(&Self::path()?, overrides)?;
+        for entry in &config.providers {
+            entry.validate()?;
+        }
+        config.colors.validate()?;
+        Ok(config)
+    }
+
+    /// Where the conversation database lives: `ASCIIVISION_DB`, then
+    /// `db_path`, then `conversations.db` in the data directory. The chat
+    /// deck, the dashboard and every `db` command resolve it here, so they
+    /// always open the same file.
+    pub fn database_path(&self) -> Result<PathBuf> {
+        if let Some(path) = std::env::var("ASCIIVISION_DB").ok().filter(|path| !path.is_empty()) {
+            return Ok(expand_home(&path));
+        }
+        match self.db_path.as_deref() {
+            Some(path) => Ok(expand_home(path)),
+            None => Database::db_path(),
+        }
+    }
+
+    pub fn open_database(&self) -> Result<Database> {
+        Database::open(self.database_path()?)
+    }
+
+    pub fn loading_video(&self) -> Option<PathBuf> {
+        self.loading_video.as_deref().map(expand_home)
+    }
+
+    pub fn history_limit(&self) -> usize {
+        self.history_limit.unwrap_or(20)
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries.unwrap_or(3)
+    }
+
+    pub fn custom_providers(&self) -> Vec<AIProvider> {
+        self.providers
+            .iter()
+            .map(|entry| {
+                let name = entry.name.clone();
+                let badge = entry.name.to_ascii_uppercase();
+                let api_key_env = entry.api_key_env.clone().filter(|env| !env.is_empty());
+                let color = entry
+                    .color
+                    .as_deref()
+                    .and_then(parse_hex_color)
+                    .unwrap_or(Color::Rgb(214, 196, 132));
+                match &entry.command {
+                    Some(command) => AIProvider::new(PluginProvider {
+                        name,
+                        badge,
+                        command: expand_home(command),
+                        args: entry.args.clone(),
+                        model: entry.model.clone(),
+                        api_key_env,
+                        color,
+                    }),
+                    None => AIProvider::new(CustomProvider {
+                        name,
+                        badge,
+                        base_url: entry.base_url.clone(),
+                        api_key_env,
+                        model: entry.model.clone(),
+                        color,
+                    }),
+                }
+            })
+            .collect()
+    }
+}
+
+impl ProviderEntry {
+    fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(anyhow!("provider entry is missing a name"));
+        }
+        // the name is also the archive, settings and keychain key, so a
+        // built-in's would mix the two providers' history and credentials
+        let name = self.name.trim().to_ascii_lowercase();
+        if let Some(vendor) = BUILTIN
+            .iter()
+            .find(|vendor| vendor.key == name || vendor.aliases.contains(&name.as_str()))
+        {
+            return Err(anyhow!(
+                "provider `{}` clashes with the built-in `{}` provider; pick another name",
+                self.name,
+                vendor.key
+            ));
+        }
+        match &self.command {
+            Some(_) if !self.base_url.is_empty() => {
+                return Err(anyhow!(
+                    "provider `{}` sets both base_url and command; keep one",
+                    self.name
+                ));
+            }
+            Some(command) if command.trim().is_empty() => {
+                return Err(anyhow!("provider `{}` command is empty", self.name));
+            }
+            Some(_) => {}
+            None if !self.base_url.starts_with("http://")
+                && !self.base_url.starts_with("https://") =>
+            {
+                return Err(anyhow!(
+                    "provider `{}` base_url must start with http:// or https://",
+                    self.name
+                ));
+            }
+            None if self.model.trim().is_empty() => {
+                return Err(anyhow!("provider `{}` is missing a model", self.name));
+            }
+            None => {}
+        }
+        if let Some(color) = &self.color {
+            if parse_hex_color(color).is_none() {
+                return Err(anyhow!(
+                    "provider `{}` color `{}` is not #rrggbb",
+                    self.name,
+                    color
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Expands a leading `~/` to the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), paths::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str) -> ProviderEntry {
+        ProviderEntry {
+            name: name.to_string(),
+            base_url: "http://127.0.0.1:8000/v1/chat/completions".to_string(),
+            command: None,
+            args: Vec::new(),
+            model: "llama".to_string(),
+            api_key_env: None,
+            color: None,
+        }
+    }
+
+    #[test]
+    fn custom_providers_cannot_take_built_in_names() {
+        for name in ["claude", "GPT", " ollama ", "local", "or"] {
+            let error = entry(name).validate().expect_err(name);
+            assert!(error.to_string().contains("clashes with the built-in"), "{}", error);
+        }
+        entry("vllm").validate().expect("a name of its own");
+    }
+}
@@ -0,0 +1,2678 @@
+use anyhow::{anyhow, Result};
+use chrono::{Duration as Days, Local, NaiveDate, TimeZone};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{Clear, Paragraph, Wrap},
+};
+use regex::RegexBuilder;
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::ai::ProviderRegistry;
+use crate::analytics::{AnalyticsPanel, Comparison, UsageReport};
+use crate::config::{expand_home, Config};
+use crate::db::{
+    self, BackupFile, ConversationSummary, Database, DatabaseStats, Duplicate, LoggedMessage,
+    MessageFilter, Revision, SearchHit, StoredAttachment, MATCH_END, MATCH_START,
+};
+use crate::help::HelpOverlay;
+use crate::keymap::{self, DashAction};
+use crate::logging::LogViewer;
+use crate::metrics::HealthReport;
+use crate::notify;
+use crate::rag::{self, Embedder, Recalled};
+use crate::streaks::Streaks;
+use crate::text;
+use crate::theme::t;
+use crate::topics::TopicReport;
+use crate::transcript::{self, Entry, EntryKind};
+use crate::{centered_area, human_bytes, next_frame};
+
+const SEARCH_LIMIT: usize = 50;
+/// Exchanges the Semantic view lists.
+const SEMANTIC_LIMIT: usize = 20;
+/// Date ranges `r` cycles through in the Usage and Health views (`parse_date_range` input).
+const USAGE_RANGES: [&str; 4] = ["7d", "30d", "90d", ""];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Stats,
+    Messages,
+    Timeline,
+    Search,
+    Semantic,
+    Usage,
+    Topics,
+    Compare,
+    Streaks,
+    Duplicates,
+    Health,
+}
+
+impl View {
+    fn next(self) -> Self {
+        match self {
+            View::Stats => View::Messages,
+            View::Messages => View::Timeline,
+            View::Timeline => View::Search,
+            View::Search => View::Semantic,
+            View::Semantic => View::Usage,
+            View::Usage => View::Topics,
+            View::Topics => View::Compare,
+            View::Compare => View::Streaks,
+            View::Streaks => View::Duplicates,
+            View::Duplicates => View::Health,
+            View::Health => View::Stats,
+        }
+    }
+
+    /// Views where letters go into a query rather than running commands.
+    fn takes_text(self) -> bool {
+        matches!(self, View::Search | View::Semantic)
+    }
+}
+
+/// The Messages view: the sessions matching the filter, or one of them opened.
+#[derive(Default)]
+struct MessageLog {
+    sessions: Vec<ConversationSummary>,
+    session_selected: usize,
+    /// The opened session, counted under the filter.
+    open: Option<ConversationSummary>,
+    /// The filter narrowed to the opened session.
+    scope: MessageFilter,
+    /// A window of the opened session's messages, starting at `window_start`.
+    rows: Vec<LoggedMessage>,
+    window_start: usize,
+    /// Position of the selected message in the whole session.
+    selected: usize,
+    /// Scroll offset while the selected message is shown full-screen.
+    detail: Option<u16>,
+    /// Furthest the detail view scrolls, from the last render.
+    detail_max: Cell<u16>,
+    /// Shown in place of the selected message's text in the detail view.
+    aside: Option<Aside>,
+    loaded: bool,
+}
+
+/// `a` and `e` in the detail view: a message's attachments, or what an
+/// edited prompt said before.
+enum Aside {
+    Attachments(Vec<StoredAttachment>),
+    Revisions(Vec<Revision>),
+}
+
+impl MessageLog {
+    /// Reloads the session list (and the opened session), keeping both
+    /// selections where they still match.
+    fn load(&mut self, db: Option<&Database>, filter: &MessageFilter) {
+        self.loaded = true;
+        let selected = self.sessions.get(self.session_selected).map(|session| session.id);
+        self.sessions = db.and_then(|db| db.conversations(filter).ok()).unwrap_or_default();
+        self.session_selected = selected
+            .and_then(|id| self.sessions.iter().position(|session| session.id == id))
+            .unwrap_or(0);
+        if let Some(open) = self.open.as_ref().map(|session| session.id) {
+            let anchor = self.current().map(|row| row.id);
+            self.open_session(db, open, anchor, filter);
+        }
+    }
+
+    /// Opens `session` at `anchor` (its first message without one); closes it
+    /// when the filter leaves nothing of it.
+    fn open_session(
+        &mut self,
+        db: Option<&Database>,
+        session: i64,
+        anchor: Option<i64>,
+        filter: &MessageFilter,
+    ) {
+        self.scope = MessageFilter {
+            conversation: Some(session),
+            ..filter.clone()
+        };
+        self.open = match self.sessions.iter().position(|summary| summary.id == session) {
+            Some(index) => {
+                self.session_selected = index;
+                db.and_then(|db| db.conversations(&self.scope).ok())
+                    .and_then(|mut sessions| sessions.pop())
+            }
+            None => None,
+        };
+        let position = anchor
+            .zip(db)
+            .and_then(|(id, db)| db.message_position(&self.scope, id).ok().flatten());
+        self.selected = position.unwrap_or(0);
+        self.detail = self.detail.filter(|_| position.is_some());
+        self.rows.clear();
+        self.fetch(db);
+        if self.open.is_none() {
+            self.detail = None;
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.open.as_ref().map_or(0, |session| session.messages as usize)
+    }
+
+    fn current(&self) -> Option<&LoggedMessage> {
+        self.row(self.selected)
+    }
+
+    /// The message at `position` in the session, if it is in the window.
+    fn row(&self, position: usize) -> Option<&LoggedMessage> {
+        position.checked_sub(self.window_start).and_then(|index| self.rows.get(index))
+    }
+
+    /// Re-centres the window on the selection once it comes within
+    /// `PREFETCH` messages of an edge that has more beyond it.
+    fn fetch(&mut self, db: Option<&Database>) {
+        let total = self.total();
+        let end = self.window_start + self.rows.len();
+        let outside = self.selected < self.window_start || self.selected >= end;
+        let near_start = self.window_start > 0 && self.selected < self.window_start + PREFETCH;
+        let near_end = end < total && self.selected + PREFETCH >= end;
+        if total == 0 || !(outside || near_start || near_end) {
+            return;
+        }
+        self.window_start = self.selected.saturating_sub(PAGE / 2);
+        self.rows = db
+            .and_then(|db| db.messages_page(&self.scope, self.window_start, PAGE).ok())
+            .unwrap_or_default();
+    }
+
+    /// Scrolls the detail view, stopping at the end of the message.
+    fn scroll(&mut self, delta: i32) {
+        if let Some(scroll) = self.detail.as_mut() {
+            let max = self.detail_max.get() as i32;
+            *scroll = (*scroll as i32 + delta).clamp(0, max) as u16;
+        }
+    }
+
+    fn step(&mut self, db: Option<&Database>, delta: isize) {
+        let total = self.total();
+        let (selected, len) = match self.open {
+            Some(_) => (&mut self.selected, total),
+            None => (&mut self.session_selected, self.sessions.len()),
+        };
+        let last = len.saturating_sub(1) as isize;
+        *selected = (*selected as isize).saturating_add(delta).clamp(0, last) as usize;
+        if self.open.is_some() {
+            self.fetch(db);
+        }
+    }
+}
+
+/// The Timeline view: every provider's messages in one stream, in the order
+/// they were sent, opened at the newest.
+#[derive(Default)]
+struct Timeline {
+    /// A window of the stream, starting at `window_start`.
+    rows: Vec<LoggedMessage>,
+    window_start: usize,
+    total: usize,
+    selected: usize,
+    loaded: bool,
+}
+
+impl Timeline {
+    fn load(&mut self, db: Option<&Database>, filter: &MessageFilter) {
+        self.loaded = true;
+        self.total = db.and_then(|db| db.timeline_len(filter).ok()).unwrap_or(0);
+        self.selected = self.total.saturating_sub(1);
+        self.rows.clear();
+        self.fetch(db, filter);
+    }
+
+    fn row(&self, position: usize) -> Option<&LoggedMessage> {
+        position.checked_sub(self.window_start).and_then(|index| self.rows.get(index))
+    }
+
+    /// Moves the window the way `MessageLog::fetch` does.
+    fn fetch(&mut self, db: Option<&Database>, filter: &MessageFilter) {
+        let end = self.window_start + self.rows.len();
+        let outside = self.selected < self.window_start || self.selected >= end;
+        let near_start = self.window_start > 0 && self.selected < self.window_start + PREFETCH;
+        let near_end = end < self.total && self.selected + PREFETCH >= end;
+        if self.total == 0 || !(outside || near_start || near_end) {
+            return;
+        }
+        self.window_start = self.selected.saturating_sub(PAGE / 2);
+        self.rows = db
+            .and_then(|db| db.timeline(filter, self.window_start, PAGE).ok())
+            .unwrap_or_default();
+    }
+
+    fn step(&mut self, db: Option<&Database>, filter: &MessageFilter, delta: isize) {
+        let last = self.total.saturating_sub(1) as isize;
+        self.selected = (self.selected as isize).saturating_add(delta).clamp(0, last) as usize;
+        self.fetch(db, filter);
+    }
+}
+
+#[derive(Default)]
+struct Search {
+    query: String,
+    /// Ctrl+R: the text left after the operators is a regular expression.
+    regex: bool,
+    hits: Vec<SearchHit>,
+    selected: usize,
+    error: Option<String>,
+}
+
+impl Search {
+    fn run(&mut self, db: Option<&Database>, filter: &MessageFilter) {
+        self.selected = 0;
+        self.error = None;
+        let hits = match db {
+            Some(db) if !self.query.trim().is_empty() => self.matches(db, filter),
+            _ => Ok(Vec::new()),
+        };
+        self.hits = hits.unwrap_or_else(|error| {
+            self.error = Some(format!("{:#}", error));
+            Vec::new()
+        });
+    }
+
+    fn matches(&self, db: &Database, filter: &MessageFilter) -> Result<Vec<SearchHit>> {
+        let (text, filter) = parse_search(&self.query, filter)?;
+        if self.regex {
+            let pattern = match text.as_str() {
+                "" => None,
+                text => Some(
+                    RegexBuilder::new(text)
+                        .case_insensitive(true)
+                        .build()
+                        .map_err(|error| anyhow!("{}", regex_error(&error)))?,
+                ),
+            };
+            return db.search_pattern(pattern.as_ref(), &filter, SEARCH_LIMIT);
+        }
+        if text.is_empty() {
+            // operators alone list the newest messages they match
+            return db.search_pattern(None, &filter, SEARCH_LIMIT);
+        }
+        db.search(&text, &filter, SEARCH_LIMIT)
+    }
+}
+
+/// The Semantic view: archived exchanges ranked by meaning against a query,
+/// using the vectors `asciivision index-history` stored.
+#[derive(Default)]
+struct Semantic {
+    query: String,
+    /// The query `found` answers; `Enter` on a changed one searches again.
+    searched: String,
+    /// `None` when `[rag]` names a provider that cannot embed.
+    embedder: Option<Embedder>,
+    found: Vec<Recalled>,
+    selected: usize,
+    /// Set by `Enter`; the query is embedded once the frame saying so is drawn.
+    pending: bool,
+    error: Option<String>,
+}
+
+impl Semantic {
+    fn new(config: &Config) -> Self {
+        let providers = ProviderRegistry::new(config.custom_providers());
+        match Embedder::new(&providers, &config.rag) {
+            Ok(embedder) => Self {
+                embedder: Some(embedder),
+                ..Self::default()
+            },
+            Err(error) => Self {
+                error: Some(format!("{:#}", error)),
+                ..Self::default()
+            },
+        }
+    }
+
+    async fn run(&mut self, db: Option<&Database>) {
+        self.pending = false;
+        let Some(embedder) = &self.embedder else {
+            return;
+        };
+        self.searched = self.query.clone();
+        let embedding = embedder
+            .embed(std::slice::from_ref(&self.query))
+            .await
+            .and_then(|mut vectors| vectors.pop().ok_or_else(|| anyhow!("no embedding returned")));
+        match embedding {
+            Ok(embedding) => self.rank(db, &embedding),
+            Err(error) => {
+                self.found.clear();
+                self.error = Some(format!("{:#}", error));
+            }
+        }
+    }
+
+    fn rank(&mut self, db: Option<&Database>, embedding: &[f32]) {
+        self.selected = 0;
+        self.error = None;
+        let (Some(db), Some(embedder)) = (db, &self.embedder) else {
+            return;
+        };
+        self.found = rag::recall(db, &embedder.model, embedding, SEMANTIC_LIMIT)
+            .unwrap_or_else(|error| {
+                self.error = Some(format!("{:#}", error));
+                Vec::new()
+            });
+    }
+}
+
+/// The Usage view's report, reloaded every 5s and whenever the filter changes.
+#[derive(Default)]
+struct Usage {
+    report: UsageReport,
+    refreshed: Option<Instant>,
+    /// Index into `USAGE_RANGES` of the last preset picked with `r`.
+    preset: usize,
+}
+
+impl Usage {
+    fn refresh(&mut self, db: Option<&Database>, filter: &MessageFilter) {
+        if self.refreshed.is_some_and(|at| at.elapsed() < Duration::from_secs(5)) {
+            return;
+        }
+        self.refreshed = Some(Instant::now());
+        self.report = db.map(|db| UsageReport::load(db, filter)).unwrap_or_default();
+    }
+}
+
+/// The Health view's metrics, reloaded every 5s and whenever the filter changes.
+#[derive(Default)]
+struct Health {
+    report: HealthReport,
+    refreshed: Option<Instant>,
+}
+
+impl Health {
+    fn refresh(&mut self, db: Option<&Database>, filter: &MessageFilter) {
+        if self.refreshed.is_some_and(|at| at.elapsed() < Duration::from_secs(5)) {
+            return;
+        }
+        self.refreshed = Some(Instant::now());
+        self.report = db.map(|db| HealthReport::load(db, filter)).unwrap_or_default();
+    }
+}
+
+/// The Topics view's word counts, recounted whenever the filter changes.
+#[derive(Default)]
+struct Topics {
+    report: TopicReport,
+    stale: bool,
+}
+
+/// The Compare view's per-provider table, reloaded whenever the filter changes.
+#[derive(Default)]
+struct Compare {
+    report: Comparison,
+    stale: bool,
+}
+
+/// Messages of the opened session held in memory at once.
+const PAGE: usize = 200;
+
+/// The window moves once the selection is this close to one of its edges.
+const PREFETCH: usize = 50;
+
+/// A refresh slower than this lights the status line's lock indicator.
+const SLOW_READ: Duration = Duration::from_millis(250);
+
+/// How long the lock indicator stays lit after a delayed read.
+const CONTENTION_SHOWN: Duration = Duration::from_secs(3);
+
+/// A database the dashboard can switch to with `o`.
+struct Profile {
+    name: String,
+    path: PathBuf,
+    /// For a backup opened from the picker, the profile it is a backup of.
+    backup_of: Option<usize>,
+}
+
+/// `R`: the backups of one profile's archive, newest first.
+struct BackupPicker {
+    /// Index into `profiles` of the archive they back up and restore into.
+    target: usize,
+    files: Vec<BackupFile>,
+    selected: usize,
+    /// Set by `r` until `y` confirms the restore.
+    confirm: bool,
+}
+
+/// The configured database, then `[[db_profiles]]`, then `--db` when given.
+fn profiles(config: &Config, db: Option<PathBuf>) -> Vec<Profile> {
+    let mut profiles = Vec::new();
+    if let Ok(path) = config.database_path() {
+        profiles.push(Profile {
+            name: "default".to_string(),
+            path,
+            backup_of: None,
+        });
+    }
+    profiles.extend(config.db_profiles.iter().map(|profile| Profile {
+        name: profile.name.clone(),
+        path: expand_home(&profile.path),
+        backup_of: None,
+    }));
+    if let Some(path) = db {
+        profiles.push(Profile {
+            name: "--db".to_string(),
+            path,
+            backup_of: None,
+        });
+    }
+    profiles
+}
+
+/// The Streaks view's records, recomputed whenever the filter changes.
+#[derive(Default)]
+struct StreakView {
+    report: Streaks,
+    stale: bool,
+}
+
+/// The Duplicates view's report, searched again whenever the filter changes.
+#[derive(Default)]
+struct Duplicates {
+    report: Vec<Duplicate>,
+    selected: usize,
+    stale: bool,
+}
+
+/// Everything the standalone dashboard shows, with the filter every view shares.
+struct Dashboard {
+    db: Option<Database>,
+    profiles: Vec<Profile>,
+    /// Index into `profiles` of the open database.
+    profile: usize,
+    view: View,
+    filter: MessageFilter,
+    /// Providers `p` cycles through.
+    providers: Vec<String>,
+    /// Conversation tags `t` cycles through.
+    tags: Vec<String>,
+    panel: AnalyticsPanel,
+    log: MessageLog,
+    timeline: Timeline,
+    search: Search,
+    semantic: Semantic,
+    usage: Usage,
+    topics: Topics,
+    compare: Compare,
+    streaks: StreakView,
+    duplicates: Duplicates,
+    health: Health,
+    /// Status line message until the next key, and whether it is an error.
+    notice: Option<(String, bool)>,
+    /// When a read last waited on the chat deck's lock or ran slow.
+    contended: Option<Instant>,
+    backups: Option<BackupPicker>,
+    /// `i`: the About this database panel, read when it opens.
+    about: Option<DatabaseStats>,
+    /// F12: recent warnings and errors.
+    logs: LogViewer,
+    help: HelpOverlay,
+}
+
+impl Dashboard {
+    /// Opens the archive named by `--db`, or the first configured profile.
+    fn new(config: &Config, db: Option<PathBuf>) -> Result<Self> {
+        let from_flag = db.is_some();
+        let mut dash = Self {
+            db: None,
+            profiles: profiles(config, db),
+            profile: 0,
+            providers: Vec::new(),
+            tags: Vec::new(),
+            view: View::Stats,
+            filter: MessageFilter::default(),
+            panel: AnalyticsPanel::new(),
+            log: MessageLog::default(),
+            timeline: Timeline::default(),
+            search: Search::default(),
+            semantic: Semantic::new(config),
+            usage: Usage::default(),
+            topics: Topics {
+                stale: true,
+                ..Topics::default()
+            },
+            compare: Compare {
+                stale: true,
+                ..Compare::default()
+            },
+            streaks: StreakView {
+                stale: true,
+                ..StreakView::default()
+            },
+            duplicates: Duplicates {
+                stale: true,
+                ..Duplicates::default()
+            },
+            health: Health::default(),
+            notice: None,
+            contended: None,
+            backups: None,
+            about: None,
+            logs: LogViewer::default(),
+            help: HelpOverlay::new("ANALYTICS // KEYS", keymap::dash_bindings()),
+        };
+        if from_flag {
+            dash.open_profile(dash.profiles.len() - 1)?;
+        } else if !dash.profiles.is_empty() {
+            if let Err(error) = dash.open_profile(0) {
+                dash.notice = Some((format!("{:#}", error), true));
+            }
+        }
+        Ok(dash)
+    }
+
+    /// Re-runs every view against a changed filter, keeping the Messages
+    /// selection where it still matches.
+    fn apply_filter(&mut self) {
+        let db = self.db.as_ref();
+        self.panel.set_filter(self.filter.clone());
+        self.usage.refreshed = None;
+        self.health.refreshed = None;
+        self.topics.stale = true;
+        self.compare.stale = true;
+        self.streaks.stale = true;
+        self.duplicates.stale = true;
+        if self.log.loaded {
+            self.log.load(db, &self.filter);
+        }
+        if self.timeline.loaded {
+            self.timeline.load(db, &self.filter);
+        }
+        if !self.search.query.is_empty() {
+            self.search.run(db, &self.filter);
+        }
+    }
+
+    /// Switches to `profiles[index]`, keeping the filter but dropping a
+    /// provider or tag the new archive has never used. The archive is opened
+    /// read-only, so a mistyped path never leaves an empty database behind.
+    fn open_profile(&mut self, index: usize) -> Result<()> {
+        let profile = &self.profiles[index];
+        if !profile.path.exists() {
+            return Err(anyhow!("no database at {}", profile.path.display()));
+        }
+        let db = Database::open_read_only(profile.path.clone())?;
+        if let Some(backup) = db.migration_backup() {
+            let saved = format!(
+                "archive upgraded // previous version saved as {}",
+                backup.display()
+            );
+            self.notice = Some((saved, false));
+        }
+        self.providers = db.providers().unwrap_or_default();
+        self.tags = db.tags().unwrap_or_default();
+        self.db = Some(db);
+        self.profile = index;
+        if let Some(provider) = &self.filter.provider {
+            if !self.providers.contains(provider) {
+                self.filter.provider = None;
+            }
+        }
+        if let Some(tag) = &self.filter.tag {
+            if !self.tags.contains(tag) {
+                self.filter.tag = None;
+            }
+        }
+        self.log = MessageLog::default();
+        self.timeline = Timeline::default();
+        self.apply_filter();
+        Ok(())
+    }
+
+    /// `o`: opens the next profile, reporting one that fails to open.
+    fn cycle_profile(&mut self) {
+        if self.profiles.len() < 2 {
+            self.notice = Some(("add [[db_profiles]] to config.toml to switch".to_string(), true));
+            return;
+        }
+        let next = (self.profile + 1) % self.profiles.len();
+        let name = self.profiles[next].name.clone();
+        self.notice = Some(match self.open_profile(next) {
+            Ok(()) => self.notice.take().unwrap_or((format!("opened {}", name), false)),
+            Err(error) => (format!("{}: {:#}", name, error), true),
+        });
+    }
+
+    /// The profile backups are made of: the open one, or the one an opened
+    /// backup came from.
+    fn home_profile(&self) -> usize {
+        self.profiles
+            .get(self.profile)
+            .and_then(|profile| profile.backup_of)
+            .unwrap_or(self.profile)
+    }
+
+    /// `B`: copies the open archive into `backups/` next to its profile's
+    /// database.
+    fn backup(&mut self) {
+        let Some(db) = &self.db else {
+            self.notice = Some(("no database open to back up".to_string(), true));
+            return;
+        };
+        let home = &self.profiles[self.home_profile()].path;
+        let path = db::backup_path(home);
+        self.notice = Some(match db.backup_to(&path) {
+            Ok(()) => (format!("backed up to {}", path.display()), false),
+            Err(error) => (format!("backup failed: {:#}", error), true),
+        });
+        if let Some(picker) = &mut self.backups {
+            picker.files = db::list_backups(&db::backup_dir(home));
+            picker.selected = 0;
+        }
+    }
+
+    /// `i`: reads sizes, counts and index health of the open database.
+    fn open_about(&mut self) {
+        let Some(db) = &self.db else {
+            self.notice = Some(("no database open".to_string(), true));
+            return;
+        };
+        match db.stats() {
+            Ok(stats) => self.about = Some(stats),
+            Err(error) => self.notice = Some((format!("stats failed: {:#}", error), true)),
+        }
+    }
+
+    /// `R`: lists the home profile's backups.
+    fn open_backups(&mut self) {
+        let target = self.home_profile();
+        let Some(profile) = self.profiles.get(target) else {
+            return;
+        };
+        self.backups = Some(BackupPicker {
+            target,
+            files: db::list_backups(&db::backup_dir(&profile.path)),
+            selected: 0,
+            confirm: false,
+        });
+    }
+
+    fn handle_backup_key(&mut self, key: KeyEvent) {
+        let Some(picker) = &mut self.backups else {
+            return;
+        };
+        if picker.confirm {
+            picker.confirm = false;
+            if key.code == KeyCode::Char('y') {
+                self.restore_backup();
+            }
+            return;
+        }
+        let last = picker.files.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.backups = None,
+            KeyCode::Up | KeyCode::Char('k') => picker.selected = picker.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => picker.selected = (picker.selected + 1).min(last),
+            KeyCode::Char('r') if !picker.files.is_empty() => picker.confirm = true,
+            KeyCode::Char('B') => self.backup(),
+            KeyCode::Enter => self.open_backup(),
+            _ => {}
+        }
+    }
+
+    /// Browses the selected backup read-only, as a profile `o` can leave.
+    fn open_backup(&mut self) {
+        let Some(picker) = self.backups.take() else {
+            return;
+        };
+        let Some(file) = picker.files.get(picker.selected) else {
+            return;
+        };
+        let index = match self.profiles.iter().position(|profile| profile.path == file.path) {
+            Some(index) => index,
+            None => {
+                let name = file.path.file_stem().map(|stem| stem.to_string_lossy());
+                self.profiles.push(Profile {
+                    name: format!("backup {}", name.unwrap_or_default()),
+                    path: file.path.clone(),
+                    backup_of: Some(picker.target),
+                });
+                self.profiles.len() - 1
+            }
+        };
+        self.notice = Some(match self.open_profile(index) {
+            Ok(()) => (format!("opened {}", file.path.display()), false),
+            Err(error) => (format!("{:#}", error), true),
+        });
+    }
+
+    /// Writes the selected backup over its profile's archive, after saving the
+    /// archive as it was to a fresh backup.
+    fn restore_backup(&mut self) {
+        let Some(picker) = self.backups.take() else {
+            return;
+        };
+        let Some(file) = picker.files.get(picker.selected) else {
+            return;
+        };
+        let target = self.profiles[picker.target].path.clone();
+        // the dashboard's own connection is dropped so it cannot pin old pages
+        self.db = None;
+        let result = Database::open_read_only(target.clone())
+            .and_then(|db| db.snapshot("restore"))
+            .and_then(|saved| Database::restore(&target, &file.path).map(|()| saved));
+        let reopened = self.open_profile(picker.target);
+        self.notice = Some(match result.and_then(|saved| reopened.map(|()| saved)) {
+            Ok(saved) => (
+                format!(
+                    "restored {} (previous archive saved as {})",
+                    file.path.display(),
+                    saved.display()
+                ),
+                false,
+            ),
+            Err(error) => (format!("restore failed: {:#}", error), true),
+        });
+    }
+
+    /// Runs a filter or panel key; false for keys the current view should
+    /// get instead (Esc closing a session).
+    fn run_action(&mut self, action: DashAction, date_input: &mut Option<String>) -> bool {
+        match action {
+            DashAction::Provider => self.cycle_provider(),
+            DashAction::Role(role) => {
+                let same = self.filter.role == Some(role);
+                self.filter.role = if same { None } else { Some(role) };
+                self.apply_filter();
+            }
+            DashAction::Tag => self.cycle_tag(),
+            DashAction::Dates => *date_input = Some(String::new()),
+            DashAction::Profile => self.cycle_profile(),
+            DashAction::Backup => self.backup(),
+            DashAction::Backups => self.open_backups(),
+            DashAction::About => self.open_about(),
+            DashAction::Archived => {
+                self.filter.hidden = !self.filter.hidden;
+                self.apply_filter();
+            }
+            DashAction::Help => self.help.toggle(),
+            DashAction::NextView => self.view = self.view.next(),
+            DashAction::Logs => self.logs.toggle(),
+            DashAction::Quit => return false,
+        }
+        true
+    }
+
+    /// Steps the provider filter through every archived provider, then off.
+    fn cycle_provider(&mut self) {
+        let next = match &self.filter.provider {
+            None => 0,
+            Some(current) => self
+                .providers
+                .iter()
+                .position(|provider| provider == current)
+                .map_or(0, |index| index + 1),
+        };
+        self.filter.provider = self.providers.get(next).cloned();
+        self.apply_filter();
+    }
+
+    /// `t`: steps the tag filter through every tag in use, then off.
+    fn cycle_tag(&mut self) {
+        if self.tags.is_empty() {
+            let hint = "no tagged conversations // /tag <name> in chat";
+            self.notice = Some((hint.to_string(), true));
+            return;
+        }
+        let next = match &self.filter.tag {
+            None => 0,
+            Some(current) => self
+                .tags
+                .iter()
+                .position(|tag| tag == current)
+                .map_or(0, |index| index + 1),
+        };
+        self.filter.tag = self.tags.get(next).cloned();
+        self.apply_filter();
+    }
+
+    /// Brings the current view's data up to date before drawing, noting
+    /// whether the reads had to wait.
+    fn refresh(&mut self) {
+        let started = Instant::now();
+        self.reload();
+        if Database::take_busy_waits() > 0 || started.elapsed() > SLOW_READ {
+            self.contended = Some(Instant::now());
+        }
+    }
+
+    fn reload(&mut self) {
+        let db = self.db.as_ref();
+        match self.view {
+            View::Stats => self.panel.refresh(db),
+            View::Usage => self.usage.refresh(db, &self.filter),
+            View::Health => self.health.refresh(db, &self.filter),
+            View::Topics if self.topics.stale => {
+                self.topics.stale = false;
+                self.topics.report = db
+                    .map(|db| TopicReport::load(db, &self.filter))
+                    .unwrap_or_default();
+            }
+            View::Compare if self.compare.stale => {
+                self.compare.stale = false;
+                self.compare.report = db
+                    .map(|db| Comparison::load(db, &self.filter))
+                    .unwrap_or_default();
+            }
+            View::Streaks if self.streaks.stale => {
+                self.streaks.stale = false;
+                self.streaks.report =
+                    db.map(|db| Streaks::load(db, &self.filter)).unwrap_or_default();
+            }
+            View::Duplicates if self.duplicates.stale => {
+                self.duplicates.stale = false;
+                self.duplicates.report =
+                    db.and_then(|db| db.duplicates(&self.filter).ok()).unwrap_or_default();
+                let last = self.duplicates.report.len().saturating_sub(1);
+                self.duplicates.selected = self.duplicates.selected.min(last);
+            }
+            View::Messages if !self.log.loaded => self.log.load(db, &self.filter),
+            View::Timeline if !self.timeline.loaded => self.timeline.load(db, &self.filter),
+            View::Topics
+            | View::Compare
+            | View::Streaks
+            | View::Duplicates
+            | View::Messages
+            | View::Timeline
+            | View::Search
+            | View::Semantic => {}
+        }
+    }
+
+    /// Writes the opened session, or every message the filter matches, to a
+    /// Markdown transcript in the working directory.
+    fn export(&mut self) {
+        let Some(db) = &self.db else {
+            return;
+        };
+        let (filter, scope, title) = match &self.log.open {
+            Some(session) => (
+                MessageFilter {
+                    conversation: Some(session.id),
+                    ..self.filter.clone()
+                },
+                format!("session-{}", session.id),
+                session_heading(session),
+            ),
+            None => (
+                self.filter.clone(),
+                self.filter.provider.clone().unwrap_or_else(|| "archive".to_string()),
+                format!("asciivision archive // {}", filter_label(&self.filter)),
+            ),
+        };
+        let path = transcript::default_path(&scope);
+        let result = db.filtered_messages(&filter).and_then(|rows| {
+            let entries: Vec<Entry> = rows.iter().map(transcript_entry).collect();
+            transcript::write(&path, &transcript::markdown(&title, &entries))?;
+            Ok(rows.len())
+        });
+        self.notice = Some(match result {
+            Ok(count) => (format!("exported {} messages to {}", count, path.display()), false),
+            Err(error) => (format!("export failed: {:#}", error), true),
+        });
+    }
+
+    /// `m` in Duplicates: folds every listed copy into its original through a
+    /// short-lived writable connection, the dashboard's own being read-only.
+    fn merge_duplicates(&mut self) {
+        let profile = &self.profiles[self.profile];
+        if profile.backup_of.is_some() {
+            let notice = "a backup is open read-only // o switches back to merge".to_string();
+            self.notice = Some((notice, true));
+            return;
+        }
+        if self.duplicates.report.is_empty() {
+            self.notice = Some(("no duplicates to merge".to_string(), false));
+            return;
+        }
+        let result = Database::open(profile.path.clone())
+            .and_then(|db| db.merge_duplicates(&self.duplicates.report));
+        self.notice = Some(match result {
+            Ok((removed, backup)) => (
+                format!(
+                    "merged {} duplicate messages // backup at {}",
+                    removed,
+                    backup.display()
+                ),
+                false,
+            ),
+            Err(error) => (format!("merge failed: {:#}", error), true),
+        });
+        self.apply_filter();
+    }
+
+    /// Opens the session holding message `id` in the Messages view, with the
+    /// message selected.
+    fn open_in_messages(&mut self, id: i64) {
+        let db = self.db.as_ref();
+        let session = db.and_then(|db| db.conversation_of(id).ok());
+        self.log.load(db, &self.filter);
+        self.log.detail = None;
+        if let Some(session) = session.flatten() {
+            self.log.open_session(db, session, Some(id), &self.filter);
+        }
+        self.view = View::Messages;
+    }
+
+    /// Keys for the current view; `true` quits.
+    fn handle_view_key(&mut self, key: KeyEvent) -> bool {
+        let db = self.db.as_ref();
+        match self.view {
+            View::Stats => {
+                if key.code == KeyCode::Char('b') {
+                    self.panel.toggle_bookmark_filter();
+                }
+            }
+            View::Usage | View::Health => {
+                if key.code == KeyCode::Char('r') {
+                    self.usage.preset = (self.usage.preset + 1) % USAGE_RANGES.len();
+                    let range = USAGE_RANGES[self.usage.preset];
+                    if let Ok((since, until)) = parse_date_range(range) {
+                        self.filter.since = since;
+                        self.filter.until = until;
+                    }
+                    self.apply_filter();
+                }
+            }
+            View::Topics => {}
+            View::Compare if key.code == KeyCode::Char('r') => self.compare.stale = true,
+            View::Compare => {}
+            View::Streaks => {}
+            View::Duplicates => {
+                let duplicates = &mut self.duplicates;
+                match key.code {
+                    KeyCode::Up => duplicates.selected = duplicates.selected.saturating_sub(1),
+                    KeyCode::Down => {
+                        let last = duplicates.report.len().saturating_sub(1);
+                        duplicates.selected = (duplicates.selected + 1).min(last);
+                    }
+                    KeyCode::Enter => {
+                        let selected = duplicates.report.get(duplicates.selected);
+                        if let Some(id) = selected.map(|duplicate| duplicate.original.id) {
+                            self.open_in_messages(id);
+                        }
+                    }
+                    KeyCode::Char('m') => self.merge_duplicates(),
+                    _ => {}
+                }
+            }
+            View::Messages if key.code == KeyCode::Char('x') => self.export(),
+            View::Messages => handle_log_key(&mut self.log, db, key, &self.filter),
+            View::Timeline => {
+                let timeline = &mut self.timeline;
+                let filter = &self.filter;
+                match key.code {
+                    KeyCode::Up => timeline.step(db, filter, -1),
+                    KeyCode::Down => timeline.step(db, filter, 1),
+                    KeyCode::PageUp => timeline.step(db, filter, -20),
+                    KeyCode::PageDown => timeline.step(db, filter, 20),
+                    KeyCode::Home => timeline.step(db, filter, isize::MIN),
+                    KeyCode::End => timeline.step(db, filter, isize::MAX),
+                    KeyCode::Enter => {
+                        if let Some(id) = timeline.row(timeline.selected).map(|row| row.id) {
+                            self.open_in_messages(id);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            View::Search => {
+                let search = &mut self.search;
+                match key.code {
+                    KeyCode::Esc if search.query.is_empty() => return true,
+                    KeyCode::Esc => {
+                        search.query.clear();
+                        search.run(db, &self.filter);
+                    }
+                    KeyCode::Enter => {
+                        let hit = search.hits.get(search.selected).map(|hit| hit.message_id);
+                        if let Some(id) = hit {
+                            self.open_in_messages(id);
+                        }
+                    }
+                    KeyCode::Up => search.selected = search.selected.saturating_sub(1),
+                    KeyCode::Down => {
+                        let last = search.hits.len().saturating_sub(1);
+                        search.selected = (search.selected + 1).min(last);
+                    }
+                    KeyCode::Backspace => {
+                        search.query.pop();
+                        search.run(db, &self.filter);
+                    }
+                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        search.regex = !search.regex;
+                        search.run(db, &self.filter);
+                    }
+                    KeyCode::Char(c) => {
+                        search.query.push(c);
+                        search.run(db, &self.filter);
+                    }
+                    _ => {}
+                }
+            }
+            View::Semantic => {
+                let semantic = &mut self.semantic;
+                match key.code {
+                    KeyCode::Esc if semantic.query.is_empty() => return true,
+                    KeyCode::Esc => semantic.query.clear(),
+                    KeyCode::Enter if semantic.query != semantic.searched => {
+                        semantic.pending = !semantic.query.trim().is_empty();
+                    }
+                    KeyCode::Enter => {
+                        let found = semantic.found.get(semantic.selected);
+                        let id = found.and_then(|found| found.messages.first()).map(|m| m.id);
+                        if let Some(id) = id {
+                            self.open_in_messages(id);
+                        }
+                    }
+                    KeyCode::Up => semantic.selected = semantic.selected.saturating_sub(1),
+                    KeyCode::Down => {
+                        let last = semantic.found.len().saturating_sub(1);
+                        semantic.selected = (semantic.selected + 1).min(last);
+                    }
+                    KeyCode::Backspace => {
+                        semantic.query.pop();
+                    }
+                    KeyCode::Char(c) => semantic.query.push(c),
+                    _ => {}
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Standalone analytics view for `asciivision analytics`.
+/// Runs the dashboard over the configured database, or over `db` when given.
+/// With `frames` (`--smoke`) keys are not read; each frame moves on to the
+/// next view, and it stops after that many or at the first error notice.
+pub async fn run<B: Backend>(
+    terminal: &mut Terminal<B>,
+    config: &Config,
+    db: Option<PathBuf>,
+    frames: Option<u32>,
+) -> Result<()> {
+    let mut dash = Dashboard::new(config, db)?;
+    // `d` opens a date range prompt in the status line
+    let mut date_input: Option<String> = None;
+    let started = Instant::now();
+    let mut drawn = 0;
+    let mut focused = true;
+
+    loop {
+        if let Some(frames) = frames {
+            if let Some((notice, true)) = &dash.notice {
+                return Err(anyhow!("{:?} view: {}", dash.view, notice));
+            }
+            if drawn >= frames {
+                return Ok(());
+            }
+            if drawn > 0 {
+                dash.view = dash.view.next();
+            }
+        }
+        while frames.is_none() && event::poll(Duration::from_millis(5))? {
+            let key = match event::read()? {
+                Event::Key(key) => key,
+                Event::FocusGained => {
+                    focused = true;
+                    continue;
+                }
+                Event::FocusLost => {
+                    focused = false;
+                    continue;
+                }
+                _ => continue,
+            };
+            let ctrl_c =
+                key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c');
+            if ctrl_c {
+                return Ok(());
+            }
+            if dash.logs.open {
+                dash.logs.handle_key(key);
+                continue;
+            }
+            if dash.help.open {
+                dash.help.handle_key(key);
+                continue;
+            }
+            let action = keymap::dash_action(key);
+            if action == Some(DashAction::Logs) {
+                dash.logs.toggle();
+                continue;
+            }
+            dash.notice = None;
+            if let Some(input) = date_input.as_mut() {
+                match key.code {
+                    KeyCode::Esc => date_input = None,
+                    KeyCode::Enter => {
+                        match parse_date_range(input) {
+                            Ok((since, until)) => {
+                                dash.filter.since = since;
+                                dash.filter.until = until;
+                            }
+                            Err(error) => dash.notice = Some((error.to_string(), true)),
+                        }
+                        date_input = None;
+                        dash.apply_filter();
+                    }
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Char(c) => input.push(c),
+                    _ => {}
+                }
+                continue;
+            }
+            if dash.backups.is_some() {
+                dash.handle_backup_key(key);
+                continue;
+            }
+            if dash.about.is_some() {
+                if matches!(key.code, KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('i')) {
+                    dash.about = None;
+                }
+                continue;
+            }
+            let reading = dash.view == View::Messages && dash.log.detail.is_some();
+            let closes_session = dash.view == View::Messages
+                && dash.log.open.is_some()
+                && matches!(key.code, KeyCode::Esc | KeyCode::Backspace);
+            match action {
+                Some(DashAction::NextView) => {
+                    dash.view = dash.view.next();
+                    continue;
+                }
+                Some(DashAction::Quit) if !dash.view.takes_text() && !closes_session => {
+                    return Ok(());
+                }
+                Some(DashAction::Help) if !dash.view.takes_text() => {
+                    dash.help.toggle();
+                    continue;
+                }
+                Some(action)
+                    if !dash.view.takes_text()
+                        && !reading
+                        && dash.run_action(action, &mut date_input) =>
+                {
+                    continue;
+                }
+                _ => {}
+            }
+            if dash.handle_view_key(key) {
+                return Ok(());
+            }
+        }
+
+        dash.refresh();
+        let phase = if config.reduce_motion { 0.0 } else { started.elapsed().as_secs_f32() };
+
+        terminal.draw(|frame| draw(frame, &mut dash, date_input.as_deref(), phase))?;
+        drawn += 1;
+        if dash.semantic.pending {
+            dash.semantic.run(dash.db.as_ref()).await;
+        }
+        next_frame(focused, Duration::from_millis(50)).await?;
+    }
+}
+
+/// One frame of the dashboard: the current view, any open picker or panel,
+/// the status line and the log viewer.
+fn draw(frame: &mut Frame, dash: &mut Dashboard, date_input: Option<&str>, phase: f32) {
+    let area = frame.area();
+    let [body, status] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(area);
+    let filter = &dash.filter;
+    let keys = match dash.view {
+        View::Stats => {
+            dash.panel.render(frame, body, phase);
+            let bookmarks = if dash.panel.bookmarks_only { "all" } else { "bookmarks" };
+            format!(
+                "refresh 5s  b {}  p/u/a/t filter  d dates  i about  q/esc quit",
+                bookmarks
+            )
+        }
+        View::Messages => {
+            render_log(frame, body, &dash.log);
+            if dash.log.detail.is_some() {
+                "up/down pgup/pgdn scroll  n/p next/previous message  a attachments  e edits  esc back  q quit"
+            } else if dash.log.open.is_some() {
+                "up/down browse  enter read  esc sessions  x export  p/u/a/t filter  d dates"
+            } else {
+                "up/down pick  enter open session  x export  p/u/a/t filter  A archived  d dates  q/esc quit"
+            }
+            .to_string()
+        }
+        View::Timeline => {
+            render_timeline(frame, body, &dash.timeline);
+            "up/down pgup/pgdn home/end browse  enter open in messages  p/u/a/t filter  d dates  q/esc quit"
+                .to_string()
+        }
+        View::Usage => {
+            let range = date_label(filter).unwrap_or_else(|| "all time".to_string());
+            dash.usage.report.render(frame, body, &range);
+            "r 7d/30d/90d/all  p filter  d dates  q/esc quit".to_string()
+        }
+        View::Topics => {
+            dash.topics.report.render(frame, body, &filter_label(filter));
+            "p/u/a/t filter  d dates  q/esc quit".to_string()
+        }
+        View::Compare => {
+            dash.compare.report.render(frame, body, &filter_label(filter));
+            "r reload  u/a/d filter  q/esc quit".to_string()
+        }
+        View::Streaks => {
+            dash.streaks.report.render(frame, body, &filter_label(filter));
+            "p/u/a/t filter  d dates  q/esc quit".to_string()
+        }
+        View::Health => {
+            let range = date_label(filter).unwrap_or_else(|| "all time".to_string());
+            dash.health.report.render(frame, body, &range);
+            "refresh 5s  r 7d/30d/90d/all  d dates  q/esc quit".to_string()
+        }
+        View::Duplicates => {
+            render_duplicates(frame, body, &dash.duplicates, &filter_label(filter));
+            "up/down pick  enter open in messages  m merge all  p/u/a/t filter  d dates  q/esc quit"
+                .to_string()
+        }
+        View::Search => {
+            render_search(frame, body, &dash.search);
+            "type to search  ctrl+r regex  up/down pick  enter open in messages  esc clear/quit"
+                .to_string()
+        }
+        View::Semantic => {
+            render_semantic(frame, body, &dash.semantic);
+            "type a question  enter search / open in messages  up/down pick  esc clear/quit"
+                .to_string()
+        }
+    };
+    let keys = match &dash.backups {
+        Some(picker) => {
+            let target = &dash.profiles[picker.target];
+            render_backups(frame, body, picker, target);
+            if picker.confirm {
+                format!("y restores over {}, any other key cancels", target.path.display())
+            } else {
+                "up/down pick  enter open  r restore  B back up now  esc close".to_string()
+            }
+        }
+        None => keys,
+    };
+    let keys = match &dash.about {
+        Some(stats) => {
+            render_about(frame, body, stats);
+            "esc close".to_string()
+        }
+        None => keys,
+    };
+    let line = match (date_input, &dash.notice) {
+        (Some(input), _) => Line::from(vec![
+            Span::styled(
+                " dates (2026-10-01..2026-10-07, 7d, today; empty clears): ",
+                t().label_style(),
+            ),
+            Span::styled(format!("{}_", input), t().text_style()),
+        ]),
+        (None, Some((notice, error))) => Line::from(Span::styled(
+            format!(" {}", notice),
+            Style::default().fg(if *error { t().danger } else { t().accent4 }),
+        )),
+        (None, None) => {
+            let busy = dash.contended.is_some_and(|at| at.elapsed() < CONTENTION_SHOWN);
+            let mut spans = Vec::new();
+            if busy {
+                spans.push(Span::styled(
+                    " db busy ",
+                    Style::default().fg(t().bg_base).bg(t().danger).bold(),
+                ));
+            }
+            spans.push(Span::raw(format!(
+                " {}  filter: {}  tab view  ? keys  {}",
+                profile_label(dash),
+                filter_label(filter),
+                keys
+            )));
+            Line::from(spans)
+        }
+    };
+    frame.render_widget(
+        Paragraph::new(line).style(t().muted_style().bg(t().bg_base)),
+        status,
+    );
+    if dash.help.open {
+        dash.help.render(frame, area);
+    }
+    if dash.logs.open {
+        dash.logs.render(frame, area);
+    }
+}
+
+/// The `R` picker, floating over the current view.
+fn render_backups(frame: &mut Frame, area: Rect, picker: &BackupPicker, target: &Profile) {
+    let popup = centered_area(area, 70, 60);
+    frame.render_widget(Clear, popup);
+    let title = format!(" BACKUPS // {} // {} ", target.name, picker.files.len());
+    frame.render_widget(t().block(title, t().accent4, t().accent3), popup);
+    let inner = popup.inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    if picker.files.is_empty() {
+        let dir = db::backup_dir(&target.path);
+        frame.render_widget(
+            Paragraph::new(format!("no backups in {} yet; B makes one", dir.display()))
+                .wrap(Wrap { trim: true })
+                .style(t().muted_style().bg(t().panel_bg))
+                .alignment(Alignment::Center),
+            inner,
+        );
+        return;
+    }
+
+    let rows = inner.height.max(1) as usize;
+    let first = picker.selected.saturating_sub(rows.saturating_sub(1));
+    let lines: Vec<Line> = picker
+        .files
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(rows)
+        .map(|(index, file)| {
+            let name_style = if index == picker.selected {
+                Style::default().fg(t().accent4).bold().reversed()
+            } else {
+                t().text_style()
+            };
+            let name = file.path.file_name().unwrap_or_default().to_string_lossy();
+            Line::from(vec![
+                Span::styled(format!("{} ", clock(file.modified)), t().muted_style()),
+                Span::styled(
+                    format!("{:>9}  ", human_bytes(file.bytes)),
+                    Style::default().fg(t().accent3),
+                ),
+                Span::styled(name.to_string(), name_style),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).style(t().panel_style()), inner);
+}
+
+/// The `i` panel, floating over the current view.
+fn render_about(frame: &mut Frame, area: Rect, stats: &DatabaseStats) {
+    let popup = centered_area(area, 70, 70);
+    frame.render_widget(Clear, popup);
+    frame.render_widget(t().block(" ABOUT THIS DATABASE ", t().accent4, t().accent3), popup);
+    let inner = popup.inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    let row = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{:<14}", label), t().label_style()),
+            Span::styled(value, t().text_style()),
+        ])
+    };
+    let path = stats.path.as_ref().map(|path| path.display().to_string());
+    let span = match (stats.oldest, stats.newest) {
+        (Some(oldest), Some(newest)) => format!("{} .. {}", clock(oldest), clock(newest)),
+        _ => "empty".to_string(),
+    };
+    let health = stats.health();
+    let health_style = if health == "ok" {
+        Style::default().fg(t().accent4)
+    } else {
+        Style::default().fg(t().danger)
+    };
+    let mut lines = vec![
+        row("file", path.unwrap_or_else(|| "in memory".to_string())),
+        row(
+            "size",
+            format!(
+                "{} (+{} wal, {} free)  schema v{}",
+                human_bytes(stats.bytes),
+                human_bytes(stats.wal_bytes),
+                human_bytes(stats.free_bytes),
+                stats.schema_version
+            ),
+        ),
+        row(
+            "holds",
+            format!(
+                "{} messages in {} conversations, {} bookmarked",
+                stats.messages, stats.conversations, stats.bookmarks
+            ),
+        ),
+        row(
+            "hidden",
+            format!(
+                "{} deleted messages, {} archived conversations",
+                stats.deleted, stats.archived
+            ),
+        ),
+        row("spans", span),
+        Line::from(vec![
+            Span::styled(format!("{:<14}", "health"), t().label_style()),
+            Span::styled(health, health_style),
+        ]),
+        Line::raw(""),
+        Line::from(Span::styled("by provider", t().label_style())),
+    ];
+    lines.extend(stats.providers.iter().map(|count| {
+        Line::from(vec![
+            Span::styled(format!("  {:<16}", count.provider), Style::default().fg(t().accent3)),
+            Span::styled(
+                format!("{:>7} msgs  {:>5} conversations", count.messages, count.conversations),
+                t().text_style(),
+            ),
+        ])
+    }));
+    lines.push(Line::raw(""));
+    lines.push(Line::from(Span::styled("largest conversations", t().label_style())));
+    lines.extend(stats.largest.iter().map(|count| {
+        let title = if count.title.is_empty() { "untitled session" } else { &count.title };
+        Line::from(vec![
+            Span::styled(format!("  {:>7} msgs  ", count.messages), t().text_style()),
+            Span::styled(format!("{:<12}", count.provider), Style::default().fg(t().accent3)),
+            Span::styled(text::clip(title, 48), t().muted_style()),
+        ])
+    }));
+    frame.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }).style(t().panel_style()),
+        inner,
+    );
+}
+
+/// The open database's path, prefixed with the profile name
+/// when there is more than one to switch between.
+fn profile_label(dash: &Dashboard) -> String {
+    match dash.profiles.get(dash.profile) {
+        Some(profile) if dash.profiles.len() > 1 => {
+            format!("[{}] {}  o db", profile.name, profile.path.display())
+        }
+        Some(profile) => profile.path.display().to_string(),
+        None => "no database".to_string(),
+    }
+}
+
+fn handle_log_key(
+    log: &mut MessageLog,
+    db: Option<&Database>,
+    key: KeyEvent,
+    filter: &MessageFilter,
+) {
+    if log.detail.is_some() {
+        match key.code {
+            KeyCode::Esc | KeyCode::Backspace | KeyCode::Enter => {
+                log.detail = None;
+                log.aside = None;
+            }
+            KeyCode::Char('a') if matches!(log.aside, Some(Aside::Attachments(_))) => {
+                log.aside = None;
+                log.detail = Some(0);
+            }
+            KeyCode::Char('e') if matches!(log.aside, Some(Aside::Revisions(_))) => {
+                log.aside = None;
+                log.detail = Some(0);
+            }
+            KeyCode::Char('a') => {
+                let row = log.current().filter(|row| !row.attachments.is_empty());
+                if let (Some(db), Some(row)) = (db, row) {
+                    log.aside = db.attachments(row.id).ok().map(Aside::Attachments);
+                    log.detail = Some(0);
+                }
+            }
+            KeyCode::Char('e') => {
+                let row = log.current().filter(|row| row.revisions > 0);
+                if let (Some(db), Some(row)) = (db, row) {
+                    log.aside = db.revisions(row.id).ok().map(Aside::Revisions);
+                    log.detail = Some(0);
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => log.scroll(-1),
+            KeyCode::Down | KeyCode::Char('j') => log.scroll(1),
+            KeyCode::PageUp => log.scroll(-10),
+            KeyCode::PageDown | KeyCode::Char(' ') => log.scroll(10),
+            KeyCode::Home => log.detail = Some(0),
+            KeyCode::End => log.scroll(i32::MAX / 2),
+            KeyCode::Char('n') | KeyCode::Char('p') => {
+                log.step(db, if key.code == KeyCode::Char('n') { 1 } else { -1 });
+                log.detail = Some(0);
+                log.aside = None;
+            }
+            _ => {}
+        }
+        return;
+    }
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => log.step(db, -1),
+        KeyCode::Down | KeyCode::Char('j') => log.step(db, 1),
+        KeyCode::PageUp => log.step(db, -10),
+        KeyCode::PageDown => log.step(db, 10),
+        KeyCode::Home => log.step(db, isize::MIN),
+        KeyCode::End => log.step(db, isize::MAX),
+        KeyCode::Enter if log.open.is_none() => {
+            if let Some(session) = log.sessions.get(log.session_selected).map(|s| s.id) {
+                log.open_session(db, session, None, filter);
+            }
+        }
+        KeyCode::Enter if log.current().is_some() => log.detail = Some(0),
+        KeyCode::Esc | KeyCode::Backspace => log.open = None,
+        _ => {}
+    }
+}
+
+fn filter_label(filter: &MessageFilter) -> String {
+    let mut parts = vec![
+        filter.provider.clone().unwrap_or_else(|| "all providers".to_string()),
+        filter.role.unwrap_or("all roles").to_string(),
+    ];
+    parts.extend(filter.tag.as_ref().map(|tag| format!("#{}", tag)));
+    parts.extend(date_label(filter));
+    if filter.hidden {
+        parts.push("+archived/deleted".to_string());
+    }
+    parts.join(" ")
+}
+
+/// `2026-10-01..2026-10-07`, with an open end left blank; `None` without a range.
+fn date_label(filter: &MessageFilter) -> Option<String> {
+    let day = |timestamp: i64| {
+        Local
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .map(|time| time.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    };
+    if filter.since.is_none() && filter.until.is_none() {
+        return None;
+    }
+    Some(format!(
+        "{}..{}",
+        filter.since.map(day).unwrap_or_default(),
+        // `until` is exclusive: show the last day it includes
+        filter.until.map(|until| day(until - 1)).unwrap_or_default()
+    ))
+}
+
+/// Parses the `d` prompt into `(since, until)` unix seconds: `7d` for the last
+/// seven days, `today`, one `YYYY-MM-DD` day, or a `from..to` range of days with
+/// either end left open. Empty input clears the range.
+fn parse_date_range(input: &str) -> Result<(Option<i64>, Option<i64>)> {
+    let input = input.trim();
+    let midnight = |date: NaiveDate| {
+        date.and_hms_opt(0, 0, 0)
+            .and_then(|time| Local.from_local_datetime(&time).earliest())
+            .map(|time| time.timestamp())
+            .ok_or_else(|| anyhow!("no local midnight on {}", date))
+    };
+    let parse_day = |text: &str| {
+        NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d")
+            .map_err(|_| anyhow!("expected YYYY-MM-DD, got `{}`", text.trim()))
+    };
+    let today = Local::now().date_naive();
+
+    if input.is_empty() {
+        return Ok((None, None));
+    }
+    if input == "today" {
+        return Ok((Some(midnight(today)?), None));
+    }
+    if let Some(days) = input.strip_suffix('d').and_then(|days| days.parse::<i64>().ok()) {
+        let since = today - Days::days(days.max(1) - 1);
+        return Ok((Some(midnight(since)?), None));
+    }
+    let (from, to) = match input.split_once("..") {
+        Some((from, to)) => (from.trim(), to.trim()),
+        None => (input, input),
+    };
+    let since = match from {
+        "" => None,
+        from => Some(midnight(parse_day(from)?)?),
+    };
+    let until = match to {
+        "" => None,
+        to => Some(midnight(parse_day(to)? + Days::days(1))?),
+    };
+    Ok((since, until))
+}
+
+/// Applies a Search query's operators on top of `filter` and returns the text
+/// left to match. `role:user|assistant` and `provider:<key>` replace the
+/// dashboard's filter; `after:` and `before:` take a `parse_date_range` day
+/// (`after:` starts the day after it, `before:` stops at its start).
+fn parse_search(query: &str, filter: &MessageFilter) -> Result<(String, MessageFilter)> {
+    let mut filter = filter.clone();
+    let mut text = Vec::new();
+    for token in query.split(' ') {
+        let Some((key, value)) = token.split_once(':') else {
+            text.push(token);
+            continue;
+        };
+        match key {
+            "role" => {
+                filter.role = Some(match value {
+                    "user" => "user",
+                    "assistant" | "ai" => "assistant",
+                    _ => return Err(anyhow!("role: takes user or assistant, not `{}`", value)),
+                });
+            }
+            "provider" if value.is_empty() => {
+                return Err(anyhow!("provider: needs a provider, e.g. provider:claude"));
+            }
+            "provider" => filter.provider = Some(value.to_lowercase()),
+            "tag" if value.is_empty() => return Err(anyhow!("tag: needs a tag, e.g. tag:work")),
+            "tag" => filter.tag = Some(value.trim_start_matches('#').to_lowercase()),
+            "before" | "after" => {
+                let (since, until) = parse_date_range(value)
+                    .ok()
+                    .filter(|_| !value.is_empty())
+                    .ok_or_else(|| anyhow!("{}: takes YYYY-MM-DD, 7d or today", key))?;
+                if key == "before" {
+                    filter.until = since;
+                } else {
+                    filter.since = until.or(since);
+                }
+            }
+            _ => text.push(token),
+        }
+    }
+    Ok((text.join(" ").trim().to_string(), filter))
+}
+
+/// The last line of a regex error: the reason, without the pattern echo.
+fn regex_error(error: &regex::Error) -> String {
+    let message = error.to_string();
+    let reason = message.lines().last().unwrap_or_default().trim();
+    reason.strip_prefix("error: ").unwrap_or(reason).to_string()
+}
+
+fn transcript_entry(row: &LoggedMessage) -> Entry<'_> {
+    let (kind, speaker) = match (row.role.as_str(), row.kind.as_str()) {
+        (_, "shell") => (EntryKind::Shell, "Shell".to_string()),
+        (_, "error") => (EntryKind::Note, format!("{} error", row.provider)),
+        ("assistant", _) => (EntryKind::Reply, row.provider.clone()),
+        _ => (EntryKind::Prompt, "You".to_string()),
+    };
+    Entry {
+        kind,
+        speaker,
+        model: row.model.clone(),
+        timestamp: Local
+            .timestamp_opt(row.timestamp, 0)
+            .single()
+            .unwrap_or_else(Local::now),
+        content: &row.content,
+    }
+}
+
+fn clock(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// `Fix the borrow checker error // 2026-10-17 13:55`
+fn session_heading(session: &ConversationSummary) -> String {
+    let title = if session.title.is_empty() { "untitled session" } else { &session.title };
+    format!("{} // {}", title, clock(session.started))
+}
+
+/// `1h 05m`, `12m`, `<1m`
+fn span_label(seconds: i64) -> String {
+    match seconds / 60 {
+        0 => "<1m".to_string(),
+        minutes if minutes < 60 => format!("{}m", minutes),
+        minutes => format!("{}h {:02}m", minutes / 60, minutes % 60),
+    }
+}
+
+fn render_sessions(frame: &mut Frame, area: Rect, log: &MessageLog) {
+    // the selected session's `/summarize` summary, when it has one
+    let summary = log.sessions.get(log.session_selected).map(|session| session.summary.as_str());
+    let (area, summary) = match summary.filter(|summary| !summary.is_empty()) {
+        Some(summary) => {
+            let [list_area, summary_area] =
+                Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)])
+                    .areas(area);
+            (list_area, Some((summary_area, summary)))
+        }
+        None => (area, None),
+    };
+    let title = format!(
+        " SESSIONS // {}/{} ",
+        (log.session_selected + 1).min(log.sessions.len()),
+        log.sessions.len()
+    );
+    frame.render_widget(t().block(title, t().accent2, t().accent1), area);
+    let inner = area.inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    if log.sessions.is_empty() {
+        frame.render_widget(
+            Paragraph::new("no archived sessions")
+                .style(t().muted_style().bg(t().panel_bg))
+                .alignment(Alignment::Center),
+            inner,
+        );
+        return;
+    }
+
+    let rows = inner.height.max(1) as usize;
+    let first = log
+        .session_selected
+        .saturating_sub(rows / 2)
+        .min(log.sessions.len().saturating_sub(rows));
+    let lines: Vec<Line> = log
+        .sessions
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(rows)
+        .map(|(index, session)| {
+            let title_style = if index == log.session_selected {
+                Style::default().fg(t().accent4).bold().reversed()
+            } else {
+                t().text_style()
+            };
+            let title = if session.title.is_empty() { "untitled session" } else { &session.title };
+            Line::from(vec![
+                Span::styled(format!("{} ", clock(session.started)), t().muted_style()),
+                Span::styled(
+                    format!(
+                        "{:>5} msgs {:>7}  ",
+                        session.messages,
+                        span_label(session.ended - session.started)
+                    ),
+                    Style::default().fg(t().accent3),
+                ),
+                Span::styled(
+                    format!("{} ", text::fit(&session.providers, 18)),
+                    t().muted_style(),
+                ),
+                Span::styled(title.to_string(), title_style),
+                Span::styled(
+                    if session.archived { "  [archived]" } else { "" },
+                    t().muted_style(),
+                ),
+                Span::styled(
+                    session
+                        .tags
+                        .split(',')
+                        .filter(|tag| !tag.is_empty())
+                        .map(|tag| format!("  #{}", tag))
+                        .collect::<String>(),
+                    Style::default().fg(t().accent1),
+                ),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).style(t().panel_style()), inner);
+
+    if let Some((area, summary)) = summary {
+        frame.render_widget(t().block(" SUMMARY ", t().accent4, t().accent3), area);
+        frame.render_widget(
+            Paragraph::new(summary)
+                .wrap(Wrap { trim: false })
+                .style(t().text_style().bg(t().panel_bg)),
+            area.inner(Margin {
+                horizontal: 1,
+                vertical: 1,
+            }),
+        );
+    }
+}
+
+fn render_log(frame: &mut Frame, area: Rect, log: &MessageLog) {
+    let Some(session) = &log.open else {
+        render_sessions(frame, area, log);
+        return;
+    };
+    if let (Some(scroll), Some(row)) = (log.detail, log.current()) {
+        render_detail(frame, area, log, row, scroll);
+        return;
+    }
+    let [list_area, detail_area] =
+        Layout::vertical([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(area);
+    let title = format!(
+        " {} // {}/{} ",
+        session_heading(session),
+        log.selected + 1,
+        log.total()
+    );
+    frame.render_widget(t().block(title, t().accent2, t().accent1), list_area);
+    let inner = list_area.inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    let Some(current) = log.current() else {
+        frame.render_widget(
+            Paragraph::new("no archived messages")
+                .style(t().muted_style().bg(t().panel_bg))
+                .alignment(Alignment::Center),
+            inner,
+        );
+        return;
+    };
+
+    let rows = inner.height.max(1) as usize;
+    let first = log.selected.saturating_sub(rows / 2).min(log.total().saturating_sub(rows));
+    let width = inner.width.saturating_sub(36) as usize;
+    let lines: Vec<Line> = (first..first + rows)
+        .filter_map(|position| log.row(position).map(|row| (position, row)))
+        .map(|(position, row)| {
+            let selected = position == log.selected;
+            let edited = if row.revisions > 0 { " (edited)" } else { "" };
+            let preview = notify::preview(&row.content, width.saturating_sub(edited.len()).max(8));
+            let text_style = if selected {
+                Style::default().fg(t().accent4).bold().reversed()
+            } else if row.deleted {
+                t().muted_style().crossed_out()
+            } else {
+                t().text_style()
+            };
+            Line::from(vec![
+                Span::styled(format!("{} ", clock(row.timestamp)), t().muted_style()),
+                Span::styled(
+                    format!("{} {} ", text::fit(&row.provider, 9), text::fit(&row.role, 5)),
+                    Style::default().fg(t().accent3),
+                ),
+                // marks prompts that carried attachments
+                Span::styled(
+                    if row.attachments.is_empty() { "  " } else { "+ " },
+                    Style::default().fg(t().accent1),
+                ),
+                Span::styled(preview, text_style),
+                Span::styled(edited, t().muted_style().italic()),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).style(t().panel_style()), inner);
+
+    let row = current;
+    let title = format!(" #{} // {} // {} {} ", row.id, source(row), row.role, row.kind);
+    frame.render_widget(t().block(title, t().accent4, t().accent3), detail_area);
+    frame.render_widget(
+        Paragraph::new(row.content.as_str())
+            .wrap(Wrap { trim: false })
+            .style(t().text_style().bg(t().panel_bg)),
+        detail_area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        }),
+    );
+}
+
+/// The whole archive as one stream, a heading above each day's first message
+/// and every row labelled with the provider that answered it.
+fn render_timeline(frame: &mut Frame, area: Rect, timeline: &Timeline) {
+    let [list_area, detail_area] =
+        Layout::vertical([Constraint::Percentage(70), Constraint::Percentage(30)]).areas(area);
+    let title = format!(
+        " timeline // {}/{} ",
+        (timeline.selected + 1).min(timeline.total),
+        timeline.total
+    );
+    frame.render_widget(t().block(title, t().accent2, t().accent1), list_area);
+    let inner = list_area.inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    let Some(current) = timeline.row(timeline.selected) else {
+        frame.render_widget(
+            Paragraph::new("no archived messages")
+                .style(t().muted_style().bg(t().panel_bg))
+                .alignment(Alignment::Center),
+            inner,
+        );
+        return;
+    };
+
+    let rows = inner.height.max(1) as usize;
+    let first = timeline.selected.saturating_sub(rows / 2);
+    let width = inner.width.saturating_sub(22) as usize;
+    let mut lines = Vec::new();
+    let mut selected_line = 0;
+    let mut last_day = None;
+    for position in first..first + rows {
+        let Some(row) = timeline.row(position) else {
+            break;
+        };
+        let day = day_heading(row.timestamp);
+        if last_day.as_ref() != Some(&day) {
+            lines.push(Line::from(Span::styled(
+                format!("── {} ──", day),
+                t().label_style(),
+            )));
+            last_day = Some(day);
+        }
+        let selected = position == timeline.selected;
+        if selected {
+            selected_line = lines.len();
+        }
+        let text_style = if selected {
+            Style::default().fg(t().accent4).bold().reversed()
+        } else if row.deleted {
+            t().muted_style().crossed_out()
+        } else {
+            t().text_style()
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{} ", time_of_day(row.timestamp)), t().muted_style()),
+            Span::styled(
+                format!("{} ", text::fit(&row.provider, 9)),
+                Style::default().fg(provider_color(&row.provider)),
+            ),
+            Span::styled(format!("{} ", text::fit(&row.role, 5)), t().muted_style()),
+            Span::styled(notify::preview(&row.content, width.max(8)), text_style),
+        ]));
+    }
+    // day headings can push the selection past the bottom edge
+    let skip = (selected_line + 1).saturating_sub(rows);
+    frame.render_widget(
+        Paragraph::new(lines.split_off(skip)).style(t().panel_style()),
+        inner,
+    );
+
+    let row = current;
+    let title = format!(
+        " #{} // {} // {} {} // {} ",
+        row.id,
+        source(row),
+        row.role,
+        row.kind,
+        clock(row.timestamp)
+    );
+    frame.render_widget(t().block(title, t().accent4, t().accent3), detail_area);
+    frame.render_widget(
+        Paragraph::new(row.content.as_str())
+            .wrap(Wrap { trim: false })
+            .style(t().text_style().bg(t().panel_bg)),
+        detail_area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        }),
+    );
+}
+
+/// `gpt (gpt-5-nano)`: who wrote a message, with the model when recorded.
+fn source(row: &LoggedMessage) -> String {
+    match &row.model {
+        Some(model) => format!("{} ({})", row.provider, model),
+        None => row.provider.clone(),
+    }
+}
+
+/// The theme color of a built-in provider, by the name messages are stored
+/// under; custom providers share one accent.
+fn provider_color(provider: &str) -> Color {
+    let colors = t().providers;
+    match provider {
+        "claude" => colors.claude,
+        "grok" => colors.grok,
+        "gpt" => colors.openai,
+        "gemini" => colors.gemini,
+        "ollama" => colors.ollama,
+        "openrouter" => colors.openrouter,
+        _ => t().accent3,
+    }
+}
+
+/// `Saturday 2026-10-17`
+fn day_heading(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%A %Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// `13:55`
+fn time_of_day(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// The selected message full-screen, wrapped and lightly formatted.
+fn render_detail(
+    frame: &mut Frame,
+    area: Rect,
+    log: &MessageLog,
+    row: &LoggedMessage,
+    scroll: u16,
+) {
+    let edited = if row.revisions > 0 { " (edited)" } else { "" };
+    let title = format!(
+        " {}/{} // {} // {} // {}{} ",
+        log.selected + 1,
+        log.total(),
+        source(row),
+        row.role,
+        clock(row.timestamp),
+        edited
+    );
+    let mut footer = format!(" {} chars ", row.content.chars().count());
+    match &log.aside {
+        Some(Aside::Attachments(_)) => footer = " a back to message ".to_string(),
+        Some(Aside::Revisions(_)) => footer = " e back to message ".to_string(),
+        None => {
+            if !row.attachments.is_empty() {
+                footer.push_str(&format!("// a attachments: {} ", row.attachments));
+            }
+            if row.revisions > 0 {
+                footer.push_str(&format!("// e {} earlier versions ", row.revisions));
+            }
+        }
+    }
+    let block = t().block(title, t().accent4, t().accent3).title_bottom(footer);
+    let inner = block.inner(area).inner(Margin {
+        horizontal: 1,
+        vertical: 0,
+    });
+    frame.render_widget(block, area);
+
+    let lines = match &log.aside {
+        Some(Aside::Attachments(files)) => attachment_lines(files),
+        Some(Aside::Revisions(revisions)) => revision_lines(revisions),
+        None => markdown_lines(&row.content),
+    };
+    let widget = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .style(t().text_style().bg(t().panel_bg));
+    let max = widget.line_count(inner.width).saturating_sub(inner.height as usize);
+    let max = max.min(u16::MAX as usize) as u16;
+    log.detail_max.set(max);
+    frame.render_widget(widget.scroll((scroll.min(max), 0)), inner);
+}
+
+/// Each attachment under a name // type // size header.
+fn attachment_lines(files: &[StoredAttachment]) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    for file in files {
+        let header = format!(
+            "\u{2500}\u{2500} {} // {} // {}",
+            file.filename,
+            file.mime,
+            human_bytes(file.data.len() as u64)
+        );
+        lines.push(Line::from(Span::styled(header, t().label_style())));
+        for line in String::from_utf8_lossy(&file.data).lines() {
+            lines.push(Line::from(Span::styled(line.to_string(), t().text_style())));
+        }
+        lines.push(Line::default());
+    }
+    lines
+}
+
+/// Earlier versions of an edited prompt, newest first, each under the time it
+/// was sent.
+fn revision_lines(revisions: &[Revision]) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    for (number, revision) in revisions.iter().enumerate().rev() {
+        let header = format!(
+            "\u{2500}\u{2500} version {} // {}",
+            number + 1,
+            clock(revision.timestamp)
+        );
+        lines.push(Line::from(Span::styled(header, t().label_style())));
+        lines.extend(markdown_lines(&revision.content));
+        lines.push(Line::default());
+    }
+    lines
+}
+
+/// Headings, bullets, quotes, fenced code and inline `code`/**bold**, styled
+/// with the theme.
+fn markdown_lines(content: &str) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            lines.push(Line::from(Span::styled(line.to_string(), t().muted_style())));
+        } else if in_code {
+            lines.push(Line::from(vec![
+                Span::styled("\u{2502} ", t().muted_style()),
+                Span::styled(line.to_string(), Style::default().fg(t().accent3)),
+            ]));
+        } else if trimmed.starts_with('#') {
+            let style = Style::default().fg(t().accent4).bold();
+            lines.push(Line::from(Span::styled(line.to_string(), style)));
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            lines.push(Line::from(vec![
+                Span::styled("\u{2503} ", Style::default().fg(t().accent2)),
+                Span::styled(quote.trim_start().to_string(), t().muted_style().italic()),
+            ]));
+        } else if let Some(item) = trimmed.strip_prefix("- ").or(trimmed.strip_prefix("* ")) {
+            let indent = line.len() - trimmed.len();
+            let mut spans = vec![Span::styled(
+                format!("{}\u{2022} ", " ".repeat(indent)),
+                Style::default().fg(t().accent1),
+            )];
+            spans.extend(inline_spans(item));
+            lines.push(Line::from(spans));
+        } else {
+            lines.push(Line::from(inline_spans(line)));
+        }
+    }
+    lines
+}
+
+/// Splits `text` on backticks and `**`, styling code and bold runs.
+fn inline_spans(text: &str) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let parts: Vec<&str> = text.split('`').collect();
+    for (index, part) in parts.iter().enumerate() {
+        // odd parts sit between a pair of backticks, unless the last one is unclosed
+        if index % 2 == 1 && index + 1 < parts.len() {
+            spans.push(Span::styled(format!("`{}`", part), Style::default().fg(t().accent3)));
+            continue;
+        }
+        if index % 2 == 1 {
+            spans.push(Span::styled(format!("`{}", part), t().text_style()));
+            continue;
+        }
+        for (index, part) in part.split("**").enumerate() {
+            if part.is_empty() {
+                continue;
+            }
+            let style = if index % 2 == 1 { t().text_style().bold() } else { t().text_style() };
+            spans.push(Span::styled(part.to_string(), style));
+        }
+    }
+    spans
+}
+
+/// Messages archived more than once, with how many extra copies each has;
+/// the selected one's text is shown below.
+fn render_duplicates(frame: &mut Frame, area: Rect, duplicates: &Duplicates, filter: &str) {
+    let [list_area, detail_area] =
+        Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(area);
+    let copies: usize = duplicates.report.iter().map(|duplicate| duplicate.copies.len()).sum();
+    let title = format!(
+        " duplicates // {} extra copies of {} messages // {} ",
+        copies,
+        duplicates.report.len(),
+        filter
+    );
+    frame.render_widget(t().block(title, t().accent2, t().accent1), list_area);
+    let inner = list_area.inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    let Some(current) = duplicates.report.get(duplicates.selected) else {
+        frame.render_widget(
+            Paragraph::new("no duplicate messages")
+                .style(t().muted_style().bg(t().panel_bg))
+                .alignment(Alignment::Center),
+            inner,
+        );
+        return;
+    };
+
+    let rows = inner.height.max(1) as usize;
+    let first = duplicates
+        .selected
+        .saturating_sub(rows / 2)
+        .min(duplicates.report.len().saturating_sub(rows));
+    let width = inner.width.saturating_sub(40) as usize;
+    let lines: Vec<Line> = duplicates
+        .report
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(rows)
+        .map(|(index, duplicate)| {
+            let row = &duplicate.original;
+            let text_style = if index == duplicates.selected {
+                Style::default().fg(t().accent4).bold().reversed()
+            } else {
+                t().text_style()
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!("+{:<3} ", duplicate.copies.len()),
+                    Style::default().fg(t().danger),
+                ),
+                Span::styled(format!("{} ", clock(row.timestamp)), t().muted_style()),
+                Span::styled(
+                    format!("{} {} ", text::fit(&row.provider, 9), text::fit(&row.role, 5)),
+                    Style::default().fg(t().accent3),
+                ),
+                Span::styled(notify::preview(&row.content, width.max(8)), text_style),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).style(t().panel_style()), inner);
+
+    let row = &current.original;
+    let ids: Vec<String> = current.copies.iter().map(|id| format!("#{}", id)).collect();
+    let title = format!(" #{} // copies {} ", row.id, ids.join(" "));
+    frame.render_widget(t().block(title, t().accent4, t().accent3), detail_area);
+    frame.render_widget(
+        Paragraph::new(row.content.as_str())
+            .wrap(Wrap { trim: false })
+            .style(t().text_style().bg(t().panel_bg)),
+        detail_area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        }),
+    );
+}
+
+fn render_search(frame: &mut Frame, area: Rect, search: &Search) {
+    let [input_area, results_area] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(area);
+    let title = if search.regex { " SEARCH // REGEX " } else { " SEARCH " };
+    frame.render_widget(t().block(title, t().accent2, t().accent1), input_area);
+    let mut input = vec![
+        Span::styled("> ", t().label_style()),
+        Span::styled(search.query.as_str(), t().text_style()),
+        Span::styled("_", Style::default().fg(t().accent4)),
+    ];
+    if let Some(error) = &search.error {
+        input.push(Span::styled(format!("   {}", error), Style::default().fg(t().danger)));
+    }
+    frame.render_widget(
+        Paragraph::new(Line::from(input))
+        .style(t().panel_style()),
+        input_area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        }),
+    );
+
+    let title = format!(" MATCHES // {} ", search.hits.len());
+    frame.render_widget(t().block(title, t().accent2, t().accent1), results_area);
+    let inner = results_area.inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    let note = match &search.error {
+        Some(_) => Some("nothing searched until the query above is fixed".to_string()),
+        None if search.query.trim().is_empty() => Some(
+            "type to search every provider's messages; role: provider: before: after: narrow it"
+                .to_string(),
+        ),
+        None if search.hits.is_empty() => Some("no matches".to_string()),
+        None => None,
+    };
+    if let Some(note) = note {
+        frame.render_widget(
+            Paragraph::new(note)
+                .style(t().muted_style().bg(t().panel_bg))
+                .alignment(Alignment::Center),
+            inner,
+        );
+        return;
+    }
+
+    // two lines per hit: header, then the highlighted snippet
+    let visible = (inner.height as usize / 2).max(1);
+    let first = search.selected.saturating_sub(visible - 1);
+    let mut lines = Vec::new();
+    for (index, hit) in search.hits.iter().enumerate().skip(first).take(visible) {
+        let marker = if index == search.selected { "> " } else { "  " };
+        lines.push(Line::from(vec![
+            Span::styled(marker, Style::default().fg(t().accent4).bold()),
+            Span::styled(format!("{} ", clock(hit.timestamp)), t().muted_style()),
+            Span::styled(
+                format!("{} // {}", hit.provider, hit.role),
+                Style::default().fg(t().accent3),
+            ),
+        ]));
+        let mut spans = vec![Span::raw("    ")];
+        spans.extend(highlight(&hit.snippet, index == search.selected));
+        lines.push(Line::from(spans));
+    }
+    frame.render_widget(Paragraph::new(lines).style(t().panel_style()), inner);
+}
+
+fn render_semantic(frame: &mut Frame, area: Rect, semantic: &Semantic) {
+    let [input_area, results_area] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(area);
+    let title = match &semantic.embedder {
+        Some(embedder) => format!(" SEMANTIC SEARCH // {} ", embedder.model),
+        None => " SEMANTIC SEARCH ".to_string(),
+    };
+    frame.render_widget(t().block(title, t().accent2, t().accent1), input_area);
+    let mut input = vec![
+        Span::styled("> ", t().label_style()),
+        Span::styled(semantic.query.as_str(), t().text_style()),
+        Span::styled("_", Style::default().fg(t().accent4)),
+    ];
+    if let Some(error) = &semantic.error {
+        input.push(Span::styled(format!("   {}", error), Style::default().fg(t().danger)));
+    }
+    frame.render_widget(
+        Paragraph::new(Line::from(input)).style(t().panel_style()),
+        input_area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        }),
+    );
+
+    let title = format!(" SIMILAR EXCHANGES // {} ", semantic.found.len());
+    frame.render_widget(t().block(title, t().accent2, t().accent1), results_area);
+    let inner = results_area.inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    let note = if semantic.pending {
+        Some("embedding the question ...")
+    } else if semantic.searched.is_empty() || semantic.query != semantic.searched {
+        Some("ask in your own words and press enter; past exchanges are ranked by meaning, not keywords")
+    } else if semantic.found.is_empty() {
+        Some("nothing similar // `asciivision index-history` embeds new messages")
+    } else {
+        None
+    };
+    if let Some(note) = note {
+        frame.render_widget(
+            Paragraph::new(note)
+                .style(t().muted_style().bg(t().panel_bg))
+                .alignment(Alignment::Center),
+            inner,
+        );
+        return;
+    }
+
+    // a header line, then a line per message of the exchange
+    let width = inner.width.saturating_sub(10) as usize;
+    let mut lines = Vec::new();
+    let mut selected_line = 0;
+    for (index, found) in semantic.found.iter().enumerate() {
+        let Some(first) = found.messages.first() else {
+            continue;
+        };
+        let selected = index == semantic.selected;
+        if selected {
+            selected_line = lines.len() + found.messages.len();
+        }
+        let marker = if selected { "> " } else { "  " };
+        lines.push(Line::from(vec![
+            Span::styled(marker, Style::default().fg(t().accent4).bold()),
+            Span::styled(format!("{} ", clock(first.timestamp)), t().muted_style()),
+            Span::styled(
+                format!("{} ", first.provider),
+                Style::default().fg(provider_color(&first.provider)),
+            ),
+            Span::styled(format!("{:.2}", found.score), Style::default().fg(t().accent3)),
+        ]));
+        for message in &found.messages {
+            let text_style = if selected { t().text_style().bold() } else { t().text_style() };
+            lines.push(Line::from(vec![
+                Span::styled(format!("    {} ", text::fit(&message.role, 9)), t().muted_style()),
+                Span::styled(notify::preview(&message.content, width.max(8)), text_style),
+            ]));
+        }
+    }
+    let skip = (selected_line + 1).saturating_sub(inner.height as usize);
+    frame.render_widget(
+        Paragraph::new(lines.split_off(skip)).style(t().panel_style()),
+        inner,
+    );
+}
+
+/// Splits a snippet on its match markers, styling the matched terms.
+fn highlight(snippet: &str, selected: bool) -> Vec<Span<'static>> {
+    let flat = notify::preview(snippet, usize::MAX);
+    let text_style = if selected {
+        t().text_style().bold()
+    } else {
+        t().text_style()
+    };
+    let mut spans = Vec::new();
+    let mut matched = false;
+    for part in flat.split([MATCH_START, MATCH_END]) {
+        if !part.is_empty() {
+            let style = if matched {
+                Style::default().fg(t().bg_base).bg(t().accent4).bold()
+            } else {
+                text_style
+            };
+            spans.push(Span::styled(part.to_string(), style));
+        }
+        matched = !matched;
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Archive;
+    use crate::metrics::Metric;
+    use asciivision_core::testing::buffer_text;
+    use insta::assert_snapshot;
+    use ratatui::backend::TestBackend;
+    use serde_json::json;
+
+    /// Unix seconds for a fixed local time, so day headings and clock times
+    /// come out the same in every time zone.
+    fn at(day: u32, hour: u32, minute: u32) -> i64 {
+        Local
+            .with_ymd_and_hms(2026, 3, day, hour, minute, 0)
+            .single()
+            .expect("unambiguous fixture time")
+            .timestamp()
+    }
+
+    fn message(provider: &str, role: &str, content: &str, timestamp: i64) -> serde_json::Value {
+        let mut message = json!({
+            "provider": provider,
+            "role": role,
+            "kind": "chat",
+            "content": content,
+            "timestamp": timestamp,
+        });
+        if role == "assistant" {
+            message["model"] = json!(format!("{}-model", provider));
+            message["input_tokens"] = json!(content.len() * 3);
+            message["output_tokens"] = json!(content.len());
+            message["latency_ms"] = json!(1200);
+        }
+        message
+    }
+
+    fn conversation(
+        provider: &str,
+        title: &str,
+        messages: &[(&str, &str, i64)],
+    ) -> serde_json::Value {
+        let messages: Vec<_> = messages
+            .iter()
+            .map(|(role, content, timestamp)| message(provider, role, content, *timestamp))
+            .collect();
+        json!({
+            "provider": provider,
+            "title": title,
+            "created_at": messages[0]["timestamp"],
+            "messages": messages,
+        })
+    }
+
+    /// A dashboard over a throwaway archive, removed when dropped.
+    struct Fixture {
+        dash: Dashboard,
+        path: PathBuf,
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            self.dash.db = None;
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.path.display(), suffix));
+            }
+        }
+    }
+
+    /// Two days of Claude and GPT sessions with one prompt sent twice, the
+    /// GPT one summarized. The status line names `fixture.db` rather than the
+    /// temporary path.
+    fn fixture(name: &str) -> Fixture {
+        let path = std::env::temp_dir()
+            .join(format!("asciivision-{}-{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let prompt = "does my terminal do 24-bit color?";
+        let mut colors = conversation(
+            "claude",
+            "terminal colors",
+            &[
+                ("user", prompt, at(13, 9, 30)),
+                ("user", prompt, at(13, 9, 30)),
+                ("assistant", "check COLORTERM for truecolor", at(13, 9, 31)),
+                ("user", "and inside tmux?", at(13, 9, 40)),
+                ("assistant", "set the Tc override in tmux.conf", at(13, 9, 41)),
+            ],
+        );
+        colors["tags"] = json!(["work"]);
+        let mut backups = conversation(
+            "gpt",
+            "sqlite backups",
+            &[
+                ("user", "is VACUUM INTO a safe backup?", at(14, 21, 5)),
+                ("assistant", "yes, it reads a snapshot", at(14, 21, 6)),
+            ],
+        );
+        backups["summary"] = json!("- VACUUM INTO copies a consistent snapshot\n- safe while writing");
+        let archive: Archive = serde_json::from_value(json!({
+            "format": "asciivision-archive",
+            "version": 1,
+            "exported_at": at(15, 12, 0),
+            "conversations": [colors, backups],
+        }))
+        .expect("fixture archive");
+        Database::open(path.clone())
+            .and_then(|db| db.import_archive(&archive, false))
+            .expect("fixture database");
+        let mut dash = Dashboard::new(&Config::default(), Some(path.clone())).expect("dashboard");
+        dash.profiles = vec![Profile {
+            name: "fixture".to_string(),
+            path: PathBuf::from("fixture.db"),
+            backup_of: None,
+        }];
+        dash.profile = 0;
+        Fixture { dash, path }
+    }
+
+    fn draw_view(dash: &mut Dashboard, view: View) -> Buffer {
+        dash.view = view;
+        dash.refresh();
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).expect("test terminal");
+        terminal
+            .draw(|frame| draw(frame, dash, None, 0.0))
+            .expect("draw");
+        terminal.backend().buffer().clone()
+    }
+
+    #[test]
+    fn messages_view() {
+        let mut fixture = fixture("messages");
+        let buffer = draw_view(&mut fixture.dash, View::Messages);
+        assert_snapshot!("dashboard_messages", buffer_text(&buffer));
+    }
+
+    #[test]
+    fn timeline_view() {
+        let mut fixture = fixture("timeline");
+        let buffer = draw_view(&mut fixture.dash, View::Timeline);
+        assert_snapshot!("dashboard_timeline", buffer_text(&buffer));
+    }
+
+    #[test]
+    fn topics_view() {
+        let mut fixture = fixture("topics");
+        let buffer = draw_view(&mut fixture.dash, View::Topics);
+        assert_snapshot!("dashboard_topics", buffer_text(&buffer));
+    }
+
+    #[test]
+    fn compare_view() {
+        let mut fixture = fixture("compare");
+        let buffer = draw_view(&mut fixture.dash, View::Compare);
+        assert_snapshot!("dashboard_compare", buffer_text(&buffer));
+    }
+
+    #[test]
+    fn duplicates_view() {
+        let mut fixture = fixture("duplicates");
+        let buffer = draw_view(&mut fixture.dash, View::Duplicates);
+        assert_snapshot!("dashboard_duplicates", buffer_text(&buffer));
+    }
+
+    #[test]
+    fn semantic_view() {
+        let mut fixture = fixture("semantic");
+        let model = fixture.dash.semantic.embedder.as_ref().expect("embedder").model.clone();
+        // the backups exchange points along the query, the tmux one half way
+        let vectors = vec![
+            (1, vec![0.0, 0.0, 1.0]),
+            (4, vec![0.5, 1.0, 0.0]),
+            (5, vec![0.4, 1.0, 0.1]),
+            (6, vec![1.0, 0.0, 0.0]),
+            (7, vec![0.9, 0.2, 0.0]),
+        ];
+        Database::open(fixture.path.clone())
+            .and_then(|db| db.save_message_embeddings(&model, &vectors))
+            .expect("fixture embeddings");
+        let semantic = &mut fixture.dash.semantic;
+        semantic.query = "keeping a database safe".to_string();
+        semantic.searched = semantic.query.clone();
+        semantic.rank(fixture.dash.db.as_ref(), &[1.0, 0.1, 0.0]);
+        let buffer = draw_view(&mut fixture.dash, View::Semantic);
+        assert_snapshot!("dashboard_semantic", buffer_text(&buffer));
+    }
+
+    #[test]
+    fn health_view() {
+        let mut fixture = fixture("health");
+        let video = |fps| Metric::Video {
+            source: "loop.mp4".to_string(),
+            fps: Some(fps),
+        };
+        let error = || Metric::ProviderError {
+            provider: "gpt".to_string(),
+        };
+        Database::open(fixture.path.clone())
+            .and_then(|db| {
+                db.record_metrics(&[
+                    (Metric::Session { plain: false }, at(13, 9, 0)),
+                    (video(24.0), at(13, 9, 50)),
+                    (Metric::Session { plain: true }, at(14, 21, 0)),
+                    (video(20.0), at(14, 21, 30)),
+                    (error(), at(14, 21, 4)),
+                    (error(), at(14, 21, 5)),
+                ])
+            })
+            .expect("fixture metrics");
+        let buffer = draw_view(&mut fixture.dash, View::Health);
+        assert_snapshot!("dashboard_health", buffer_text(&buffer));
+    }
+
+    #[test]
+    fn help_overlay() {
+        let mut fixture = fixture("help");
+        fixture.dash.help.toggle();
+        for c in "filter".chars() {
+            fixture.dash.help.handle_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+        let buffer = draw_view(&mut fixture.dash, View::Stats);
+        assert_snapshot!("dashboard_help", buffer_text(&buffer));
+    }
+}
@@ -1,35 +1,575 @@
-use anyhow::{Context, Result};
-use rusqlite::{params, Connection};
-use std::path::PathBuf;
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use rusqlite::backup::Backup;
+use rusqlite::types::Value;
+use rusqlite::{
+    params, Connection, OpenFlags, OptionalExtension, ToSql, Transaction, TransactionBehavior,
+};
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::ai::Usage;
+use crate::attach::Attachment;
+use crate::keys::KeyUse;
+use crate::metrics::Metric;
+use crate::paths;
 
 pub struct Database {
     conn: Connection,
+    /// `messages_fts` is available; searches fall back to `LIKE` otherwise.
+    fts: bool,
+    /// Conversation new messages are saved under, created with the first of
+    /// them.
+    conversation: Cell<Option<i64>>,
+    /// Backup taken before this open migrated the file to a newer schema.
+    migration_backup: Option<PathBuf>,
+}
+
+/// Stored in `PRAGMA user_version` once `init` has run; bump it with every
+/// schema change so read-only opens know to migrate the file first.
+const SCHEMA_VERSION: i64 = 13;
+
+/// Automatic backups kept in `backups/`; older ones are deleted as new ones
+/// are taken. Backups made from the dashboard are never rotated out.
+const AUTO_BACKUPS: usize = 10;
+
+/// Removes attachments, revisions and embeddings whose message is gone, then
+/// blobs nothing points at.
+const DROP_ORPHAN_ROWS: &str =
+    "DELETE FROM attachments WHERE message_id NOT IN (SELECT id FROM messages);
+     DELETE FROM attachment_blobs WHERE sha256 NOT IN (SELECT sha256 FROM attachments);
+     DELETE FROM message_revisions WHERE message_id NOT IN (SELECT id FROM messages);
+     DELETE FROM message_embeddings WHERE message_id NOT IN (SELECT id FROM messages);";
+
+/// Messages that are neither soft-deleted nor in an archived conversation.
+const VISIBLE: &str = "deleted_at IS NULL AND (conversation_id IS NULL
+     OR conversation_id NOT IN (SELECT id FROM conversations WHERE archived = 1))";
+
+/// How long a statement waits on another process's lock before failing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lock waits on read-only connections since the last `take_busy_waits`.
+static BUSY_WAITS: AtomicU64 = AtomicU64::new(0);
+
+/// Wraps each matched term in a search snippet.
+pub const MATCH_START: char = '\u{1}';
+pub const MATCH_END: char = '\u{2}';
+
+/// Request metadata stored alongside a model reply.
+#[derive(Clone)]
+pub struct ReplyMeta {
+    pub model: String,
+    pub latency_ms: u64,
+    pub http_status: Option<u16>,
+    pub usage: Usage,
+    /// `None` when the model has no known price.
+    pub cost: Option<f64>,
+    /// Why the model stopped, when the provider said (streamed replies only).
+    pub finish_reason: Option<String>,
+}
+
+/// One indexed document chunk with its embedding.
+pub struct StoredChunk {
+    pub path: String,
+    pub content: String,
+    pub embedding: Vec<f32>,
+}
+
+/// A persisted message row, as loaded back for history restore.
+pub struct StoredMessage {
+    pub id: i64,
+    pub role: String,
+    pub kind: String,
+    pub content: String,
+    /// Unix seconds.
+    pub timestamp: i64,
+    /// Present for model replies saved with request metadata.
+    pub meta: Option<ReplyMeta>,
+    pub bookmarked: bool,
+    /// Earlier versions of an edited prompt, oldest first.
+    pub revisions: Vec<String>,
+}
+
+/// Request counters for one provider key, as listed by `/keys`.
+pub struct KeyUsage {
+    pub provider: String,
+    /// The env var the key is read from.
+    pub label: String,
+    pub requests: i64,
+    pub unauthorized: i64,
+    pub rate_limited: i64,
+    /// Unix seconds of the latest request.
+    pub last_used: i64,
+}
+
+/// Which archived messages the dashboard lists, searches and counts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageFilter {
+    /// Provider key, e.g. `claude`.
+    pub provider: Option<String>,
+    pub conversation: Option<i64>,
+    /// Only conversations carrying this tag.
+    pub tag: Option<String>,
+    /// `user` or `assistant`.
+    pub role: Option<&'static str>,
+    /// Unix seconds, inclusive.
+    pub since: Option<i64>,
+    /// Unix seconds, exclusive.
+    pub until: Option<i64>,
+    /// Also match soft-deleted messages and archived conversations.
+    pub hidden: bool,
+}
+
+impl MessageFilter {
+    /// A `WHERE` condition over `messages` columns (`1` when unfiltered), with
+    /// the provider, tag, role and time bounds as `:name` parameters.
+    pub fn sql(&self) -> FilterSql {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if !self.hidden {
+            conditions.push(VISIBLE);
+        }
+        if let Some(provider) = &self.provider {
+            conditions.push("provider = :provider");
+            values.push((":provider", Value::Text(provider.clone())));
+        }
+        if let Some(conversation) = self.conversation {
+            conditions.push("conversation_id = :conversation");
+            values.push((":conversation", Value::Integer(conversation)));
+        }
+        if let Some(tag) = &self.tag {
+            conditions.push(
+                "conversation_id IN (SELECT ct.conversation_id FROM conversation_tags ct
+                 JOIN tags t ON t.id = ct.tag_id WHERE t.name = :tag)",
+            );
+            values.push((":tag", Value::Text(tag.clone())));
+        }
+        if let Some(role) = self.role {
+            conditions.push("role = :role");
+            values.push((":role", Value::Text(role.to_string())));
+        }
+        if let Some(since) = self.since {
+            conditions.push("timestamp >= :since");
+            values.push((":since", Value::Integer(since)));
+        }
+        if let Some(until) = self.until {
+            conditions.push("timestamp < :until");
+            values.push((":until", Value::Integer(until)));
+        }
+        let condition = if conditions.is_empty() {
+            "1".to_string()
+        } else {
+            conditions.join(" AND ")
+        };
+        FilterSql { condition, values }
+    }
+}
+
+/// A `MessageFilter` as SQL. Statements using `condition` bind `params`, and
+/// name any parameters of their own too, since SQLite numbers named and `?`
+/// parameters in one sequence.
+pub struct FilterSql {
+    pub condition: String,
+    values: Vec<(&'static str, Value)>,
+}
+
+impl FilterSql {
+    /// The filter's values followed by `extra`, for rusqlite's named binding.
+    pub fn params<'a>(
+        &'a self,
+        extra: &[(&'a str, &'a dyn ToSql)],
+    ) -> Vec<(&'a str, &'a dyn ToSql)> {
+        let mut params: Vec<(&str, &dyn ToSql)> =
+            self.values.iter().map(|(name, value)| (*name, value as &dyn ToSql)).collect();
+        params.extend_from_slice(extra);
+        params
+    }
+}
+
+/// One archived message as listed by the dashboard's Messages view.
+pub struct LoggedMessage {
+    pub id: i64,
+    pub provider: String,
+    pub role: String,
+    pub kind: String,
+    pub content: String,
+    /// Unix seconds.
+    pub timestamp: i64,
+    /// Model that wrote a reply, when recorded.
+    pub model: Option<String>,
+    /// Soft-deleted; only listed with `MessageFilter::hidden`.
+    pub deleted: bool,
+    /// Comma-separated names of files sent with it; empty when none.
+    pub attachments: String,
+    /// How many earlier versions `/edit` left behind.
+    pub revisions: usize,
+}
+
+const LOGGED_MESSAGE_COLUMNS: &str = "SELECT id, provider, role, kind, content, timestamp, model,
+        deleted_at IS NOT NULL,
+        (SELECT COALESCE(GROUP_CONCAT(filename, ', '), '') FROM attachments
+         WHERE message_id = messages.id),
+        (SELECT COUNT(*) FROM message_revisions WHERE message_id = messages.id)
+     FROM messages";
+
+fn logged_message(row: &rusqlite::Row) -> rusqlite::Result<LoggedMessage> {
+    Ok(LoggedMessage {
+        id: row.get(0)?,
+        provider: row.get(1)?,
+        role: row.get(2)?,
+        kind: row.get(3)?,
+        content: row.get(4)?,
+        timestamp: row.get(5)?,
+        model: row.get(6)?,
+        deleted: row.get(7)?,
+        attachments: row.get(8)?,
+        revisions: row.get::<_, i64>(9)? as usize,
+    })
+}
+
+/// An earlier version of an edited prompt.
+pub struct Revision {
+    pub content: String,
+    /// Unix seconds; when this version was sent.
+    pub timestamp: i64,
+}
+
+/// A file archived with a prompt.
+pub struct StoredAttachment {
+    pub filename: String,
+    pub mime: String,
+    pub data: Vec<u8>,
+}
+
+/// Whether every one of an archived conversation's `messages` is already
+/// stored: same provider, role, kind, text and time.
+fn already_archived(conn: &Connection, messages: &[ArchivedMessage]) -> Result<bool> {
+    let mut stmt = conn.prepare(
+        "SELECT EXISTS(
+            SELECT 1 FROM messages
+            WHERE content_hash = ?1 AND provider = ?2 AND role = ?3 AND kind = ?4
+              AND timestamp = ?5
+         )",
+    )?;
+    for message in messages {
+        let exists: bool = stmt.query_row(
+            params![
+                sha256_hex(message.content.as_bytes()),
+                message.provider,
+                message.role,
+                message.kind,
+                message.timestamp
+            ],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Ok(false);
+        }
+    }
+    Ok(!messages.is_empty())
+}
+
+/// Hex SHA-256 of `data`: attachment blob keys and `messages.content_hash`.
+fn sha256_hex(data: &[u8]) -> String {
+    let hash = ring::digest::digest(&ring::digest::SHA256, data);
+    hash.as_ref().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Archives the files sent with prompt `message_id`, whole even when only
+/// part of one went to the model.
+fn insert_attachments(
+    conn: &Connection,
+    message_id: i64,
+    attachments: &[Attachment],
+) -> Result<()> {
+    for attachment in attachments {
+        let mime = crate::attach::mime_type(&attachment.name);
+        insert_attachment(conn, message_id, &attachment.name, mime, &attachment.data)?;
+    }
+    Ok(())
+}
+
+/// Replaces the text of prompt `message_id`, which `/edit` took back,
+/// keeping what it said before as a revision. The prompt is restored if
+/// `/edit` soft-deleted it.
+fn revise_message(conn: &Connection, message_id: i64, content: &str) -> Result<()> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs() as i64;
+    conn.execute(
+        "INSERT INTO message_revisions (message_id, content, timestamp)
+         SELECT id, content, timestamp FROM messages WHERE id = ?1",
+        params![message_id],
+    )?;
+    conn.execute(
+        "UPDATE messages SET content = ?2, timestamp = ?3, deleted_at = NULL,
+            content_hash = ?4
+         WHERE id = ?1",
+        params![message_id, content, now, sha256_hex(content.as_bytes())],
+    )?;
+    // the old vector no longer matches; `index-history` embeds it again
+    conn.execute(
+        "DELETE FROM message_embeddings WHERE message_id = ?1",
+        params![message_id],
+    )?;
+    Ok(())
+}
+
+/// Stores `data` under its SHA-256 unless an identical file already is, and
+/// links it to `message_id` unless it already carries the same file.
+fn insert_attachment(
+    conn: &Connection,
+    message_id: i64,
+    filename: &str,
+    mime: &str,
+    data: &[u8],
+) -> Result<()> {
+    let sha256 = sha256_hex(data);
+    conn.execute(
+        "INSERT OR IGNORE INTO attachment_blobs (sha256, data) VALUES (?1, ?2)",
+        params![sha256, data],
+    )?;
+    conn.execute(
+        "INSERT INTO attachments (message_id, filename, mime, bytes, sha256)
+         SELECT ?1, ?2, ?3, ?4, ?5
+         WHERE NOT EXISTS (
+            SELECT 1 FROM attachments WHERE message_id = ?1 AND filename = ?2 AND sha256 = ?5
+         )",
+        params![message_id, filename, mime, data.len() as i64, sha256],
+    )?;
+    Ok(())
+}
+
+/// One conversation (a chat deck run or `asciivision ask` call), as listed by
+/// the dashboard.
+pub struct ConversationSummary {
+    pub id: i64,
+    /// Preview of the conversation's first prompt; empty when it has none.
+    pub title: String,
+    pub messages: i64,
+    /// Unix seconds of the first and last message.
+    pub started: i64,
+    pub ended: i64,
+    /// Comma-separated provider keys.
+    pub providers: String,
+    /// Comma-separated tag names; empty when untagged.
+    pub tags: String,
+    pub archived: bool,
+    /// The latest `/summarize` result; empty when never summarized.
+    pub summary: String,
+}
+
+/// A prompt sent on a cron schedule by `asciivision daemon`.
+pub struct ScheduledJob {
+    pub id: i64,
+    pub prompt: String,
+    /// Five-field cron expression, in local time.
+    pub cron: String,
+    pub provider: String,
+    pub model: Option<String>,
+    pub created_at: i64,
+    /// Unix seconds of the last run, successful or not.
+    pub last_run: Option<i64>,
+}
+
+/// A full-text search match; `snippet` marks matches with `MATCH_START`/`MATCH_END`.
+pub struct SearchHit {
+    pub message_id: i64,
+    pub provider: String,
+    pub role: String,
+    pub timestamp: i64,
+    pub snippet: String,
+}
+
+/// A bookmarked message, as listed by `/bookmarks`.
+pub struct Bookmark {
+    pub message_id: i64,
+    pub provider: String,
+    pub role: String,
+    pub content: String,
+    /// Unix seconds the message was saved.
+    pub timestamp: i64,
 }
 
 impl Database {
     pub fn connection(&self) -> &Connection {
         &self.conn
     }
+
+    /// The file this connection has open.
+    pub fn path(&self) -> PathBuf {
+        PathBuf::from(self.conn.path().unwrap_or_default())
+    }
 }
 
 impl Database {
-    pub fn new() -> Result<Self> {
-        let path = Self::db_path()?;
+    pub fn open(path: PathBuf) -> Result<Self> {
         if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
         }
 
         let conn = Connection::open(&path)
             .with_context(|| format!("failed to open database at {}", path.display()))?;
+        Self::prepare(conn)
+    }
 
-        let db = Self { conn };
+    /// A private archive that lives only as long as the connection.
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::prepare(Connection::open_in_memory()?)
+    }
+
+    /// Migrates the schema behind `conn` to `SCHEMA_VERSION`.
+    fn prepare(conn: Connection) -> Result<Self> {
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        // WAL lets the dashboard read while the chat deck writes; it sticks to
+        // the file, and filesystems without shared memory just keep the
+        // rollback journal.
+        let _ = conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| {
+            row.get::<_, String>(0)
+        });
+
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let existing: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'messages')",
+            [],
+            |row| row.get(0),
+        )?;
+        let mut db = Self {
+            conn,
+            fts: false,
+            conversation: Cell::new(None),
+            migration_backup: None,
+        };
+        // an in-memory archive has no file to back up
+        let on_disk = db.conn.path().is_some_and(|path| !path.is_empty());
+        if existing && version < SCHEMA_VERSION && on_disk {
+            let backup = db
+                .snapshot(&format!("v{}-upgrade", version))
+                .context("backing up the archive before upgrading its schema")?;
+            db.migration_backup = Some(backup);
+        }
         db.init()?;
+        db.fts = db.init_fts().is_ok();
+        db.conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
         Ok(db)
     }
 
-    fn db_path() -> Result<PathBuf> {
-        let home = std::env::var("HOME").context("HOME environment variable not set")?;
-        Ok(PathBuf::from(home).join(".config/asciivision/conversations.db"))
+    /// Opens an existing archive for the analytics dashboard without write
+    /// access, so it never holds a lock the chat deck's inserts have to wait
+    /// on. A file last written by an older build is migrated first through a
+    /// short-lived writable connection.
+    pub fn open_read_only(path: PathBuf) -> Result<Self> {
+        let open = || -> Result<Connection> {
+            let conn = Connection::open_with_flags(
+                &path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )
+            .with_context(|| format!("failed to open database at {}", path.display()))?;
+            conn.busy_handler(Some(wait_for_lock))?;
+            Ok(conn)
+        };
+        let mut conn = open()?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let mut migration_backup = None;
+        if version < SCHEMA_VERSION {
+            drop(conn);
+            migration_backup = Self::open(path.clone())?.migration_backup;
+            conn = open()?;
+        }
+        let fts = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'messages_fts')",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(Self {
+            conn,
+            fts,
+            conversation: Cell::new(None),
+            migration_backup,
+        })
+    }
+
+    /// Gives up on a lock after `timeout` rather than `BUSY_TIMEOUT`, for a
+    /// connection read from a thread that cannot wait.
+    pub fn set_busy_timeout(&self, timeout: Duration) -> Result<()> {
+        self.conn.busy_timeout(timeout)?;
+        Ok(())
+    }
+
+    /// How many times read-only connections waited on a lock since the last
+    /// call.
+    pub fn take_busy_waits() -> u64 {
+        BUSY_WAITS.swap(0, Ordering::Relaxed)
+    }
+
+    /// The default database file in the data directory; callers go through
+    /// `Config::database_path`, which honors the overrides.
+    pub fn db_path() -> Result<PathBuf> {
+        Ok(paths::with_legacy_fallback(&paths::data_dir()?, "conversations.db"))
+    }
+
+    /// Copies the archive to `path` with SQLite's online backup, which waits
+    /// out the chat deck's writes instead of copying a half-written file.
+    pub fn backup_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut target = Connection::open(path)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        Backup::new(&self.conn, &mut target)?.run_to_completion(
+            256,
+            Duration::from_millis(10),
+            None,
+        )?;
+        Ok(())
+    }
+
+    /// Backs the archive up to `backups/` before a schema upgrade or a
+    /// destructive command, naming the file after `reason`, then deletes the
+    /// oldest automatic backups past `AUTO_BACKUPS`.
+    pub fn snapshot(&self, reason: &str) -> Result<PathBuf> {
+        let db_path = self.path();
+        let path = auto_backup_path(&db_path, reason);
+        self.backup_to(&path)?;
+        let stem = format!("{}-", backup_stem(&db_path));
+        let automatic = list_backups(&backup_dir(&db_path)).into_iter().filter(|backup| {
+            backup.path.file_name().and_then(|name| name.to_str()).is_some_and(|name| {
+                name.starts_with(&stem) && name.contains(AUTO_BACKUP_MARK)
+            })
+        });
+        for old in automatic.skip(AUTO_BACKUPS) {
+            let _ = std::fs::remove_file(&old.path);
+        }
+        Ok(path)
+    }
+
+    /// Where the archive was saved before this open upgraded its schema.
+    pub fn migration_backup(&self) -> Option<&Path> {
+        self.migration_backup.as_deref()
+    }
+
+    /// Replaces the archive at `target` with the contents of `backup`, under
+    /// SQLite's locks so a running chat deck never reads a partial copy.
+    pub fn restore(target: &Path, backup: &Path) -> Result<()> {
+        let source = Connection::open_with_flags(backup, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("failed to open {}", backup.display()))?;
+        let mut target = Connection::open(target)
+            .with_context(|| format!("failed to open {}", target.display()))?;
+        target.busy_timeout(BUSY_TIMEOUT)?;
+        Backup::new(&source, &mut target)?.run_to_completion(
+            256,
+            Duration::from_millis(10),
+            None,
+        )?;
+        Ok(())
     }
 
     fn init(&self) -> Result<()> {
@@ -44,25 +584,2901 @@ impl Database {
             )",
             [],
         )?;
+        self.add_column_if_missing("messages", "replaced", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column_if_missing("messages", "input_tokens", "INTEGER")?;
+        self.add_column_if_missing("messages", "output_tokens", "INTEGER")?;
+        self.add_column_if_missing("messages", "cache_read_tokens", "INTEGER")?;
+        self.add_column_if_missing("messages", "cache_write_tokens", "INTEGER")?;
+        self.add_column_if_missing("messages", "cost_usd", "REAL")?;
+        self.add_column_if_missing("messages", "model", "TEXT")?;
+        self.add_column_if_missing("messages", "latency_ms", "INTEGER")?;
+        self.add_column_if_missing("messages", "http_status", "INTEGER")?;
+        self.add_column_if_missing("messages", "finish_reason", "TEXT")?;
+        // unix seconds of a soft delete; `purge` removes the row for good
+        self.add_column_if_missing("messages", "deleted_at", "INTEGER")?;
+        // SHA-256 of `content`, so the same text saved twice is found by lookup
+        self.add_column_if_missing("messages", "content_hash", "TEXT")?;
+        self.backfill_content_hashes()?;
+        // every provider shares this one table; history restore, retries and
+        // edits look up a provider's newest rows through this index
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS messages_provider ON messages (provider, id)",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS conversations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                provider TEXT NOT NULL DEFAULT '',
+                title TEXT NOT NULL DEFAULT '',
+                created_at INTEGER NOT NULL,
+                archived INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )?;
+        self.add_column_if_missing(
+            "messages",
+            "conversation_id",
+            "INTEGER REFERENCES conversations(id)",
+        )?;
+        self.add_column_if_missing("conversations", "summary", "TEXT NOT NULL DEFAULT ''")?;
+        self.migrate_sessions()?;
+        self.backfill_conversations()?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS tags (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS conversation_tags (
+                conversation_id INTEGER NOT NULL REFERENCES conversations(id),
+                tag_id INTEGER NOT NULL REFERENCES tags(id),
+                PRIMARY KEY (conversation_id, tag_id)
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS messages_conversation ON messages (conversation_id, id)",
+            [],
+        )?;
+        // the dashboard's Timeline reads every provider in send order
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS messages_timestamp ON messages (timestamp, id)",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS messages_content_hash ON messages (content_hash)",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS arena_votes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                prompt TEXT NOT NULL,
+                winner TEXT NOT NULL,
+                model TEXT NOT NULL,
+                candidates TEXT NOT NULL,
+                timestamp INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS bookmarks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                message_id INTEGER NOT NULL UNIQUE,
+                timestamp INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS api_key_usage (
+                provider TEXT NOT NULL,
+                label TEXT NOT NULL,
+                requests INTEGER NOT NULL DEFAULT 0,
+                unauthorized INTEGER NOT NULL DEFAULT 0,
+                rate_limited INTEGER NOT NULL DEFAULT 0,
+                last_used INTEGER NOT NULL,
+                PRIMARY KEY (provider, label)
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS app_metrics (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                event TEXT NOT NULL,
+                detail TEXT NOT NULL,
+                value REAL,
+                timestamp INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS rag_chunks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                path TEXT NOT NULL,
+                chunk INTEGER NOT NULL,
+                model TEXT NOT NULL,
+                content TEXT NOT NULL,
+                embedding BLOB NOT NULL,
+                timestamp INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        // `asciivision index-history`: one vector per message and model
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS message_embeddings (
+                message_id INTEGER NOT NULL REFERENCES messages(id),
+                model TEXT NOT NULL,
+                embedding BLOB NOT NULL,
+                PRIMARY KEY (message_id, model)
+            )",
+            [],
+        )?;
+        // files sent with a prompt; identical files share one blob
+        self.conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS attachment_blobs (
+                sha256 TEXT PRIMARY KEY,
+                data BLOB NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS attachments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                message_id INTEGER NOT NULL REFERENCES messages(id),
+                filename TEXT NOT NULL,
+                mime TEXT NOT NULL,
+                bytes INTEGER NOT NULL,
+                sha256 TEXT NOT NULL REFERENCES attachment_blobs(sha256)
+             );
+             CREATE INDEX IF NOT EXISTS attachments_message ON attachments (message_id);",
+        )?;
+        // what a prompt said before each `/edit`
+        self.conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS message_revisions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                message_id INTEGER NOT NULL REFERENCES messages(id),
+                content TEXT NOT NULL,
+                timestamp INTEGER NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS message_revisions_message
+                ON message_revisions (message_id);",
+        )?;
+        // prompts `asciivision daemon` sends on a cron schedule
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS scheduled_jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                prompt TEXT NOT NULL,
+                cron TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                model TEXT,
+                created_at INTEGER NOT NULL,
+                last_run INTEGER
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Moves the `sessions` table and `messages.session_id` column written by
+    /// schema version 1 over to `conversations`, taking each one's provider
+    /// from its first message.
+    fn migrate_sessions(&self) -> Result<()> {
+        let exists: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'sessions')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Ok(());
+        }
+        let tx = self.write_tx()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO conversations (id, provider, title, created_at)
+             SELECT s.id,
+                    COALESCE((SELECT provider FROM messages m
+                              WHERE m.session_id = s.id ORDER BY m.id LIMIT 1), ''),
+                    s.title, s.started
+             FROM sessions s",
+            [],
+        )?;
+        tx.execute(
+            "UPDATE messages SET conversation_id = session_id
+             WHERE conversation_id IS NULL AND session_id IS NOT NULL",
+            [],
+        )?;
+        tx.execute("ALTER TABLE messages DROP COLUMN session_id", [])?;
+        tx.execute("DROP TABLE sessions", [])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Wraps rows saved before conversations were recorded into one legacy
+    /// conversation per provider.
+    fn backfill_conversations(&self) -> Result<()> {
+        let providers: Vec<(String, i64)> = self
+            .conn
+            .prepare(
+                "SELECT provider, MIN(timestamp) FROM messages
+                 WHERE conversation_id IS NULL GROUP BY provider",
+            )?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if providers.is_empty() {
+            return Ok(());
+        }
+        let tx = self.write_tx()?;
+        for (provider, created_at) in providers {
+            tx.execute(
+                "INSERT INTO conversations (provider, title, created_at) VALUES (?1, ?2, ?3)",
+                params![provider, format!("{} history", provider), created_at],
+            )?;
+            tx.execute(
+                "UPDATE messages SET conversation_id = ?1
+                 WHERE conversation_id IS NULL AND provider = ?2",
+                params![tx.last_insert_rowid(), provider],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Hashes rows saved before `content_hash` was recorded.
+    fn backfill_content_hashes(&self) -> Result<()> {
+        let rows: Vec<(i64, String)> = self
+            .conn
+            .prepare("SELECT id, content FROM messages WHERE content_hash IS NULL")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let tx = self.write_tx()?;
+        {
+            let mut stmt = tx.prepare("UPDATE messages SET content_hash = ?2 WHERE id = ?1")?;
+            for (id, content) in rows {
+                stmt.execute(params![id, sha256_hex(content.as_bytes())])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Saves further messages under a new conversation, e.g. after `/clear`.
+    pub fn new_conversation(&self) {
+        self.conversation.set(None);
+    }
+
+    /// The current conversation, created on first use by `provider`'s message.
+    fn conversation_id(&self, conn: &Connection, provider: &str, timestamp: i64) -> Result<i64> {
+        if let Some(conversation) = self.conversation.get() {
+            return Ok(conversation);
+        }
+        conn.execute(
+            "INSERT INTO conversations (provider, created_at) VALUES (?1, ?2)",
+            params![provider, timestamp],
+        )?;
+        let conversation = conn.last_insert_rowid();
+        self.conversation.set(Some(conversation));
+        Ok(conversation)
+    }
+
+    /// Full-text index over message content, kept in sync by triggers and
+    /// backfilled from existing rows when first created.
+    fn init_fts(&self) -> Result<()> {
+        let exists: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'messages_fts')",
+            [],
+            |row| row.get(0),
+        )?;
+        self.conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts
+                USING fts5(content, content = 'messages', content_rowid = 'id');
+             CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+                INSERT INTO messages_fts (rowid, content) VALUES (new.id, new.content);
+             END;
+             CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+                INSERT INTO messages_fts (messages_fts, rowid, content)
+                VALUES ('delete', old.id, old.content);
+             END;
+             CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF content ON messages
+             BEGIN
+                INSERT INTO messages_fts (messages_fts, rowid, content)
+                VALUES ('delete', old.id, old.content);
+                INSERT INTO messages_fts (rowid, content) VALUES (new.id, new.content);
+             END;",
+        )?;
+        if !exists {
+            self.conn
+                .execute("INSERT INTO messages_fts (messages_fts) VALUES ('rebuild')", [])?;
+        }
+        Ok(())
+    }
+
+    /// Stores an `/arena` vote; `candidates` are the provider keys that answered.
+    pub fn save_arena_vote(
+        &self,
+        prompt: &str,
+        winner: &str,
+        model: &str,
+        candidates: &[&str],
+    ) -> Result<()> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+        self.conn.execute(
+            "INSERT INTO arena_votes (prompt, winner, model, candidates, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![prompt, winner, model, candidates.join(","), timestamp],
+        )?;
+        Ok(())
+    }
+
+    fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let exists = self
+            .conn
+            .prepare(&format!("PRAGMA table_info({})", table))?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+        if !exists {
+            self.conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            )?;
+        }
         Ok(())
     }
 
+    /// Saves one transcript row and returns its id. Model replies (and provider
+    /// errors) pass `meta` so analytics can chart provider performance. A
+    /// prompt repeating the conversation's last one, with nothing but errors
+    /// in between, returns the saved prompt's id instead of a second row.
     pub fn save_message(
         &self,
         provider: &str,
         role: &str,
         kind: &str,
         content: &str,
-    ) -> Result<()> {
+        meta: Option<&ReplyMeta>,
+    ) -> Result<i64> {
+        self.in_conversation(|tx| self.insert_message(tx, provider, role, kind, content, meta))
+    }
+
+    /// Saves a prompt and the reply (or provider error) it got in one
+    /// transaction, returning both ids. Used where the whole exchange is known
+    /// at once, like `asciivision ask`.
+    pub fn save_exchange(
+        &self,
+        provider: &str,
+        prompt: &str,
+        attachments: &[Attachment],
+        kind: &str,
+        reply: &str,
+        meta: &ReplyMeta,
+    ) -> Result<(i64, i64)> {
+        self.in_conversation(|tx| {
+            let prompt_id = self.insert_message(tx, provider, "user", "chat", prompt, None)?;
+            insert_attachments(tx, prompt_id, attachments)?;
+            let reply_id = self.insert_message(tx, provider, "assistant", kind, reply, Some(meta))?;
+            Ok((prompt_id, reply_id))
+        })
+    }
+
+    /// Runs `write` in a write transaction that may start the current
+    /// conversation, forgetting a conversation created by one that rolled back.
+    fn in_conversation<T>(&self, write: impl FnOnce(&Transaction) -> Result<T>) -> Result<T> {
+        let started = self.conversation.get();
+        let result = self.write_tx().and_then(|tx| {
+            let value = write(&tx)?;
+            tx.commit()?;
+            Ok(value)
+        });
+        if result.is_err() {
+            self.conversation.set(started);
+        }
+        result
+    }
+
+    /// A transaction that takes the write lock up front, so it waits out the
+    /// busy timeout instead of failing when a reader's snapshot goes stale
+    /// mid-way, as a deferred one can under WAL. Foreign keys are checked at
+    /// commit, so a delete can drop messages first and `DROP_ORPHAN_ROWS` the
+    /// attachments and revisions that pointed at them after.
+    fn write_tx(&self) -> Result<Transaction<'_>> {
+        let tx = Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)?;
+        tx.execute_batch("PRAGMA defer_foreign_keys = ON")?;
+        Ok(tx)
+    }
+
+    fn insert_message(
+        &self,
+        conn: &Connection,
+        provider: &str,
+        role: &str,
+        kind: &str,
+        content: &str,
+        meta: Option<&ReplyMeta>,
+    ) -> Result<i64> {
         let timestamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)?
             .as_secs() as i64;
+        let usage = meta.map(|meta| meta.usage).filter(|usage| !usage.is_empty());
+        let conversation = self.conversation_id(conn, provider, timestamp)?;
+        let hash = sha256_hex(content.as_bytes());
+        if role == "user" {
+            // a prompt sent again after a failed request is the same message
+            let previous: Option<i64> = conn
+                .query_row(
+                    "SELECT id FROM (
+                        SELECT id, role, kind, content_hash FROM messages
+                        WHERE conversation_id = ?1 AND replaced = 0 AND deleted_at IS NULL
+                          AND kind != 'error'
+                        ORDER BY id DESC LIMIT 1
+                     )
+                     WHERE role = 'user' AND kind = ?2 AND content_hash = ?3",
+                    params![conversation, kind, hash],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(id) = previous {
+                return Ok(id);
+            }
+        }
 
-        self.conn.execute(
-            "INSERT INTO messages (provider, role, kind, content, timestamp)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![provider, role, kind, content, timestamp],
+        conn.execute(
+            "INSERT INTO messages (
+                provider, role, kind, content, timestamp,
+                model, latency_ms, http_status, input_tokens, output_tokens, cost_usd,
+                cache_read_tokens, cache_write_tokens, finish_reason, conversation_id,
+                content_hash
+             )
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+            params![
+                provider,
+                role,
+                kind,
+                content,
+                timestamp,
+                meta.map(|meta| meta.model.as_str()),
+                meta.map(|meta| meta.latency_ms as i64),
+                meta.and_then(|meta| meta.http_status),
+                usage.map(|usage| usage.input_tokens),
+                usage.map(|usage| usage.output_tokens),
+                meta.and_then(|meta| meta.cost),
+                usage.map(|usage| usage.cache_read_tokens),
+                usage.map(|usage| usage.cache_write_tokens),
+                meta.and_then(|meta| meta.finish_reason.as_deref()),
+                conversation,
+                hash,
+            ],
         )?;
-        Ok(())
+        let id = conn.last_insert_rowid();
+        if role == "user" && kind == "chat" {
+            conn.execute(
+                "UPDATE conversations SET title = ?1 WHERE id = ?2 AND title = ''",
+                params![conversation_title(content), conversation],
+            )?;
+        }
+        Ok(id)
+    }
+
+    /// The last `limit` messages saved for `provider`, oldest first.
+    pub fn recent_messages(&self, provider: &str, limit: usize) -> Result<Vec<StoredMessage>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT role, kind, content, timestamp, model, latency_ms, http_status,
+                    input_tokens, output_tokens, cost_usd, id,
+                    EXISTS(SELECT 1 FROM bookmarks WHERE message_id = messages.id),
+                    cache_read_tokens, cache_write_tokens, finish_reason,
+                    (SELECT json_group_array(content) FROM (
+                        SELECT content FROM message_revisions
+                        WHERE message_id = messages.id ORDER BY id
+                     ))
+             FROM messages
+             WHERE provider = ?1 AND replaced = 0 AND kind != 'error' AND {}
+             ORDER BY id DESC
+             LIMIT ?2",
+            VISIBLE
+        ))?;
+        let mut rows = stmt
+            .query_map(params![provider, limit as i64], |row| {
+                let model: Option<String> = row.get(4)?;
+                let meta = match model {
+                    Some(model) => Some(ReplyMeta {
+                        model,
+                        latency_ms: row.get::<_, Option<i64>>(5)?.unwrap_or(0) as u64,
+                        http_status: row.get::<_, Option<i64>>(6)?.map(|status| status as u16),
+                        usage: Usage {
+                            input_tokens: row.get::<_, Option<i64>>(7)?.unwrap_or(0) as u32,
+                            output_tokens: row.get::<_, Option<i64>>(8)?.unwrap_or(0) as u32,
+                            cache_read_tokens: row.get::<_, Option<i64>>(12)?.unwrap_or(0) as u32,
+                            cache_write_tokens: row.get::<_, Option<i64>>(13)?.unwrap_or(0)
+                                as u32,
+                        },
+                        cost: row.get(9)?,
+                        finish_reason: row.get(14)?,
+                    }),
+                    None => None,
+                };
+                Ok(StoredMessage {
+                    id: row.get(10)?,
+                    role: row.get(0)?,
+                    kind: row.get(1)?,
+                    content: row.get(2)?,
+                    timestamp: row.get(3)?,
+                    meta,
+                    bookmarked: row.get(11)?,
+                    revisions: serde_json::from_str(&row.get::<_, String>(15)?)
+                        .unwrap_or_default(),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.reverse();
+        Ok(rows)
+    }
+
+    /// Archives a prompt with the files sent with it, in one transaction, as
+    /// a new version of prompt `revise` when `/edit` took that one back.
+    /// Returns the prompt's id.
+    pub fn save_prompt(
+        &self,
+        provider: &str,
+        content: &str,
+        revise: Option<i64>,
+        attachments: &[Attachment],
+    ) -> Result<i64> {
+        self.in_conversation(|tx| {
+            let id = match revise {
+                Some(id) => revise_message(tx, id, content).map(|()| id)?,
+                None => self.insert_message(tx, provider, "user", "chat", content, None)?,
+            };
+            insert_attachments(tx, id, attachments)?;
+            Ok(id)
+        })
+    }
+
+    pub fn attachments(&self, message_id: i64) -> Result<Vec<StoredAttachment>> {
+        let rows = self
+            .conn
+            .prepare(
+                "SELECT a.filename, a.mime, b.data
+                 FROM attachments a JOIN attachment_blobs b ON b.sha256 = a.sha256
+                 WHERE a.message_id = ?1
+                 ORDER BY a.id",
+            )?
+            .query_map(params![message_id], |row| {
+                Ok(StoredAttachment {
+                    filename: row.get(0)?,
+                    mime: row.get(1)?,
+                    data: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Earlier versions of `message_id`, oldest first.
+    pub fn revisions(&self, message_id: i64) -> Result<Vec<Revision>> {
+        let rows = self
+            .conn
+            .prepare(
+                "SELECT content, timestamp FROM message_revisions
+                 WHERE message_id = ?1
+                 ORDER BY id",
+            )?
+            .query_map(params![message_id], |row| {
+                Ok(Revision {
+                    content: row.get(0)?,
+                    timestamp: row.get(1)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Flags the newest live assistant reply for `provider` as replaced by a retry.
+    pub fn mark_last_reply_replaced(&self, provider: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE messages SET replaced = 1
+             WHERE id = (
+                SELECT MAX(id) FROM messages
+                WHERE provider = ?1 AND role = 'assistant' AND kind = 'chat' AND replaced = 0
+                  AND deleted_at IS NULL
+             )",
+            params![provider],
+        )?;
+        Ok(())
+    }
+
+    /// Soft-deletes the newest user prompt for `provider` and every row saved
+    /// after it.
+    pub fn delete_from_last_prompt(&self, provider: &str) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+        self.conn.execute(
+            "UPDATE messages SET deleted_at = ?2
+             WHERE provider = ?1 AND deleted_at IS NULL AND id >= (
+                SELECT MAX(id) FROM messages
+                WHERE provider = ?1 AND role = 'user' AND kind = 'chat' AND deleted_at IS NULL
+             )",
+            params![provider, now],
+        )?;
+        Ok(())
+    }
+
+    /// Soft-deletes one archived message; it stays in the file, hidden, until
+    /// `purge`.
+    pub fn delete_message(&self, message_id: i64) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+        self.conn.execute(
+            "UPDATE messages SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            params![message_id, now],
+        )?;
+        Ok(())
+    }
+
+    /// Archives the current conversation, hiding it from history restore,
+    /// search and the dashboard, and starts a new one. Returns whether there
+    /// was one to archive.
+    pub fn archive_conversation(&self) -> Result<bool> {
+        let Some(id) = self.conversation.get() else {
+            return Ok(false);
+        };
+        self.conn.execute("UPDATE conversations SET archived = 1 WHERE id = ?1", params![id])?;
+        self.new_conversation();
+        Ok(true)
+    }
+
+    /// Permanently removes soft-deleted messages and, with `archived`, every
+    /// archived conversation, then compacts the file so the text is gone from
+    /// disk too. Returns the messages and conversations removed.
+    pub fn purge(&self, archived: bool) -> Result<(usize, usize)> {
+        let doomed = if archived {
+            "deleted_at IS NOT NULL
+             OR conversation_id IN (SELECT id FROM conversations WHERE archived = 1)"
+        } else {
+            "deleted_at IS NOT NULL"
+        };
+        let tx = self.write_tx()?;
+        let messages = tx.execute(&format!("DELETE FROM messages WHERE {}", doomed), [])?;
+        let mut conversations = 0;
+        if archived {
+            tx.execute(
+                "DELETE FROM conversation_tags
+                 WHERE conversation_id IN (SELECT id FROM conversations WHERE archived = 1)",
+                [],
+            )?;
+            conversations = tx.execute("DELETE FROM conversations WHERE archived = 1", [])?;
+        }
+        tx.execute(
+            "DELETE FROM bookmarks WHERE message_id NOT IN (SELECT id FROM messages)",
+            [],
+        )?;
+        tx.execute_batch(DROP_ORPHAN_ROWS)?;
+        tx.commit()?;
+        if messages > 0 || conversations > 0 {
+            self.conn.execute_batch("VACUUM")?;
+        }
+        Ok((messages, conversations))
+    }
+
+    /// Messages saved more than once under `filter`: the same text from the
+    /// same provider and role, either repeated in one conversation with
+    /// nothing from the other side in between (a prompt resent after a failed
+    /// request) or at the same second (an archive imported again under a new
+    /// title). Largest groups first.
+    pub fn duplicates(&self, filter: &MessageFilter) -> Result<Vec<Duplicate>> {
+        let filter = filter.sql();
+        let pairs: Vec<(i64, i64)> = self
+            .conn
+            .prepare(&format!(
+                "SELECT original, id FROM (
+                    SELECT m.id,
+                           (SELECT MIN(d.id) FROM messages d
+                            WHERE d.content_hash = m.content_hash AND d.id < m.id
+                              AND d.provider = m.provider AND d.role = m.role
+                              AND d.kind = m.kind AND d.replaced = 0
+                              AND (d.timestamp = m.timestamp
+                                   OR d.conversation_id = m.conversation_id AND NOT EXISTS (
+                                      SELECT 1 FROM messages x
+                                      WHERE x.conversation_id = m.conversation_id
+                                        AND x.id > d.id AND x.id < m.id AND x.replaced = 0
+                                        AND x.kind != 'error' AND x.role != m.role
+                                   ))) AS original
+                    FROM messages m
+                    WHERE replaced = 0 AND {}
+                 )
+                 WHERE original IS NOT NULL
+                 ORDER BY original, id",
+                filter.condition
+            ))?
+            .query_map(filter.params(&[]).as_slice(), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut copies: BTreeMap<i64, Vec<i64>> = BTreeMap::new();
+        for (original, id) in pairs {
+            copies.entry(original).or_default().push(id);
+        }
+        if copies.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids: Vec<String> = copies.keys().map(|id| id.to_string()).collect();
+        let mut duplicates = self
+            .conn
+            .prepare(&format!(
+                "{} WHERE id IN ({}) ORDER BY id",
+                LOGGED_MESSAGE_COLUMNS,
+                ids.join(",")
+            ))?
+            .query_map([], logged_message)?
+            .map(|row| {
+                let original = row?;
+                let copies = copies.remove(&original.id).unwrap_or_default();
+                Ok(Duplicate { original, copies })
+            })
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        duplicates.sort_by_key(|duplicate| Reverse(duplicate.copies.len()));
+        Ok(duplicates)
+    }
+
+    /// Folds each of `duplicates`' copies into its original, after a backup:
+    /// a bookmark, attachments and revisions move over, then the copies and
+    /// any conversation they leave empty are deleted. Returns the messages
+    /// removed and where the backup went.
+    pub fn merge_duplicates(&self, duplicates: &[Duplicate]) -> Result<(usize, PathBuf)> {
+        let backup = self.snapshot("dedup")?;
+        let tx = self.write_tx()?;
+        let mut removed = 0;
+        for duplicate in duplicates {
+            let original = duplicate.original.id;
+            for &copy in &duplicate.copies {
+                tx.execute(
+                    "INSERT OR IGNORE INTO bookmarks (message_id, timestamp)
+                     SELECT ?1, timestamp FROM bookmarks WHERE message_id = ?2",
+                    params![original, copy],
+                )?;
+                tx.execute(
+                    "UPDATE attachments SET message_id = ?1
+                     WHERE message_id = ?2 AND sha256 NOT IN (
+                        SELECT sha256 FROM attachments WHERE message_id = ?1
+                     )",
+                    params![original, copy],
+                )?;
+                tx.execute(
+                    "UPDATE message_revisions SET message_id = ?1 WHERE message_id = ?2",
+                    params![original, copy],
+                )?;
+                tx.execute("DELETE FROM bookmarks WHERE message_id = ?1", params![copy])?;
+                let conversation: Option<i64> = tx
+                    .query_row(
+                        "DELETE FROM messages WHERE id = ?1 RETURNING conversation_id",
+                        params![copy],
+                        |row| row.get(0),
+                    )
+                    .optional()?
+                    .flatten();
+                removed += 1;
+                let Some(conversation) = conversation else {
+                    continue;
+                };
+                let empty: bool = tx.query_row(
+                    "SELECT NOT EXISTS(SELECT 1 FROM messages WHERE conversation_id = ?1)",
+                    params![conversation],
+                    |row| row.get(0),
+                )?;
+                if empty {
+                    tx.execute(
+                        "DELETE FROM conversation_tags WHERE conversation_id = ?1",
+                        params![conversation],
+                    )?;
+                    tx.execute("DELETE FROM conversations WHERE id = ?1", params![conversation])?;
+                }
+            }
+        }
+        tx.execute_batch(DROP_ORPHAN_ROWS)?;
+        tx.commit()?;
+        Ok((removed, backup))
+    }
+
+    /// Size of the database file, not counting the WAL.
+    pub fn size_bytes(&self) -> Result<u64> {
+        let pages: i64 = self.conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let page_size: i64 = self.conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        Ok((pages * page_size) as u64)
+    }
+
+    /// Sizes, counts and index health of the open archive; read-only.
+    pub fn stats(&self) -> Result<DatabaseStats> {
+        let pragma = |name: &str| -> Result<i64> {
+            Ok(self.conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get(0))?)
+        };
+        let path = self.conn.path().filter(|path| !path.is_empty()).map(PathBuf::from);
+        let wal_bytes = path
+            .as_ref()
+            .and_then(|path| std::fs::metadata(format!("{}-wal", path.display())).ok())
+            .map_or(0, |meta| meta.len());
+        let (messages, oldest, newest): (i64, Option<i64>, Option<i64>) = self.conn.query_row(
+            "SELECT COUNT(*), MIN(timestamp), MAX(timestamp) FROM messages",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        let count = |table: &str| -> Result<usize> {
+            let sql = format!("SELECT COUNT(*) FROM {}", table);
+            Ok(self.conn.query_row(&sql, [], |row| row.get::<_, i64>(0))? as usize)
+        };
+        let providers = self
+            .conn
+            .prepare(
+                "SELECT provider, COUNT(*), COUNT(DISTINCT conversation_id)
+                 FROM messages
+                 GROUP BY provider
+                 ORDER BY COUNT(*) DESC, provider",
+            )?
+            .query_map([], |row| {
+                Ok(ProviderCount {
+                    provider: row.get(0)?,
+                    messages: row.get::<_, i64>(1)? as usize,
+                    conversations: row.get::<_, i64>(2)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let largest = self
+            .conn
+            .prepare(
+                "SELECT c.provider, c.title, COUNT(*)
+                 FROM messages m JOIN conversations c ON c.id = m.conversation_id
+                 GROUP BY c.id
+                 ORDER BY COUNT(*) DESC, c.id DESC
+                 LIMIT 10",
+            )?
+            .query_map([], |row| {
+                Ok(ConversationCount {
+                    provider: row.get(0)?,
+                    title: row.get(1)?,
+                    messages: row.get::<_, i64>(2)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let integrity: String = self.conn.query_row("PRAGMA quick_check(1)", [], |row| row.get(0))?;
+        let mut missing_indexes = Vec::new();
+        for index in [
+            "messages_provider",
+            "messages_conversation",
+            "messages_timestamp",
+            "messages_content_hash",
+        ] {
+            let exists: bool = self.conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = ?1)",
+                params![index],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                missing_indexes.push(index.to_string());
+            }
+        }
+        // one docsize row per indexed message; counting the FTS table itself
+        // would just count `messages`
+        let fts_rows = if self.fts { Some(count("messages_fts_docsize")?) } else { None };
+        let page_size = pragma("page_size")? as u64;
+        Ok(DatabaseStats {
+            path,
+            bytes: self.size_bytes()?,
+            wal_bytes,
+            free_bytes: pragma("freelist_count")? as u64 * page_size,
+            schema_version: pragma("user_version")?,
+            messages: messages as usize,
+            conversations: count("conversations")?,
+            bookmarks: count("bookmarks")?,
+            deleted: count("messages WHERE deleted_at IS NOT NULL")?,
+            archived: count("conversations WHERE archived = 1")?,
+            oldest,
+            newest,
+            providers,
+            largest,
+            integrity,
+            missing_indexes,
+            fts_rows,
+        })
+    }
+
+    /// Deletes the messages `policy` no longer keeps, oldest first, and the
+    /// conversations that leaves empty, then compacts the file. Bookmarked
+    /// messages are always kept. With `dry_run` nothing is changed and the
+    /// report says what would go. `backup` snapshots the archive first when
+    /// anything is deleted; the startup prune skips it, since backups there
+    /// would pile up past the size the limits are meant to hold the file to.
+    pub fn prune(
+        &self,
+        policy: &RetentionPolicy,
+        dry_run: bool,
+        backup: bool,
+    ) -> Result<PruneReport> {
+        let bytes_before = self.size_bytes()?;
+        let mut report = PruneReport {
+            messages: 0,
+            conversations: 0,
+            through: None,
+            bytes_before,
+            bytes_after: bytes_before,
+            backup: None,
+        };
+        let mut doomed = Vec::new();
+        if let Some(days) = policy.max_age_days {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs() as i64;
+            doomed.push(format!("m.timestamp < {}", now - days as i64 * 86_400));
+        }
+        if policy.max_messages == Some(0) {
+            doomed.push("1".to_string());
+        } else if let Some(keep) = policy.max_messages {
+            let first_kept: Option<i64> = self
+                .conn
+                .query_row(
+                    "SELECT id FROM messages ORDER BY id DESC LIMIT 1 OFFSET ?1",
+                    params![keep.saturating_sub(1) as i64],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(id) = first_kept {
+                doomed.push(format!("m.id < {}", id));
+            }
+        }
+        if let Some(mb) = policy.max_size_mb {
+            let max_bytes = mb * 1024 * 1024;
+            if bytes_before > max_bytes {
+                // free the same share of message text as the file is over
+                let total: i64 = self.conn.query_row(
+                    "SELECT COALESCE(SUM(LENGTH(content)), 0) FROM messages",
+                    [],
+                    |row| row.get(0),
+                )?;
+                let excess = (bytes_before - max_bytes) as f64 / bytes_before as f64;
+                let target = (total as f64 * excess).ceil() as i64;
+                let last: Option<i64> = self
+                    .conn
+                    .query_row(
+                        "SELECT id FROM (
+                            SELECT id, SUM(LENGTH(content)) OVER (ORDER BY id) AS running
+                            FROM messages
+                         )
+                         WHERE running >= ?1
+                         ORDER BY id
+                         LIMIT 1",
+                        params![target],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if let Some(id) = last {
+                    doomed.push(format!("m.id <= {}", id));
+                }
+            }
+        }
+        if doomed.is_empty() {
+            return Ok(report);
+        }
+        let doomed = format!(
+            "({}) AND m.id NOT IN (SELECT message_id FROM bookmarks)",
+            doomed.join(" OR ")
+        );
+        // the backup has to finish before the write lock is taken
+        if backup && !dry_run {
+            let any: bool = self.conn.query_row(
+                &format!("SELECT EXISTS(SELECT 1 FROM messages m WHERE {})", doomed),
+                [],
+                |row| row.get(0),
+            )?;
+            if any {
+                report.backup = Some(self.snapshot("prune")?);
+            }
+        }
+
+        let tx = self.write_tx()?;
+        let (messages, through): (i64, Option<i64>) = tx.query_row(
+            &format!("SELECT COUNT(*), MAX(m.timestamp) FROM messages m WHERE {}", doomed),
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        // only conversations this prune empties; a new one without messages
+        // yet stays
+        let emptied = format!(
+            "SELECT c.id FROM conversations c
+             WHERE EXISTS (SELECT 1 FROM messages m WHERE m.conversation_id = c.id AND {0})
+               AND NOT EXISTS (
+                 SELECT 1 FROM messages m WHERE m.conversation_id = c.id AND NOT ({0})
+               )",
+            doomed
+        );
+        let conversations: i64 =
+            tx.query_row(&format!("SELECT COUNT(*) FROM ({})", emptied), [], |row| row.get(0))?;
+        report.messages = messages as usize;
+        report.conversations = conversations as usize;
+        report.through = through;
+        if dry_run || messages == 0 {
+            return Ok(report);
+        }
+        tx.execute_batch(&format!(
+            "CREATE TEMP TABLE pruned_conversations AS {};
+             DELETE FROM conversation_tags
+             WHERE conversation_id IN (SELECT id FROM pruned_conversations);
+             DELETE FROM messages WHERE id IN (SELECT m.id FROM messages m WHERE {});
+             DELETE FROM conversations WHERE id IN (SELECT id FROM pruned_conversations);
+             DROP TABLE pruned_conversations;",
+            emptied, doomed
+        ))?;
+        tx.execute_batch(DROP_ORPHAN_ROWS)?;
+        tx.commit()?;
+        self.conn.execute_batch("VACUUM")?;
+        report.bytes_after = self.size_bytes()?;
+        Ok(report)
+    }
+
+    /// Bookmarks `message_id`, or removes its bookmark; returns the new state.
+    pub fn toggle_bookmark(&self, message_id: i64) -> Result<bool> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+        let tx = self.write_tx()?;
+        let removed = tx.execute(
+            "DELETE FROM bookmarks WHERE message_id = ?1",
+            params![message_id],
+        )?;
+        if removed == 0 {
+            tx.execute(
+                "INSERT INTO bookmarks (message_id, timestamp) VALUES (?1, ?2)",
+                params![message_id, timestamp],
+            )?;
+        }
+        tx.commit()?;
+        Ok(removed == 0)
+    }
+
+    /// The newest `limit` bookmarked messages across all providers.
+    pub fn bookmarks(&self, limit: usize) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(
+            &format!(
+                "SELECT m.id, m.provider, m.role, m.content, m.timestamp
+                 FROM bookmarks b JOIN messages m ON m.id = b.message_id
+                 WHERE {}
+                 ORDER BY b.id DESC
+                 LIMIT ?1",
+                VISIBLE
+            ),
+        )?;
+        let rows = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(Bookmark {
+                    message_id: row.get(0)?,
+                    provider: row.get(1)?,
+                    role: row.get(2)?,
+                    content: row.get(3)?,
+                    timestamp: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Messages in `filter` matching every word of `query` as a prefix, best
+    /// match first: FTS5 ranked by bm25, or `LIKE` ranked by occurrences. Backs
+    /// both the chat deck's `/` search and the dashboard's Search view.
+    pub fn search(
+        &self,
+        query: &str,
+        filter: &MessageFilter,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+        if self.fts {
+            // quote every word so FTS5 syntax in the query is matched literally
+            let fts_query = words
+                .iter()
+                .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let filter = filter.sql();
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT m.id, m.provider, m.role, m.timestamp,
+                        snippet(messages_fts, 0, char(1), char(2), '...', 16)
+                 FROM messages_fts JOIN messages m ON m.id = messages_fts.rowid
+                 WHERE messages_fts MATCH :query AND m.replaced = 0 AND {}
+                 ORDER BY bm25(messages_fts), m.id DESC
+                 LIMIT :limit",
+                filter.condition
+            ))?;
+            let limit = limit as i64;
+            let params = filter.params(&[(":query", &fts_query), (":limit", &limit)]);
+            let rows = stmt
+                .query_map(params.as_slice(), |row| {
+                    Ok(SearchHit {
+                        message_id: row.get(0)?,
+                        provider: row.get(1)?,
+                        role: row.get(2)?,
+                        timestamp: row.get(3)?,
+                        snippet: row.get(4)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            return Ok(rows);
+        }
+
+        let filter = filter.sql();
+        let mut conditions = vec![filter.condition.clone()];
+        let mut rank = Vec::new();
+        for index in 1..=words.len() {
+            conditions.push(format!("content LIKE '%' || :word{} || '%' ESCAPE '\\'", index));
+            // the rank counts the word as typed, not its LIKE-escaped form
+            rank.push(format!(
+                "(length(content) - length(replace(lower(content), lower(:term{0}), '')))
+                 / length(:term{0})",
+                index
+            ));
+        }
+        let sql = format!(
+            "SELECT id, provider, role, timestamp, content
+             FROM messages
+             WHERE replaced = 0 AND {}
+             ORDER BY ({}) DESC, id DESC
+             LIMIT {}",
+            conditions.join(" AND "),
+            rank.join(" + "),
+            limit
+        );
+        let escaped: Vec<String> = words
+            .iter()
+            .map(|word| word.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"))
+            .collect();
+        let names: Vec<(String, String)> = (1..=words.len())
+            .map(|index| (format!(":word{}", index), format!(":term{}", index)))
+            .collect();
+        let extra: Vec<(&str, &dyn ToSql)> = names
+            .iter()
+            .zip(escaped.iter().zip(&words))
+            .flat_map(|((word_name, term_name), (escaped, word))| {
+                [
+                    (word_name.as_str(), escaped as &dyn ToSql),
+                    (term_name.as_str(), word as &dyn ToSql),
+                ]
+            })
+            .collect();
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(filter.params(&extra).as_slice(), |row| {
+                let content: String = row.get(4)?;
+                Ok(SearchHit {
+                    message_id: row.get(0)?,
+                    provider: row.get(1)?,
+                    role: row.get(2)?,
+                    timestamp: row.get(3)?,
+                    snippet: like_snippet(&content, &words),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// The newest messages in `filter` that `pattern` matches, with the first
+    /// match marked in the snippet; every message in `filter` without one.
+    pub fn search_pattern(
+        &self,
+        pattern: Option<&Regex>,
+        filter: &MessageFilter,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        let filter = filter.sql();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, provider, role, timestamp, content
+             FROM messages
+             WHERE replaced = 0 AND {}
+             ORDER BY id DESC",
+            filter.condition
+        ))?;
+        let mut rows = stmt.query(filter.params(&[]).as_slice())?;
+        let mut hits = Vec::new();
+        while hits.len() < limit {
+            let Some(row) = rows.next()? else {
+                break;
+            };
+            let content: String = row.get(4)?;
+            let found = match pattern {
+                Some(pattern) => match pattern.find(&content) {
+                    Some(found) => Some(found.range()),
+                    None => continue,
+                },
+                None => None,
+            };
+            hits.push(SearchHit {
+                message_id: row.get(0)?,
+                provider: row.get(1)?,
+                role: row.get(2)?,
+                timestamp: row.get(3)?,
+                snippet: range_snippet(&content, found),
+            });
+        }
+        Ok(hits)
+    }
+
+    /// Conversations with messages matching `filter`, most recent first;
+    /// archived ones only with `filter.hidden`.
+    pub fn conversations(&self, filter: &MessageFilter) -> Result<Vec<ConversationSummary>> {
+        let filter = filter.sql();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT m.conversation_id, c.title, COUNT(*), MIN(m.timestamp),
+                    MAX(m.timestamp), GROUP_CONCAT(DISTINCT m.provider),
+                    (SELECT COALESCE(GROUP_CONCAT(t.name, ','), '')
+                     FROM conversation_tags ct JOIN tags t ON t.id = ct.tag_id
+                     WHERE ct.conversation_id = m.conversation_id),
+                    c.archived, c.summary
+             FROM (SELECT * FROM messages WHERE replaced = 0 AND {}) m
+             JOIN conversations c ON c.id = m.conversation_id
+             GROUP BY m.conversation_id
+             ORDER BY MAX(m.id) DESC",
+            filter.condition
+        ))?;
+        let rows = stmt
+            .query_map(filter.params(&[]).as_slice(), |row| {
+                Ok(ConversationSummary {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    messages: row.get(2)?,
+                    started: row.get(3)?,
+                    ended: row.get(4)?,
+                    providers: row.get(5)?,
+                    tags: row.get(6)?,
+                    archived: row.get(7)?,
+                    summary: row.get(8)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Stores a `/summarize` summary on the current conversation, replacing
+    /// any earlier one. False when nothing has been archived in it yet.
+    pub fn set_conversation_summary(&self, summary: &str) -> Result<bool> {
+        let Some(conversation) = self.conversation.get() else {
+            return Ok(false);
+        };
+        self.conn.execute(
+            "UPDATE conversations SET summary = ?1 WHERE id = ?2",
+            params![summary, conversation],
+        )?;
+        Ok(true)
+    }
+
+    pub fn conversation_of(&self, message_id: i64) -> Result<Option<i64>> {
+        let conversation = self
+            .conn
+            .query_row(
+                "SELECT conversation_id FROM messages WHERE id = ?1",
+                params![message_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(conversation.flatten())
+    }
+
+    pub fn add_scheduled_job(
+        &self,
+        prompt: &str,
+        cron: &str,
+        provider: &str,
+        model: Option<&str>,
+    ) -> Result<i64> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+        self.conn.execute(
+            "INSERT INTO scheduled_jobs (prompt, cron, provider, model, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![prompt, cron, provider, model, timestamp],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Every scheduled job, oldest first.
+    pub fn scheduled_jobs(&self) -> Result<Vec<ScheduledJob>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, prompt, cron, provider, model, created_at, last_run
+             FROM scheduled_jobs ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(ScheduledJob {
+                    id: row.get(0)?,
+                    prompt: row.get(1)?,
+                    cron: row.get(2)?,
+                    provider: row.get(3)?,
+                    model: row.get(4)?,
+                    created_at: row.get(5)?,
+                    last_run: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// False when there was no job `id`.
+    pub fn remove_scheduled_job(&self, id: i64) -> Result<bool> {
+        let removed = self.conn.execute("DELETE FROM scheduled_jobs WHERE id = ?1", params![id])?;
+        Ok(removed > 0)
+    }
+
+    pub fn mark_scheduled_run(&self, id: i64, timestamp: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE scheduled_jobs SET last_run = ?2 WHERE id = ?1",
+            params![id, timestamp],
+        )?;
+        Ok(())
+    }
+
+    /// Tags the current conversation, starting one for `provider` when nothing
+    /// has been saved yet so a tag set before the first prompt sticks.
+    pub fn add_tag(&self, provider: &str, tag: &str) -> Result<()> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+        self.in_conversation(|tx| {
+            let conversation = self.conversation_id(tx, provider, timestamp)?;
+            tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", params![tag])?;
+            tx.execute(
+                "INSERT OR IGNORE INTO conversation_tags (conversation_id, tag_id)
+                 SELECT ?1, id FROM tags WHERE name = ?2",
+                params![conversation, tag],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Takes `tag` off the current conversation; `false` when it was not on it.
+    pub fn remove_tag(&self, tag: &str) -> Result<bool> {
+        let Some(conversation) = self.conversation.get() else {
+            return Ok(false);
+        };
+        let removed = self.conn.execute(
+            "DELETE FROM conversation_tags
+             WHERE conversation_id = ?1 AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+            params![conversation, tag],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Tags on the current conversation, alphabetically.
+    pub fn current_tags(&self) -> Result<Vec<String>> {
+        let Some(conversation) = self.conversation.get() else {
+            return Ok(Vec::new());
+        };
+        let mut stmt = self.conn.prepare(
+            "SELECT t.name FROM conversation_tags ct JOIN tags t ON t.id = ct.tag_id
+             WHERE ct.conversation_id = ?1
+             ORDER BY t.name",
+        )?;
+        let rows = stmt
+            .query_map(params![conversation], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Every tag on at least one conversation, alphabetically.
+    pub fn tags(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT t.name FROM tags t JOIN conversation_tags ct ON ct.tag_id = t.id
+             ORDER BY t.name",
+        )?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Every message matching `filter`, oldest first.
+    pub fn filtered_messages(&self, filter: &MessageFilter) -> Result<Vec<LoggedMessage>> {
+        let filter = filter.sql();
+        let rows = self
+            .conn
+            .prepare(&format!(
+                "{} WHERE replaced = 0 AND {} ORDER BY id",
+                LOGGED_MESSAGE_COLUMNS,
+                filter.condition
+            ))?
+            .query_map(filter.params(&[]).as_slice(), logged_message)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Up to `limit` messages matching `filter`, oldest first, skipping the
+    /// first `offset`.
+    pub fn messages_page(
+        &self,
+        filter: &MessageFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<LoggedMessage>> {
+        let filter = filter.sql();
+        let (limit, offset) = (limit as i64, offset as i64);
+        let params = filter.params(&[(":limit", &limit), (":offset", &offset)]);
+        let rows = self
+            .conn
+            .prepare(&format!(
+                "{} WHERE replaced = 0 AND {} ORDER BY id LIMIT :limit OFFSET :offset",
+                LOGGED_MESSAGE_COLUMNS,
+                filter.condition
+            ))?
+            .query_map(params.as_slice(), logged_message)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Up to `limit` of the messages matching `filter` from every provider,
+    /// ordered by when they were sent and skipping the first `offset`.
+    pub fn timeline(
+        &self,
+        filter: &MessageFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<LoggedMessage>> {
+        let filter = filter.sql();
+        let (limit, offset) = (limit as i64, offset as i64);
+        let params = filter.params(&[(":limit", &limit), (":offset", &offset)]);
+        let rows = self
+            .conn
+            .prepare(&format!(
+                "{} WHERE replaced = 0 AND {} ORDER BY timestamp, id LIMIT :limit OFFSET :offset",
+                LOGGED_MESSAGE_COLUMNS,
+                filter.condition
+            ))?
+            .query_map(params.as_slice(), logged_message)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// How many messages `timeline` has to page through for `filter`.
+    pub fn timeline_len(&self, filter: &MessageFilter) -> Result<usize> {
+        let filter = filter.sql();
+        let count: i64 = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM messages WHERE replaced = 0 AND {}", filter.condition),
+            filter.params(&[]).as_slice(),
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Where message `id` falls among the messages matching `filter`, oldest
+    /// first; `None` when the filter leaves it out.
+    pub fn message_position(&self, filter: &MessageFilter, id: i64) -> Result<Option<usize>> {
+        let filter = filter.sql();
+        let (before, present): (i64, bool) = self.conn.query_row(
+            &format!(
+                "SELECT (SELECT COUNT(*) FROM messages WHERE replaced = 0 AND {0} AND id < :id),
+                        EXISTS(SELECT 1 FROM messages WHERE replaced = 0 AND {0} AND id = :id)",
+                filter.condition
+            ),
+            filter.params(&[(":id", &id)]).as_slice(),
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok(present.then_some(before as usize))
+    }
+
+    /// Adds `uses` to the per-key request counters.
+    pub fn record_key_uses(&self, uses: &[KeyUse]) -> Result<()> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+        let tx = self.write_tx()?;
+        let mut stmt = tx.prepare(
+            "INSERT INTO api_key_usage
+                (provider, label, requests, unauthorized, rate_limited, last_used)
+             VALUES (?1, ?2, 1, ?3, ?4, ?5)
+             ON CONFLICT (provider, label) DO UPDATE SET
+                requests = requests + 1,
+                unauthorized = unauthorized + excluded.unauthorized,
+                rate_limited = rate_limited + excluded.rate_limited,
+                last_used = excluded.last_used",
+        )?;
+        for key_use in uses {
+            stmt.execute(params![
+                key_use.provider,
+                key_use.label,
+                (key_use.status == 401) as i64,
+                (key_use.status == 429) as i64,
+                timestamp
+            ])?;
+        }
+        drop(stmt);
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Appends app health metrics, each with the Unix seconds it happened at.
+    pub fn record_metrics(&self, metrics: &[(Metric, i64)]) -> Result<()> {
+        let tx = self.write_tx()?;
+        let mut stmt = tx.prepare(
+            "INSERT INTO app_metrics (event, detail, value, timestamp) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (metric, timestamp) in metrics {
+            let (event, detail, value) = metric.row();
+            stmt.execute(params![event, detail, value, timestamp])?;
+        }
+        drop(stmt);
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Every conversation, oldest first, with its tags and messages.
+    pub fn export_archive(&self) -> Result<Archive> {
+        let mut conversations: Vec<(i64, ArchivedConversation)> = self
+            .conn
+            .prepare(
+                "SELECT c.id, c.provider, c.title, c.created_at, c.archived,
+                        (SELECT COALESCE(GROUP_CONCAT(t.name, ','), '')
+                         FROM conversation_tags ct JOIN tags t ON t.id = ct.tag_id
+                         WHERE ct.conversation_id = c.id),
+                        (SELECT MAX(COALESCE(MAX(timestamp), 0), COALESCE(MAX(deleted_at), 0))
+                         FROM messages WHERE conversation_id = c.id),
+                        c.summary
+                 FROM conversations c
+                 ORDER BY c.id",
+            )?
+            .query_map([], |row| {
+                let tags: String = row.get(5)?;
+                Ok((
+                    row.get(0)?,
+                    ArchivedConversation {
+                        provider: row.get(1)?,
+                        title: row.get(2)?,
+                        created_at: row.get(3)?,
+                        archived: row.get(4)?,
+                        tags: tags
+                            .split(',')
+                            .filter(|tag| !tag.is_empty())
+                            .map(Into::into)
+                            .collect(),
+                        updated_at: row.get(6)?,
+                        summary: row.get(7)?,
+                        messages: Vec::new(),
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT provider, role, kind, content, timestamp, replaced,
+                    EXISTS(SELECT 1 FROM bookmarks WHERE message_id = messages.id),
+                    model, latency_ms, http_status, input_tokens, output_tokens,
+                    cache_read_tokens, cache_write_tokens, cost_usd, finish_reason, id,
+                    (SELECT json_group_array(json_object('content', content, 'timestamp', timestamp))
+                     FROM (SELECT content, timestamp FROM message_revisions
+                           WHERE message_id = messages.id ORDER BY id))
+             FROM messages
+             WHERE conversation_id = ?1 AND deleted_at IS NULL
+             ORDER BY id",
+        )?;
+        let mut attachments = self.conn.prepare(
+            "SELECT a.filename, a.mime, b.data
+             FROM attachments a JOIN attachment_blobs b ON b.sha256 = a.sha256
+             WHERE a.message_id = ?1
+             ORDER BY a.id",
+        )?;
+        for (id, conversation) in &mut conversations {
+            let messages = stmt
+                .query_map(params![*id], |row| {
+                    let message = ArchivedMessage {
+                        provider: row.get(0)?,
+                        role: row.get(1)?,
+                        kind: row.get(2)?,
+                        content: row.get(3)?,
+                        timestamp: row.get(4)?,
+                        replaced: row.get(5)?,
+                        bookmarked: row.get(6)?,
+                        model: row.get(7)?,
+                        latency_ms: row.get(8)?,
+                        http_status: row.get(9)?,
+                        input_tokens: row.get(10)?,
+                        output_tokens: row.get(11)?,
+                        cache_read_tokens: row.get(12)?,
+                        cache_write_tokens: row.get(13)?,
+                        cost_usd: row.get(14)?,
+                        finish_reason: row.get(15)?,
+                        attachments: Vec::new(),
+                        revisions: serde_json::from_str(&row.get::<_, String>(17)?)
+                            .unwrap_or_default(),
+                    };
+                    Ok((row.get::<_, i64>(16)?, message))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for (message_id, mut message) in messages {
+                message.attachments = attachments
+                    .query_map(params![message_id], |row| {
+                        Ok(ArchivedAttachment::new(row.get(0)?, row.get(1)?, row.get(2)?))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                conversation.messages.push(message);
+            }
+        }
+        Ok(Archive {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            exported_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs() as i64,
+            conversations: conversations
+                .into_iter()
+                .map(|(_, conversation)| conversation)
+                .collect(),
+        })
+    }
+
+    /// Adds `archive`'s conversations as new ones, in one transaction. A
+    /// conversation already here (same provider, title and start) is skipped,
+    /// so importing the same file twice changes nothing; with `newer_wins` it
+    /// is replaced instead when the archive's copy was written to last. One
+    /// whose every message is already archived, e.g. under a title changed
+    /// since, is skipped too.
+    pub fn import_archive(&self, archive: &Archive, newer_wins: bool) -> Result<ImportSummary> {
+        if archive.format != ARCHIVE_FORMAT {
+            return Err(anyhow!("not an asciivision archive"));
+        }
+        if archive.version > ARCHIVE_VERSION {
+            return Err(anyhow!(
+                "archive version {} is newer than this build reads ({})",
+                archive.version,
+                ARCHIVE_VERSION
+            ));
+        }
+        let mut summary = ImportSummary {
+            conversations: 0,
+            updated: 0,
+            messages: 0,
+            skipped: 0,
+        };
+        let tx = self.write_tx()?;
+        for conversation in &archive.conversations {
+            let existing: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM conversations
+                     WHERE provider = ?1 AND title = ?2 AND created_at = ?3",
+                    params![conversation.provider, conversation.title, conversation.created_at],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let id = match existing {
+                Some(id) => {
+                    let updated_at: i64 = tx.query_row(
+                        "SELECT MAX(COALESCE(MAX(timestamp), 0), COALESCE(MAX(deleted_at), 0))
+                         FROM messages WHERE conversation_id = ?1",
+                        params![id],
+                        |row| row.get(0),
+                    )?;
+                    if !newer_wins || conversation.updated_at() <= updated_at {
+                        summary.skipped += 1;
+                        continue;
+                    }
+                    tx.execute(
+                        "DELETE FROM bookmarks
+                         WHERE message_id IN (SELECT id FROM messages WHERE conversation_id = ?1)",
+                        params![id],
+                    )?;
+                    tx.execute("DELETE FROM messages WHERE conversation_id = ?1", params![id])?;
+                    tx.execute_batch(DROP_ORPHAN_ROWS)?;
+                    tx.execute(
+                        "DELETE FROM conversation_tags WHERE conversation_id = ?1",
+                        params![id],
+                    )?;
+                    tx.execute(
+                        "UPDATE conversations SET archived = ?2, summary = ?3 WHERE id = ?1",
+                        params![id, conversation.archived, conversation.summary],
+                    )?;
+                    summary.updated += 1;
+                    id
+                }
+                None if already_archived(&tx, &conversation.messages)? => {
+                    summary.skipped += 1;
+                    continue;
+                }
+                None => {
+                    tx.execute(
+                        "INSERT INTO conversations (provider, title, created_at, archived, summary)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![
+                            conversation.provider,
+                            conversation.title,
+                            conversation.created_at,
+                            conversation.archived,
+                            conversation.summary
+                        ],
+                    )?;
+                    summary.conversations += 1;
+                    tx.last_insert_rowid()
+                }
+            };
+            for tag in &conversation.tags {
+                tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", params![tag])?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO conversation_tags (conversation_id, tag_id)
+                     SELECT ?1, id FROM tags WHERE name = ?2",
+                    params![id, tag],
+                )?;
+            }
+            for message in &conversation.messages {
+                tx.execute(
+                    "INSERT INTO messages (
+                        provider, role, kind, content, timestamp, replaced,
+                        model, latency_ms, http_status, input_tokens, output_tokens,
+                        cache_read_tokens, cache_write_tokens, cost_usd, finish_reason,
+                        conversation_id, content_hash
+                     )
+                     VALUES (
+                        ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17
+                     )",
+                    params![
+                        message.provider,
+                        message.role,
+                        message.kind,
+                        message.content,
+                        message.timestamp,
+                        message.replaced,
+                        message.model,
+                        message.latency_ms,
+                        message.http_status,
+                        message.input_tokens,
+                        message.output_tokens,
+                        message.cache_read_tokens,
+                        message.cache_write_tokens,
+                        message.cost_usd,
+                        message.finish_reason,
+                        id,
+                        sha256_hex(message.content.as_bytes())
+                    ],
+                )?;
+                let message_id = tx.last_insert_rowid();
+                if message.bookmarked {
+                    tx.execute(
+                        "INSERT INTO bookmarks (message_id, timestamp) VALUES (?1, ?2)",
+                        params![message_id, message.timestamp],
+                    )?;
+                }
+                for attachment in &message.attachments {
+                    insert_attachment(
+                        &tx,
+                        message_id,
+                        &attachment.filename,
+                        &attachment.mime,
+                        &attachment.data()?,
+                    )?;
+                }
+                for revision in &message.revisions {
+                    tx.execute(
+                        "INSERT INTO message_revisions (message_id, content, timestamp)
+                         VALUES (?1, ?2, ?3)",
+                        params![message_id, revision.content, revision.timestamp],
+                    )?;
+                }
+            }
+            summary.messages += conversation.messages.len();
+        }
+        tx.commit()?;
+        Ok(summary)
+    }
+
+    /// Every provider key with archived messages, alphabetically.
+    pub fn providers(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT provider FROM messages ORDER BY provider")?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Counters for every key that has made a request, grouped by provider.
+    pub fn key_usage(&self) -> Result<Vec<KeyUsage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT provider, label, requests, unauthorized, rate_limited, last_used
+             FROM api_key_usage
+             ORDER BY provider, label",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(KeyUsage {
+                    provider: row.get(0)?,
+                    label: row.get(1)?,
+                    requests: row.get(2)?,
+                    unauthorized: row.get(3)?,
+                    rate_limited: row.get(4)?,
+                    last_used: row.get(5)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Replaces every chunk indexed for `path` under `model`.
+    pub fn replace_rag_chunks(
+        &self,
+        path: &str,
+        model: &str,
+        chunks: &[(String, Vec<f32>)],
+    ) -> Result<()> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+        let tx = self.write_tx()?;
+        tx.execute(
+            "DELETE FROM rag_chunks WHERE path = ?1 AND model = ?2",
+            params![path, model],
+        )?;
+        for (index, (content, embedding)) in chunks.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO rag_chunks (path, chunk, model, content, embedding, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![path, index as i64, model, content, embedding_blob(embedding), timestamp],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Every chunk embedded with `model`.
+    pub fn rag_chunks(&self, model: &str) -> Result<Vec<StoredChunk>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT path, content, embedding FROM rag_chunks WHERE model = ?1 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map(params![model], |row| {
+                Ok(StoredChunk {
+                    path: row.get(0)?,
+                    content: row.get(1)?,
+                    embedding: blob_embedding(&row.get::<_, Vec<u8>>(2)?),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    pub fn rag_chunk_count(&self, model: &str) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM rag_chunks WHERE model = ?1",
+            params![model],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Prompts and replies, oldest first, with no embedding from `model` yet.
+    pub fn unembedded_messages(&self, model: &str) -> Result<Vec<(i64, String)>> {
+        let rows = self
+            .conn
+            .prepare(&format!(
+                "SELECT id, content FROM messages
+                 WHERE kind = 'chat' AND replaced = 0 AND content != '' AND {}
+                   AND id NOT IN (SELECT message_id FROM message_embeddings WHERE model = ?1)
+                 ORDER BY id",
+                VISIBLE
+            ))?
+            .query_map(params![model], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    pub fn save_message_embeddings(&self, model: &str, vectors: &[(i64, Vec<f32>)]) -> Result<()> {
+        let tx = self.write_tx()?;
+        for (message_id, embedding) in vectors {
+            tx.execute(
+                "INSERT OR REPLACE INTO message_embeddings (message_id, model, embedding)
+                 VALUES (?1, ?2, ?3)",
+                params![message_id, model, embedding_blob(embedding)],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Every visible message's vector from `model`, by message id.
+    pub fn message_embeddings(&self, model: &str) -> Result<Vec<(i64, Vec<f32>)>> {
+        let rows = self
+            .conn
+            .prepare(&format!(
+                "SELECT message_embeddings.message_id, message_embeddings.embedding
+                 FROM message_embeddings JOIN messages ON messages.id = message_embeddings.message_id
+                 WHERE message_embeddings.model = ?1 AND replaced = 0 AND {}",
+                VISIBLE
+            ))?
+            .query_map(params![model], |row| {
+                Ok((row.get(0)?, blob_embedding(&row.get::<_, Vec<u8>>(1)?)))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Message `id` with the other half of its exchange: the reply that
+    /// answered a prompt, or the prompt a reply answered. Oldest first.
+    pub fn exchange(&self, id: i64) -> Result<Vec<LoggedMessage>> {
+        let query = format!("{} WHERE id = ?1", LOGGED_MESSAGE_COLUMNS);
+        let Some(message) = self.conn.query_row(&query, params![id], logged_message).optional()?
+        else {
+            return Ok(Vec::new());
+        };
+        let (role, direction) = match message.role.as_str() {
+            "user" => ("assistant", "> ?1 ORDER BY id"),
+            _ => ("user", "< ?1 ORDER BY id DESC"),
+        };
+        // the nearest one in the same conversation, stopping at the next
+        // message of the same role
+        let other = self
+            .conn
+            .query_row(
+                &format!(
+                    "{} WHERE conversation_id IS (SELECT conversation_id FROM messages WHERE id = ?1)
+                       AND kind = 'chat' AND replaced = 0 AND {} AND id {} LIMIT 1",
+                    LOGGED_MESSAGE_COLUMNS, VISIBLE, direction
+                ),
+                params![id],
+                logged_message,
+            )
+            .optional()?
+            .filter(|other| other.role == role);
+        Ok(match other {
+            Some(other) if other.id < message.id => vec![other, message],
+            Some(other) => vec![message, other],
+            None => vec![message],
+        })
+    }
+}
+
+/// Little-endian `f32`s, as embeddings are stored.
+fn embedding_blob(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn blob_embedding(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+/// Up to 60 bytes either side of `found` (the start of `content` without
+/// it), with `found` wrapped in the match markers.
+fn range_snippet(content: &str, found: Option<std::ops::Range<usize>>) -> String {
+    const CONTEXT: usize = 60;
+    let found = found.unwrap_or(0..0);
+    let mut start = found.start.saturating_sub(CONTEXT);
+    while !content.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (found.end + CONTEXT).min(content.len());
+    while !content.is_char_boundary(end) {
+        end += 1;
+    }
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push_str("...");
+    }
+    snippet.push_str(&content[start..found.start]);
+    if !found.is_empty() {
+        snippet.push(MATCH_START);
+        snippet.push_str(&content[found.clone()]);
+        snippet.push(MATCH_END);
+    }
+    snippet.push_str(&content[found.end..end]);
+    if end < content.len() {
+        snippet.push_str("...");
+    }
+    snippet
+}
+
+/// A snippet of `content` around the first match of `words`, with every match
+/// wrapped in `MATCH_START`/`MATCH_END` (the `LIKE` fallback's `snippet()`).
+fn like_snippet(content: &str, words: &[&str]) -> String {
+    const CONTEXT: usize = 60;
+    let flat = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let lower = flat.to_lowercase();
+    // lowercasing can change byte lengths; fall back to the start of the text then
+    let aligned = lower.len() == flat.len();
+    let needles: Vec<String> = words.iter().map(|word| word.to_lowercase()).collect();
+    let first = needles
+        .iter()
+        .filter_map(|needle| lower.find(needle.as_str()))
+        .min()
+        .filter(|_| aligned)
+        .unwrap_or(0);
+    let mut start = first.saturating_sub(CONTEXT);
+    while !flat.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (first + CONTEXT * 2).min(flat.len());
+    while !flat.is_char_boundary(end) {
+        end += 1;
+    }
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push_str("...");
+    }
+    let mut index = start;
+    while index < end {
+        let hit = needles
+            .iter()
+            .filter(|_| aligned && lower.is_char_boundary(index))
+            .filter(|needle| !needle.is_empty() && lower[index..].starts_with(needle.as_str()))
+            .map(|needle| needle.len())
+            .filter(|len| flat.is_char_boundary(index + len))
+            .max();
+        match hit {
+            Some(len) => {
+                snippet.push(MATCH_START);
+                snippet.push_str(&flat[index..index + len]);
+                snippet.push(MATCH_END);
+                index += len;
+            }
+            None => {
+                let ch = flat[index..].chars().next().unwrap_or(' ');
+                snippet.push(ch);
+                index += ch.len_utf8();
+            }
+        }
+    }
+    if end < flat.len() {
+        snippet.push_str("...");
+    }
+    snippet
+}
+
+/// `asciivision db export` output: every conversation with its tags and
+/// messages, for moving history to another machine.
+#[derive(Serialize, Deserialize)]
+pub struct Archive {
+    /// Always `asciivision-archive`.
+    pub format: String,
+    pub version: u32,
+    /// Unix seconds.
+    pub exported_at: i64,
+    pub conversations: Vec<ArchivedConversation>,
+}
+
+const ARCHIVE_FORMAT: &str = "asciivision-archive";
+const ARCHIVE_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchivedConversation {
+    pub provider: String,
+    pub title: String,
+    pub created_at: i64,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Unix seconds of the last message saved or deleted; 0 in archives that
+    /// predate it.
+    #[serde(default)]
+    pub updated_at: i64,
+    /// The `/summarize` summary, if any.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub summary: String,
+    pub messages: Vec<ArchivedMessage>,
+}
+
+impl ArchivedConversation {
+    /// When this copy was last written to, for last-write-wins merges.
+    fn updated_at(&self) -> i64 {
+        let newest = self.messages.iter().map(|message| message.timestamp).max();
+        self.updated_at.max(newest.unwrap_or(self.created_at))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchivedMessage {
+    pub provider: String,
+    pub role: String,
+    pub kind: String,
+    pub content: String,
+    pub timestamp: i64,
+    #[serde(default)]
+    pub replaced: bool,
+    #[serde(default)]
+    pub bookmarked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_tokens: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_tokens: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read_tokens: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write_tokens: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<ArchivedAttachment>,
+    /// Earlier versions of an edited prompt, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revisions: Vec<ArchivedRevision>,
+}
+
+/// A text file is carried as text and anything else, such as a pasted image,
+/// as base64.
+#[derive(Serialize, Deserialize)]
+pub struct ArchivedAttachment {
+    pub filename: String,
+    pub mime: String,
+    /// Version 1 archives only hold text and leave it out.
+    #[serde(default)]
+    pub encoding: AttachmentEncoding,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentEncoding {
+    #[default]
+    Text,
+    Base64,
+}
+
+impl ArchivedAttachment {
+    fn new(filename: String, mime: String, data: Vec<u8>) -> Self {
+        let (encoding, content) = match String::from_utf8(data) {
+            Ok(text) => (AttachmentEncoding::Text, text),
+            Err(error) => (
+                AttachmentEncoding::Base64,
+                base64::engine::general_purpose::STANDARD.encode(error.as_bytes()),
+            ),
+        };
+        Self {
+            filename,
+            mime,
+            encoding,
+            content,
+        }
+    }
+
+    /// The file's bytes, as they were stored before export.
+    fn data(&self) -> Result<Vec<u8>> {
+        match self.encoding {
+            AttachmentEncoding::Text => Ok(self.content.as_bytes().to_vec()),
+            AttachmentEncoding::Base64 => base64::engine::general_purpose::STANDARD
+                .decode(&self.content)
+                .with_context(|| format!("attachment {} is not valid base64", self.filename)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchivedRevision {
+    pub content: String,
+    pub timestamp: i64,
+}
+
+/// `[retention]` in config.toml: limits applied on startup and by
+/// `asciivision db prune`. Unset limits (the default) keep everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Delete messages older than this many days.
+    pub max_age_days: Option<u64>,
+    /// Keep at most this many messages, newest first; 0 keeps none but the
+    /// bookmarked ones.
+    pub max_messages: Option<u64>,
+    /// Delete the oldest messages until the file is roughly this size.
+    pub max_size_mb: Option<u64>,
+}
+
+impl RetentionPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.max_age_days.is_none() && self.max_messages.is_none() && self.max_size_mb.is_none()
+    }
+}
+
+/// What `prune` deleted, or would delete on a dry run.
+pub struct PruneReport {
+    pub messages: usize,
+    /// Conversations left without messages, removed along with them.
+    pub conversations: usize,
+    /// Timestamp of the newest message removed.
+    pub through: Option<i64>,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    /// Automatic backup taken before anything was deleted.
+    pub backup: Option<PathBuf>,
+}
+
+/// What `Database::stats` found: the dashboard's About panel and
+/// `asciivision db stats`.
+pub struct DatabaseStats {
+    pub path: Option<PathBuf>,
+    pub bytes: u64,
+    /// Pending in the write-ahead log, not yet checkpointed into the file.
+    pub wal_bytes: u64,
+    /// Unused pages a prune or delete left behind.
+    pub free_bytes: u64,
+    pub schema_version: i64,
+    pub messages: usize,
+    pub conversations: usize,
+    pub bookmarks: usize,
+    /// Soft-deleted messages and archived conversations, until purged.
+    pub deleted: usize,
+    pub archived: usize,
+    /// Timestamps of the oldest and newest message.
+    pub oldest: Option<i64>,
+    pub newest: Option<i64>,
+    /// Most messages first.
+    pub providers: Vec<ProviderCount>,
+    /// The conversations with the most messages, largest first.
+    pub largest: Vec<ConversationCount>,
+    /// `ok`, or the first problem `PRAGMA quick_check` reports.
+    pub integrity: String,
+    /// Expected indexes that are missing.
+    pub missing_indexes: Vec<String>,
+    /// Rows in the search index, or `None` without FTS5.
+    pub fts_rows: Option<usize>,
+}
+
+impl DatabaseStats {
+    /// `ok`, or the problems found, joined with `; `.
+    pub fn health(&self) -> String {
+        let mut problems = Vec::new();
+        if self.integrity != "ok" {
+            problems.push(format!("quick_check: {}", self.integrity));
+        }
+        for index in &self.missing_indexes {
+            problems.push(format!("index {} missing", index));
+        }
+        match self.fts_rows {
+            Some(rows) if rows != self.messages => problems.push(format!(
+                "search index holds {} of {} messages",
+                rows, self.messages
+            )),
+            None => problems.push("no full-text search (FTS5 unavailable)".to_string()),
+            Some(_) => {}
+        }
+        if problems.is_empty() {
+            "ok".to_string()
+        } else {
+            problems.join("; ")
+        }
+    }
+}
+
+pub struct ProviderCount {
+    pub provider: String,
+    pub messages: usize,
+    pub conversations: usize,
+}
+
+pub struct ConversationCount {
+    pub provider: String,
+    pub title: String,
+    pub messages: usize,
+}
+
+/// A message `Database::duplicates` found saved more than once.
+pub struct Duplicate {
+    /// The first copy, which a merge keeps.
+    pub original: LoggedMessage,
+    /// Ids of the later copies, oldest first.
+    pub copies: Vec<i64>,
+}
+
+/// What `import_archive` added and what it left out as already present.
+pub struct ImportSummary {
+    pub conversations: usize,
+    /// Existing conversations replaced by a newer copy.
+    pub updated: usize,
+    pub messages: usize,
+    pub skipped: usize,
+}
+
+/// A backup made from the analytics dashboard.
+pub struct BackupFile {
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Unix seconds of the last write.
+    pub modified: i64,
+}
+
+/// `backups/` next to the archive at `db_path`.
+pub fn backup_dir(db_path: &Path) -> PathBuf {
+    db_path.parent().unwrap_or(Path::new(".")).join("backups")
+}
+
+/// `backups/<name>-<time>.db` for a new backup of the archive at `db_path`.
+pub fn backup_path(db_path: &Path) -> PathBuf {
+    backup_dir(db_path).join(format!(
+        "{}-{}.db",
+        backup_stem(db_path),
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ))
+}
+
+/// Sets automatic backups apart from the ones made by hand.
+const AUTO_BACKUP_MARK: &str = "-before-";
+
+/// `backups/<name>-<time>-before-<reason>.db` for `Database::snapshot`.
+fn auto_backup_path(db_path: &Path, reason: &str) -> PathBuf {
+    backup_dir(db_path).join(format!(
+        "{}-{}{}{}.db",
+        backup_stem(db_path),
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        AUTO_BACKUP_MARK,
+        reason
+    ))
+}
+
+fn backup_stem(db_path: &Path) -> &str {
+    db_path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("conversations")
+}
+
+/// The `.db` files in `dir`, newest first.
+pub fn list_backups(dir: &Path) -> Vec<BackupFile> {
+    let mut backups: Vec<BackupFile> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "db"))
+                .filter_map(|entry| {
+                    let meta = entry.metadata().ok()?;
+                    let modified = meta.modified().ok()?.duration_since(std::time::UNIX_EPOCH);
+                    Some(BackupFile {
+                        path: entry.path(),
+                        bytes: meta.len(),
+                        modified: modified.ok()?.as_secs() as i64,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    backups.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| b.path.cmp(&a.path)));
+    backups
+}
+
+/// Busy handler for read-only connections: counts the wait and retries every
+/// 10ms until `BUSY_TIMEOUT`.
+fn wait_for_lock(attempt: i32) -> bool {
+    BUSY_WAITS.fetch_add(1, Ordering::Relaxed);
+    std::thread::sleep(Duration::from_millis(10));
+    (attempt as u128) < BUSY_TIMEOUT.as_millis() / 10
+}
+
+/// First line of a conversation's opening prompt, shortened for the dashboard.
+fn conversation_title(prompt: &str) -> String {
+    let line = prompt.lines().find(|line| !line.trim().is_empty()).unwrap_or("").trim();
+    if line.chars().count() <= 80 {
+        line.to_string()
+    } else {
+        let cut: String = line.chars().take(77).collect();
+        format!("{}...", cut.trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// One conversation of `(role, content, timestamp)` chat messages.
+    fn conversation(
+        provider: &str,
+        title: &str,
+        tags: &[&str],
+        messages: &[(&str, &str, i64)],
+    ) -> serde_json::Value {
+        let messages: Vec<_> = messages
+            .iter()
+            .map(|(role, content, timestamp)| {
+                json!({
+                    "provider": provider,
+                    "role": role,
+                    "kind": "chat",
+                    "content": content,
+                    "timestamp": timestamp,
+                })
+            })
+            .collect();
+        json!({
+            "provider": provider,
+            "title": title,
+            "created_at": messages[0]["timestamp"],
+            "tags": tags,
+            "messages": messages,
+        })
+    }
+
+    fn archive(conversations: Vec<serde_json::Value>) -> Archive {
+        serde_json::from_value(json!({
+            "format": ARCHIVE_FORMAT,
+            "version": ARCHIVE_VERSION,
+            "exported_at": 1000,
+            "conversations": conversations,
+        }))
+        .expect("fixture archive")
+    }
+
+    fn attachment(name: &str, data: &[u8]) -> Attachment {
+        Attachment {
+            name: name.to_string(),
+            content: String::from_utf8_lossy(data).into_owned(),
+            bytes: data.len(),
+            truncated: false,
+            data: data.to_vec(),
+            image_type: None,
+            thumbnail: None,
+        }
+    }
+
+    /// `conversations.db` in a fresh temp directory, for tests that need the
+    /// file itself: a second connection, or a backup beside it.
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("asciivision-{}-{}", name, uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        dir.join("conversations.db")
+    }
+
+    fn contents(db: &Database, filter: &MessageFilter) -> Vec<String> {
+        let messages = db.filtered_messages(filter).expect("filtered messages");
+        messages.into_iter().map(|message| message.content).collect()
+    }
+
+    #[test]
+    fn version_0_archives_migrate() {
+        let conn = Connection::open_in_memory().expect("connection");
+        conn.execute_batch(
+            "CREATE TABLE messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                provider TEXT NOT NULL,
+                role TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                content TEXT NOT NULL,
+                timestamp INTEGER NOT NULL
+             );
+             INSERT INTO messages (provider, role, kind, content, timestamp) VALUES
+                ('claude', 'user', 'chat', 'hello claude', 100),
+                ('gpt', 'user', 'chat', 'hello gpt', 150),
+                ('claude', 'assistant', 'chat', 'hello back', 200);",
+        )
+        .expect("version 0 schema");
+
+        let db = Database::prepare(conn).expect("migrate");
+        let version: i64 =
+            db.conn.query_row("PRAGMA user_version", [], |row| row.get(0)).expect("version");
+        assert_eq!(version, SCHEMA_VERSION);
+        assert!(db.migration_backup().is_none());
+        let titles: Vec<(String, i64)> = db
+            .conn
+            .prepare("SELECT title, created_at FROM conversations ORDER BY id")
+            .expect("statement")
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("query")
+            .collect::<rusqlite::Result<_>>()
+            .expect("conversations");
+        let titles: Vec<_> = titles.iter().map(|(title, at)| (title.as_str(), *at)).collect();
+        assert_eq!(titles, [("claude history", 100), ("gpt history", 150)]);
+        let unhashed: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM messages WHERE content_hash IS NULL", [], |row| {
+                row.get(0)
+            })
+            .expect("count");
+        assert_eq!(unhashed, 0);
+        assert_eq!(contents(&db, &MessageFilter::default()).len(), 3);
+        assert_eq!(db.search("claude", &MessageFilter::default(), 10).expect("search").len(), 1);
+    }
+
+    #[test]
+    fn version_1_sessions_become_conversations() {
+        let conn = Connection::open_in_memory().expect("connection");
+        conn.execute_batch(
+            "CREATE TABLE sessions (id INTEGER PRIMARY KEY, title TEXT NOT NULL, started INTEGER);
+             CREATE TABLE messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                provider TEXT NOT NULL,
+                role TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                content TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                session_id INTEGER
+             );
+             INSERT INTO sessions (id, title, started) VALUES (7, 'planning', 100);
+             INSERT INTO messages (provider, role, kind, content, timestamp, session_id) VALUES
+                ('claude', 'user', 'chat', 'plan it', 100, 7),
+                ('claude', 'user', 'chat', 'loose', 300, NULL);
+             PRAGMA user_version = 1;",
+        )
+        .expect("version 1 schema");
+
+        let db = Database::prepare(conn).expect("migrate");
+        let conversation = |content: &str| -> String {
+            db.conn
+                .query_row(
+                    "SELECT c.title FROM messages m JOIN conversations c
+                        ON c.id = m.conversation_id
+                     WHERE m.content = ?1",
+                    params![content],
+                    |row| row.get(0),
+                )
+                .expect("conversation")
+        };
+        assert_eq!(conversation("plan it"), "planning");
+        assert_eq!(conversation("loose"), "claude history");
+        let sessions: bool = db
+            .conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'sessions')",
+                [],
+                |row| row.get(0),
+            )
+            .expect("sessions");
+        assert!(!sessions);
+    }
+
+    #[test]
+    fn writes_wait_out_a_lock_while_readers_carry_on() {
+        let path = temp_path("wal");
+        let db = Database::open(path.clone()).expect("database");
+        let mode: String =
+            db.conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).expect("mode");
+        assert_eq!(mode, "wal");
+        db.save_message("claude", "user", "chat", "first", None).expect("save");
+
+        // another process takes the write lock for a moment
+        let (locked, wait) = std::sync::mpsc::channel();
+        let holder = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                let other = Database::open(path).expect("second connection");
+                let tx = other.write_tx().expect("lock");
+                locked.send(()).expect("signal");
+                std::thread::sleep(Duration::from_millis(200));
+                tx.commit().expect("commit");
+            })
+        };
+        wait.recv().expect("locked");
+        let reader = Database::open_read_only(path.clone()).expect("reader");
+        assert_eq!(contents(&reader, &MessageFilter::default()), ["first"]);
+        db.save_message("claude", "assistant", "chat", "second", None).expect("waited");
+        holder.join().expect("holder");
+        assert_eq!(contents(&reader, &MessageFilter::default()), ["first", "second"]);
+        let _ = std::fs::remove_dir_all(path.parent().expect("temp dir"));
+    }
+
+    #[test]
+    fn filter_values_are_bound() {
+        let db = Database::open_in_memory().expect("database");
+        db.import_archive(
+            &archive(vec![
+                conversation("o'brien", "quoted", &["it's mine"], &[("user", "quoted one", 100)]),
+                conversation("claude", "plain", &[], &[("user", "plain one", 200)]),
+            ]),
+            false,
+        )
+        .expect("import");
+
+        let provider = MessageFilter {
+            provider: Some("o'brien".to_string()),
+            ..MessageFilter::default()
+        };
+        assert_eq!(contents(&db, &provider), ["quoted one"]);
+        let tag = MessageFilter {
+            tag: Some("it's mine".to_string()),
+            ..MessageFilter::default()
+        };
+        assert_eq!(contents(&db, &tag), ["quoted one"]);
+        let injected = MessageFilter {
+            provider: Some("x' OR '1'='1".to_string()),
+            ..MessageFilter::default()
+        };
+        assert!(contents(&db, &injected).is_empty());
+        let window = MessageFilter {
+            since: Some(150),
+            role: Some("user"),
+            ..MessageFilter::default()
+        };
+        assert_eq!(contents(&db, &window), ["plain one"]);
+
+        // filter values next to a statement's own parameters
+        assert_eq!(db.timeline(&provider, 0, 10).expect("timeline").len(), 1);
+        assert_eq!(db.search("one", &window, 10).expect("search").len(), 1);
+        let id = db.filtered_messages(&window).expect("messages")[0].id;
+        assert_eq!(db.message_position(&window, id).expect("position"), Some(0));
+    }
+
+    #[test]
+    fn export_round_trips_into_another_database() {
+        let db = Database::open_in_memory().expect("database");
+        db.save_message("claude", "user", "chat", "question", None).expect("save");
+        let answer = db.save_message("claude", "assistant", "chat", "answer", None).expect("save");
+        db.add_tag("claude", "work").expect("tag");
+        db.toggle_bookmark(answer).expect("bookmark");
+
+        let copy = Database::open_in_memory().expect("database");
+        let archive = db.export_archive().expect("export");
+        let summary = copy.import_archive(&archive, false).expect("import");
+        assert_eq!((summary.conversations, summary.messages), (1, 2));
+        assert_eq!(contents(&copy, &MessageFilter::default()), ["question", "answer"]);
+        assert_eq!(copy.tags().expect("tags"), ["work"]);
+        let bookmarks = copy.bookmarks(10).expect("bookmarks");
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].content, "answer");
+
+        let again = copy.import_archive(&archive, false).expect("import again");
+        assert_eq!((again.conversations, again.skipped), (0, 1));
+        assert_eq!(contents(&copy, &MessageFilter::default()).len(), 2);
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_messages() {
+        let lines: Vec<(&str, &str, i64)> = vec![
+            ("user", "first", 100),
+            ("assistant", "second", 110),
+            ("user", "third", 200),
+            ("assistant", "fourth", 210),
+        ];
+        let pruned = |keep: u64| -> (Vec<String>, PruneReport) {
+            let db = Database::open_in_memory().expect("database");
+            db.import_archive(&archive(vec![conversation("claude", "t", &[], &lines)]), false)
+                .expect("import");
+            let policy = RetentionPolicy {
+                max_messages: Some(keep),
+                ..RetentionPolicy::default()
+            };
+            let report = db.prune(&policy, false, false).expect("prune");
+            (contents(&db, &MessageFilter::default()), report)
+        };
+
+        let (kept, report) = pruned(3);
+        assert_eq!(kept, ["second", "third", "fourth"]);
+        assert_eq!((report.messages, report.conversations), (1, 0));
+        assert_eq!(report.through, Some(100));
+        assert_eq!(pruned(1).0, ["fourth"]);
+        let (kept, report) = pruned(0);
+        assert!(kept.is_empty());
+        assert_eq!((report.messages, report.conversations), (4, 1));
+        assert_eq!(pruned(10).0.len(), 4);
+    }
+
+    #[test]
+    fn prune_by_age_spares_bookmarks() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("clock")
+            .as_secs() as i64;
+        let db = Database::open_in_memory().expect("database");
+        let mut kept = conversation("claude", "kept", &[], &[("user", "saved", 100)]);
+        kept["messages"][0]["bookmarked"] = json!(true);
+        db.import_archive(
+            &archive(vec![
+                kept,
+                conversation("claude", "old", &[], &[("user", "stale", 200)]),
+                conversation("claude", "new", &[], &[("user", "fresh", now - 60)]),
+            ]),
+            false,
+        )
+        .expect("import");
+        let policy = RetentionPolicy {
+            max_age_days: Some(30),
+            ..RetentionPolicy::default()
+        };
+
+        let report = db.prune(&policy, true, false).expect("dry run");
+        assert_eq!((report.messages, report.conversations), (1, 1));
+        assert_eq!(contents(&db, &MessageFilter::default()).len(), 3);
+
+        let report = db.prune(&policy, false, false).expect("prune");
+        assert_eq!((report.messages, report.through), (1, Some(200)));
+        assert_eq!(contents(&db, &MessageFilter::default()), ["saved", "fresh"]);
+        let report = db.prune(&RetentionPolicy::default(), false, false).expect("no policy");
+        assert_eq!(report.messages, 0);
+    }
+
+    #[test]
+    fn deletes_hide_until_purged() {
+        let db = Database::open_in_memory().expect("database");
+        db.save_message("claude", "user", "chat", "kept", None).expect("save");
+        let gone = db.save_message("claude", "assistant", "chat", "gone", None).expect("save");
+        db.delete_message(gone).expect("delete");
+        let hidden = MessageFilter {
+            hidden: true,
+            ..MessageFilter::default()
+        };
+        assert_eq!(contents(&db, &MessageFilter::default()), ["kept"]);
+        assert_eq!(contents(&db, &hidden), ["kept", "gone"]);
+
+        assert!(db.archive_conversation().expect("archive"));
+        db.save_message("claude", "user", "chat", "fresh", None).expect("save");
+        assert_eq!(contents(&db, &MessageFilter::default()), ["fresh"]);
+        assert_eq!(contents(&db, &hidden), ["kept", "gone", "fresh"]);
+
+        assert_eq!(db.purge(false).expect("purge"), (1, 0));
+        assert_eq!(contents(&db, &hidden), ["kept", "fresh"]);
+        assert_eq!(db.purge(true).expect("purge archived"), (1, 1));
+        assert_eq!(contents(&db, &hidden), ["fresh"]);
+    }
+
+    #[test]
+    fn import_keeps_the_last_write() {
+        let db = Database::open_in_memory().expect("database");
+        let original = [("user", "question", 100), ("assistant", "answer", 110)];
+        let newer = [("user", "question", 100), ("assistant", "better answer", 300)];
+        let older = [("user", "question", 100), ("assistant", "draft", 105)];
+        let import = |lines: &[(&str, &str, i64)], newer_wins: bool| {
+            let copy = archive(vec![conversation("claude", "t", &["kept"], lines)]);
+            db.import_archive(&copy, newer_wins).expect("import")
+        };
+        import(&original, false);
+
+        let summary = import(&newer, false);
+        assert_eq!((summary.updated, summary.skipped), (0, 1));
+        assert_eq!(contents(&db, &MessageFilter::default()), ["question", "answer"]);
+
+        let summary = import(&older, true);
+        assert_eq!((summary.updated, summary.skipped), (0, 1));
+        assert_eq!(contents(&db, &MessageFilter::default()), ["question", "answer"]);
+
+        let summary = import(&newer, true);
+        assert_eq!((summary.conversations, summary.updated, summary.messages), (0, 1, 2));
+        assert_eq!(contents(&db, &MessageFilter::default()), ["question", "better answer"]);
+        let tagged = MessageFilter {
+            tag: Some("kept".to_string()),
+            ..MessageFilter::default()
+        };
+        assert_eq!(contents(&db, &tagged).len(), 2);
+        assert_eq!(db.conversations(&MessageFilter::default()).expect("list").len(), 1);
+    }
+
+    #[test]
+    fn identical_files_share_one_blob() {
+        let db = Database::open_in_memory().expect("database");
+        let files = [attachment("notes.md", b"# notes")];
+        let first = db.save_prompt("claude", "summarize", None, &files).expect("save");
+        let second = db.save_prompt("claude", "again", None, &files).expect("save");
+        let blobs = |db: &Database| -> i64 {
+            db.conn
+                .query_row("SELECT COUNT(*) FROM attachment_blobs", [], |row| row.get(0))
+                .expect("count")
+        };
+        assert_eq!(blobs(&db), 1);
+        for id in [first, second] {
+            let stored = db.attachments(id).expect("attachments");
+            assert_eq!(stored[0].filename, "notes.md");
+            assert_eq!(stored[0].data, b"# notes");
+        }
+
+        db.delete_message(first).expect("delete");
+        db.purge(false).expect("purge");
+        assert_eq!(blobs(&db), 1);
+        db.delete_message(second).expect("delete");
+        db.purge(false).expect("purge");
+        assert_eq!(blobs(&db), 0);
+    }
+
+    #[test]
+    fn edits_keep_earlier_versions() {
+        let db = Database::open_in_memory().expect("database");
+        let id = db.save_prompt("claude", "v1", None, &[]).expect("save");
+        db.save_prompt("claude", "v2", Some(id), &[]).expect("revise");
+        db.delete_message(id).expect("delete");
+        assert!(contents(&db, &MessageFilter::default()).is_empty());
+        db.save_prompt("claude", "v3", Some(id), &[]).expect("revise");
+        assert_eq!(contents(&db, &MessageFilter::default()), ["v3"]);
+        let versions = |db: &Database, id| -> Vec<String> {
+            db.revisions(id)
+                .expect("revisions")
+                .into_iter()
+                .map(|revision| revision.content)
+                .collect()
+        };
+        assert_eq!(versions(&db, id), ["v1", "v2"]);
+
+        let copy = Database::open_in_memory().expect("database");
+        copy.import_archive(&db.export_archive().expect("export"), false).expect("import");
+        let id = copy.filtered_messages(&MessageFilter::default()).expect("messages")[0].id;
+        assert_eq!(versions(&copy, id), ["v1", "v2"]);
+    }
+
+    #[test]
+    fn duplicates_are_found_and_merged() {
+        let path = temp_path("dedup");
+        let db = Database::open(path.clone()).expect("database");
+        let prompt = db.save_message("claude", "user", "chat", "hi", None).expect("save");
+        db.save_message("claude", "assistant", "error", "timed out", None).expect("save");
+        let retry = db.save_message("claude", "user", "chat", "hi", None).expect("retry");
+        assert_eq!(retry, prompt);
+
+        let lines = [("user", "question", 100), ("assistant", "answer", 110)];
+        let mut renamed = lines.to_vec();
+        renamed.push(("user", "follow-up", 120));
+        db.import_archive(&archive(vec![conversation("claude", "first", &[], &lines)]), false)
+            .expect("import");
+        db.import_archive(&archive(vec![conversation("claude", "second", &[], &renamed)]), false)
+            .expect("import again");
+        let duplicates = db.duplicates(&MessageFilter::default()).expect("duplicates");
+        let found: Vec<_> = duplicates
+            .iter()
+            .map(|duplicate| (duplicate.original.content.as_str(), duplicate.copies.len()))
+            .collect();
+        assert_eq!(found, [("question", 1), ("answer", 1)]);
+
+        let (removed, backup) = db.merge_duplicates(&duplicates).expect("merge");
+        assert_eq!(removed, 2);
+        assert!(backup.exists());
+        assert!(db.duplicates(&MessageFilter::default()).expect("duplicates").is_empty());
+        assert_eq!(
+            contents(&db, &MessageFilter::default()),
+            ["hi", "timed out", "question", "answer", "follow-up"]
+        );
+        let _ = std::fs::remove_dir_all(path.parent().expect("temp dir"));
+    }
+
+    #[test]
+    fn prompts_are_saved_with_their_attachments() {
+        let db = Database::open_in_memory().expect("database");
+        let notes = [attachment("notes.md", b"# notes")];
+        let id = db.save_prompt("claude", "first", None, &notes).expect("save");
+        let files = db.attachments(id).expect("attachments");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].filename, "notes.md");
+        assert_eq!(files[0].data, b"# notes");
+
+        let more = [attachment("more.txt", b"more")];
+        assert_eq!(db.save_prompt("claude", "second", Some(id), &more).expect("revise"), id);
+        assert_eq!(contents(&db, &MessageFilter::default()), ["second"]);
+        let revisions = db.revisions(id).expect("revisions");
+        assert_eq!(revisions.iter().map(|r| r.content.as_str()).collect::<Vec<_>>(), ["first"]);
+        assert_eq!(db.attachments(id).expect("attachments").len(), 2);
+    }
+
+    #[test]
+    fn binary_attachments_survive_export() {
+        let db = Database::open_in_memory().expect("database");
+        let png = b"\x89PNG\r\n\x1a\n\x00\xff\xfe";
+        let files = [attachment("shot.png", png), attachment("notes.md", b"# notes")];
+        db.save_prompt("claude", "look", None, &files).expect("save");
+
+        let json = serde_json::to_value(db.export_archive().expect("export")).expect("json");
+        let exported = &json["conversations"][0]["messages"][0]["attachments"];
+        assert_eq!(exported[0]["encoding"], "base64");
+        assert_eq!(exported[1]["encoding"], "text");
+        assert_eq!(exported[1]["content"], "# notes");
+
+        let copy = Database::open_in_memory().expect("database");
+        let archive = serde_json::from_value(json).expect("archive");
+        copy.import_archive(&archive, false).expect("import");
+        let id = copy.filtered_messages(&MessageFilter::default()).expect("messages")[0].id;
+        let imported = copy.attachments(id).expect("attachments");
+        assert_eq!(imported[0].data, png);
+        assert_eq!(imported[0].mime, "image/png");
+        assert_eq!(imported[1].data, b"# notes");
+    }
+
+    #[test]
+    fn like_search_ranks_words_with_wildcards() {
+        let mut db = Database::open_in_memory().expect("database");
+        db.fts = false;
+        db.import_archive(
+            &archive(vec![conversation(
+                "claude",
+                "t",
+                &[],
+                &[
+                    ("user", "snake_case and snake_case again", 100),
+                    ("user", "snake_case once", 200),
+                    ("user", "snakeXcase is no match", 300),
+                ],
+            )]),
+            false,
+        )
+        .expect("import");
+        let hits = db.search("snake_case", &MessageFilter::default(), 10).expect("search");
+        let found: Vec<i64> = hits.iter().map(|hit| hit.timestamp).collect();
+        assert_eq!(found, [100, 200]);
     }
 }
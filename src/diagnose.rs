@@ -0,0 +1,138 @@
+use anyhow::{bail, Result};
+use asciivision_core::ColorDepth;
+use asciivision_terminal::Capabilities;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::config::Config;
+
+/// How long to wait for the terminal to answer the startup queries. Most
+/// answer within a few milliseconds; a slow ssh link may need the rest.
+const PROBE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// `[terminal]`: overrides for what the startup probe decides.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TerminalConfig {
+    /// Ask the terminal for its cell size, graphics support and glyph widths;
+    /// off uses the environment alone.
+    pub probe: bool,
+    /// `auto` (default), `truecolor`, `256` or `16`.
+    pub colors: Option<String>,
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            probe: true,
+            colors: None,
+        }
+    }
+}
+
+impl TerminalConfig {
+    /// The forced color depth, `None` for `auto`.
+    fn colors(&self) -> Result<Option<ColorDepth>> {
+        Ok(match self.colors.as_deref().unwrap_or("auto") {
+            "auto" => None,
+            "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Ansi256),
+            "16" => Some(ColorDepth::Ansi16),
+            other => bail!(
+                "[terminal] colors {:?} ignored, expected auto, truecolor, 256 or 16",
+                other
+            ),
+        })
+    }
+}
+
+/// Works out the terminal's capabilities; raw mode must already be on.
+pub fn detect(config: &TerminalConfig) -> Capabilities {
+    if config.probe {
+        Capabilities::probe(PROBE_TIMEOUT)
+    } else {
+        Capabilities::from_env()
+    }
+}
+
+/// Points the ASCII renderers at the best mode `caps` allows.
+pub fn apply(caps: &Capabilities, config: &TerminalConfig) {
+    let colors = config.colors().unwrap_or_else(|error| {
+        tracing::warn!("{}", error);
+        None
+    });
+    asciivision_core::set_color_depth(colors.unwrap_or(caps.colors));
+    if let Some(aspect) = caps.cell_aspect() {
+        asciivision_core::set_cell_aspect(aspect);
+    }
+    tracing::info!(
+        "terminal {:?}: {} colors, cell {:?} px, kitty {}, sixel {}, wide glyphs {:?}",
+        caps.term,
+        asciivision_core::color_depth().name(),
+        caps.cell_pixels,
+        caps.kitty_graphics,
+        caps.sixel,
+        caps.wide_glyphs
+    );
+}
+
+/// `asciivision --diagnose`: probes the terminal and prints what was found and
+/// what the renderers will do with it.
+pub fn run(config: &Config) -> Result<()> {
+    // raw mode alone, so nothing but the report reaches stdout
+    let caps = match enable_raw_mode() {
+        Ok(()) => {
+            let caps = detect(&config.terminal);
+            disable_raw_mode()?;
+            caps
+        }
+        Err(_) => Capabilities::from_env(),
+    };
+    let forced = config.terminal.colors().unwrap_or_else(|error| {
+        eprintln!("warning: {}", error);
+        None
+    });
+    let yes_no = |on: bool| if on { "yes" } else { "no" };
+    let pixels = |size: Option<(u16, u16)>| match size {
+        Some((width, height)) => format!("{}x{} px", width, height),
+        None => "unknown".to_string(),
+    };
+
+    let program = caps.program.as_deref().map(|p| format!(" ({})", p)).unwrap_or_default();
+    let term = if caps.term.is_empty() { "unset" } else { caps.term.as_str() };
+    println!("terminal       {}{}", term, program);
+    println!("size           {}x{} cells", caps.cells.0, caps.cells.1);
+    println!("colors         {} (from {})", caps.colors.name(), caps.colors_from);
+    println!("utf-8 locale   {}", yes_no(caps.utf8));
+    println!(
+        "wide glyphs    {}",
+        match caps.wide_glyphs {
+            Some(true) => "2 cells",
+            Some(false) => "1 cell, CJK and emoji will misalign",
+            None => "not measured",
+        }
+    );
+    println!("kitty graphics {}", yes_no(caps.kitty_graphics));
+    println!("sixel          {}", yes_no(caps.sixel));
+    println!("window         {}", pixels(caps.window_pixels));
+    println!("cell           {}", pixels(caps.cell_pixels));
+    println!(
+        "probe          {}",
+        match caps.answered {
+            Some(took) => format!("answered in {} ms", took.as_millis()),
+            None if !config.terminal.probe => "off ([terminal] probe = false)".to_string(),
+            None => "no answer, environment only".to_string(),
+        }
+    );
+    println!();
+    let colors = forced.unwrap_or(caps.colors);
+    let source = if forced.is_some() { "[terminal] colors" } else { "detected" };
+    println!("video colors   {} ({})", colors.name(), source);
+    println!(
+        "cell aspect    {:.2} ({})",
+        caps.cell_aspect().unwrap_or(asciivision_core::cell_aspect()),
+        if caps.cell_aspect().is_some() { "measured" } else { "assumed" }
+    );
+    Ok(())
+}
@@ -0,0 +1,114 @@
+//! The chat deck's unsent input and attachments, kept in `draft.json` in the
+//! data directory so a crash, a dropped SSH session or a stray Ctrl+C does not
+//! lose a long prompt. The next launch offers it back.
+
+use anyhow::{Context, Result};
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::attach::Attachment;
+use crate::paths;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Draft {
+    pub input: String,
+    pub attachments: Vec<DraftAttachment>,
+    /// Unix seconds.
+    pub saved_at: i64,
+}
+
+/// An attachment by name with its whole text, since the file it came from
+/// may have changed or, for piped stdin, never existed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DraftAttachment {
+    pub name: String,
+    pub text: String,
+}
+
+impl Draft {
+    pub fn new(input: &str, attachments: &[Attachment]) -> Self {
+        Self {
+            input: input.to_string(),
+            // a pasted image is not text and is left out
+            attachments: attachments
+                .iter()
+                .filter(|attachment| attachment.image_type.is_none())
+                .map(|attachment| DraftAttachment {
+                    name: attachment.name.clone(),
+                    text: String::from_utf8_lossy(&attachment.data).into_owned(),
+                })
+                .collect(),
+            saved_at: Local::now().timestamp(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.input.trim().is_empty() && self.attachments.is_empty()
+    }
+
+    /// Same input and attachments, whenever each was saved.
+    pub fn same_as(&self, other: &Draft) -> bool {
+        self.input == other.input && self.attachments == other.attachments
+    }
+
+    pub fn attachments(&self) -> Vec<Attachment> {
+        self.attachments
+            .iter()
+            .map(|saved| Attachment::from_text(saved.name.clone(), saved.text.clone()))
+            .collect()
+    }
+
+    /// `312 chars + 2 attachments, saved 14:02`
+    pub fn summary(&self) -> String {
+        let mut summary = format!("{} chars", self.input.chars().count());
+        match self.attachments.len() {
+            0 => {}
+            1 => summary.push_str(" + 1 attachment"),
+            count => summary.push_str(&format!(" + {} attachments", count)),
+        }
+        let Some(saved) = Local.timestamp_opt(self.saved_at, 0).single() else {
+            return summary;
+        };
+        let format = if saved.date_naive() == Local::now().date_naive() {
+            "%H:%M"
+        } else {
+            "%Y-%m-%d %H:%M"
+        };
+        format!("{}, saved {}", summary, saved.format(format))
+    }
+}
+
+fn path() -> Result<PathBuf> {
+    Ok(paths::data_dir()?.join("draft.json"))
+}
+
+/// The draft left by the last session, if it had anything in it.
+pub fn load() -> Option<Draft> {
+    let text = std::fs::read_to_string(path().ok()?).ok()?;
+    serde_json::from_str::<Draft>(&text).ok().filter(|draft| !draft.is_empty())
+}
+
+/// Writes `draft`, or removes the file when it is empty. The file is replaced
+/// in one rename so a crash mid-write leaves the previous draft intact.
+pub fn save(draft: &Draft) -> Result<()> {
+    let path = path()?;
+    if draft.is_empty() {
+        return match std::fs::remove_file(&path) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+                Err(error).with_context(|| format!("failed to remove {}", path.display()))
+            }
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, serde_json::to_vec(draft)?)
+        .with_context(|| format!("failed to write {}", partial.display()))?;
+    std::fs::rename(&partial, &path)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
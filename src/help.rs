@@ -0,0 +1,161 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{Clear, Paragraph},
+};
+
+use crate::centered_area;
+use crate::keymap::Binding;
+use crate::theme::t;
+
+/// The key cheat sheet: F1 in the chat deck, `?` in `play` and `analytics`.
+/// Typing narrows it to rows whose keys, action or context contain the text.
+#[derive(Debug, Default)]
+pub struct HelpOverlay {
+    pub open: bool,
+    title: &'static str,
+    bindings: Vec<Binding>,
+    query: String,
+    scroll: usize,
+}
+
+impl HelpOverlay {
+    pub fn new(title: &'static str, bindings: Vec<Binding>) -> Self {
+        Self {
+            title,
+            bindings,
+            ..Self::default()
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.scroll = 0;
+    }
+
+    /// Handles a key while the overlay is open. Esc clears the search first.
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc if !self.query.is_empty() => {
+                self.query.clear();
+                self.scroll = 0;
+            }
+            KeyCode::Esc | KeyCode::F(1) => self.open = false,
+            KeyCode::Char('?') if self.query.is_empty() => self.open = false,
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll = (self.scroll + 1).min(self.last_row()),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::PageDown => self.scroll = (self.scroll + 10).min(self.last_row()),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.scroll = 0;
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.query.push(c);
+                self.scroll = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn last_row(&self) -> usize {
+        self.matches().len().saturating_sub(1)
+    }
+
+    fn matches(&self) -> Vec<&Binding> {
+        let query = self.query.to_lowercase();
+        self.bindings
+            .iter()
+            .filter(|binding| {
+                query.is_empty()
+                    || [binding.keys.as_str(), binding.action, binding.context]
+                        .iter()
+                        .any(|field| field.to_lowercase().contains(&query))
+            })
+            .collect()
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let popup = centered_area(area, 80, 80);
+        frame.render_widget(Clear, popup);
+        let matches = self.matches();
+        let title = format!(" {} // {} keys ", self.title, matches.len());
+        let footer = " type to search  up/down pgup/pgdn scroll  esc close ";
+        let block = t().block(title, t().accent1, t().accent1).title_bottom(footer);
+        let inner = block.inner(popup);
+        frame.render_widget(block, popup);
+        if inner.height < 3 {
+            return;
+        }
+
+        let [search, header, list] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(1),
+        ])
+        .areas(inner);
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled("search: ", t().label_style()),
+                Span::styled(format!("{}_", self.query), t().text_style()),
+            ]))
+            .style(t().panel_style()),
+            search,
+        );
+
+        let keys_width = matches
+            .iter()
+            .map(|binding| binding.keys.chars().count())
+            .max()
+            .unwrap_or(4)
+            .clamp(4, 28);
+        let context_width = 12;
+        let action_width =
+            (list.width as usize).saturating_sub(keys_width + context_width + 2).max(8);
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                format!(
+                    "{:<kw$} {:<aw$} {}",
+                    "KEY",
+                    "ACTION",
+                    "CONTEXT",
+                    kw = keys_width,
+                    aw = action_width
+                ),
+                Style::default().fg(t().accent4).bold(),
+            ))
+            .style(t().panel_style()),
+            header,
+        );
+
+        if matches.is_empty() {
+            frame.render_widget(
+                Paragraph::new("no key matches the search").style(t().muted_style()),
+                list,
+            );
+            return;
+        }
+        let rows = list.height as usize;
+        let start = self.scroll.min(matches.len().saturating_sub(rows));
+        let lines: Vec<Line> = matches
+            .iter()
+            .skip(start)
+            .take(rows)
+            .map(|binding| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:<width$} ", binding.keys, width = keys_width),
+                        Style::default().fg(t().accent3),
+                    ),
+                    Span::styled(
+                        format!("{:<width$} ", binding.action, width = action_width),
+                        t().text_style(),
+                    ),
+                    Span::styled(binding.context, t().muted_style()),
+                ])
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).style(t().panel_style()), list);
+    }
+}
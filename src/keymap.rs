@@ -0,0 +1,559 @@
+//! Key bindings of the chat deck, `play` and `analytics`. Keys dispatched
+//! through the tables below are listed by the help overlay straight from
+//! them, so a new binding shows up in help without further work.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// One row of the help overlay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub keys: String,
+    pub action: &'static str,
+    /// Where the binding applies: a mode, a view or `global`.
+    pub context: &'static str,
+}
+
+impl Binding {
+    fn new(keys: impl Into<String>, action: &'static str, context: &'static str) -> Self {
+        Self {
+            keys: keys.into(),
+            action,
+            context,
+        }
+    }
+}
+
+/// `Ctrl+r`, `F2`, `PgUp`, ... as written in help.
+pub fn key_label(code: KeyCode, modifiers: KeyModifiers) -> String {
+    let key = match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        KeyCode::Up => "Up".to_string(),
+        KeyCode::Down => "Down".to_string(),
+        KeyCode::Left => "Left".to_string(),
+        KeyCode::Right => "Right".to_string(),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        KeyCode::Home => "Home".to_string(),
+        KeyCode::End => "End".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Delete => "Del".to_string(),
+        other => format!("{:?}", other),
+    };
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        format!("Ctrl+{}", key)
+    } else {
+        key
+    }
+}
+
+/// Rows for a dispatch table, one per action with all of its keys, in the
+/// order the actions first appear.
+fn rows<A: Copy + PartialEq>(
+    table: &[(KeyCode, KeyModifiers, A)],
+    describe: impl Fn(A) -> (&'static str, &'static str),
+) -> Vec<Binding> {
+    let mut rows: Vec<(A, Vec<String>)> = Vec::new();
+    for &(code, modifiers, action) in table {
+        let label = key_label(code, modifiers);
+        match rows.iter_mut().find(|(seen, _)| *seen == action) {
+            Some((_, keys)) => keys.push(label),
+            None => rows.push((action, vec![label])),
+        }
+    }
+    rows.into_iter()
+        .map(|(action, keys)| {
+            let (text, context) = describe(action);
+            Binding::new(keys.join(" "), text, context)
+        })
+        .collect()
+}
+
+/// The first action in `table` bound to `key`. A table entry with modifiers
+/// only needs those held; one without matches whatever is held.
+fn lookup<A: Copy>(table: &[(KeyCode, KeyModifiers, A)], key: KeyEvent) -> Option<A> {
+    table
+        .iter()
+        .find(|(code, modifiers, _)| *code == key.code && key.modifiers.contains(*modifiers))
+        .map(|&(_, _, action)| action)
+}
+
+const NONE: KeyModifiers = KeyModifiers::NONE;
+const CTRL: KeyModifiers = KeyModifiers::CONTROL;
+
+/// Which layer of the chat keymap is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditMode {
+    /// Keys type into the prompt (the only mode when vim mode is off).
+    Insert,
+    /// Keys scroll, search and yank the transcript.
+    Normal,
+    /// Typing a `/` search query.
+    Search,
+    /// Up/Down pick a single message for copy, delete, quote or detail view.
+    Select,
+}
+
+impl EditMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            EditMode::Insert => "INSERT",
+            EditMode::Normal => "NORMAL",
+            EditMode::Search => "SEARCH",
+            EditMode::Select => "SELECT",
+        }
+    }
+}
+
+/// Transcript actions bound in normal mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalAction {
+    ScrollDown,
+    ScrollUp,
+    HalfPageDown,
+    HalfPageUp,
+    Top,
+    Bottom,
+    StartSearch,
+    NextMatch,
+    PrevMatch,
+    Yank,
+    Insert,
+    /// `:` -- insert mode with a `/` already typed, for slash commands.
+    Command,
+    Select,
+    /// `F` -- toggle sticking to the newest output, like `less +F`.
+    Follow,
+}
+
+impl NormalAction {
+    fn describe(self) -> (&'static str, &'static str) {
+        let text = match self {
+            NormalAction::ScrollDown => "scroll down a line",
+            NormalAction::ScrollUp => "scroll up a line",
+            NormalAction::HalfPageDown => "scroll down half a page",
+            NormalAction::HalfPageUp => "scroll up half a page",
+            NormalAction::Top => "jump to the top",
+            NormalAction::Bottom => "jump to the bottom and follow",
+            NormalAction::StartSearch => "search the transcript",
+            NormalAction::NextMatch => "older match",
+            NormalAction::PrevMatch => "newer match",
+            NormalAction::Yank => "yank the match or last reply",
+            NormalAction::Insert => "insert mode",
+            NormalAction::Command => "type a slash command",
+            NormalAction::Select => "select mode",
+            NormalAction::Follow => "toggle following new output",
+        };
+        (text, "vim normal")
+    }
+}
+
+/// Normal-mode keys; `gg` is matched apart, as it takes two presses.
+const NORMAL_KEYS: &[(KeyCode, KeyModifiers, NormalAction)] = &[
+    (KeyCode::Char('j'), NONE, NormalAction::ScrollDown),
+    (KeyCode::Down, NONE, NormalAction::ScrollDown),
+    (KeyCode::Char('k'), NONE, NormalAction::ScrollUp),
+    (KeyCode::Up, NONE, NormalAction::ScrollUp),
+    (KeyCode::Char('d'), NONE, NormalAction::HalfPageDown),
+    (KeyCode::Char('u'), NONE, NormalAction::HalfPageUp),
+    (KeyCode::Char('G'), NONE, NormalAction::Bottom),
+    (KeyCode::Char('/'), NONE, NormalAction::StartSearch),
+    (KeyCode::Char('n'), NONE, NormalAction::NextMatch),
+    (KeyCode::Char('N'), NONE, NormalAction::PrevMatch),
+    (KeyCode::Char('y'), NONE, NormalAction::Yank),
+    (KeyCode::Char('i'), NONE, NormalAction::Insert),
+    (KeyCode::Char('a'), NONE, NormalAction::Insert),
+    (KeyCode::Char(':'), NONE, NormalAction::Command),
+    (KeyCode::Char('v'), NONE, NormalAction::Select),
+    (KeyCode::Char('F'), NONE, NormalAction::Follow),
+];
+
+/// Actions on the highlighted message in select mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectAction {
+    Previous,
+    Next,
+    First,
+    Last,
+    Copy,
+    Delete,
+    Undo,
+    Quote,
+    Bookmark,
+    Revisions,
+    Detail,
+    Exit,
+}
+
+impl SelectAction {
+    fn describe(self) -> (&'static str, &'static str) {
+        let text = match self {
+            SelectAction::Previous => "previous message",
+            SelectAction::Next => "next message",
+            SelectAction::First => "first message",
+            SelectAction::Last => "last message",
+            SelectAction::Copy => "copy to the clipboard",
+            SelectAction::Delete => "delete",
+            SelectAction::Undo => "undo the delete",
+            SelectAction::Quote => "quote into the prompt",
+            SelectAction::Bookmark => "toggle bookmark",
+            SelectAction::Revisions => "show earlier edits",
+            SelectAction::Detail => "view full-screen",
+            SelectAction::Exit => "leave select mode",
+        };
+        (text, "select")
+    }
+}
+
+const SELECT_KEYS: &[(KeyCode, KeyModifiers, SelectAction)] = &[
+    (KeyCode::Up, NONE, SelectAction::Previous),
+    (KeyCode::Char('k'), NONE, SelectAction::Previous),
+    (KeyCode::Down, NONE, SelectAction::Next),
+    (KeyCode::Char('j'), NONE, SelectAction::Next),
+    (KeyCode::Home, NONE, SelectAction::First),
+    (KeyCode::Char('g'), NONE, SelectAction::First),
+    (KeyCode::End, NONE, SelectAction::Last),
+    (KeyCode::Char('G'), NONE, SelectAction::Last),
+    (KeyCode::Char('c'), NONE, SelectAction::Copy),
+    (KeyCode::Char('y'), NONE, SelectAction::Copy),
+    (KeyCode::Char('d'), NONE, SelectAction::Delete),
+    (KeyCode::Delete, NONE, SelectAction::Delete),
+    (KeyCode::Char('u'), NONE, SelectAction::Undo),
+    (KeyCode::Char('r'), NONE, SelectAction::Quote),
+    (KeyCode::Char('>'), NONE, SelectAction::Quote),
+    (KeyCode::Char('b'), NONE, SelectAction::Bookmark),
+    (KeyCode::Char('e'), NONE, SelectAction::Revisions),
+    (KeyCode::Enter, NONE, SelectAction::Detail),
+    (KeyCode::Char('v'), NONE, SelectAction::Detail),
+    (KeyCode::Esc, NONE, SelectAction::Exit),
+    (KeyCode::Char('q'), NONE, SelectAction::Exit),
+];
+
+/// Chat deck shortcuts that work whatever the edit mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeckAction {
+    Help,
+    CycleProvider,
+    ToggleVideo,
+    CycleEffects,
+    ToggleWebcam,
+    CycleLayout,
+    Tiles,
+    CyclePanel,
+    RandomTheme,
+    ResetTheme,
+    Logs,
+    ClearTranscript,
+    Retry,
+    EditLast,
+    Select,
+    ToggleMeta,
+    PasteImage,
+    /// Move tiling focus by `(dx, dy)`.
+    Focus(i32, i32),
+    /// Swap the focused tile with its neighbour at `(dx, dy)`.
+    Swap(i32, i32),
+    /// Grow the focused split by this many percent (negative shrinks).
+    Resize(i8),
+}
+
+impl DeckAction {
+    fn describe(self) -> (&'static str, &'static str) {
+        match self {
+            DeckAction::Help => ("this help (and a random theme)", "global"),
+            DeckAction::CycleProvider => ("cycle AI provider", "global"),
+            DeckAction::ToggleVideo => ("toggle the video panel", "global"),
+            DeckAction::CycleEffects => ("cycle 3D effects, then off", "global"),
+            DeckAction::ToggleWebcam => ("toggle webcam capture", "global"),
+            DeckAction::CycleLayout => ("cycle tiling layout preset", "global"),
+            DeckAction::Tiles => ("boot or focus the Tiles PTY panel", "global"),
+            DeckAction::CyclePanel => ("cycle the focused tile's panel", "global"),
+            DeckAction::RandomTheme => ("randomize the color theme", "global"),
+            DeckAction::ResetTheme => ("reset the theme", "global"),
+            DeckAction::Logs => ("recent warnings and errors", "global"),
+            DeckAction::ClearTranscript => ("clear the transcript", "global"),
+            DeckAction::Retry => ("regenerate the last reply", "global"),
+            DeckAction::EditLast => ("edit and resend the last prompt", "global"),
+            DeckAction::Select => ("select a message", "global"),
+            DeckAction::ToggleMeta => ("timestamps, model, tokens, latency", "global"),
+            DeckAction::PasteImage => ("attach the clipboard image (vision models)", "global"),
+            DeckAction::Focus(-1, 0) => ("focus the tile to the left", "tiling"),
+            DeckAction::Focus(0, -1) => ("focus the tile above", "tiling"),
+            DeckAction::Focus(..) => ("focus the tile below", "tiling"),
+            DeckAction::Swap(-1, 0) => ("swap with the tile to the left", "tiling"),
+            DeckAction::Swap(1, 0) => ("swap with the tile to the right", "tiling"),
+            DeckAction::Swap(0, -1) => ("swap with the tile above", "tiling"),
+            DeckAction::Swap(..) => ("swap with the tile below", "tiling"),
+            DeckAction::Resize(step) if step < 0 => ("narrow the focused split", "tiling"),
+            DeckAction::Resize(_) => ("widen the focused split", "tiling"),
+        }
+    }
+}
+
+/// Ctrl+l clears the transcript, so there is no key to focus rightwards.
+const DECK_KEYS: &[(KeyCode, KeyModifiers, DeckAction)] = &[
+    (KeyCode::F(1), NONE, DeckAction::Help),
+    (KeyCode::F(2), NONE, DeckAction::CycleProvider),
+    (KeyCode::F(3), NONE, DeckAction::ToggleVideo),
+    (KeyCode::F(4), NONE, DeckAction::CycleEffects),
+    (KeyCode::F(5), NONE, DeckAction::ToggleWebcam),
+    (KeyCode::F(6), NONE, DeckAction::CycleLayout),
+    (KeyCode::F(7), NONE, DeckAction::Tiles),
+    (KeyCode::F(8), NONE, DeckAction::CyclePanel),
+    (KeyCode::Char('n'), CTRL, DeckAction::CyclePanel),
+    (KeyCode::F(9), NONE, DeckAction::RandomTheme),
+    (KeyCode::F(10), NONE, DeckAction::ResetTheme),
+    (KeyCode::F(12), NONE, DeckAction::Logs),
+    (KeyCode::Char('l'), CTRL, DeckAction::ClearTranscript),
+    (KeyCode::Char('r'), CTRL, DeckAction::Retry),
+    (KeyCode::Char('e'), CTRL, DeckAction::EditLast),
+    (KeyCode::Char('s'), CTRL, DeckAction::Select),
+    (KeyCode::Char('t'), CTRL, DeckAction::ToggleMeta),
+    (KeyCode::Char('v'), CTRL, DeckAction::PasteImage),
+    (KeyCode::Char('h'), CTRL, DeckAction::Focus(-1, 0)),
+    (KeyCode::Char('k'), CTRL, DeckAction::Focus(0, -1)),
+    (KeyCode::Char('j'), CTRL, DeckAction::Focus(0, 1)),
+    (KeyCode::Char('H'), CTRL, DeckAction::Swap(-1, 0)),
+    (KeyCode::Char('L'), CTRL, DeckAction::Swap(1, 0)),
+    (KeyCode::Char('K'), CTRL, DeckAction::Swap(0, -1)),
+    (KeyCode::Char('J'), CTRL, DeckAction::Swap(0, 1)),
+    (KeyCode::Char('['), CTRL, DeckAction::Resize(-5)),
+    (KeyCode::Char(']'), CTRL, DeckAction::Resize(5)),
+];
+
+/// Keys the prompt editor handles itself, for help.
+const PROMPT_KEYS: &[(&str, &str)] = &[
+    ("Enter", "send the prompt, or approve a pending tool"),
+    ("Tab", "complete /commands, providers, models, themes and paths"),
+    ("Esc", "cancel request, drop outbox, clear input; twice exits"),
+    ("Up Down", "scroll the transcript"),
+    ("PgUp PgDn", "scroll a page"),
+    ("End", "follow new output"),
+    ("Left Right", "switch /compare tabs"),
+    ("Ctrl+c", "quit"),
+];
+
+/// Keys while typing a vim search.
+const SEARCH_KEYS: &[(&str, &str)] = &[
+    ("Enter", "jump to the newest match"),
+    ("Esc", "cancel the search"),
+];
+
+/// Keys while `/voice` is recording.
+const VOICE_KEYS: &[(&str, &str)] = &[
+    ("Enter", "stop and transcribe into the input"),
+    ("Esc", "discard the recording"),
+];
+
+/// Every chat deck binding, for the F1 overlay.
+pub fn chat_bindings() -> Vec<Binding> {
+    let mut bindings = rows(DECK_KEYS, DeckAction::describe);
+    bindings.extend(PROMPT_KEYS.iter().map(|(keys, text)| Binding::new(*keys, text, "prompt")));
+    bindings.extend(rows(NORMAL_KEYS, NormalAction::describe));
+    let (top, context) = NormalAction::Top.describe();
+    bindings.push(Binding::new("gg", top, context));
+    bindings.extend(SEARCH_KEYS.iter().map(|(keys, text)| Binding::new(*keys, text, "vim search")));
+    bindings.extend(VOICE_KEYS.iter().map(|(keys, text)| Binding::new(*keys, text, "voice")));
+    bindings.extend(rows(SELECT_KEYS, SelectAction::describe));
+    bindings
+}
+
+/// `asciivision play` keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerAction {
+    Quit,
+    Help,
+}
+
+impl PlayerAction {
+    fn describe(self) -> (&'static str, &'static str) {
+        match self {
+            PlayerAction::Quit => ("quit", "player"),
+            PlayerAction::Help => ("this help", "player"),
+        }
+    }
+}
+
+const PLAYER_KEYS: &[(KeyCode, KeyModifiers, PlayerAction)] = &[
+    (KeyCode::Char('q'), NONE, PlayerAction::Quit),
+    (KeyCode::Esc, NONE, PlayerAction::Quit),
+    (KeyCode::Char('c'), CTRL, PlayerAction::Quit),
+    (KeyCode::Char('?'), NONE, PlayerAction::Help),
+];
+
+pub fn player_action(key: KeyEvent) -> Option<PlayerAction> {
+    lookup(PLAYER_KEYS, key)
+}
+
+pub fn player_bindings() -> Vec<Binding> {
+    rows(PLAYER_KEYS, PlayerAction::describe)
+}
+
+/// `asciivision analytics` keys that work in every view but Search and Semantic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DashAction {
+    Quit,
+    NextView,
+    Help,
+    Logs,
+    Provider,
+    Role(&'static str),
+    Tag,
+    Dates,
+    Profile,
+    Backup,
+    Backups,
+    About,
+    Archived,
+}
+
+impl DashAction {
+    fn describe(self) -> (&'static str, &'static str) {
+        let text = match self {
+            DashAction::Quit => "quit",
+            DashAction::NextView => "next view",
+            DashAction::Help => "this help",
+            DashAction::Logs => "recent warnings and errors",
+            DashAction::Provider => "filter by provider",
+            DashAction::Role("user") => "only your prompts",
+            DashAction::Role(_) => "only replies",
+            DashAction::Tag => "filter by tag",
+            DashAction::Dates => "filter by date range",
+            DashAction::Profile => "next archive profile",
+            DashAction::Backup => "back up the archive now",
+            DashAction::Backups => "browse and restore backups",
+            DashAction::About => "about this database",
+            DashAction::Archived => "include archived conversations",
+        };
+        (text, "all views")
+    }
+}
+
+const DASH_KEYS: &[(KeyCode, KeyModifiers, DashAction)] = &[
+    (KeyCode::Char('q'), NONE, DashAction::Quit),
+    (KeyCode::Esc, NONE, DashAction::Quit),
+    (KeyCode::Tab, NONE, DashAction::NextView),
+    (KeyCode::Char('?'), NONE, DashAction::Help),
+    (KeyCode::F(12), NONE, DashAction::Logs),
+    (KeyCode::Char('p'), NONE, DashAction::Provider),
+    (KeyCode::Char('u'), NONE, DashAction::Role("user")),
+    (KeyCode::Char('a'), NONE, DashAction::Role("assistant")),
+    (KeyCode::Char('t'), NONE, DashAction::Tag),
+    (KeyCode::Char('d'), NONE, DashAction::Dates),
+    (KeyCode::Char('o'), NONE, DashAction::Profile),
+    (KeyCode::Char('B'), NONE, DashAction::Backup),
+    (KeyCode::Char('R'), NONE, DashAction::Backups),
+    (KeyCode::Char('i'), NONE, DashAction::About),
+    (KeyCode::Char('A'), NONE, DashAction::Archived),
+];
+
+pub fn dash_action(key: KeyEvent) -> Option<DashAction> {
+    lookup(DASH_KEYS, key)
+}
+
+/// Keys a single dashboard view handles itself, for help.
+const VIEW_KEYS: &[(&str, &str, &str)] = &[
+    ("b", "toggle bookmarked messages only", "Stats"),
+    ("Up Down", "pick a session or message", "Messages"),
+    ("Enter", "open the session, then read the message", "Messages"),
+    ("Esc Backspace", "back to the sessions", "Messages"),
+    ("x", "export to a Markdown transcript", "Messages"),
+    ("n p", "next or previous message while reading", "Messages"),
+    ("a e", "attachments or edits while reading", "Messages"),
+    ("Up Down PgUp PgDn Home End", "browse", "Timeline"),
+    ("Enter", "open in Messages", "Timeline"),
+    ("type", "search; operators role: provider: tag: after: before:", "Search"),
+    ("Ctrl+r", "toggle regex mode", "Search"),
+    ("Enter", "open the hit in Messages", "Search"),
+    ("Esc", "clear the query, then quit", "Search"),
+    ("type Enter", "rank past exchanges by meaning", "Semantic"),
+    ("Enter", "open the selected exchange in Messages", "Semantic"),
+    ("Esc", "clear the query, then quit", "Semantic"),
+    ("r", "cycle 7d / 30d / 90d / all time", "Usage Health"),
+    ("r", "reload", "Compare"),
+    ("Enter", "open the original in Messages", "Duplicates"),
+    ("m", "merge every copy into its original", "Duplicates"),
+];
+
+/// Every dashboard binding, for the `?` overlay.
+pub fn dash_bindings() -> Vec<Binding> {
+    let mut bindings = rows(DASH_KEYS, DashAction::describe);
+    bindings.extend(
+        VIEW_KEYS.iter().map(|(keys, text, context)| Binding::new(*keys, text, context)),
+    );
+    bindings
+}
+
+/// Translates chat keys into actions. With vim mode off every key is left to
+/// the regular insert-mode handling.
+pub struct Keymap {
+    pub vim: bool,
+    pub mode: EditMode,
+    pending_g: bool,
+}
+
+impl Keymap {
+    pub fn new(vim: bool) -> Self {
+        Self {
+            vim,
+            mode: if vim { EditMode::Normal } else { EditMode::Insert },
+            pending_g: false,
+        }
+    }
+
+    pub fn set_vim(&mut self, vim: bool) {
+        self.vim = vim;
+        self.pending_g = false;
+        self.mode = EditMode::Insert;
+    }
+
+    /// Normal-mode binding for `key`, if any. Keys with Ctrl/Alt held are never
+    /// bound so tiling and global shortcuts keep working.
+    pub fn normal_action(&mut self, key: KeyEvent) -> Option<NormalAction> {
+        if !self.vim || self.mode != EditMode::Normal {
+            return None;
+        }
+        if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            self.pending_g = false;
+            return None;
+        }
+
+        let pending_g = std::mem::take(&mut self.pending_g);
+        match key.code {
+            KeyCode::Char('g') if pending_g => Some(NormalAction::Top),
+            KeyCode::Char('g') => {
+                self.pending_g = true;
+                None
+            }
+            _ => lookup(NORMAL_KEYS, key),
+        }
+    }
+
+    /// Select-mode binding for `key`. Arrow keys work with or without vim mode.
+    pub fn select_action(&self, key: KeyEvent) -> Option<SelectAction> {
+        if self.mode != EditMode::Select
+            || key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return None;
+        }
+        lookup(SELECT_KEYS, key)
+    }
+
+    /// The deck shortcut bound to `key`, in any mode.
+    pub fn deck_action(&self, key: KeyEvent) -> Option<DeckAction> {
+        lookup(DECK_KEYS, key)
+    }
+
+    /// Mode to return to after search or select mode ends.
+    pub fn resting_mode(&self) -> EditMode {
+        if self.vim {
+            EditMode::Normal
+        } else {
+            EditMode::Insert
+        }
+    }
+}
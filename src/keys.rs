@@ -0,0 +1,150 @@
+use anyhow::{anyhow, Context, Result};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::io::{BufRead, IsTerminal};
+use std::sync::OnceLock;
+
+use crate::ai::{AIProvider, ProviderRegistry};
+
+/// Keychain service name; each key is stored under the provider key as account.
+const SERVICE: &str = "asciivision";
+
+/// Keychain answers by account, so the chat deck doesn't hit the OS keychain
+/// on every request.
+static CACHE: OnceLock<Mutex<HashMap<String, Option<String>>>> = OnceLock::new();
+
+/// Index of the active key per provider, for providers with several keys.
+static ROTATION: OnceLock<Mutex<HashMap<String, usize>>> = OnceLock::new();
+
+/// Responses per key not yet written to the database.
+static USES: OnceLock<Mutex<Vec<KeyUse>>> = OnceLock::new();
+
+/// One response to a request made with a provider key.
+pub struct KeyUse {
+    pub provider: String,
+    /// The env var the key came from; the key itself is never recorded.
+    pub label: String,
+    pub status: u16,
+}
+
+fn entry(account: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, account).context("OS keychain unavailable")
+}
+
+/// The API key from `env_var`, falling back to the OS keychain entry for
+/// `account`. Keys are never written to disk by asciivision itself.
+pub fn lookup(env_var: &str, account: &str) -> Option<String> {
+    if let Ok(key) = std::env::var(env_var) {
+        if !key.trim().is_empty() {
+            return Some(key);
+        }
+    }
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some(cached) = cache.lock().get(account) {
+        return cached.clone();
+    }
+    let stored = entry(account).ok().and_then(|entry| entry.get_password().ok());
+    cache.lock().insert(account.to_string(), stored.clone());
+    stored
+}
+
+/// Which of `count` keys `provider` requests should use.
+pub fn active(provider: &str, count: usize) -> usize {
+    let rotation = ROTATION.get_or_init(Default::default).lock();
+    rotation.get(provider).copied().unwrap_or(0) % count.max(1)
+}
+
+/// Moves `provider` on from key `from` and returns the new index. When a
+/// concurrent request already rotated away, its choice is kept.
+pub fn rotate(provider: &str, from: usize, count: usize) -> usize {
+    let mut rotation = ROTATION.get_or_init(Default::default).lock();
+    let current = rotation.entry(provider.to_string()).or_insert(0);
+    if *current % count.max(1) == from {
+        *current = (from + 1) % count.max(1);
+    }
+    *current % count.max(1)
+}
+
+pub fn record_use(provider: &str, label: &str, status: u16) {
+    USES.get_or_init(Default::default).lock().push(KeyUse {
+        provider: provider.to_string(),
+        label: label.to_string(),
+        status,
+    });
+}
+
+/// Drains the key uses recorded since the last call.
+pub fn take_uses() -> Vec<KeyUse> {
+    std::mem::take(&mut *USES.get_or_init(Default::default).lock())
+}
+
+/// Asks for a key on the terminal without echo. Used by `ask` when neither the
+/// environment nor the keychain has one; the key lives only in this process.
+pub fn prompt(provider: &AIProvider) -> Result<String> {
+    let key = rpassword::prompt_password(format!(
+        "{} API key (not saved; use `asciivision keys set {}` to keep it): ",
+        provider.name(),
+        provider.db_key()
+    ))?;
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        return Err(anyhow!("no API key entered"));
+    }
+    CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .insert(provider.db_key().to_string(), Some(key.clone()));
+    Ok(key)
+}
+
+/// `asciivision keys set <provider>`: prompts for the key and stores it.
+pub fn set(name: &str, providers: &ProviderRegistry) -> Result<()> {
+    let provider = keyed_provider(name, providers)?;
+    let key = read_key(&provider)?;
+    entry(provider.db_key())?
+        .set_password(&key)
+        .context("failed to store the key in the OS keychain")?;
+    println!("stored {} key in the OS keychain", provider.db_key());
+    Ok(())
+}
+
+/// `asciivision keys delete <provider>`.
+pub fn delete(name: &str, providers: &ProviderRegistry) -> Result<()> {
+    let provider = keyed_provider(name, providers)?;
+    entry(provider.db_key())?
+        .delete_credential()
+        .context("failed to remove the key from the OS keychain")?;
+    println!("removed {} key from the OS keychain", provider.db_key());
+    Ok(())
+}
+
+fn keyed_provider(name: &str, providers: &ProviderRegistry) -> Result<AIProvider> {
+    let provider = providers
+        .all()
+        .find(|provider| provider.db_key().eq_ignore_ascii_case(name.trim()))
+        .cloned()
+        .ok_or_else(|| {
+            let known: Vec<&str> = providers.all().map(AIProvider::db_key).collect();
+            anyhow!("unknown provider `{}` (one of: {})", name, known.join(", "))
+        })?;
+    if !provider.uses_api_key() {
+        return Err(anyhow!("{} does not use an API key", provider.db_key()));
+    }
+    Ok(provider)
+}
+
+/// Hidden prompt on a terminal; one line from stdin when piped.
+fn read_key(provider: &AIProvider) -> Result<String> {
+    let key = if std::io::stdin().is_terminal() {
+        rpassword::prompt_password(format!("{} API key: ", provider.name()))?
+    } else {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        line
+    };
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        return Err(anyhow!("no API key entered"));
+    }
+    Ok(key)
+}
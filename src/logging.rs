@@ -0,0 +1,322 @@
+//! Structured logging through `tracing`. Events from every crate in the
+//! workspace go to a daily file under `<data dir>/logs` and into a ring of
+//! recent records that the F12 log viewer reads, never to the terminal.
+
+use anyhow::Context;
+use chrono::{DateTime, Local, NaiveDate};
+use crossterm::event::{KeyCode, KeyEvent};
+use parking_lot::{const_mutex, Mutex};
+use ratatui::{
+    prelude::*,
+    widgets::{Clear, Paragraph},
+};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::fs::File;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::{span, Event, Level, Metadata, Subscriber};
+
+use crate::{centered_area, paths, theme::t};
+
+/// Records kept in memory for the log viewer.
+const RECENT: usize = 500;
+
+static RECORDS: Mutex<VecDeque<LogRecord>> = const_mutex(VecDeque::new());
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// `[logging]` in config.toml.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// `error`, `warn`, `info` (default), `debug` or `trace`. Other crates'
+    /// events are capped at `warn` so `debug` stays readable.
+    pub level: Option<String>,
+    /// Daily log files to keep (default 7).
+    pub keep_days: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// Installs the global subscriber. Problems, a bad `level` or no usable data
+/// directory, are reported as the first warnings in the viewer.
+pub fn init(config: &LogConfig) {
+    let mut problems = Vec::new();
+    let max = match config.level.as_deref() {
+        Some(level) => level.parse::<LevelFilter>().unwrap_or_else(|_| {
+            problems.push(format!(
+                "[logging] level {:?} ignored, expected error, warn, info, debug or trace",
+                level
+            ));
+            LevelFilter::INFO
+        }),
+        None => LevelFilter::INFO,
+    };
+    let dir = paths::data_dir().map(|dir| dir.join("logs")).and_then(|dir| {
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        Ok(dir)
+    });
+    let file = match dir {
+        Ok(dir) => {
+            let _ = LOG_DIR.set(dir.clone());
+            Some(Mutex::new(DailyFile::new(dir, config.keep_days.unwrap_or(7))))
+        }
+        Err(error) => {
+            problems.push(format!("logging to the viewer only: {:#}", error));
+            None
+        }
+    };
+    let logger = Logger {
+        max,
+        file,
+        next_span: AtomicU64::new(1),
+    };
+    if tracing::subscriber::set_global_default(logger).is_err() {
+        return;
+    }
+    for problem in problems {
+        tracing::warn!("{}", problem);
+    }
+}
+
+/// Where the log files are written, once [`init`] found a place for them.
+pub fn log_dir() -> Option<&'static Path> {
+    LOG_DIR.get().map(PathBuf::as_path)
+}
+
+/// The most recent records, oldest first.
+pub fn recent() -> Vec<LogRecord> {
+    RECORDS.lock().iter().cloned().collect()
+}
+
+struct Logger {
+    max: LevelFilter,
+    file: Option<Mutex<DailyFile>>,
+    next_span: AtomicU64,
+}
+
+impl Subscriber for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        let max = if metadata.target().starts_with("asciivision") {
+            self.max
+        } else {
+            self.max.min(LevelFilter::WARN)
+        };
+        *metadata.level() <= max
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.max)
+    }
+
+    // spans are not recorded, only the events inside them
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let record = LogRecord {
+            time: Local::now(),
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            message: fields.message + &fields.rest,
+        };
+        if let Some(file) = &self.file {
+            file.lock().write(&record);
+        }
+        let mut records = RECORDS.lock();
+        if records.len() == RECENT {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+/// An event's message followed by its other fields as ` name=value`.
+#[derive(Default)]
+struct Fields {
+    message: String,
+    rest: String,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.rest, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.rest, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// `asciivision.<date>.log`, switching files at midnight and deleting the
+/// oldest beyond `keep`.
+struct DailyFile {
+    dir: PathBuf,
+    keep: usize,
+    day: Option<NaiveDate>,
+    file: Option<File>,
+}
+
+impl DailyFile {
+    fn new(dir: PathBuf, keep: usize) -> Self {
+        Self {
+            dir,
+            keep: keep.max(1),
+            day: None,
+            file: None,
+        }
+    }
+
+    fn write(&mut self, record: &LogRecord) {
+        let day = record.time.date_naive();
+        if self.day != Some(day) {
+            let path = self.dir.join(format!("asciivision.{}.log", day.format("%Y-%m-%d")));
+            self.file = File::options().create(true).append(true).open(path).ok();
+            self.day = Some(day);
+            self.rotate();
+        }
+        if let Some(file) = self.file.as_mut() {
+            let _ = writeln!(
+                file,
+                "{} {:>5} {}: {}",
+                record.time.format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
+                record.level,
+                record.target,
+                record.message
+            );
+        }
+    }
+
+    fn rotate(&self) {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut logs: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("asciivision.") && name.ends_with(".log"))
+            })
+            .collect();
+        // the date in the name sorts oldest first
+        logs.sort();
+        for path in logs.iter().rev().skip(self.keep) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// The F12 overlay listing recent warnings and errors, or every record.
+#[derive(Debug, Default)]
+pub struct LogViewer {
+    pub open: bool,
+    all: bool,
+    /// Lines scrolled up from the newest record.
+    scroll: usize,
+}
+
+impl LogViewer {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.scroll = 0;
+    }
+
+    /// Handles a key while the viewer is open.
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc | KeyCode::F(12) | KeyCode::Char('q') => self.open = false,
+            KeyCode::Char('a') => {
+                self.all = !self.all;
+                self.scroll = 0;
+            }
+            KeyCode::Up => self.scroll += 1,
+            KeyCode::Down => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::PageUp => self.scroll += 20,
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(20),
+            KeyCode::Home => self.scroll = usize::MAX,
+            KeyCode::End => self.scroll = 0,
+            _ => {}
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let popup = centered_area(area, 90, 80);
+        frame.render_widget(Clear, popup);
+        let records: Vec<LogRecord> = recent()
+            .into_iter()
+            .filter(|record| self.all || record.level <= Level::WARN)
+            .collect();
+        let title = format!(
+            " LOG // {} {} ",
+            records.len(),
+            if self.all { "records" } else { "warnings and errors" }
+        );
+        let keys = " a all levels  up/down pgup/pgdn scroll  esc close ";
+        let footer = match log_dir() {
+            Some(dir) => format!("{}// {} ", keys, dir.display()),
+            None => keys.to_string(),
+        };
+        let block = t().block(title, t().accent2, t().accent2).title_bottom(footer);
+        let inner = block.inner(popup);
+        frame.render_widget(block, popup);
+
+        if records.is_empty() {
+            let empty = if self.all { "nothing logged yet" } else { "no warnings or errors" };
+            frame.render_widget(Paragraph::new(empty).style(t().muted_style()), inner);
+            return;
+        }
+        let rows = inner.height as usize;
+        let end = records.len().saturating_sub(self.scroll.min(records.len().saturating_sub(rows)));
+        let start = end.saturating_sub(rows);
+        let lines: Vec<Line> = records[start..end]
+            .iter()
+            .map(|record| {
+                let color = match record.level {
+                    Level::ERROR => t().danger,
+                    Level::WARN => t().accent3,
+                    Level::INFO => t().text,
+                    _ => t().muted,
+                };
+                Line::from(vec![
+                    Span::styled(record.time.format("%H:%M:%S ").to_string(), t().muted_style()),
+                    Span::styled(format!("{:<5} ", record.level), Style::default().fg(color)),
+                    Span::styled(format!("{} ", record.target), t().muted_style()),
+                    Span::styled(record.message.replace('\n', " "), t().text_style()),
+                ])
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).style(t().panel_style()), inner);
+    }
+}
@@ -1,62 +1,145 @@
 use anyhow::{Context, Result};
-use clap::Parser;
-use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
+use asciivision_config::{paths, theme};
+use asciivision_core::{render_fitted, AsciiFrame};
+use asciivision_terminal::TerminalGuard;
+use asciivision_video::{VideoOptions, VideoPlayer};
+use chrono::{DateTime, Local, TimeZone};
+use clap::{Args, Parser, Subcommand};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
+    backend::TestBackend,
     prelude::*,
-    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    TerminalOptions, Viewport,
+    widgets::{
+        Block, BorderType, Borders, Clear, Paragraph, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Wrap,
+    },
 };
 use serde::Deserialize;
 use std::{
-    collections::VecDeque,
+    cell::Cell,
+    collections::{HashMap, HashSet, VecDeque},
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 mod ai;
 mod analytics;
+mod archiver;
+mod ask;
+mod attach;
+mod backdrop;
+mod capabilities;
 mod client;
+mod clipboard;
+mod complete;
+mod compare;
+mod config;
+mod dashboard;
 mod db;
+mod diagnose;
+mod draft;
 mod effects;
 mod games;
+mod help;
+mod keymap;
+mod keys;
+mod logging;
 mod memory;
 mod message;
+mod metrics;
+mod net;
+mod notify;
+mod player;
+mod plugin;
+mod pricing;
+mod provider;
+mod rag;
+mod schedule;
+mod screensaver;
 mod server;
 mod shell;
+mod streaks;
+mod sync;
 mod sysmon;
-mod theme;
 mod tiling;
 mod tiles;
+mod text;
 mod tools;
-mod video;
+mod topics;
+mod transcript;
+mod voice;
 mod webcam;
+mod websearch;
 
 use ai::{
-    list_ollama_models, ollama_install_hint, AIClient, AIProvider, AIResponse,
-    Message as ApiMessage, OllamaModelInfo, StreamChunk,
+    is_network_error, list_ollama_models, list_openrouter_models, ollama_install_hint,
+    ollama_model_capabilities, AIClient,
+    AIProvider, AIResponse, ApiError, Message as ApiMessage, OllamaModelInfo, OpenRouterModelInfo,
+    ProviderRegistry, ProviderSettings, StreamChunk, Usage,
 };
 use analytics::AnalyticsPanel;
+use archiver::Archiver;
+use attach::Attachment;
+use backdrop::ChatBackground;
 use client::VideoChatClient;
-use db::Database;
+use capabilities::{Capabilities, CapabilityTable};
+use compare::{ComparePane, CompareState, PaneLayout};
+use complete::{Completion, Sources};
+use config::Config;
+use db::{Database, MessageFilter, ReplyMeta, RetentionPolicy, StoredMessage};
+use draft::Draft;
 use effects::EffectsEngine;
 use games::{GameKind, GamesPanel};
+use help::HelpOverlay;
+use keymap::{DeckAction, EditMode, Keymap, NormalAction, SelectAction};
+use logging::LogViewer;
 use memory::AgentMemory;
+use metrics::Metric;
+use notify::NotifyConfig;
+use pricing::{format_cost, ModelPrice, PriceTable};
+use provider::{OLLAMA, OPENROUTER};
+use rag::{Embedder, RagConfig, Recalled};
+use screensaver::{Screensaver, ScreensaverConfig};
 use server::VideoChatServer;
 use shell::{format_outcome, run as run_shell, ShellOutcome};
 use sysmon::SystemMonitor;
+use text::truncate;
 use tiling::{LayoutPreset, PanelKind, TilingManager};
 use tiles::TilesPanel;
 use tools::{ToolCall, ToolResult, TrustLevel};
-use video::VideoPlayer;
-use theme::t;
+use tachyonfx::{fx, Effect, Interpolation};
+use theme::{t, Drift, ThemeName};
+use voice::{Recording, VoiceConfig};
 use webcam::WebcamCapture;
+use websearch::{SearchResult, WebSearchConfig};
 
 const INTRO_DURATION: Duration = Duration::from_millis(7600);
+/// How much longer than `INTRO_DURATION` the intro waits on startup work.
+const STARTUP_WAIT_LIMIT: Duration = Duration::from_secs(20);
+/// Context sent to the model beyond this is summarized by `build_context`.
+const MAX_CONTEXT_CHARS: usize = 30000;
+/// Appended to the context for `/summarize`.
+const SUMMARY_PROMPT: &str = "Summarize our conversation so far for a reader who missed it: the \
+    questions asked, the answers and decisions reached, and anything left open. Use short bullet \
+    points, keep names, numbers and code identifiers exact, and reply with the summary only.";
+/// How long a deleted message can be restored before it is removed from the database.
+const UNDO_WINDOW: Duration = Duration::from_secs(8);
+/// First wait before queued prompts are resent; doubles per failure up to a minute.
+const OUTBOX_RETRY: Duration = Duration::from_secs(5);
+/// How often the input and attachments are written to `draft.json`.
+const DRAFT_INTERVAL: Duration = Duration::from_secs(3);
+/// Most archived matches a `/` search in the chat transcript considers.
+const CHAT_SEARCH_LIMIT: usize = 1000;
+/// How long the chat deck's own reads wait out a lock: about a frame, where
+/// the archiver waits seconds.
+const READ_BUSY_TIMEOUT: Duration = Duration::from_millis(15);
 
 const LARGE_LOGO: &[&str] = &[
     "  █████╗ ███████╗ ██████╗ ██╗ ██╗ ██╗   ██╗ ██╗ ███████╗ ██╗  ██████╗  ███╗   ██╗",
@@ -78,19 +161,229 @@ const SMALL_LOGO: &[&str] = &[
 const SCROLLER_TEXT: &str =
     " ASCIIVISION v2.0 // AI DEMOZONE // LIVE VIDEO CHAT // WEBCAM ASCII // 3D EFFECTS ENGINE // TRUE PTY TILES // !bash !curl !brew // F2 MODEL // F3 VIDEO // F4 FX CYCLE // F5 WEBCAM // F6 LAYOUT // F7 TILES // CTRL+L PURGE // THIS TERMINAL HAS LEFT THE BUILDING ";
 
+type Tui = Terminal<CrosstermBackend<std::io::Stdout>>;
+
+/// Off-screen terminal size for `--smoke` and `play --headless`.
+const SMOKE_WIDTH: u16 = 160;
+const SMOKE_HEIGHT: u16 = 48;
+
 #[derive(Parser, Debug)]
 #[command(
     name = "asciivision",
-    about = "All-in-one terminal powerhouse: AI chat, live video, webcam streaming, 3D effects, analytics"
+    about = "All-in-one terminal powerhouse: AI chat, live video, webcam streaming, 3D effects, analytics",
+    args_conflicts_with_subcommands = true
 )]
-struct Args {
-    #[arg(long, default_value = "claude")]
-    provider: String,
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Flags for the chat deck when no subcommand is given
+    #[command(flatten)]
+    chat: ChatArgs,
+
+    /// Override one config.toml setting for this run, e.g. `--set theme=dracula`
+    /// or `--set retention.max_age_days=90` (repeatable)
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    set: Vec<String>,
+
+    /// Turn off video effects, fades, 3D effects and animation in every view
+    /// (same as `reduce_motion = true`)
+    #[arg(long, global = true)]
+    no_effects: bool,
+
+    /// Probe the terminal (colors, glyph widths, kitty/sixel graphics, cell
+    /// size in pixels), print what was found and exit
+    #[arg(long)]
+    diagnose: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Launch the AI chat deck (default)
+    Chat(ChatArgs),
+    /// Play a video file as full-screen ASCII art
+    Play(PlayArgs),
+    /// Open the conversation analytics dashboard
+    Analytics(AnalyticsArgs),
+    /// Chunk and embed text/markdown files for `/rag`
+    Index(IndexArgs),
+    /// Embed archived prompts and replies for `/recall`
+    IndexHistory,
+    /// Send one prompt (plus any piped stdin) and print the reply
+    Ask(AskArgs),
+    /// Store a prompt for `daemon` to send on a cron schedule
+    Schedule(ScheduleArgs),
+    /// Send scheduled prompts as they fall due, saving each answer as a session
+    Daemon,
+    /// Store or remove provider API keys in the OS keychain
+    Keys {
+        #[command(subcommand)]
+        action: KeysAction,
+    },
+    /// Export or import the conversation archive
+    Db {
+        #[command(subcommand)]
+        action: DbAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DbAction {
+    /// Write every conversation, message and tag to a JSON file
+    Export { path: PathBuf },
+    /// Add the conversations from a JSON file made by `db export`
+    Import { path: PathBuf },
+    /// Delete history past the `[retention]` limits (flags override them)
+    Prune(PruneArgs),
+    /// Print sizes, counts, date span and index health
+    Stats,
+    /// Merge with the `[sync]` remote copy, then upload the result
+    Sync,
+    /// Permanently remove soft-deleted messages
+    Purge {
+        /// Also remove archived conversations
+        #[arg(long, default_value_t = false)]
+        archived: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+struct PruneArgs {
+    /// Report what would be deleted without deleting it
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Delete messages older than this many days
+    #[arg(long)]
+    max_age_days: Option<u64>,
+
+    /// Keep at most this many messages
+    #[arg(long)]
+    max_messages: Option<u64>,
+
+    /// Delete the oldest messages until the file is roughly this size
+    #[arg(long)]
+    max_size_mb: Option<u64>,
+}
+
+#[derive(Subcommand, Debug)]
+enum KeysAction {
+    /// Prompt for a provider's API key and store it in the keychain
+    Set { provider: String },
+    /// Remove a provider's API key from the keychain
+    Delete { provider: String },
+}
+
+#[derive(Args, Debug)]
+struct AskArgs {
+    /// The prompt; piped stdin is attached below it
+    #[arg(required = true)]
+    prompt: Vec<String>,
+
+    /// Provider to ask; defaults to `default_provider` in config.toml, then claude
+    #[arg(long)]
+    provider: Option<String>,
+
+    /// Model id (required for ollama and openrouter)
+    #[arg(long)]
+    model: Option<String>,
+
+    /// Print one JSON object: provider, model, content, usage and latency
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct ScheduleArgs {
+    /// The prompt to send
+    prompt: Vec<String>,
+
+    /// When to send it: minute hour day month weekday, in local time
+    /// (e.g. "0 9 * * 1-5" for weekdays at nine)
+    #[arg(long, required_unless_present_any = ["list", "remove"])]
+    cron: Option<String>,
+
+    /// Provider to ask; defaults to `default_provider` in config.toml, then claude
+    #[arg(long)]
+    provider: Option<String>,
+
+    /// Model id (required for ollama and openrouter)
+    #[arg(long)]
+    model: Option<String>,
+
+    /// List scheduled prompts with their next and last runs
+    #[arg(long, default_value_t = false, conflicts_with_all = ["prompt", "cron", "remove"])]
+    list: bool,
+
+    /// Delete the scheduled prompt with this id
+    #[arg(long, value_name = "ID", conflicts_with_all = ["prompt", "cron"])]
+    remove: Option<i64>,
+}
+
+#[derive(Args, Debug)]
+struct AnalyticsArgs {
+    /// Database to open instead of the configured one, e.g. a backup or export
+    #[arg(long)]
+    db: Option<PathBuf>,
+
+    /// Load the database and draw every view off-screen for FRAMES frames
+    /// (default 60), then exit: 0 when it worked, 1 with the error otherwise
+    #[arg(long, value_name = "FRAMES", num_args = 0..=1, default_missing_value = "60")]
+    smoke: Option<u32>,
+}
+
+#[derive(Args, Debug)]
+struct IndexArgs {
+    /// Directory to index recursively (.md, .markdown, .txt, .rst)
+    dir: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct PlayArgs {
+    /// Video file to play
+    file: PathBuf,
+
+    /// Restart when the video ends instead of exiting
+    #[arg(long = "loop", default_value_t = false)]
+    looping: bool,
+
+    /// Decode and render off-screen without a terminal, then exit: 0 when
+    /// frames came through, 1 with the error otherwise
+    #[arg(long, default_value_t = false)]
+    headless: bool,
+
+    /// Frames to render with --headless
+    #[arg(long, default_value_t = 120, requires = "headless")]
+    frames: u32,
+
+    /// Play in ROWS lines of the normal scrollback instead of the alternate
+    /// screen; the last frame stays behind when it ends
+    #[arg(
+        long,
+        value_name = "ROWS",
+        num_args = 0..=1,
+        default_missing_value = "20",
+        conflicts_with = "headless",
+        value_parser = clap::value_parser!(u16).range(2..)
+    )]
+    inline: Option<u16>,
+}
+
+#[derive(Args, Debug)]
+struct ChatArgs {
+    /// Provider to start on; defaults to `default_provider` in config.toml, then claude
+    #[arg(long)]
+    provider: Option<String>,
 
     #[arg(long)]
     background_video: Option<String>,
 
+    /// Video looped faintly behind the chat messages; overrides `[chat_background] video`
     #[arg(long)]
+    chat_background: Option<String>,
+
+    /// Loading video for the intro; falls back to config, then the data dirs
+    #[arg(long, alias = "loading-video")]
     intro_video: Option<String>,
 
     #[arg(long, default_value_t = false)]
@@ -121,6 +414,16 @@ struct Args {
     /// Start with 3D effects active
     #[arg(long, default_value_t = false)]
     effects: bool,
+
+    /// Screen-reader friendly: no alternate screen, colors or effects; prompts
+    /// are read a line at a time and messages printed as plain lines
+    #[arg(long, default_value_t = false, conflicts_with = "smoke")]
+    plain: bool,
+
+    /// Start the deck and draw FRAMES frames (default 60) off-screen without
+    /// reading keys, then exit: 0 when it worked, 1 with the error otherwise
+    #[arg(long, value_name = "FRAMES", num_args = 0..=1, default_missing_value = "60")]
+    smoke: Option<u32>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -145,6 +448,57 @@ struct ChatMessage {
     accent: Color,
     include_in_context: bool,
     context_role: &'static str,
+    /// The prompt as typed, before `@file` expansion (user messages only).
+    prompt: Option<String>,
+    /// Pages cited by a `/web` augmented reply.
+    sources: Vec<SearchResult>,
+    /// Model, tokens and latency of a model reply.
+    meta: Option<ReplyMeta>,
+    timestamp: DateTime<Local>,
+    /// Row id in the conversation database, once archived.
+    db_id: Option<i64>,
+    /// Finds the message again when its queued archive write finishes.
+    key: u64,
+    bookmarked: bool,
+    /// Queued in the outbox because the provider was unreachable.
+    pending: bool,
+    /// What an edited prompt said before, oldest first.
+    revisions: Vec<String>,
+    /// Earlier versions expanded under the message (`e` in select mode).
+    show_revisions: bool,
+    /// Pasted or attached images, sent with the prompt to vision models.
+    images: Vec<ai::Image>,
+}
+
+/// A prompt `/edit` pulled back into the input line; the next prompt to the
+/// same provider replaces it as a new version.
+struct PendingEdit {
+    /// Row id of the archived prompt.
+    db_id: Option<i64>,
+    provider: String,
+    /// Every earlier version, oldest first, ending with the one pulled back.
+    revisions: Vec<String>,
+}
+
+/// A `/summarize` result and the transcript message it runs up to.
+struct ContextSummary {
+    text: String,
+    through: u64,
+    /// Send the summary in place of the messages it covers.
+    trim: bool,
+}
+
+/// Prompts that could not reach the provider, resent automatically.
+struct Outbox {
+    attempts: u32,
+    next_attempt: Instant,
+}
+
+/// A message removed in select mode, kept until the undo window closes.
+struct PendingDelete {
+    message: ChatMessage,
+    index: usize,
+    deadline: Instant,
 }
 
 struct PendingApprovalState {
@@ -155,16 +509,31 @@ struct PendingApprovalState {
 
 struct RevealJob {
     message_index: usize,
-    full_text: Vec<char>,
+    /// Revealed a grapheme at a time so accents and emoji are never split.
+    full_text: Vec<String>,
     revealed: usize,
     speed: usize,
 }
 
+/// One row in the model picker, already formatted for display.
+#[derive(Debug, Clone)]
+struct PickerModel {
+    name: String,
+    meta: String,
+    price: Option<ModelPrice>,
+    /// What the catalog or local server reports the model supports.
+    capabilities: Option<Capabilities>,
+}
+
 #[allow(dead_code)]
 enum AppEvent {
     AiFinished {
         session_id: u64,
         result: std::result::Result<String, String>,
+        /// Set when the provider rejected the request with an HTTP error.
+        http_status: Option<u16>,
+        /// The provider was unreachable; the prompt goes to the outbox.
+        offline: bool,
     },
     AiToolCalls {
         session_id: u64,
@@ -182,6 +551,45 @@ enum AppEvent {
         session_id: u64,
         chunk: StreamChunk,
     },
+    /// Token usage of a non-streaming call (tool-result follow-ups).
+    AiUsage {
+        session_id: u64,
+        usage: Usage,
+    },
+    /// Query embedding for a `/rag` prompt.
+    RagEmbedded {
+        session_id: u64,
+        query: String,
+        result: std::result::Result<Vec<f32>, String>,
+    },
+    /// Query embedding for `/recall`, made with the `[rag]` model.
+    RecallEmbedded {
+        query: String,
+        model: String,
+        result: std::result::Result<Vec<f32>, String>,
+    },
+    /// Text heard in a `/voice` recording.
+    Transcribed {
+        result: std::result::Result<String, String>,
+    },
+    /// Search results for a `/web` prompt, ready to go into its context.
+    WebResults {
+        session_id: u64,
+        query: String,
+        result: std::result::Result<Vec<SearchResult>, String>,
+    },
+    /// A chunk for one pane of `/compare`.
+    CompareChunk {
+        round: u64,
+        pane: usize,
+        chunk: StreamChunk,
+    },
+    CompareFinished {
+        round: u64,
+        pane: usize,
+        /// Error text and HTTP status when the request failed.
+        error: Option<(String, Option<u16>)>,
+    },
     ShellFinished {
         outcome: ShellOutcome,
     },
@@ -192,40 +600,168 @@ enum AppEvent {
     YoutubeFailed {
         error: String,
     },
-    OllamaModelsReady {
-        models: Vec<OllamaModelInfo>,
+    /// A `/summarize` reply covering the transcript up to message `through`.
+    Summarized {
+        through: u64,
+        trim: bool,
+        result: std::result::Result<String, String>,
+    },
+    ModelsReady {
+        provider: AIProvider,
+        models: Vec<PickerModel>,
     },
-    OllamaModelsFailed {
+    ModelsFailed {
+        provider: AIProvider,
         error: String,
     },
+    /// The archive opened off the UI thread, with agent memory and the starting
+    /// provider's recent messages read from it.
+    ArchiveReady {
+        db: Option<Database>,
+        archiver: Option<Archiver>,
+        memory: AgentMemory,
+        history: std::result::Result<Vec<StoredMessage>, String>,
+        /// Results of the startup `[sync]` and `[retention]` prune, or why
+        /// the archive could not be opened.
+        notices: Vec<String>,
+    },
     PendingApproval {
         session_id: u64,
         tool_calls: Vec<ToolCall>,
         context: Vec<ApiMessage>,
     },
+    /// A queued archive write finished; applies its result.
+    Archived(Box<dyn FnOnce(&mut App) + Send>),
+}
+
+/// Background startup work the intro waits for before handing over to chat.
+#[derive(Clone, Copy, Default)]
+struct Startup {
+    /// Database, agent memory and history restore.
+    archive: bool,
+    /// The starting provider's model catalog and capabilities.
+    models: bool,
+}
+
+impl Startup {
+    fn done(&self) -> bool {
+        !self.archive && !self.models
+    }
+
+    fn pending(&self) -> String {
+        let mut pending = Vec::new();
+        if self.archive {
+            pending.push("archive + history");
+        }
+        if self.models {
+            pending.push("model catalog");
+        }
+        pending.join(" + ")
+    }
+}
+
+/// Geometry of the transcript as last drawn.
+#[derive(Clone, Copy, Default)]
+struct TranscriptView {
+    width: u16,
+    height: u16,
+    max_scroll: usize,
 }
 
 struct App {
     mode: AppMode,
     provider: AIProvider,
+    providers: ProviderRegistry,
     ai_client: AIClient,
+    /// The provider and client an `/ask` turn borrowed the deck from, put
+    /// back once its reply is done.
+    ask_return: Option<(AIProvider, AIClient)>,
     video: Option<VideoPlayer>,
     video_enabled: bool,
     video_source_label: String,
+    screensaver_config: ScreensaverConfig,
+    /// Looped by the screensaver: `[screensaver] video`, else the loading video.
+    screensaver_video: Option<PathBuf>,
+    screensaver: Option<Screensaver>,
+    /// `[chat_background]` / `--chat-background`; off under `--no-video` and `reduce_motion`.
+    chat_background: Option<ChatBackground>,
+    /// Last key press, for the screensaver's idle timer.
+    last_activity: Instant,
     pending_video_load: bool,
-    ollama_models: Vec<OllamaModelInfo>,
+    picker_models: Vec<PickerModel>,
     ollama_selected_model: Option<String>,
-    show_ollama_picker: bool,
-    ollama_picker_loading: bool,
-    ollama_picker_error: Option<String>,
-    ollama_selection_input: String,
-    ollama_picker_scroll: usize,
+    openrouter_selected_model: Option<String>,
+    provider_settings: HashMap<String, ProviderSettings>,
+    max_retries: u32,
+    prices: PriceTable,
+    capabilities: CapabilityTable,
+    /// Usage not yet attached to an archived reply.
+    turn_usage: Usage,
+    /// Why the streaming reply stopped, as the provider reported it.
+    turn_finish: Option<String>,
+    session_usage: Usage,
+    session_cost: f64,
+    /// When the current model request was sent, for reply latency.
+    request_started: Instant,
+    last_http_status: Option<u16>,
+    show_model_picker: bool,
+    model_picker_loading: bool,
+    model_picker_error: Option<String>,
+    model_selection_input: String,
+    model_picker_scroll: usize,
     input: String,
+    /// Files queued with `/attach` for the next prompt.
+    attachments: Vec<Attachment>,
+    /// The last session's unsent draft, until `/draft` restores or discards
+    /// it; autosave leaves `draft.json` alone meanwhile.
+    draft_offer: Option<Draft>,
+    /// What `draft.json` holds now, and when the input was last checked.
+    draft_saved: Option<Draft>,
+    draft_checked: Instant,
+    editing: Option<PendingEdit>,
+    /// Multi-provider mode started with `/compare <a> <b>` or `/arena`.
+    compare: Option<CompareState>,
+    /// `/web`: run each prompt through the search backend first.
+    web_enabled: bool,
+    web_config: WebSearchConfig,
+    /// Results waiting to be attached to the next assistant reply.
+    pending_sources: Vec<SearchResult>,
+    /// `/voice` take in progress; Enter stops it, Esc discards it.
+    voice: Option<Recording>,
+    voice_config: VoiceConfig,
+    /// A finished take is at the transcription backend.
+    transcribing: bool,
+    /// False while the terminal reports it has lost focus.
+    terminal_focused: bool,
+    notify_config: NotifyConfig,
+    /// `/rag`: set while local document retrieval is on.
+    rag: Option<Embedder>,
+    rag_config: RagConfig,
+    /// The last `/recall` query and what it found, for `/recall use`.
+    recalled: Option<(String, Vec<Recalled>)>,
     messages: Vec<ChatMessage>,
     reveal_queue: VecDeque<RevealJob>,
-    show_help: bool,
+    help: HelpOverlay,
+    log_viewer: LogViewer,
     follow_tail: bool,
     scroll_lines: usize,
+    /// Ctrl+T / `/meta`: timestamps, model, tokens and latency under each message.
+    show_meta: bool,
+    /// Transcript width and max scroll from the last render, for vim motions.
+    transcript_view: Cell<TranscriptView>,
+    keymap: Keymap,
+    search_query: String,
+    /// Message index of the current `/` search match.
+    search_hit: Option<usize>,
+    /// Highlighted message while in select mode.
+    selected_message: Option<usize>,
+    /// Message shown full-screen in the detail pane.
+    detail_message: Option<usize>,
+    detail_scroll: u16,
+    pending_delete: Option<PendingDelete>,
+    outbox: Option<Outbox>,
+    /// Tab completion popup over the input.
+    completion: Option<Completion>,
     pending_ai: bool,
     pending_shells: usize,
     session_id: u64,
@@ -233,20 +769,28 @@ struct App {
     last_shell_status: String,
     events_tx: mpsc::UnboundedSender<AppEvent>,
     events_rx: mpsc::UnboundedReceiver<AppEvent>,
+    /// Read connection to the conversation archive, which gives up on a lock
+    /// after `READ_BUSY_TIMEOUT`; writes and the slower reads are queued on
+    /// `archiver` so SQLite never holds up a frame.
     db: Option<Database>,
+    archiver: Option<Archiver>,
     last_tick: Instant,
     intro_started: Instant,
+    startup: Startup,
     status_note: String,
 
     // new modules
     effects: EffectsEngine,
+    /// `reduce_motion` / `--no-effects`: animations hold still and 3D effects
+    /// stay off.
+    reduce_motion: bool,
     games: GamesPanel,
     tiles: TilesPanel,
     analytics: AnalyticsPanel,
     tiling: TilingManager,
     sysmon: SystemMonitor,
     webcam: Option<WebcamCapture>,
-    webcam_frame: Option<video::AsciiFrame>,
+    webcam_frame: Option<AsciiFrame>,
     video_chat: Option<VideoChatClient>,
     username: String,
     /// cached body area for tiling direction calculations
@@ -258,34 +802,77 @@ struct App {
     pending_approval: Option<PendingApprovalState>,
     tool_loop_depth: usize,
     streaming_active: bool,
+    /// Cancels the in-flight model request (Esc while the link is busy).
+    ai_cancel: Option<CancellationToken>,
+    /// When the pending backoff ends, plus the attempt/status label for the input bar.
+    retry_notice: Option<(Instant, String)>,
     last_esc_time: Instant,
     stream_buffer: String,
     stream_message_index: Option<usize>,
     pinned_messages: Vec<usize>,
+    /// The last `/summarize` result, standing in for the turns it covers
+    /// when asked to.
+    summary: Option<ContextSummary>,
+    summarizing: bool,
     shell_output_history: VecDeque<String>,
     prev_mode: AppMode,
 }
 
+/// A fresh `ChatMessage::key`.
+fn next_message_key() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
 impl ChatMessage {
     fn user(content: String) -> Self {
         Self {
             kind: MessageKind::User,
             label: "YOU".to_string(),
             content,
-            accent: t().accent4,
+            accent: t().user,
             include_in_context: true,
             context_role: "user",
+            prompt: None,
+            sources: Vec::new(),
+            meta: None,
+            timestamp: Local::now(),
+            db_id: None,
+            key: next_message_key(),
+            bookmarked: false,
+            pending: false,
+            revisions: Vec::new(),
+            show_revisions: false,
+            images: Vec::new(),
         }
     }
 
-    fn assistant(provider: &AIProvider) -> Self {
+    /// A reply from `model`, named after the provider and also the model
+    /// when it isn't the provider's default.
+    fn assistant(provider: &AIProvider, model: &str) -> Self {
+        let label = if model.is_empty() || model == provider.model() {
+            provider.name().to_string()
+        } else {
+            format!("{} // {}", provider.name(), model)
+        };
         Self {
             kind: MessageKind::Assistant,
-            label: provider.name().to_string(),
+            label,
             content: String::new(),
             accent: provider.color(),
             include_in_context: true,
             context_role: "assistant",
+            prompt: None,
+            sources: Vec::new(),
+            meta: None,
+            timestamp: Local::now(),
+            db_id: None,
+            key: next_message_key(),
+            bookmarked: false,
+            pending: false,
+            revisions: Vec::new(),
+            show_revisions: false,
+            images: Vec::new(),
         }
     }
 
@@ -297,6 +884,17 @@ impl ChatMessage {
             accent,
             include_in_context: true,
             context_role: "user",
+            prompt: None,
+            sources: Vec::new(),
+            meta: None,
+            timestamp: Local::now(),
+            db_id: None,
+            key: next_message_key(),
+            bookmarked: false,
+            pending: false,
+            revisions: Vec::new(),
+            show_revisions: false,
+            images: Vec::new(),
         }
     }
 
@@ -308,6 +906,17 @@ impl ChatMessage {
             accent: t().accent1,
             include_in_context: false,
             context_role: "user",
+            prompt: None,
+            sources: Vec::new(),
+            meta: None,
+            timestamp: Local::now(),
+            db_id: None,
+            key: next_message_key(),
+            bookmarked: false,
+            pending: false,
+            revisions: Vec::new(),
+            show_revisions: false,
+            images: Vec::new(),
         }
     }
 }
@@ -316,7 +925,7 @@ impl RevealJob {
     fn new(message_index: usize, text: String, speed: usize) -> Self {
         Self {
             message_index,
-            full_text: text.chars().collect(),
+            full_text: text::graphemes(&text).into_iter().map(str::to_string).collect(),
             revealed: 0,
             speed,
         }
@@ -324,35 +933,82 @@ impl RevealJob {
 }
 
 impl App {
-    fn new(args: Args) -> Result<Self> {
+    fn new(args: ChatArgs, config: &Config) -> Result<Self> {
         let (events_tx, events_rx) = mpsc::unbounded_channel();
-        let provider = AIProvider::from_input(&args.provider);
+        let providers = ProviderRegistry::new(config.custom_providers());
+        let provider = providers.resolve(
+            args.provider
+                .as_deref()
+                .or(config.default_provider.as_deref())
+                .unwrap_or("claude"),
+        );
+        // Explicit paths first (CLI, then config), then the bundled/installed fallbacks.
+        let requested: Vec<PathBuf> = [args.background_video.clone(), args.intro_video.clone()]
+            .into_iter()
+            .flatten()
+            .map(PathBuf::from)
+            .chain(config.loading_video())
+            .collect();
+        let mut video_notices: Vec<String> = requested
+            .iter()
+            .filter(|path| !path.exists())
+            .map(|path| format!("video not found: {}", path.display()))
+            .collect();
         let video_path = if args.no_video {
             None
         } else {
-            resolve_video_path(args.background_video, args.intro_video)
+            resolve_video_path(&requested)
         };
+        let video = video_path.as_ref().and_then(|path| {
+            let options = VideoOptions {
+                looping: true,
+                effects: !config.reduce_motion,
+                ..VideoOptions::default()
+            };
+            VideoPlayer::new(path, options)
+                .map_err(|error| {
+                    video_notices.push(format!("video {} failed: {:#}", path.display(), error));
+                })
+                .ok()
+        });
         let video_source_label = video_path
             .as_ref()
+            .filter(|_| video.is_some())
             .and_then(|path| path.file_name())
             .and_then(|name| name.to_str())
             .unwrap_or("synthetic raster")
             .to_string();
-        let video = match video_path {
-            Some(path) => Some(VideoPlayer::new(path, (132, 46), true)?),
-            None => None,
-        };
-
-        let db = if args.no_db {
-            None
-        } else {
-            Database::new().ok()
-        };
+        let screensaver_video = config
+            .screensaver
+            .video
+            .as_deref()
+            .map(config::expand_home)
+            .or_else(|| video_path.clone());
+        let chat_background = args
+            .chat_background
+            .as_deref()
+            .or(config.chat_background.video.as_deref())
+            .filter(|_| !args.no_video && !config.reduce_motion)
+            .map(config::expand_home)
+            .and_then(|path| {
+                let (width, height) = crossterm::terminal::size().unwrap_or((120, 40));
+                let background = config.chat_background.clone();
+                ChatBackground::start(&path, width, height, background)
+                    .map_err(|error| {
+                        video_notices.push(format!(
+                            "chat background {} failed: {:#}",
+                            path.display(),
+                            error
+                        ));
+                    })
+                    .ok()
+            });
 
         let mut effects = EffectsEngine::new();
-        if args.effects {
+        if args.effects && !config.reduce_motion {
             effects.active = true;
         }
+        let skip_intro = args.skip_intro || config.reduce_motion;
 
         let webcam = if args.webcam {
             let config = webcam::WebcamConfig {
@@ -365,37 +1021,78 @@ impl App {
             None
         };
 
-        let mut agent_memory = AgentMemory::new();
-        if let Some(ref db) = db {
-            let _ = AgentMemory::init_table(db);
-            agent_memory.load(db);
-        }
-
         let mut app = Self {
-            mode: if args.skip_intro {
+            mode: if skip_intro {
                 AppMode::Chat
             } else {
                 AppMode::Intro
             },
             provider: provider.clone(),
+            providers,
             ai_client: AIClient::new(provider.clone(), None),
+            ask_return: None,
             video_enabled: true,
             video,
             video_source_label,
+            screensaver_config: config.screensaver.clone(),
+            screensaver_video,
+            screensaver: None,
+            chat_background,
+            last_activity: Instant::now(),
             pending_video_load: false,
-            ollama_models: Vec::new(),
+            picker_models: Vec::new(),
             ollama_selected_model: None,
-            show_ollama_picker: false,
-            ollama_picker_loading: false,
-            ollama_picker_error: None,
-            ollama_selection_input: String::new(),
-            ollama_picker_scroll: 0,
+            openrouter_selected_model: None,
+            provider_settings: config.provider_settings.clone(),
+            max_retries: config.max_retries(),
+            prices: PriceTable::new(config.pricing.clone()),
+            capabilities: CapabilityTable::new(config.capabilities.clone()),
+            turn_usage: Usage::default(),
+            turn_finish: None,
+            session_usage: Usage::default(),
+            session_cost: 0.0,
+            request_started: Instant::now(),
+            last_http_status: None,
+            show_model_picker: false,
+            model_picker_loading: false,
+            model_picker_error: None,
+            model_selection_input: String::new(),
+            model_picker_scroll: 0,
             input: String::new(),
+            attachments: Vec::new(),
+            draft_offer: None,
+            draft_saved: None,
+            draft_checked: Instant::now(),
+            editing: None,
+            compare: None,
+            web_enabled: false,
+            web_config: config.web_search.clone(),
+            pending_sources: Vec::new(),
+            voice: None,
+            voice_config: config.voice.clone(),
+            transcribing: false,
+            terminal_focused: true,
+            notify_config: config.notifications.clone(),
+            rag: None,
+            rag_config: config.rag.clone(),
+            recalled: None,
             messages: Vec::new(),
             reveal_queue: VecDeque::new(),
-            show_help: false,
+            help: HelpOverlay::new("HELP // ASCIIVISION KEYS", keymap::chat_bindings()),
+            log_viewer: LogViewer::default(),
             follow_tail: true,
             scroll_lines: 0,
+            show_meta: false,
+            transcript_view: Cell::new(TranscriptView::default()),
+            keymap: Keymap::new(config.vim_mode),
+            search_query: String::new(),
+            search_hit: None,
+            selected_message: None,
+            detail_message: None,
+            detail_scroll: 0,
+            pending_delete: None,
+            outbox: None,
+            completion: None,
             pending_ai: false,
             pending_shells: 0,
             session_id: 0,
@@ -403,12 +1100,15 @@ impl App {
             last_shell_status: "shell bus idle".to_string(),
             events_tx,
             events_rx,
-            db,
+            db: None,
+            archiver: None,
             last_tick: Instant::now(),
             intro_started: Instant::now(),
+            startup: Startup::default(),
             status_note: "cold boot // intro online".to_string(),
 
             effects,
+            reduce_motion: config.reduce_motion,
             games: GamesPanel::new(),
             tiles: TilesPanel::new(),
             analytics: AnalyticsPanel::new(),
@@ -421,83 +1121,400 @@ impl App {
             body_area: Rect::default(),
 
             trust_level: TrustLevel::ConfirmDestructive,
-            agent_memory,
+            agent_memory: AgentMemory::new(),
             pending_approval: None,
             tool_loop_depth: 0,
             streaming_active: false,
+            ai_cancel: None,
+            retry_notice: None,
             last_esc_time: Instant::now() - Duration::from_secs(10),
             stream_buffer: String::new(),
             stream_message_index: None,
             pinned_messages: Vec::new(),
+            summary: None,
+            summarizing: false,
             shell_output_history: VecDeque::new(),
-            prev_mode: if args.skip_intro {
+            prev_mode: if skip_intro {
                 AppMode::Chat
             } else {
                 AppMode::Intro
             },
         };
+        app.rebuild_ai_client();
 
-        app.add_system_message(
-            "shell deck armed: use !<command> for bash, or /curl and /brew for shortcuts",
-        );
-        app.add_system_message(format!(
-            "provider uplink live: {} // F2 rotate // F4 fx cycle // F5 webcam // F7 tiles",
-            app.provider_display_name()
-        ));
-        app.add_system_message(format!(
-            "agentic mode online: tool-use loop active // trust level: {} // /trust to cycle",
-            app.trust_level.name()
-        ));
-        app.add_system_message(
-            "context: @<filepath> to inject file // /pin to pin messages // /remember <key>=<value> to store memory"
-        );
-        app.add_system_message(
-            "video chat: /server <port> to host, /connect ws://<addr> to join, /chat <msg> to send"
-        );
-        app.add_system_message(
-            "games bay online: /games to load the arcade panel, 1-3 to launch, WASD to play when that tile is focused"
-        );
-        app.add_system_message(
-            "tiles online: /tiles or F7 boots live PTY terminals // /tiles 4 for a 2x2 shell grid"
-        );
-        app.add_system_message(
-            "tiling: Ctrl+hjkl focus, Ctrl+Shift+hjkl swap, Ctrl+[/] resize, Ctrl+n cycle panel, /layout cycle preset"
-        );
+        // the keyboard tour is no use to someone reading `--plain` output
+        if !args.plain {
+            app.add_system_message(
+                "shell deck armed: use !<command> for bash, or /curl and /brew for shortcuts",
+            );
+            app.add_system_message(format!(
+                "provider uplink live: {} // F2 rotate // F4 fx cycle // F5 webcam // F7 tiles",
+                app.provider_display_name()
+            ));
+            app.add_system_message(format!(
+                "agentic mode online: tool-use loop active // trust level: {} // /trust to cycle",
+                app.trust_level.name()
+            ));
+            app.add_system_message(
+                "context: @<filepath> to inject file // /pin to pin messages // /remember <key>=<value> to store memory"
+            );
+            app.add_system_message(
+                "video chat: /server <port> to host, /connect ws://<addr> to join, /chat <msg> to send"
+            );
+            app.add_system_message(
+                "games bay online: /games to load the arcade panel, 1-3 to launch, WASD to play when that tile is focused"
+            );
+            app.add_system_message(
+                "tiles online: /tiles or F7 boots live PTY terminals // /tiles 4 for a 2x2 shell grid"
+            );
+            app.add_system_message(
+                "tiling: Ctrl+hjkl focus, Ctrl+Shift+hjkl swap, Ctrl+[/] resize, Ctrl+n cycle panel, /layout cycle preset"
+            );
+        }
 
-        if app.video.is_none() {
-            app.add_system_message("video signal offline: no bundled mp4 found, falling back to synthetic raster field");
+        for notice in video_notices {
+            app.add_system_message(notice);
+        }
+        if app.video.is_none() && !args.no_video {
+            let data_dir = paths::data_dir()
+                .map(|dir| dir.join("loading.mp4").display().to_string())
+                .unwrap_or_else(|_| "the data directory".to_string());
+            app.add_system_message(format!("video signal offline: no loading video found (loading_video, --intro-video or {}), running the procedural boot animation", data_dir));
         }
 
-        if app.db.is_none() && !args.no_db {
-            app.add_system_message(
-                "conversation archive offline: ~/.config/asciivision could not be initialized",
-            );
+        if let Some(error) = &config.load_error {
+            tracing::warn!("config ignored: {}", error);
+            app.add_system_message(format!("config ignored: {}", error));
+        }
+        let custom: Vec<String> = app
+            .providers
+            .custom()
+            .map(|provider| provider.name().to_string())
+            .collect();
+        if !custom.is_empty() {
+            app.add_system_message(format!(
+                "custom providers from config: {} // /provider <name> to route",
+                custom.join(", ")
+            ));
         }
 
-        if app.provider == AIProvider::Ollama {
-            app.prepare_ollama_provider("startup route");
+        if app.provider.has_model_picker() {
+            app.startup.models = true;
+            app.prepare_model_picker("startup route");
         }
 
         if app.webcam.is_some() {
             app.add_system_message("webcam capture online: live ascii feed active");
         }
 
+        if !args.no_db {
+            app.open_archive(config);
+        }
+        app.apply_theme_drift();
+
         Ok(app)
     }
 
+    /// Opens the conversation database, loads agent memory and reads back the
+    /// starting provider's history on a blocking thread while the intro plays.
+    fn open_archive(&mut self, config: &Config) {
+        self.startup.archive = true;
+        let path = config.database_path();
+        let limit = config.history_limit();
+        let retention = config.retention.clone();
+        let sync = Some(config.sync.clone()).filter(|sync| sync.on_startup);
+        let provider = self.provider.db_key().to_string();
+        let tx = self.events_tx.clone();
+        let runtime = tokio::runtime::Handle::current();
+        tokio::task::spawn_blocking(move || {
+            let opened = path.and_then(Database::open);
+            let mut memory = AgentMemory::new();
+            let mut history = Ok(Vec::new());
+            let mut notices = Vec::new();
+            if let Ok(db) = &opened {
+                if let Some(backup) = db.migration_backup() {
+                    notices.push(format!(
+                        "archive upgraded // previous version saved as {}",
+                        backup.display()
+                    ));
+                }
+                if let Some(sync) = sync {
+                    notices.push(match runtime.block_on(sync::run(&sync, db)) {
+                        Ok(report) => format!("sync: {}", report.summary()),
+                        Err(error) => format!("sync failed: {:#}", error),
+                    });
+                }
+                if !retention.is_empty() {
+                    match db.prune(&retention, false, false) {
+                        Ok(report) if report.messages == 0 => {}
+                        Ok(report) => notices.push(format!(
+                            "retention: pruned {} old messages // {} -> {}",
+                            report.messages,
+                            human_bytes(report.bytes_before),
+                            human_bytes(report.bytes_after)
+                        )),
+                        Err(error) => notices.push(format!("retention prune failed: {:#}", error)),
+                    }
+                }
+                let _ = AgentMemory::init_table(db);
+                memory.load(db);
+                if limit > 0 {
+                    history = db
+                        .recent_messages(&provider, limit)
+                        .map_err(|error| error.to_string());
+                }
+            }
+            // reads stay on this thread's side; the opened connection becomes
+            // the archiver's
+            let opened = opened.and_then(|db| {
+                let reader = Database::open_read_only(db.path())?;
+                reader.set_busy_timeout(READ_BUSY_TIMEOUT)?;
+                Ok((reader, Archiver::spawn(db)))
+            });
+            let (db, archiver) = match opened {
+                Ok((reader, archiver)) => (Some(reader), Some(archiver)),
+                Err(error) => {
+                    notices.push(format!("conversation archive offline: {:#}", error));
+                    (None, None)
+                }
+            };
+            let _ = tx.send(AppEvent::ArchiveReady {
+                db,
+                archiver,
+                memory,
+                history,
+                notices,
+            });
+        });
+    }
+
+    /// `/tag [name ...] [-name ...]`: adds and removes tags on the archived
+    /// conversation, then lists the ones it carries.
+    fn tag_conversation(&mut self, args: &str) {
+        let args = args.to_string();
+        let provider = self.provider.db_key().to_string();
+        let queued = self.archive(
+            move |db| {
+                let mut failed = None;
+                for arg in args.split_whitespace() {
+                    let (remove, name) = match arg.strip_prefix('-') {
+                        Some(name) => (true, name),
+                        None => (false, arg),
+                    };
+                    let name = name.trim_start_matches('#').to_lowercase();
+                    if name.is_empty() {
+                        continue;
+                    }
+                    let result = if remove {
+                        db.remove_tag(&name).map(|_| ())
+                    } else {
+                        db.add_tag(&provider, &name)
+                    };
+                    if let Err(error) = result {
+                        failed = Some(format!("tag error: {:#}", error));
+                        break;
+                    }
+                }
+                Ok(match (failed, db.current_tags()) {
+                    (Some(error), _) => error,
+                    (None, Err(error)) => format!("tag error: {:#}", error),
+                    (None, Ok(tags)) if tags.is_empty() => {
+                        "no tags on this conversation // /tag <name> adds one".to_string()
+                    }
+                    (None, Ok(tags)) => format!(
+                        "tags: {}",
+                        tags.iter().map(|tag| format!("#{}", tag)).collect::<Vec<_>>().join(" ")
+                    ),
+                })
+            },
+            |app, message| app.add_system_message(message),
+        );
+        if !queued {
+            self.add_system_message("tags offline: database not available");
+        }
+    }
+
+    /// `/export [path]`: writes the conversation on screen (without system
+    /// notices) as a Markdown transcript.
+    fn export_transcript(&mut self, path: Option<&str>) {
+        let entries: Vec<transcript::Entry> = self
+            .messages
+            .iter()
+            .filter_map(|message| {
+                let (kind, speaker) = match message.kind {
+                    MessageKind::User => (transcript::EntryKind::Prompt, "You".to_string()),
+                    MessageKind::Assistant => {
+                        (transcript::EntryKind::Reply, message.label.clone())
+                    }
+                    MessageKind::Shell => (transcript::EntryKind::Shell, "Shell".to_string()),
+                    MessageKind::System => return None,
+                };
+                Some(transcript::Entry {
+                    kind,
+                    speaker,
+                    model: message.meta.as_ref().map(|meta| meta.model.clone()),
+                    timestamp: message.timestamp,
+                    content: &message.content,
+                })
+            })
+            .collect();
+        if entries.is_empty() {
+            self.add_system_message("nothing to export yet");
+            return;
+        }
+        let path = path
+            .map(config::expand_home)
+            .unwrap_or_else(|| transcript::default_path(self.provider.db_key()));
+        let title = format!("asciivision // {}", self.provider_display_name());
+        let count = entries.len();
+        let note = match transcript::write(&path, &transcript::markdown(&title, &entries)) {
+            Ok(()) => format!("exported {} messages to {}", count, path.display()),
+            Err(error) => format!("export failed: {:#}", error),
+        };
+        self.add_system_message(note);
+    }
+
+    /// Replays the provider's most recent archived messages so the model keeps
+    /// context across restarts.
+    fn restore_history(&mut self, history: std::result::Result<Vec<StoredMessage>, String>) {
+        let rows = match history {
+            Ok(rows) => rows,
+            Err(error) => {
+                self.add_system_message(format!("history restore failed: {}", error));
+                return;
+            }
+        };
+        if rows.is_empty() {
+            return;
+        }
+
+        self.add_system_message(format!(
+            "──── restored {} messages from {} archive ────",
+            rows.len(),
+            self.provider.db_key()
+        ));
+        for row in rows {
+            let mut message = match (row.role.as_str(), row.kind.as_str()) {
+                (_, "shell") => ChatMessage {
+                    content: row.content,
+                    ..ChatMessage::shell(t().accent3)
+                },
+                ("assistant", _) => {
+                    let model = row.meta.as_ref().map_or("", |meta| meta.model.as_str());
+                    let reply = ChatMessage::assistant(&self.provider, model);
+                    ChatMessage {
+                        content: row.content,
+                        meta: row.meta,
+                        ..reply
+                    }
+                }
+                _ => ChatMessage::user(row.content),
+            };
+            if let Some(timestamp) = Local.timestamp_opt(row.timestamp, 0).single() {
+                message.timestamp = timestamp;
+            }
+            message.db_id = Some(row.id);
+            message.bookmarked = row.bookmarked;
+            message.revisions = row.revisions;
+            self.messages.push(message);
+        }
+        self.add_system_message("──── end of restored history // new session below ────");
+    }
+
+    /// True, with a note saying why, when `reduce_motion` keeps 3D effects off.
+    fn motion_reduced(&mut self) -> bool {
+        if self.reduce_motion {
+            self.status_note = "3D fx disabled by reduce_motion / --no-effects".to_string();
+        }
+        self.reduce_motion
+    }
+
+    /// Replaces the video panel's tachyonfx effects with the theme's hue drift.
+    fn apply_theme_drift(&mut self) {
+        let drift = t().drift;
+        if let Some(video) = self.video.as_mut() {
+            video.clear_effects();
+            if let Some(drift) = drift {
+                video.add_effect(drift_effect(drift));
+            }
+        }
+    }
+
     fn rebuild_ai_client(&mut self) {
-        let model = if self.provider == AIProvider::Ollama {
+        self.ai_client = self.client_for(&self.provider);
+    }
+
+    /// Bell and/or desktop notification for a finished reply, when the terminal
+    /// is unfocused and the provider has not opted out.
+    fn notify_reply(&self, text: &str) {
+        if self.terminal_focused {
+            return;
+        }
+        let enabled = self
+            .provider_settings
+            .get(self.provider.db_key())
+            .and_then(|settings| settings.notify)
+            .unwrap_or(true);
+        if !enabled {
+            return;
+        }
+        if self.notify_config.bell {
+            notify::bell();
+        }
+        if self.notify_config.desktop {
+            notify::desktop(
+                format!("asciivision // {}", self.provider_display_name()),
+                notify::preview(text, self.notify_config.preview_chars),
+            );
+        }
+    }
+
+    /// A client for `provider` with its configured settings and picked model.
+    fn client_for(&self, provider: &AIProvider) -> AIClient {
+        let model = if provider.is(&OLLAMA) {
             self.ollama_selected_model.clone()
+        } else if provider.is(&OPENROUTER) {
+            self.openrouter_selected_model.clone()
         } else {
             None
         };
-        self.ai_client = AIClient::new(self.provider.clone(), model);
+        let settings = self
+            .provider_settings
+            .get(provider.db_key())
+            .cloned()
+            .unwrap_or_default();
+        let client = AIClient::new(provider.clone(), model)
+            .with_settings(settings)
+            .with_max_retries(self.max_retries);
+        let capabilities = self.capabilities.lookup(provider, client.model_label());
+        client.with_capabilities(capabilities)
+    }
+
+    /// The runtime-picked model for the active provider, if it uses a picker.
+    fn selected_model(&self) -> Option<&String> {
+        if self.provider.is(&OLLAMA) {
+            self.ollama_selected_model.as_ref()
+        } else if self.provider.is(&OPENROUTER) {
+            self.openrouter_selected_model.as_ref()
+        } else {
+            None
+        }
+    }
+
+    fn set_selected_model(&mut self, model: Option<String>) {
+        if self.provider.is(&OLLAMA) {
+            self.ollama_selected_model = model;
+        } else if self.provider.is(&OPENROUTER) {
+            self.openrouter_selected_model = model;
+        }
     }
 
     fn provider_display_name(&self) -> String {
-        if self.provider == AIProvider::Ollama {
-            if let Some(model) = &self.ollama_selected_model {
+        if self.provider.has_model_picker() {
+            if let Some(model) = self.selected_model() {
                 format!("{} ({})", self.provider.name(), model)
+            } else if self.provider.is(&OPENROUTER) {
+                format!("{} (auto)", self.provider.name())
             } else {
                 format!("{} (select model)", self.provider.name())
             }
@@ -506,10 +1523,17 @@ impl App {
         }
     }
 
+    /// An empty reply from the active provider, labelled with its model.
+    fn reply_message(&self) -> ChatMessage {
+        ChatMessage::assistant(&self.provider, self.ai_client.model_label())
+    }
+
     fn provider_status_badge(&self) -> String {
-        if self.provider == AIProvider::Ollama {
-            if let Some(model) = &self.ollama_selected_model {
+        if self.provider.has_model_picker() {
+            if let Some(model) = self.selected_model() {
                 format!("{} // {}", self.provider.badge(), truncate(model, 24))
+            } else if self.provider.is(&OPENROUTER) {
+                format!("{} // auto", self.provider.badge())
             } else {
                 format!("{} // select model", self.provider.badge())
             }
@@ -518,14 +1542,89 @@ impl App {
         }
     }
 
-    fn request_ollama_models(&mut self) {
-        self.ollama_picker_loading = true;
-        self.ollama_picker_error = None;
+    /// Token window of the active model: config override, then the picker
+    /// catalog, then the provider's stock model.
+    fn context_window(&self) -> u32 {
+        self.provider_settings
+            .get(self.provider.db_key())
+            .and_then(|settings| settings.context_window)
+            .unwrap_or_else(|| self.ai_client.capabilities().context_window)
+    }
+
+    /// Rough size of the next request (4 characters per token) and whether
+    /// `build_context` will have to summarize older turns to send it.
+    fn context_estimate(&self) -> (usize, bool) {
+        let included: Vec<&ChatMessage> =
+            self.messages.iter().filter(|message| message.include_in_context).collect();
+        let shell: usize = self.shell_output_history.iter().take(5).map(String::len).sum();
+        let chars = self.agent_memory.context_block().len()
+            + shell
+            + included.iter().map(|message| message.content.len()).sum::<usize>()
+            + self.input.len();
+        (chars / 4, chars > MAX_CONTEXT_CHARS && included.len() > 4)
+    }
+
+    fn request_models(&mut self) {
+        self.model_picker_loading = true;
+        self.model_picker_error = None;
+        self.picker_models.clear();
+        let provider = self.provider.clone();
         let tx = self.events_tx.clone();
         tokio::spawn(async move {
-            let event = match list_ollama_models().await {
-                Ok(models) => AppEvent::OllamaModelsReady { models },
-                Err(error) => AppEvent::OllamaModelsFailed {
+            let listed = if provider.is(&OPENROUTER) {
+                list_openrouter_models().await.map(|models| {
+                    let default = Capabilities::provider_default(&provider);
+                    models
+                        .iter()
+                        .map(|model| PickerModel {
+                            name: model.id.clone(),
+                            meta: format_openrouter_model_meta(model),
+                            price: model.prompt_price.zip(model.completion_price).map(
+                                |(input, output)| ModelPrice { input, output },
+                            ),
+                            capabilities: Some(Capabilities {
+                                vision: model.vision,
+                                tools: model.tools,
+                                context_window: model.context_length.map_or(
+                                    default.context_window,
+                                    |tokens| tokens.min(u32::MAX as u64) as u32,
+                                ),
+                                ..default
+                            }),
+                        })
+                        .collect()
+                })
+            } else {
+                match list_ollama_models().await {
+                    Ok(models) => {
+                        let default = Capabilities::provider_default(&provider);
+                        let mut picker = Vec::new();
+                        for model in &models {
+                            // older servers report nothing; keep the defaults then
+                            let reported = ollama_model_capabilities(&model.name)
+                                .await
+                                .ok()
+                                .filter(|reported| !reported.is_empty());
+                            picker.push(PickerModel {
+                                name: model.name.clone(),
+                                meta: format_ollama_model_meta(model),
+                                price: None,
+                                capabilities: reported.map(|reported| Capabilities {
+                                    vision: reported.iter().any(|c| c == "vision"),
+                                    tools: reported.iter().any(|c| c == "tools"),
+                                    ..default
+                                }),
+                            });
+                        }
+                        Ok(picker)
+                    }
+                    Err(error) => Err(error),
+                }
+            };
+            let event = match listed {
+                Ok(models) => AppEvent::ModelsReady { provider, models },
+                Err(error) => AppEvent::ModelsFailed {
+                    provider,
                     error: error.to_string(),
                 },
             };
@@ -533,155 +1632,166 @@ impl App {
         });
     }
 
-    fn prepare_ollama_provider(&mut self, route: &str) {
-        self.show_ollama_picker = true;
-        self.ollama_selection_input.clear();
-        self.ollama_picker_scroll = 0;
-        self.request_ollama_models();
+    fn prepare_model_picker(&mut self, route: &str) {
+        self.show_model_picker = true;
+        self.model_selection_input.clear();
+        self.model_picker_scroll = 0;
+        self.request_models();
         self.rebuild_ai_client();
-        if let Some(model) = self.ollama_selected_model.clone() {
+        let label = self.provider.db_key().to_string();
+        if let Some(model) = self.selected_model().cloned() {
             self.add_system_message(format!(
                 "{} -> {} // current model: {} // type a number to switch",
                 route,
                 self.provider.name(),
                 model
             ));
-            self.status_note = format!("ollama ready: {}", truncate(&model, 28));
+            self.status_note = format!("{} ready: {}", label, truncate(&model, 28));
         } else {
             self.add_system_message(format!(
                 "{} -> {} // type a model number and press Enter",
                 route,
                 self.provider.name()
             ));
-            self.status_note = "ollama: select model".to_string();
+            self.status_note = format!("{}: select model", label);
         }
     }
 
     fn set_provider(&mut self, provider: AIProvider, route: &str) {
         self.session_id = self.session_id.wrapping_add(1);
         self.pending_ai = false;
+        self.ask_return = None;
         self.provider = provider;
-        if self.provider == AIProvider::Ollama {
-            self.prepare_ollama_provider(route);
+        if self.provider.has_model_picker() {
+            self.prepare_model_picker(route);
         } else {
-            self.show_ollama_picker = false;
-            self.ollama_selection_input.clear();
-            self.ollama_picker_error = None;
+            self.show_model_picker = false;
+            self.model_selection_input.clear();
+            self.model_picker_error = None;
             self.rebuild_ai_client();
             self.add_system_message(format!("{} -> {}", route, self.provider.name()));
             self.status_note = format!("active provider: {}", self.provider_status_badge());
         }
     }
 
-    fn select_ollama_model_by_number(&mut self, selection: usize) {
-        if selection == 0 || selection > self.ollama_models.len() {
-            self.status_note = format!("ollama model {} is out of range", selection);
+    fn select_model(&mut self, model: String) {
+        self.set_selected_model(Some(model.clone()));
+        self.show_model_picker = false;
+        self.model_selection_input.clear();
+        self.rebuild_ai_client();
+        let label = self.provider.db_key().to_string();
+        self.add_system_message(format!("{} model selected -> {}", label, model));
+        self.status_note = format!("{} model: {}", label, truncate(&model, 28));
+    }
+
+    fn select_model_by_number(&mut self, selection: usize) {
+        let label = self.provider.db_key().to_string();
+        if selection == 0 || selection > self.picker_models.len() {
+            self.status_note = format!("{} model {} is out of range", label, selection);
             self.add_system_message(format!(
-                "ollama model {} is out of range. choose 1-{}.",
+                "{} model {} is out of range. choose 1-{}.",
+                label,
                 selection,
-                self.ollama_models.len()
+                self.picker_models.len()
             ));
             return;
         }
 
-        let model = self.ollama_models[selection - 1].name.clone();
-        self.ollama_selected_model = Some(model.clone());
-        self.show_ollama_picker = false;
-        self.ollama_selection_input.clear();
-        self.rebuild_ai_client();
-        self.add_system_message(format!("ollama model selected -> {}", model));
-        self.status_note = format!("ollama model: {}", truncate(&model, 28));
+        let model = self.picker_models[selection - 1].name.clone();
+        self.select_model(model);
     }
 
-    fn confirm_ollama_selection(&mut self) {
-        if self.ollama_picker_loading {
-            self.status_note = "ollama models are still loading".to_string();
+    fn confirm_model_selection(&mut self) {
+        let label = self.provider.db_key().to_string();
+        if self.model_picker_loading {
+            self.status_note = format!("{} models are still loading", label);
             return;
         }
 
-        if self.ollama_models.is_empty() {
+        if self.picker_models.is_empty() {
             let error = self
-                .ollama_picker_error
+                .model_picker_error
                 .clone()
-                .unwrap_or_else(|| "no ollama models available".to_string());
-            self.add_system_message(format!("ollama unavailable: {}", error));
-            self.status_note = "ollama unavailable".to_string();
+                .unwrap_or_else(|| format!("no {} models available", label));
+            self.add_system_message(format!("{} unavailable: {}", label, error));
+            self.status_note = format!("{} unavailable", label);
             return;
         }
 
-        if self.ollama_selection_input.is_empty() {
-            if let Some(model) = &self.ollama_selected_model {
-                self.show_ollama_picker = false;
-                self.status_note = format!("ollama model: {}", truncate(model, 28));
+        if self.model_selection_input.is_empty() {
+            if let Some(model) = self.selected_model() {
+                self.status_note = format!("{} model: {}", label, truncate(model, 28));
+                self.show_model_picker = false;
             } else {
-                self.status_note = "type an ollama model number first".to_string();
+                self.status_note = format!("type an {} model number first", label);
             }
             return;
         }
 
-        match self.ollama_selection_input.parse::<usize>() {
-            Ok(selection) => self.select_ollama_model_by_number(selection),
+        match self.model_selection_input.parse::<usize>() {
+            Ok(selection) => self.select_model_by_number(selection),
             Err(_) => {
-                self.add_system_message("ollama selection must be a number");
-                self.status_note = "invalid ollama model number".to_string();
+                self.add_system_message(format!("{} selection must be a number", label));
+                self.status_note = format!("invalid {} model number", label);
             }
         }
     }
 
-    fn handle_ollama_picker_key(&mut self, key: KeyEvent) -> bool {
-        if !self.show_ollama_picker {
+    fn handle_model_picker_key(&mut self, key: KeyEvent) -> bool {
+        if !self.show_model_picker {
             return false;
         }
 
         match key.code {
             KeyCode::Esc => {
-                self.show_ollama_picker = false;
-                self.ollama_selection_input.clear();
-                self.status_note = if let Some(model) = &self.ollama_selected_model {
-                    format!("ollama model: {}", truncate(model, 28))
+                self.show_model_picker = false;
+                self.model_selection_input.clear();
+                let label = self.provider.db_key().to_string();
+                self.status_note = if let Some(model) = self.selected_model() {
+                    format!("{} model: {}", label, truncate(model, 28))
                 } else {
-                    "ollama picker closed".to_string()
+                    format!("{} picker closed", label)
                 };
             }
-            KeyCode::Enter => self.confirm_ollama_selection(),
+            KeyCode::Enter => self.confirm_model_selection(),
             KeyCode::Backspace => {
-                self.ollama_selection_input.pop();
+                self.model_selection_input.pop();
             }
             KeyCode::Up => {
-                self.ollama_picker_scroll = self.ollama_picker_scroll.saturating_sub(1);
+                self.model_picker_scroll = self.model_picker_scroll.saturating_sub(1);
             }
             KeyCode::Down => {
-                self.ollama_picker_scroll = self
-                    .ollama_picker_scroll
+                self.model_picker_scroll = self
+                    .model_picker_scroll
                     .saturating_add(1)
-                    .min(self.ollama_models.len().saturating_sub(1));
+                    .min(self.picker_models.len().saturating_sub(1));
             }
             KeyCode::PageUp => {
-                self.ollama_picker_scroll = self.ollama_picker_scroll.saturating_sub(8);
+                self.model_picker_scroll = self.model_picker_scroll.saturating_sub(8);
             }
             KeyCode::PageDown => {
-                self.ollama_picker_scroll = self
-                    .ollama_picker_scroll
+                self.model_picker_scroll = self
+                    .model_picker_scroll
                     .saturating_add(8)
-                    .min(self.ollama_models.len().saturating_sub(1));
+                    .min(self.picker_models.len().saturating_sub(1));
             }
             KeyCode::Char('j') if !key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.ollama_picker_scroll = self
-                    .ollama_picker_scroll
+                self.model_picker_scroll = self
+                    .model_picker_scroll
                     .saturating_add(1)
-                    .min(self.ollama_models.len().saturating_sub(1));
+                    .min(self.picker_models.len().saturating_sub(1));
             }
             KeyCode::Char('k') if !key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.ollama_picker_scroll = self.ollama_picker_scroll.saturating_sub(1);
+                self.model_picker_scroll = self.model_picker_scroll.saturating_sub(1);
             }
             KeyCode::Char('r') if !key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.request_ollama_models();
-                self.status_note = "refreshing ollama models".to_string();
+                self.request_models();
+                self.status_note = format!("refreshing {} models", self.provider.db_key());
             }
             KeyCode::Char(c)
                 if c.is_ascii_digit() && !key.modifiers.contains(KeyModifiers::CONTROL) =>
             {
-                self.ollama_selection_input.push(c);
+                self.model_selection_input.push(c);
             }
             _ => {}
         }
@@ -690,37 +1800,89 @@ impl App {
     }
 
     fn tick(&mut self) {
-        if matches!(self.mode, AppMode::Intro) && self.intro_started.elapsed() >= INTRO_DURATION {
-            self.mode = AppMode::Chat;
-            self.status_note = "intro faded into live deck".to_string();
+        let intro = self.intro_started.elapsed();
+        if matches!(self.mode, AppMode::Intro) && intro >= INTRO_DURATION {
+            if self.startup.done() {
+                self.mode = AppMode::Chat;
+                self.status_note = "intro faded into live deck".to_string();
+            } else if intro >= INTRO_DURATION + STARTUP_WAIT_LIMIT {
+                self.mode = AppMode::Chat;
+                self.status_note = format!("deck live // still loading {}", self.startup.pending());
+            }
         }
 
-        if let Some(video) = &mut self.video {
-            if self.video_enabled || matches!(self.mode, AppMode::Intro) {
-                video.tick();
-            }
+        // the video and the arcade hold still while focus is elsewhere
+        let playing = self.video_enabled || matches!(self.mode, AppMode::Intro);
+        if let Some(video) = self.video.as_mut().filter(|_| playing && self.terminal_focused) {
+            video.tick();
+        }
+        if let Some(background) = self.chat_background.as_mut().filter(|_| self.terminal_focused) {
+            background.tick();
         }
 
         self.sysmon.refresh();
 
-        // poll webcam -- drain all buffered frames to keep latency low
-        if let Some(ref cam) = self.webcam {
-            while let Some(frame) = cam.try_recv() {
-                self.webcam_frame = Some(frame);
+        if let Some(screensaver) = &mut self.screensaver {
+            screensaver.tick();
+        } else if self.mode == AppMode::Chat && !self.pending_ai {
+            let idle = self.screensaver_config.idle_after();
+            if idle.is_some_and(|idle| self.last_activity.elapsed() >= idle) {
+                self.start_screensaver();
             }
-            if self.webcam_frame.is_none() {
-                if let Some(err) = cam.error() {
-                    self.status_note = format!("webcam: {}", truncate(&err, 40));
+        }
+
+        if self.pending_delete.as_ref().is_some_and(|pending| Instant::now() >= pending.deadline) {
+            self.commit_pending_delete();
+        }
+        self.save_key_uses();
+        self.save_metrics();
+
+        let max_take = self.voice_config.max_duration();
+        if self.voice.as_mut().is_some_and(|take| take.exited() || take.elapsed() >= max_take) {
+            self.stop_voice();
+        }
+
+        if !self.pending_ai {
+            if let Some((provider, client)) = self.ask_return.take() {
+                self.provider = provider;
+                self.ai_client = client;
+            }
+        }
+
+        let now = Instant::now();
+        let resend = self.outbox.as_ref().is_some_and(|outbox| now >= outbox.next_attempt);
+        if resend && !self.pending_ai && self.reveal_queue.is_empty() {
+            self.status_note = "resending queued prompts".to_string();
+            self.stream_response(self.ai_client.clone());
+        }
+
+        // poll webcam -- drain all buffered frames to keep latency low
+        if let Some(ref cam) = self.webcam {
+            while let Some(frame) = cam.try_recv() {
+                self.webcam_frame = Some(frame);
+            }
+            if self.webcam_frame.is_none() {
+                if let Some(err) = cam.error() {
+                    self.status_note = format!("webcam: {}", truncate(&err, 40));
                 }
             }
         }
 
         while let Ok(event) = self.events_rx.try_recv() {
             match event {
-                AppEvent::AiFinished { session_id, result } => {
+                AppEvent::AiFinished {
+                    session_id,
+                    result,
+                    http_status,
+                    offline,
+                } => {
                     if session_id != self.session_id {
                         continue;
                     }
+                    self.retry_notice = None;
+                    if http_status.is_some() {
+                        self.last_http_status = http_status;
+                    }
 
                     self.pending_ai = false;
                     self.tool_loop_depth = 0;
@@ -729,16 +1891,30 @@ impl App {
                     self.stream_message_index = None;
                     match result {
                         Ok(text) => {
-                            let message = ChatMessage::assistant(&self.provider);
+                            self.outbox_delivered();
+                            let message = self.reply_message();
                             let index = self.messages.len();
                             self.messages.push(message);
-                            self.persist(&self.provider, "assistant", "chat", &text);
+                            self.persist_reply(Some(index), "chat", &text);
+                            self.notify_reply(&text);
                             self.reveal_queue.push_back(RevealJob::new(index, text, 9));
-                            self.follow_tail = true;
                             self.status_note =
                                 format!("{} response injected", self.provider_status_badge());
                         }
+                        Err(error) if offline => {
+                            let provider = self.provider.name();
+                            tracing::warn!(provider, "offline, prompt queued: {}", error);
+                            self.queue_offline(&error);
+                        }
                         Err(error) => {
+                            tracing::error!(
+                                provider = self.provider.name(),
+                                status = ?http_status,
+                                "provider request failed: {}",
+                                error
+                            );
+                            self.outbox_delivered();
+                            self.persist_reply(None, "error", &error);
                             self.add_system_message(format!("provider fault: {}", error));
                             self.status_note = "provider fault".to_string();
                         }
@@ -755,10 +1931,10 @@ impl App {
                     }
 
                     if !text.is_empty() {
-                        let message = ChatMessage::assistant(&self.provider);
+                        let message = self.reply_message();
                         let index = self.messages.len();
                         self.messages.push(message);
-                        self.persist(&self.provider, "assistant", "chat", &text);
+                        self.persist_reply(Some(index), "chat", &text);
                         self.reveal_queue.push_back(RevealJob::new(index, text, 12));
                     }
 
@@ -830,16 +2006,35 @@ impl App {
                     let client = self.ai_client.clone();
                     let tx = self.events_tx.clone();
                     let tool_defs = tools::all_tool_definitions();
+                    let cancel = self
+                        .ai_cancel
+                        .get_or_insert_with(CancellationToken::new)
+                        .clone();
+                    self.request_started = Instant::now();
 
                     tokio::spawn(async move {
-                        let result = client
-                            .send_with_tool_results(context.clone(), &tool_calls, &tool_results, Some(&tool_defs))
-                            .await;
-                        match result {
+                        let result = tokio::select! {
+                            _ = cancel.cancelled() => return,
+                            result = client.send_with_tool_results(
+                                context.clone(),
+                                &tool_calls,
+                                &tool_results,
+                                Some(&tool_defs),
+                            ) => result,
+                        };
+                        if let Ok((_, usage)) = &result {
+                            let _ = tx.send(AppEvent::AiUsage {
+                                session_id,
+                                usage: *usage,
+                            });
+                        }
+                        match result.map(|(response, _)| response) {
                             Ok(AIResponse::Text(text)) => {
                                 let _ = tx.send(AppEvent::AiFinished {
                                     session_id,
                                     result: Ok(text),
+                                    http_status: None,
+                                    offline: false,
                                 });
                             }
                             Ok(AIResponse::ToolCalls(new_calls, text)) => {
@@ -854,16 +2049,89 @@ impl App {
                                 let _ = tx.send(AppEvent::AiFinished {
                                     session_id,
                                     result: Err(e.to_string()),
+                                    http_status: e.downcast_ref::<ApiError>().map(|e| e.status),
+                                    offline: false,
                                 });
                             }
                         }
                     });
                 }
+                AppEvent::AiUsage { session_id, usage } => {
+                    if session_id == self.session_id {
+                        self.record_usage(usage);
+                    }
+                }
+                AppEvent::RagEmbedded {
+                    session_id,
+                    query,
+                    result,
+                } => {
+                    if session_id == self.session_id {
+                        self.handle_rag_embedding(query, result);
+                    }
+                }
+                AppEvent::RecallEmbedded {
+                    query,
+                    model,
+                    result,
+                } => self.show_recalled(query, model, result),
+                AppEvent::Transcribed { result } => self.insert_transcript(result),
+                AppEvent::WebResults {
+                    session_id,
+                    query,
+                    result,
+                } => {
+                    if session_id == self.session_id {
+                        self.handle_web_results(query, result);
+                    }
+                }
+                AppEvent::CompareChunk { round, pane, chunk } => {
+                    self.handle_compare_chunk(round, pane, chunk);
+                }
+                AppEvent::CompareFinished { round, pane, error } => {
+                    self.finish_compare_pane(round, pane, error);
+                }
                 AppEvent::StreamChunk { session_id, chunk } => {
                     if session_id != self.session_id {
                         continue;
                     }
+                    self.outbox_delivered();
+                    if !matches!(
+                        chunk,
+                        StreamChunk::Retrying { .. }
+                            | StreamChunk::KeyRotated { .. }
+                            | StreamChunk::Usage(_)
+                            | StreamChunk::Status(_)
+                            | StreamChunk::Finished(_)
+                    ) {
+                        self.retry_notice = None;
+                    }
                     match chunk {
+                        StreamChunk::Usage(usage) => self.record_usage(usage),
+                        StreamChunk::Status(status) => self.last_http_status = Some(status),
+                        StreamChunk::Finished(reason) => self.turn_finish = Some(reason),
+                        StreamChunk::KeyRotated { status, from, to } => {
+                            self.status_note =
+                                format!("HTTP {} on {} // switched to {}", status, from, to);
+                        }
+                        StreamChunk::Retrying {
+                            attempt,
+                            max_retries,
+                            delay,
+                            status,
+                        } => {
+                            self.retry_notice = Some((
+                                Instant::now() + delay,
+                                format!("{}/{} // HTTP {}", attempt, max_retries, status),
+                            ));
+                            self.status_note = format!(
+                                "{} busy (HTTP {}) // retry {}/{}",
+                                self.provider_status_badge(),
+                                status,
+                                attempt,
+                                max_retries
+                            );
+                        }
                         StreamChunk::Delta(text) => {
                             self.stream_buffer.push_str(&text);
                             if let Some(idx) = self.stream_message_index {
@@ -871,7 +2139,6 @@ impl App {
                                     msg.content = self.stream_buffer.clone();
                                 }
                             }
-                            self.follow_tail = true;
                         }
                         StreamChunk::ToolCallsReceived(tool_calls, _text) => {
                             self.streaming_active = false;
@@ -881,7 +2148,8 @@ impl App {
                                 }
                             }
                             if self.stream_message_index.is_some() && !self.stream_buffer.is_empty() {
-                                self.persist(&self.provider, "assistant", "chat", &self.stream_buffer);
+                                let text = self.stream_buffer.clone();
+                                self.persist_reply(self.stream_message_index, "chat", &text);
                             }
                             self.stream_buffer.clear();
                             self.stream_message_index = None;
@@ -924,7 +2192,8 @@ impl App {
                             }
                             if self.stream_message_index.is_some() {
                                 let final_text = self.stream_buffer.clone();
-                                self.persist(&self.provider, "assistant", "chat", &final_text);
+                                self.persist_reply(self.stream_message_index, "chat", &final_text);
+                                self.notify_reply(&final_text);
                             }
                             self.stream_buffer.clear();
                             self.stream_message_index = None;
@@ -933,6 +2202,7 @@ impl App {
                         }
                     }
                 }
+                AppEvent::Archived(done) => done(self),
                 AppEvent::PendingApproval {
                     session_id,
                     tool_calls,
@@ -953,8 +2223,13 @@ impl App {
                     let accent = if success { t().accent3 } else { t().danger };
                     let text = format_outcome(&outcome, 4200);
                     let index = self.messages.len();
-                    self.messages.push(ChatMessage::shell(accent));
-                    self.persist(&self.provider, "user", "shell", &text);
+                    let mut message = ChatMessage::shell(accent);
+                    if !success {
+                        message.label = "OPS FAILED".to_string();
+                    }
+                    let provider = self.provider.db_key().to_string();
+                    self.persist(message.key, &provider, "shell", &text);
+                    self.messages.push(message);
                     self.reveal_queue.push_back(RevealJob::new(index, text.clone(), 18));
 
                     self.shell_output_history.push_front(
@@ -989,13 +2264,14 @@ impl App {
                         "ops payload returned for `{}`",
                         truncate(&outcome.command, 26)
                     );
-                    self.follow_tail = true;
                 }
                 AppEvent::YoutubeReady { title, source } => {
                     self.pending_video_load = false;
-                    match VideoPlayer::new(source, (132, 46), false) {
+                    match VideoPlayer::new(source, VideoOptions::default()) {
                         Ok(player) => {
+                            self.record_video();
                             self.video = Some(player);
+                            self.apply_theme_drift();
                             self.video_enabled = true;
                             self.video_source_label = title.clone();
                             self.tiling.set_focused_panel(PanelKind::Video);
@@ -1014,33 +2290,76 @@ impl App {
                     self.add_system_message(format!("youtube error: {}", error));
                     self.status_note = "youtube load failed".to_string();
                 }
-                AppEvent::OllamaModelsReady { models } => {
-                    self.ollama_picker_loading = false;
-                    self.ollama_picker_error = None;
-                    self.ollama_models = models;
-                    if let Some(selected) = &self.ollama_selected_model {
-                        if !self.ollama_models.iter().any(|model| &model.name == selected) {
-                            self.ollama_selected_model = None;
-                            self.rebuild_ai_client();
+                AppEvent::Summarized {
+                    through,
+                    trim,
+                    result,
+                } => self.finish_summary(through, trim, result),
+                AppEvent::ArchiveReady {
+                    db,
+                    archiver,
+                    memory,
+                    history,
+                    notices,
+                } => {
+                    self.startup.archive = false;
+                    for notice in notices {
+                        self.add_system_message(notice);
+                    }
+                    self.db = db;
+                    self.archiver = archiver;
+                    self.agent_memory = memory;
+                    self.restore_history(history);
+                }
+                AppEvent::ModelsReady { provider, models } => {
+                    self.startup.models = false;
+                    // a late catalog for a provider we already left is stale
+                    if provider != self.provider {
+                        continue;
+                    }
+                    self.model_picker_loading = false;
+                    self.model_picker_error = None;
+                    for model in &models {
+                        if let Some(price) = model.price {
+                            self.prices.insert_default(model.name.clone(), price);
+                        }
+                        if let Some(capabilities) = model.capabilities {
+                            self.capabilities.insert(model.name.clone(), capabilities);
                         }
                     }
-                    if self.provider == AIProvider::Ollama {
-                        self.add_system_message(format!(
-                            "ollama models ready: {} detected on this machine. type a number and press Enter.",
-                            self.ollama_models.len()
-                        ));
-                        self.status_note =
-                            format!("ollama models ready: {}", self.ollama_models.len());
+                    self.picker_models = models;
+                    if let Some(selected) = self.selected_model() {
+                        if !self.picker_models.iter().any(|model| &model.name == selected) {
+                            self.set_selected_model(None);
+                        }
                     }
+                    self.rebuild_ai_client();
+                    let label = provider.db_key();
+                    let origin = if provider.is(&OLLAMA) {
+                        "detected on this machine"
+                    } else {
+                        "in the catalog"
+                    };
+                    self.add_system_message(format!(
+                        "{} models ready: {} {}. type a number and press Enter.",
+                        label,
+                        self.picker_models.len(),
+                        origin
+                    ));
+                    self.status_note =
+                        format!("{} models ready: {}", label, self.picker_models.len());
                 }
-                AppEvent::OllamaModelsFailed { error } => {
-                    self.ollama_picker_loading = false;
-                    self.ollama_models.clear();
-                    self.ollama_picker_error = Some(error.clone());
-                    if self.provider == AIProvider::Ollama {
-                        self.add_system_message(format!("ollama error: {}", error));
-                        self.status_note = "ollama unavailable".to_string();
+                AppEvent::ModelsFailed { provider, error } => {
+                    self.startup.models = false;
+                    if provider != self.provider {
+                        continue;
                     }
+                    self.model_picker_loading = false;
+                    self.picker_models.clear();
+                    self.model_picker_error = Some(error.clone());
+                    let label = provider.db_key();
+                    self.add_system_message(format!("{} error: {}", label, error));
+                    self.status_note = format!("{} unavailable", label);
                 }
             }
         }
@@ -1048,13 +2367,15 @@ impl App {
         let now = Instant::now();
         let elapsed = now.saturating_duration_since(self.last_tick);
         self.last_tick = now;
-        self.games.tick(elapsed.as_secs_f32());
+        if self.terminal_focused {
+            self.games.tick(elapsed.as_secs_f32());
+        }
         let tick_factor = ((elapsed.as_secs_f32() / 0.016).ceil() as usize).max(1);
 
         if let Some(job) = self.reveal_queue.front_mut() {
             job.revealed = (job.revealed + job.speed * tick_factor).min(job.full_text.len());
             if let Some(message) = self.messages.get_mut(job.message_index) {
-                message.content = job.full_text.iter().take(job.revealed).collect();
+                message.content = job.full_text[..job.revealed].concat();
             }
             if job.revealed >= job.full_text.len() {
                 self.reveal_queue.pop_front();
@@ -1066,6 +2387,11 @@ impl App {
         while event::poll(Duration::from_millis(10))? {
             match event::read()? {
                 Event::Key(key) => {
+                    self.last_activity = Instant::now();
+                    if self.screensaver.take().is_some() {
+                        self.status_note = "screensaver dismissed".to_string();
+                        continue;
+                    }
                     if key.modifiers.contains(KeyModifiers::CONTROL)
                         && key.code == KeyCode::Char('c')
                     {
@@ -1095,10 +2421,8 @@ impl App {
                         AppMode::Exit => return Ok(true),
                     }
                 }
-                Event::Resize(_, _) => {
-                    self.follow_tail = true;
-                    self.scroll_lines = 0;
-                }
+                Event::FocusGained => self.terminal_focused = true,
+                Event::FocusLost => self.terminal_focused = false,
                 _ => {}
             }
         }
@@ -1106,17 +2430,24 @@ impl App {
     }
 
     fn handle_chat_key(&mut self, key: KeyEvent) -> Result<bool> {
-        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('l') {
-            self.messages.clear();
-            self.reveal_queue.clear();
-            self.status_note = "transcript purged".to_string();
+        if self.log_viewer.open {
+            self.log_viewer.handle_key(key);
+            return Ok(false);
+        }
+        if self.help.open {
+            self.help.handle_key(key);
+            return Ok(false);
+        }
+        let deck = self.keymap.deck_action(key);
+        if let Some(action @ (DeckAction::Logs | DeckAction::ClearTranscript)) = deck {
+            self.run_deck_action(action);
             return Ok(false);
         }
 
         if !matches!(key.code, KeyCode::F(_))
             && !(key.modifiers.contains(KeyModifiers::CONTROL)
                 && matches!(key.code, KeyCode::Char('l') | KeyCode::Char('c')))
-            && self.handle_ollama_picker_key(key)
+            && self.handle_model_picker_key(key)
         {
             return Ok(false);
         }
@@ -1140,10 +2471,66 @@ impl App {
             return Ok(false);
         }
 
+        if self.detail_message.is_some() {
+            self.handle_detail_key(key);
+            return Ok(false);
+        }
+
+        if self.voice.is_some() && matches!(key.code, KeyCode::Enter | KeyCode::Esc) {
+            if key.code == KeyCode::Enter {
+                self.stop_voice();
+            } else {
+                self.voice = None;
+                self.status_note = "recording discarded".to_string();
+            }
+            return Ok(false);
+        }
+
+        if self.keymap.mode == EditMode::Search {
+            self.handle_search_key(key);
+            return Ok(false);
+        }
+
+        if let Some(action) = self.keymap.select_action(key) {
+            self.run_select_action(action);
+            return Ok(false);
+        }
+
+        if let Some(action) = self.keymap.normal_action(key) {
+            self.run_normal_action(action);
+            return Ok(false);
+        }
+
+        // Unbound printable keys do nothing outside insert mode.
+        if matches!(self.keymap.mode, EditMode::Normal | EditMode::Select)
+            && !key.modifiers.contains(KeyModifiers::CONTROL)
+            && matches!(key.code, KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Tab)
+        {
+            return Ok(false);
+        }
+
+        if self.completion.is_some() && self.handle_completion_key(key) {
+            return Ok(false);
+        }
+
+        if let Some(action) = deck {
+            self.run_deck_action(action);
+            return Ok(false);
+        }
+
         match key.code {
             KeyCode::Esc => {
                 if self.pending_approval.is_some() {
                     self.reject_pending();
+                } else if self.pending_ai {
+                    self.cancel_ai();
+                } else if self.compare.as_ref().is_some_and(CompareState::busy) {
+                    self.cancel_compare();
+                } else if self.outbox.is_some() {
+                    self.drop_outbox();
+                } else if self.keymap.vim && self.keymap.mode == EditMode::Insert {
+                    self.keymap.mode = EditMode::Normal;
+                    self.status_note = "normal mode // i to type, / to search".to_string();
                 } else if !self.input.is_empty() {
                     self.input.clear();
                     self.status_note = "input cleared".to_string();
@@ -1155,24 +2542,102 @@ impl App {
                     self.status_note = "press Esc again to exit (or Ctrl+C)".to_string();
                 }
             }
-            KeyCode::F(1) => {
-                self.show_help = !self.show_help;
+            KeyCode::Left | KeyCode::Right if self.compare.is_some() => {
+                if let Some(compare) = self.compare.as_mut() {
+                    compare.cycle_tab(if key.code == KeyCode::Left { -1 } else { 1 });
+                }
+            }
+            KeyCode::End => {
+                self.follow_tail = true;
+                self.status_note = "following new output".to_string();
+            }
+            KeyCode::PageUp => {
+                let page = self.transcript_view.get().height.saturating_sub(2).max(1);
+                self.scroll_transcript(-(page as isize));
+            }
+            KeyCode::PageDown => {
+                let page = self.transcript_view.get().height.saturating_sub(2).max(1);
+                self.scroll_transcript(page as isize);
+            }
+            KeyCode::Up => self.scroll_transcript(-1),
+            KeyCode::Down => self.scroll_transcript(1),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Enter => {
+                if self.pending_approval.is_some() && self.input.is_empty() {
+                    self.approve_pending();
+                } else {
+                    let input = self.input.trim().to_string();
+                    self.input.clear();
+                    if !input.is_empty() {
+                        self.dispatch_input(input);
+                    }
+                }
+            }
+            KeyCode::Tab => {
+                if self.input.starts_with('/') {
+                    self.open_completion();
+                } else {
+                    self.input.push_str("    ");
+                }
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.input.push(c);
+            }
+            _ => {}
+        }
+
+        Ok(false)
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                self.search_query.clear();
+                self.keymap.mode = EditMode::Normal;
+            }
+            KeyCode::Enter => {
+                self.keymap.mode = EditMode::Normal;
+                self.search_hit = None;
+                self.jump_to_match(true);
+            }
+            KeyCode::Backspace if self.search_query.is_empty() => {
+                self.keymap.mode = EditMode::Normal;
+            }
+            KeyCode::Backspace => {
+                self.search_query.pop();
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.search_query.push(c);
+            }
+            _ => {}
+        }
+    }
+
+    fn run_deck_action(&mut self, action: DeckAction) {
+        match action {
+            DeckAction::Help => {
+                self.help.toggle();
                 theme::set_random_theme();
+                self.apply_theme_drift();
                 self.status_note = "theme randomized // F1 help".to_string();
             }
-            KeyCode::F(9) => {
+            DeckAction::RandomTheme => {
                 theme::set_random_theme();
+                self.apply_theme_drift();
                 self.status_note = "theme randomized".to_string();
                 self.add_system_message("color palette randomized -- F9 again for another, /theme reset to restore defaults");
             }
-            KeyCode::F(10) => {
+            DeckAction::ResetTheme => {
                 theme::reset_theme();
+                self.apply_theme_drift();
                 self.status_note = "theme restored to default".to_string();
             }
-            KeyCode::F(2) => {
-                self.set_provider(self.provider.cycle(), "uplink rerouted");
+            DeckAction::CycleProvider => {
+                self.set_provider(self.providers.next(&self.provider), "uplink rerouted");
             }
-            KeyCode::F(3) => {
+            DeckAction::ToggleVideo => {
                 self.video_enabled = !self.video_enabled;
                 self.status_note = if self.video_enabled {
                     "video bus online".to_string()
@@ -1180,7 +2645,8 @@ impl App {
                     "video bus muted".to_string()
                 };
             }
-            KeyCode::F(4) => {
+            DeckAction::CycleEffects if self.motion_reduced() => {}
+            DeckAction::CycleEffects => {
                 self.effects.cycle_with_off();
                 self.status_note = if self.effects.active {
                     format!("3D fx: {}", self.effects.kind.name())
@@ -1188,7 +2654,7 @@ impl App {
                     "3D fx offline".to_string()
                 };
             }
-            KeyCode::F(5) => {
+            DeckAction::ToggleWebcam => {
                 if self.webcam.is_some() {
                     self.webcam = None;
                     self.webcam_frame = None;
@@ -1207,155 +2673,719 @@ impl App {
                     }
                 }
             }
-            KeyCode::F(6) => {
+            DeckAction::CycleLayout => {
                 let preset = self.tiling.preset.cycle();
                 self.tiling.apply_preset(preset);
                 self.status_note = format!("layout: {}", preset.name());
             }
-            KeyCode::F(7) => {
-                match self.tiles.activate_default() {
-                    Ok(()) => {
-                        self.tiling.set_focused_panel(PanelKind::Tiles);
-                        self.status_note = self.tiles.status_note().to_string();
-                    }
-                    Err(error) => {
-                        self.add_system_message(format!("tiles error: {}", error));
-                        self.status_note = "tiles failed to boot".to_string();
-                    }
+            DeckAction::Tiles => match self.tiles.activate_default() {
+                Ok(()) => {
+                    self.tiling.set_focused_panel(PanelKind::Tiles);
+                    self.status_note = self.tiles.status_note().to_string();
                 }
-            }
-            KeyCode::F(8) => {
+                Err(error) => {
+                    self.add_system_message(format!("tiles error: {}", error));
+                    self.status_note = "tiles failed to boot".to_string();
+                }
+            },
+            DeckAction::CyclePanel => {
                 self.tiling.cycle_focused_panel();
                 if let Some(p) = self.tiling.focused_panel() {
                     self.status_note = format!("tile -> {}", p.name());
                 }
             }
-            KeyCode::PageUp => {
-                self.follow_tail = false;
-                self.scroll_lines = self.scroll_lines.saturating_sub(8);
-            }
-            KeyCode::PageDown => {
-                self.scroll_lines += 8;
+            DeckAction::Logs => self.log_viewer.toggle(),
+            DeckAction::ClearTranscript => {
+                self.messages.clear();
+                self.reveal_queue.clear();
+                self.search_hit = None;
+                self.leave_select_mode();
+                self.status_note = "transcript purged".to_string();
             }
-            KeyCode::Up => {
-                self.follow_tail = false;
-                self.scroll_lines = self.scroll_lines.saturating_sub(1);
+            DeckAction::Retry => self.retry_last_response(None),
+            DeckAction::EditLast => self.edit_last_prompt(),
+            DeckAction::Select => self.enter_select_mode(),
+            DeckAction::ToggleMeta => self.toggle_meta(),
+            DeckAction::PasteImage => self.paste_clipboard_image(),
+            DeckAction::Focus(dx, dy) => {
+                self.tiling.focus_direction(self.body_area, dx, dy);
+                if let Some(p) = self.tiling.focused_panel() {
+                    self.status_note = format!("focus: {}", p.name());
+                }
             }
-            KeyCode::Down => {
-                self.scroll_lines += 1;
+            DeckAction::Swap(dx, dy) => {
+                self.tiling.swap_focused_with_direction(self.body_area, dx, dy);
             }
-            KeyCode::Backspace => {
-                self.input.pop();
+            DeckAction::Resize(step) => self.tiling.resize_focused(step as f32 / 100.0),
+        }
+    }
+
+    fn run_normal_action(&mut self, action: NormalAction) {
+        let half_page = (self.transcript_view.get().height / 2).max(1) as isize;
+        match action {
+            NormalAction::ScrollDown => self.scroll_transcript(1),
+            NormalAction::ScrollUp => self.scroll_transcript(-1),
+            NormalAction::HalfPageDown => self.scroll_transcript(half_page),
+            NormalAction::HalfPageUp => self.scroll_transcript(-half_page),
+            NormalAction::Top => {
+                self.follow_tail = false;
+                self.scroll_lines = 0;
             }
-            KeyCode::Enter => {
-                if self.pending_approval.is_some() && self.input.is_empty() {
-                    self.approve_pending();
-                } else {
-                    let input = self.input.trim().to_string();
-                    self.input.clear();
-                    if !input.is_empty() {
-                        self.dispatch_input(input);
-                    }
-                }
+            NormalAction::Bottom => self.follow_tail = true,
+            NormalAction::Follow => self.toggle_follow(),
+            NormalAction::StartSearch => {
+                self.search_query.clear();
+                self.keymap.mode = EditMode::Search;
             }
-            KeyCode::Tab => {
-                self.input.push_str("    ");
-            }
-            KeyCode::Char(c) => {
-                if key.modifiers.contains(KeyModifiers::CONTROL) {
-                    let area = self.body_area;
-                    match c {
-                        'h' => self.tiling.focus_direction(area, -1, 0),
-                        'l' => self.tiling.focus_direction(area, 1, 0),
-                        'k' => self.tiling.focus_direction(area, 0, -1),
-                        'j' => self.tiling.focus_direction(area, 0, 1),
-                        'H' => self.tiling.swap_focused_with_direction(area, -1, 0),
-                        'L' => self.tiling.swap_focused_with_direction(area, 1, 0),
-                        'K' => self.tiling.swap_focused_with_direction(area, 0, -1),
-                        'J' => self.tiling.swap_focused_with_direction(area, 0, 1),
-                        'n' => {
-                            self.tiling.cycle_focused_panel();
-                            if let Some(p) = self.tiling.focused_panel() {
-                                self.status_note = format!("tile -> {}", p.name());
-                            }
-                        }
-                        '[' => self.tiling.resize_focused(-0.05),
-                        ']' => self.tiling.resize_focused(0.05),
-                        _ => {}
-                    }
-                    if matches!(c, 'h' | 'l' | 'k' | 'j') {
-                        if let Some(p) = self.tiling.focused_panel() {
-                            self.status_note = format!("focus: {}", p.name());
-                        }
-                    }
-                } else if !key.modifiers.contains(KeyModifiers::CONTROL) {
-                    self.input.push(c);
+            NormalAction::NextMatch => self.jump_to_match(true),
+            NormalAction::PrevMatch => self.jump_to_match(false),
+            NormalAction::Yank => self.yank_message(),
+            NormalAction::Insert => self.keymap.mode = EditMode::Insert,
+            NormalAction::Command => {
+                self.keymap.mode = EditMode::Insert;
+                if self.input.is_empty() {
+                    self.input.push('/');
                 }
             }
-            _ => {}
+            NormalAction::Select => self.enter_select_mode(),
         }
-
-        Ok(false)
     }
 
-    fn should_route_key_to_tiles(&self, key: KeyEvent) -> bool {
-        if matches!(key.code, KeyCode::F(_)) {
-            return false;
+    fn enter_select_mode(&mut self) {
+        if self.messages.is_empty() {
+            self.status_note = "nothing to select".to_string();
+            return;
         }
+        let last = self.messages.len() - 1;
+        let index = self.search_hit.unwrap_or(last).min(last);
+        self.keymap.mode = EditMode::Select;
+        self.select_message(index);
+    }
 
-        if !key.modifiers.contains(KeyModifiers::CONTROL) {
-            return true;
+    fn leave_select_mode(&mut self) {
+        self.selected_message = None;
+        self.detail_message = None;
+        if self.keymap.mode == EditMode::Select {
+            self.keymap.mode = self.keymap.resting_mode();
         }
+    }
 
-        // Ctrl+j/k goes to tiles for inner terminal cycling
-        if matches!(key.code, KeyCode::Char('j') | KeyCode::Char('k')) {
-            return true;
-        }
+    fn select_message(&mut self, index: usize) {
+        self.selected_message = Some(index);
+        self.scroll_to_message(index);
+    }
 
-        // Block Ctrl+h/l (outer focus), Ctrl+Shift (swap), Ctrl+n, Ctrl+[/]
-        !matches!(
-            key.code,
-            KeyCode::Char('h')
-                | KeyCode::Char('l')
-                | KeyCode::Char('H')
-                | KeyCode::Char('J')
-                | KeyCode::Char('K')
-                | KeyCode::Char('L')
-                | KeyCode::Char('n')
-                | KeyCode::Char('[')
-                | KeyCode::Char(']')
-        )
+    fn run_select_action(&mut self, action: SelectAction) {
+        let Some(index) = self.selected_message.filter(|&i| i < self.messages.len()) else {
+            self.leave_select_mode();
+            return;
+        };
+        let last = self.messages.len() - 1;
+        match action {
+            SelectAction::Previous => self.select_message(index.saturating_sub(1)),
+            SelectAction::Next => self.select_message((index + 1).min(last)),
+            SelectAction::First => self.select_message(0),
+            SelectAction::Last => self.select_message(last),
+            SelectAction::Copy => self.copy_message(index),
+            SelectAction::Delete => self.delete_message(index),
+            SelectAction::Undo => self.undo_delete(),
+            SelectAction::Bookmark => self.toggle_bookmark(index),
+            SelectAction::Revisions => {
+                let message = &mut self.messages[index];
+                if message.revisions.is_empty() {
+                    self.status_note = "message was never edited".to_string();
+                } else {
+                    message.show_revisions = !message.show_revisions;
+                }
+            }
+            SelectAction::Quote => {
+                let quoted: Vec<String> = self.messages[index]
+                    .content
+                    .lines()
+                    .map(|line| format!("> {}", line))
+                    .collect();
+                self.input = format!("{}\n\n{}", quoted.join("\n"), self.input);
+                self.status_note = format!("quoting {}", self.messages[index].label);
+                self.leave_select_mode();
+                self.keymap.mode = EditMode::Insert;
+            }
+            SelectAction::Detail => {
+                self.detail_message = Some(index);
+                self.detail_scroll = 0;
+            }
+            SelectAction::Exit => {
+                self.leave_select_mode();
+                self.follow_tail = true;
+                self.status_note = "selection cleared".to_string();
+            }
+        }
     }
 
-    fn dispatch_input(&mut self, input: String) {
-        self.follow_tail = true;
+    /// Removes a message from the transcript and from the context sent to the model.
+    /// The archived copy is soft-deleted once the undo window closes.
+    fn delete_message(&mut self, index: usize) {
+        if self.pending_ai || self.stream_message_index.is_some() || !self.reveal_queue.is_empty() {
+            self.status_note = "wait for the current reply before deleting messages".to_string();
+            return;
+        }
+        self.commit_pending_delete();
+        let removed = self.messages.remove(index);
+        self.search_hit = None;
+        self.status_note = format!(
+            "deleted {} message // u to undo within {}s",
+            removed.label,
+            UNDO_WINDOW.as_secs()
+        );
+        self.pending_delete = Some(PendingDelete {
+            message: removed,
+            index,
+            deadline: Instant::now() + UNDO_WINDOW,
+        });
+        if self.messages.is_empty() {
+            self.leave_select_mode();
+        } else {
+            self.select_message(index.min(self.messages.len() - 1));
+        }
+    }
 
-        if input == "/help" {
-            self.show_help = !self.show_help;
+    /// Puts the last deleted message back while its undo window is open.
+    fn undo_delete(&mut self) {
+        let Some(pending) = self.pending_delete.take() else {
+            self.status_note = "nothing to undo".to_string();
             return;
+        };
+        let index = pending.index.min(self.messages.len());
+        self.status_note = format!("restored {} message", pending.message.label);
+        self.messages.insert(index, pending.message);
+        self.search_hit = None;
+        if self.keymap.mode == EditMode::Select {
+            self.select_message(index);
         }
+    }
 
-        if input == "/clear" {
-            self.messages.clear();
-            self.reveal_queue.clear();
-            self.status_note = "transcript purged".to_string();
+    /// Deletes the pending message from the database; the undo window is over.
+    fn commit_pending_delete(&mut self) {
+        let Some(pending) = self.pending_delete.take() else {
             return;
+        };
+        if let Some(id) = pending.message.db_id {
+            self.archive(move |db| db.delete_message(id), |_, ()| {});
         }
+    }
 
-        if input == "/video" {
-            self.video_enabled = !self.video_enabled;
-            self.status_note = if self.video_enabled {
-                "video bus online".to_string()
+    /// Tab on a slash command: completes a single match outright, otherwise
+    /// extends to the shared prefix and opens the popup.
+    fn open_completion(&mut self) {
+        let sources = Sources {
+            providers: self.providers.all().map(|p| p.db_key().to_string()).collect(),
+            models: if self.provider.is(&OPENROUTER) {
+                self.picker_models.iter().map(|model| model.name.clone()).collect()
             } else {
-                "video bus muted".to_string()
-            };
+                Vec::new()
+            },
+            themes: ThemeName::ALL.iter().map(|theme| theme.name().to_string()).collect(),
+        };
+        let Some(completion) = Completion::new(&self.input, &sources) else {
             return;
+        };
+        match completion.candidates.len() {
+            0 => self.status_note = "no completions".to_string(),
+            1 => {
+                let choice = completion.candidates[0].clone();
+                completion.apply(&mut self.input, &choice);
+            }
+            _ => {
+                let common = completion.common();
+                if common.len() > self.input.len() - completion.start {
+                    self.input.truncate(completion.start);
+                    self.input.push_str(&common);
+                }
+                self.completion = Some(completion);
+            }
         }
+    }
 
-        if let Some(url) = input.strip_prefix("/youtube ") {
-            let url = url.trim().to_string();
-            if url.is_empty() {
-                self.add_system_message("usage: /youtube https://youtube.com/watch?v=...");
+    /// Keys while the completion popup is open. Unhandled keys close it and
+    /// fall through to normal input handling.
+    fn handle_completion_key(&mut self, key: KeyEvent) -> bool {
+        let Some(completion) = self.completion.as_mut() else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Tab | KeyCode::Down => completion.step(1),
+            KeyCode::BackTab | KeyCode::Up => completion.step(-1),
+            KeyCode::Enter => {
+                let choice = completion.candidates[completion.selected].clone();
+                completion.apply(&mut self.input, &choice);
+                self.completion = None;
+                if choice.ends_with('/') {
+                    self.open_completion();
+                }
+            }
+            KeyCode::Esc => self.completion = None,
+            _ => {
+                self.completion = None;
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Bookmarks (or un-bookmarks) an archived message in the database.
+    fn toggle_bookmark(&mut self, index: usize) {
+        if self.archiver.is_none() {
+            self.status_note = "bookmarks need the conversation database".to_string();
+            return;
+        }
+        let Some(id) = self.messages[index].db_id else {
+            self.status_note = "only archived messages can be bookmarked".to_string();
+            return;
+        };
+        let key = self.messages[index].key;
+        self.archive(
+            move |db| db.toggle_bookmark(id),
+            move |app, bookmarked| {
+                let Some(message) = app.messages.iter_mut().find(|message| message.key == key)
+                else {
+                    return;
+                };
+                message.bookmarked = bookmarked;
+                app.status_note = if bookmarked {
+                    format!("bookmarked {} message", message.label)
+                } else {
+                    format!("removed bookmark from {} message", message.label)
+                };
+            },
+        );
+    }
+
+    /// `/bookmarks`: lists the newest bookmarked messages across providers.
+    fn list_bookmarks(&mut self) {
+        let Some(db) = &self.db else {
+            self.add_system_message("bookmarks need the conversation database");
+            return;
+        };
+        let bookmarks = match db.bookmarks(20) {
+            Ok(bookmarks) => bookmarks,
+            Err(error) => {
+                self.add_system_message(format!("bookmarks unavailable: {}", error));
+                return;
+            }
+        };
+        if bookmarks.is_empty() {
+            self.add_system_message("no bookmarks yet // Ctrl+S to select a message, then b");
+            return;
+        }
+        let mut lines = vec![format!("bookmarks ({} newest):", bookmarks.len())];
+        for bookmark in bookmarks {
+            let when = Local
+                .timestamp_opt(bookmark.timestamp, 0)
+                .single()
+                .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            lines.push(format!(
+                "  #{} {} {} {} // {}",
+                bookmark.message_id,
+                when,
+                bookmark.provider,
+                bookmark.role,
+                notify::preview(&bookmark.content, 90)
+            ));
+        }
+        self.add_system_message(lines.join("\n"));
+    }
+
+    /// Writes per-key request counters gathered by the AI clients.
+    /// Mentions the draft an earlier session left behind, if any.
+    fn offer_draft(&mut self) {
+        let Some(draft) = draft::load() else {
+            return;
+        };
+        self.add_system_message(format!(
+            "unsent draft from last session ({}): /draft restores it, /draft discard drops it",
+            draft.summary()
+        ));
+        self.status_note = "draft waiting // /draft to restore".to_string();
+        self.draft_offer = Some(draft);
+    }
+
+    /// Writes the input and attachments to `draft.json` when they changed,
+    /// at most every `DRAFT_INTERVAL` unless `now`.
+    fn autosave_draft(&mut self, now: bool) {
+        if self.draft_offer.is_some() || (!now && self.draft_checked.elapsed() < DRAFT_INTERVAL) {
+            return;
+        }
+        self.draft_checked = Instant::now();
+        let current = Draft::new(&self.input, &self.attachments);
+        if self.draft_saved.as_ref().is_some_and(|saved| saved.same_as(&current)) {
+            return;
+        }
+        if let Err(error) = draft::save(&current) {
+            tracing::warn!("draft not saved: {:#}", error);
+        }
+        self.draft_saved = Some(current);
+    }
+
+    fn handle_draft_command(&mut self, args: &str) {
+        let Some(offer) = self.draft_offer.take() else {
+            self.add_system_message("no draft from an earlier session");
+            return;
+        };
+        match args {
+            "" | "restore" => {
+                if !self.input.is_empty() {
+                    self.input.push('\n');
+                }
+                self.input.push_str(&offer.input);
+                self.attachments.extend(offer.attachments());
+                self.status_note = format!("draft restored: {}", offer.summary());
+            }
+            "discard" => {
+                if let Err(error) = draft::save(&Draft::new("", &[])) {
+                    tracing::warn!("draft not removed: {:#}", error);
+                }
+                self.add_system_message("saved draft discarded");
+            }
+            _ => {
+                self.draft_offer = Some(offer);
+                self.add_system_message("usage: /draft [restore|discard]");
+            }
+        }
+    }
+
+    fn save_key_uses(&mut self) {
+        let uses = keys::take_uses();
+        if !uses.is_empty() {
+            self.archive(move |db| db.record_key_uses(&uses), |_, ()| {});
+        }
+    }
+
+    /// Writes pending metrics once the archive is open; until then they wait.
+    fn save_metrics(&mut self) {
+        if self.archiver.is_none() {
+            return;
+        }
+        let metrics = metrics::take();
+        if !metrics.is_empty() {
+            self.archive(move |db| db.record_metrics(&metrics), |_, ()| {});
+        }
+    }
+
+    /// Counts the panel video as played, with the frame rate it reached.
+    fn record_video(&self) {
+        if let Some(video) = self.video.as_ref().filter(|video| video.has_signal()) {
+            metrics::record(Metric::Video {
+                source: self.video_source_label.clone(),
+                fps: video.average_fps(),
+            });
+        }
+    }
+
+    fn list_key_usage(&mut self) {
+        let Some(db) = &self.db else {
+            self.add_system_message("key usage needs the conversation database");
+            return;
+        };
+        let usage = match db.key_usage() {
+            Ok(usage) => usage,
+            Err(error) => {
+                self.add_system_message(format!("key usage unavailable: {}", error));
+                return;
+            }
+        };
+        if usage.is_empty() {
+            self.add_system_message("no keyed requests recorded yet");
+            return;
+        }
+        let mut lines = vec!["api keys (requests // 401s // 429s // last used):".to_string()];
+        for key in usage {
+            let when = Local
+                .timestamp_opt(key.last_used, 0)
+                .single()
+                .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            lines.push(format!(
+                "  {} {} // {} // {} // {} // {}",
+                key.provider, key.label, key.requests, key.unauthorized, key.rate_limited, when
+            ));
+        }
+        self.add_system_message(lines.join("\n"));
+    }
+
+    fn handle_detail_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('v') => {
+                self.detail_message = None;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.detail_scroll = self.detail_scroll.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.detail_scroll = self.detail_scroll.saturating_add(1);
+            }
+            KeyCode::PageUp => self.detail_scroll = self.detail_scroll.saturating_sub(10),
+            KeyCode::PageDown => self.detail_scroll = self.detail_scroll.saturating_add(10),
+            KeyCode::Home => self.detail_scroll = 0,
+            KeyCode::Char('c') | KeyCode::Char('y') => {
+                if let Some(index) = self.detail_message {
+                    self.copy_message(index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn toggle_meta(&mut self) {
+        self.show_meta = !self.show_meta;
+        self.status_note = if self.show_meta {
+            "message metadata shown".to_string()
+        } else {
+            "message metadata hidden".to_string()
+        };
+    }
+
+    /// `F`: pins the view where it is, or sticks it to the newest output again.
+    fn toggle_follow(&mut self) {
+        if self.follow_tail {
+            self.scroll_lines = self.transcript_view.get().max_scroll;
+            self.follow_tail = false;
+            self.status_note = "follow off // view pinned".to_string();
+        } else {
+            self.follow_tail = true;
+            self.status_note = "following new output".to_string();
+        }
+    }
+
+    fn scroll_transcript(&mut self, delta: isize) {
+        let max_scroll = self.transcript_view.get().max_scroll;
+        let current = if self.follow_tail {
+            max_scroll
+        } else {
+            self.scroll_lines.min(max_scroll)
+        };
+        self.scroll_lines = current.saturating_add_signed(delta).min(max_scroll);
+        self.follow_tail = self.scroll_lines >= max_scroll;
+    }
+
+    /// Moves the search hit to the next older (`older`) or newer message matching
+    /// `search_query`, wrapping around, and scrolls it to the top of the view.
+    fn jump_to_match(&mut self, older: bool) {
+        if self.search_query.is_empty() {
+            self.status_note = "no search pattern // press / to search".to_string();
+            return;
+        }
+        // archived messages go through the database's full-text search, on
+        // the archiver's thread
+        let since = self
+            .messages
+            .iter()
+            .filter(|message| message.db_id.is_some())
+            .map(|message| message.timestamp.timestamp())
+            .min();
+        if let Some(since) = since {
+            let query = self.search_query.clone();
+            let filter = MessageFilter {
+                since: Some(since - 1),
+                ..MessageFilter::default()
+            };
+            let searched = self.lookup(
+                move |db| Ok((db.search(&query, &filter, CHAT_SEARCH_LIMIT).ok(), query)),
+                move |app, (hits, query)| {
+                    // a newer search replaced this one while it ran
+                    if app.search_query != query {
+                        return;
+                    }
+                    let archived = hits.map(|hits| hits.iter().map(|hit| hit.message_id).collect());
+                    app.land_on_match(older, archived.as_ref());
+                },
+            );
+            if searched {
+                return;
+            }
+        }
+        self.land_on_match(older, None);
+    }
+
+    /// Finishes `jump_to_match` once the rows in `archived`, if any, are known
+    /// to match.
+    fn land_on_match(&mut self, older: bool, archived: Option<&HashSet<i64>>) {
+        let matches = self.search_matches(archived);
+
+        let hit = match (older, self.search_hit) {
+            (true, Some(current)) => matches.iter().rev().find(|&&i| i < current).or(matches.last()),
+            (true, None) => matches.last(),
+            (false, Some(current)) => matches.iter().find(|&&i| i > current).or(matches.first()),
+            (false, None) => matches.first(),
+        }
+        .copied();
+
+        let Some(index) = hit else {
+            self.search_hit = None;
+            self.status_note = format!("pattern not found: {}", self.search_query);
+            return;
+        };
+        self.search_hit = Some(index);
+        self.scroll_to_message(index);
+        let position = matches.iter().position(|&i| i == index).unwrap_or(0) + 1;
+        self.status_note = format!("/{} // match {}/{}", self.search_query, position, matches.len());
+    }
+
+    /// Transcript indexes matching `search_query`, in order. Archived messages
+    /// match when full-text search found them (`archived`); the rest (notes,
+    /// or every message without a database) are matched as a substring.
+    fn search_matches(&self, archived: Option<&HashSet<i64>>) -> Vec<usize> {
+        let needle = self.search_query.to_lowercase();
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| match (archived, message.db_id) {
+                (Some(archived), Some(id)) => archived.contains(&id),
+                _ => message.content.to_lowercase().contains(&needle),
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn scroll_to_message(&mut self, index: usize) {
+        let view = self.transcript_view.get();
+        let (lines, starts) = self.transcript_lines();
+        let offset = starts
+            .get(index)
+            .map(|&start| wrapped_height(&lines[..start], view.width))
+            .unwrap_or(0);
+        self.follow_tail = false;
+        self.scroll_lines = offset.min(view.max_scroll);
+    }
+
+    /// Copies the search hit, or the latest reply, to the clipboard.
+    fn yank_message(&mut self) {
+        let target = self
+            .search_hit
+            .filter(|&index| index < self.messages.len())
+            .or_else(|| {
+                self.messages
+                    .iter()
+                    .rposition(|message| matches!(message.kind, MessageKind::Assistant))
+            });
+        match target {
+            Some(index) => self.copy_message(index),
+            None => self.status_note = "nothing to yank".to_string(),
+        }
+    }
+
+    fn copy_message(&mut self, index: usize) {
+        let Some(message) = self.messages.get(index) else {
+            return;
+        };
+        self.status_note = match clipboard::copy(&message.content) {
+            Ok(()) => format!(
+                "copied {} chars from {}",
+                message.content.chars().count(),
+                message.label
+            ),
+            Err(error) => format!("copy failed: {}", error),
+        };
+    }
+
+    fn should_route_key_to_tiles(&self, key: KeyEvent) -> bool {
+        if matches!(key.code, KeyCode::F(_)) {
+            return false;
+        }
+
+        if !key.modifiers.contains(KeyModifiers::CONTROL) {
+            return true;
+        }
+
+        // Ctrl+j/k goes to tiles for inner terminal cycling
+        if matches!(key.code, KeyCode::Char('j') | KeyCode::Char('k')) {
+            return true;
+        }
+
+        // Block Ctrl+h/l (outer focus), Ctrl+Shift (swap), Ctrl+n, Ctrl+[/]
+        !matches!(
+            key.code,
+            KeyCode::Char('h')
+                | KeyCode::Char('l')
+                | KeyCode::Char('H')
+                | KeyCode::Char('J')
+                | KeyCode::Char('K')
+                | KeyCode::Char('L')
+                | KeyCode::Char('n')
+                | KeyCode::Char('[')
+                | KeyCode::Char(']')
+        )
+    }
+
+    fn dispatch_input(&mut self, input: String) {
+        self.follow_tail = true;
+
+        if input == "/help" {
+            self.help.toggle();
+            return;
+        }
+
+        if input == "/draft" || input.starts_with("/draft ") {
+            let args = input.trim_start_matches("/draft").trim().to_string();
+            self.handle_draft_command(&args);
+            return;
+        }
+
+        if input == "/clear" {
+            self.messages.clear();
+            self.reveal_queue.clear();
+            self.editing = None;
+            self.archive(
+                |db| {
+                    db.new_conversation();
+                    Ok(())
+                },
+                |_, ()| {},
+            );
+            self.status_note = "transcript purged".to_string();
+            return;
+        }
+
+        if input == "/archive" {
+            let queued = self.archive(
+                |db| db.archive_conversation(),
+                |app, archived| {
+                    if archived {
+                        app.messages.clear();
+                        app.reveal_queue.clear();
+                        app.editing = None;
+                        app.status_note =
+                            "session archived // hidden from history, search and analytics"
+                                .to_string();
+                    } else {
+                        app.add_system_message("nothing archived yet in this session");
+                    }
+                },
+            );
+            if !queued {
+                self.add_system_message("archive offline: database not available");
+            }
+            return;
+        }
+
+        if input == "/tag" || input.starts_with("/tag ") {
+            self.tag_conversation(&input["/tag".len()..]);
+            return;
+        }
+
+        if input == "/video" {
+            self.video_enabled = !self.video_enabled;
+            self.status_note = if self.video_enabled {
+                "video bus online".to_string()
+            } else {
+                "video bus muted".to_string()
+            };
+            return;
+        }
+
+        if let Some(url) = input.strip_prefix("/youtube ") {
+            let url = url.trim().to_string();
+            if url.is_empty() {
+                self.add_system_message("usage: /youtube https://youtube.com/watch?v=...");
                 return;
             }
             self.pending_video_load = true;
@@ -1395,6 +3425,10 @@ impl App {
             return;
         }
 
+        if (input == "/3d" || input == "/effects" || input == "/fx") && self.motion_reduced() {
+            return;
+        }
+
         if input == "/3d" || input == "/effects" {
             self.effects.active = !self.effects.active;
             self.status_note = if self.effects.active {
@@ -1417,6 +3451,7 @@ impl App {
 
         if input == "/randomize" || input == "/theme random" {
             theme::set_random_theme();
+            self.apply_theme_drift();
             self.add_system_message("color palette randomized -- /theme reset to restore defaults");
             self.status_note = "theme randomized".to_string();
             return;
@@ -1424,20 +3459,60 @@ impl App {
 
         if input == "/theme reset" || input == "/theme default" {
             theme::reset_theme();
+            self.apply_theme_drift();
             self.add_system_message("theme restored to factory defaults");
             self.status_note = "theme reset".to_string();
             return;
         }
 
+        if input == "/theme" {
+            let names: Vec<&str> = ThemeName::ALL.iter().map(|theme| theme.name()).collect();
+            self.add_system_message(format!(
+                "theme: {} // available: {} // /theme <name> to switch",
+                t().name.name(),
+                names.join(", ")
+            ));
+            return;
+        }
+
+        if let Some(name) = input.strip_prefix("/theme ") {
+            match ThemeName::parse(name) {
+                Some(theme) => {
+                    theme::set_theme(theme);
+                    self.apply_theme_drift();
+                    self.status_note = format!("theme: {}", theme.name());
+                }
+                None => self.add_system_message(format!(
+                    "unknown theme `{}` // try default, solarized, dracula, monochrome or random",
+                    name.trim()
+                )),
+            }
+            return;
+        }
+
         if input == "/analytics" {
             self.analytics.active = !self.analytics.active;
             if self.analytics.active {
-                self.analytics.refresh(self.db.as_ref());
+                self.refresh_analytics();
                 self.tiling.set_focused_panel(PanelKind::Analytics);
             }
             return;
         }
 
+        if input == "/analytics bookmarks" {
+            self.analytics.toggle_bookmark_filter();
+            self.analytics.active = true;
+            self.refresh_analytics();
+            self.tiling.set_focused_panel(PanelKind::Analytics);
+            let scope = if self.analytics.bookmarks_only {
+                "bookmarked messages"
+            } else {
+                "all messages"
+            };
+            self.add_system_message(format!("analytics: {}", scope));
+            return;
+        }
+
         if input == "/layout" {
             let preset = self.tiling.preset.cycle();
             self.tiling.apply_preset(preset);
@@ -1557,7 +3632,7 @@ impl App {
                 let addr_clone = addr.clone();
                 tokio::spawn(async move {
                     if let Err(e) = server.run(&addr_clone).await {
-                        eprintln!("server error: {}", e);
+                        tracing::error!("video chat server on {} stopped: {}", addr_clone, e);
                     }
                 });
                 self.status_note = format!("video chat server live on :{}", port);
@@ -1602,12 +3677,25 @@ impl App {
         }
 
         if let Some(provider_name) = input.strip_prefix("/provider ") {
-            self.set_provider(AIProvider::from_input(provider_name), "manual route");
+            self.set_provider(self.providers.resolve(provider_name), "manual route");
             return;
         }
 
         if input == "/ollama" {
-            self.set_provider(AIProvider::Ollama, "manual route");
+            self.set_provider(OLLAMA.into(), "manual route");
+            return;
+        }
+
+        if input == "/openrouter" {
+            self.set_provider(OPENROUTER.into(), "manual route");
+            return;
+        }
+
+        if let Some(model) = input.strip_prefix("/openrouter ") {
+            if !self.provider.is(&OPENROUTER) {
+                self.set_provider(OPENROUTER.into(), "manual route");
+            }
+            self.select_model(model.trim().to_string());
             return;
         }
 
@@ -1621,17 +3709,24 @@ impl App {
 
         if let Some(rest) = input.strip_prefix("/remember ") {
             if let Some((key, value)) = rest.split_once('=') {
-                let key = key.trim();
-                let value = value.trim();
-                if let Some(ref db) = self.db {
-                    match AgentMemory::remember(db, key, value, memory::MemoryKind::UserSet) {
-                        Ok(_) => {
-                            self.agent_memory.load(db);
-                            self.add_system_message(format!("remembered: {} = {}", key, value));
+                let key = key.trim().to_string();
+                let value = value.trim().to_string();
+                let queued = self.archive(
+                    move |db| {
+                        let kind = memory::MemoryKind::UserSet;
+                        Ok(match AgentMemory::remember(db, &key, &value, kind) {
+                            Ok(_) => format!("remembered: {} = {}", key, value),
+                            Err(e) => format!("memory error: {}", e),
+                        })
+                    },
+                    |app, message| {
+                        if let Some(db) = &app.db {
+                            app.agent_memory.load(db);
                         }
-                        Err(e) => self.add_system_message(format!("memory error: {}", e)),
-                    }
-                } else {
+                        app.add_system_message(message);
+                    },
+                );
+                if !queued {
                     self.add_system_message("memory offline: database not available");
                 }
             } else {
@@ -1641,30 +3736,46 @@ impl App {
         }
 
         if let Some(key) = input.strip_prefix("/forget ") {
-            let key = key.trim();
-            if let Some(ref db) = self.db {
-                match AgentMemory::forget(db, key) {
-                    Ok(true) => {
-                        self.agent_memory.load(db);
-                        self.add_system_message(format!("forgot: {}", key));
+            let key = key.trim().to_string();
+            self.archive(
+                move |db| {
+                    Ok(match AgentMemory::forget(db, &key) {
+                        Ok(true) => format!("forgot: {}", key),
+                        Ok(false) => format!("no memory found for: {}", key),
+                        Err(e) => format!("memory error: {}", e),
+                    })
+                },
+                |app, message| {
+                    if let Some(db) = &app.db {
+                        app.agent_memory.load(db);
                     }
-                    Ok(false) => self.add_system_message(format!("no memory found for: {}", key)),
-                    Err(e) => self.add_system_message(format!("memory error: {}", e)),
-                }
-            }
+                    app.add_system_message(message);
+                },
+            );
             return;
         }
 
-        if let Some(key) = input.strip_prefix("/recall ") {
-            let key = key.trim();
-            if let Some(ref db) = self.db {
-                match AgentMemory::recall(db, key) {
-                    Some(value) => self.add_system_message(format!("{} = {}", key, value)),
-                    None => self.add_system_message(format!("no memory for: {}", key)),
-                }
+        if let Some(query) = input.strip_prefix("/recall ") {
+            let query = query.trim().to_string();
+            if query == "use" {
+                self.use_recalled();
+                return;
             }
-            return;
-        }
+            if self.archiver.is_none() {
+                self.recall_history(query);
+                return;
+            }
+            // a memory key wins; anything else searches past conversations
+            let key = query.clone();
+            self.lookup(
+                move |db| Ok(AgentMemory::recall(db, &key)),
+                move |app, memory| match memory {
+                    Some(value) => app.add_system_message(format!("{} = {}", query, value)),
+                    None => app.recall_history(query),
+                },
+            );
+            return;
+        }
 
         if input == "/memory" {
             if let Some(ref db) = self.db {
@@ -1703,6 +3814,182 @@ impl App {
             return;
         }
 
+        if input == "/summarize" || input.starts_with("/summarize ") {
+            match input["/summarize".len()..].trim() {
+                "" => self.summarize(false),
+                "trim" => self.summarize(true),
+                "off" => match self.summary.as_mut().filter(|summary| summary.trim) {
+                    Some(summary) => {
+                        summary.trim = false;
+                        self.add_system_message("full transcript back in context");
+                    }
+                    None => self.add_system_message("context is not trimmed"),
+                },
+                _ => self.add_system_message(
+                    "usage: /summarize [trim] // trim sends the summary in place of the turns \
+                     it covers, /summarize off undoes that",
+                ),
+            }
+            return;
+        }
+
+        if input == "/compare" || input.starts_with("/compare ") {
+            let args = input.trim_start_matches("/compare").to_string();
+            self.set_compare(&args);
+            return;
+        }
+
+        if input == "/arena" || input.starts_with("/arena ") {
+            let args = input.trim_start_matches("/arena").to_string();
+            self.set_arena(&args);
+            return;
+        }
+
+        if input == "/ask" || input.starts_with("/ask ") {
+            let args = input.trim_start_matches("/ask").to_string();
+            self.ask_provider(&args);
+            return;
+        }
+
+        if input == "/voice" {
+            self.toggle_voice();
+            return;
+        }
+
+        if let Some(choice) = input.strip_prefix("/vote ") {
+            self.vote_arena(choice);
+            return;
+        }
+
+        if input == "/meta" {
+            self.toggle_meta();
+            return;
+        }
+
+        if input == "/undo" {
+            self.undo_delete();
+            return;
+        }
+
+        if input == "/screensaver" {
+            self.start_screensaver();
+            return;
+        }
+
+        if input == "/bookmarks" {
+            self.list_bookmarks();
+            return;
+        }
+
+        if input == "/keys" {
+            self.list_key_usage();
+            return;
+        }
+
+        if input == "/select" {
+            self.enter_select_mode();
+            return;
+        }
+
+        if input == "/rag" {
+            self.toggle_rag();
+            return;
+        }
+
+        if input == "/web" {
+            self.web_enabled = !self.web_enabled;
+            self.add_system_message(if self.web_enabled {
+                format!(
+                    "web search on: prompts are searched via {} first and replies cite [n] sources",
+                    self.web_config.backend.name()
+                )
+            } else {
+                "web search off".to_string()
+            });
+            return;
+        }
+
+        if input == "/vim" {
+            self.keymap.set_vim(!self.keymap.vim);
+            self.add_system_message(if self.keymap.vim {
+                "vim mode on: Esc for normal mode (j/k gg G / n N y), i to type"
+            } else {
+                "vim mode off"
+            });
+            return;
+        }
+
+        if input == "/attach" {
+            if self.attachments.is_empty() {
+                self.add_system_message("usage: /attach <path> (Tab completes paths) // /detach to clear");
+            } else {
+                let chips: Vec<String> = self.attachments.iter().map(Attachment::chip).collect();
+                self.add_system_message(format!("attached for next prompt: {}", chips.join(", ")));
+            }
+            return;
+        }
+
+        if input == "/export" || input.starts_with("/export ") {
+            let path = input["/export".len()..].trim();
+            self.export_transcript((!path.is_empty()).then_some(path));
+            return;
+        }
+
+        if let Some(path) = input.strip_prefix("/attach ") {
+            if attach::is_image(path.trim()) {
+                if self.can_take_images() {
+                    match Attachment::load_image(path.trim()) {
+                        Ok(attachment) => self.attach_image(attachment),
+                        Err(error) => self.add_system_message(format!("attach failed: {:#}", error)),
+                    }
+                }
+                return;
+            }
+            match Attachment::load(path.trim()) {
+                Ok(attachment) => {
+                    if attachment.truncated {
+                        self.add_system_message(format!(
+                            "attachment {} is {} bytes; only the first {} will be sent",
+                            attachment.name,
+                            attachment.bytes,
+                            attach::MAX_ATTACHMENT_BYTES
+                        ));
+                    }
+                    self.status_note = format!("attached {}", attachment.chip());
+                    self.attachments.push(attachment);
+                }
+                Err(error) => self.add_system_message(format!("attach failed: {:#}", error)),
+            }
+            return;
+        }
+
+        if input == "/detach" {
+            let count = self.attachments.len();
+            self.attachments.clear();
+            self.status_note = format!("{} attachments dropped", count);
+            return;
+        }
+
+        if input == "/edit" {
+            self.edit_last_prompt();
+            return;
+        }
+
+        if input == "/retry" || input.starts_with("/retry ") {
+            let arg = input.trim_start_matches("/retry").trim();
+            if arg.is_empty() {
+                self.retry_last_response(None);
+            } else {
+                match arg.parse::<f32>() {
+                    Ok(temperature) if (0.0..=2.0).contains(&temperature) => {
+                        self.retry_last_response(Some(temperature));
+                    }
+                    _ => self.add_system_message("usage: /retry [temperature 0.0-2.0]"),
+                }
+            }
+            return;
+        }
+
         if input == "/stream" || input == "/streaming" {
             self.add_system_message("streaming is enabled for all non-tool-use prompts. responses appear character-by-character.");
             return;
@@ -1713,7 +4000,11 @@ impl App {
             return;
         }
 
-        self.start_ai(input);
+        if self.compare.is_some() {
+            self.start_compare(input);
+        } else {
+            self.start_ai(input);
+        }
     }
 
     fn start_ai(&mut self, input: String) {
@@ -1722,8 +4013,8 @@ impl App {
             return;
         }
 
-        if self.provider == AIProvider::Ollama && self.ollama_selected_model.is_none() {
-            self.show_ollama_picker = true;
+        if self.provider.is(&OLLAMA) && self.ollama_selected_model.is_none() {
+            self.show_model_picker = true;
             self.status_note = "select an ollama model first".to_string();
             self.add_system_message(
                 "Ollama is active, but no model is selected yet. Type a model number in the picker and press Enter.",
@@ -1731,28 +4022,907 @@ impl App {
             return;
         }
 
-        let enriched_input = self.inject_file_references(&input);
+        let provider = self.provider.db_key().to_string();
+        let key = next_message_key();
+        let revisions = self.persist_prompt(key, &provider, &input, self.attachments.clone());
+        self.editing = None;
+        let query = input.trim().to_string();
+        self.push_user_prompt(input, key, revisions);
+        if self.outbox.is_some() {
+            if let Some(message) = self.messages.last_mut() {
+                message.pending = true;
+            }
+            self.status_note = format!("offline // {} prompts queued", self.queued_prompts());
+            return;
+        }
+        match self.rag.clone() {
+            Some(embedder) => self.start_rag_lookup(embedder, query),
+            None => self.continue_prompt(query),
+        }
+    }
 
-        let message = ChatMessage::user(enriched_input.clone());
-        self.persist(&self.provider, "user", "chat", &input);
-        self.messages.push(message);
+    /// Searches the web first when `/web` is on, otherwise streams the reply.
+    fn continue_prompt(&mut self, query: String) {
+        if self.web_enabled {
+            self.start_web_search(query);
+        } else {
+            self.stream_response(self.ai_client.clone());
+        }
+    }
+
+    /// `/rag` turns retrieval on when chunks exist for the configured model.
+    fn toggle_rag(&mut self) {
+        if self.rag.take().is_some() {
+            self.add_system_message("rag off");
+            return;
+        }
+        let embedder = match Embedder::new(&self.providers, &self.rag_config) {
+            Ok(embedder) => embedder,
+            Err(error) => {
+                self.add_system_message(format!("rag unavailable: {}", error));
+                return;
+            }
+        };
+        let Some(db) = &self.db else {
+            self.add_system_message("rag needs the conversation database (started with --no-db)");
+            return;
+        };
+        match db.rag_chunk_count(&embedder.model) {
+            Ok(0) => self.add_system_message(format!(
+                "no documents indexed for {}: run `asciivision index <dir>` first",
+                embedder.model
+            )),
+            Ok(count) => {
+                self.add_system_message(format!(
+                    "rag on: {} chunks embedded with {} // the closest {} are prepended to each prompt",
+                    count, embedder.model, self.rag_config.top_k
+                ));
+                self.rag = Some(embedder);
+            }
+            Err(error) => self.add_system_message(format!("rag unavailable: {}", error)),
+        }
+    }
+
+    /// Embeds the prompt for `/rag`; retrieval runs once `AppEvent::RagEmbedded` arrives.
+    fn start_rag_lookup(&mut self, embedder: Embedder, query: String) {
+        self.pending_ai = true;
+        self.follow_tail = true;
+        self.status_note = format!("embedding via {} ...", embedder.provider.name());
+
+        let session_id = self.session_id;
+        let tx = self.events_tx.clone();
+        tokio::spawn(async move {
+            let result = embedder
+                .embed(std::slice::from_ref(&query))
+                .await
+                .map(|mut vectors| vectors.pop().unwrap_or_default())
+                .map_err(|e| format!("{:#}", e));
+            let _ = tx.send(AppEvent::RagEmbedded {
+                session_id,
+                query,
+                result,
+            });
+        });
+    }
+
+    /// Prepends the closest indexed chunks to the pending prompt.
+    fn handle_rag_embedding(
+        &mut self,
+        query: String,
+        result: std::result::Result<Vec<f32>, String>,
+    ) {
+        let hits = match (&self.rag, &self.db, result) {
+            (Some(embedder), Some(db), Ok(embedding)) => {
+                rag::retrieve(db, &embedder.model, &embedding, self.rag_config.top_k)
+                    .map_err(|e| e.to_string())
+            }
+            (_, _, Err(error)) => Err(error),
+            _ => Ok(Vec::new()),
+        };
+        match hits {
+            Ok(hits) if !hits.is_empty() => {
+                let block = rag::context_block(&hits);
+                if let Some(message) = self
+                    .messages
+                    .iter_mut()
+                    .rev()
+                    .find(|message| message.kind == MessageKind::User)
+                {
+                    message.content.insert_str(0, &block);
+                }
+                let best = hits.first().map(|hit| hit.score).unwrap_or_default();
+                self.status_note = format!("rag: {} chunks (best {:.2})", hits.len(), best);
+            }
+            Ok(_) => self.status_note = "rag: no indexed chunks matched".to_string(),
+            Err(error) => self.add_system_message(format!(
+                "rag lookup failed: {} // answering without local documents",
+                error
+            )),
+        }
+        self.continue_prompt(query);
+    }
+
+    /// `/recall <query>`: embeds the query, then lists the archived exchanges
+    /// closest to it once `AppEvent::RecallEmbedded` arrives.
+    fn recall_history(&mut self, query: String) {
+        if self.db.is_none() {
+            self.add_system_message("recall needs the conversation database (started with --no-db)");
+            return;
+        }
+        let embedder = match Embedder::new(&self.providers, &self.rag_config) {
+            Ok(embedder) => embedder,
+            Err(error) => {
+                self.add_system_message(format!("recall unavailable: {}", error));
+                return;
+            }
+        };
+        self.status_note = format!("recalling via {} ...", embedder.provider.name());
+        let tx = self.events_tx.clone();
+        tokio::spawn(async move {
+            let result = embedder
+                .embed(std::slice::from_ref(&query))
+                .await
+                .map(|mut vectors| vectors.pop().unwrap_or_default())
+                .map_err(|e| format!("{:#}", e));
+            let _ = tx.send(AppEvent::RecallEmbedded {
+                query,
+                model: embedder.model,
+                result,
+            });
+        });
+    }
+
+    fn show_recalled(
+        &mut self,
+        query: String,
+        model: String,
+        result: std::result::Result<Vec<f32>, String>,
+    ) {
+        let embedding = match result {
+            Ok(embedding) => embedding,
+            Err(error) => return self.list_recalled(query, &model, Err(error)),
+        };
+        if self.archiver.is_none() {
+            return self.list_recalled(query, &model, Ok(Vec::new()));
+        }
+        let top_k = self.rag_config.top_k;
+        let read_model = model.clone();
+        self.lookup(
+            move |db| {
+                Ok(rag::recall(db, &read_model, &embedding, top_k).map_err(|e| e.to_string()))
+            },
+            move |app, found| app.list_recalled(query, &model, found),
+        );
+    }
+
+    fn list_recalled(
+        &mut self,
+        query: String,
+        model: &str,
+        found: std::result::Result<Vec<rag::Recalled>, String>,
+    ) {
+        let found = match found {
+            Ok(found) if found.is_empty() => {
+                self.add_system_message(format!(
+                    "nothing recalled for \"{}\" // `asciivision index-history` embeds past messages with {}",
+                    query, model
+                ));
+                self.status_note = "recall: no matches".to_string();
+                return;
+            }
+            Ok(found) => found,
+            Err(error) => {
+                self.add_system_message(format!("recall failed: {}", error));
+                return;
+            }
+        };
+        let mut lines = vec![format!(
+            "recall \"{}\": {} past exchanges // /recall use adds them to the context",
+            query,
+            found.len()
+        )];
+        for (number, exchange) in found.iter().enumerate() {
+            let Some(first) = exchange.messages.first() else {
+                continue;
+            };
+            let when = Local.timestamp_opt(first.timestamp, 0).single().unwrap_or_else(Local::now);
+            lines.push(format!(
+                "[{}] {} {} ({:.2})",
+                number + 1,
+                when.format("%Y-%m-%d %H:%M"),
+                first.provider,
+                exchange.score
+            ));
+            for message in &exchange.messages {
+                let speaker = if message.role == "user" { "you" } else { "ai" };
+                lines.push(format!("  {}: {}", speaker, notify::preview(&message.content, 90)));
+            }
+        }
+        self.add_system_message(lines.join("\n"));
+        self.status_note = format!("recall: {} exchanges", found.len());
+        self.recalled = Some((query, found));
+    }
+
+    /// `/recall use`: puts the last recalled exchanges into the context.
+    fn use_recalled(&mut self) {
+        let Some((query, found)) = self.recalled.take() else {
+            self.add_system_message("nothing recalled yet: /recall <query> first");
+            return;
+        };
+        self.messages.push(ChatMessage {
+            label: "RECALL".to_string(),
+            include_in_context: true,
+            ..ChatMessage::system(rag::recall_block(&query, &found))
+        });
+        self.status_note = format!("recall: {} exchanges in context", found.len());
+    }
+
+    /// `/voice`: starts recording, or stops and transcribes a take in progress.
+    fn toggle_voice(&mut self) {
+        if self.voice.is_some() {
+            self.stop_voice();
+            return;
+        }
+        if self.transcribing {
+            self.add_system_message("still transcribing the last recording");
+            return;
+        }
+        match Recording::start(&self.voice_config) {
+            Ok(take) => {
+                self.voice = Some(take);
+                self.status_note = format!(
+                    "recording up to {}s // Enter stops, Esc discards",
+                    self.voice_config.max_duration().as_secs()
+                );
+            }
+            Err(error) => self.add_system_message(format!("voice unavailable: {:#}", error)),
+        }
+    }
+
+    /// Ends the take and sends it for transcription; the text lands in the
+    /// input once `AppEvent::Transcribed` arrives.
+    fn stop_voice(&mut self) {
+        let Some(take) = self.voice.take() else {
+            return;
+        };
+        let seconds = take.elapsed().as_secs_f32();
+        let wav = match take.stop() {
+            Ok(wav) => wav,
+            Err(error) => {
+                self.add_system_message(format!("voice: {:#}", error));
+                return;
+            }
+        };
+        self.transcribing = true;
+        self.status_note = format!(
+            "transcribing {:.1}s via {} ...",
+            seconds,
+            self.voice_config.backend.name()
+        );
+        let tx = self.events_tx.clone();
+        let config = self.voice_config.clone();
+        tokio::spawn(async move {
+            let result = config.transcribe(wav).await.map_err(|e| format!("{:#}", e));
+            let _ = tx.send(AppEvent::Transcribed { result });
+        });
+    }
+
+    fn insert_transcript(&mut self, result: std::result::Result<String, String>) {
+        self.transcribing = false;
+        match result {
+            Ok(text) => {
+                if !self.input.is_empty() && !self.input.ends_with(char::is_whitespace) {
+                    self.input.push(' ');
+                }
+                self.input.push_str(&text);
+                self.status_note = format!(
+                    "transcribed {} words // Enter sends",
+                    text.split_whitespace().count()
+                );
+            }
+            Err(error) => self.add_system_message(format!("transcription failed: {}", error)),
+        }
+    }
+
+    /// Searches the web for the queued prompt; the reply streams once
+    /// `AppEvent::WebResults` arrives.
+    fn start_web_search(&mut self, query: String) {
+        self.pending_ai = true;
+        self.follow_tail = true;
+        self.status_note = format!("searching {} ...", self.web_config.backend.name());
+
+        let session_id = self.session_id;
+        let tx = self.events_tx.clone();
+        let config = self.web_config.clone();
+        tokio::spawn(async move {
+            let result = config.search(&query).await.map_err(|e| format!("{:#}", e));
+            let _ = tx.send(AppEvent::WebResults {
+                session_id,
+                query,
+                result,
+            });
+        });
+    }
+
+    /// Adds search results to the pending prompt's context and streams the reply.
+    fn handle_web_results(
+        &mut self,
+        query: String,
+        result: std::result::Result<Vec<SearchResult>, String>,
+    ) {
+        match result {
+            Ok(results) if !results.is_empty() => {
+                let block = websearch::context_block(&query, &results);
+                if let Some(message) = self
+                    .messages
+                    .iter_mut()
+                    .rev()
+                    .find(|message| message.kind == MessageKind::User)
+                {
+                    message.content.push_str(&block);
+                }
+                self.pending_sources = results;
+            }
+            Ok(_) => self.add_system_message("web search found nothing; answering without sources"),
+            Err(error) => self.add_system_message(format!(
+                "web search failed: {} // answering without sources",
+                error
+            )),
+        }
+        self.stream_response(self.ai_client.clone());
+    }
+
+    /// `/summarize`: asks the current provider to sum up the conversation so
+    /// far, in the background like a reply.
+    fn summarize(&mut self, trim: bool) {
+        if self.pending_ai || self.summarizing {
+            self.add_system_message("wait for the current reply before summarizing");
+            return;
+        }
+        let Some(through) = self
+            .messages
+            .iter()
+            .rev()
+            .find(|message| message.include_in_context)
+            .map(|message| message.key)
+        else {
+            self.add_system_message("nothing to summarize yet");
+            return;
+        };
+        let mut context = self.build_context();
+        context.push(ApiMessage {
+            role: "user".to_string(),
+            content: SUMMARY_PROMPT.to_string(),
+            images: Vec::new(),
+        });
+        self.summarizing = true;
+        self.status_note = "summarizing conversation".to_string();
+        let client = self.ai_client.clone();
+        let tx = self.events_tx.clone();
+        tokio::spawn(async move {
+            let result = client.send_message(context).await.map_err(|error| format!("{:#}", error));
+            let _ = tx.send(AppEvent::Summarized {
+                through,
+                trim,
+                result,
+            });
+        });
+    }
+
+    fn finish_summary(&mut self, through: u64, trim: bool, result: Result<String, String>) {
+        self.summarizing = false;
+        let text = match result {
+            Ok(text) if !text.trim().is_empty() => text.trim().to_string(),
+            Ok(_) => {
+                self.add_system_message("summary failed: the reply was empty");
+                return;
+            }
+            Err(error) => {
+                self.add_system_message(format!("summary failed: {}", error));
+                return;
+            }
+        };
+        self.add_system_message(format!("conversation summary:\n{}", text));
+        let summary = text.clone();
+        let archived = self.archive(
+            move |db| db.set_conversation_summary(&summary),
+            |app, stored| {
+                if !stored {
+                    app.status_note = "summary not archived: nothing saved this session".into();
+                }
+            },
+        );
+        let covered = self.messages.iter().position(|message| message.key == through);
+        self.status_note = match (trim, covered) {
+            (true, Some(index)) => format!(
+                "context trimmed: {} earlier messages replaced by the summary",
+                self.messages[..=index].iter().filter(|m| m.include_in_context).count()
+            ),
+            _ if archived => "summary saved to the session".to_string(),
+            _ => "summarized".to_string(),
+        };
+        self.summary = Some(ContextSummary {
+            text,
+            through,
+            trim,
+        });
+    }
+
+    /// Whether the current model reads images; says why not when it doesn't.
+    fn can_take_images(&mut self) -> bool {
+        if self.ai_client.capabilities().vision {
+            return true;
+        }
+        let model = self.ai_client.model_label();
+        let model = if model.is_empty() { self.provider.name() } else { model };
+        self.add_system_message(format!(
+            "{} is text-only and can't read images // switch to a vision model or attach a text \
+             file",
+            model
+        ));
+        false
+    }
+
+    /// Ctrl+v: queues the clipboard image for the next prompt, previewed in
+    /// ASCII above the input.
+    fn paste_clipboard_image(&mut self) {
+        if !self.can_take_images() {
+            return;
+        }
+        match clipboard::paste_image().and_then(Attachment::pasted_image) {
+            Ok(attachment) => self.attach_image(attachment),
+            Err(error) => self.status_note = format!("paste failed: {:#}", error),
+        }
+    }
+
+    fn attach_image(&mut self, attachment: Attachment) {
+        self.status_note = format!("attached {}", attachment.chip());
+        self.attachments.push(attachment);
+    }
+
+    /// Adds a typed prompt to the transcript with `@file` references and
+    /// attachments expanded.
+    fn push_user_prompt(&mut self, input: String, key: u64, revisions: Vec<String>) {
+        let mut enriched_input = self.inject_file_references(&input);
+        let mut images = Vec::new();
+        for attachment in self.attachments.drain(..) {
+            enriched_input.push_str(&attachment.to_prompt_block());
+            images.extend(attachment.to_image());
+        }
+        self.messages.push(ChatMessage {
+            prompt: Some(input),
+            key,
+            revisions,
+            images,
+            ..ChatMessage::user(enriched_input)
+        });
+    }
+
+    /// `/ask <provider> <question>` sends one prompt to another provider in
+    /// this thread; the deck goes back to the active one after the reply.
+    fn ask_provider(&mut self, args: &str) {
+        let Some((name, question)) = args.trim().split_once(char::is_whitespace) else {
+            self.add_system_message("usage: /ask <provider> <question>");
+            return;
+        };
+        if self.pending_ai || !self.reveal_queue.is_empty() {
+            self.add_system_message("output pipeline busy: wait for the current reply before /ask");
+            return;
+        }
+        if self.compare.is_some() {
+            self.add_system_message("leave compare mode first: /compare off");
+            return;
+        }
+        if self.outbox.is_some() {
+            self.add_system_message("offline: /ask needs a live link // queued prompts go to the active provider");
+            return;
+        }
+        let provider = self.providers.resolve(name);
+        if provider != self.provider {
+            let client = self.client_for(&provider);
+            if client.model_label().is_empty() {
+                self.add_system_message(format!(
+                    "/ask {} needs a model: pick one with /provider {} first",
+                    provider.db_key(),
+                    provider.db_key()
+                ));
+                return;
+            }
+            let provider = std::mem::replace(&mut self.provider, provider);
+            let client = std::mem::replace(&mut self.ai_client, client);
+            self.ask_return = Some((provider, client));
+        }
+        self.start_ai(question.trim().to_string());
+    }
+
+    /// `/compare <a> <b>` turns side-by-side mode on; `/compare off` turns it off.
+    fn set_compare(&mut self, args: &str) {
+        let names: Vec<&str> = args.split_whitespace().collect();
+        if names == ["diff"] {
+            self.toggle_compare_diff();
+            return;
+        }
+        if names.is_empty() || names == ["off"] {
+            if self.compare.is_some() {
+                self.cancel_compare();
+                self.compare = None;
+                self.add_system_message("compare mode off");
+            } else {
+                self.add_system_message("usage: /compare <provider> <provider> // /compare off");
+            }
+            return;
+        }
+        let [left, right] = names.as_slice() else {
+            self.add_system_message("usage: /compare <provider> <provider> // /compare off");
+            return;
+        };
+        if self.compare.as_ref().is_some_and(CompareState::busy) {
+            self.add_system_message("compare busy: wait for both replies or press Esc");
+            return;
+        }
+
+        let mut panes = Vec::with_capacity(2);
+        for name in [left, right] {
+            let provider = self.providers.resolve(name);
+            if provider.is(&OLLAMA) && self.ollama_selected_model.is_none() {
+                self.add_system_message(
+                    "compare needs an Ollama model: pick one with /ollama first, then retry /compare",
+                );
+                return;
+            }
+            let client = self.client_for(&provider);
+            panes.push(ComparePane::new(provider, client));
+        }
+        self.add_system_message(format!(
+            "compare mode: prompts go to {} and {} side by side // the left reply stays in context // /compare off to leave",
+            panes[0].provider.name(),
+            panes[1].provider.name()
+        ));
+        self.compare = Some(CompareState::new(panes, PaneLayout::Columns));
+    }
+
+    /// `/compare diff` or `/arena diff`: lines the replies up by sentence and
+    /// marks where they disagree.
+    fn toggle_compare_diff(&mut self) {
+        let Some(compare) = self.compare.as_mut() else {
+            self.add_system_message("diff needs compare mode: /compare <provider> <provider> or /arena");
+            return;
+        };
+        compare.diff = !compare.diff;
+        self.status_note = if compare.diff {
+            format!("{} diff on // marked sentences appear in one reply only", compare.label())
+        } else {
+            format!("{} diff off", compare.label())
+        };
+    }
+
+    /// `/arena` sends every prompt to all configured providers; `/arena off` leaves.
+    fn set_arena(&mut self, args: &str) {
+        if args.trim() == "diff" {
+            self.toggle_compare_diff();
+            return;
+        }
+        if args.trim() == "off" || (args.trim().is_empty() && self.compare.is_some()) {
+            self.cancel_compare();
+            self.compare = None;
+            self.add_system_message("arena closed");
+            return;
+        }
+        if self.compare.is_some() {
+            self.add_system_message("leave compare mode first: /compare off");
+            return;
+        }
+
+        let ollama_ready = self.ollama_selected_model.is_some();
+        let providers: Vec<AIProvider> = self
+            .providers
+            .all()
+            .filter(|provider| provider.is_configured())
+            .filter(|provider| !provider.is(&OLLAMA) || ollama_ready)
+            .cloned()
+            .collect();
+        if providers.len() < 2 {
+            self.add_system_message(
+                "arena needs at least two configured providers (API keys in .env, or an Ollama model picked)",
+            );
+            return;
+        }
+        let names: Vec<&str> = providers.iter().map(|provider| provider.badge()).collect();
+        self.add_system_message(format!(
+            "arena: prompts go to {} // Left/Right switch tabs, /vote <n> picks the best answer, /arena off to leave",
+            names.join(", ")
+        ));
+        let panes = providers
+            .into_iter()
+            .map(|provider| {
+                let client = self.client_for(&provider);
+                ComparePane::new(provider, client)
+            })
+            .collect();
+        self.compare = Some(CompareState::new(panes, PaneLayout::Tabs));
+    }
+
+    /// Records `/vote <n>` for the last finished arena round.
+    fn vote_arena(&mut self, choice: &str) {
+        let Some(compare) = self.compare.as_mut().filter(|c| c.layout == PaneLayout::Tabs) else {
+            self.add_system_message("no arena running: start one with /arena");
+            return;
+        };
+        if compare.prompt.is_empty() || compare.busy() {
+            self.add_system_message("nothing to vote on yet: wait for every answer to arrive");
+            return;
+        }
+        if compare.voted {
+            self.add_system_message("already voted on this round");
+            return;
+        }
+        let pane_count = compare.panes.len();
+        let Some(winner) = choice
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .filter(|&index| compare.panes.get(index).is_some_and(|pane| pane.error.is_none()))
+        else {
+            self.add_system_message(format!(
+                "usage: /vote <1-{}> (answers that failed can't win)",
+                pane_count
+            ));
+            return;
+        };
+
+        compare.voted = true;
+        compare.active = winner;
+        let pane = &compare.panes[winner];
+        let candidates: Vec<&str> = compare
+            .panes
+            .iter()
+            .filter(|pane| pane.error.is_none())
+            .map(|pane| pane.provider.db_key())
+            .collect();
+        let prompt = compare.prompt.clone();
+        let winner = pane.provider.db_key().to_string();
+        let model = pane.client.model_label().to_string();
+        let candidates: Vec<String> = candidates.into_iter().map(String::from).collect();
+        let note = format!("vote recorded: {} wins this round", pane.provider.name());
+        self.archive(
+            move |db| {
+                let candidates: Vec<&str> = candidates.iter().map(String::as_str).collect();
+                db.save_arena_vote(&prompt, &winner, &model, &candidates)
+            },
+            |_, ()| {},
+        );
+        self.add_system_message(note);
+    }
+
+    /// Sends one prompt to both compare panes at once.
+    fn start_compare(&mut self, input: String) {
+        let busy = self.compare.as_ref().is_some_and(CompareState::busy);
+        if busy || self.pending_ai || !self.reveal_queue.is_empty() {
+            self.add_system_message("output pipeline busy: wait for both replies before sending a new prompt");
+            return;
+        }
+        let Some(providers) = self.compare.as_ref().map(|compare| {
+            let panes = compare.panes.iter();
+            panes.map(|pane| pane.provider.clone()).collect::<Vec<_>>()
+        }) else {
+            return;
+        };
+        let key = next_message_key();
+        let mut revisions = Vec::new();
+        let mut attachments = self.attachments.clone();
+        for provider in &providers {
+            let files = std::mem::take(&mut attachments);
+            revisions.extend(self.persist_prompt(key, provider.db_key(), &input, files));
+        }
+        self.editing = None;
+        self.push_user_prompt(input.clone(), key, revisions);
+        let context = self.build_context();
+
+        let Some(compare) = self.compare.as_mut() else {
+            return;
+        };
+        let (round, cancel) = compare.begin(&input);
+        for (index, pane) in compare.panes.iter().enumerate() {
+            let client = pane.client.clone();
+            let context = context.clone();
+            let cancel = cancel.clone();
+            let tx = self.events_tx.clone();
+            tokio::spawn(async move {
+                let (chunk_tx, mut chunk_rx) = tokio::sync::mpsc::unbounded_channel();
+                let stream_task =
+                    tokio::spawn(async move { client.send_streaming(context, chunk_tx).await });
+                let abort = stream_task.abort_handle();
+
+                let forward = async {
+                    while let Some(chunk) = chunk_rx.recv().await {
+                        let _ = tx.send(AppEvent::CompareChunk {
+                            round,
+                            pane: index,
+                            chunk,
+                        });
+                    }
+                    let error = match stream_task.await {
+                        Ok(Ok(())) => None,
+                        Ok(Err(e)) => {
+                            let status = e.downcast_ref::<ApiError>().map(|e| e.status);
+                            Some((e.to_string(), status))
+                        }
+                        Err(e) => Some((e.to_string(), None)),
+                    };
+                    let _ = tx.send(AppEvent::CompareFinished {
+                        round,
+                        pane: index,
+                        error,
+                    });
+                };
+
+                tokio::select! {
+                    _ = cancel.cancelled() => abort.abort(),
+                    _ = forward => {}
+                }
+            });
+        }
+        self.follow_tail = true;
+        let badges: Vec<&str> = providers.iter().map(|provider| provider.badge()).collect();
+        self.status_note = format!("comparing -> {}", badges.join(" // "));
+    }
+
+    fn handle_compare_chunk(&mut self, round: u64, index: usize, chunk: StreamChunk) {
+        let Some(compare) = self.compare.as_mut().filter(|compare| compare.round == round) else {
+            return;
+        };
+        let started = compare.started;
+        let pane = &mut compare.panes[index];
+        match chunk {
+            StreamChunk::Delta(text) => {
+                pane.first_token.get_or_insert_with(|| started.elapsed());
+                pane.note = None;
+                pane.reply.push_str(&text);
+            }
+            StreamChunk::Usage(usage) => {
+                pane.usage += usage;
+                self.session_usage += usage;
+                if let Some(price) = self.prices.lookup(pane.client.model_label()) {
+                    self.session_cost += price.cost(usage);
+                }
+            }
+            StreamChunk::Status(status) => pane.http_status = Some(status),
+            StreamChunk::Finished(reason) => pane.finish_reason = Some(reason),
+            StreamChunk::Retrying {
+                attempt,
+                max_retries,
+                delay,
+                status,
+            } => {
+                pane.note = Some(format!(
+                    "HTTP {} // retry {}/{} in {:.0}s",
+                    status,
+                    attempt,
+                    max_retries,
+                    delay.as_secs_f32().ceil()
+                ));
+            }
+            StreamChunk::KeyRotated { status, to, .. } => {
+                pane.note = Some(format!("HTTP {} // switched to {}", status, to));
+            }
+            StreamChunk::ToolCallsReceived(..) | StreamChunk::Done => {}
+        }
+    }
+
+    fn finish_compare_pane(
+        &mut self,
+        round: u64,
+        index: usize,
+        error: Option<(String, Option<u16>)>,
+    ) {
+        let Some(compare) = self.compare.as_mut().filter(|compare| compare.round == round) else {
+            return;
+        };
+        let latency = compare.started.elapsed();
+        let pane = &mut compare.panes[index];
+        pane.busy = false;
+        pane.latency = Some(latency);
+        if let Some((message, status)) = error {
+            pane.error = Some(message);
+            pane.http_status = status.or(pane.http_status);
+        }
+
+        let meta = pane_meta(&self.prices, pane);
+        let (kind, content) = match &pane.error {
+            Some(error) => ("error", error.as_str()),
+            None => ("chat", pane.reply.as_str()),
+        };
+        let key = next_message_key();
+        pane.message_key = key;
+        let (provider, kind, content) =
+            (pane.provider.db_key().to_string(), kind.to_string(), content.to_string());
+        let busy = compare.busy();
+        self.archive(
+            move |db| db.save_message(&provider, "assistant", &kind, &content, Some(&meta)),
+            move |app, id| {
+                let compare = app.compare.as_mut().filter(|compare| compare.round == round);
+                if let Some(pane) = compare.and_then(|compare| compare.panes.get_mut(index)) {
+                    pane.message_id = Some(id);
+                }
+                app.assign_db_id(key, id);
+            },
+        );
+        if !busy {
+            self.record_compare_round();
+        }
+    }
+
+    /// Copies both replies into the transcript. Only the first successful one is
+    /// kept in the model context so user and assistant turns keep alternating.
+    fn record_compare_round(&mut self) {
+        let Some(compare) = &self.compare else {
+            return;
+        };
+        let keep = compare.panes.iter().position(|pane| pane.error.is_none());
+        let mut summary = Vec::new();
+        let mut replies = Vec::new();
+        for (index, pane) in compare.panes.iter().enumerate() {
+            let latency = pane.latency.unwrap_or_default().as_secs_f32();
+            summary.push(format!("{} {:.2}s", pane.provider.badge(), latency));
+            let content = match &pane.error {
+                Some(error) if pane.reply.is_empty() => format!("[{}]", error),
+                Some(error) => format!("{}\n[{}]", pane.reply, error),
+                None => pane.reply.clone(),
+            };
+            replies.push(ChatMessage {
+                label: format!("{} // {:.2}s", pane.provider.name(), latency),
+                content,
+                include_in_context: keep == Some(index),
+                meta: Some(pane_meta(&self.prices, pane)),
+                db_id: pane.message_id,
+                key: pane.message_key,
+                ..ChatMessage::assistant(&pane.provider, pane.client.model_label())
+            });
+        }
+        self.messages.extend(replies);
+        self.status_note = format!("{} done: {}", compare.label(), summary.join(" vs "));
+        if compare.layout == PaneLayout::Tabs {
+            self.add_system_message(format!(
+                "arena round done // /vote <1-{}> for the best answer",
+                compare.panes.len()
+            ));
+        }
+    }
+
+    fn cancel_compare(&mut self) {
+        if self.compare.as_mut().is_some_and(CompareState::cancel) {
+            self.record_compare_round();
+            self.status_note = "compare cancelled".to_string();
+        }
+    }
+
+    /// Streams a reply to the current context into a fresh assistant message.
+    fn stream_response(&mut self, client: AIClient) {
+        self.request_started = Instant::now();
+        self.last_http_status = None;
+        self.turn_finish = None;
         self.pending_ai = true;
         self.streaming_active = true;
         self.tool_loop_depth = 0;
         self.status_note = format!("streaming -> {}", self.provider_status_badge());
 
         // Create the assistant message shell for streaming into
-        let assistant_msg = ChatMessage::assistant(&self.provider);
+        let assistant_msg = ChatMessage {
+            sources: std::mem::take(&mut self.pending_sources),
+            ..self.reply_message()
+        };
         let msg_index = self.messages.len();
         self.messages.push(assistant_msg);
         self.stream_message_index = Some(msg_index);
         self.stream_buffer.clear();
 
         let session_id = self.session_id;
-        let client = self.ai_client.clone();
         let tx = self.events_tx.clone();
         let context = self.build_context();
         let tool_defs = tools::all_tool_definitions();
+        let cancel = CancellationToken::new();
+        self.ai_cancel = Some(cancel.clone());
 
         tokio::spawn(async move {
             let (chunk_tx, mut chunk_rx) = tokio::sync::mpsc::unbounded_channel();
@@ -1762,21 +4932,197 @@ impl App {
                     .send_streaming_with_tools(context, Some(&tool_defs), chunk_tx)
                     .await
             });
+            let abort = stream_task.abort_handle();
+
+            let forward = async {
+                while let Some(chunk) = chunk_rx.recv().await {
+                    let _ = tx.send(AppEvent::StreamChunk {
+                        session_id,
+                        chunk,
+                    });
+                }
+
+                if let Ok(Err(e)) = stream_task.await {
+                    let _ = tx.send(AppEvent::AiFinished {
+                        session_id,
+                        result: Err(e.to_string()),
+                        http_status: e.downcast_ref::<ApiError>().map(|e| e.status),
+                        offline: is_network_error(&e),
+                    });
+                }
+            };
+
+            tokio::select! {
+                _ = cancel.cancelled() => abort.abort(),
+                _ = forward => {}
+            }
+        });
+    }
+
+    /// Aborts the in-flight model request; late events from it are dropped by
+    /// the session bump.
+    fn cancel_ai(&mut self) {
+        if let Some(cancel) = self.ai_cancel.take() {
+            cancel.cancel();
+        }
+        self.session_id = self.session_id.wrapping_add(1);
+        self.pending_ai = false;
+        self.streaming_active = false;
+        self.tool_loop_depth = 0;
+        self.retry_notice = None;
+        self.pending_sources.clear();
+
+        if let Some(index) = self.stream_message_index.take() {
+            if self.stream_buffer.is_empty() {
+                if index + 1 == self.messages.len() {
+                    self.messages.pop();
+                }
+            } else {
+                let partial = std::mem::take(&mut self.stream_buffer);
+                self.persist_reply(Some(index), "chat", &partial);
+            }
+        }
+        self.stream_buffer.clear();
+
+        self.add_system_message(format!(
+            "request cancelled: {} link aborted by user",
+            self.provider.db_key()
+        ));
+        self.status_note = "request cancelled".to_string();
+    }
+
+    /// Marks the unanswered prompts as pending and schedules a resend after a
+    /// network failure.
+    fn queue_offline(&mut self, error: &str) {
+        let empty_shell = self.messages.last().is_some_and(|message| {
+            message.kind == MessageKind::Assistant && message.content.is_empty()
+        });
+        if empty_shell {
+            self.messages.pop();
+        }
+        let start = self
+            .messages
+            .iter()
+            .rposition(|message| message.kind == MessageKind::Assistant)
+            .map_or(0, |index| index + 1);
+        for message in &mut self.messages[start..] {
+            message.pending = message.kind == MessageKind::User;
+        }
+
+        let attempts = self.outbox.as_ref().map_or(0, |outbox| outbox.attempts) + 1;
+        let delay = (OUTBOX_RETRY * 2u32.pow(attempts.min(5) - 1)).min(Duration::from_secs(60));
+        if attempts == 1 {
+            self.add_system_message(format!(
+                "network unreachable: {} // prompt queued, resending when the connection \
+                 returns (Esc drops the queue)",
+                error
+            ));
+        }
+        self.outbox = Some(Outbox {
+            attempts,
+            next_attempt: Instant::now() + delay,
+        });
+        self.status_note = format!(
+            "offline // {} pending // retry in {}s",
+            self.queued_prompts(),
+            delay.as_secs()
+        );
+    }
+
+    fn queued_prompts(&self) -> usize {
+        self.messages.iter().filter(|message| message.pending).count()
+    }
+
+    /// The provider answered, so anything queued has now been sent.
+    fn outbox_delivered(&mut self) {
+        if self.outbox.take().is_some() {
+            for message in &mut self.messages {
+                message.pending = false;
+            }
+        }
+    }
+
+    /// Esc while offline: stop resending. The prompts stay in the transcript and
+    /// go out with the next message.
+    fn drop_outbox(&mut self) {
+        let queued = self.queued_prompts();
+        self.outbox_delivered();
+        self.status_note = format!("outbox cleared // {} prompts kept in context", queued);
+    }
+
+        /// Drops the last assistant reply and asks the provider again, optionally
+    /// at a different temperature.
+    fn retry_last_response(&mut self, temperature: Option<f32>) {
+        if self.pending_ai || !self.reveal_queue.is_empty() {
+            self.add_system_message("output pipeline busy: wait for the current reply before retrying");
+            return;
+        }
+        let Some(index) = self
+            .messages
+            .iter()
+            .rposition(|msg| msg.kind == MessageKind::Assistant)
+        else {
+            self.add_system_message("nothing to retry: no assistant reply in this transcript");
+            return;
+        };
 
-            while let Some(chunk) = chunk_rx.recv().await {
-                let _ = tx.send(AppEvent::StreamChunk {
-                    session_id,
-                    chunk,
-                });
+        self.messages.remove(index);
+        self.pinned_messages.retain(|&pinned| pinned != index);
+        for pinned in &mut self.pinned_messages {
+            if *pinned > index {
+                *pinned -= 1;
             }
+        }
+        let provider = self.provider.db_key().to_string();
+        self.archive(move |db| db.mark_last_reply_replaced(&provider), |_, ()| {});
 
-            if let Ok(Err(e)) = stream_task.await {
-                let _ = tx.send(AppEvent::AiFinished {
-                    session_id,
-                    result: Err(e.to_string()),
-                });
+        let client = match temperature {
+            Some(temperature) => {
+                self.add_system_message(format!("retrying at temperature {:.2}", temperature));
+                self.ai_client.clone().with_temperature(temperature)
             }
+            None => {
+                self.add_system_message("retrying last response");
+                self.ai_client.clone()
+            }
+        };
+        self.stream_response(client);
+    }
+
+    /// Pulls the last prompt back into the input line and drops it, plus
+    /// everything after it, from the transcript and the archive. The next
+    /// prompt becomes its new version, with the old text kept as a revision.
+    fn edit_last_prompt(&mut self) {
+        if self.pending_ai || !self.reveal_queue.is_empty() {
+            self.add_system_message("output pipeline busy: wait for the current reply before editing");
+            return;
+        }
+        let Some(index) = self
+            .messages
+            .iter()
+            .rposition(|msg| msg.kind == MessageKind::User)
+        else {
+            self.add_system_message("nothing to edit: no prompt in this transcript");
+            return;
+        };
+
+        let message = &self.messages[index];
+        self.input = message.prompt.clone().unwrap_or_else(|| message.content.clone());
+        let mut revisions = message.revisions.clone();
+        revisions.push(self.input.clone());
+        self.editing = Some(PendingEdit {
+            db_id: message.db_id,
+            provider: self.provider.db_key().to_string(),
+            revisions,
         });
+        let dropped = self.messages.len() - index;
+        self.messages.truncate(index);
+        self.pinned_messages.retain(|&pinned| pinned < index);
+        self.stream_message_index = None;
+        let provider = self.provider.db_key().to_string();
+        self.archive(move |db| db.delete_from_last_prompt(&provider), |_, ()| {});
+        self.follow_tail = true;
+        self.status_note = format!("editing last prompt // {} messages dropped", dropped);
     }
 
     fn start_shell(&mut self, command: String) {
@@ -1818,9 +5164,147 @@ impl App {
         self.messages.push(message);
     }
 
-    fn persist(&self, provider: &AIProvider, role: &str, kind: &str, content: &str) {
-        if let Some(db) = &self.db {
-            let _ = db.save_message(provider.db_key(), role, kind, content);
+    /// Queues a prompt and the files sent with it for the archive, as a new
+    /// version of the one `/edit` took back when that went to `provider`. The
+    /// row id lands on transcript message `key`. Returns the earlier versions.
+    fn persist_prompt(
+        &mut self,
+        key: u64,
+        provider: &str,
+        input: &str,
+        attachments: Vec<Attachment>,
+    ) -> Vec<String> {
+        let (revise, revisions) = match self.editing.take_if(|edit| edit.provider == provider) {
+            Some(edit) => (edit.db_id, edit.revisions),
+            None => (None, Vec::new()),
+        };
+        let (provider, input) = (provider.to_string(), input.to_string());
+        self.archive(
+            move |db| db.save_prompt(&provider, &input, revise, &attachments),
+            move |app, id| app.assign_db_id(key, id),
+        );
+        revisions
+    }
+
+    /// Queues one of the user's transcript rows for the archive.
+    fn persist(&mut self, key: u64, provider: &str, kind: &str, content: &str) {
+        let (provider, kind) = (provider.to_string(), kind.to_string());
+        let content = content.to_string();
+        self.archive(
+            move |db| db.save_message(&provider, "user", &kind, &content, None),
+            move |app, id| app.assign_db_id(key, id),
+        );
+    }
+
+    /// Queues `write` on the archiver; `done` gets its result back on the UI
+    /// thread, while a failure is reported in the transcript rather than
+    /// dropping the row silently. False without a database.
+    fn archive<T: Send + 'static>(
+        &self,
+        write: impl FnOnce(&Database) -> Result<T> + Send + 'static,
+        done: impl FnOnce(&mut App, T) + Send + 'static,
+    ) -> bool {
+        self.on_archiver("archive write failed", write, done)
+    }
+
+    /// Runs `read` on the archiver, after the writes queued before it, so a
+    /// locked database never stalls a frame; `done` gets the result back on
+    /// the UI thread. False without a database.
+    fn lookup<T: Send + 'static>(
+        &self,
+        read: impl FnOnce(&Database) -> Result<T> + Send + 'static,
+        done: impl FnOnce(&mut App, T) + Send + 'static,
+    ) -> bool {
+        self.on_archiver("archive read failed", read, done)
+    }
+
+    fn on_archiver<T: Send + 'static>(
+        &self,
+        failure: &'static str,
+        job: impl FnOnce(&Database) -> Result<T> + Send + 'static,
+        done: impl FnOnce(&mut App, T) + Send + 'static,
+    ) -> bool {
+        let Some(archiver) = &self.archiver else {
+            return false;
+        };
+        let tx = self.events_tx.clone();
+        archiver.queue(move |db| {
+            let result = job(db);
+            let _ = tx.send(AppEvent::Archived(Box::new(move |app: &mut App| match result {
+                Ok(value) => done(app, value),
+                Err(error) => {
+                    tracing::error!("{}: {:#}", failure, error);
+                    app.add_system_message(format!("{}: {:#}", failure, error))
+                }
+            })));
+        });
+        true
+    }
+
+    /// Reloads the analytics panel's stats on the archiver once they are
+    /// stale.
+    fn refresh_analytics(&mut self) {
+        if self.archiver.is_none() {
+            self.analytics.refresh(None);
+            return;
+        }
+        if let Some(query) = self.analytics.queue_refresh() {
+            self.lookup(
+                move |db| {
+                    let stats = query.load(db);
+                    Ok((query, stats))
+                },
+                |app, (query, stats)| app.analytics.loaded(query, stats),
+            );
+        }
+    }
+
+    /// Records the row id a queued insert got on transcript message `key`,
+    /// wherever it has moved since. A prompt archived under several providers
+    /// keeps the first.
+    fn assign_db_id(&mut self, key: u64, id: i64) {
+        let pending = self.pending_delete.as_mut().map(|pending| &mut pending.message);
+        let mut messages = self.messages.iter_mut().chain(pending);
+        if let Some(message) = messages.find(|message| message.key == key) {
+            message.db_id.get_or_insert(id);
+        }
+    }
+
+    /// Archives a reply with its request metadata and the usage accumulated
+    /// since the previous reply, attaching both to the transcript message at
+    /// `message`.
+    fn persist_reply(&mut self, message: Option<usize>, kind: &str, content: &str) {
+        let usage = std::mem::take(&mut self.turn_usage);
+        let model = self.ai_client.model_label().to_string();
+        let meta = ReplyMeta {
+            cost: self.prices.lookup(&model).map(|price| price.cost(usage)),
+            model,
+            latency_ms: self.request_started.elapsed().as_millis() as u64,
+            http_status: self.last_http_status,
+            usage,
+            finish_reason: self.turn_finish.take(),
+        };
+        let key = message.and_then(|index| self.messages.get_mut(index)).map(|message| {
+            message.meta = Some(meta.clone());
+            message.key
+        });
+        let provider = self.provider.db_key().to_string();
+        let (kind, content) = (kind.to_string(), content.to_string());
+        self.archive(
+            move |db| db.save_message(&provider, "assistant", &kind, &content, Some(&meta)),
+            move |app, id| {
+                if let Some(key) = key {
+                    app.assign_db_id(key, id);
+                }
+            },
+        );
+    }
+
+    fn record_usage(&mut self, usage: Usage) {
+        self.turn_usage += usage;
+        self.session_usage += usage;
+        if let Some(price) = self.prices.lookup(self.ai_client.model_label()) {
+            self.session_cost += price.cost(usage);
         }
     }
 
@@ -1875,7 +5359,6 @@ impl App {
     }
 
     fn build_context(&self) -> Vec<ApiMessage> {
-        const MAX_CONTEXT_CHARS: usize = 30000;
         const RECENT_BUDGET_RATIO: f32 = 0.70;
 
         let mut preamble: Vec<ApiMessage> = Vec::new();
@@ -1886,6 +5369,7 @@ impl App {
             preamble.push(ApiMessage {
                 role: "user".to_string(),
                 content: format!("[System context - agent memory]\n{}", memory_block),
+                images: Vec::new(),
             });
         }
 
@@ -1902,6 +5386,7 @@ impl App {
             preamble.push(ApiMessage {
                 role: "user".to_string(),
                 content: format!("[System context - recent command outputs]\n{}", shell_ctx),
+                images: Vec::new(),
             });
         }
 
@@ -1912,29 +5397,46 @@ impl App {
                     preamble.push(ApiMessage {
                         role: msg.context_role.to_string(),
                         content: format!("[Pinned] {}", msg.content),
+                        images: msg.images.clone(),
                     });
                 }
             }
         }
 
+        // A trimming /summarize stands in for the messages it covers
+        let summarized = self.summary.as_ref().filter(|summary| summary.trim).and_then(|summary| {
+            let index = self.messages.iter().position(|msg| msg.key == summary.through)?;
+            Some((index, &summary.text))
+        });
+        if let Some((_, text)) = summarized {
+            preamble.push(ApiMessage {
+                role: "user".to_string(),
+                content: format!("[Conversation summary - earlier turns]\n{}", text),
+                images: Vec::new(),
+            });
+        }
+        let first = summarized.map_or(0, |(index, _)| index + 1);
+
         // Collect conversation messages (non-pinned)
-        let conversation: Vec<(String, String)> = self
+        let conversation: Vec<(String, String, &[ai::Image])> = self
             .messages
             .iter()
             .enumerate()
+            .skip(first)
             .filter(|(i, msg)| msg.include_in_context && !self.pinned_messages.contains(i))
-            .map(|(_, msg)| (msg.context_role.to_string(), msg.content.clone()))
+            .map(|(_, msg)| (msg.context_role.to_string(), msg.content.clone(), &msg.images[..]))
             .collect();
 
         let preamble_chars: usize = preamble.iter().map(|m| m.content.len()).sum();
-        let conv_chars: usize = conversation.iter().map(|(_, c)| c.len()).sum();
+        let conv_chars: usize = conversation.iter().map(|(_, c, _)| c.len()).sum();
         let total_chars = preamble_chars + conv_chars;
 
         let mut context_msgs = preamble;
 
         if total_chars <= MAX_CONTEXT_CHARS || conversation.len() <= 4 {
-            for (role, content) in conversation {
-                context_msgs.push(ApiMessage { role, content });
+            for (role, content, images) in conversation {
+                let images = images.to_vec();
+                context_msgs.push(ApiMessage { role, content, images });
             }
         } else {
             // Summarize older messages, keep recent ones verbatim
@@ -1944,7 +5446,7 @@ impl App {
             // Find split point: keep as many recent messages as fit in budget
             let mut recent_chars = 0;
             let mut split = conversation.len();
-            for (i, (_, content)) in conversation.iter().enumerate().rev() {
+            for (i, (_, content, _)) in conversation.iter().enumerate().rev() {
                 if recent_chars + content.len() > budget_for_recent {
                     split = i + 1;
                     break;
@@ -1968,7 +5470,8 @@ impl App {
                 (summary_budget / old_messages.len()).max(40).min(200)
             };
 
-            for (role, content) in old_messages {
+            // images in the compressed part are dropped with the rest of it
+            for (role, content, _) in old_messages {
                 let tag = if role == "user" { "User" } else { "Assistant" };
                 let compressed = truncate(content, per_msg_budget);
                 summary_parts.push(format!("- {}: {}", tag, compressed));
@@ -1982,13 +5485,15 @@ impl App {
                         old_messages.len(),
                         summary_parts.join("\n")
                     ),
+                    images: Vec::new(),
                 });
             }
 
-            for (role, content) in recent_messages {
+            for (role, content, images) in recent_messages {
                 context_msgs.push(ApiMessage {
                     role: role.clone(),
                     content: content.clone(),
+                    images: images.to_vec(),
                 });
             }
         }
@@ -2032,7 +5537,12 @@ impl App {
 
     fn render(&mut self, frame: &mut Frame) {
         let area = frame.area();
-        let phase = self.intro_started.elapsed().as_secs_f32();
+        // a fixed phase stills the backdrop, scroller, raster bars and spinners
+        let phase = if self.reduce_motion {
+            0.0
+        } else {
+            self.intro_started.elapsed().as_secs_f32()
+        };
         render_background(frame.buffer_mut(), area, phase);
 
         match self.mode {
@@ -2040,15 +5550,30 @@ impl App {
             AppMode::Chat => self.render_chat(frame, area, phase),
             AppMode::Exit => {}
         }
+        if let Some(screensaver) = &self.screensaver {
+            screensaver.render(frame, phase);
+        }
+    }
+
+    fn start_screensaver(&mut self) {
+        let (width, height) = crossterm::terminal::size().unwrap_or((120, 40));
+        let video = self.screensaver_video.as_deref();
+        match Screensaver::start(video, width, height, self.reduce_motion) {
+            Ok(screensaver) => self.screensaver = Some(screensaver),
+            Err(error) => {
+                // don't retry every tick once the idle timer has run out
+                self.last_activity = Instant::now();
+                self.add_system_message(format!("screensaver video failed: {:#}", error));
+            }
+        }
     }
 
     fn render_intro(&self, frame: &mut Frame, area: Rect, phase: f32) {
-        let outer = Block::default()
-            .title(" [SYSTEM:DEM0ZONE v2.0] [MODE:AGENTIC] [MODULES:AI+VIDEO+WEBCAM+3D+ANALYTICS] ")
-            .title_style(Style::default().fg(t().accent4).bold())
-            .borders(Borders::ALL)
-            .border_type(BorderType::Double)
-            .border_style(Style::default().fg(t().accent1));
+        let outer = t().block(
+            " [SYSTEM:DEM0ZONE v2.0] [MODE:AGENTIC] [MODULES:AI+VIDEO+WEBCAM+3D+ANALYTICS] ",
+            t().accent4,
+            t().accent1,
+        );
         frame.render_widget(outer, area);
 
         let inner = area.inner(Margin {
@@ -2061,7 +5586,7 @@ impl App {
             let video_area = centered_area(inner, 82, 54);
             let shell = Block::default()
                 .title(" LIVE FEED // DECOMPRESSING ")
-                .title_style(Style::default().fg(t().accent2).bold())
+                .title_style(t().label_style())
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(t().accent3));
             frame.render_widget(shell, video_area);
@@ -2073,6 +5598,8 @@ impl App {
                 }),
                 0.95,
             );
+        } else {
+            render_boot_animation(frame.buffer_mut(), centered_area(inner, 82, 54), phase);
         }
 
         let burst_x = inner.x + inner.width.saturating_mul(22) / 100;
@@ -2095,8 +5622,8 @@ impl App {
 
         let info = vec![
             Line::from(vec![
-                Span::styled("v2.0.0", Style::default().fg(t().muted)),
-                Span::styled("  (POWERHOUSE)  ", Style::default().fg(t().text)),
+                Span::styled("v2.0.0", t().muted_style()),
+                Span::styled("  (POWERHOUSE)  ", t().text_style()),
                 Span::styled("//", Style::default().fg(t().accent1)),
                 Span::styled(
                     "  ALL-IN-ONE TERMINAL COMMAND CENTER",
@@ -2104,24 +5631,29 @@ impl App {
                 ),
             ]),
             Line::from(vec![
-                Span::styled("STACK:", Style::default().fg(t().accent2).bold()),
+                Span::styled("STACK:", t().label_style()),
                 Span::styled(
                     " ASCII VIDEO | MULTI-AI | LIVE BASH | WEBCAM | 3D FX | VIDEO CHAT | ANALYTICS",
-                    Style::default().fg(t().text),
+                    t().text_style(),
                 ),
             ]),
             Line::from(vec![
-                Span::styled("STATE:", Style::default().fg(t().accent2).bold()),
+                Span::styled("STATE:", t().label_style()),
                 Span::styled(
-                    " cracktro boot stream -> auto-transitions into the full command deck",
-                    Style::default().fg(t().text),
+                    if self.startup.done() {
+                        " cracktro boot stream -> auto-transitions into the full command deck"
+                            .to_string()
+                    } else {
+                        format!(" cracktro boot stream // loading {}", self.startup.pending())
+                    },
+                    t().text_style(),
                 ),
             ]),
             Line::from(vec![
-                Span::styled("INPUT:", Style::default().fg(t().accent2).bold()),
+                Span::styled("INPUT:", t().label_style()),
                 Span::styled(
                     " ENTER / SPACE skips intro immediately",
-                    Style::default().fg(t().text),
+                    t().text_style(),
                 ),
             ]),
         ];
@@ -2139,7 +5671,7 @@ impl App {
         frame.render_widget(
             Paragraph::new(Text::from(info))
                 .wrap(Wrap { trim: false })
-                .style(Style::default().bg(t().panel_bg)),
+                .style(t().panel_style()),
             info_area.inner(Margin {
                 horizontal: 1,
                 vertical: 1,
@@ -2167,7 +5699,7 @@ impl App {
             .constraints([
                 Constraint::Length(5),
                 Constraint::Min(14),
-                Constraint::Length(4),
+                Constraint::Length(self.input_height()),
                 Constraint::Length(1),
             ])
             .split(area);
@@ -2177,26 +5709,41 @@ impl App {
 
         self.render_header(frame, layout[0], phase);
 
-        // tiling-based panel rendering
-        let tiles = self.tiling.layout(body_area);
-        let focused_id = self.tiling.focused;
-        for (id, panel, rect) in &tiles {
-            let is_focused = *id == focused_id;
-            self.render_tile_panel(frame, *panel, *rect, phase, is_focused);
+        if let Some(compare) = &self.compare {
+            compare.render(frame, body_area);
+        } else {
+            // tiling-based panel rendering
+            let tiles = self.tiling.layout(body_area);
+            let focused_id = self.tiling.focused;
+            for (id, panel, rect) in &tiles {
+                let is_focused = *id == focused_id;
+                self.render_tile_panel(frame, *panel, *rect, phase, is_focused);
+            }
         }
 
         // Effects render ONLY inside the dedicated Effects3D tile panel,
         // not as a global overlay (which would overwrite all other panels).
 
         self.render_input(frame, layout[2]);
+        if let Some(completion) = &self.completion {
+            render_completion(frame, layout[2], completion);
+        }
         render_scroller(frame.buffer_mut(), layout[3], SCROLLER_TEXT, phase, t().accent1);
 
-        if self.show_help {
-            self.render_help_overlay(frame, area);
+        if self.help.open {
+            self.help.render(frame, area);
+        }
+
+        if self.show_model_picker {
+            self.render_model_picker_overlay(frame, area);
         }
 
-        if self.show_ollama_picker {
-            self.render_ollama_overlay(frame, area);
+        if let Some(message) = self.detail_message.and_then(|index| self.messages.get(index)) {
+            render_message_detail(frame, area, message, self.detail_scroll);
+        }
+
+        if self.log_viewer.open {
+            self.log_viewer.render(frame, area);
         }
     }
 
@@ -2213,7 +5760,7 @@ impl App {
         }
         // solid background fill: kill ghost artifacts from previous frames
         frame.render_widget(
-            Block::default().style(Style::default().bg(t().panel_bg)),
+            Block::default().style(t().panel_style()),
             area,
         );
         match panel {
@@ -2232,7 +5779,7 @@ impl App {
                 };
                 let block = Block::default()
                     .title(title)
-                    .title_style(Style::default().fg(t().accent2).bold())
+                    .title_style(t().label_style())
                     .borders(Borders::ALL)
                     .border_style(Style::default().fg(if is_focused { t().accent4 } else { t().accent1 }));
                 frame.render_widget(block, area);
@@ -2242,14 +5789,14 @@ impl App {
                 } else {
                     frame.render_widget(
                         Paragraph::new("F4 to cycle effects (includes off)")
-                            .style(Style::default().fg(t().muted).bg(t().panel_bg))
+                            .style(t().muted_style().bg(t().panel_bg))
                             .alignment(Alignment::Center),
                         inner,
                     );
                 }
             }
             PanelKind::Analytics => {
-                self.analytics.refresh(self.db.as_ref());
+                self.refresh_analytics();
                 self.analytics.render(frame, area, phase);
             }
             PanelKind::VideoChatFeeds => self.render_videochat_feeds(frame, area, phase),
@@ -2280,12 +5827,7 @@ impl App {
     }
 
     fn render_header(&self, frame: &mut Frame, area: Rect, phase: f32) {
-        let block = Block::default()
-            .title(" COMMAND DECK ")
-            .title_style(Style::default().fg(t().accent1).bold())
-            .borders(Borders::ALL)
-            .border_type(BorderType::Double)
-            .border_style(Style::default().fg(t().accent1));
+        let block = t().block(" COMMAND DECK ", t().accent1, t().accent1);
         frame.render_widget(block, area);
 
         let inner = area.inner(Margin {
@@ -2339,6 +5881,9 @@ impl App {
             if self.pending_ai { "live" } else { "idle" },
             self.pending_shells,
         );
+        let gauge = self.context_gauge();
+        let gauge_width = gauge.width() as u16;
+        let meta = truncate(&meta, inner.width.saturating_sub(12 + gauge_width) as usize);
         render_gradient_text(
             frame.buffer_mut(),
             inner.x + 10,
@@ -2347,10 +5892,19 @@ impl App {
             t().text,
             self.provider.color(),
         );
+        if inner.width > gauge_width + 40 {
+            let gauge_area = Rect {
+                x: inner.right() - gauge_width,
+                y: inner.y + 1,
+                width: gauge_width,
+                height: 1,
+            };
+            frame.render_widget(Paragraph::new(gauge), gauge_area);
+        }
 
         let status = truncate(&self.status_note, inner.width.saturating_sub(24) as usize);
         let badge = format!("[{}]", current_spinner(phase));
-        let x = area.x + area.width.saturating_sub(status.chars().count() as u16 + 6);
+        let x = area.x + area.width.saturating_sub(text::width(&status) as u16 + 6);
         render_gradient_text(
             frame.buffer_mut(),
             x,
@@ -2361,69 +5915,236 @@ impl App {
         );
     }
 
-    fn render_messages_inner(&self, frame: &mut Frame, inner: Rect) {
+    /// `ctx ▰▰▰▱▱ 5.2k/200k`: estimated context against the model window, green
+    /// below 60%, yellow below 85%, red above. `trim` marks that older turns
+    /// will be summarized.
+    fn context_gauge(&self) -> Line<'static> {
+        const CELLS: usize = 10;
+        let (tokens, trimmed) = self.context_estimate();
+        let window = self.context_window().max(1) as usize;
+        let ratio = tokens as f64 / window as f64;
+        let color = if ratio >= 0.85 {
+            t().danger
+        } else if ratio >= 0.6 {
+            t().accent2
+        } else {
+            t().accent3
+        };
+        let filled = ((ratio * CELLS as f64).ceil() as usize).min(CELLS);
+        let mut spans = vec![
+            Span::styled("ctx ", t().label_style()),
+            Span::styled("▰".repeat(filled), Style::default().fg(color)),
+            Span::styled("▱".repeat(CELLS - filled), t().muted_style()),
+            Span::styled(
+                format!(" {}/{}", format_tokens(tokens as u32), format_tokens(window as u32)),
+                Style::default().fg(color),
+            ),
+        ];
+        if trimmed {
+            spans.push(Span::styled(" trim", Style::default().fg(t().accent2).bold()));
+        }
+        Line::from(spans)
+    }
+
+    /// Transcript lines plus the index of each message's first line.
+    fn transcript_lines(&self) -> (Vec<Line<'_>>, Vec<usize>) {
         let mut lines = Vec::new();
-        for message in &self.messages {
-            let tag = match message.kind {
-                MessageKind::User => "USER",
-                MessageKind::Assistant => "AI",
-                MessageKind::Shell => "OPS",
-                MessageKind::System => "SYS",
+        let mut starts = Vec::with_capacity(self.messages.len());
+        for (index, message) in self.messages.iter().enumerate() {
+            starts.push(lines.len());
+            // a shape and a tag per role, so no role is told apart by hue alone
+            let (marker, tag) = match message.kind {
+                MessageKind::User => ("▸", "USER"),
+                MessageKind::Assistant => ("◆", "AI"),
+                MessageKind::Shell => ("$", "OPS"),
+                MessageKind::System => ("•", "SYS"),
             };
-            lines.push(Line::from(vec![
-                Span::styled(
-                    format!("{} ", message.label),
-                    Style::default().fg(message.accent).bold(),
-                ),
+            let selected = self.keymap.mode == EditMode::Select && self.selected_message == Some(index);
+            let accent = match message.kind {
+                MessageKind::User => t().user,
+                MessageKind::System => t().accent1,
+                _ => message.accent,
+            };
+            let mut label_style = Style::default().fg(accent).bold();
+            if selected || self.search_hit == Some(index) {
+                label_style = label_style.reversed();
+            }
+            let mut header = vec![
+                Span::styled(format!("{} {} ", marker, message.label), label_style),
                 Span::styled(format!("[{}]", tag), Style::default().fg(t().accent2)),
-            ]));
+            ];
+            if let Some(meta) = &message.meta {
+                header.push(Span::styled(
+                    format!(" {:.1}s", meta.latency_ms as f32 / 1000.0),
+                    t().muted_style(),
+                ));
+            }
+            if message.bookmarked {
+                header.push(Span::styled(" ★", Style::default().fg(t().accent3).bold()));
+            }
+            if !message.revisions.is_empty() {
+                header.push(Span::styled(" (edited)", t().muted_style().italic()));
+            }
+            if message.pending {
+                header.push(Span::styled(" pending", Style::default().fg(t().accent2).italic()));
+            }
+            lines.push(Line::from(header));
 
-            if message.content.is_empty() {
+            let live = self.pending_ai && self.stream_message_index == Some(index);
+            if message.content.is_empty() && live {
+                lines.push(self.waiting_line("waiting on"));
+            } else if message.content.is_empty() {
                 lines.push(Line::from(Span::styled(
                     "  . . .",
-                    Style::default().fg(t().muted),
+                    t().muted_style(),
                 )));
             } else {
+                let text_style = Style::default().fg(match message.kind {
+                    MessageKind::System => t().system,
+                    _ => t().text,
+                });
                 for content_line in message.content.lines() {
+                    if selected {
+                        lines.push(Line::from(vec![
+                            Span::styled("▌ ", Style::default().fg(accent).bold()),
+                            Span::styled(content_line, text_style.bg(t().panel_alt)),
+                        ]));
+                    } else {
+                        lines.push(Line::from(Span::styled(
+                            format!("  {}", content_line),
+                            text_style,
+                        )));
+                    }
+                }
+            }
+            if live && !message.content.is_empty() {
+                lines.push(self.waiting_line("streaming from"));
+            }
+            if message.show_revisions {
+                for (number, revision) in message.revisions.iter().enumerate().rev() {
                     lines.push(Line::from(Span::styled(
-                        format!("  {}", content_line),
-                        Style::default().fg(match message.kind {
-                            MessageKind::System => Color::Rgb(171, 183, 192),
-                            _ => t().text,
-                        }),
+                        format!("  ── version {} ──", number + 1),
+                        t().label_style(),
                     )));
+                    for revision_line in revision.lines() {
+                        lines.push(Line::from(Span::styled(
+                            format!("  {}", revision_line),
+                            t().muted_style(),
+                        )));
+                    }
+                }
+            }
+            if self.show_meta {
+                lines.push(Line::from(Span::styled(
+                    format!("  {}", message_meta_label(message)),
+                    t().muted_style().italic(),
+                )));
+            }
+            if !message.sources.is_empty() {
+                lines.push(Line::from(Span::styled("  sources:", t().muted_style())));
+                for (number, source) in message.sources.iter().enumerate() {
+                    lines.push(Line::from(vec![
+                        Span::styled(format!("  [{}] ", number + 1), t().muted_style()),
+                        Span::styled(source.title.as_str(), t().text_style()),
+                        Span::styled(
+                            format!(" {}", source.url),
+                            Style::default().fg(t().accent2).underlined(),
+                        ),
+                    ]));
                 }
             }
             lines.push(Line::from(""));
         }
 
+        (lines, starts)
+    }
+
+    /// Spinner, provider/model and elapsed seconds for the reply in flight.
+    fn waiting_line(&self, verb: &str) -> Line<'static> {
+        let spinner = current_spinner(self.intro_started.elapsed().as_secs_f32());
+        let model = self.ai_client.model_label();
+        let target = if model.is_empty() {
+            self.provider.name().to_string()
+        } else {
+            format!("{} // {}", self.provider.name(), model)
+        };
+        Line::from(vec![
+            Span::styled(
+                format!("  {} ", spinner),
+                Style::default().fg(self.provider.color()).bold(),
+            ),
+            Span::styled(format!("{} {} ", verb, target), t().muted_style()),
+            Span::styled(
+                format!("{:.1}s", self.request_started.elapsed().as_secs_f32()),
+                Style::default().fg(t().accent4),
+            ),
+        ])
+    }
+
+    fn render_messages_inner(&self, frame: &mut Frame, inner: Rect) {
+        let (mut lines, _) = self.transcript_lines();
         if lines.is_empty() {
             lines.push(Line::from(Span::styled(
                 "No traffic yet. Ask the model something, !shell, /webcam, /3d, /server, or /connect.",
-                Style::default().fg(t().muted),
+                t().muted_style(),
             )));
         }
 
-        // Estimate wrapped line count so scroll doesn't overshoot
-        let wrap_width = inner.width.max(1) as usize;
-        let total_wrapped: usize = lines.iter().map(|l| {
-            let w = l.width();
-            if w == 0 { 1 } else { (w + wrap_width - 1) / wrap_width }
-        }).sum();
-        let total_lines = total_wrapped.max(1);
+        // The rightmost column is kept for the scrollbar.
+        let text_area = Rect {
+            width: inner.width.saturating_sub(1),
+            ..inner
+        };
+        let widget = Paragraph::new(Text::from(lines))
+            .wrap(Wrap { trim: false })
+            .style(t().panel_style());
+        let total_lines = widget.line_count(text_area.width).max(1);
         let visible_lines = inner.height as usize;
         let max_scroll = total_lines.saturating_sub(visible_lines);
+        self.transcript_view.set(TranscriptView {
+            width: text_area.width,
+            height: inner.height,
+            max_scroll,
+        });
         let scroll = if self.follow_tail {
             max_scroll
         } else {
             self.scroll_lines.min(max_scroll)
         };
 
-        let widget = Paragraph::new(Text::from(lines))
-            .wrap(Wrap { trim: false })
-            .scroll((scroll as u16, 0))
-            .style(Style::default().bg(t().panel_bg));
-        frame.render_widget(widget, inner);
+        frame.render_widget(widget.scroll((scroll.min(u16::MAX as usize) as u16, 0)), text_area);
+        if let Some(background) = &self.chat_background {
+            background.render(frame.buffer_mut(), text_area);
+        }
+        let below = max_scroll - scroll;
+        if below > 0 && inner.height > 0 {
+            let marker = format!(" \u{25BC} {} more lines // End to follow ", below);
+            let width = (marker.chars().count() as u16).min(text_area.width);
+            let marker_area = Rect {
+                x: text_area.x + text_area.width - width,
+                y: inner.y + inner.height - 1,
+                width,
+                height: 1,
+            };
+            frame.render_widget(
+                Paragraph::new(marker).style(Style::default().fg(t().bg_base).bg(t().accent4)),
+                marker_area,
+            );
+        }
+        if max_scroll > 0 {
+            let mut state = ScrollbarState::new(max_scroll + 1)
+                .viewport_content_length(visible_lines)
+                .position(scroll);
+            frame.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                    .begin_symbol(None)
+                    .end_symbol(None)
+                    .track_style(t().muted_style())
+                    .thumb_style(Style::default().fg(t().accent1)),
+                inner,
+                &mut state,
+            );
+        }
     }
 
     fn render_video_panel(&self, frame: &mut Frame, area: Rect, phase: f32) {
@@ -2438,7 +6159,7 @@ impl App {
         };
         let block = Block::default()
             .title(title)
-            .title_style(Style::default().fg(t().accent2).bold())
+            .title_style(t().label_style())
             .borders(Borders::ALL)
             .border_style(Style::default().fg(t().accent1));
         frame.render_widget(block, area);
@@ -2494,7 +6215,7 @@ impl App {
         });
 
         if let Some(ref ascii) = self.webcam_frame {
-            render_ascii_frame(frame.buffer_mut(), inner, ascii, 0.9);
+            render_fitted(frame.buffer_mut(), inner, ascii, 0.9);
         } else {
             let msg = if let Some(ref cam) = self.webcam {
                 if let Some(err) = cam.error() {
@@ -2534,29 +6255,29 @@ impl App {
 
         let lines = vec![
             Line::from(vec![
-                Span::styled("provider: ", Style::default().fg(t().accent2).bold()),
+                Span::styled("provider: ", t().label_style()),
                 Span::styled(
                     self.provider_display_name(),
                     Style::default().fg(self.provider.color()),
                 ),
             ]),
             Line::from(vec![
-                Span::styled("status:   ", Style::default().fg(t().accent2).bold()),
+                Span::styled("status:   ", t().label_style()),
                 Span::styled(
                     if self.pending_ai {
                         "awaiting model response"
                     } else {
                         "terminal steady"
                     },
-                    Style::default().fg(t().text),
+                    t().text_style(),
                 ),
             ]),
             Line::from(vec![
-                Span::styled("shell:    ", Style::default().fg(t().accent2).bold()),
-                Span::styled(&self.last_shell_status, Style::default().fg(t().text)),
+                Span::styled("shell:    ", t().label_style()),
+                Span::styled(&self.last_shell_status, t().text_style()),
             ]),
             Line::from(vec![
-                Span::styled("3d fx:    ", Style::default().fg(t().accent2).bold()),
+                Span::styled("3d fx:    ", t().label_style()),
                 Span::styled(
                     if self.effects.active {
                         self.effects.kind.name()
@@ -2567,7 +6288,7 @@ impl App {
                 ),
             ]),
             Line::from(vec![
-                Span::styled("webcam:   ", Style::default().fg(t().accent2).bold()),
+                Span::styled("webcam:   ", t().label_style()),
                 Span::styled(
                     if self.webcam.is_some() { "active" } else { "offline" },
                     Style::default().fg(if self.webcam.is_some() { t().accent3 } else { t().muted }),
@@ -2608,39 +6329,39 @@ impl App {
         let mut lines = vec![
             Line::from(Span::styled(
                 "!<cmd>         raw shell",
-                Style::default().fg(t().text),
+                t().text_style(),
             )),
             Line::from(Span::styled(
                 "/server <port> host video chat",
-                Style::default().fg(t().text),
+                t().text_style(),
             )),
             Line::from(Span::styled(
                 "/connect <url> join video chat",
-                Style::default().fg(t().text),
+                t().text_style(),
             )),
             Line::from(Span::styled(
                 "/webcam /3d /fx /analytics /games /tiles [1-8]",
-                Style::default().fg(t().text),
+                t().text_style(),
             )),
             Line::from(Span::styled(
-                "/provider <name> /ollama switch ai route or local model picker",
-                Style::default().fg(t().text),
+                "/provider <name> /ollama /openrouter switch ai route or model picker",
+                t().text_style(),
             )),
             Line::from(Span::styled(
                 "/youtube <url> stream YouTube into video bus",
-                Style::default().fg(t().text),
+                t().text_style(),
             )),
             Line::from(""),
             Line::from(Span::styled(
                 "recent ops:",
-                Style::default().fg(t().accent2).bold(),
+                t().label_style(),
             )),
         ];
 
         if self.recent_commands.is_empty() {
             lines.push(Line::from(Span::styled(
                 "  none yet",
-                Style::default().fg(t().muted),
+                t().muted_style(),
             )));
         } else {
             for command in &self.recent_commands {
@@ -2656,7 +6377,7 @@ impl App {
 
         frame.render_widget(
             Paragraph::new(Text::from(lines))
-                .style(Style::default().bg(t().panel_bg))
+                .style(t().panel_style())
                 .wrap(Wrap { trim: false }),
             inner,
         );
@@ -2666,12 +6387,7 @@ impl App {
     }
 
     fn render_videochat_feeds(&self, frame: &mut Frame, area: Rect, _phase: f32) {
-        let block = Block::default()
-            .title(" VIDEO CHAT // LIVE FEEDS ")
-            .title_style(Style::default().fg(t().accent2).bold())
-            .borders(Borders::ALL)
-            .border_type(BorderType::Double)
-            .border_style(Style::default().fg(t().accent1));
+        let block = t().block(" VIDEO CHAT // LIVE FEEDS ", t().accent2, t().accent1);
         frame.render_widget(block, area);
 
         let inner = area.inner(Margin {
@@ -2683,7 +6399,7 @@ impl App {
             let frames = vc.remote_frames.read();
             if frames.is_empty() {
                 if let Some(ref local) = *vc.local_frame.read() {
-                    render_ascii_frame(frame.buffer_mut(), inner, local, 0.9);
+                    render_fitted(frame.buffer_mut(), inner, local, 0.9);
                     render_gradient_text(
                         frame.buffer_mut(),
                         inner.x + 1,
@@ -2695,7 +6411,7 @@ impl App {
                 } else {
                     frame.render_widget(
                         Paragraph::new("waiting for video feeds...")
-                            .style(Style::default().fg(t().muted).bg(t().panel_bg))
+                            .style(t().muted_style().bg(t().panel_bg))
                             .alignment(Alignment::Center),
                         inner,
                     );
@@ -2726,7 +6442,7 @@ impl App {
                     for c in 0..cols {
                         if let Some((uname, ascii_frame)) = frame_iter.next() {
                             let cell_area = col_layout[c];
-                            render_ascii_frame(frame.buffer_mut(), cell_area, ascii_frame, 0.85);
+                            render_fitted(frame.buffer_mut(), cell_area, ascii_frame, 0.85);
                             let is_self = uname == &self.username;
                             let label = if is_self {
                                 format!("{} (you)", uname)
@@ -2748,7 +6464,7 @@ impl App {
         } else {
             frame.render_widget(
                 Paragraph::new("not connected. use /server <port> or /connect ws://<addr>")
-                    .style(Style::default().fg(t().muted).bg(t().panel_bg))
+                    .style(t().muted_style().bg(t().panel_bg))
                     .alignment(Alignment::Center),
                 inner,
             );
@@ -2784,25 +6500,25 @@ impl App {
                 };
                 lines.push(Line::from(vec![
                     Span::styled(format!("{}: ", uname), Style::default().fg(color).bold()),
-                    Span::styled(content.as_str(), Style::default().fg(t().text)),
+                    Span::styled(content.as_str(), t().text_style()),
                 ]));
             }
             if lines.is_empty() {
                 lines.push(Line::from(Span::styled(
                     "no messages yet. use /chat <msg>",
-                    Style::default().fg(t().muted),
+                    t().muted_style(),
                 )));
             }
             frame.render_widget(
                 Paragraph::new(Text::from(lines))
                     .wrap(Wrap { trim: false })
-                    .style(Style::default().bg(t().panel_bg)),
+                    .style(t().panel_style()),
                 inner,
             );
         } else {
             frame.render_widget(
                 Paragraph::new("offline")
-                    .style(Style::default().fg(t().muted).bg(t().panel_bg))
+                    .style(t().muted_style().bg(t().panel_bg))
                     .alignment(Alignment::Center),
                 inner,
             );
@@ -2812,7 +6528,7 @@ impl App {
     fn render_videochat_users(&self, frame: &mut Frame, area: Rect, phase: f32) {
         let block = Block::default()
             .title(" CONNECTED USERS ")
-            .title_style(Style::default().fg(t().accent2).bold())
+            .title_style(t().label_style())
             .borders(Borders::ALL)
             .border_style(Style::default().fg(t().accent1));
         frame.render_widget(block, area);
@@ -2827,14 +6543,14 @@ impl App {
             let status = vc.get_status();
             let mut lines = vec![
                 Line::from(vec![
-                    Span::styled("status: ", Style::default().fg(t().accent2).bold()),
-                    Span::styled(status, Style::default().fg(t().text)),
+                    Span::styled("status: ", t().label_style()),
+                    Span::styled(status, t().text_style()),
                 ]),
             ];
             if users.is_empty() {
                 lines.push(Line::from(Span::styled(
                     "  no users yet",
-                    Style::default().fg(t().muted),
+                    t().muted_style(),
                 )));
             } else {
                 for u in users.iter() {
@@ -2852,7 +6568,7 @@ impl App {
                                 .bold(),
                         ),
                         if is_self {
-                            Span::styled(" (you)", Style::default().fg(t().muted))
+                            Span::styled(" (you)", t().muted_style())
                         } else {
                             Span::raw("")
                         },
@@ -2862,51 +6578,96 @@ impl App {
             frame.render_widget(
                 Paragraph::new(Text::from(lines))
                     .wrap(Wrap { trim: false })
-                    .style(Style::default().bg(t().panel_bg)),
+                    .style(t().panel_style()),
                 inner,
             );
         } else {
             frame.render_widget(
                 Paragraph::new("offline")
-                    .style(Style::default().fg(t().muted).bg(t().panel_bg))
+                    .style(t().muted_style().bg(t().panel_bg))
                     .alignment(Alignment::Center),
                 inner,
             );
         }
     }
 
+    /// Rows of the input box: a chip line with attachments, and room for
+    /// the tallest image thumbnail.
+    fn input_height(&self) -> u16 {
+        let thumbnails = self.attachments.iter().filter_map(|a| a.thumbnail.as_ref());
+        let thumbnail = thumbnails.map(|thumbnail| thumbnail.height).max().unwrap_or(0);
+        if self.attachments.is_empty() {
+            4
+        } else {
+            5 + thumbnail
+        }
+    }
+
     fn render_input(&self, frame: &mut Frame, area: Rect) {
-        let block = Block::default()
-            .title(" TRANSMIT ")
-            .title_style(Style::default().fg(t().accent4).bold())
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(t().accent3))
-            .border_type(BorderType::Double);
+        let block = t().block(" TRANSMIT ", t().accent4, t().accent3);
         frame.render_widget(block, area);
 
-        let inner = area.inner(Margin {
+        let mut inner = area.inner(Margin {
             horizontal: 1,
             vertical: 1,
         });
+        let thumbnail_rows = self.input_height().saturating_sub(5).min(inner.height);
+        if thumbnail_rows > 0 {
+            // image previews side by side above the chips, as they'll be sent
+            let mut x = inner.x;
+            for thumbnail in self.attachments.iter().filter_map(|a| a.thumbnail.as_ref()) {
+                let width = thumbnail.width.min(inner.right().saturating_sub(x));
+                let cell = Rect::new(x, inner.y, width, thumbnail_rows);
+                render_fitted(frame.buffer_mut(), cell, thumbnail, 1.0);
+                x = x.saturating_add(width + 1);
+            }
+            inner.y += thumbnail_rows;
+            inner.height -= thumbnail_rows;
+        }
 
+        let retry_label = self.retry_notice.as_ref().map(|(until, label)| {
+            let remaining = until.saturating_duration_since(Instant::now()).as_secs_f32();
+            format!("RETRYING IN {:.0}s ({}) [Esc=cancel]", remaining.ceil(), label)
+        });
+        let voice_label = self.voice.as_ref().map(|take| {
+            format!(
+                "● REC {:.1}s ▕{}▏ [Enter=stop Esc=discard]",
+                take.elapsed().as_secs_f32(),
+                voice::meter(take.level(), 12)
+            )
+        });
         let status = if self.pending_approval.is_some() {
             "APPROVAL PENDING [Enter=approve Esc=reject]"
+        } else if let Some(label) = voice_label.as_deref() {
+            label
+        } else if self.transcribing {
+            "TRANSCRIBING"
+        } else if self.keymap.mode == EditMode::Select {
+            "SELECT [Up/Dn move  c copy  d delete  u undo  r quote  b bookmark  e edits  Enter view  Esc done]"
+        } else if let Some(label) = retry_label.as_deref().filter(|_| self.pending_ai) {
+            label
         } else if self.pending_ai {
             if self.tool_loop_depth > 0 {
                 "AGENT TOOL LOOP"
             } else {
-                "MODEL LINK BUSY"
+                "MODEL LINK BUSY [Esc=cancel]"
             }
         } else if self.streaming_active {
             "STREAMING"
         } else if self.pending_shells > 0 {
             "OPS EXECUTING"
+        } else if self.compare.as_ref().is_some_and(CompareState::busy) {
+            "COMPARING [Esc=cancel]"
         } else if self.video_chat.as_ref().map_or(false, |c| c.is_connected()) {
             "READY // VC LIVE"
+        } else if self.compare.as_ref().is_some_and(|c| c.layout == PaneLayout::Tabs) {
+            "READY // ARENA [Left/Right tabs  /vote n]"
+        } else if self.compare.is_some() {
+            "READY // COMPARE"
         } else {
             "READY"
         };
-        let status_color = if self.pending_approval.is_some() {
+        let status_color = if self.pending_approval.is_some() || self.voice.is_some() {
             t().accent2
         } else {
             self.provider.color()
@@ -2916,28 +6677,64 @@ impl App {
             TrustLevel::ConfirmDestructive => "safe",
             TrustLevel::ConfirmAll => "ask",
         });
-        let lines = vec![
-            Line::from(vec![
-                Span::styled("> ", Style::default().fg(t().accent4).bold()),
+        let cost_tag = if self.session_usage.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "  |  session {} // {} tok",
+                format_cost(self.session_cost),
+                format_tokens(self.session_usage.input_tokens + self.session_usage.output_tokens)
+            )
+        };
+        let (prompt_marker, prompt_text) = if self.keymap.mode == EditMode::Search {
+            ("/", self.search_query.as_str())
+        } else if self.input.is_empty() {
+            ("> ", "prompt, !bash, @file, /ollama, /games, /tiles, /trust, /remember ...")
+        } else {
+            ("> ", self.input.as_str())
+        };
+        let mut mode_line = vec![
+            Span::styled("mode: ", t().label_style()),
+            Span::styled(status, Style::default().fg(status_color)),
+        ];
+        if self.keymap.vim || self.keymap.mode == EditMode::Select {
+            mode_line.insert(
+                0,
                 Span::styled(
-                    if self.input.is_empty() {
-                        "prompt, !bash, @file, /ollama, /games, /tiles, /trust, /remember ..."
-                    } else {
-                        self.input.as_str()
-                    },
-                    Style::default().fg(t().text),
+                    format!("-- {} -- ", self.keymap.mode.label()),
+                    Style::default().fg(t().accent4).bold(),
                 ),
-                Span::styled("_", Style::default().fg(t().accent2).bold()),
-            ]),
+            );
+        }
+        mode_line.push(Span::styled(
+            format!(
+                "  |  {}{}  |  F1 help  F2 ai  F4 fx  F5 cam  F6 layout  F7 tiles  F8 panel",
+                trust_tag, cost_tag
+            ),
+            t().muted_style(),
+        ));
+
+        let mut lines = Vec::new();
+        if !self.attachments.is_empty() {
+            let mut chips = vec![Span::styled("attached ", t().muted_style())];
+            for attachment in &self.attachments {
+                let color = if attachment.truncated { t().accent2 } else { t().accent3 };
+                chips.push(Span::styled(
+                    format!("[{}]", attachment.chip()),
+                    Style::default().fg(color).bold(),
+                ));
+                chips.push(Span::raw(" "));
+            }
+            lines.push(Line::from(chips));
+        }
+        lines.extend([
             Line::from(vec![
-                Span::styled("mode: ", Style::default().fg(t().accent2).bold()),
-                Span::styled(status, Style::default().fg(status_color)),
-                Span::styled(
-                    format!("  |  {}  |  F1 help  F2 ai  F4 fx  F5 cam  F6 layout  F7 tiles  F8 panel", trust_tag),
-                    Style::default().fg(t().muted),
-                ),
+                Span::styled(prompt_marker, Style::default().fg(t().accent4).bold()),
+                Span::styled(prompt_text, t().text_style()),
+                Span::styled("_", t().label_style()),
             ]),
-        ];
+            Line::from(mode_line),
+        ]);
 
         frame.render_widget(
             Paragraph::new(Text::from(lines))
@@ -2947,20 +6744,17 @@ impl App {
         );
     }
 
-    fn render_ollama_overlay(&self, frame: &mut Frame, area: Rect) {
+    fn render_model_picker_overlay(&self, frame: &mut Frame, area: Rect) {
         let popup = centered_area(area, 74, 72);
         frame.render_widget(Clear, popup);
-        let title = if self.ollama_picker_loading {
-            " OLLAMA // DISCOVERING LOCAL MODELS "
-        } else {
-            " OLLAMA // MODEL PICKER "
+        let ollama = self.provider.is(&OLLAMA);
+        let title = match (ollama, self.model_picker_loading) {
+            (true, true) => " OLLAMA // DISCOVERING LOCAL MODELS ",
+            (true, false) => " OLLAMA // MODEL PICKER ",
+            (false, true) => " OPENROUTER // FETCHING CATALOG ",
+            (false, false) => " OPENROUTER // MODEL PICKER ",
         };
-        let block = Block::default()
-            .title(title)
-            .title_style(Style::default().fg(self.provider.color()).bold())
-            .borders(Borders::ALL)
-            .border_type(BorderType::Double)
-            .border_style(Style::default().fg(t().accent1));
+        let block = t().block(title, self.provider.color(), t().accent1);
         frame.render_widget(block, popup);
 
         let inner = popup.inner(Margin {
@@ -2968,40 +6762,51 @@ impl App {
             vertical: 1,
         });
 
-        let current_model = self
-            .ollama_selected_model
-            .clone()
-            .unwrap_or_else(|| "none selected".to_string());
+        let current_model = self.selected_model().cloned().unwrap_or_else(|| {
+            if ollama {
+                "none selected".to_string()
+            } else {
+                "openrouter/auto".to_string()
+            }
+        });
         let mut lines = vec![
             Line::from(vec![
-                Span::styled("MODEL SELECT ", Style::default().fg(t().accent2).bold()),
+                Span::styled("MODEL SELECT ", t().label_style()),
                 Span::styled(
                     "Type a number and press Enter. Esc closes. R refreshes. J/K or arrows scroll.",
-                    Style::default().fg(t().text),
+                    t().text_style(),
                 ),
             ]),
             Line::from(vec![
-                Span::styled("current: ", Style::default().fg(t().accent2).bold()),
+                Span::styled("current: ", t().label_style()),
                 Span::styled(current_model, Style::default().fg(self.provider.color())),
             ]),
             Line::from(""),
         ];
 
-        if self.ollama_picker_loading {
+        if self.model_picker_loading {
             lines.push(Line::from(Span::styled(
-                "Scanning the local Ollama API for installed models...",
+                if ollama {
+                    "Scanning the local Ollama API for installed models..."
+                } else {
+                    "Fetching the OpenRouter model catalog..."
+                },
                 Style::default().fg(t().accent4),
             )));
-        } else if let Some(error) = &self.ollama_picker_error {
+        } else if let Some(error) = &self.model_picker_error {
             lines.push(Line::from(Span::styled(
-                "Ollama is not ready on this machine:",
+                if ollama {
+                    "Ollama is not ready on this machine:"
+                } else {
+                    "OpenRouter catalog unavailable:"
+                },
                 Style::default().fg(t().danger).bold(),
             )));
             lines.push(Line::from(Span::styled(
                 error,
-                Style::default().fg(t().text),
+                t().text_style(),
             )));
-            if error.contains("not installed") {
+            if ollama && error.contains("not installed") {
                 lines.push(Line::from(""));
                 lines.push(Line::from(Span::styled(
                     ollama_install_hint(),
@@ -3009,27 +6814,21 @@ impl App {
                 )));
             }
         } else {
-            for (idx, model) in self.ollama_models.iter().enumerate() {
-                let marker = if self
-                    .ollama_selected_model
-                    .as_ref()
-                    .map(|selected| selected == &model.name)
-                    .unwrap_or(false)
-                {
+            for (idx, model) in self.picker_models.iter().enumerate() {
+                let marker = if self.selected_model() == Some(&model.name) {
                     ">"
                 } else {
                     " "
                 };
-                let meta = format_ollama_model_meta(model);
                 lines.push(Line::from(vec![
                     Span::styled(
                         format!("{} {:>2}. ", marker, idx + 1),
-                        Style::default().fg(t().accent2).bold(),
+                        t().label_style(),
                     ),
-                    Span::styled(model.name.clone(), Style::default().fg(t().text)),
+                    Span::styled(model.name.clone(), t().text_style()),
                     Span::styled(
-                        format!("  [{}]", meta),
-                        Style::default().fg(t().muted),
+                        format!("  [{}]", model.meta),
+                        t().muted_style(),
                     ),
                 ]));
             }
@@ -3037,127 +6836,103 @@ impl App {
 
         lines.push(Line::from(""));
         lines.push(Line::from(vec![
-            Span::styled("selection: ", Style::default().fg(t().accent2).bold()),
-            Span::styled(
-                if self.ollama_selection_input.is_empty() {
-                    "_".to_string()
-                } else {
-                    self.ollama_selection_input.clone()
-                },
-                Style::default().fg(self.provider.color()),
-            ),
-        ]));
-
-        frame.render_widget(
-            Paragraph::new(Text::from(lines))
-                .wrap(Wrap { trim: false })
-                .scroll((self.ollama_picker_scroll as u16, 0))
-                .style(Style::default().bg(t().panel_bg)),
-            inner,
-        );
-    }
-
-    fn render_help_overlay(&self, frame: &mut Frame, area: Rect) {
-        let popup = centered_area(area, 78, 75);
-        frame.render_widget(Clear, popup);
-        let block = Block::default()
-            .title(" HELP // ASCIIVISION v2 OPERATIONS MANUAL ")
-            .title_style(Style::default().fg(t().accent1).bold())
-            .borders(Borders::ALL)
-            .border_type(BorderType::Double)
-            .border_style(Style::default().fg(t().accent1));
-        frame.render_widget(block, popup);
-
-        let text = Text::from(vec![
-            Line::from(vec![
-                Span::styled("PROMPTS    ", Style::default().fg(t().accent2).bold()),
-                Span::styled("plain text goes to the active AI provider; Ollama opens a local model picker", Style::default().fg(t().text)),
-            ]),
-            Line::from(vec![
-                Span::styled("SHELL      ", Style::default().fg(t().accent2).bold()),
-                Span::styled("prefix with ! to execute locally: !ls, !git status, !curl ...", Style::default().fg(t().text)),
-            ]),
-            Line::from(vec![
-                Span::styled("VIDEO CHAT ", Style::default().fg(t().accent2).bold()),
-                Span::styled("/server <port>, /connect ws://<addr>, /chat <msg>", Style::default().fg(t().text)),
-            ]),
-            Line::from(vec![
-                Span::styled("VIDEO BUS  ", Style::default().fg(t().accent2).bold()),
-                Span::styled("/video toggles the panel, /youtube <url> streams YouTube into it", Style::default().fg(t().text)),
-            ]),
-            Line::from(vec![
-                Span::styled("WEBCAM     ", Style::default().fg(t().accent2).bold()),
-                Span::styled("/webcam or F5 to toggle live ASCII webcam feed", Style::default().fg(t().text)),
-            ]),
-            Line::from(vec![
-                Span::styled("3D EFFECTS ", Style::default().fg(t().accent2).bold()),
-                Span::styled("/3d toggles, /fx or F4 cycle matrix -> plasma -> starfield -> wireframe -> fire -> particles -> off", Style::default().fg(t().text)),
-            ]),
-            Line::from(vec![
-                Span::styled("ANALYTICS  ", Style::default().fg(t().accent2).bold()),
-                Span::styled("/analytics opens the live conversation stats dashboard", Style::default().fg(t().text)),
-            ]),
-            Line::from(vec![
-                Span::styled("GAMES      ", Style::default().fg(t().accent2).bold()),
-                Span::styled("/games loads the arcade panel; 1-3 launches Pac-Man, Space Invaders, or 3D Penguin", Style::default().fg(t().text)),
-            ]),
-            Line::from(vec![
-                Span::styled("TILES      ", Style::default().fg(t().accent2).bold()),
-                Span::styled("/tiles or /tiles <1-8> boots real PTY terminals inside a focused tile; Ctrl+j/k cycle inner terminals, Ctrl+h/l move app focus", Style::default().fg(t().text)),
-            ]),
-            Line::from(vec![
-                Span::styled("SHORTCUTS  ", Style::default().fg(t().accent2).bold()),
-                Span::styled("/curl, /brew, /provider, /ollama, /video, /youtube, /clear, /help, /username, /games, /tiles", Style::default().fg(t().text)),
-            ]),
-            Line::from(""),
-            Line::from(Span::styled("Keyboard", Style::default().fg(t().accent4).bold())),
-            Line::from("  F1       toggle this overlay"),
-            Line::from("  F2       cycle AI provider (Claude, Grok, GPT-5, Gemini, Ollama)"),
-            Line::from("  F3       toggle live video panel"),
-            Line::from("  F4       cycle 3D effects, then off, then repeat"),
-            Line::from("  F5       toggle webcam capture"),
-            Line::from("  F6       cycle tiling layout preset"),
-            Line::from("  F7       boot/focus the Tiles PTY panel"),
-            Line::from("  F8       cycle focused tile panel type"),
-            Line::from("  F9       randomize color theme"),
-            Line::from("  F10      reset theme to defaults"),
-            Line::from("  Ctrl+L   clear transcript"),
-            Line::from("  PgUp/Dn  scroll transcript"),
-            Line::from("  Esc      exit"),
-            Line::from(""),
-            Line::from(Span::styled("Tiling (Hyprland-style)", Style::default().fg(t().accent4).bold())),
-            Line::from("  Ctrl+h/l  focus tile left/right"),
-            Line::from("  Ctrl+j/k  focus tile down/up"),
-            Line::from("  Ctrl+H/L  swap tile left/right (shift)"),
-            Line::from("  Ctrl+J/K  swap tile down/up (shift)"),
-            Line::from("  Ctrl+[/]  resize focused split narrower/wider"),
-            Line::from("  Ctrl+n    cycle focused tile to next panel type"),
-            Line::from("  /layout  cycle layout (default, dual, triple, quad, webcam, focus)"),
-            Line::from("  Games     focus arcade tile and use 1-3 / WASD / Esc / R"),
-            Line::from("  Tiles     focus PTY tile and type directly; Ctrl+j/k cycle inner terminals, Ctrl+h/l move app focus"),
-            Line::from(""),
-            Line::from(Span::styled("Modules", Style::default().fg(t().accent4).bold())),
-            Line::from("  AI Chat: Claude 4.5, Grok 4, GPT-5, Gemini 3 Flash, local Ollama models"),
-            Line::from("  Video: MP4 ASCII playback | Webcam: Live camera ASCII feed"),
-            Line::from("  Video Chat: WebSocket multi-user streaming"),
-            Line::from("  3D FX: matrix, plasma, starfield, wireframe, fire, particles"),
-            Line::from("  Games: Pac-Man, Space Invaders, 3D Penguin"),
-            Line::from("  Tiles: 1-8 live PTY terminals for codex / claude / gemini / shell work"),
-            Line::from("  Sys Monitor: CPU, memory, swap, network, load average"),
-            Line::from("  Analytics: Real-time conversation statistics"),
-            Line::from("  Shell: Full bash, curl, brew integration"),
-        ]);
+            Span::styled("selection: ", t().label_style()),
+            Span::styled(
+                if self.model_selection_input.is_empty() {
+                    "_".to_string()
+                } else {
+                    self.model_selection_input.clone()
+                },
+                Style::default().fg(self.provider.color()),
+            ),
+        ]));
 
         frame.render_widget(
-            Paragraph::new(text)
+            Paragraph::new(Text::from(lines))
                 .wrap(Wrap { trim: false })
-                .style(Style::default().fg(t().text).bg(t().panel_bg)),
-            popup.inner(Margin {
-                horizontal: 2,
-                vertical: 1,
-            }),
+                .scroll((self.model_picker_scroll as u16, 0))
+                .style(t().panel_style()),
+            inner,
         );
     }
+
+}
+
+/// `842`, `12.4k`, `1.3M`
+fn format_tokens(tokens: u32) -> String {
+    match tokens {
+        0..=999 => tokens.to_string(),
+        1_000..=999_999 => format!("{:.1}k", tokens as f32 / 1_000.0),
+        _ => format!("{:.1}M", tokens as f32 / 1_000_000.0),
+    }
+}
+
+fn drift_effect(drift: Drift) -> Effect {
+    let timer = (drift.period_ms, Interpolation::SineInOut);
+    fx::repeating(fx::ping_pong(fx::hsl_shift(Some([drift.hue, 0.0, 0.0]), None, timer)))
+}
+
+/// Rows `lines` take up when wrapped to `width` columns.
+/// `Ctrl+T` line: when the message was written and, for replies, how it was served.
+fn message_meta_label(message: &ChatMessage) -> String {
+    let format = if message.timestamp.date_naive() == Local::now().date_naive() {
+        "%H:%M:%S"
+    } else {
+        "%Y-%m-%d %H:%M"
+    };
+    let mut parts = vec![message.timestamp.format(format).to_string()];
+    if let Some(meta) = &message.meta {
+        if !meta.model.is_empty() {
+            parts.push(meta.model.clone());
+        }
+        if !meta.usage.is_empty() {
+            parts.push(format!(
+                "{} in / {} out tok",
+                meta.usage.input_tokens, meta.usage.output_tokens
+            ));
+        }
+        let usage = meta.usage;
+        if usage.cache_read_tokens + usage.cache_write_tokens > 0 {
+            let prompt = usage.input_tokens + usage.cache_read_tokens + usage.cache_write_tokens;
+            parts.push(format!(
+                "cache {} hit ({:.0}%) / {} written",
+                usage.cache_read_tokens,
+                usage.cache_read_tokens as f32 * 100.0 / prompt as f32,
+                usage.cache_write_tokens
+            ));
+        }
+        parts.push(format!("{:.2}s", meta.latency_ms as f32 / 1000.0));
+        if let Some(cost) = meta.cost {
+            parts.push(format_cost(cost));
+        }
+        if let Some(status) = meta.http_status.filter(|status| *status != 200) {
+            parts.push(format!("http {}", status));
+        }
+        if let Some(reason) = &meta.finish_reason {
+            parts.push(format!("stop {}", reason));
+        }
+    }
+    parts.join(" // ")
+}
+
+/// Request metadata for a finished `/compare` pane.
+fn pane_meta(prices: &PriceTable, pane: &ComparePane) -> ReplyMeta {
+    let model = pane.client.model_label().to_string();
+    ReplyMeta {
+        cost: prices.lookup(&model).map(|price| price.cost(pane.usage)),
+        model,
+        latency_ms: pane.latency.unwrap_or_default().as_millis() as u64,
+        http_status: pane.http_status,
+        usage: pane.usage,
+        finish_reason: pane.finish_reason.clone(),
+    }
+}
+
+/// Rows `lines` take once word-wrapped to `width`, as the transcript renders them.
+fn wrapped_height(lines: &[Line], width: u16) -> usize {
+    Paragraph::new(Text::from(lines.to_vec()))
+        .wrap(Wrap { trim: false })
+        .line_count(width)
 }
 
 fn parse_shell_command(input: &str) -> Option<&str> {
@@ -3181,17 +6956,29 @@ fn parse_shell_command(input: &str) -> Option<&str> {
     }
 }
 
-fn resolve_video_path(background: Option<String>, intro: Option<String>) -> Option<PathBuf> {
-    let mut candidates = Vec::new();
-    if let Some(path) = background {
-        candidates.push(PathBuf::from(path));
-    }
-    if let Some(path) = intro {
-        candidates.push(PathBuf::from(path));
+/// First existing video among `requested` and the standard locations: the
+/// working directory, next to the binary, then the user and system data dirs.
+fn resolve_video_path(requested: &[PathBuf]) -> Option<PathBuf> {
+    let mut dirs = vec![PathBuf::from("."), PathBuf::from("demo-videos")];
+    if let Some(exe_dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        dirs.push(exe_dir.join("demo-videos"));
+        dirs.push(exe_dir);
     }
-    candidates.push(PathBuf::from("demo-videos/demo.mp4"));
-
-    candidates.into_iter().find(|path| Path::new(path).exists())
+    dirs.extend(paths::data_dir().ok());
+    dirs.push(PathBuf::from("/usr/local/share/asciivision"));
+    dirs.push(PathBuf::from("/usr/share/asciivision"));
+
+    let fallbacks = dirs
+        .into_iter()
+        .flat_map(|dir| [dir.join("loading.mp4"), dir.join("demo.mp4")]);
+    requested
+        .iter()
+        .cloned()
+        .chain(fallbacks)
+        .find(|path| path.is_file())
 }
 
 #[derive(Deserialize)]
@@ -3284,86 +7071,6 @@ fn ytdlp_spawn_error(error: std::io::Error) -> anyhow::Error {
     }
 }
 
-fn render_ascii_frame(buffer: &mut Buffer, area: Rect, ascii: &video::AsciiFrame, intensity: f32) {
-    if area.width == 0 || area.height == 0 || ascii.width == 0 || ascii.height == 0 {
-        return;
-    }
-
-    // If source matches destination closely, render directly (fast path)
-    let needs_scaling =
-        ascii.width.abs_diff(area.width) > 2 || ascii.height.abs_diff(area.height) > 2;
-
-    if !needs_scaling {
-        let content_width = std::cmp::min(ascii.width, area.width);
-        let content_height = std::cmp::min(ascii.height, area.height);
-        let offset_x = area.x + (area.width - content_width) / 2;
-        let offset_y = area.y + (area.height - content_height) / 2;
-
-        for y in 0..content_height {
-            for x in 0..content_width {
-                let index = y as usize * ascii.width as usize + x as usize;
-                if index >= ascii.cells.len() {
-                    break;
-                }
-                let (glyph, r, g, b) = ascii.cells[index];
-                let scanline = if y % 2 == 0 { 0.84 } else { 1.0 };
-                let factor = (intensity * scanline).clamp(0.1, 1.2);
-                let fg = scale_rgb(r, g, b, factor);
-                let bg = scale_rgb(r, g, b, factor * 0.16);
-
-                if let Some(cell) = buffer.cell_mut((offset_x + x, offset_y + y)) {
-                    cell.set_char(glyph);
-                    cell.set_fg(fg);
-                    cell.set_bg(bg);
-                }
-            }
-        }
-        return;
-    }
-
-    // Scaled path: nearest-neighbor sampling with aspect-ratio-preserving letterbox.
-    // Both source and destination are in terminal-cell coordinates (where each cell
-    // is ~2x taller than wide), so we compare ratios directly -- no extra correction
-    // needed here since the webcam capture already accounts for cell aspect ratio.
-    let src_ratio = ascii.width as f32 / ascii.height as f32;
-    let dst_ratio = area.width as f32 / area.height as f32;
-
-    let (fit_w, fit_h) = if src_ratio > dst_ratio {
-        let h = (area.width as f32 / src_ratio).round().max(1.0) as u16;
-        (area.width, h.min(area.height))
-    } else {
-        let w = (area.height as f32 * src_ratio).round().max(1.0) as u16;
-        (w.min(area.width), area.height)
-    };
-
-    let offset_x = area.x + (area.width.saturating_sub(fit_w)) / 2;
-    let offset_y = area.y + (area.height.saturating_sub(fit_h)) / 2;
-
-    for y in 0..fit_h {
-        let src_y = ((y as f32 * ascii.height as f32 / fit_h as f32) as usize)
-            .min(ascii.height as usize - 1);
-        for x in 0..fit_w {
-            let src_x = ((x as f32 * ascii.width as f32 / fit_w as f32) as usize)
-                .min(ascii.width as usize - 1);
-            let index = src_y * ascii.width as usize + src_x;
-            if index >= ascii.cells.len() {
-                continue;
-            }
-            let (glyph, r, g, b) = ascii.cells[index];
-            let scanline = if y % 2 == 0 { 0.84 } else { 1.0 };
-            let factor = (intensity * scanline).clamp(0.1, 1.2);
-            let fg = scale_rgb(r, g, b, factor);
-            let bg = scale_rgb(r, g, b, factor * 0.16);
-
-            if let Some(cell) = buffer.cell_mut((offset_x + x, offset_y + y)) {
-                cell.set_char(glyph);
-                cell.set_fg(fg);
-                cell.set_bg(bg);
-            }
-        }
-    }
-}
-
 fn render_background(buffer: &mut Buffer, area: Rect, phase: f32) {
     for y in area.y..area.y + area.height {
         let band = (((y as f32 * 0.23) + phase * 1.6).sin() * 0.5 + 0.5) * 0.26;
@@ -3482,19 +7189,19 @@ fn render_gradient_text(buffer: &mut Buffer, x: u16, y: u16, text: &str, start:
         return;
     }
     let max_x = buf_area.x + buf_area.width;
-    let length = text.chars().count().max(1);
-    for (index, glyph) in text.chars().enumerate() {
-        let px = x.saturating_add(index as u16);
-        if px >= max_x {
+    let length = text::width(text).max(1);
+    let mut column = 0;
+    for grapheme in text::graphemes(text) {
+        let px = x.saturating_add(column as u16);
+        let width = text::width(grapheme);
+        if px as usize + width > max_x as usize {
             break;
         }
-        if px < buf_area.x {
-            continue;
-        }
-        if let Some(cell) = buffer.cell_mut((px, y)) {
-            cell.set_char(glyph);
-            cell.set_fg(mix_color(start, end, index as f32 / length as f32));
+        if px >= buf_area.x && width > 0 {
+            let color = mix_color(start, end, column as f32 / length as f32);
+            buffer.set_string(px, y, grapheme, Style::default().fg(color));
         }
+        column += width;
     }
 }
 
@@ -3543,6 +7250,71 @@ fn render_synthetic_scope(buffer: &mut Buffer, area: Rect, phase: f32) {
     }
 }
 
+/// Intro stand-in when no loading video is available: a plasma field with a
+/// boot log typing itself out over it.
+fn render_boot_animation(buffer: &mut Buffer, area: Rect, phase: f32) {
+    const RAMP: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+    const BOOT_LOG: &[&str] = &[
+        "video bus ........ no signal, synthesizing",
+        "provider uplink .. ok",
+        "sqlite archive ... ok",
+        "tiling wm ........ ok",
+        "fx engine ........ ok",
+        "handoff -> command deck",
+    ];
+    if area.width < 4 || area.height < 4 {
+        return;
+    }
+    let inner = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width - 2,
+        height: area.height - 2,
+    };
+    for y in inner.y..inner.y + inner.height {
+        for x in inner.x..inner.x + inner.width {
+            let fx = x as f32 * 0.11;
+            let fy = y as f32 * 0.23;
+            let value = ((fx + phase * 1.3).sin()
+                + (fy - phase * 0.9).sin()
+                + ((fx + fy) * 0.7 + phase).sin()
+                + ((fx * fx + fy * fy).sqrt() * 0.9 - phase * 1.7).sin())
+                / 8.0
+                + 0.5;
+            let level = (value.clamp(0.0, 0.999) * RAMP.len() as f32) as usize;
+            if let Some(cell) = buffer.cell_mut((x, y)) {
+                cell.set_char(RAMP[level]);
+                cell.set_fg(mix_color(t().bg_alt, t().accent3, value));
+            }
+        }
+    }
+
+    let shell = Block::default()
+        .title(" PROCEDURAL BOOT // NO VIDEO SIGNAL ")
+        .title_style(t().label_style())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(t().accent3));
+    shell.render(area, buffer);
+
+    let shown = ((phase * 1.6) as usize + 1).min(BOOT_LOG.len());
+    let top = (inner.y + inner.height)
+        .saturating_sub(shown as u16 + 1)
+        .max(inner.y);
+    for (row, line) in BOOT_LOG.iter().take(shown).enumerate() {
+        if top + row as u16 >= inner.y + inner.height {
+            break;
+        }
+        let text = if row + 1 == shown && shown < BOOT_LOG.len() {
+            format!("> {} {}", line, current_spinner(phase))
+        } else {
+            format!("> {}", line)
+        };
+        let width = inner.width.saturating_sub(2) as usize;
+        let text = text::clip(&text, width);
+        render_gradient_text(buffer, inner.x + 1, top + row as u16, text, t().accent4, t().text);
+    }
+}
+
 fn render_scroller(buffer: &mut Buffer, area: Rect, text: &str, phase: f32, accent: Color) {
     if area.width == 0 || area.height == 0 {
         return;
@@ -3576,6 +7348,74 @@ fn current_spinner(phase: f32) -> &'static str {
     FRAMES[((phase * 8.0) as usize) % FRAMES.len()]
 }
 
+/// Full-screen view of one message with wrapping, opened from select mode.
+fn render_message_detail(frame: &mut Frame, area: Rect, message: &ChatMessage, scroll: u16) {
+    let popup = area.inner(Margin {
+        horizontal: 2,
+        vertical: 1,
+    });
+    frame.render_widget(Clear, popup);
+    let title = format!(" {} // {} chars ", message.label, message.content.chars().count());
+    let block = t()
+        .block(title, message.accent, message.accent)
+        .title_bottom(" Up/Dn PgUp/PgDn scroll  c copy  Esc close ");
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+
+    frame.render_widget(
+        Paragraph::new(message.content.as_str())
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0))
+            .style(t().text_style().bg(t().panel_bg)),
+        inner.inner(Margin {
+            horizontal: 1,
+            vertical: 0,
+        }),
+    );
+}
+
+/// The Tab completion list, floating just above the input box.
+fn render_completion(frame: &mut Frame, input_area: Rect, completion: &Completion) {
+    const VISIBLE: usize = 8;
+    let count = completion.candidates.len();
+    let rows = count.min(VISIBLE);
+    let widest = completion.candidates.iter().map(|c| text::width(c)).max().unwrap_or(0);
+    let width = (widest as u16 + 4).max(16);
+    let width = width.min(input_area.width.saturating_sub(2));
+    let height = (rows as u16 + 2).min(input_area.y);
+    if height < 3 {
+        return;
+    }
+    let popup = Rect {
+        x: input_area.x + 1,
+        y: input_area.y - height,
+        width,
+        height,
+    };
+    frame.render_widget(Clear, popup);
+    let title = format!(" {}/{} ", completion.selected + 1, count);
+    frame.render_widget(t().block(title, t().accent4, t().accent3), popup);
+
+    let first = completion.selected.saturating_sub(rows - 1);
+    let lines: Vec<Line> = completion.candidates[first..first + rows]
+        .iter()
+        .enumerate()
+        .map(|(offset, candidate)| {
+            let style = if first + offset == completion.selected {
+                Style::default().fg(t().accent4).bold().reversed()
+            } else {
+                t().text_style()
+            };
+            Line::from(Span::styled(candidate.as_str(), style))
+        })
+        .collect();
+    let inner = popup.inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    frame.render_widget(Paragraph::new(lines).style(t().panel_style()), inner);
+}
+
 fn centered_area(area: Rect, width_percent: u16, height_percent: u16) -> Rect {
     let vertical = Layout::default()
         .direction(Direction::Vertical)
@@ -3618,14 +7458,6 @@ fn to_rgb(color: Color) -> (u8, u8, u8) {
     }
 }
 
-fn scale_rgb(r: u8, g: u8, b: u8, factor: f32) -> Color {
-    Color::Rgb(
-        (r as f32 * factor).clamp(0.0, 255.0) as u8,
-        (g as f32 * factor).clamp(0.0, 255.0) as u8,
-        (b as f32 * factor).clamp(0.0, 255.0) as u8,
-    )
-}
-
 fn format_ollama_model_meta(model: &OllamaModelInfo) -> String {
     let mut parts = Vec::new();
     if model.is_cloud {
@@ -3650,6 +7482,27 @@ fn format_ollama_model_meta(model: &OllamaModelInfo) -> String {
     }
 }
 
+fn format_openrouter_model_meta(model: &OpenRouterModelInfo) -> String {
+    let mut parts = Vec::new();
+    if let Some(context) = model.context_length {
+        parts.push(format!("{}k ctx", context / 1000));
+    }
+    match (model.prompt_price, model.completion_price) {
+        (Some(prompt), Some(completion)) if prompt == 0.0 && completion == 0.0 => {
+            parts.push("free".to_string());
+        }
+        (Some(prompt), Some(completion)) => {
+            parts.push(format!("${:.2}/${:.2} per M", prompt, completion));
+        }
+        _ => {}
+    }
+    if parts.is_empty() {
+        "model".to_string()
+    } else {
+        parts.join(" | ")
+    }
+}
+
 fn human_bytes(bytes: u64) -> String {
     const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
     let mut value = bytes as f64;
@@ -3665,19 +7518,6 @@ fn human_bytes(bytes: u64) -> String {
     }
 }
 
-fn truncate(value: &str, max_chars: usize) -> String {
-    if value.chars().count() <= max_chars {
-        value.to_string()
-    } else {
-        let mut result = value
-            .chars()
-            .take(max_chars.saturating_sub(1))
-            .collect::<String>();
-        result.push('\u{2026}');
-        result
-    }
-}
-
 fn hash32(x: u16, y: u16, seed: u32) -> u32 {
     let mut value = x as u32;
     value = value.wrapping_mul(0x45d9f3b);
@@ -3688,10 +7528,14 @@ fn hash32(x: u16, y: u16, seed: u32) -> u32 {
     value ^ (value >> 16)
 }
 
-async fn run_app(
-    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
-    args: Args,
-) -> Result<()> {
+/// Builds the chat deck from its flags, starting the video chat server and
+/// attaching piped stdin, for `run_app` and `run_plain`.
+fn start_app(
+    args: ChatArgs,
+    config: &Config,
+    stdin: Option<Attachment>,
+    network_error: Option<String>,
+) -> Result<App> {
     if let Some(port) = args.serve {
         let addr = format!("0.0.0.0:{}", port);
         let server = Arc::new(VideoChatServer::new());
@@ -3699,14 +7543,26 @@ async fn run_app(
         let addr_clone = addr.clone();
         tokio::spawn(async move {
             if let Err(e) = server_clone.run(&addr_clone).await {
-                eprintln!("server error: {}", e);
+                tracing::error!("video chat server on {} stopped: {}", addr_clone, e);
             }
         });
     }
 
     let connect_url = args.connect.clone();
     let username = args.username.clone();
-    let mut app = App::new(args)?;
+    let plain = args.plain;
+    let mut app = App::new(args, config)?;
+    metrics::record(Metric::Session { plain });
+    if let Some(error) = network_error {
+        app.add_system_message(error);
+    }
+    if let Some(attachment) = stdin {
+        app.add_system_message(format!(
+            "piped stdin attached to your first prompt: {}",
+            attachment.chip()
+        ));
+        app.attachments.push(attachment);
+    }
 
     if let Some(url) = connect_url {
         let client = VideoChatClient::new(username.clone(), url.clone());
@@ -3717,30 +7573,191 @@ async fn run_app(
             url, username, url
         ));
     }
+    Ok(app)
+}
+
+async fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    args: ChatArgs,
+    config: &Config,
+    stdin: Option<Attachment>,
+    network_error: Option<String>,
+) -> Result<()> {
+    let smoke = args.smoke;
+    let mut app = start_app(args, config, stdin, network_error)?;
+    // smoke runs leave the real draft alone
+    let drafts = smoke.is_none();
+    if drafts {
+        app.offer_draft();
+    }
 
+    let mut drawn = 0;
     loop {
-        if app.handle_input()? {
+        let quit = match smoke {
+            Some(frames) => drawn >= frames,
+            None => app.handle_input()?,
+        };
+        if quit {
+            if drafts {
+                app.autosave_draft(true);
+            }
+            app.commit_pending_delete();
+            app.save_key_uses();
+            app.record_video();
+            app.save_metrics();
+            if let Some(archiver) = app.archiver.take() {
+                archiver.finish();
+            }
             break;
         }
         app.tick();
+        if drafts {
+            app.autosave_draft(false);
+        }
         // detect mode transitions (intro->chat) and force full terminal redraw
         if app.mode != app.prev_mode {
             app.prev_mode = app.mode.clone();
-            // 1) physically clear the terminal screen via raw ANSI
-            execute!(
-                terminal.backend_mut(),
-                crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
-                crossterm::cursor::MoveTo(0, 0)
-            )?;
+            // 1) physically clear the terminal screen
+            terminal.backend_mut().clear()?;
+            terminal.backend_mut().set_cursor_position(Position::ORIGIN)?;
             // 2) reset ratatui's back buffer so next draw() diffs against blank
             terminal.clear()?;
             // 3) immediately draw the new mode's first frame
             terminal.draw(|frame| app.render(frame))?;
         }
         terminal.draw(|frame| app.render(frame))?;
-        tokio::time::sleep(Duration::from_millis(16)).await;
+        drawn += 1;
+        next_frame(app.terminal_focused, Duration::from_millis(16)).await?;
+    }
+
+    Ok(())
+}
+
+/// What `/help` prints in `--plain` mode.
+const PLAIN_HELP: &str = "\
+type a prompt and press Enter to send it to the active provider
+!<command> runs a shell command, /<command> a deck command, for example
+/provider <name>, /retry, /edit, /attach <file>, /web, /rag, /bookmarks, /export,
+/compare <a> <b>, /clear and /archive
+when a tool needs approval, press Enter or type y to run it, n to reject it
+/quit or Ctrl+D leaves, after the reply in flight has been printed";
+
+/// `--plain`: the chat deck for screen readers and other assistive technology.
+/// Nothing is drawn; prompts and commands are read a line at a time from
+/// stdin, every finished message is printed once as `LABEL: text`, and status
+/// changes are announced on their own `status:` lines.
+async fn run_plain(args: ChatArgs, config: &Config, network_error: Option<String>) -> Result<()> {
+    let args = ChatArgs {
+        plain: true,
+        skip_intro: true,
+        no_video: true,
+        effects: false,
+        ..args
+    };
+    let mut app = start_app(args, config, None, network_error)?;
+
+    let (lines_tx, mut lines_rx) = mpsc::unbounded_channel::<String>();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines().map_while(|line| line.ok()) {
+            if lines_tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    // without raw mode Ctrl+C is a signal; catch it so the archive is flushed
+    let (interrupt_tx, mut interrupt) = tokio::sync::oneshot::channel::<()>();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            let _ = interrupt_tx.send(());
+        }
+    });
+
+    println!("asciivision plain mode // {} // /help lists commands", app.provider.name());
+    // lines typed while a reply is in flight wait for it
+    let mut queued: VecDeque<String> = VecDeque::new();
+    let mut printed = 0;
+    let mut status = app.status_note.clone();
+    let mut approval = false;
+    let mut eof = false;
+    loop {
+        tokio::select! {
+            line = lines_rx.recv(), if !eof => match line {
+                Some(line) => queued.push_back(line.trim().to_string()),
+                None => eof = true,
+            },
+            _ = &mut interrupt => break,
+            _ = tokio::time::sleep(Duration::from_millis(16)) => {}
+        }
+
+        let busy = app.pending_ai
+            || app.pending_shells > 0
+            || !app.reveal_queue.is_empty()
+            || app.compare.as_ref().is_some_and(CompareState::busy);
+        if app.pending_approval.is_some() {
+            if let Some(line) = queued.pop_front() {
+                match line.as_str() {
+                    "" | "y" | "yes" => app.approve_pending(),
+                    "n" | "no" => app.reject_pending(),
+                    _ => println!("status: press Enter or type y to approve, n to reject"),
+                }
+            }
+        } else if !busy {
+            if let Some(line) = queued.pop_front() {
+                if line == "/quit" || line == "/exit" {
+                    break;
+                } else if line == "/help" {
+                    println!("{}", PLAIN_HELP);
+                } else if !line.is_empty() {
+                    app.dispatch_input(line);
+                }
+            }
+        }
+
+        app.tick();
+        // replies are printed whole, so skip the typing animation
+        while let Some(job) = app.reveal_queue.pop_front() {
+            if let Some(message) = app.messages.get_mut(job.message_index) {
+                message.content = job.full_text.into_iter().collect();
+            }
+        }
+
+        printed = printed.min(app.messages.len());
+        while let Some(message) = app.messages.get(printed) {
+            if app.pending_ai && app.stream_message_index == Some(printed) {
+                break;
+            }
+            // rules like the restored-history markers read as noise
+            let content = message.content.trim_matches(|c| c == '─' || c == ' ');
+            println!("{}: {}", message.label, content);
+            printed += 1;
+        }
+        if app.status_note != status {
+            status = app.status_note.clone();
+            println!("status: {}", status);
+        }
+        if app.pending_approval.is_some() != approval {
+            approval = app.pending_approval.is_some();
+            if approval {
+                println!("status: tool approval needed, press Enter or type y to run, n to reject");
+            }
+        }
+
+        let idle = !app.pending_ai
+            && app.pending_shells == 0
+            && app.pending_approval.is_none()
+            && !app.compare.as_ref().is_some_and(CompareState::busy);
+        if app.mode == AppMode::Exit || (eof && queued.is_empty() && idle) {
+            break;
+        }
     }
 
+    app.commit_pending_delete();
+    app.save_key_uses();
+    app.save_metrics();
+    if let Some(archiver) = app.archiver.take() {
+        archiver.finish();
+    }
     Ok(())
 }
 
@@ -3749,20 +7766,304 @@ async fn main() -> Result<()> {
     let _ = dotenvy::dotenv();
     let _ = dotenvy::from_filename("archive/mega-cli/.env");
 
-    let args = Args::parse();
+    let cli = Cli::parse();
+    let mut config = Config::load(&cli.set);
+    config.reduce_motion |= cli.no_effects;
+    let diagnose = cli.diagnose;
+    let command = cli.command.unwrap_or(Command::Chat(cli.chat));
+    logging::init(&config.logging);
+    theme::set_color_vision(config.color_vision);
+    metrics::enable(config.metrics);
+    theme::set_color_overrides(config.colors.clone());
+    if config.theme != ThemeName::Default {
+        theme::set_theme(config.theme);
+    }
+    let network_error = net::init(&config.network)
+        .err()
+        .map(|error| format!("[network] settings ignored: {:#}", error));
+    if let Some(error) = network_error.as_ref().filter(|_| !matches!(command, Command::Chat(_))) {
+        eprintln!("warning: {}", error);
+    }
+    if diagnose {
+        return diagnose::run(&config);
+    }
+    if let Command::Index(args) = &command {
+        return rag::index(&args.dir, &config).await;
+    }
+    if let Command::IndexHistory = &command {
+        return rag::index_history(&config).await;
+    }
+    if let Command::Keys { action } = &command {
+        let providers = ProviderRegistry::new(config.custom_providers());
+        return match action {
+            KeysAction::Set { provider } => keys::set(provider, &providers),
+            KeysAction::Delete { provider } => keys::delete(provider, &providers),
+        };
+    }
+    if let Command::Db { action } = &command {
+        return db_command(action, &config).await;
+    }
+    if let Command::Schedule(args) = &command {
+        return match (args.list, args.remove, args.cron.as_deref()) {
+            (true, _, _) => schedule::list(&config),
+            (_, Some(id), _) => schedule::remove(id, &config),
+            (_, None, cron) => schedule::add(
+                &args.prompt.join(" "),
+                cron.unwrap_or_default(),
+                args.provider.as_deref(),
+                args.model.as_deref(),
+                &config,
+            ),
+        };
+    }
+    if let Command::Daemon = &command {
+        return schedule::daemon(&config).await;
+    }
+    // plain mode reads its prompts from stdin as they are typed
+    let command = match command {
+        Command::Chat(args) if args.plain || (config.plain && args.smoke.is_none()) => {
+            return run_plain(args, &config, network_error).await;
+        }
+        command => command,
+    };
+    // Piped input has to be drained before raw mode; crossterm then reads keys
+    // from /dev/tty.
+    let stdin = match &command {
+        Command::Chat(args) if args.smoke.is_none() => ask::read_piped_stdin(),
+        Command::Ask(_) => ask::read_piped_stdin(),
+        _ => None,
+    };
+    if let Command::Ask(args) = &command {
+        let prompt = args.prompt.join(" ");
+        let provider = args.provider.as_deref();
+        let model = args.model.clone();
+        return ask::run(&prompt, provider, model, stdin, args.json, &config).await;
+    }
+
+    let smoke = match &command {
+        Command::Chat(args) => args.smoke.map(|frames| ("chat", frames)),
+        Command::Play(args) => args.headless.then_some(("play", args.frames)),
+        Command::Analytics(args) => args.smoke.map(|frames| ("analytics", frames)),
+        _ => None,
+    };
+    if let Some((name, frames)) = smoke {
+        let mut terminal = Terminal::new(TestBackend::new(SMOKE_WIDTH, SMOKE_HEIGHT))?;
+        let started = Instant::now();
+        run_tui(&mut terminal, command, &config, stdin, network_error).await?;
+        println!(
+            "{}: drew {} frames off-screen in {:.1}s",
+            name,
+            frames,
+            started.elapsed().as_secs_f32()
+        );
+        return Ok(());
+    }
+
+    asciivision_terminal::install_panic_hook();
+    let inline = match &command {
+        Command::Play(args) => args.inline,
+        _ => None,
+    };
+    let (mut guard, mut terminal) = setup_terminal(inline, &config)?;
+    let result = run_tui(&mut terminal, command, &config, stdin, network_error).await;
+    if inline.is_some() {
+        // leave the cursor on the viewport's last row so the frame scrolls up
+        // into the history instead of being drawn over by the shell prompt
+        let bottom = terminal.get_frame().area().bottom().saturating_sub(1);
+        terminal.set_cursor_position(Position::new(0, bottom))?;
+    }
+    guard.restore()?;
+    if inline.is_some() {
+        println!();
+    }
+
+    result
+}
+
+/// Runs the chat deck, `play` or `analytics` on `terminal`.
+async fn run_tui<B: Backend>(
+    terminal: &mut Terminal<B>,
+    command: Command,
+    config: &Config,
+    stdin: Option<Attachment>,
+    network_error: Option<String>,
+) -> Result<()> {
+    match command {
+        Command::Chat(args) => run_app(terminal, args, config, stdin, network_error).await,
+        Command::Play(args) => {
+            let frames = args.headless.then_some(args.frames);
+            let result = player::run(terminal, config, &args.file, args.looping, frames).await;
+            metrics::flush(config);
+            result
+        }
+        Command::Analytics(args) => dashboard::run(terminal, config, args.db, args.smoke).await,
+        Command::Index(_)
+        | Command::IndexHistory
+        | Command::Ask(_)
+        | Command::Keys { .. }
+        | Command::Db { .. }
+        | Command::Schedule(_)
+        | Command::Daemon => {
+            unreachable!("index, ask, keys, db, schedule and daemon run before the terminal is set up")
+        }
+    }
+}
+
+async fn db_command(action: &DbAction, config: &Config) -> Result<()> {
+    let db = config.open_database()?;
+    if let Some(backup) = db.migration_backup() {
+        eprintln!("upgraded the archive's schema; previous version saved as {}", backup.display());
+    }
+    match action {
+        DbAction::Export { path } => {
+            let archive = db.export_archive()?;
+            let messages: usize = archive.conversations.iter().map(|c| c.messages.len()).sum();
+            std::fs::write(path, serde_json::to_vec_pretty(&archive)?)
+                .with_context(|| format!("writing {}", path.display()))?;
+            println!(
+                "exported {} conversations ({} messages) to {}",
+                archive.conversations.len(),
+                messages,
+                path.display()
+            );
+        }
+        DbAction::Import { path } => {
+            let bytes =
+                std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+            let archive = serde_json::from_slice(&bytes)
+                .with_context(|| format!("parsing {}", path.display()))?;
+            println!("backed up to {}", db.snapshot("import")?.display());
+            let summary = db.import_archive(&archive, false)?;
+            println!(
+                "imported {} conversations ({} messages), skipped {} already present",
+                summary.conversations, summary.messages, summary.skipped
+            );
+        }
+        DbAction::Sync => println!("{}", sync::run(&config.sync, &db).await?.summary()),
+        DbAction::Purge { archived } => {
+            println!("backed up to {}", db.snapshot("purge")?.display());
+            let (messages, conversations) = db.purge(*archived)?;
+            if *archived {
+                println!(
+                    "purged {} messages and {} archived conversations",
+                    messages, conversations
+                );
+            } else {
+                println!("purged {} deleted messages", messages);
+            }
+        }
+        DbAction::Stats => {
+            let stats = db.stats()?;
+            let day = |timestamp: Option<i64>| {
+                timestamp
+                    .and_then(|timestamp| Local.timestamp_opt(timestamp, 0).single())
+                    .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "-".to_string())
+            };
+            if let Some(path) = &stats.path {
+                println!("file           {}", path.display());
+            }
+            println!(
+                "size           {} (+{} wal, {} free), schema v{}",
+                human_bytes(stats.bytes),
+                human_bytes(stats.wal_bytes),
+                human_bytes(stats.free_bytes),
+                stats.schema_version
+            );
+            println!(
+                "holds          {} messages in {} conversations, {} bookmarked",
+                stats.messages, stats.conversations, stats.bookmarks
+            );
+            println!(
+                "hidden         {} deleted messages, {} archived conversations",
+                stats.deleted, stats.archived
+            );
+            println!("spans          {} .. {}", day(stats.oldest), day(stats.newest));
+            println!("health         {}", stats.health());
+            println!();
+            for count in &stats.providers {
+                println!(
+                    "{:<14} {:>7} msgs {:>5} conversations",
+                    count.provider, count.messages, count.conversations
+                );
+            }
+            println!();
+            for count in &stats.largest {
+                println!("{:>7} msgs  {:<12} {}", count.messages, count.provider, count.title);
+            }
+        }
+        DbAction::Prune(args) => {
+            let policy = RetentionPolicy {
+                max_age_days: args.max_age_days.or(config.retention.max_age_days),
+                max_messages: args.max_messages.or(config.retention.max_messages),
+                max_size_mb: args.max_size_mb.or(config.retention.max_size_mb),
+            };
+            if policy.is_empty() {
+                println!("no retention limits set // see [retention] in config.toml");
+                return Ok(());
+            }
+            let report = db.prune(&policy, args.dry_run, true)?;
+            let through = report
+                .through
+                .and_then(|timestamp| Local.timestamp_opt(timestamp, 0).single())
+                .map(|time| format!(", through {}", time.format("%Y-%m-%d")))
+                .unwrap_or_default();
+            let (verb, size) = if args.dry_run {
+                ("would delete", format!("{} now", human_bytes(report.bytes_before)))
+            } else {
+                let size = format!(
+                    "{} -> {}",
+                    human_bytes(report.bytes_before),
+                    human_bytes(report.bytes_after)
+                );
+                ("deleted", size)
+            };
+            println!(
+                "{} {} messages and {} emptied conversations{} // {}",
+                verb, report.messages, report.conversations, through, size
+            );
+            if let Some(backup) = &report.backup {
+                println!("backed up to {}", backup.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Redraw interval of every TUI while the terminal reports it lost focus.
+const UNFOCUSED_FRAME: Duration = Duration::from_millis(250);
+
+/// Waits out one frame of a TUI loop: `frame` while the terminal has focus,
+/// otherwise `UNFOCUSED_FRAME`, cut short by the next terminal event so that
+/// focus coming back redraws at once.
+async fn next_frame(focused: bool, frame: Duration) -> Result<()> {
+    if focused {
+        tokio::time::sleep(frame).await;
+    } else {
+        tokio::task::block_in_place(|| event::poll(UNFOCUSED_FRAME))?;
+    }
+    Ok(())
+}
 
+/// Full-screen on the alternate screen, or with `inline` a viewport of that
+/// many rows below the cursor in the normal scrollback. The terminal is
+/// probed once raw mode is on so the renderers can pick their color depth and
+/// cell aspect.
+fn setup_terminal(inline: Option<u16>, config: &Config) -> Result<(TerminalGuard, Tui)> {
     // suppress ALL FFmpeg log output before anything else --
     // FFmpeg writes to stderr which corrupts the TUI display
     unsafe { ffmpeg_sys_next::av_log_set_level(ffmpeg_sys_next::AV_LOG_QUIET) };
 
-    // redirect stderr to /dev/null so nothing can corrupt the TUI
-    #[cfg(unix)]
-    {
-        use std::os::unix::io::AsRawFd;
-        if let Ok(devnull) = std::fs::File::open("/dev/null") {
-            extern "C" { fn dup2(oldfd: i32, newfd: i32) -> i32; }
-            unsafe { dup2(devnull.as_raw_fd(), 2); }
-        }
+    // send stderr to /dev/null so nothing can corrupt the TUI; the guard
+    // hands it back on restore so errors and panics are still printed
+    asciivision_terminal::silence_stderr();
+
+    if let Some(rows) = inline {
+        let guard = TerminalGuard::enter_inline()?;
+        diagnose::apply(&diagnose::detect(&config.terminal), &config.terminal);
+        let backend = CrosstermBackend::new(std::io::stdout());
+        let options = TerminalOptions { viewport: Viewport::Inline(rows) };
+        return Ok((guard, Terminal::with_options(backend, options)?));
     }
 
     // request a large terminal window before entering raw mode
@@ -3775,18 +8076,112 @@ async fn main() -> Result<()> {
         std::thread::sleep(Duration::from_millis(150));
     }
 
-    enable_raw_mode()?;
-    let mut stdout = std::io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
+    let guard = TerminalGuard::enter()?;
+    diagnose::apply(&diagnose::detect(&config.terminal), &config.terminal);
+    let backend = CrosstermBackend::new(std::io::stdout());
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
+    Ok((guard, terminal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asciivision_core::testing::buffer_text;
+    use insta::assert_snapshot;
+    use ratatui::backend::TestBackend;
+
+    /// A chat deck with no archive, video or intro and an empty transcript.
+    fn app() -> App {
+        let cli = Cli::parse_from([
+            "asciivision",
+            "--no-db",
+            "--no-video",
+            "--skip-intro",
+            "--provider",
+            "claude",
+        ]);
+        let mut app = App::new(cli.chat, &Config::default()).expect("chat deck");
+        app.messages.clear();
+        app
+    }
 
-    let result = run_app(&mut terminal, args).await;
+    fn render(width: u16, height: u16, draw: impl FnOnce(&mut Frame)) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("test terminal");
+        terminal.draw(draw).expect("draw");
+        terminal.backend().buffer().clone()
+    }
 
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
+    #[test]
+    fn transcript_layout() {
+        let mut app = app();
+        app.messages.push(ChatMessage::system("uplink established"));
+        app.messages.push(ChatMessage::user("summarize the tiling presets".to_string()));
+        let mut reply = ChatMessage::assistant(&app.provider, "");
+        reply.content = "There are five presets:\n- default\n- focus\n- grid\n- wide\n- stack\n\n\
+            F6 cycles them and /layout picks one by name."
+            .to_string();
+        app.messages.push(reply);
+        let buffer = render(60, 20, |frame| app.render_messages_tile(frame, frame.area(), true));
+        assert_snapshot!("chat_transcript", buffer_text(&buffer));
+    }
 
-    result
+    #[test]
+    fn wide_text_transcript() {
+        let mut app = app();
+        app.messages.push(ChatMessage::user("translate: the café is open 🙂".to_string()));
+        let mut reply = ChatMessage::assistant(&app.provider, "");
+        reply.content = "カフェは営業中です。日本語の文は空白なしで折り返されます 👍🏽\n\
+            Cafe\u{301} keeps its accent on one column."
+            .to_string();
+        app.messages.push(reply);
+        let buffer = render(40, 14, |frame| app.render_messages_tile(frame, frame.area(), true));
+        assert_snapshot!("chat_transcript_wide", buffer_text(&buffer));
+    }
+
+    #[test]
+    fn empty_transcript() {
+        let app = app();
+        let buffer = render(60, 6, |frame| app.render_messages_tile(frame, frame.area(), false));
+        assert_snapshot!("chat_transcript_empty", buffer_text(&buffer));
+    }
+
+    #[test]
+    fn input_with_completion() {
+        let mut app = app();
+        app.input = "/th".to_string();
+        let completion = Completion {
+            start: 0,
+            candidates: vec!["/theme".to_string(), "/think".to_string()],
+            selected: 1,
+        };
+        let buffer = render(60, 8, |frame| {
+            let input = Rect::new(0, 5, 60, 3);
+            app.render_input(frame, input);
+            render_completion(frame, input, &completion);
+        });
+        assert_snapshot!("chat_input_completion", buffer_text(&buffer));
+    }
+
+    #[test]
+    fn input_with_image_preview() {
+        let mut app = app();
+        let pixels: Vec<u8> = (0..48u8).flat_map(|i| [i * 5, 120, 255 - i * 5]).collect();
+        let mut image = Attachment::from_text("clipboard-101500.png", String::new());
+        image.bytes = 48_213;
+        image.image_type = Some("image/png");
+        image.thumbnail = Some(AsciiFrame::from_rgb24(&pixels, 12, 4, 12 * 3));
+        app.attachments.push(image);
+        app.input = "what is in this picture?".to_string();
+        let height = app.input_height();
+        let buffer = render(50, height, |frame| app.render_input(frame, frame.area()));
+        assert_snapshot!("chat_input_image", buffer_text(&buffer));
+    }
+
+    #[test]
+    fn message_detail_overlay() {
+        let message = ChatMessage::user("a long prompt\nthat spans\nthree lines".to_string());
+        let buffer = render(40, 8, |frame| render_message_detail(frame, frame.area(), &message, 0));
+        assert_snapshot!("chat_message_detail", buffer_text(&buffer));
+    }
 }
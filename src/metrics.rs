@@ -0,0 +1,274 @@
+//! Opt-in app health metrics for the dashboard's Health view: chat deck
+//! sessions, videos played and the frame rate they reached, and failed
+//! provider requests. With `metrics = true` in config.toml they are written to
+//! the `app_metrics` table of the local archive; nothing is ever sent anywhere.
+
+use parking_lot::Mutex;
+use ratatui::{prelude::*, widgets::Paragraph};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use crate::config::Config;
+use crate::db::{Database, MessageFilter};
+use crate::theme::t;
+
+/// Set from `metrics` in config.toml; [`record`] does nothing while it is off.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Metrics not yet written to the database, with the Unix seconds they
+/// happened at.
+static PENDING: OnceLock<Mutex<Vec<(Metric, i64)>>> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Metric {
+    /// The chat deck started; `plain` under `--plain`.
+    Session { plain: bool },
+    /// A video stopped playing, with the frames per second it showed.
+    Video { source: String, fps: Option<f32> },
+    /// A request to `provider` failed after its retries.
+    ProviderError { provider: String },
+}
+
+impl Metric {
+    /// `(event, detail, value)` as stored in `app_metrics`.
+    pub fn row(&self) -> (&'static str, &str, Option<f64>) {
+        match self {
+            Metric::Session { plain } => ("session", if *plain { "plain" } else { "tui" }, None),
+            Metric::Video { source, fps } => ("video", source, fps.map(f64::from)),
+            Metric::ProviderError { provider } => ("provider_error", provider, None),
+        }
+    }
+}
+
+pub fn enable(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn record(metric: Metric) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let now = chrono::Local::now().timestamp();
+    PENDING.get_or_init(Default::default).lock().push((metric, now));
+}
+
+/// Drains the metrics recorded since the last call.
+pub fn take() -> Vec<(Metric, i64)> {
+    std::mem::take(&mut *PENDING.get_or_init(Default::default).lock())
+}
+
+/// Writes pending metrics straight to the archive, for commands that run
+/// without the chat deck's background writer.
+pub fn flush(config: &Config) {
+    let pending = take();
+    if pending.is_empty() {
+        return;
+    }
+    if let Err(error) = config.open_database().and_then(|db| db.record_metrics(&pending)) {
+        tracing::warn!("metrics not saved: {:#}", error);
+    }
+}
+
+/// One day of the Health view.
+pub struct HealthDay {
+    /// `YYYY-MM-DD` in local time.
+    pub day: String,
+    pub sessions: i64,
+    pub videos: i64,
+    pub avg_fps: Option<f64>,
+    pub errors: i64,
+}
+
+/// The dashboard's Health view over the filter's date range.
+#[derive(Default)]
+pub struct HealthReport {
+    pub sessions: i64,
+    /// Of `sessions`, those started with `--plain`.
+    pub plain_sessions: i64,
+    pub videos: i64,
+    pub avg_fps: Option<f64>,
+    pub errors: i64,
+    /// Failed requests by provider, most first.
+    pub providers: Vec<(String, i64)>,
+    /// Newest first.
+    pub days: Vec<HealthDay>,
+}
+
+impl HealthReport {
+    /// Only the date range of `filter` applies; metrics have no provider,
+    /// tag or role of their own.
+    pub fn load(db: &Database, filter: &MessageFilter) -> Self {
+        let mut range = vec!["1".to_string()];
+        if let Some(since) = filter.since {
+            range.push(format!("timestamp >= {}", since));
+        }
+        if let Some(until) = filter.until {
+            range.push(format!("timestamp < {}", until));
+        }
+        let range = range.join(" AND ");
+        let conn = db.connection();
+        let mut report = conn
+            .query_row(
+                &format!(
+                    "SELECT COALESCE(SUM(event = 'session'), 0),
+                            COALESCE(SUM(event = 'session' AND detail = 'plain'), 0),
+                            COALESCE(SUM(event = 'video'), 0),
+                            AVG(CASE WHEN event = 'video' THEN value END),
+                            COALESCE(SUM(event = 'provider_error'), 0)
+                     FROM app_metrics WHERE {}",
+                    range
+                ),
+                [],
+                |row| {
+                    Ok(Self {
+                        sessions: row.get(0)?,
+                        plain_sessions: row.get(1)?,
+                        videos: row.get(2)?,
+                        avg_fps: row.get(3)?,
+                        errors: row.get(4)?,
+                        ..Self::default()
+                    })
+                },
+            )
+            .unwrap_or_default();
+        report.providers = conn
+            .prepare(&format!(
+                "SELECT detail, COUNT(*) FROM app_metrics
+                 WHERE event = 'provider_error' AND {}
+                 GROUP BY detail ORDER BY 2 DESC, 1",
+                range
+            ))
+            .ok()
+            .map(|mut stmt| {
+                stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                    .ok()
+                    .map(|rows| rows.filter_map(|r| r.ok()).collect())
+                    .unwrap_or_default()
+            })
+            .unwrap_or_default();
+        report.days = conn
+            .prepare(&format!(
+                "SELECT date(timestamp, 'unixepoch', 'localtime'),
+                        SUM(event = 'session'),
+                        SUM(event = 'video'),
+                        AVG(CASE WHEN event = 'video' THEN value END),
+                        SUM(event = 'provider_error')
+                 FROM app_metrics WHERE {}
+                 GROUP BY 1 ORDER BY 1 DESC",
+                range
+            ))
+            .ok()
+            .map(|mut stmt| {
+                stmt.query_map([], |row| {
+                    Ok(HealthDay {
+                        day: row.get(0)?,
+                        sessions: row.get(1)?,
+                        videos: row.get(2)?,
+                        avg_fps: row.get(3)?,
+                        errors: row.get(4)?,
+                    })
+                })
+                .ok()
+                .map(|rows| rows.filter_map(|r| r.ok()).collect())
+                .unwrap_or_default()
+            })
+            .unwrap_or_default();
+        report
+    }
+
+    fn is_empty(&self) -> bool {
+        self.sessions == 0 && self.videos == 0 && self.errors == 0
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, range: &str) {
+        let block = t().block(format!(" APP HEALTH // {} ", range), t().accent2, t().accent1);
+        frame.render_widget(block, area);
+        let inner = area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        });
+        if self.is_empty() {
+            frame.render_widget(
+                Paragraph::new(
+                    "no app metrics in this range (metrics = true in config.toml records them)",
+                )
+                .style(t().muted_style().bg(t().panel_bg))
+                .alignment(Alignment::Center),
+                inner,
+            );
+            return;
+        }
+
+        let [summary, days] =
+            Layout::vertical([Constraint::Length(5), Constraint::Min(2)]).areas(inner);
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(summary);
+        let lines = vec![
+            Line::from(vec![
+                Span::styled("SESSIONS:        ", t().label_style()),
+                Span::styled(self.sessions.to_string(), Style::default().fg(t().accent4).bold()),
+                Span::styled(format!("  {} plain", self.plain_sessions), t().muted_style()),
+            ]),
+            Line::from(vec![
+                Span::styled("VIDEOS PLAYED:   ", t().label_style()),
+                Span::styled(self.videos.to_string(), t().text_style()),
+            ]),
+            Line::from(vec![
+                Span::styled("AVERAGE FPS:     ", t().label_style()),
+                Span::styled(fps_label(self.avg_fps), Style::default().fg(t().accent3)),
+            ]),
+            Line::from(vec![
+                Span::styled("PROVIDER ERRORS: ", t().label_style()),
+                Span::styled(
+                    self.errors.to_string(),
+                    Style::default().fg(if self.errors > 0 { t().danger } else { t().accent4 }),
+                ),
+            ]),
+        ];
+        frame.render_widget(Paragraph::new(lines).style(t().panel_style()), left);
+
+        let mut lines = vec![Line::from(Span::styled("ERRORS BY PROVIDER", t().label_style()))];
+        for (provider, count) in self.providers.iter().take(right.height.saturating_sub(1) as usize) {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:<20} ", provider), t().text_style()),
+                Span::styled(format!("{:>6}", count), Style::default().fg(t().danger)),
+            ]));
+        }
+        if self.providers.is_empty() {
+            lines.push(Line::from(Span::styled("none", t().muted_style())));
+        }
+        frame.render_widget(Paragraph::new(lines).style(t().panel_style()), right);
+
+        let mut lines = vec![Line::from(Span::styled(
+            format!("{:<12} {:>9} {:>7} {:>8} {:>7}", "DAY", "sessions", "videos", "fps", "errors"),
+            t().label_style(),
+        ))];
+        for day in self.days.iter().take(days.height.saturating_sub(1) as usize) {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:<12} ", day.day), t().text_style()),
+                Span::styled(
+                    format!("{:>9} {:>7} ", day.sessions, day.videos),
+                    Style::default().fg(t().accent3),
+                ),
+                Span::styled(
+                    format!("{:>8} ", fps_label(day.avg_fps)),
+                    Style::default().fg(t().accent1),
+                ),
+                Span::styled(
+                    format!("{:>7}", day.errors),
+                    if day.errors > 0 {
+                        Style::default().fg(t().danger)
+                    } else {
+                        t().muted_style()
+                    },
+                ),
+            ]));
+        }
+        frame.render_widget(Paragraph::new(lines).style(t().panel_style()), days);
+    }
+}
+
+fn fps_label(fps: Option<f64>) -> String {
+    fps.map(|fps| format!("{:.1}", fps)).unwrap_or_else(|| "-".to_string())
+}
@@ -0,0 +1,53 @@
+use anyhow::{Context, Result};
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+use crate::config::expand_home;
+
+/// The `[network]` config table, applied to every outgoing HTTP request.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Proxy URL for all traffic. When unset, `HTTPS_PROXY` / `HTTP_PROXY` /
+    /// `ALL_PROXY` from the environment are used.
+    pub proxy: Option<String>,
+    /// Comma-separated hosts that bypass `proxy`, like `NO_PROXY`.
+    pub no_proxy: Option<String>,
+    /// PEM file with extra root certificates, e.g. a corporate TLS-inspection CA.
+    pub ca_bundle: Option<String>,
+}
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Builds the shared client from `config`. On error the settings are ignored
+/// and a plain client is used; the error is returned for reporting.
+pub fn init(config: &NetworkConfig) -> Result<()> {
+    let _ = CLIENT.set(build(config)?);
+    Ok(())
+}
+
+fn build(config: &NetworkConfig) -> Result<Client> {
+    let mut builder = Client::builder();
+    if let Some(url) = config.proxy.as_deref().filter(|url| !url.trim().is_empty()) {
+        let proxy = Proxy::all(url.trim()).with_context(|| format!("invalid proxy {}", url))?;
+        let no_proxy = config.no_proxy.as_deref().and_then(NoProxy::from_string);
+        builder = builder.proxy(proxy.no_proxy(no_proxy));
+    }
+    if let Some(path) = &config.ca_bundle {
+        let path = expand_home(path);
+        let pem = std::fs::read(&path)
+            .with_context(|| format!("failed to read CA bundle {}", path.display()))?;
+        let certs = Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("{} is not a PEM certificate bundle", path.display()))?;
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// The shared HTTP client, with the `[network]` proxy and CA settings.
+pub fn client() -> Client {
+    CLIENT.get_or_init(Client::new).clone()
+}
@@ -0,0 +1,75 @@
+use serde::Deserialize;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::text;
+
+/// The `[notifications]` config table. Both channels only fire while the
+/// terminal is unfocused.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Ring the terminal bell when a reply lands.
+    pub bell: bool,
+    /// Post a desktop notification with a preview of the reply.
+    pub desktop: bool,
+    /// Characters of the reply shown in the desktop notification.
+    pub preview_chars: usize,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            bell: false,
+            desktop: false,
+            preview_chars: 140,
+        }
+    }
+}
+
+pub fn bell() {
+    let mut out = std::io::stdout();
+    let _ = out.write_all(b"\x07");
+    let _ = out.flush();
+}
+
+/// Posts a desktop notification via `osascript` on macOS or `notify-send`
+/// elsewhere. Runs on a background thread and fails silently.
+pub fn desktop(title: String, body: String) {
+    std::thread::spawn(move || {
+        let mut command = if cfg!(target_os = "macos") {
+            let script = format!(
+                "display notification \"{}\" with title \"{}\"",
+                escape_applescript(&body),
+                escape_applescript(&title)
+            );
+            let mut command = Command::new("osascript");
+            command.arg("-e").arg(script);
+            command
+        } else {
+            let mut command = Command::new("notify-send");
+            command.arg("--app-name=asciivision").arg(title).arg(body);
+            command
+        };
+        let _ = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    });
+}
+
+fn escape_applescript(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The reply collapsed onto one line and cut to `max` columns.
+pub fn preview(reply: &str, max: usize) -> String {
+    let flat = reply.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text::width(&flat) <= max {
+        flat
+    } else {
+        let cut = text::clip(&flat, max.saturating_sub(3));
+        format!("{}...", cut.trim_end())
+    }
+}
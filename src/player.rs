@@ -0,0 +1,111 @@
+use anyhow::{bail, Result};
+use asciivision_video::{RenderFit, SizePolicy, VideoOptions, VideoPlayer};
+use crossterm::event::{self, Event, KeyModifiers};
+use ratatui::{prelude::*, widgets::Paragraph};
+use std::{path::Path, time::Duration};
+
+use crate::config::Config;
+use crate::help::HelpOverlay;
+use crate::keymap::{self, PlayerAction};
+use crate::metrics::{self, Metric};
+use crate::next_frame;
+use crate::theme::t;
+
+/// ASCII playback for `asciivision play <file>`, full-screen or `--inline`. With `frames`
+/// (`--headless`) keys are not read and it stops after that many frames,
+/// failing if none of them showed video.
+pub async fn run<B: Backend>(
+    terminal: &mut Terminal<B>,
+    config: &Config,
+    path: &Path,
+    looping: bool,
+    frames: Option<u32>,
+) -> Result<()> {
+    // the viewport, which is only part of the screen with `play --inline`
+    let size = terminal.get_frame().area();
+    let mut video = VideoPlayer::new(
+        path,
+        VideoOptions {
+            size: SizePolicy::FitSource {
+                max_width: size.width,
+                max_height: size.height.saturating_sub(1),
+            },
+            fit: RenderFit::Letterbox,
+            looping,
+            effects: !config.reduce_motion,
+        },
+    )?;
+    let label = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("video")
+        .to_string();
+
+    let mut drawn = 0;
+    let mut focused = true;
+    let mut help = HelpOverlay::new("PLAY // KEYS", keymap::player_bindings());
+    let result = 'play: loop {
+        if frames.is_some_and(|frames| drawn >= frames) {
+            break headless_result(&video, path, drawn);
+        }
+        while frames.is_none() && event::poll(Duration::from_millis(5))? {
+            match event::read()? {
+                Event::Key(key) => match keymap::player_action(key) {
+                    _ if help.open && !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        help.handle_key(key)
+                    }
+                    Some(PlayerAction::Quit) => break 'play Ok(()),
+                    Some(PlayerAction::Help) => help.toggle(),
+                    None => {}
+                },
+                Event::FocusGained => focused = true,
+                Event::FocusLost => focused = false,
+                _ => {}
+            }
+        }
+
+        video.tick();
+        if video.is_finished() {
+            break match frames {
+                Some(_) => headless_result(&video, path, drawn),
+                None => Ok(()),
+            };
+        }
+
+        terminal.draw(|frame| {
+            let area = frame.area();
+            let [screen, status] =
+                Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(area);
+            video.render(frame, screen, 1.0);
+            frame.render_widget(
+                Paragraph::new(format!(
+                    " {}  sig:{}  {}  q/esc quit  ? keys",
+                    label,
+                    if video.has_signal() { "lock" } else { "seek" },
+                    if looping { "loop" } else { "once" },
+                ))
+                .style(Style::default().fg(t().muted).bg(t().bg_base)),
+                status,
+            );
+            if help.open {
+                help.render(frame, area);
+            }
+        })?;
+        drawn += 1;
+        next_frame(focused, Duration::from_millis(16)).await?;
+    };
+    if video.has_signal() {
+        metrics::record(Metric::Video {
+            source: label,
+            fps: video.average_fps(),
+        });
+    }
+    result
+}
+
+fn headless_result(video: &VideoPlayer, path: &Path, drawn: u32) -> Result<()> {
+    if !video.has_signal() {
+        bail!("no frame decoded from {} in {} frames", path.display(), drawn);
+    }
+    Ok(())
+}
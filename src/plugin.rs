@@ -0,0 +1,257 @@
+//! Providers implemented by an external executable, declared as a
+//! `[[providers]]` table with a `command`. Each request starts the command
+//! once, writes the request to its stdin as one JSON object and reads the
+//! reply back from stdout as JSON lines:
+//!
+//! ```text
+//! {"delta": "Hel"}                                   reply text, in pieces
+//! {"tool_calls": [{"id", "name", "arguments"}]}      tools the model wants run
+//! {"usage": {"input_tokens": 12, "output_tokens": 3}} added to the totals
+//! {"finish": "stop"}                                 why the model stopped
+//! {"error": "quota exceeded"}                        fails the request
+//! ```
+//!
+//! The reply ends when the command exits; a non-zero exit fails the request
+//! with whatever it wrote to stderr.
+
+use anyhow::{anyhow, Context, Result};
+use futures::future::BoxFuture;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use crate::ai::{AIClient, AIResponse, Message, StreamChunk, Usage};
+use crate::capabilities::Capabilities;
+use crate::keys;
+use crate::provider::{Provider, Turn};
+use crate::tools::{ToolCall, ToolDefinition, ToolResult};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginProvider {
+    pub name: String,
+    pub badge: String,
+    pub command: PathBuf,
+    pub args: Vec<String>,
+    /// Passed through as `model`; empty leaves the choice to the plugin.
+    pub model: String,
+    /// Looked up like any provider key and handed to the command as
+    /// `ASCIIVISION_API_KEY`.
+    pub api_key_env: Option<String>,
+    pub color: Color,
+}
+
+#[derive(Serialize)]
+struct PluginRequest<'a> {
+    model: &'a str,
+    messages: &'a [Message],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    tools: &'a [ToolDefinition],
+    /// With `tool_results`, the calls from the previous reply and what they returned.
+    tool_calls: &'a [ToolCall],
+    tool_results: &'a [ToolResult],
+    /// Whether deltas are shown as they arrive; the protocol is the same either way.
+    stream: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PluginEvent {
+    delta: Option<String>,
+    tool_calls: Option<Vec<ToolCall>>,
+    usage: Option<PluginUsage>,
+    finish: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PluginUsage {
+    input_tokens: u32,
+    output_tokens: u32,
+}
+
+impl PluginProvider {
+    /// Runs the command for `turn`, forwarding each event to `chunk_tx` as it
+    /// is read, and returns the whole reply.
+    async fn exchange(
+        &self,
+        client: &AIClient,
+        turn: Turn<'_>,
+        chunk_tx: Option<&mpsc::UnboundedSender<StreamChunk>>,
+    ) -> Result<(AIResponse, Usage)> {
+        let (tool_calls, tool_results) = turn.results.unwrap_or((&[], &[]));
+        let request = PluginRequest {
+            model: client.model_label(),
+            messages: &turn.messages,
+            system: client.system_prompt().map(String::as_str),
+            temperature: client.settings().temperature,
+            max_tokens: client.settings().max_tokens,
+            tools: turn.tools.unwrap_or(&[]),
+            tool_calls,
+            tool_results,
+            stream: chunk_tx.is_some(),
+        };
+
+        let mut command = Command::new(&self.command);
+        command
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(env) = &self.api_key_env {
+            let key = keys::lookup(env, &self.name).with_context(|| {
+                format!(
+                    "{} not set in environment or keychain (asciivision keys set {})",
+                    env, self.name
+                )
+            })?;
+            command.env("ASCIIVISION_API_KEY", key);
+        }
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("failed to start {}", self.command.display()))?;
+
+        let mut stdin = child.stdin.take().context("plugin stdin unavailable")?;
+        let mut line = serde_json::to_vec(&request)?;
+        line.push(b'\n');
+        stdin
+            .write_all(&line)
+            .await
+            .with_context(|| format!("failed to send the request to {}", self.name))?;
+        drop(stdin);
+
+        let mut stderr = child.stderr.take().context("plugin stderr unavailable")?;
+        let stderr = tokio::spawn(async move {
+            let mut text = String::new();
+            let _ = stderr.read_to_string(&mut text).await;
+            text
+        });
+
+        let stdout = child.stdout.take().context("plugin stdout unavailable")?;
+        let mut lines = BufReader::new(stdout).lines();
+        let mut text = String::new();
+        let mut calls = Vec::new();
+        let mut usage = Usage::default();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: PluginEvent = serde_json::from_str(&line).with_context(|| {
+                format!("{} sent a line that is not an event: {}", self.name, line)
+            })?;
+            if let Some(error) = event.error {
+                return Err(anyhow!("{}: {}", self.name, error));
+            }
+            if let Some(delta) = event.delta {
+                text.push_str(&delta);
+                if let Some(tx) = chunk_tx {
+                    let _ = tx.send(StreamChunk::Delta(delta));
+                }
+            }
+            calls.extend(event.tool_calls.unwrap_or_default());
+            if let Some(reported) = event.usage {
+                let reported = Usage {
+                    input_tokens: reported.input_tokens,
+                    output_tokens: reported.output_tokens,
+                    ..Usage::default()
+                };
+                usage += reported;
+                if let Some(tx) = chunk_tx {
+                    let _ = tx.send(StreamChunk::Usage(reported));
+                }
+            }
+            if let (Some(reason), Some(tx)) = (event.finish, chunk_tx) {
+                let _ = tx.send(StreamChunk::Finished(reason));
+            }
+        }
+
+        let status = child.wait().await?;
+        if !status.success() {
+            let stderr = stderr.await.unwrap_or_default();
+            let detail = stderr.lines().rev().find(|line| !line.trim().is_empty());
+            return Err(match detail {
+                Some(detail) => anyhow!("{} failed ({}): {}", self.name, status, detail.trim()),
+                None => anyhow!("{} failed ({})", self.name, status),
+            });
+        }
+        let reply = if calls.is_empty() {
+            AIResponse::Text(text)
+        } else {
+            AIResponse::ToolCalls(calls, text)
+        };
+        Ok((reply, usage))
+    }
+}
+
+impl Provider for PluginProvider {
+    fn key(&self) -> &str {
+        &self.name
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn badge(&self) -> &str {
+        &self.badge
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn default_model(&self) -> &str {
+        &self.model
+    }
+
+    fn api_key_env(&self) -> &str {
+        self.api_key_env.as_deref().unwrap_or("")
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            vision: false,
+            tools: true,
+            streaming: true,
+            system_prompt: true,
+            context_window: 32_768,
+        }
+    }
+
+    fn send<'a>(
+        &'a self,
+        client: &'a AIClient,
+        turn: Turn<'a>,
+    ) -> BoxFuture<'a, Result<(AIResponse, Usage)>> {
+        Box::pin(self.exchange(client, turn, None))
+    }
+
+    fn stream<'a>(
+        &'a self,
+        client: &'a AIClient,
+        turn: Turn<'a>,
+        chunk_tx: mpsc::UnboundedSender<StreamChunk>,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            match self.exchange(client, turn, Some(&chunk_tx)).await?.0 {
+                AIResponse::Text(_) => {
+                    let _ = chunk_tx.send(StreamChunk::Done);
+                }
+                AIResponse::ToolCalls(calls, text) => {
+                    let _ = chunk_tx.send(StreamChunk::ToolCallsReceived(calls, text));
+                }
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,86 @@
+//! Per-model token prices for the session cost readout and the archive.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::ai::Usage;
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl ModelPrice {
+    /// Cache reads bill at a tenth of the input price and cache writes at 1.25x.
+    pub fn cost(&self, usage: Usage) -> f64 {
+        let input = usage.input_tokens as f64
+            + usage.cache_read_tokens as f64 * 0.1
+            + usage.cache_write_tokens as f64 * 1.25;
+        (input * self.input + usage.output_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// List prices matched by model-name prefix; the longest match wins.
+const BUILTIN: &[(&str, f64, f64)] = &[
+    ("claude-opus-4-5", 5.0, 25.0),
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-haiku-4-5", 1.0, 5.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("gpt-5-nano", 0.05, 0.40),
+    ("gpt-5-mini", 0.25, 2.0),
+    ("gpt-5", 1.25, 10.0),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.5, 10.0),
+    ("grok-4-fast", 0.20, 0.50),
+    ("grok-4", 3.0, 15.0),
+    ("grok-3-mini", 0.30, 0.50),
+    ("gemini-3-flash", 0.50, 3.0),
+    ("gemini-3-pro", 2.0, 12.0),
+    ("gemini-2.5-flash", 0.30, 2.50),
+    ("gemini-2.5-pro", 1.25, 10.0),
+];
+
+/// Built-in prices plus exact-name entries from `[pricing]` in config.toml and
+/// the OpenRouter catalog. Local models (Ollama) have no entry and cost nothing.
+#[derive(Debug, Clone, Default)]
+pub struct PriceTable {
+    exact: HashMap<String, ModelPrice>,
+}
+
+impl PriceTable {
+    pub fn new(overrides: HashMap<String, ModelPrice>) -> Self {
+        Self { exact: overrides }
+    }
+
+    /// Adds a price unless config already pins one for `model`.
+    pub fn insert_default(&mut self, model: String, price: ModelPrice) {
+        self.exact.entry(model).or_insert(price);
+    }
+
+    pub fn lookup(&self, model: &str) -> Option<ModelPrice> {
+        if let Some(price) = self.exact.get(model) {
+            return Some(*price);
+        }
+        // OpenRouter ids are `vendor/model`; fall back to the bare model name.
+        let bare = model.rsplit('/').next().unwrap_or(model);
+        BUILTIN
+            .iter()
+            .filter(|(prefix, _, _)| bare.starts_with(prefix))
+            .max_by_key(|(prefix, _, _)| prefix.len())
+            .map(|&(_, input, output)| ModelPrice { input, output })
+    }
+}
+
+/// `$0.0042`, `$1.27`
+pub fn format_cost(cost: f64) -> String {
+    if cost < 1.0 {
+        format!("${:.4}", cost)
+    } else {
+        format!("${:.2}", cost)
+    }
+}
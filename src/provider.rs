@@ -0,0 +1,358 @@
+//! Everything the deck can route a prompt to sits behind [`Provider`]: the
+//! built-in vendors below, OpenAI-compatible endpoints declared with
+//! `[[providers]]` in config.toml, and external executables from
+//! [`crate::plugin`]. A new HTTP vendor is one more [`Vendor`] in [`BUILTIN`].
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use ratatui::style::Color;
+use tokio::sync::mpsc;
+
+use crate::ai::{AIClient, AIResponse, Message, StreamChunk, Usage};
+use crate::capabilities::Capabilities;
+use crate::theme::t;
+use crate::tools::{ToolCall, ToolDefinition, ToolResult};
+
+/// One request, already adapted to what the model accepts.
+pub struct Turn<'a> {
+    pub messages: Vec<Message>,
+    pub tools: Option<&'a [ToolDefinition]>,
+    /// The calls the model asked for and what they returned, when the
+    /// request carries tool results back.
+    pub results: Option<(&'a [ToolCall], &'a [ToolResult])>,
+}
+
+pub trait Provider: Send + Sync {
+    /// Name used by `--provider`, `[provider_settings.<key>]`, the keychain
+    /// and the archive.
+    fn key(&self) -> &str;
+    fn name(&self) -> &str;
+    fn badge(&self) -> &str;
+    fn color(&self) -> Color;
+
+    /// Other names `--provider` and `/provider` accept.
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Model used when neither the picker nor config names one; empty when
+    /// one has to be picked first.
+    fn default_model(&self) -> &str;
+
+    /// Env var holding the API key, which may also be in the OS keychain;
+    /// empty when requests go out without one.
+    fn api_key_env(&self) -> &str {
+        ""
+    }
+
+    /// Whether the model is picked at runtime from a catalog.
+    fn has_model_picker(&self) -> bool {
+        false
+    }
+
+    /// What the stock model accepts; specific models refine it.
+    fn capabilities(&self) -> Capabilities;
+
+    /// Chat endpoint, for providers speaking one of the HTTP [`Wire`] formats.
+    fn endpoint(&self) -> String {
+        String::new()
+    }
+
+    /// OpenAI-compatible `/embeddings` URL and the model used when `[rag]`
+    /// names none.
+    fn embeddings(&self) -> Option<(String, &str)> {
+        None
+    }
+
+    fn send<'a>(
+        &'a self,
+        client: &'a AIClient,
+        turn: Turn<'a>,
+    ) -> BoxFuture<'a, Result<(AIResponse, Usage)>>;
+
+    /// Streams the reply as chunks. By default it is sent whole and arrives
+    /// in one piece.
+    fn stream<'a>(
+        &'a self,
+        client: &'a AIClient,
+        turn: Turn<'a>,
+        chunk_tx: mpsc::UnboundedSender<StreamChunk>,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(client.reply_as_stream(turn, chunk_tx))
+    }
+}
+
+/// Request and streaming formats spoken over HTTP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wire {
+    Anthropic,
+    /// `/chat/completions`, also used by Grok, Ollama, OpenRouter and most
+    /// local servers.
+    OpenAI,
+    /// No SSE parser; replies always land whole.
+    Gemini,
+}
+
+/// A built-in HTTP vendor.
+#[derive(Debug, Clone, Copy)]
+pub struct Vendor {
+    pub key: &'static str,
+    pub aliases: &'static [&'static str],
+    pub name: &'static str,
+    pub badge: &'static str,
+    pub color: fn() -> Color,
+    pub url: &'static str,
+    pub model: &'static str,
+    pub api_key_env: &'static str,
+    pub wire: Wire,
+    pub vision: bool,
+    /// Of the stock model. Ollama's is its default `num_ctx`; catalog
+    /// models report their own through the picker.
+    pub context_window: u32,
+    pub model_picker: bool,
+    /// Embeddings URL and default embedding model.
+    pub embeddings: Option<(&'static str, &'static str)>,
+}
+
+pub const CLAUDE: Vendor = Vendor {
+    key: "claude",
+    aliases: &["anthropic"],
+    name: "Claude Haiku 4.5",
+    badge: "ANTHROPIC",
+    color: || t().providers.claude,
+    url: "https://api.anthropic.com/v1/messages",
+    model: "claude-haiku-4-5",
+    api_key_env: "CLAUDE_API_KEY",
+    wire: Wire::Anthropic,
+    vision: true,
+    context_window: 200_000,
+    model_picker: false,
+    embeddings: None,
+};
+
+pub const GROK: Vendor = Vendor {
+    key: "grok",
+    aliases: &["xai"],
+    name: "Grok 4 Fast",
+    badge: "X.AI",
+    color: || t().providers.grok,
+    url: "https://api.x.ai/v1/chat/completions",
+    model: "grok-4-fast-non-reasoning",
+    api_key_env: "GROK_API_KEY",
+    wire: Wire::OpenAI,
+    vision: true,
+    context_window: 2_000_000,
+    model_picker: false,
+    embeddings: None,
+};
+
+pub const OPENAI: Vendor = Vendor {
+    key: "gpt",
+    aliases: &["openai", "gpt5", "gpt-5"],
+    name: "GPT-5 Nano",
+    badge: "OPENAI",
+    color: || t().providers.openai,
+    url: "https://api.openai.com/v1/chat/completions",
+    model: "gpt-5-nano",
+    api_key_env: "OPENAI_API_KEY",
+    wire: Wire::OpenAI,
+    vision: true,
+    context_window: 400_000,
+    model_picker: false,
+    embeddings: Some(("https://api.openai.com/v1/embeddings", "text-embedding-3-small")),
+};
+
+pub const GEMINI: Vendor = Vendor {
+    key: "gemini",
+    aliases: &["google"],
+    name: "Gemini 3 Flash",
+    badge: "GOOGLE",
+    color: || t().providers.gemini,
+    url: "https://generativelanguage.googleapis.com/v1beta/models",
+    model: "gemini-3-flash-preview",
+    api_key_env: "GEMINI_API_KEY",
+    wire: Wire::Gemini,
+    vision: true,
+    context_window: 1_048_576,
+    model_picker: false,
+    embeddings: None,
+};
+
+pub const OLLAMA: Vendor = Vendor {
+    key: "ollama",
+    aliases: &["local"],
+    name: "Ollama Local",
+    badge: "OLLAMA",
+    color: || t().providers.ollama,
+    url: "http://127.0.0.1:11434/v1/chat/completions",
+    model: "",
+    api_key_env: "",
+    wire: Wire::OpenAI,
+    vision: false,
+    context_window: 4_096,
+    model_picker: true,
+    embeddings: Some(("http://127.0.0.1:11434/v1/embeddings", "nomic-embed-text")),
+};
+
+pub const OPENROUTER: Vendor = Vendor {
+    key: "openrouter",
+    aliases: &["or"],
+    name: "OpenRouter",
+    badge: "OPENROUTER",
+    color: || t().providers.openrouter,
+    url: "https://openrouter.ai/api/v1/chat/completions",
+    model: "openrouter/auto",
+    api_key_env: "OPENROUTER_API_KEY",
+    wire: Wire::OpenAI,
+    vision: false,
+    context_window: 128_000,
+    model_picker: true,
+    embeddings: None,
+};
+
+/// In `/provider` cycling order; the first is the fallback for unknown names.
+pub const BUILTIN: [Vendor; 6] = [CLAUDE, GROK, OPENAI, GEMINI, OLLAMA, OPENROUTER];
+
+impl Provider for Vendor {
+    fn key(&self) -> &str {
+        self.key
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn badge(&self) -> &str {
+        self.badge
+    }
+
+    fn color(&self) -> Color {
+        (self.color)()
+    }
+
+    fn aliases(&self) -> &[&str] {
+        self.aliases
+    }
+
+    fn default_model(&self) -> &str {
+        self.model
+    }
+
+    fn api_key_env(&self) -> &str {
+        self.api_key_env
+    }
+
+    fn has_model_picker(&self) -> bool {
+        self.model_picker
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            vision: self.vision,
+            tools: true,
+            streaming: self.wire != Wire::Gemini,
+            system_prompt: true,
+            context_window: self.context_window,
+        }
+    }
+
+    fn endpoint(&self) -> String {
+        self.url.to_string()
+    }
+
+    fn embeddings(&self) -> Option<(String, &str)> {
+        self.embeddings.map(|(url, model)| (url.to_string(), model))
+    }
+
+    fn send<'a>(
+        &'a self,
+        client: &'a AIClient,
+        turn: Turn<'a>,
+    ) -> BoxFuture<'a, Result<(AIResponse, Usage)>> {
+        Box::pin(client.send_over(self.wire, turn))
+    }
+
+    fn stream<'a>(
+        &'a self,
+        client: &'a AIClient,
+        turn: Turn<'a>,
+        chunk_tx: mpsc::UnboundedSender<StreamChunk>,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(client.stream_over(self.wire, turn, chunk_tx))
+    }
+}
+
+/// An OpenAI-compatible server declared in config (llama.cpp, vLLM, LM Studio, ...).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomProvider {
+    pub name: String,
+    pub badge: String,
+    /// Base URL up to and including the version segment, e.g. `http://127.0.0.1:8080/v1`.
+    pub base_url: String,
+    pub api_key_env: Option<String>,
+    pub model: String,
+    pub color: Color,
+}
+
+impl Provider for CustomProvider {
+    fn key(&self) -> &str {
+        &self.name
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn badge(&self) -> &str {
+        &self.badge
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn default_model(&self) -> &str {
+        &self.model
+    }
+
+    // local servers usually run without auth, so only send a key when one is declared
+    fn api_key_env(&self) -> &str {
+        self.api_key_env.as_deref().unwrap_or("")
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            vision: false,
+            tools: true,
+            streaming: true,
+            system_prompt: true,
+            context_window: 32_768,
+        }
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/chat/completions", self.base_url.trim_end_matches('/'))
+    }
+
+    fn embeddings(&self) -> Option<(String, &str)> {
+        let url = format!("{}/embeddings", self.base_url.trim_end_matches('/'));
+        Some((url, &self.model))
+    }
+
+    fn send<'a>(
+        &'a self,
+        client: &'a AIClient,
+        turn: Turn<'a>,
+    ) -> BoxFuture<'a, Result<(AIResponse, Usage)>> {
+        Box::pin(client.send_over(Wire::OpenAI, turn))
+    }
+
+    fn stream<'a>(
+        &'a self,
+        client: &'a AIClient,
+        turn: Turn<'a>,
+        chunk_tx: mpsc::UnboundedSender<StreamChunk>,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(client.stream_over(Wire::OpenAI, turn, chunk_tx))
+    }
+}
@@ -0,0 +1,303 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::ai::{AIProvider, ProviderRegistry};
+use crate::config::Config;
+use crate::db::{Database, LoggedMessage};
+
+/// Extensions picked up by `asciivision index`.
+const INDEXED_EXTENSIONS: &[&str] = &["md", "markdown", "txt", "rst"];
+/// Chunks sent per embeddings request.
+const EMBED_BATCH: usize = 32;
+/// Characters of a message embedded by `index-history`; the opening of a
+/// long reply says enough about what it covers.
+const HISTORY_CHARS: usize = 2000;
+
+/// The `[rag]` config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RagConfig {
+    /// Provider serving embeddings: `ollama`, `gpt` or a custom name.
+    pub provider: String,
+    /// Defaults to `nomic-embed-text` (Ollama) or `text-embedding-3-small` (OpenAI).
+    pub model: Option<String>,
+    /// Target chunk size in characters.
+    pub chunk_chars: usize,
+    /// Chunks prepended to each prompt.
+    pub top_k: usize,
+}
+
+impl Default for RagConfig {
+    fn default() -> Self {
+        Self {
+            provider: "ollama".to_string(),
+            model: None,
+            chunk_chars: 1200,
+            top_k: 4,
+        }
+    }
+}
+
+/// Provider and model pair used to embed both documents and queries.
+#[derive(Debug, Clone)]
+pub struct Embedder {
+    pub provider: AIProvider,
+    pub model: String,
+}
+
+impl Embedder {
+    pub fn new(providers: &ProviderRegistry, config: &RagConfig) -> Result<Self> {
+        let provider = providers.resolve(&config.provider);
+        let model = match &config.model {
+            Some(model) => model.clone(),
+            None => provider
+                .default_embedding_model()
+                .ok_or_else(|| {
+                    anyhow!(
+                        "{} cannot embed: set [rag] provider to ollama, gpt or a custom endpoint",
+                        provider.name()
+                    )
+                })?
+                .to_string(),
+        };
+        Ok(Self { provider, model })
+    }
+
+    pub async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.provider.embed(&self.model, inputs).await
+    }
+}
+
+/// A chunk retrieved for a prompt.
+pub struct RagHit {
+    pub path: String,
+    pub content: String,
+    pub score: f32,
+}
+
+/// `asciivision index <dir>`: chunks and embeds every text/markdown file under
+/// `dir` into the conversation database.
+pub async fn index(dir: &Path, config: &Config) -> Result<()> {
+    let providers = ProviderRegistry::new(config.custom_providers());
+    let embedder = Embedder::new(&providers, &config.rag)?;
+    let db = config.open_database()?;
+
+    let mut files = Vec::new();
+    collect_files(dir, &mut files).with_context(|| format!("failed to read {}", dir.display()))?;
+    files.sort();
+    if files.is_empty() {
+        println!(
+            "no .md, .markdown, .txt or .rst files under {}",
+            dir.display()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "indexing {} files with {} // {}",
+        files.len(),
+        embedder.provider.name(),
+        embedder.model
+    );
+    let mut total = 0;
+    for file in &files {
+        let Ok(text) = std::fs::read_to_string(file) else {
+            println!("  skip {} (not utf-8)", file.display());
+            continue;
+        };
+        let chunks = chunk_text(&text, config.rag.chunk_chars.max(200));
+        if chunks.is_empty() {
+            continue;
+        }
+        let mut embedded = Vec::with_capacity(chunks.len());
+        for batch in chunks.chunks(EMBED_BATCH) {
+            let vectors = embedder
+                .embed(batch)
+                .await
+                .with_context(|| format!("failed to embed {}", file.display()))?;
+            embedded.extend(batch.iter().cloned().zip(vectors));
+        }
+        let path = file.canonicalize().unwrap_or_else(|_| file.clone());
+        db.replace_rag_chunks(&path.to_string_lossy(), &embedder.model, &embedded)?;
+        println!("  {} ({} chunks)", file.display(), embedded.len());
+        total += embedded.len();
+    }
+    println!(
+        "done: {} chunks stored // toggle /rag in the chat deck to use them",
+        total
+    );
+    Ok(())
+}
+
+/// `asciivision index-history`: embeds every archived prompt and reply that
+/// has no vector from the `[rag]` model yet, for `/recall`.
+pub async fn index_history(config: &Config) -> Result<()> {
+    let providers = ProviderRegistry::new(config.custom_providers());
+    let embedder = Embedder::new(&providers, &config.rag)?;
+    let db = config.open_database()?;
+    let pending = db.unembedded_messages(&embedder.model)?;
+    if pending.is_empty() {
+        println!("nothing new to embed with {}", embedder.model);
+        return Ok(());
+    }
+
+    println!(
+        "indexing {} messages with {} // {}",
+        pending.len(),
+        embedder.provider.name(),
+        embedder.model
+    );
+    let mut done = 0;
+    for batch in pending.chunks(EMBED_BATCH) {
+        let texts: Vec<String> = batch
+            .iter()
+            .map(|(_, content)| content.chars().take(HISTORY_CHARS).collect())
+            .collect();
+        let vectors = embedder.embed(&texts).await.context("failed to embed messages")?;
+        let ids = batch.iter().map(|(id, _)| *id);
+        db.save_message_embeddings(&embedder.model, &ids.zip(vectors).collect::<Vec<_>>())?;
+        done += batch.len();
+        println!("  {}/{}", done, pending.len());
+    }
+    println!("done: /recall <query> in the chat deck searches them");
+    Ok(())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| INDEXED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Splits on blank lines and packs paragraphs into chunks of about `size`
+/// characters; longer paragraphs are cut at character boundaries.
+fn chunk_text(text: &str, size: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if !current.is_empty() && current.chars().count() + paragraph.chars().count() > size {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if paragraph.chars().count() > size {
+            let chars: Vec<char> = paragraph.chars().collect();
+            for piece in chars.chunks(size) {
+                chunks.push(piece.iter().collect());
+            }
+            continue;
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// The `top_k` stored chunks closest to `query` by cosine similarity.
+pub fn retrieve(db: &Database, model: &str, query: &[f32], top_k: usize) -> Result<Vec<RagHit>> {
+    let mut hits: Vec<RagHit> = db
+        .rag_chunks(model)?
+        .into_iter()
+        .filter(|chunk| chunk.embedding.len() == query.len())
+        .map(|chunk| RagHit {
+            score: cosine(&chunk.embedding, query),
+            path: chunk.path,
+            content: chunk.content,
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(top_k);
+    Ok(hits)
+}
+
+/// A past exchange found by `/recall`: the prompt and reply, or whichever
+/// of them is left.
+pub struct Recalled {
+    pub score: f32,
+    pub messages: Vec<LoggedMessage>,
+}
+
+/// The `top_k` archived exchanges closest to `query`, scored by their
+/// closest message.
+pub fn recall(db: &Database, model: &str, query: &[f32], top_k: usize) -> Result<Vec<Recalled>> {
+    let mut scored: Vec<(i64, f32)> = db
+        .message_embeddings(model)?
+        .into_iter()
+        .filter(|(_, embedding)| embedding.len() == query.len())
+        .map(|(id, embedding)| (id, cosine(&embedding, query)))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut found: Vec<Recalled> = Vec::new();
+    for (id, score) in scored {
+        if found.len() == top_k {
+            break;
+        }
+        // a prompt and its reply both matching is one exchange
+        let seen = found.iter().any(|recalled| recalled.messages.iter().any(|m| m.id == id));
+        if !seen {
+            found.push(Recalled {
+                score,
+                messages: db.exchange(id)?,
+            });
+        }
+    }
+    Ok(found)
+}
+
+/// Recalled exchanges formatted as context for the next prompts.
+pub fn recall_block(query: &str, recalled: &[Recalled]) -> String {
+    let mut block = format!("[Earlier conversations related to \"{}\"]\n", query);
+    for exchange in recalled {
+        block.push('\n');
+        for message in &exchange.messages {
+            let speaker = match message.role.as_str() {
+                "user" => "user".to_string(),
+                _ => message.model.clone().unwrap_or_else(|| message.provider.clone()),
+            };
+            let content: String = message.content.chars().take(HISTORY_CHARS).collect();
+            block.push_str(&format!("{}: {}\n", speaker, content));
+        }
+    }
+    block
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denom = norm(a) * norm(b);
+    if denom == 0.0 {
+        0.0
+    } else {
+        dot / denom
+    }
+}
+
+/// Retrieved chunks formatted to sit in front of the user's prompt.
+pub fn context_block(hits: &[RagHit]) -> String {
+    let mut block = String::from("[Local documents that may be relevant to the question below]\n");
+    for hit in hits {
+        block.push_str(&format!("\n--- {} ---\n{}\n", hit.path, hit.content));
+    }
+    block.push_str("\n[Question]\n");
+    block
+}
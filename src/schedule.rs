@@ -0,0 +1,317 @@
+//! Prompts sent on a cron schedule: `asciivision schedule` stores them in the
+//! archive and `asciivision daemon` sends each one when it falls due, saving
+//! the answer as a new session tagged `scheduled`.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Datelike, Duration, Local, NaiveDateTime, TimeZone, Timelike};
+
+use crate::ai::{AIResponse, ProviderRegistry};
+use crate::ask;
+use crate::config::Config;
+use crate::db::ScheduledJob;
+
+/// Tag put on every session a scheduled prompt starts.
+const TAG: &str = "scheduled";
+
+/// A five-field cron expression: minute, hour, day of month, month and day of
+/// week (0 or 7 is Sunday). Fields take `*`, numbers, `a-b` ranges, `/n`
+/// steps and comma lists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Day of month or of week left as `*`; when both are restricted either
+    /// one matching is enough, as in cron.
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            bail!(
+                "cron {:?} needs five fields: minute hour day month weekday",
+                expression
+            );
+        };
+        let bad = |name: &str| format!("bad {} in cron {:?}", name, expression);
+        let mut weekdays = field(weekday, 0, 7).with_context(|| bad("weekday"))?;
+        // 7 is Sunday too
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: field(minute, 0, 59).with_context(|| bad("minute"))?,
+            hours: field(hour, 0, 23).with_context(|| bad("hour"))?,
+            days: field(day, 1, 31).with_context(|| bad("day of month"))?,
+            months: field(month, 1, 12).with_context(|| bad("month"))?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    fn day_matches(&self, time: &NaiveDateTime) -> bool {
+        let day = self.days & (1 << time.day()) != 0;
+        let weekday = self.weekdays & (1 << time.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+
+    /// The first minute after `after` the expression matches, within the
+    /// next four years; `None` for dates that never come, like `30 2`.
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = time + Duration::days(4 * 366);
+        while time < limit {
+            if self.months & (1 << time.month()) == 0 || !self.day_matches(&time) {
+                time = (time.date() + Duration::days(1)).and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+}
+
+/// One cron field as a bitmask of the values it allows.
+fn field(text: &str, min: u32, max: u32) -> Result<u64> {
+    let mut mask = 0u64;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|step| *step > 0)),
+            None => (part, Some(1)),
+        };
+        let step = step.ok_or_else(|| anyhow!("bad step in {:?}", part))?;
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (number(start, min, max)?, number(end, min, max)?),
+            // `5/15` runs from 5 to the end of the range
+            None if part.contains('/') => (number(range, min, max)?, max),
+            None => {
+                let value = number(range, min, max)?;
+                (value, value)
+            }
+        };
+        if start > end {
+            bail!("range {:?} runs backwards", range);
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn number(text: &str, min: u32, max: u32) -> Result<u32> {
+    text.parse::<u32>()
+        .ok()
+        .filter(|value| (min..=max).contains(value))
+        .ok_or_else(|| anyhow!("{:?} is not a number from {} to {}", text, min, max))
+}
+
+fn local(timestamp: i64) -> NaiveDateTime {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.naive_local())
+        .unwrap_or_default()
+}
+
+/// When `job` runs next: the first match after its last run, or after it was
+/// created. A run missed while no daemon was up is due at once.
+fn next_run(job: &ScheduledJob) -> Result<Option<NaiveDateTime>> {
+    let cron = Cron::parse(&job.cron)?;
+    Ok(cron.next_after(local(job.last_run.unwrap_or(job.created_at))))
+}
+
+/// `asciivision schedule <prompt> --cron <expr>`: stores a prompt for the daemon.
+pub fn add(
+    prompt: &str,
+    cron: &str,
+    provider: Option<&str>,
+    model: Option<&str>,
+    config: &Config,
+) -> Result<()> {
+    if prompt.trim().is_empty() {
+        bail!("schedule needs a prompt");
+    }
+    let parsed = Cron::parse(cron)?;
+    let providers = ProviderRegistry::new(config.custom_providers());
+    let provider = providers.resolve(
+        provider
+            .or(config.default_provider.as_deref())
+            .unwrap_or("claude"),
+    );
+    if provider.has_model_picker() && model.is_none() {
+        bail!("{} needs --model <id>", provider.db_key());
+    }
+    let db = config.open_database()?;
+    let id = db.add_scheduled_job(prompt, cron, provider.db_key(), model)?;
+    let next = parsed
+        .next_after(Local::now().naive_local())
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "never".to_string());
+    println!("scheduled #{} for {}: next run {}", id, provider.db_key(), next);
+    if !provider.is_configured() {
+        eprintln!(
+            "warning: {} has no API key yet; the daemon can't ask for one (asciivision keys set {})",
+            provider.name(),
+            provider.db_key()
+        );
+    }
+    Ok(())
+}
+
+/// `asciivision schedule --list`
+pub fn list(config: &Config) -> Result<()> {
+    let jobs = config.open_database()?.scheduled_jobs()?;
+    if jobs.is_empty() {
+        println!("no scheduled prompts // asciivision schedule \"<prompt>\" --cron \"0 9 * * 1-5\"");
+        return Ok(());
+    }
+    let when = |time: Option<NaiveDateTime>| {
+        time.map_or("never".to_string(), |time| time.format("%Y-%m-%d %H:%M").to_string())
+    };
+    for job in jobs {
+        let next = next_run(&job).unwrap_or(None);
+        let provider = match &job.model {
+            Some(model) => format!("{}/{}", job.provider, model),
+            None => job.provider.clone(),
+        };
+        println!(
+            "#{:<3} {:<15} {:<20} next {}  last {}",
+            job.id,
+            job.cron,
+            provider,
+            when(next),
+            when(job.last_run.map(local))
+        );
+        println!("     {}", job.prompt);
+    }
+    Ok(())
+}
+
+/// `asciivision schedule --remove <id>`
+pub fn remove(id: i64, config: &Config) -> Result<()> {
+    if !config.open_database()?.remove_scheduled_job(id)? {
+        bail!("no scheduled prompt #{}", id);
+    }
+    println!("removed scheduled prompt #{}", id);
+    Ok(())
+}
+
+/// `asciivision daemon`: checks the schedule every minute and sends whatever
+/// is due, one prompt at a time, until interrupted.
+pub async fn daemon(config: &Config) -> Result<()> {
+    let count = config.open_database()?.scheduled_jobs()?.len();
+    println!("daemon: watching {} scheduled prompts, Ctrl+C stops", count);
+    loop {
+        // reread each minute so `schedule` changes apply without a restart
+        let jobs = config.open_database().and_then(|db| db.scheduled_jobs());
+        match jobs {
+            Ok(jobs) => {
+                for job in jobs {
+                    let now = Local::now();
+                    match next_run(&job) {
+                        Ok(Some(next)) if next <= now.naive_local() => {
+                            run_job(&job, now.timestamp(), config).await
+                        }
+                        Ok(_) => {}
+                        Err(error) => tracing::warn!("scheduled prompt #{}: {:#}", job.id, error),
+                    }
+                }
+            }
+            Err(error) => eprintln!("daemon: archive unavailable: {:#}", error),
+        }
+        let wait = 60 - Local::now().second().min(59) as u64;
+        tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
+    }
+}
+
+/// Sends one job's prompt as a new session and records the run, even a failed
+/// one, so an unreachable provider is retried at the next match rather than
+/// every minute.
+async fn run_job(job: &ScheduledJob, now: i64, config: &Config) {
+    let stamp = Local::now().format("%Y-%m-%d %H:%M");
+    let outcome = async {
+        let db = config.open_database()?;
+        db.mark_scheduled_run(job.id, now)?;
+        let providers = ProviderRegistry::new(config.custom_providers());
+        let provider = providers.resolve(&job.provider);
+        if !provider.is_configured() {
+            bail!("{} has no API key", provider.name());
+        }
+        let client = ask::client(&provider, job.model.clone(), config);
+        let (result, latency_ms) = ask::exchange(&client, &job.prompt, &[], Some(&db), config).await;
+        db.add_tag(provider.db_key(), TAG)?;
+        let (AIResponse::Text(text) | AIResponse::ToolCalls(_, text)) = result?.0;
+        Ok((text.chars().count(), latency_ms))
+    };
+    match outcome.await {
+        Ok((chars, latency_ms)) => println!(
+            "{} #{} {}: {} chars in {:.1}s",
+            stamp,
+            job.id,
+            job.provider,
+            chars,
+            latency_ms as f32 / 1000.0
+        ),
+        Err(error) => eprintln!("{} #{} {}: failed: {:#}", stamp, job.id, job.provider, error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2026-03-02 is a Monday
+        let date = NaiveDate::from_ymd_opt(2026, 3, day).expect("fixture date");
+        date.and_hms_opt(hour, minute, 0).expect("fixture time")
+    }
+
+    #[test]
+    fn weekday_mornings() {
+        let cron = Cron::parse("0 9 * * 1-5").expect("cron");
+        assert_eq!(cron.next_after(at(2, 8, 30)), Some(at(2, 9, 0)));
+        assert_eq!(cron.next_after(at(2, 9, 0)), Some(at(3, 9, 0)));
+        // Friday after nine skips the weekend
+        assert_eq!(cron.next_after(at(6, 9, 1)), Some(at(9, 9, 0)));
+    }
+
+    #[test]
+    fn steps_lists_and_sunday() {
+        let cron = Cron::parse("*/20 8,18 * * *").expect("cron");
+        assert_eq!(cron.next_after(at(2, 8, 20)), Some(at(2, 8, 40)));
+        assert_eq!(cron.next_after(at(2, 8, 40)), Some(at(2, 18, 0)));
+        let sunday = Cron::parse("30 7 * * 7").expect("cron");
+        assert_eq!(sunday.next_after(at(2, 0, 0)), Some(at(8, 7, 30)));
+        // day of month or weekday, either one
+        let either = Cron::parse("0 0 15 * 0").expect("cron");
+        assert_eq!(either.next_after(at(2, 0, 0)), Some(at(8, 0, 0)));
+        assert_eq!(either.next_after(at(14, 0, 0)), Some(at(15, 0, 0)));
+    }
+
+    #[test]
+    fn bad_expressions_are_refused() {
+        assert!(Cron::parse("0 9 * *").is_err());
+        assert!(Cron::parse("60 9 * * *").is_err());
+        assert!(Cron::parse("0 9 * * 5-1").is_err());
+        assert!(Cron::parse("*/0 * * * *").is_err());
+        assert_eq!(Cron::parse("0 0 31 2 *").expect("cron").next_after(at(2, 0, 0)), None);
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::Result;
+use asciivision_video::{RenderFit, SizePolicy, VideoOptions, VideoPlayer};
+use ratatui::{prelude::*, widgets::Paragraph};
+use serde::Deserialize;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::theme::t;
+use crate::{centered_area, mix_color, render_background, render_boot_animation};
+
+/// How long the chat takes to fade out, and then the video to fade in.
+const FADE: Duration = Duration::from_millis(1200);
+
+/// `[screensaver]`: a looping ASCII video shown once the chat deck sits idle.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ScreensaverConfig {
+    /// Minutes without a key press before it starts; unset or 0 turns it off.
+    pub after_minutes: Option<u64>,
+    /// Video to loop; defaults to the loading video.
+    pub video: Option<String>,
+}
+
+impl ScreensaverConfig {
+    pub fn idle_after(&self) -> Option<Duration> {
+        self.after_minutes
+            .filter(|minutes| *minutes > 0)
+            .map(|minutes| Duration::from_secs(minutes * 60))
+    }
+}
+
+pub struct Screensaver {
+    /// `None` falls back to the boot animation.
+    video: Option<VideoPlayer>,
+    started: Instant,
+    /// Zero under `reduce_motion`, which cuts straight to the video.
+    fade: Duration,
+}
+
+impl Screensaver {
+    /// Starts decoding `path` sized for a `width` x `height` terminal.
+    pub fn start(
+        path: Option<&Path>,
+        width: u16,
+        height: u16,
+        reduce_motion: bool,
+    ) -> Result<Self> {
+        let video = match path {
+            Some(path) => Some(VideoPlayer::new(
+                path,
+                VideoOptions {
+                    size: SizePolicy::FitSource {
+                        max_width: width,
+                        max_height: height.saturating_sub(1),
+                    },
+                    fit: RenderFit::Letterbox,
+                    looping: true,
+                    effects: !reduce_motion,
+                },
+            )?),
+            None => None,
+        };
+        Ok(Self {
+            video,
+            started: Instant::now(),
+            fade: if reduce_motion { Duration::ZERO } else { FADE },
+        })
+    }
+
+    pub fn tick(&mut self) {
+        if let Some(video) = &mut self.video {
+            video.tick();
+        }
+    }
+
+    /// Draws over the chat deck already in `frame`: first dims it toward the
+    /// background, then takes the screen over with the video.
+    pub fn render(&self, frame: &mut Frame, phase: f32) {
+        let area = frame.area();
+        let elapsed = self.started.elapsed().as_secs_f32();
+        let fade = self.fade.as_secs_f32();
+        if elapsed < fade {
+            dim(frame.buffer_mut(), area, elapsed / fade);
+            return;
+        }
+
+        render_background(frame.buffer_mut(), area, phase);
+        let [screen, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(area);
+        let intensity = if fade > 0.0 { ((elapsed - fade) / fade).min(1.0) } else { 1.0 };
+        match &self.video {
+            Some(video) => video.render(frame, screen, intensity),
+            None => {
+                let boot = centered_area(screen, 82, 54);
+                render_boot_animation(frame.buffer_mut(), boot, phase);
+            }
+        }
+        frame.render_widget(
+            Paragraph::new(" screensaver // any key returns to chat ")
+                .alignment(Alignment::Center)
+                .style(t().muted_style()),
+            status,
+        );
+    }
+}
+
+/// Blends every cell in `area` toward the theme background by `amount`.
+fn dim(buffer: &mut Buffer, area: Rect, amount: f32) {
+    let background = t().bg_base;
+    for y in area.top()..area.bottom() {
+        for x in area.left()..area.right() {
+            let cell = &mut buffer[(x, y)];
+            cell.fg = mix_color(cell.fg, background, amount);
+            if cell.bg != Color::Reset {
+                cell.bg = mix_color(cell.bg, background, amount);
+            }
+        }
+    }
+}
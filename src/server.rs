@@ -28,7 +28,7 @@ impl VideoChatServer {
             let server = Arc::clone(&self);
             tokio::spawn(async move {
                 if let Err(e) = server.handle_connection(stream).await {
-                    eprintln!("connection error: {}", e);
+                    tracing::warn!("video chat connection dropped: {}", e);
                 }
             });
         }
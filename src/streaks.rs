@@ -0,0 +1,275 @@
+//! Daily streaks, records and milestone badges for the dashboard's Streaks
+//! view, computed from message timestamps.
+
+use chrono::{Local, NaiveDate};
+use ratatui::{prelude::*, widgets::Paragraph};
+
+use crate::db::{Database, MessageFilter};
+use crate::theme::t;
+
+/// Badges as `(label, what is counted, threshold)`.
+const MILESTONES: &[(&str, Measure, u64)] = &[
+    ("First contact", Measure::Messages, 1),
+    ("Regular", Measure::Messages, 100),
+    ("Power user", Measure::Messages, 1_000),
+    ("Archivist", Measure::Messages, 10_000),
+    ("Three in a row", Measure::Streak, 3),
+    ("Week streak", Measure::Streak, 7),
+    ("Month streak", Measure::Streak, 30),
+    ("Hundred days", Measure::Streak, 100),
+    ("Wordsmith", Measure::WordsWritten, 10_000),
+    ("Novelist", Measure::WordsWritten, 100_000),
+    ("Well read", Measure::WordsReceived, 1_000_000),
+    ("Polyglot", Measure::Providers, 3),
+    ("Full house", Measure::Providers, 6),
+];
+
+#[derive(Clone, Copy)]
+enum Measure {
+    Messages,
+    /// Longest streak in days.
+    Streak,
+    WordsWritten,
+    WordsReceived,
+    Providers,
+}
+
+#[derive(Default)]
+pub struct Streaks {
+    pub messages: u64,
+    pub providers: u64,
+    /// Days with at least one message.
+    pub active_days: usize,
+    /// Consecutive active days up to today, or up to yesterday when today has
+    /// nothing yet.
+    pub current: usize,
+    pub longest: usize,
+    /// Last day of the longest streak.
+    pub longest_end: Option<NaiveDate>,
+    /// Day with the most messages, and how many.
+    pub busiest: Option<(NaiveDate, u64)>,
+    /// Words in your prompts and in model replies.
+    pub words_written: u64,
+    pub words_received: u64,
+}
+
+impl Streaks {
+    pub fn load(db: &Database, filter: &MessageFilter) -> Self {
+        let conn = db.connection();
+        let scope = filter.sql();
+        let params = scope.params(&[]);
+        let days: Vec<(NaiveDate, u64)> = conn
+            .prepare(&format!(
+                "SELECT date(timestamp, 'unixepoch', 'localtime'), COUNT(*)
+                 FROM messages
+                 WHERE replaced = 0 AND {}
+                 GROUP BY 1
+                 ORDER BY 1",
+                scope.condition
+            ))
+            .ok()
+            .map(|mut stmt| {
+                stmt.query_map(params.as_slice(), |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))
+                    .ok()
+                    .map(|rows| {
+                        rows.filter_map(|r| r.ok())
+                            .filter_map(|(day, count)| {
+                                NaiveDate::parse_from_str(&day, "%Y-%m-%d")
+                                    .ok()
+                                    .map(|day| (day, count))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            })
+            .unwrap_or_default();
+
+        let mut streaks = Self {
+            messages: days.iter().map(|(_, count)| count).sum(),
+            active_days: days.len(),
+            busiest: days.iter().copied().max_by_key(|(day, count)| (*count, *day)),
+            providers: conn
+                .query_row(
+                    &format!(
+                        "SELECT COUNT(DISTINCT provider) FROM messages WHERE replaced = 0 AND {}",
+                        scope.condition
+                    ),
+                    params.as_slice(),
+                    |row| row.get(0),
+                )
+                .unwrap_or(0),
+            ..Self::default()
+        };
+        streaks.count_streaks(&days, Local::now().date_naive());
+        streaks.count_words(db, filter);
+        streaks
+    }
+
+    fn count_streaks(&mut self, days: &[(NaiveDate, u64)], today: NaiveDate) {
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for (day, _) in days {
+            run = match previous {
+                Some(previous) if previous.succ_opt() == Some(*day) => run + 1,
+                _ => 1,
+            };
+            if run > self.longest {
+                self.longest = run;
+                self.longest_end = Some(*day);
+            }
+            previous = Some(*day);
+        }
+        // the run that ends the archive is current if it reaches yesterday
+        let yesterday = today.pred_opt();
+        self.current = match previous {
+            Some(last) if last == today || Some(last) == yesterday => run,
+            _ => 0,
+        };
+    }
+
+    /// Whitespace-separated words in chat messages, streamed row by row.
+    fn count_words(&mut self, db: &Database, filter: &MessageFilter) {
+        let filter = filter.sql();
+        let sql = format!(
+            "SELECT role, content FROM messages
+             WHERE kind = 'chat' AND replaced = 0 AND {}",
+            filter.condition
+        );
+        let Ok(mut stmt) = db.connection().prepare(&sql) else {
+            return;
+        };
+        let Ok(rows) = stmt.query_map(filter.params(&[]).as_slice(), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        }) else {
+            return;
+        };
+        for (role, content) in rows.flatten() {
+            let words = content.split_whitespace().count() as u64;
+            if role == "user" {
+                self.words_written += words;
+            } else {
+                self.words_received += words;
+            }
+        }
+    }
+
+    fn measure(&self, measure: Measure) -> u64 {
+        match measure {
+            Measure::Messages => self.messages,
+            Measure::Streak => self.longest as u64,
+            Measure::WordsWritten => self.words_written,
+            Measure::WordsReceived => self.words_received,
+            Measure::Providers => self.providers,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, scope: &str) {
+        let title = format!(" STREAKS // {} ", scope);
+        frame.render_widget(t().block(title, t().accent2, t().accent1), area);
+        let inner = area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        });
+        if self.messages == 0 {
+            frame.render_widget(
+                Paragraph::new("no messages for this filter")
+                    .style(t().muted_style().bg(t().panel_bg))
+                    .alignment(Alignment::Center),
+                inner,
+            );
+            return;
+        }
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(inner);
+
+        let date = |day: &NaiveDate| day.format("%Y-%m-%d").to_string();
+        let current_style = if self.current > 0 {
+            Style::default().fg(t().accent4).bold()
+        } else {
+            t().muted_style()
+        };
+        let ratio = if self.words_written > 0 {
+            format!("  (x{:.1})", self.words_received as f64 / self.words_written as f64)
+        } else {
+            String::new()
+        };
+        let lines = vec![
+            Line::from(vec![
+                Span::styled("CURRENT STREAK: ", t().label_style()),
+                Span::styled(days_label(self.current), current_style),
+            ]),
+            Line::from(vec![
+                Span::styled("LONGEST STREAK: ", t().label_style()),
+                Span::styled(days_label(self.longest), Style::default().fg(t().accent3)),
+                Span::styled(
+                    self.longest_end
+                        .map(|end| format!("  ending {}", date(&end)))
+                        .unwrap_or_default(),
+                    t().muted_style(),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("ACTIVE DAYS:    ", t().label_style()),
+                Span::styled(self.active_days.to_string(), t().text_style()),
+            ]),
+            Line::from(vec![
+                Span::styled("BUSIEST DAY:    ", t().label_style()),
+                Span::styled(
+                    self.busiest
+                        .map(|(day, count)| format!("{}  {} messages", date(&day), count))
+                        .unwrap_or_default(),
+                    Style::default().fg(t().accent1),
+                ),
+            ]),
+            Line::default(),
+            Line::from(vec![
+                Span::styled("WORDS WRITTEN:  ", t().label_style()),
+                Span::styled(self.words_written.to_string(), t().text_style()),
+            ]),
+            Line::from(vec![
+                Span::styled("WORDS RECEIVED: ", t().label_style()),
+                Span::styled(self.words_received.to_string(), t().text_style()),
+                Span::styled(ratio, t().muted_style()),
+            ]),
+            Line::from(vec![
+                Span::styled("MESSAGES:       ", t().label_style()),
+                Span::styled(self.messages.to_string(), t().text_style()),
+                Span::styled(format!("  across {} providers", self.providers), t().muted_style()),
+            ]),
+        ];
+        frame.render_widget(Paragraph::new(lines).style(t().panel_style()), left);
+
+        let earned = MILESTONES
+            .iter()
+            .filter(|(_, measure, threshold)| self.measure(*measure) >= *threshold)
+            .count();
+        let mut badges = vec![Line::from(Span::styled(
+            format!("MILESTONES  {}/{}", earned, MILESTONES.len()),
+            t().label_style(),
+        ))];
+        for (label, measure, threshold) in MILESTONES {
+            let value = self.measure(*measure);
+            badges.push(if value >= *threshold {
+                Line::from(vec![
+                    Span::styled("\u{2605} ", Style::default().fg(t().accent4).bold()),
+                    Span::styled(*label, t().text_style().bold()),
+                ])
+            } else {
+                Line::from(vec![
+                    Span::styled("\u{2606} ", t().muted_style()),
+                    Span::styled(format!("{:<16}", label), t().muted_style()),
+                    Span::styled(format!("{}/{}", value, threshold), t().muted_style()),
+                ])
+            });
+        }
+        frame.render_widget(Paragraph::new(badges).style(t().panel_style()), right);
+    }
+}
+
+fn days_label(days: usize) -> String {
+    match days {
+        1 => "1 day".to_string(),
+        days => format!("{} days", days),
+    }
+}
@@ -0,0 +1,250 @@
+//! `[sync]`: keeps the conversation archive in step across machines through
+//! one `db export` JSON file on a WebDAV server or an S3-compatible bucket.
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::{Method, RequestBuilder, StatusCode, Url};
+use ring::{digest, hmac};
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::db::{Archive, Database, ImportSummary};
+use crate::net;
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncBackend {
+    Webdav,
+    S3,
+}
+
+/// The `[sync]` config table; without `backend` syncing is off.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    pub backend: Option<SyncBackend>,
+    /// WebDAV: URL of the archive file. S3: the endpoint, e.g.
+    /// `https://s3.us-east-1.amazonaws.com` or a MinIO/R2 URL.
+    pub url: String,
+    /// S3 bucket, addressed path-style under `url`.
+    pub bucket: String,
+    /// S3 object key.
+    pub key: String,
+    pub region: String,
+    /// WebDAV basic-auth user; its password is read from `password_env`.
+    pub username: Option<String>,
+    pub password_env: String,
+    pub access_key_env: String,
+    pub secret_key_env: String,
+    /// Sync in the background each time the chat deck starts.
+    pub on_startup: bool,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            backend: None,
+            url: String::new(),
+            bucket: String::new(),
+            key: "asciivision-archive.json".to_string(),
+            region: "us-east-1".to_string(),
+            username: None,
+            password_env: "ASCIIVISION_SYNC_PASSWORD".to_string(),
+            access_key_env: "AWS_ACCESS_KEY_ID".to_string(),
+            secret_key_env: "AWS_SECRET_ACCESS_KEY".to_string(),
+            on_startup: false,
+        }
+    }
+}
+
+/// What one `run` pulled in and pushed back.
+pub struct SyncReport {
+    pub pulled: ImportSummary,
+    /// Conversations in the uploaded archive.
+    pub pushed: usize,
+}
+
+impl SyncReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "pulled {} new and {} updated conversations, pushed {}",
+            self.pulled.conversations, self.pulled.updated, self.pushed
+        )
+    }
+}
+
+/// Downloads the remote archive, merges it into the local database with the
+/// most recently written copy of each conversation winning, then uploads the
+/// merged result.
+pub async fn run(sync: &SyncConfig, db: &Database) -> Result<SyncReport> {
+    let Some(backend) = sync.backend else {
+        return Err(anyhow!("sync is off // set backend in [sync]"));
+    };
+    let remote = Remote { sync, backend };
+    let pulled = match remote.get().await? {
+        Some(bytes) => {
+            let archive: Archive = serde_json::from_slice(&bytes)
+                .context("remote file is not an asciivision archive")?;
+            db.import_archive(&archive, true)?
+        }
+        None => ImportSummary {
+            conversations: 0,
+            updated: 0,
+            messages: 0,
+            skipped: 0,
+        },
+    };
+    let archive = db.export_archive()?;
+    remote.put(serde_json::to_vec(&archive)?).await?;
+    Ok(SyncReport {
+        pulled,
+        pushed: archive.conversations.len(),
+    })
+}
+
+struct Remote<'a> {
+    sync: &'a SyncConfig,
+    backend: SyncBackend,
+}
+
+impl Remote<'_> {
+    /// The remote archive, or `None` before the first push.
+    async fn get(&self) -> Result<Option<Vec<u8>>> {
+        let response = self.request(Method::GET, Vec::new())?.send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = checked(response).await?;
+        Ok(Some(response.bytes().await?.to_vec()))
+    }
+
+    async fn put(&self, body: Vec<u8>) -> Result<()> {
+        checked(self.request(Method::PUT, body)?.send().await?).await?;
+        Ok(())
+    }
+
+    fn request(&self, method: Method, body: Vec<u8>) -> Result<RequestBuilder> {
+        let client = net::client();
+        match self.backend {
+            SyncBackend::Webdav => {
+                let url = Url::parse(&self.sync.url)
+                    .with_context(|| format!("invalid [sync] url {}", self.sync.url))?;
+                let mut request = client.request(method, url).timeout(TIMEOUT);
+                if let Some(user) = &self.sync.username {
+                    let password = std::env::var(&self.sync.password_env).ok();
+                    request = request.basic_auth(user, password);
+                }
+                if !body.is_empty() {
+                    request = request.header("Content-Type", "application/json").body(body);
+                }
+                Ok(request)
+            }
+            SyncBackend::S3 => {
+                if self.sync.bucket.is_empty() {
+                    return Err(anyhow!("[sync] backend = \"s3\" needs a bucket"));
+                }
+                let endpoint = self.sync.url.trim_end_matches('/');
+                let path = format!(
+                    "/{}/{}",
+                    uri_encode(&self.sync.bucket),
+                    uri_encode(self.sync.key.trim_start_matches('/'))
+                );
+                let url = Url::parse(&format!("{}{}", endpoint, path))
+                    .with_context(|| format!("invalid [sync] url {}", self.sync.url))?;
+                let headers = self.sign(method.as_str(), &url, &body)?;
+                let mut request = client.request(method, url).timeout(TIMEOUT);
+                for (name, value) in headers {
+                    request = request.header(name, value);
+                }
+                Ok(request.body(body))
+            }
+        }
+    }
+
+    /// AWS Signature Version 4 headers for a path-style request with no
+    /// query string.
+    fn sign(&self, method: &str, url: &Url, body: &[u8]) -> Result<Vec<(&'static str, String)>> {
+        let env = |name: &str| {
+            std::env::var(name).with_context(|| format!("{} not set in environment", name))
+        };
+        let access_key = env(&self.sync.access_key_env)?;
+        let secret_key = env(&self.sync.secret_key_env)?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(anyhow!("[sync] url has no host")),
+        };
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let day = now.format("%Y%m%d").to_string();
+        let payload_hash = hex(digest::digest(&digest::SHA256, body).as_ref());
+        let canonical = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n\
+             host;x-amz-content-sha256;x-amz-date\n{}",
+            method,
+            url.path(),
+            host,
+            payload_hash,
+            amz_date,
+            payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", day, self.sync.region);
+        let to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(digest::digest(&digest::SHA256, canonical.as_bytes()).as_ref())
+        );
+        let mut signing_key = format!("AWS4{}", secret_key).into_bytes();
+        for part in [day.as_str(), self.sync.region.as_str(), "s3", "aws4_request"] {
+            signing_key = hmac_sha256(&signing_key, part.as_bytes());
+        }
+        let signature = hex(&hmac_sha256(&signing_key, to_sign.as_bytes()));
+        Ok(vec![
+            ("x-amz-date", amz_date),
+            ("x-amz-content-sha256", payload_hash),
+            (
+                "Authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, \
+                     SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                    access_key, scope, signature
+                ),
+            ),
+        ])
+    }
+}
+
+async fn checked(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        Err(anyhow!("sync failed ({}): {}", status, body.trim()))
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data).as_ref().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Percent-encodes everything but unreserved characters and `/`, as SigV4
+/// expects of an S3 object path.
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
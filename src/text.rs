@@ -0,0 +1,110 @@
+//! Text measured in terminal columns rather than chars, so CJK and emoji,
+//! which take two columns, and combining marks, which take none, line up and
+//! are never cut in half. Wrapping is mostly left to ratatui's `Paragraph`,
+//! which measures the same way; `wrap` is for when the rows have to be
+//! counted before drawing.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Columns `text` takes on screen.
+pub fn width(text: &str) -> usize {
+    text.width()
+}
+
+/// The user-perceived characters of `text`: a letter with its accents, or an
+/// emoji with its modifiers, is one.
+pub fn graphemes(text: &str) -> Vec<&str> {
+    text.graphemes(true).collect()
+}
+
+/// The longest start of `text` that fits in `columns`.
+pub fn clip(text: &str, columns: usize) -> &str {
+    let mut used = 0;
+    for (start, grapheme) in text.grapheme_indices(true) {
+        used += grapheme.width();
+        if used > columns {
+            return &text[..start];
+        }
+    }
+    text
+}
+
+/// `text` cut to `columns` with an ellipsis when it is longer.
+pub fn truncate(text: &str, columns: usize) -> String {
+    if width(text) <= columns {
+        return text.to_string();
+    }
+    let mut result = clip(text, columns.saturating_sub(1)).to_string();
+    result.push('\u{2026}');
+    result
+}
+
+/// `text` clipped or padded with spaces to exactly `columns`, for table
+/// cells (`format!("{:<9}")` pads by chars and misaligns wide text).
+pub fn fit(text: &str, columns: usize) -> String {
+    let clipped = clip(text, columns);
+    // a wide glyph that would straddle the edge leaves one column to pad
+    format!("{}{}", clipped, " ".repeat(columns - width(clipped)))
+}
+
+/// `text` broken at spaces into rows of at most `columns`; a word longer
+/// than a row is cut. Always at least one row.
+pub fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let columns = columns.max(1);
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    for mut word in text.split_whitespace() {
+        while width(word) > columns {
+            if !row.is_empty() {
+                rows.push(std::mem::take(&mut row));
+            }
+            let head = match clip(word, columns) {
+                // one glyph wider than the row still gets a row of its own
+                "" => graphemes(word)[0],
+                head => head,
+            };
+            rows.push(head.to_string());
+            word = &word[head.len()..];
+        }
+        if word.is_empty() {
+            continue;
+        }
+        if !row.is_empty() && width(&row) + 1 + width(word) > columns {
+            rows.push(std::mem::take(&mut row));
+        }
+        if !row.is_empty() {
+            row.push(' ');
+        }
+        row.push_str(word);
+    }
+    if !row.is_empty() || rows.is_empty() {
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_and_combining_text_is_measured_in_columns() {
+        assert_eq!(width("日本語"), 6);
+        assert_eq!(width("e\u{301}"), 1);
+        assert_eq!(graphemes("e\u{301}👍🏽!").len(), 3);
+        assert_eq!(clip("日本語", 5), "日本");
+        assert_eq!(truncate("日本語テキスト", 7), "日本語…");
+        assert_eq!(truncate("cafe\u{301}", 4), "cafe\u{301}");
+        assert_eq!(fit("日本語", 5), "日本 ");
+        assert_eq!(fit("ok", 4), "ok  ");
+    }
+
+    #[test]
+    fn wrap_breaks_at_spaces_and_cuts_long_words() {
+        assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+        assert_eq!(wrap("日本語テキスト ok", 6), ["日本語", "テキス", "ト ok"]);
+        assert_eq!(wrap("abcdefgh", 3), ["abc", "def", "gh"]);
+        assert_eq!(wrap("", 5), [""]);
+    }
+}
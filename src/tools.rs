@@ -179,6 +179,15 @@ pub fn all_tool_definitions() -> Vec<ToolDefinition> {
                 "required": []
             }),
         },
+        ToolDefinition {
+            name: "get_current_time".to_string(),
+            description: "Get the current local date and time, its UTC offset, and the Unix timestamp".to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        },
     ]
 }
 
@@ -190,6 +199,7 @@ pub async fn execute_tool(call: &ToolCall) -> ToolResult {
         "search_files" => execute_search_files(&call.arguments).await,
         "http_request" => execute_http_request(&call.arguments).await,
         "get_system_info" => execute_get_system_info(&call.arguments).await,
+        "get_current_time" => execute_get_current_time(),
         _ => Err(anyhow!("unknown tool: {}", call.name)),
     };
 
@@ -303,7 +313,7 @@ async fn execute_http_request(args: &serde_json::Value) -> Result<String> {
         .ok_or_else(|| anyhow!("missing 'url' argument"))?;
     let body = args.get("body").and_then(|v| v.as_str()).unwrap_or("");
 
-    let client = reqwest::Client::new();
+    let client = crate::net::client();
     let mut req = match method.to_uppercase().as_str() {
         "GET" => client.get(url),
         "POST" => client.post(url),
@@ -374,6 +384,16 @@ async fn execute_get_system_info(_args: &serde_json::Value) -> Result<String> {
     ))
 }
 
+fn execute_get_current_time() -> Result<String> {
+    let now = chrono::Local::now();
+    Ok(format!(
+        "Local: {}\nUTC: {}\nUnix: {}",
+        now.format("%A %Y-%m-%d %H:%M:%S %:z"),
+        now.to_utc().format("%Y-%m-%d %H:%M:%S"),
+        now.timestamp()
+    ))
+}
+
 fn fmt_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = 1024 * 1024;
@@ -0,0 +1,164 @@
+//! Word and bigram frequencies over archived messages, for the dashboard's
+//! Topics view.
+
+use ratatui::{prelude::*, widgets::Paragraph};
+use std::collections::HashMap;
+
+use crate::db::{Database, MessageFilter};
+use crate::theme::t;
+
+/// Most frequent words and bigrams kept per report.
+const TOP_N: usize = 40;
+
+/// Common English words, plus chat filler, left out of the counts.
+const STOPWORDS: &[&str] = &[
+    "about", "above", "after", "again", "against", "all", "also", "and", "any", "are", "aren't",
+    "because", "been", "before", "being", "below", "between", "both", "but", "can", "can't",
+    "could", "couldn't", "did", "didn't", "does", "doesn't", "doing", "don't", "down", "during",
+    "each", "even", "few", "for", "from", "further", "get", "gets", "got", "had", "has", "hasn't",
+    "have", "haven't", "having", "he'd", "he's", "her", "here", "here's", "hers", "herself",
+    "him", "himself", "his", "how", "how's", "i'd", "i'll", "i'm", "i've", "into", "isn't", "it's",
+    "its", "itself", "just", "let", "let's", "like", "make", "may", "might", "more", "most",
+    "much", "must", "myself", "need", "not", "now", "off", "once", "one", "only", "other",
+    "ought", "our", "ours", "ourselves", "out", "over", "own", "please", "same", "she", "she'd",
+    "she's", "should", "shouldn't", "some", "such", "sure", "than", "that", "that's", "the",
+    "their", "theirs", "them", "themselves", "then", "there", "there's", "these", "they",
+    "they'd", "they'll", "they're", "they've", "this", "those", "through", "too", "under",
+    "until", "use", "used", "using", "very", "want", "was", "wasn't", "way", "we'd", "we'll",
+    "we're", "we've", "were", "weren't", "what", "what's", "when", "when's", "where", "where's",
+    "which", "while", "who", "who's", "whom", "why", "why's", "will", "with", "won't", "would",
+    "wouldn't", "yes", "you", "you'd", "you'll", "you're", "you've", "your", "yours",
+    "yourself", "yourselves",
+];
+
+/// Counts for the Topics view, most frequent first.
+#[derive(Default)]
+pub struct TopicReport {
+    pub messages: usize,
+    pub words: Vec<(String, usize)>,
+    pub bigrams: Vec<(String, usize)>,
+}
+
+impl TopicReport {
+    /// Counts over the chat messages in `filter` (shell output is left out),
+    /// one row at a time so a large archive is never held in memory.
+    pub fn load(db: &Database, filter: &MessageFilter) -> Self {
+        let mut counts = Counts::default();
+        let filter = filter.sql();
+        let sql = format!(
+            "SELECT content FROM messages WHERE kind = 'chat' AND replaced = 0 AND {}",
+            filter.condition
+        );
+        if let Ok(mut stmt) = db.connection().prepare(&sql) {
+            let params = filter.params(&[]);
+            if let Ok(rows) = stmt.query_map(params.as_slice(), |row| row.get::<_, String>(0)) {
+                for content in rows.flatten() {
+                    counts.add(&content);
+                }
+            }
+        }
+        Self {
+            messages: counts.messages,
+            words: top(counts.words),
+            // a pair seen once says nothing about a topic
+            bigrams: top(counts.bigrams).into_iter().filter(|(_, count)| *count > 1).collect(),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, scope: &str) {
+        let title = format!(" TOPICS // {} // {} messages ", scope, self.messages);
+        frame.render_widget(t().block(title, t().accent2, t().accent1), area);
+        let inner = area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        });
+        if self.words.is_empty() {
+            frame.render_widget(
+                Paragraph::new("no words to count for this filter")
+                    .style(t().muted_style().bg(t().panel_bg))
+                    .alignment(Alignment::Center),
+                inner,
+            );
+            return;
+        }
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(inner);
+        render_counts(frame, left, "TOP WORDS", &self.words, t().accent1);
+        render_counts(frame, right, "TOP BIGRAMS", &self.bigrams, t().accent3);
+    }
+}
+
+#[derive(Default)]
+struct Counts {
+    messages: usize,
+    words: HashMap<String, usize>,
+    bigrams: HashMap<String, usize>,
+}
+
+impl Counts {
+    fn add(&mut self, content: &str) {
+        self.messages += 1;
+        let mut previous: Option<String> = None;
+        for token in tokens(content) {
+            if !is_topic_word(&token) {
+                // a bigram never spans a stopword
+                previous = None;
+                continue;
+            }
+            *self.words.entry(token.clone()).or_default() += 1;
+            if let Some(previous) = previous.replace(token.clone()) {
+                *self.bigrams.entry(format!("{} {}", previous, token)).or_default() += 1;
+            }
+        }
+    }
+}
+
+/// Lowercased words; apostrophes inside a word are kept so contractions match
+/// the stopword list.
+fn tokens(content: &str) -> impl Iterator<Item = String> + '_ {
+    content
+        .split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '\u{2019}')
+        .map(|word| {
+            word.trim_matches(|c| c == '\'' || c == '\u{2019}')
+                .replace('\u{2019}', "'")
+        })
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+}
+
+fn is_topic_word(word: &str) -> bool {
+    word.chars().count() >= 3
+        && !word.chars().all(|c| c.is_ascii_digit())
+        && !STOPWORDS.contains(&word)
+}
+
+fn top(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.truncate(TOP_N);
+    counts
+}
+
+fn render_counts(
+    frame: &mut Frame,
+    area: Rect,
+    title: &str,
+    counts: &[(String, usize)],
+    color: Color,
+) {
+    let most = counts.first().map_or(1, |(_, count)| *count).max(1);
+    let label_width = 22;
+    let bar_width = (area.width as usize).saturating_sub(label_width + 9);
+    let mut lines = vec![Line::from(Span::styled(title, t().label_style()))];
+    for (term, count) in counts.iter().take(area.height.saturating_sub(1) as usize) {
+        let filled = (count * bar_width / most).max(1);
+        let term: String = term.chars().take(label_width - 1).collect();
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:<width$}", term, width = label_width), t().text_style()),
+            Span::styled(format!("{:>6} ", count), t().muted_style()),
+            Span::styled("\u{2588}".repeat(filled), Style::default().fg(color)),
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines).style(t().panel_style()), area);
+}
@@ -0,0 +1,89 @@
+//! Markdown transcripts, shared by the chat deck's `/export` and the
+//! dashboard's Messages view.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use std::path::{Path, PathBuf};
+
+pub enum EntryKind {
+    Prompt,
+    Reply,
+    /// Command output, kept verbatim in a code fence.
+    Shell,
+    /// Errors and notices, quoted.
+    Note,
+}
+
+pub struct Entry<'a> {
+    pub kind: EntryKind,
+    /// `You`, the provider name, `Shell`, ...
+    pub speaker: String,
+    /// Model that wrote a reply, when known.
+    pub model: Option<String>,
+    pub timestamp: DateTime<Local>,
+    pub content: &'a str,
+}
+
+/// Renders `entries` under a `# title` header, one `###` section per message
+/// with its speaker and timestamp.
+pub fn markdown(title: &str, entries: &[Entry]) -> String {
+    let mut out = format!(
+        "# {}\n\n_Exported {} · {} messages_\n",
+        title,
+        Local::now().format("%Y-%m-%d %H:%M"),
+        entries.len()
+    );
+    for entry in entries {
+        out.push_str("\n---\n\n### ");
+        out.push_str(&entry.speaker);
+        if let Some(model) = &entry.model {
+            out.push_str(&format!(" ({})", model));
+        }
+        out.push_str(&format!(" · {}\n\n", entry.timestamp.format("%Y-%m-%d %H:%M:%S")));
+        let content = entry.content.trim_end();
+        match entry.kind {
+            EntryKind::Prompt | EntryKind::Reply => out.push_str(content),
+            EntryKind::Shell => {
+                let fence = fence_for(content);
+                out.push_str(&format!("{}text\n{}\n{}", fence, content, fence));
+            }
+            EntryKind::Note => {
+                let quoted: Vec<String> =
+                    content.lines().map(|line| format!("> {}", line)).collect();
+                out.push_str(&quoted.join("\n"));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// `asciivision-<scope>-<time>.md` in the working directory.
+pub fn default_path(scope: &str) -> PathBuf {
+    let scope: String = scope
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    PathBuf::from(format!(
+        "asciivision-{}-{}.md",
+        scope.trim_matches('-'),
+        Local::now().format("%Y%m%d-%H%M%S")
+    ))
+}
+
+pub fn write(path: &Path, markdown: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, markdown).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// A backtick fence longer than any run of backticks inside `content`.
+fn fence_for(content: &str) -> String {
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
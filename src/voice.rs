@@ -0,0 +1,345 @@
+//! `/voice`: records from the default microphone through an external recorder,
+//! meters the input level while it runs and transcribes the take with the
+//! OpenAI Whisper API or a local whisper.cpp server.
+
+use anyhow::{anyhow, bail, Context, Result};
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::keys;
+use crate::net;
+
+const SAMPLE_RATE: u32 = 16_000;
+const TIMEOUT: Duration = Duration::from_secs(120);
+/// Levels below this many dB under full scale show as silence.
+const METER_FLOOR_DB: f32 = 60.0;
+
+/// Recorders writing 16 kHz mono signed 16-bit PCM to stdout, tried in order
+/// like the clipboard tools; the first one that starts wins.
+const RECORD_COMMANDS: &[(&str, &[&str])] = &[
+    (
+        "sox",
+        &[
+            "-q", "-d", "-t", "raw", "-r", "16000", "-c", "1", "-b", "16", "-e", "signed-integer",
+            "-",
+        ],
+    ),
+    ("arecord", &["-q", "-f", "S16_LE", "-r", "16000", "-c", "1", "-t", "raw"]),
+    ("parec", &["--format=s16le", "--rate=16000", "--channels=1"]),
+];
+
+/// Transcription services usable by `/voice`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VoiceBackend {
+    #[default]
+    Openai,
+    WhisperCpp,
+}
+
+impl VoiceBackend {
+    pub fn name(self) -> &'static str {
+        match self {
+            VoiceBackend::Openai => "openai",
+            VoiceBackend::WhisperCpp => "whisper-cpp",
+        }
+    }
+
+    fn default_url(self) -> &'static str {
+        match self {
+            VoiceBackend::Openai => "https://api.openai.com/v1/audio/transcriptions",
+            VoiceBackend::WhisperCpp => "http://127.0.0.1:8080/inference",
+        }
+    }
+}
+
+/// The `[voice]` config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct VoiceConfig {
+    pub backend: VoiceBackend,
+    /// Overrides the OpenAI endpoint or whisper.cpp's `whisper-server` address.
+    pub url: Option<String>,
+    /// Sent to OpenAI; whisper.cpp uses the model it was started with.
+    pub model: String,
+    /// Overrides `OPENAI_API_KEY` (or the keychain's `gpt` key).
+    pub api_key_env: Option<String>,
+    /// ISO-639-1 code such as `en`; detected from the audio when unset.
+    pub language: Option<String>,
+    /// Recording stops by itself after this long.
+    pub max_seconds: u64,
+    /// A recorder writing 16 kHz mono s16le PCM to stdout, used instead of
+    /// trying sox, arecord and parec.
+    pub record_command: Vec<String>,
+}
+
+impl Default for VoiceConfig {
+    fn default() -> Self {
+        Self {
+            backend: VoiceBackend::default(),
+            url: None,
+            model: "whisper-1".to_string(),
+            api_key_env: None,
+            language: None,
+            max_seconds: 60,
+            record_command: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Transcript {
+    text: String,
+}
+
+impl VoiceConfig {
+    pub fn max_duration(&self) -> Duration {
+        Duration::from_secs(self.max_seconds.max(1))
+    }
+
+    fn api_key(&self) -> Result<String> {
+        let var = self.api_key_env.as_deref().unwrap_or("OPENAI_API_KEY");
+        keys::lookup(var, "gpt").with_context(|| {
+            format!("{} not set in environment or keychain (asciivision keys set gpt)", var)
+        })
+    }
+
+    /// Sends a WAV take to the backend and returns what was said.
+    pub async fn transcribe(&self, wav: Vec<u8>) -> Result<String> {
+        let boundary = format!("asciivision-{}", uuid::Uuid::new_v4().simple());
+        let mut fields = vec![("model", self.model.as_str()), ("response_format", "json")];
+        if let Some(language) = &self.language {
+            fields.push(("language", language));
+        }
+        let body = multipart(&boundary, &fields, &wav);
+        let url = self.url.as_deref().unwrap_or(self.backend.default_url());
+        let mut request = net::client()
+            .post(url)
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .body(body)
+            .timeout(TIMEOUT);
+        if self.backend == VoiceBackend::Openai {
+            request = request.bearer_auth(self.api_key()?);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("{} unreachable at {}", self.backend.name(), url))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("transcription failed ({}): {}", status, body.trim());
+        }
+        let transcript: Transcript = response.json().await?;
+        let text = transcript.text.trim().to_string();
+        if text.is_empty() {
+            bail!("no speech heard");
+        }
+        Ok(text)
+    }
+}
+
+/// A `multipart/form-data` body with text `fields` and the take as `file`.
+fn multipart(boundary: &str, fields: &[(&str, &str)], wav: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(wav.len() + 512);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                boundary, name, value
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"voice.wav\"\r\n\
+             Content-Type: audio/wav\r\n\r\n",
+            boundary
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(wav);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+/// A recorder running in the background; dropping it discards the take.
+pub struct Recording {
+    child: Child,
+    program: String,
+    reader: Option<JoinHandle<()>>,
+    samples: Arc<Mutex<Vec<i16>>>,
+    /// Level of the latest block, 0.0 to 1.0, as `f32` bits.
+    level: Arc<AtomicU32>,
+    started: Instant,
+}
+
+impl Recording {
+    pub fn start(config: &VoiceConfig) -> Result<Self> {
+        let custom: Vec<&str> = config.record_command.iter().map(String::as_str).collect();
+        let commands: Vec<(&str, &[&str])> = match custom.split_first() {
+            Some((program, args)) => vec![(*program, args)],
+            None => RECORD_COMMANDS.to_vec(),
+        };
+        for (program, args) in commands {
+            let Ok(mut child) = Command::new(program)
+                .args(args)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+            else {
+                continue;
+            };
+            let Some(mut stdout) = child.stdout.take() else {
+                continue;
+            };
+            let samples = Arc::new(Mutex::new(Vec::new()));
+            let level = Arc::new(AtomicU32::new(0));
+            let (sink, meter) = (samples.clone(), level.clone());
+            let reader = std::thread::spawn(move || {
+                // a tenth of a second per read, so the meter keeps up
+                let mut buffer = [0u8; SAMPLE_RATE as usize / 5];
+                // an odd byte waits for its partner in the next read
+                let mut pending = Vec::new();
+                while let Ok(read @ 1..) = stdout.read(&mut buffer) {
+                    pending.extend_from_slice(&buffer[..read]);
+                    let whole = pending.len() / 2 * 2;
+                    let block: Vec<i16> = pending
+                        .drain(..whole)
+                        .collect::<Vec<u8>>()
+                        .chunks_exact(2)
+                        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+                        .collect();
+                    meter.store(block_level(&block).to_bits(), Ordering::Relaxed);
+                    sink.lock().extend(block);
+                }
+            });
+            return Ok(Self {
+                child,
+                program: program.to_string(),
+                reader: Some(reader),
+                samples,
+                level,
+                started: Instant::now(),
+            });
+        }
+        if custom.is_empty() {
+            Err(anyhow!("no recorder found (sox, arecord or parec)"))
+        } else {
+            Err(anyhow!("[voice] record_command {:?} failed to start", custom[0]))
+        }
+    }
+
+    pub fn level(&self) -> f32 {
+        f32::from_bits(self.level.load(Ordering::Relaxed))
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// True once the recorder has quit on its own, e.g. with no microphone.
+    pub fn exited(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(Some(_)))
+    }
+
+    /// Stops the recorder and returns the take as a WAV file.
+    pub fn stop(mut self) -> Result<Vec<u8>> {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+        let samples = std::mem::take(&mut *self.samples.lock());
+        if samples.is_empty() {
+            bail!("{} recorded no audio: is a microphone connected?", self.program);
+        }
+        Ok(wav(&samples))
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Loudness of a block on a 0.0 to 1.0 scale, from its RMS in decibels.
+fn block_level(block: &[i16]) -> f32 {
+    if block.is_empty() {
+        return 0.0;
+    }
+    let sum: f32 = block.iter().map(|&sample| (sample as f32 / 32768.0).powi(2)).sum();
+    let power = sum / block.len() as f32;
+    let decibels = 10.0 * power.max(1e-10).log10();
+    ((decibels + METER_FLOOR_DB) / METER_FLOOR_DB).clamp(0.0, 1.0)
+}
+
+/// The level as a `width`-cell bar.
+pub fn meter(level: f32, width: usize) -> String {
+    let filled = (level.clamp(0.0, 1.0) * width as f32).round() as usize;
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// Mono 16-bit PCM samples with a WAV header.
+fn wav(samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&1u16.to_le_bytes()); // mono
+    out.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    out.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        out.extend_from_slice(&sample.to_le_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wav_header() {
+        let bytes = wav(&[0, 1, -1]);
+        assert_eq!(bytes.len(), 44 + 6);
+        assert_eq!(&bytes[..4], b"RIFF");
+        let word = |at: usize| {
+            u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+        };
+        assert_eq!(word(4), 36 + 6);
+        assert_eq!(word(24), 16_000);
+        assert_eq!(word(40), 6);
+        assert_eq!(&bytes[48..], &[0xff, 0xff]);
+    }
+
+    #[test]
+    fn levels_and_meter() {
+        assert_eq!(block_level(&[0; 160]), 0.0);
+        assert_eq!(block_level(&[i16::MIN; 160]), 1.0);
+        let quiet = block_level(&[300; 160]);
+        assert!(quiet > 0.0 && quiet < 0.5, "{}", quiet);
+        assert_eq!(meter(0.5, 8), "████░░░░");
+        assert_eq!(meter(2.0, 4), "████");
+    }
+}
@@ -1,9 +1,9 @@
 use anyhow::{anyhow, Context, Result};
 use crossbeam_channel::{bounded, Receiver, Sender};
 use ffmpeg_next::codec;
+use asciivision_core::ffmpeg::{build_scaler, frame_from_video};
 use ffmpeg_next::format::Pixel;
 use ffmpeg_next::media::Type;
-use ffmpeg_next::software::scaling::Flags;
 use ffmpeg_next::util::frame::Video;
 use std::ffi::CString;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -11,9 +11,7 @@ use std::sync::Arc;
 use std::thread;
 
 use crate::message::WsAsciiFrame;
-use crate::video::AsciiFrame;
-
-const PALETTE: &[u8] = b" .'`^\",:;Il!i><~+_-?][}{1)(|\\tfjrxnuvczXYUJCLQ0OZmwqpdbkhao*#MW&8%B@$";
+use asciivision_core::AsciiFrame;
 
 #[derive(Debug, Clone)]
 pub struct WebcamConfig {
@@ -52,11 +50,13 @@ impl WebcamCapture {
         thread::spawn(move || {
             let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                 if let Err(e) = capture_loop(&config, &tx, &active_clone) {
+                    tracing::warn!("webcam capture stopped: {}", e);
                     let msg = format!("{}", e);
                     *error_clone.lock() = Some(msg);
                 }
             }));
             if result.is_err() {
+                tracing::error!("webcam thread panicked");
                 *error_clone.lock() = Some("webcam thread panicked".to_string());
             }
         });
@@ -83,16 +83,6 @@ impl Drop for WebcamCapture {
     }
 }
 
-fn luminance(r: u8, g: u8, b: u8) -> u8 {
-    (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32).min(255.0) as u8
-}
-
-fn ascii_for(r: u8, g: u8, b: u8) -> char {
-    let y = luminance(r, g, b) as usize;
-    let idx = (y * (PALETTE.len() - 1)) / 255;
-    PALETTE[idx.min(PALETTE.len() - 1)] as char
-}
-
 fn open_webcam_device(device_spec: &str, format_name: &str, opts: ffmpeg_next::Dictionary) -> Result<ffmpeg_next::format::context::Input> {
     unsafe {
         let format_cstr = CString::new(format_name)?;
@@ -159,38 +149,31 @@ fn capture_loop(config: &WebcamConfig, tx: &Sender<AsciiFrame>, active: &Arc<Ato
     let mut decoder = dec_ctx.decoder().video().context("video decoder")?;
 
     // Compute output dimensions that preserve the webcam's native aspect ratio
-    // accounting for terminal cells being ~2x taller than wide.
+    // accounting for terminal cells being taller than wide.
     let src_w = decoder.width() as f32;
     let src_h = decoder.height() as f32;
     let src_aspect = src_w / src_h;
-    // Terminal cell aspect ratio correction: each cell is ~2x tall as it is wide,
-    // so we need ~2x the columns to look right visually.
+    // Terminal cell aspect ratio correction: with cells about twice as tall as
+    // they are wide we need about twice the columns to look right visually.
+    let cell = asciivision_core::cell_aspect();
     let target_w = config.width as f32;
     let target_h = config.height as f32;
     let (out_w, out_h) = {
         let fit_h = target_h;
-        let fit_w = (fit_h * src_aspect * 2.0).round();
+        let fit_w = (fit_h * src_aspect / cell).round();
         if fit_w <= target_w {
             (fit_w as u32, fit_h as u32)
         } else {
             let fit_w = target_w;
-            let fit_h = (fit_w / (src_aspect * 2.0)).round();
+            let fit_h = (fit_w * cell / src_aspect).round();
             (fit_w as u32, fit_h as u32)
         }
     };
     let out_w = out_w.max(4);
     let out_h = out_h.max(4);
 
-    let mut scaler = ffmpeg_next::software::scaling::Context::get(
-        decoder.format(),
-        decoder.width(),
-        decoder.height(),
-        Pixel::RGB24,
-        out_w,
-        out_h,
-        Flags::BILINEAR,
-    )
-    .context("scaler")?;
+    let mut scaler = build_scaler(decoder.format(), decoder.width(), decoder.height(), out_w, out_h)
+        .context("scaler")?;
 
     let mut decoded = ffmpeg_next::frame::Video::empty();
     let mut rgb = Video::new(Pixel::RGB24, out_w, out_h);
@@ -218,7 +201,7 @@ fn capture_loop(config: &WebcamConfig, tx: &Sender<AsciiFrame>, active: &Arc<Ato
                 thread::sleep(frame_dur - elapsed);
             }
             scaler.run(&decoded, &mut rgb)?;
-            let frame = rgb_to_ascii(&rgb, out_w as u16, out_h as u16);
+            let frame = frame_from_video(&rgb);
             if tx.send(frame).is_err() {
                 return Ok(());
             }
@@ -229,27 +212,6 @@ fn capture_loop(config: &WebcamConfig, tx: &Sender<AsciiFrame>, active: &Arc<Ato
     Ok(())
 }
 
-fn rgb_to_ascii(rgb: &Video, width: u16, height: u16) -> AsciiFrame {
-    let stride = rgb.stride(0);
-    let data = rgb.data(0);
-    let mut cells = Vec::with_capacity(width as usize * height as usize);
-
-    for y in 0..height as usize {
-        let row = &data[y * stride..y * stride + width as usize * 3];
-        for x in 0..width as usize {
-            let i = x * 3;
-            let (r, g, b) = (row[i], row[i + 1], row[i + 2]);
-            cells.push((ascii_for(r, g, b), r, g, b));
-        }
-    }
-
-    AsciiFrame {
-        width,
-        height,
-        cells,
-    }
-}
-
 pub fn ascii_frame_to_ws(frame: &AsciiFrame) -> WsAsciiFrame {
     let mut ws = WsAsciiFrame::new(frame.width, frame.height);
     for (i, &(ch, r, g, b)) in frame.cells.iter().enumerate() {
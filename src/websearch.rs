@@ -0,0 +1,208 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::net;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Search backends usable by `/web`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchBackend {
+    #[default]
+    Searxng,
+    Brave,
+    Tavily,
+}
+
+impl SearchBackend {
+    pub fn name(self) -> &'static str {
+        match self {
+            SearchBackend::Searxng => "searxng",
+            SearchBackend::Brave => "brave",
+            SearchBackend::Tavily => "tavily",
+        }
+    }
+
+    fn default_key_env(self) -> &'static str {
+        match self {
+            SearchBackend::Searxng => "",
+            SearchBackend::Brave => "BRAVE_API_KEY",
+            SearchBackend::Tavily => "TAVILY_API_KEY",
+        }
+    }
+}
+
+/// The `[web_search]` config table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WebSearchConfig {
+    pub backend: SearchBackend,
+    /// SearXNG instance, e.g. `http://localhost:8888`.
+    pub url: String,
+    /// Overrides `BRAVE_API_KEY` / `TAVILY_API_KEY`.
+    pub api_key_env: Option<String>,
+    pub max_results: usize,
+}
+
+impl Default for WebSearchConfig {
+    fn default() -> Self {
+        Self {
+            backend: SearchBackend::default(),
+            url: "http://localhost:8888".to_string(),
+            api_key_env: None,
+            max_results: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+#[derive(Deserialize)]
+struct SearxResponse {
+    #[serde(default)]
+    results: Vec<SearxResult>,
+}
+
+#[derive(Deserialize)]
+struct SearxResult {
+    title: String,
+    url: String,
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct BraveResponse {
+    web: Option<BraveWeb>,
+}
+
+#[derive(Deserialize)]
+struct BraveWeb {
+    #[serde(default)]
+    results: Vec<BraveResult>,
+}
+
+#[derive(Deserialize)]
+struct BraveResult {
+    title: String,
+    url: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct TavilyResponse {
+    #[serde(default)]
+    results: Vec<SearxResult>,
+}
+
+impl WebSearchConfig {
+    fn api_key(&self) -> Result<String> {
+        let var = self
+            .api_key_env
+            .as_deref()
+            .unwrap_or(self.backend.default_key_env());
+        std::env::var(var).with_context(|| format!("{} not set in environment", var))
+    }
+
+    pub async fn search(&self, query: &str) -> Result<Vec<SearchResult>> {
+        let client = net::client();
+        let count = self.max_results.max(1);
+
+        let results = match self.backend {
+            SearchBackend::Searxng => {
+                let url = format!("{}/search", self.url.trim_end_matches('/'));
+                let response = client
+                    .get(url)
+                    .query(&[("q", query), ("format", "json")])
+                    .timeout(TIMEOUT)
+                    .send()
+                    .await?;
+                let body: SearxResponse = checked(response).await?.json().await?;
+                body.results
+                    .into_iter()
+                    .map(|r| SearchResult {
+                        title: r.title,
+                        url: r.url,
+                        snippet: r.content,
+                    })
+                    .collect::<Vec<_>>()
+            }
+            SearchBackend::Brave => {
+                let response = client
+                    .get("https://api.search.brave.com/res/v1/web/search")
+                    .query(&[("q", query), ("count", &count.to_string())])
+                    .header("X-Subscription-Token", self.api_key()?)
+                    .header("Accept", "application/json")
+                    .timeout(TIMEOUT)
+                    .send()
+                    .await?;
+                let body: BraveResponse = checked(response).await?.json().await?;
+                body.web
+                    .map(|web| web.results)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|r| SearchResult {
+                        title: r.title,
+                        url: r.url,
+                        snippet: r.description,
+                    })
+                    .collect()
+            }
+            SearchBackend::Tavily => {
+                let response = client
+                    .post("https://api.tavily.com/search")
+                    .bearer_auth(self.api_key()?)
+                    .json(&serde_json::json!({ "query": query, "max_results": count }))
+                    .timeout(TIMEOUT)
+                    .send()
+                    .await?;
+                let body: TavilyResponse = checked(response).await?.json().await?;
+                body.results
+                    .into_iter()
+                    .map(|r| SearchResult {
+                        title: r.title,
+                        url: r.url,
+                        snippet: r.content,
+                    })
+                    .collect()
+            }
+        };
+        Ok(results.into_iter().take(count).collect())
+    }
+}
+
+async fn checked(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        Err(anyhow!("search failed ({}): {}", status, body.trim()))
+    }
+}
+
+/// Results formatted as numbered context the model can cite as `[n]`.
+pub fn context_block(query: &str, results: &[SearchResult]) -> String {
+    let mut block = format!(
+        "\n\n[Web search results for \"{}\". Cite sources inline as [n].]",
+        query
+    );
+    for (index, result) in results.iter().enumerate() {
+        block.push_str(&format!(
+            "\n[{}] {}\n{}\n{}",
+            index + 1,
+            result.title,
+            result.url,
+            result.snippet.trim()
+        ));
+    }
+    block
+}